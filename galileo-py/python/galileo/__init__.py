@@ -0,0 +1,16 @@
+"""Headless map rendering with the Galileo engine.
+
+Example (in a Jupyter notebook)::
+
+    import galileo
+    from IPython.display import Image
+
+    m = galileo.Map(lat=52.52, lon=13.40, resolution=20.0, width=800, height=600)
+    m.add_layer(galileo.Layer.raster_tiles("https://tile.openstreetmap.org/{z}/{x}/{y}.png"))
+    m.add_layer(galileo.Layer.from_geojson(open("museums.geojson").read(), color="#e0301e"))
+    Image(m.render_png())
+"""
+
+from ._galileo import Layer, Map, TileSchema
+
+__all__ = ["Layer", "Map", "TileSchema"]
@@ -0,0 +1,393 @@
+//! Python bindings of Galileo for rendering maps without a window, e.g. from Jupyter notebooks.
+//!
+//! The bindings are built into the `galileo` Python package with [maturin](https://www.maturin.rs):
+//!
+//! ```sh
+//! cd galileo-py
+//! maturin develop --release
+//! ```
+//!
+//! ```python
+//! import galileo
+//!
+//! m = galileo.Map(lat=52.52, lon=13.40, resolution=20.0, width=800, height=600)
+//! m.add_layer(galileo.Layer.raster_tiles("https://tile.openstreetmap.org/{z}/{x}/{y}.png"))
+//! m.add_layer(galileo.Layer.from_geoparquet("buildings.parquet", color="#3060c0"))
+//! open("map.png", "wb").write(m.render_png())
+//! ```
+
+use std::io::Cursor;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use galileo::layer::data_provider::geoparquet::GeoParquetReader;
+use galileo::layer::data_provider::UrlImageProvider;
+use galileo::layer::feature_layer::{Feature, Symbol};
+use galileo::layer::{FeatureLayer, Layer, RasterTileLayer};
+use galileo::map::LayerCollection;
+use galileo::render::WgpuRenderer;
+use galileo::symbol::{
+    ArbitraryGeometrySymbol, CirclePointSymbol, SimpleContourSymbol, SimplePolygonSymbol,
+};
+use galileo::tile_scheme::TileIndex;
+use galileo::{Color, LayerId, Map, MapView, TileSchema};
+use galileo_types::cartesian::{Rect, Size};
+use galileo_types::geo::Crs;
+use galileo_types::geometry::Geometry;
+use galileo_types::geometry_type::GeoSpace2d;
+use galileo_types::latlon;
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+type RasterLayer = RasterTileLayer<UrlImageProvider<TileIndex>>;
+
+/// Runtime used to load the layer data and to wait for the GPU.
+fn runtime() -> &'static tokio::runtime::Runtime {
+    static RUNTIME: OnceLock<tokio::runtime::Runtime> = OnceLock::new();
+    RUNTIME.get_or_init(|| tokio::runtime::Runtime::new().expect("failed to start async runtime"))
+}
+
+/// Tile schema used to split the map into tiles.
+#[pyclass(name = "TileSchema", module = "galileo")]
+#[derive(Clone)]
+struct PyTileSchema(TileSchema);
+
+#[pymethods]
+impl PyTileSchema {
+    /// Standard Web Mercator tile schema of 256x256 pixel tiles, used by most web maps.
+    #[staticmethod]
+    #[pyo3(signature = (lods = 18))]
+    fn web(lods: u32) -> Self {
+        Self(TileSchema::web(lods))
+    }
+
+    /// Resolution (in meters per pixel) of the level `z`, or `None` if the schema has no such level.
+    fn lod_resolution(&self, z: u32) -> Option<f64> {
+        self.0.lod_resolution(z)
+    }
+
+    /// Level of the schema that should be used to draw a map with the given resolution.
+    fn select_lod(&self, resolution: f64) -> Option<u32> {
+        self.0.select_lod(resolution).map(|lod| lod.z_index())
+    }
+
+    /// Bounding box of the tile as `(x_min, y_min, x_max, y_max)` in the projected coordinates of the schema.
+    fn tile_bbox(&self, x: i32, y: i32, z: u32) -> Option<(f64, f64, f64, f64)> {
+        self.0
+            .tile_bbox(TileIndex::new(x, y, z))
+            .map(|bbox| (bbox.x_min(), bbox.y_min(), bbox.x_max(), bbox.y_max()))
+    }
+
+    /// Tiles as `(x, y, z)` needed to draw a map of the given size (in pixels) centered at the given point with the
+    /// given resolution.
+    fn tiles(
+        &self,
+        lat: f64,
+        lon: f64,
+        resolution: f64,
+        width: u32,
+        height: u32,
+    ) -> Vec<(i32, i32, u32)> {
+        let view = MapView::new(&latlon!(lat, lon), resolution)
+            .with_size(Size::new(width as f64, height as f64));
+        self.0
+            .iter_tiles(&view)
+            .into_iter()
+            .flatten()
+            .map(|index| (index.x, index.y, index.z))
+            .collect()
+    }
+}
+
+enum LayerKind {
+    Raster(RasterLayer),
+    Features(Box<dyn FnOnce(&mut LayerCollection) -> LayerId>),
+}
+
+/// Layer of a map. A layer can be added only to one map.
+#[pyclass(name = "Layer", module = "galileo", unsendable)]
+struct PyLayer {
+    layer: Option<LayerKind>,
+}
+
+#[pymethods]
+impl PyLayer {
+    /// Layer of raster tiles loaded from the URL template with `{z}`, `{x}` and `{y}` placeholders, e.g.
+    /// `https://tile.openstreetmap.org/{z}/{x}/{y}.png`.
+    #[staticmethod]
+    fn raster_tiles(url_template: String) -> Self {
+        let tile_provider = UrlImageProvider::new(move |index: &TileIndex| {
+            url_template
+                .replace("{z}", &index.z.to_string())
+                .replace("{x}", &index.x.to_string())
+                .replace("{y}", &index.y.to_string())
+        });
+
+        let mut layer = RasterTileLayer::new(TileSchema::web(18), tile_provider, None);
+        // A map is rendered once all the tiles are loaded, so there is nothing to fade in from.
+        layer.set_fade_in_duration(Duration::ZERO);
+
+        Self {
+            layer: Some(LayerKind::Raster(layer)),
+        }
+    }
+
+    /// Layer with the features of a GeoJSON `FeatureCollection` string.
+    #[staticmethod]
+    #[pyo3(signature = (geojson, color = None))]
+    fn from_geojson(geojson: &str, color: Option<&str>) -> PyResult<Self> {
+        let collection = geojson
+            .parse::<geojson::GeoJson>()
+            .and_then(geojson::FeatureCollection::try_from)
+            .map_err(|err| PyValueError::new_err(format!("invalid GeoJSON: {err}")))?;
+
+        Self::features(collection.features, color)
+    }
+
+    /// Layer with the features of a GeoParquet file. Only the given `columns` are read, and if `bbox` is set as
+    /// `(lon_min, lat_min, lon_max, lat_max)`, only the features intersecting it.
+    #[staticmethod]
+    #[pyo3(signature = (path, columns = None, bbox = None, color = None))]
+    fn from_geoparquet(
+        path: &str,
+        columns: Option<Vec<String>>,
+        bbox: Option<(f64, f64, f64, f64)>,
+        color: Option<&str>,
+    ) -> PyResult<Self> {
+        let mut reader =
+            GeoParquetReader::open(path).map_err(|err| PyValueError::new_err(err.to_string()))?;
+        if let Some(columns) = &columns {
+            let columns: Vec<&str> = columns.iter().map(String::as_str).collect();
+            reader = reader.with_columns(&columns);
+        }
+        if let Some((x_min, y_min, x_max, y_max)) = bbox {
+            reader = reader.with_bbox(Rect::new(x_min, y_min, x_max, y_max));
+        }
+
+        let features = reader
+            .read()
+            .map_err(|err| PyValueError::new_err(err.to_string()))?;
+        Self::features(features, color)
+    }
+}
+
+impl PyLayer {
+    fn features<P, F>(features: Vec<F>, color: Option<&str>) -> PyResult<Self>
+    where
+        F: Feature + 'static,
+        F::Geom: Geometry<Point = P>,
+        ArbitraryGeometrySymbol: Symbol<F>,
+        FeatureLayer<P, F, ArbitraryGeometrySymbol, GeoSpace2d>: Layer,
+    {
+        let symbol = match color {
+            None => ArbitraryGeometrySymbol::default(),
+            Some(hex) => {
+                let color = Color::try_from_hex(hex)
+                    .ok_or_else(|| PyValueError::new_err(format!("invalid color: {hex}")))?;
+                ArbitraryGeometrySymbol::new(
+                    CirclePointSymbol::new(color, 5.0),
+                    SimpleContourSymbol::new(color, 2.0),
+                    SimplePolygonSymbol::new(color),
+                )
+            }
+        };
+
+        let layer: FeatureLayer<P, F, ArbitraryGeometrySymbol, GeoSpace2d> =
+            FeatureLayer::new(features, symbol, Crs::WGS84);
+        Ok(Self {
+            layer: Some(LayerKind::Features(Box::new(move |layers| {
+                layers.push(layer)
+            }))),
+        })
+    }
+}
+
+/// Map that is rendered into images instead of a window.
+#[pyclass(name = "Map", module = "galileo", unsendable)]
+struct PyMap {
+    map: Map,
+    raster_layers: Vec<LayerId>,
+    renderer: Option<WgpuRenderer>,
+}
+
+#[pymethods]
+impl PyMap {
+    /// Creates a map of the given size (in pixels) centered at the given point with the given resolution (in meters
+    /// per pixel).
+    #[new]
+    #[pyo3(signature = (lat, lon, resolution, width = 800, height = 600))]
+    fn new(lat: f64, lon: f64, resolution: f64, width: u32, height: u32) -> PyResult<Self> {
+        if !(resolution.is_finite() && resolution > 0.0) || width == 0 || height == 0 {
+            return Err(PyValueError::new_err(
+                "resolution and size must be positive",
+            ));
+        }
+
+        let view = MapView::new(&latlon!(lat, lon), resolution)
+            .with_size(Size::new(width as f64, height as f64));
+        Ok(Self {
+            map: Map::new_detached(view, vec![]),
+            raster_layers: vec![],
+            renderer: None,
+        })
+    }
+
+    /// Moves the map to the given point and resolution.
+    fn set_view(&mut self, lat: f64, lon: f64, resolution: f64) {
+        let view = self
+            .map
+            .view()
+            .with_position(&latlon!(lat, lon))
+            .with_resolution(resolution);
+        self.map.animate_to(view, Duration::ZERO);
+        self.map.animate();
+    }
+
+    /// Changes the size of the rendered images.
+    fn set_size(&mut self, width: u32, height: u32) {
+        self.map.set_size(Size::new(width as f64, height as f64));
+    }
+
+    /// Adds the layer to the top of the map and returns its id.
+    fn add_layer(&mut self, layer: &mut PyLayer) -> PyResult<u64> {
+        let id = match layer.layer.take() {
+            Some(LayerKind::Raster(raster)) => {
+                let id = self.map.layers_mut().push(raster);
+                self.raster_layers.push(id);
+                id
+            }
+            Some(LayerKind::Features(push)) => push(self.map.layers_mut()),
+            None => return Err(PyValueError::new_err("the layer is already added to a map")),
+        };
+
+        Ok(id.to_raw())
+    }
+
+    /// Removes the layer with the given id. Returns `False` if the map has no such layer.
+    fn remove_layer(&mut self, id: u64) -> bool {
+        let id = LayerId::from_raw(id);
+        self.raster_layers.retain(|raster| *raster != id);
+        self.map.layers_mut().remove_by_id(id).is_some()
+    }
+
+    /// Renders the map and returns the image as RGBA bytes, row by row from the top-left corner.
+    fn render_rgba<'py>(&mut self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+        let bitmap = self.render()?;
+        Ok(PyBytes::new_bound(py, &bitmap))
+    }
+
+    /// Renders the map and returns the image encoded as PNG.
+    fn render_png<'py>(&mut self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+        let (width, height) = self.size();
+        let bitmap = self.render()?;
+        let image = image::RgbaImage::from_raw(width, height, bitmap)
+            .ok_or_else(|| PyRuntimeError::new_err("renderer returned image of wrong size"))?;
+
+        let mut png = Cursor::new(vec![]);
+        image
+            .write_to(&mut png, image::ImageOutputFormat::Png)
+            .map_err(|err| PyRuntimeError::new_err(format!("failed to encode PNG: {err}")))?;
+        Ok(PyBytes::new_bound(py, png.get_ref()))
+    }
+
+    /// Ids of the layers of the map from the bottom one.
+    fn layer_ids(&self) -> Vec<u64> {
+        self.map.layers().ids().map(LayerId::to_raw).collect()
+    }
+}
+
+impl PyMap {
+    fn size(&self) -> (u32, u32) {
+        let size = self.map.view().size();
+        (size.width() as u32, size.height() as u32)
+    }
+
+    fn render(&mut self) -> PyResult<Vec<u8>> {
+        let (width, height) = self.size();
+        let size = Size::new(width, height);
+        let runtime = runtime();
+        let _guard = runtime.enter();
+
+        let renderer = match self.renderer.take() {
+            Some(mut renderer) => {
+                if renderer.size() != Size::new(width as f64, height as f64) {
+                    renderer.resize(size);
+                }
+                renderer
+            }
+            None => runtime
+                .block_on(WgpuRenderer::new_with_texture_rt(size))
+                .ok_or_else(|| PyRuntimeError::new_err("no suitable GPU adapter found"))?,
+        };
+
+        let map = &self.map;
+        let raster_layers = &self.raster_layers;
+        let image = runtime.block_on(async {
+            for id in raster_layers {
+                if let Some(layer) = map.layer::<RasterLayer>(*id) {
+                    layer.load_tiles(map.view()).await;
+                }
+            }
+
+            renderer.render(map)?;
+            renderer.get_image().await
+        });
+
+        self.renderer = Some(renderer);
+        image.map_err(|err| PyRuntimeError::new_err(format!("failed to render the map: {err}")))
+    }
+}
+
+/// Headless map rendering with the Galileo engine.
+#[pymodule]
+#[pyo3(name = "_galileo")]
+fn galileo_module(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyTileSchema>()?;
+    module.add_class::<PyLayer>()?;
+    module.add_class::<PyMap>()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tile_schema_math() {
+        let schema = PyTileSchema::web(18);
+        assert_eq!(
+            schema.select_lod(schema.lod_resolution(5).expect("level exists")),
+            Some(5)
+        );
+
+        let (x_min, _, x_max, _) = schema.tile_bbox(0, 0, 0).expect("level exists");
+        assert!((x_max - x_min - 2.0 * 20_037_508.34).abs() < 1.0);
+
+        let resolution = schema.lod_resolution(10).expect("level exists");
+        let tiles = schema.tiles(52.0, 13.0, resolution, 256, 256);
+        assert!(!tiles.is_empty() && tiles.len() <= 4);
+        assert!(tiles.iter().all(|(_, _, z)| *z == 10));
+    }
+
+    #[test]
+    fn layers_are_added_once() {
+        assert!(PyLayer::from_geojson("{", None).is_err());
+        assert!(PyLayer::from_geojson(
+            r#"{"type":"FeatureCollection","features":[]}"#,
+            Some("#xyz")
+        )
+        .is_err());
+
+        let mut layer = PyLayer::from_geojson(
+            r#"{"type":"FeatureCollection","features":[]}"#,
+            Some("#ff0000"),
+        )
+        .expect("valid layer");
+        let mut map = PyMap::new(52.0, 13.0, 100.0, 64, 64).expect("valid map");
+        let id = map.add_layer(&mut layer).expect("layer is added");
+        assert!(map.add_layer(&mut layer).is_err());
+        assert_eq!(map.layer_ids(), vec![id]);
+        assert!(map.remove_layer(id));
+    }
+}
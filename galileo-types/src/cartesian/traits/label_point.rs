@@ -0,0 +1,272 @@
+use crate::cartesian::traits::cartesian_point::CartesianPoint2d;
+use crate::cartesian::traits::polygon::CartesianPolygon;
+use crate::cartesian::{CartesianClosedContour, Point2, Rect};
+use crate::contour::{ClosedContour, Contour};
+use crate::multi_polygon::MultiPolygon;
+use crate::polygon::Polygon;
+use nalgebra::Scalar;
+use num_traits::{Bounded, Float, FromPrimitive};
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+/// Placement of labels and icons inside polygons. This trait is auto-implemented for all polygons with floating point
+/// coordinates.
+pub trait PolygonLabelPoint {
+    /// Number type of the polygon coordinates.
+    type Num: Scalar;
+
+    /// Returns the [pole of inaccessibility](https://en.wikipedia.org/wiki/Pole_of_inaccessibility) of the polygon:
+    /// the internal point that is most distant from the polygon outline.
+    ///
+    /// Unlike the centroid, this point is always inside the polygon (even for concave polygons and polygons with
+    /// holes), and it is the best place for a label or an icon, since it has the most free space around it.
+    ///
+    /// The point is searched iteratively until it is found with the given `precision` (in the units of the polygon
+    /// coordinates). For labels, a precision of a pixel size at the resolution of the rendering is enough.
+    ///
+    /// Returns `None` if the outer contour of the polygon is empty.
+    fn label_point(&self, precision: Self::Num) -> Option<Point2<Self::Num>>;
+}
+
+/// Placement of labels and icons inside multipolygons. This trait is auto-implemented for all multipolygons with
+/// floating point coordinates.
+pub trait MultiPolygonLabelPoint {
+    /// Number type of the polygon coordinates.
+    type Num: Scalar;
+
+    /// Returns the [`PolygonLabelPoint::label_point`] of the part with the largest area. A single label is usually
+    /// placed for a multipolygon, and the largest part is the most visible one.
+    ///
+    /// Returns `None` if the multipolygon has no non-empty parts.
+    fn label_point(&self, precision: Self::Num) -> Option<Point2<Self::Num>>;
+}
+
+impl<N, P, C, T> PolygonLabelPoint for T
+where
+    N: Float + Bounded + Scalar + FromPrimitive,
+    P: CartesianPoint2d<Num = N>,
+    C: ClosedContour<Point = P>,
+    T: Polygon<Contour = C>,
+{
+    type Num = N;
+
+    fn label_point(&self, precision: N) -> Option<Point2<N>> {
+        let bbox = Rect::from_points(self.outer_contour().iter_points())?;
+        let cell_size = bbox.width().min(bbox.height());
+        if cell_size <= N::zero() {
+            return Some(Point2::new(bbox.x_min(), bbox.y_min()));
+        }
+
+        let two = N::one() + N::one();
+        let half = cell_size / two;
+        let mut queue = BinaryHeap::new();
+
+        let mut x = bbox.x_min();
+        while x < bbox.x_max() {
+            let mut y = bbox.y_min();
+            while y < bbox.y_max() {
+                queue.push(Cell::new(Point2::new(x + half, y + half), half, self));
+                y = y + cell_size;
+            }
+            x = x + cell_size;
+        }
+
+        let mut best = Cell::new(centroid(self.outer_contour(), &bbox), N::zero(), self);
+        let bbox_cell = Cell::new(bbox.center(), N::zero(), self);
+        if bbox_cell.distance > best.distance {
+            best = bbox_cell;
+        }
+
+        while let Some(cell) = queue.pop() {
+            if cell.distance > best.distance {
+                best = cell.clone();
+            }
+
+            // No point inside this cell can be better than the current one by more than the precision.
+            if cell.max_distance - best.distance <= precision {
+                continue;
+            }
+
+            let half = cell.half / two;
+            for (dx, dy) in [(-half, -half), (half, -half), (-half, half), (half, half)] {
+                queue.push(Cell::new(
+                    Point2::new(cell.center.x + dx, cell.center.y + dy),
+                    half,
+                    self,
+                ));
+            }
+        }
+
+        Some(best.center)
+    }
+}
+
+impl<N, P, C, Poly, T> MultiPolygonLabelPoint for T
+where
+    N: Float + Bounded + Scalar + FromPrimitive,
+    P: CartesianPoint2d<Num = N>,
+    C: ClosedContour<Point = P>,
+    Poly: Polygon<Contour = C>,
+    T: MultiPolygon<Polygon = Poly>,
+{
+    type Num = N;
+
+    fn label_point(&self, precision: N) -> Option<Point2<N>> {
+        self.polygons()
+            .map(|polygon| (polygon.outer_contour().area_signed().abs(), polygon))
+            .max_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+            .and_then(|(_, polygon)| polygon.label_point(precision))
+    }
+}
+
+/// Square cell of the search grid.
+#[derive(Clone)]
+struct Cell<N: Scalar> {
+    center: Point2<N>,
+    half: N,
+    /// Signed distance from the cell center to the polygon outline, positive inside the polygon.
+    distance: N,
+    /// Maximum distance to the polygon outline a point inside the cell can have.
+    max_distance: N,
+}
+
+impl<N: Float + Bounded + Scalar + FromPrimitive> Cell<N> {
+    fn new<P, C, T>(center: Point2<N>, half: N, polygon: &T) -> Self
+    where
+        P: CartesianPoint2d<Num = N>,
+        C: ClosedContour<Point = P>,
+        T: Polygon<Contour = C>,
+    {
+        let distance = signed_distance(&center, polygon);
+        Self {
+            center,
+            half,
+            distance,
+            max_distance: distance + half * (N::one() + N::one()).sqrt(),
+        }
+    }
+}
+
+impl<N: Float + Scalar> PartialEq for Cell<N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<N: Float + Scalar> Eq for Cell<N> {}
+
+impl<N: Float + Scalar> PartialOrd for Cell<N> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<N: Float + Scalar> Ord for Cell<N> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.max_distance
+            .partial_cmp(&other.max_distance)
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+fn signed_distance<N, P, C, T>(point: &Point2<N>, polygon: &T) -> N
+where
+    N: Float + Bounded + Scalar + FromPrimitive,
+    P: CartesianPoint2d<Num = N>,
+    C: ClosedContour<Point = P>,
+    T: Polygon<Contour = C>,
+{
+    let distance_sq = polygon
+        .iter_segments()
+        .map(|segment| segment.distance_to_point_sq(point))
+        .fold(N::infinity(), N::min);
+    let distance = distance_sq.sqrt();
+
+    if polygon.contains_point(point) {
+        distance
+    } else {
+        -distance
+    }
+}
+
+/// Centroid of the contour area. Falls back to the center of the bounding box for degenerate contours.
+fn centroid<N, P, C>(contour: &C, bbox: &Rect<N>) -> Point2<N>
+where
+    N: Float + Bounded + Scalar + FromPrimitive,
+    P: CartesianPoint2d<Num = N>,
+    C: ClosedContour<Point = P>,
+{
+    let mut area = N::zero();
+    let mut x = N::zero();
+    let mut y = N::zero();
+
+    let mut iter = contour.iter_points_closing();
+    let Some(mut prev) = iter.next() else {
+        return bbox.center();
+    };
+
+    for p in iter {
+        let f = prev.x() * p.y() - p.x() * prev.y();
+        x = x + (prev.x() + p.x()) * f;
+        y = y + (prev.y() + p.y()) * f;
+        area = area + f * (N::one() + N::one() + N::one());
+        prev = p;
+    }
+
+    if area == N::zero() {
+        bbox.center()
+    } else {
+        Point2::new(x / area, y / area)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cartesian::Point2d;
+    use crate::impls::{ClosedContour, MultiPolygon, Polygon};
+
+    fn polygon(points: &[(f64, f64)]) -> Polygon<Point2d> {
+        Polygon::new(
+            ClosedContour::new(points.iter().map(|&(x, y)| Point2d::new(x, y)).collect()),
+            vec![],
+        )
+    }
+
+    #[test]
+    fn label_point_of_square_is_center() {
+        let square = polygon(&[(0.0, 0.0), (0.0, 10.0), (10.0, 10.0), (10.0, 0.0)]);
+        let point = square.label_point(0.01).expect("point");
+        assert!((point.x - 5.0).abs() < 0.01);
+        assert!((point.y - 5.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn label_point_is_inside_concave_polygon() {
+        // U-shaped polygon, its centroid and bbox center are outside of it.
+        let u_shape = polygon(&[
+            (0.0, 0.0),
+            (0.0, 10.0),
+            (3.0, 10.0),
+            (3.0, 3.0),
+            (7.0, 3.0),
+            (7.0, 10.0),
+            (10.0, 10.0),
+            (10.0, 0.0),
+        ]);
+        let point = u_shape.label_point(0.01).expect("point");
+        assert!(u_shape.contains_point(&point));
+        assert!(point.y < 3.0 || point.x < 3.0 || point.x > 7.0);
+    }
+
+    #[test]
+    fn multipolygon_label_is_in_largest_part() {
+        let multi = MultiPolygon::from(vec![
+            polygon(&[(0.0, 0.0), (0.0, 1.0), (1.0, 1.0), (1.0, 0.0)]),
+            polygon(&[(10.0, 10.0), (10.0, 20.0), (20.0, 20.0), (20.0, 10.0)]),
+        ]);
+        let point = multi.label_point(0.01).expect("point");
+        assert!((point.x - 15.0).abs() < 0.01);
+        assert!((point.y - 15.0).abs() < 0.01);
+    }
+}
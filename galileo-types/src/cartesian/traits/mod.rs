@@ -1,5 +1,6 @@
 mod cartesian_point;
 mod contour;
+mod label_point;
 mod polygon;
 
 pub use cartesian_point::{
@@ -8,4 +9,5 @@ pub use cartesian_point::{
 };
 
 pub use contour::{CartesianClosedContour, CartesianContour, Winding};
+pub use label_point::{MultiPolygonLabelPoint, PolygonLabelPoint};
 pub use polygon::CartesianPolygon;
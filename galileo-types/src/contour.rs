@@ -43,6 +43,12 @@ pub trait Contour {
     /// instead.
     fn iter_points(&self) -> impl Iterator<Item = &'_ Self::Point>;
 
+    /// Returns the points of the contour as a slice, if the contour stores them continuously. This allows projecting
+    /// all the points at once with [`Projection::project_slice`]. The default implementation returns `None`.
+    fn points_slice(&self) -> Option<&[Self::Point]> {
+        None
+    }
+
     /// Same as [`Contour::iter_points`] but for closed contours repeats the first point again at the end of the iterator.
     fn iter_points_closing(&self) -> impl Iterator<Item = &Self::Point> {
         Box::new(ContourPointsIterator::new(
@@ -68,12 +74,14 @@ pub trait Contour {
     where
         Proj: Projection<InPoint = Self::Point> + ?Sized,
     {
-        Some(crate::impls::Contour::new(
-            self.iter_points()
+        let points = match self.points_slice() {
+            Some(points) => projection.project_slice(points)?,
+            None => self
+                .iter_points()
                 .map(|p| projection.project(p))
                 .collect::<Option<Vec<Proj::OutPoint>>>()?,
-            self.is_closed(),
-        ))
+        };
+        Some(crate::impls::Contour::new(points, self.is_closed()))
     }
 }
 
@@ -88,6 +96,11 @@ pub trait ClosedContour {
     /// include the first point at the end of iterator for closed contours, use [`Contour::iter_points_closing`]
     /// instead.
     fn iter_points(&self) -> impl Iterator<Item = &'_ Self::Point>;
+
+    /// See [`Contour::points_slice`].
+    fn points_slice(&self) -> Option<&[Self::Point]> {
+        None
+    }
 }
 
 impl<P, T: ClosedContour<Point = P>> Contour for T {
@@ -100,6 +113,10 @@ impl<P, T: ClosedContour<Point = P>> Contour for T {
     fn iter_points(&self) -> impl Iterator<Item = &'_ Self::Point> {
         self.iter_points()
     }
+
+    fn points_slice(&self) -> Option<&[Self::Point]> {
+        ClosedContour::points_slice(self)
+    }
 }
 
 /// Iterator of contour points.
@@ -195,14 +212,7 @@ where
     where
         Proj: Projection<InPoint = Self::Point> + ?Sized,
     {
-        let points = self
-            .iter_points()
-            .map(|p| projection.project(p))
-            .collect::<Option<Vec<Proj::OutPoint>>>()?;
-        Some(Geom::Contour(crate::impls::Contour::new(
-            points,
-            self.is_closed(),
-        )))
+        Some(Geom::Contour(self.project_points(projection)?))
     }
 }
 
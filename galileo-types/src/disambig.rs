@@ -86,6 +86,10 @@ impl<T: Contour, Space> Contour for Disambig<T, Space> {
     fn iter_points(&self) -> impl Iterator<Item = &'_ Self::Point> {
         self.inner.iter_points()
     }
+
+    fn points_slice(&self) -> Option<&[Self::Point]> {
+        self.inner.points_slice()
+    }
 }
 
 impl<T: Polygon, Space> Polygon for Disambig<T, Space> {
@@ -8,4 +8,7 @@ pub enum GalileoTypesError {
     /// Geometry conversion error.
     #[error("invalid input geometry: {0}")]
     Conversion(String),
+    /// String cannot be parsed as coordinates.
+    #[error("invalid coordinates: {0}")]
+    Parse(String),
 }
@@ -1,4 +1,4 @@
-use crate::cartesian::NewCartesianPoint2d;
+use crate::cartesian::{NewCartesianPoint2d, Rect};
 use crate::geo::datum::Datum;
 use crate::geo::impls::projection::{GeodesyProjection, WebMercator};
 use crate::geo::traits::point::NewGeoPoint;
@@ -24,6 +24,9 @@ pub enum ProjectionType {
     WebMercator,
     /// `proj` or `geodesy` definition of the projection.
     Other(String),
+    /// Coordinates are not related to the Earth, e.g. pixels of an image or units of a CAD drawing. Geographic
+    /// coordinates cannot be projected into such CRS.
+    Identity,
 }
 
 impl Crs {
@@ -39,6 +42,15 @@ impl Crs {
         projection_type: ProjectionType::None,
     };
 
+    /// Planar coordinate system for non-geographic content (scanned images, game maps, floor plans etc.). Its
+    /// coordinates are used as they are, without any projection, and are not bounded.
+    ///
+    /// The datum of this CRS is not used for any calculations.
+    pub const PLANAR: Crs = Crs {
+        datum: Datum::WGS84,
+        projection_type: ProjectionType::Identity,
+    };
+
     /// Creates a new CRS.
     pub fn new(datum: Datum, projection_type: ProjectionType) -> Self {
         Self {
@@ -47,6 +59,26 @@ impl Crs {
         }
     }
 
+    /// Returns `true` if the coordinates of the CRS are not geographic, see [`Crs::PLANAR`].
+    pub fn is_planar(&self) -> bool {
+        self.projection_type == ProjectionType::Identity
+    }
+
+    /// Returns the valid range of coordinates of the CRS, or `None` if it is not known or the CRS is not bounded
+    /// (like [`Crs::PLANAR`]).
+    ///
+    /// For geographic coordinates, `x` of the rectangle is longitude and `y` is latitude.
+    pub fn domain(&self) -> Option<Rect> {
+        match &self.projection_type {
+            ProjectionType::None => Some(Rect::new(-180.0, -90.0, 180.0, 90.0)),
+            ProjectionType::WebMercator => {
+                let max = self.datum.semimajor() * std::f64::consts::PI;
+                Some(Rect::new(-max, -max, max, max))
+            }
+            _ => None,
+        }
+    }
+
     /// Returns a projection that converts geographic coordinates into the coordinates of this CRS.
     ///
     /// Returns `None` if the CRS coordinates cannot be projected from geographic coordinates.
@@ -0,0 +1,136 @@
+use crate::error::GalileoTypesError;
+
+/// Angle split into degrees, minutes and seconds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Dms {
+    /// True for southern latitudes and western longitudes.
+    pub negative: bool,
+    /// Whole degrees.
+    pub degrees: u32,
+    /// Whole minutes, `0..60`.
+    pub minutes: u32,
+    /// Seconds, `0.0..60.0`.
+    pub seconds: f64,
+}
+
+impl Dms {
+    /// Splits the angle given in decimal degrees.
+    pub fn from_degrees(value: f64) -> Self {
+        let abs = value.abs();
+        let degrees = abs.trunc();
+        let minutes = ((abs - degrees) * 60.0).trunc();
+        let seconds = (abs - degrees - minutes / 60.0) * 3600.0;
+
+        Self {
+            negative: value < 0.0,
+            degrees: degrees as u32,
+            minutes: minutes as u32,
+            seconds: seconds.max(0.0),
+        }
+    }
+
+    /// Returns the angle in decimal degrees.
+    pub fn to_degrees(&self) -> f64 {
+        let value = self.degrees as f64 + self.minutes as f64 / 60.0 + self.seconds / 3600.0;
+        if self.negative {
+            -value
+        } else {
+            value
+        }
+    }
+
+    /// Formats the angle as a latitude, e.g. `37°33'57.6"N`.
+    pub fn format_lat(&self, precision: usize) -> String {
+        self.format(precision, if self.negative { 'S' } else { 'N' })
+    }
+
+    /// Formats the angle as a longitude, e.g. `126°58'42.2"E`.
+    pub fn format_lon(&self, precision: usize) -> String {
+        self.format(precision, if self.negative { 'W' } else { 'E' })
+    }
+
+    fn format(&self, precision: usize, hemisphere: char) -> String {
+        // Round the total number of seconds first, so that e.g. 59.99" does not become 60.0".
+        let scale = 10f64.powi(precision as i32);
+        let total = ((self.degrees as f64 * 3600.0 + self.minutes as f64 * 60.0 + self.seconds)
+            * scale)
+            .round();
+        let seconds_total = total / scale;
+
+        let degrees = (seconds_total / 3600.0).floor();
+        let minutes = ((seconds_total - degrees * 3600.0) / 60.0).floor();
+        let seconds = seconds_total - degrees * 3600.0 - minutes * 60.0;
+
+        format!("{degrees}°{minutes}'{seconds:.precision$}\"{hemisphere}")
+    }
+
+    /// Parses an angle in decimal degrees (`-37.566`), degrees with hemisphere letter (`37.566 S`) or degrees,
+    /// minutes and seconds (`37°33'57.6"S`, `37 33 57.6 S`, `37d 33m 57.6s S`). Hemisphere letters must be uppercase.
+    /// Returns the value in decimal degrees.
+    pub fn parse_degrees(input: &str) -> Result<f64, GalileoTypesError> {
+        let error = || GalileoTypesError::Parse(input.to_string());
+        let input = input.trim();
+
+        let (body, negative) = match input.chars().last() {
+            Some('N' | 'E') => (&input[..input.len() - 1], false),
+            Some('S' | 'W') => (&input[..input.len() - 1], true),
+            _ => (input, false),
+        };
+
+        let numbers: Vec<&str> = body
+            .split(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-' || c == '+'))
+            .filter(|s| !s.is_empty())
+            .collect();
+        if numbers.is_empty() || numbers.len() > 3 {
+            return Err(error());
+        }
+
+        let mut parts = [0.0f64; 3];
+        for (part, number) in parts.iter_mut().zip(&numbers) {
+            *part = number.parse().map_err(|_| error())?;
+        }
+
+        let [degrees, minutes, seconds] = parts;
+        if !(0.0..60.0).contains(&minutes) || !(0.0..60.0).contains(&seconds) {
+            return Err(error());
+        }
+
+        let sign = if degrees < 0.0 || body.trim_start().starts_with('-') {
+            -1.0
+        } else {
+            1.0
+        };
+        let value = degrees.abs() + minutes / 60.0 + seconds / 3600.0;
+
+        match (negative, sign < 0.0) {
+            (true, true) => Err(error()),
+            (true, false) | (false, true) => Ok(-value),
+            (false, false) => Ok(value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rounds_seconds_with_carry() {
+        let dms = Dms::from_degrees(-10.999_999_9);
+        assert!(dms.negative);
+        assert_eq!(dms.format_lat(1), "11°0'0.0\"S");
+        assert!((dms.to_degrees() + 10.999_999_9).abs() < 1e-9);
+    }
+
+    #[test]
+    fn parses_degrees() {
+        let parse = |s| Dms::parse_degrees(s).expect(s);
+        assert_eq!(parse("12.5"), 12.5);
+        assert_eq!(parse("-12.5"), -12.5);
+        assert_eq!(parse("12.5 W"), -12.5);
+        assert_eq!(parse("12°30'0\"S"), -12.5);
+        assert!((parse("12d 30m 36s N") - 12.51).abs() < 1e-12);
+        assert!(Dms::parse_degrees("12 75 0").is_err());
+        assert!(Dms::parse_degrees("-12 S").is_err());
+    }
+}
@@ -0,0 +1,190 @@
+//! Conversion of geographic coordinates to and from the text representations used by people: decimal degrees,
+//! degrees-minutes-seconds, UTM, MGRS and Plus Codes.
+//!
+//! [`CoordinateFormat`] can be used to present coordinates in a format selected by the user (e.g. in a status bar),
+//! and [`parse_coordinates`] recognizes all the supported formats (e.g. in a search box).
+//!
+//! ```
+//! use galileo_types::geo::format::{parse_coordinates, CoordinateFormat};
+//! use galileo_types::geo::GeoPoint;
+//! use galileo_types::latlon;
+//!
+//! let point = latlon!(37.566, 126.9784);
+//! assert_eq!(
+//!     CoordinateFormat::Dms { precision: 0 }.format(&point),
+//!     "37°33'58\"N 126°58'42\"E"
+//! );
+//!
+//! let parsed = parse_coordinates("52SCG 21458 59584").unwrap();
+//! assert!((parsed.lat() - 37.566).abs() < 0.001);
+//! ```
+
+use crate::error::GalileoTypesError;
+use crate::geo::impls::GeoPoint2d;
+use crate::geo::{GeoPoint, NewGeoPoint};
+
+mod dms;
+mod plus_code;
+mod utm;
+
+pub use dms::Dms;
+pub use plus_code::{PlusCode, PlusCodeArea};
+pub use utm::{Mgrs, Utm};
+
+/// Text representation of geographic coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoordinateFormat {
+    /// Decimal degrees with the given number of digits after the decimal point, e.g. `37.56600, 126.97840`.
+    Decimal {
+        /// Number of digits after the decimal point.
+        precision: usize,
+    },
+    /// Degrees, minutes and seconds with hemisphere letters, e.g. `37°33'57.6"N 126°58'42.2"E`.
+    Dms {
+        /// Number of digits after the decimal point of the seconds.
+        precision: usize,
+    },
+    /// Universal Transverse Mercator coordinates rounded to meters, e.g. `52S 321458 4159584`.
+    Utm,
+    /// Military Grid Reference System, e.g. `52S CG 21458 59584`.
+    Mgrs {
+        /// Number of digits of easting and northing, from 0 (100 km precision) to 5 (1 m precision).
+        digits: usize,
+    },
+    /// Open Location Code (Plus Code), e.g. `8Q98HX8H+C9`.
+    PlusCode {
+        /// Number of significant digits of the code, 10 gives approximately 14 m precision.
+        length: usize,
+    },
+}
+
+impl Default for CoordinateFormat {
+    fn default() -> Self {
+        Self::Decimal { precision: 5 }
+    }
+}
+
+impl CoordinateFormat {
+    /// Formats the point. UTM and MGRS are not defined for polar areas, for points there the decimal format is used
+    /// instead.
+    pub fn format(&self, point: &impl GeoPoint<Num = f64>) -> String {
+        match *self {
+            Self::Decimal { precision } => {
+                format!("{:.precision$}, {:.precision$}", point.lat(), point.lon())
+            }
+            Self::Dms { precision } => format!(
+                "{} {}",
+                Dms::from_degrees(point.lat()).format_lat(precision),
+                Dms::from_degrees(point.lon()).format_lon(precision)
+            ),
+            Self::Utm => Utm::from_geo(point)
+                .map(|utm| utm.to_string())
+                .unwrap_or_else(|| Self::default().format(point)),
+            Self::Mgrs { digits } => Mgrs::from_geo(point, digits)
+                .map(|mgrs| mgrs.to_string())
+                .unwrap_or_else(|| Self::default().format(point)),
+            Self::PlusCode { length } => PlusCode::encode(point, length).to_string(),
+        }
+    }
+}
+
+/// Parses coordinates in any of the formats of [`CoordinateFormat`].
+///
+/// Decimal and DMS coordinates are expected in latitude-longitude order, separated by a comma or a space. For MGRS and
+/// Plus Codes the center of the designated area is returned.
+pub fn parse_coordinates(input: &str) -> Result<GeoPoint2d, GalileoTypesError> {
+    let input = input.trim();
+
+    if let Ok(code) = input.parse::<PlusCode>() {
+        return Ok(code.decode().center());
+    }
+    if let Ok(mgrs) = input.parse::<Mgrs>() {
+        return mgrs.to_geo().ok_or_else(|| parse_error(input));
+    }
+    if let Ok(utm) = input.parse::<Utm>() {
+        return utm.to_geo().ok_or_else(|| parse_error(input));
+    }
+
+    parse_lat_lon(input)
+}
+
+fn parse_lat_lon(input: &str) -> Result<GeoPoint2d, GalileoTypesError> {
+    let parts: Vec<&str> = if input.contains(',') {
+        input.split(',').map(str::trim).collect()
+    } else {
+        split_dms_pair(input)
+    };
+
+    let [lat, lon] = parts[..] else {
+        return Err(parse_error(input));
+    };
+
+    let lat = Dms::parse_degrees(lat)?;
+    let lon = Dms::parse_degrees(lon)?;
+    if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lon) {
+        return Err(parse_error(input));
+    }
+
+    Ok(GeoPoint2d::latlon(lat, lon))
+}
+
+/// Splits `37°33'57.6"N 126°58'42.2"E` or `37.566 126.978` into latitude and longitude parts.
+fn split_dms_pair(input: &str) -> Vec<&str> {
+    if let Some(pos) = input.find(['N', 'S']) {
+        let (lat, lon) = input.split_at(pos + 1);
+        return vec![lat.trim(), lon.trim()];
+    }
+
+    input.split_whitespace().collect()
+}
+
+fn parse_error(input: &str) -> GalileoTypesError {
+    GalileoTypesError::Parse(input.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::latlon;
+
+    #[test]
+    fn formats_all_representations() {
+        let point = latlon!(37.566, 126.9784);
+
+        assert_eq!(
+            CoordinateFormat::Decimal { precision: 3 }.format(&point),
+            "37.566, 126.978"
+        );
+        assert_eq!(
+            CoordinateFormat::Dms { precision: 1 }.format(&point),
+            "37°33'57.6\"N 126°58'42.2\"E"
+        );
+        assert_eq!(CoordinateFormat::Utm.format(&point), "52S 321458 4159584");
+        assert_eq!(
+            CoordinateFormat::Mgrs { digits: 5 }.format(&point),
+            "52S CG 21458 59584"
+        );
+        assert_eq!(
+            CoordinateFormat::PlusCode { length: 10 }.format(&point),
+            "8Q98HX8H+C9"
+        );
+    }
+
+    #[test]
+    fn parses_all_representations() {
+        for input in [
+            "37.566, 126.9784",
+            "37°33'57.6\"N 126°58'42.2\"E",
+            "52S 321458 4159584",
+            "52SCG2145859584",
+            "8Q98HX8H+C9",
+        ] {
+            let point = parse_coordinates(input).expect(input);
+            assert!((point.lat() - 37.566).abs() < 0.001, "{input}");
+            assert!((point.lon() - 126.9784).abs() < 0.001, "{input}");
+        }
+
+        assert!(parse_coordinates("not a coordinate").is_err());
+        assert!(parse_coordinates("95, 10").is_err());
+    }
+}
@@ -0,0 +1,240 @@
+use crate::error::GalileoTypesError;
+use crate::geo::impls::GeoPoint2d;
+use crate::geo::{GeoPoint, NewGeoPoint};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+const ALPHABET: &[u8] = b"23456789CFGHJMPQRVWX";
+const SEPARATOR: char = '+';
+const SEPARATOR_POSITION: usize = 8;
+const PADDING: char = '0';
+/// Number of digits encoded as latitude-longitude pairs.
+const PAIR_CODE_LENGTH: usize = 10;
+/// Maximum number of digits of a code.
+const MAX_CODE_LENGTH: usize = 15;
+const GRID_ROWS: i64 = 5;
+const GRID_COLUMNS: i64 = 4;
+/// Latitude is encoded as an integer number of the smallest possible cells: 20^3 * 5^5 per degree.
+const LAT_MULTIPLIER: i64 = 8000 * 3125;
+/// Longitude is encoded as an integer number of the smallest possible cells: 20^3 * 4^5 per degree.
+const LON_MULTIPLIER: i64 = 8000 * 1024;
+
+/// Full [Open Location Code](https://github.com/google/open-location-code) (Plus Code), e.g. `8Q98HX8H+C9`.
+///
+/// Short codes relative to a reference location are not supported.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PlusCode(String);
+
+/// Area designated by a [`PlusCode`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlusCodeArea {
+    /// Latitude of the southern edge of the area.
+    pub south: f64,
+    /// Longitude of the western edge of the area.
+    pub west: f64,
+    /// Height of the area in degrees.
+    pub lat_size: f64,
+    /// Width of the area in degrees.
+    pub lon_size: f64,
+}
+
+impl PlusCodeArea {
+    /// Center of the area.
+    pub fn center(&self) -> GeoPoint2d {
+        GeoPoint2d::latlon(
+            (self.south + self.lat_size / 2.0).min(90.0),
+            (self.west + self.lon_size / 2.0).min(180.0),
+        )
+    }
+}
+
+impl PlusCode {
+    /// Encodes the point with the given number of significant digits. The length is clamped to `2..=15` and rounded
+    /// up to an even number if it is shorter than 10.
+    pub fn encode(point: &impl GeoPoint<Num = f64>, length: usize) -> Self {
+        let mut length = length.clamp(2, MAX_CODE_LENGTH);
+        if length < PAIR_CODE_LENGTH && length % 2 == 1 {
+            length += 1;
+        }
+
+        let lat = point.lat().clamp(-90.0, 90.0);
+        let lon = (point.lon() + 180.0).rem_euclid(360.0);
+
+        let mut lat_value = ((lat + 90.0) * LAT_MULTIPLIER as f64).floor() as i64;
+        // The northern edge belongs to the cell below it.
+        lat_value = lat_value.min(180 * LAT_MULTIPLIER - 1);
+        let mut lon_value = (lon * LON_MULTIPLIER as f64).floor() as i64;
+
+        let mut reversed = Vec::with_capacity(MAX_CODE_LENGTH);
+        for _ in PAIR_CODE_LENGTH..MAX_CODE_LENGTH {
+            let row = lat_value % GRID_ROWS;
+            let column = lon_value % GRID_COLUMNS;
+            reversed.push(ALPHABET[(row * GRID_COLUMNS + column) as usize]);
+            lat_value /= GRID_ROWS;
+            lon_value /= GRID_COLUMNS;
+        }
+
+        for _ in 0..PAIR_CODE_LENGTH / 2 {
+            reversed.push(ALPHABET[(lon_value % 20) as usize]);
+            reversed.push(ALPHABET[(lat_value % 20) as usize]);
+            lat_value /= 20;
+            lon_value /= 20;
+        }
+
+        let digits: String = reversed.iter().rev().map(|c| *c as char).collect();
+        let digits = &digits[..length];
+
+        let code = if length >= SEPARATOR_POSITION {
+            format!(
+                "{}{SEPARATOR}{}",
+                &digits[..SEPARATOR_POSITION],
+                &digits[SEPARATOR_POSITION..]
+            )
+        } else {
+            format!(
+                "{digits}{}{SEPARATOR}",
+                PADDING.to_string().repeat(SEPARATOR_POSITION - length)
+            )
+        };
+
+        Self(code)
+    }
+
+    /// Returns the area designated by the code.
+    pub fn decode(&self) -> PlusCodeArea {
+        let digits: Vec<usize> = self
+            .0
+            .chars()
+            .filter(|c| *c != SEPARATOR && *c != PADDING)
+            .filter_map(|c| ALPHABET.iter().position(|a| *a as char == c))
+            .collect();
+
+        let mut south = -90.0;
+        let mut west = -180.0;
+        let mut lat_size = 400.0;
+        let mut lon_size = 400.0;
+
+        for pair in digits[..digits.len().min(PAIR_CODE_LENGTH)].chunks(2) {
+            lat_size /= 20.0;
+            lon_size /= 20.0;
+            south += pair[0] as f64 * lat_size;
+            if let Some(lon_digit) = pair.get(1) {
+                west += *lon_digit as f64 * lon_size;
+            }
+        }
+
+        for digit in digits.iter().skip(PAIR_CODE_LENGTH) {
+            lat_size /= GRID_ROWS as f64;
+            lon_size /= GRID_COLUMNS as f64;
+            south += (*digit as i64 / GRID_COLUMNS) as f64 * lat_size;
+            west += (*digit as i64 % GRID_COLUMNS) as f64 * lon_size;
+        }
+
+        PlusCodeArea {
+            south,
+            west,
+            lat_size,
+            lon_size,
+        }
+    }
+
+    /// The code as a string.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for PlusCode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromStr for PlusCode {
+    type Err = GalileoTypesError;
+
+    /// Parses a full code. The letters are case-insensitive.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || GalileoTypesError::Parse(s.to_string());
+        let code = s.trim().to_ascii_uppercase();
+
+        if code.find(SEPARATOR) != Some(SEPARATOR_POSITION) || code.matches(SEPARATOR).count() != 1
+        {
+            return Err(error());
+        }
+
+        let (head, tail) = code.split_at(SEPARATOR_POSITION);
+        let tail = &tail[1..];
+        let significant = head.trim_end_matches(PADDING);
+        let is_valid_digit = |c: char| ALPHABET.contains(&(c as u8)) && c.is_ascii();
+
+        let padded = significant.len() < SEPARATOR_POSITION;
+        if !significant.chars().all(is_valid_digit)
+            || !tail.chars().all(is_valid_digit)
+            || significant.len() < 2
+            || significant.len() % 2 == 1
+            || (padded && !tail.is_empty())
+            || tail.len() == 1
+            || tail.len() > MAX_CODE_LENGTH - SEPARATOR_POSITION
+        {
+            return Err(error());
+        }
+
+        // The first latitude digit must be below 9 (180 degrees) and the first longitude digit below 18 (360 degrees).
+        let first_lat = ALPHABET.iter().position(|c| *c == code.as_bytes()[0]);
+        let first_lon = ALPHABET.iter().position(|c| *c == code.as_bytes()[1]);
+        if first_lat.is_none_or(|d| d >= 9) || first_lon.is_none_or(|d| d >= 18) {
+            return Err(error());
+        }
+
+        Ok(Self(code))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::latlon;
+
+    #[test]
+    fn encodes_reference_points() {
+        assert_eq!(
+            PlusCode::encode(&latlon!(47.0000625, 8.0000625), 10).as_str(),
+            "8FVC2222+22"
+        );
+        assert_eq!(
+            PlusCode::encode(&latlon!(47.0000625, 8.0000625), 4).as_str(),
+            "8FVC0000+"
+        );
+        assert_eq!(
+            PlusCode::encode(&latlon!(90.0, 180.0), 10).as_str().len(),
+            11
+        );
+    }
+
+    #[test]
+    fn decode_contains_encoded_point() {
+        for length in [4, 8, 10, 11, 15] {
+            let point = latlon!(-41.2865, 174.7762);
+            let code = PlusCode::encode(&point, length);
+            let parsed: PlusCode = code.as_str().parse().expect("valid code");
+            let area = parsed.decode();
+
+            assert!(area.south <= point.lat() && point.lat() < area.south + area.lat_size);
+            assert!(area.west <= point.lon() && point.lon() < area.west + area.lon_size);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_codes() {
+        for code in [
+            "8FVC2222",
+            "8FVC22+22",
+            "8FVC0000+22",
+            "8FVC2222+2",
+            "ZZVC2222+22",
+        ] {
+            assert!(code.parse::<PlusCode>().is_err(), "{code}");
+        }
+    }
+}
@@ -0,0 +1,444 @@
+use crate::error::GalileoTypesError;
+use crate::geo::impls::GeoPoint2d;
+use crate::geo::{Datum, GeoPoint, NewGeoPoint};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// Scale factor on the central meridian of a UTM zone.
+const K0: f64 = 0.9996;
+const FALSE_EASTING: f64 = 500_000.0;
+const FALSE_NORTHING_SOUTH: f64 = 10_000_000.0;
+
+/// Latitude band letters, each band (except `X`) covers 8 degrees starting from 80°S.
+const BANDS: &[u8] = b"CDEFGHJKLMNPQRSTUVWX";
+/// Column letters of MGRS 100 km squares for zone sets 1, 2 and 3.
+const MGRS_COLUMNS: [&[u8]; 3] = [b"ABCDEFGH", b"JKLMNPQR", b"STUVWXYZ"];
+/// Row letters of MGRS 100 km squares.
+const MGRS_ROWS: &[u8] = b"ABCDEFGHJKLMNPQRSTUV";
+
+/// Coordinates in the Universal Transverse Mercator system on the WGS84 ellipsoid.
+///
+/// UTM is defined between 80°S and 84°N. Exceptions of zones 31-37 around Norway and Svalbard are taken into account.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Utm {
+    /// Zone number, `1..=60`.
+    pub zone: u8,
+    /// Latitude band letter (`C` to `X`). Bands from `N` are in the northern hemisphere.
+    pub band: char,
+    /// Easting in meters.
+    pub easting: f64,
+    /// Northing in meters.
+    pub northing: f64,
+}
+
+impl Utm {
+    /// Converts geographic coordinates to UTM. Returns `None` if the point is outside of the UTM latitude range.
+    pub fn from_geo(point: &impl GeoPoint<Num = f64>) -> Option<Self> {
+        let (lat, lon) = (point.lat(), normalize_lon(point.lon()));
+        if !(-80.0..=84.0).contains(&lat) {
+            return None;
+        }
+
+        let zone = zone_number(lat, lon);
+        let band = band_letter(lat);
+        let (easting, northing) = project(lat, lon, zone);
+
+        Some(Self {
+            zone,
+            band,
+            easting,
+            northing,
+        })
+    }
+
+    /// Converts the coordinates to geographic. Returns `None` if the zone or the band is invalid.
+    pub fn to_geo(&self) -> Option<GeoPoint2d> {
+        if !(1..=60).contains(&self.zone) || !BANDS.contains(&(self.band as u8)) {
+            return None;
+        }
+
+        let (lat, lon) = unproject(self.easting, self.northing, self.zone, self.is_north());
+        Some(GeoPoint2d::latlon(lat, normalize_lon(lon)))
+    }
+
+    /// Whether the coordinates are in the northern hemisphere.
+    pub fn is_north(&self) -> bool {
+        self.band >= 'N'
+    }
+}
+
+impl Display for Utm {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}{} {:.0} {:.0}",
+            self.zone,
+            self.band,
+            self.easting.floor(),
+            self.northing.floor()
+        )
+    }
+}
+
+impl FromStr for Utm {
+    type Err = GalileoTypesError;
+
+    /// Parses strings like `52S 321458 4159584`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || GalileoTypesError::Parse(s.to_string());
+        let parts: Vec<&str> = s.split_whitespace().collect();
+        let [zone, easting, northing] = parts[..] else {
+            return Err(error());
+        };
+
+        let (zone, band) = parse_zone(zone).ok_or_else(error)?;
+        let easting: f64 = easting.parse().map_err(|_| error())?;
+        let northing: f64 = northing.parse().map_err(|_| error())?;
+        if !(0.0..1_000_000.0).contains(&easting) || !(0.0..10_000_000.0).contains(&northing) {
+            return Err(error());
+        }
+
+        Ok(Self {
+            zone,
+            band,
+            easting,
+            northing,
+        })
+    }
+}
+
+/// Military Grid Reference System coordinates: UTM zone and band, 100 km square identifier and easting and northing
+/// inside the square.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mgrs {
+    /// Zone number, `1..=60`.
+    pub zone: u8,
+    /// Latitude band letter.
+    pub band: char,
+    /// Column letter of the 100 km square.
+    pub column: char,
+    /// Row letter of the 100 km square.
+    pub row: char,
+    /// Easting inside the square in meters, truncated to the precision.
+    pub easting: u32,
+    /// Northing inside the square in meters, truncated to the precision.
+    pub northing: u32,
+    /// Number of digits of easting and northing, `0..=5`.
+    pub digits: usize,
+}
+
+impl Mgrs {
+    /// Converts geographic coordinates to MGRS with the given number of digits (5 digits give 1 m precision).
+    /// Returns `None` if the point is outside of the UTM latitude range.
+    pub fn from_geo(point: &impl GeoPoint<Num = f64>, digits: usize) -> Option<Self> {
+        let utm = Utm::from_geo(point)?;
+        let digits = digits.min(5);
+
+        let column_index = (utm.easting / 100_000.0).floor() as usize;
+        let row_index = (utm.northing / 100_000.0).floor() as usize;
+        let columns = MGRS_COLUMNS[(utm.zone as usize - 1) % 3];
+
+        let step = 10u32.pow(5 - digits as u32);
+        let truncate = |value: f64| (value.rem_euclid(100_000.0) as u32) / step * step;
+
+        Some(Self {
+            zone: utm.zone,
+            band: utm.band,
+            column: *columns.get(column_index.checked_sub(1)?)? as char,
+            row: MGRS_ROWS[(row_index + row_offset(utm.zone)) % MGRS_ROWS.len()] as char,
+            easting: truncate(utm.easting),
+            northing: truncate(utm.northing),
+            digits,
+        })
+    }
+
+    /// Converts the coordinates to geographic. The center of the square designated by the coordinates is returned.
+    pub fn to_geo(&self) -> Option<GeoPoint2d> {
+        if !(1..=60).contains(&self.zone) {
+            return None;
+        }
+
+        let columns = MGRS_COLUMNS[(self.zone as usize - 1) % 3];
+        let column_index = columns.iter().position(|c| *c == self.column as u8)? + 1;
+        let row_index = MGRS_ROWS.iter().position(|c| *c == self.row as u8)?;
+        let row_index = (row_index + MGRS_ROWS.len() - row_offset(self.zone)) % MGRS_ROWS.len();
+
+        let half_step = 10f64.powi(5 - self.digits as i32) / 2.0;
+        let easting = column_index as f64 * 100_000.0 + self.easting as f64 + half_step;
+        let mut northing = row_index as f64 * 100_000.0 + self.northing as f64 + half_step;
+
+        // Row letters repeat every 2000 km, so the northing is restored from the latitude band.
+        let band_index = BANDS.iter().position(|c| *c == self.band as u8)?;
+        let band_south = -80.0 + 8.0 * band_index as f64;
+        let central_lon = central_meridian(self.zone);
+        let (_, band_min_northing) = project(band_south, central_lon, self.zone);
+        while northing < band_min_northing - 100_000.0 {
+            northing += 2_000_000.0;
+        }
+
+        Utm {
+            zone: self.zone,
+            band: self.band,
+            easting,
+            northing,
+        }
+        .to_geo()
+    }
+}
+
+impl Display for Mgrs {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{} {}{}", self.zone, self.band, self.column, self.row)?;
+        if self.digits > 0 {
+            let divider = 10u32.pow(5 - self.digits as u32);
+            write!(
+                f,
+                " {:0width$} {:0width$}",
+                self.easting / divider,
+                self.northing / divider,
+                width = self.digits
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for Mgrs {
+    type Err = GalileoTypesError;
+
+    /// Parses strings like `52S CG 21458 59584` or `52SCG2145859584`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || GalileoTypesError::Parse(s.to_string());
+        let compact: String = s.chars().filter(|c| !c.is_whitespace()).collect();
+        let compact = compact.to_ascii_uppercase();
+
+        let zone_len = compact.chars().take_while(char::is_ascii_digit).count();
+        if !compact.is_ascii() || !(1..=2).contains(&zone_len) || compact.len() < zone_len + 3 {
+            return Err(error());
+        }
+
+        let (zone, band) = parse_zone(&compact[..zone_len + 1]).ok_or_else(error)?;
+        let mut letters = compact[zone_len + 1..zone_len + 3].chars();
+        let column = letters.next().ok_or_else(error)?;
+        let row = letters.next().ok_or_else(error)?;
+
+        let numbers = &compact[zone_len + 3..];
+        if !numbers.len().is_multiple_of(2)
+            || numbers.len() > 10
+            || !numbers.chars().all(|c| c.is_ascii_digit())
+        {
+            return Err(error());
+        }
+
+        let digits = numbers.len() / 2;
+        let multiplier = 10u32.pow(5 - digits as u32);
+        let parse = |s: &str| -> Result<u32, GalileoTypesError> {
+            if s.is_empty() {
+                Ok(0)
+            } else {
+                Ok(s.parse::<u32>().map_err(|_| error())? * multiplier)
+            }
+        };
+
+        let mgrs = Self {
+            zone,
+            band,
+            column,
+            row,
+            easting: parse(&numbers[..digits])?,
+            northing: parse(&numbers[digits..])?,
+            digits,
+        };
+
+        // Validate the square letters.
+        mgrs.to_geo().ok_or_else(error)?;
+        Ok(mgrs)
+    }
+}
+
+fn parse_zone(s: &str) -> Option<(u8, char)> {
+    let (band_start, band) = s.char_indices().last()?;
+    let band = band.to_ascii_uppercase();
+    let zone: u8 = s[..band_start].parse().ok()?;
+    ((1..=60).contains(&zone) && BANDS.contains(&(band as u8))).then_some((zone, band))
+}
+
+fn normalize_lon(lon: f64) -> f64 {
+    (lon + 180.0).rem_euclid(360.0) - 180.0
+}
+
+fn zone_number(lat: f64, lon: f64) -> u8 {
+    // Southwest coast of Norway.
+    if (56.0..64.0).contains(&lat) && (3.0..12.0).contains(&lon) {
+        return 32;
+    }
+
+    // Svalbard.
+    if (72.0..=84.0).contains(&lat) && (0.0..42.0).contains(&lon) {
+        return match lon {
+            lon if lon < 9.0 => 31,
+            lon if lon < 21.0 => 33,
+            lon if lon < 33.0 => 35,
+            _ => 37,
+        };
+    }
+
+    (((lon + 180.0) / 6.0).floor() as u8 % 60) + 1
+}
+
+fn band_letter(lat: f64) -> char {
+    let index = ((lat + 80.0) / 8.0)
+        .floor()
+        .clamp(0.0, (BANDS.len() - 1) as f64) as usize;
+    BANDS[index] as char
+}
+
+fn central_meridian(zone: u8) -> f64 {
+    (zone as f64 - 1.0) * 6.0 - 180.0 + 3.0
+}
+
+/// Row letters of even zones are shifted by 5 letters.
+fn row_offset(zone: u8) -> usize {
+    if zone.is_multiple_of(2) {
+        5
+    } else {
+        0
+    }
+}
+
+fn ellipsoid() -> (f64, f64, f64) {
+    let datum = Datum::WGS84;
+    let a = datum.semimajor();
+    let f = 1.0 / datum.inv_flattening();
+    let e2 = f * (2.0 - f);
+    (a, e2, e2 / (1.0 - e2))
+}
+
+/// Transverse Mercator projection (Snyder, "Map Projections: A Working Manual", p. 61).
+fn project(lat: f64, lon: f64, zone: u8) -> (f64, f64) {
+    let (a, e2, ep2) = ellipsoid();
+    let phi = lat.to_radians();
+    let (sin_phi, cos_phi) = phi.sin_cos();
+
+    let n = a / (1.0 - e2 * sin_phi * sin_phi).sqrt();
+    let t = phi.tan().powi(2);
+    let c = ep2 * cos_phi * cos_phi;
+    let big_a = cos_phi * (lon - central_meridian(zone)).to_radians();
+
+    let e4 = e2 * e2;
+    let e6 = e4 * e2;
+    let m = a
+        * ((1.0 - e2 / 4.0 - 3.0 * e4 / 64.0 - 5.0 * e6 / 256.0) * phi
+            - (3.0 * e2 / 8.0 + 3.0 * e4 / 32.0 + 45.0 * e6 / 1024.0) * (2.0 * phi).sin()
+            + (15.0 * e4 / 256.0 + 45.0 * e6 / 1024.0) * (4.0 * phi).sin()
+            - (35.0 * e6 / 3072.0) * (6.0 * phi).sin());
+
+    let easting = K0
+        * n
+        * (big_a
+            + (1.0 - t + c) * big_a.powi(3) / 6.0
+            + (5.0 - 18.0 * t + t * t + 72.0 * c - 58.0 * ep2) * big_a.powi(5) / 120.0)
+        + FALSE_EASTING;
+    let mut northing = K0
+        * (m + n
+            * phi.tan()
+            * (big_a.powi(2) / 2.0
+                + (5.0 - t + 9.0 * c + 4.0 * c * c) * big_a.powi(4) / 24.0
+                + (61.0 - 58.0 * t + t * t + 600.0 * c - 330.0 * ep2) * big_a.powi(6) / 720.0));
+    if lat < 0.0 {
+        northing += FALSE_NORTHING_SOUTH;
+    }
+
+    (easting, northing)
+}
+
+/// Inverse of [`project`].
+fn unproject(easting: f64, northing: f64, zone: u8, is_north: bool) -> (f64, f64) {
+    let (a, e2, ep2) = ellipsoid();
+    let x = easting - FALSE_EASTING;
+    let y = if is_north {
+        northing
+    } else {
+        northing - FALSE_NORTHING_SOUTH
+    };
+
+    let e4 = e2 * e2;
+    let e6 = e4 * e2;
+    let m = y / K0;
+    let mu = m / (a * (1.0 - e2 / 4.0 - 3.0 * e4 / 64.0 - 5.0 * e6 / 256.0));
+    let e1 = (1.0 - (1.0 - e2).sqrt()) / (1.0 + (1.0 - e2).sqrt());
+
+    let phi1 = mu
+        + (3.0 * e1 / 2.0 - 27.0 * e1.powi(3) / 32.0) * (2.0 * mu).sin()
+        + (21.0 * e1 * e1 / 16.0 - 55.0 * e1.powi(4) / 32.0) * (4.0 * mu).sin()
+        + (151.0 * e1.powi(3) / 96.0) * (6.0 * mu).sin()
+        + (1097.0 * e1.powi(4) / 512.0) * (8.0 * mu).sin();
+
+    let (sin_phi1, cos_phi1) = phi1.sin_cos();
+    let n1 = a / (1.0 - e2 * sin_phi1 * sin_phi1).sqrt();
+    let t1 = phi1.tan().powi(2);
+    let c1 = ep2 * cos_phi1 * cos_phi1;
+    let r1 = a * (1.0 - e2) / (1.0 - e2 * sin_phi1 * sin_phi1).powf(1.5);
+    let d = x / (n1 * K0);
+
+    let lat = phi1
+        - (n1 * phi1.tan() / r1)
+            * (d * d / 2.0
+                - (5.0 + 3.0 * t1 + 10.0 * c1 - 4.0 * c1 * c1 - 9.0 * ep2) * d.powi(4) / 24.0
+                + (61.0 + 90.0 * t1 + 298.0 * c1 + 45.0 * t1 * t1 - 252.0 * ep2 - 3.0 * c1 * c1)
+                    * d.powi(6)
+                    / 720.0);
+    let lon = (d - (1.0 + 2.0 * t1 + c1) * d.powi(3) / 6.0
+        + (5.0 - 2.0 * c1 + 28.0 * t1 - 3.0 * c1 * c1 + 8.0 * ep2 + 24.0 * t1 * t1) * d.powi(5)
+            / 120.0)
+        / cos_phi1;
+
+    (lat.to_degrees(), central_meridian(zone) + lon.to_degrees())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::latlon;
+
+    #[test]
+    fn utm_roundtrip() {
+        for point in [
+            latlon!(37.566, 126.9784),
+            latlon!(-33.8688, 151.2093),
+            latlon!(60.0, 5.0),
+            latlon!(78.2, 15.6),
+            latlon!(-0.5, -78.5),
+        ] {
+            let utm = Utm::from_geo(&point).expect("inside UTM range");
+            let restored = utm.to_geo().expect("valid UTM");
+            assert!((restored.lat() - point.lat()).abs() < 1e-6, "{utm}");
+            assert!((restored.lon() - point.lon()).abs() < 1e-6, "{utm}");
+        }
+
+        assert_eq!(
+            Utm::from_geo(&latlon!(60.0, 5.0)).map(|utm| utm.zone),
+            Some(32)
+        );
+        assert!(Utm::from_geo(&latlon!(85.0, 0.0)).is_none());
+    }
+
+    #[test]
+    fn mgrs_roundtrip() {
+        for point in [
+            latlon!(37.566, 126.9784),
+            latlon!(-33.8688, 151.2093),
+            latlon!(51.4779, -0.0015),
+            latlon!(-54.8, -68.3),
+        ] {
+            let mgrs = Mgrs::from_geo(&point, 5).expect("inside UTM range");
+            let parsed: Mgrs = mgrs.to_string().parse().expect("valid MGRS");
+            assert_eq!(parsed, mgrs);
+
+            let restored = parsed.to_geo().expect("valid MGRS");
+            assert!((restored.lat() - point.lat()).abs() < 1e-4, "{mgrs}");
+            assert!((restored.lon() - point.lon()).abs() < 1e-4, "{mgrs}");
+        }
+    }
+}
@@ -0,0 +1,353 @@
+//! Batch implementation of the Web Mercator projection.
+//!
+//! Points are processed in groups of [`LANES`] with branch-free code, so that the compiler can vectorize the loops
+//! into SIMD instructions. Transcendental functions of the standard library are calls into the platform math library
+//! and are never vectorized, so `sin`, `cos`, `ln`, `exp` and `atan` are replaced here with polynomial
+//! approximations, accurate to a few units in the last place in the ranges used by the projection.
+
+use std::f64::consts::{FRAC_PI_2, FRAC_PI_4, LN_2, LOG2_E, SQRT_2};
+
+/// Number of points processed together.
+const LANES: usize = 8;
+
+type Chunk = [[f64; 2]; LANES];
+
+/// `1.5 * 2^52`. Adding it to a float with absolute value below `2^51` rounds it to an integer.
+const SHIFTER: f64 = 6_755_399_441_055_744.0;
+/// Exponent bits of `2^52`.
+const SHIFTER_BITS: u64 = 0x4330_0000_0000_0000;
+
+/// `tan(3π/8)`.
+const TAN_3_PI_8: f64 = 2.414_213_562_373_095;
+
+const LN_2_HI: f64 = 6.931_471_803_691_238e-1;
+const LN_2_LO: f64 = 1.908_214_929_270_587_7e-10;
+
+/// Taylor coefficients of `sin(x) / x` in powers of `x^2`.
+const SIN: [f64; 10] = [
+    1.0,
+    -1.0 / 6.0,
+    1.0 / 120.0,
+    -1.0 / 5_040.0,
+    1.0 / 362_880.0,
+    -1.0 / 39_916_800.0,
+    1.0 / 6_227_020_800.0,
+    -1.0 / 1_307_674_368_000.0,
+    1.0 / 355_687_428_096_000.0,
+    -1.0 / 121_645_100_408_832_000.0,
+];
+
+/// Taylor coefficients of `cos(x)` in powers of `x^2`.
+const COS: [f64; 11] = [
+    1.0,
+    -1.0 / 2.0,
+    1.0 / 24.0,
+    -1.0 / 720.0,
+    1.0 / 40_320.0,
+    -1.0 / 3_628_800.0,
+    1.0 / 479_001_600.0,
+    -1.0 / 87_178_291_200.0,
+    1.0 / 20_922_789_888_000.0,
+    -1.0 / 6_402_373_705_728_000.0,
+    1.0 / 2_432_902_008_176_640_000.0,
+];
+
+/// Coefficients of `atanh(t) / t` in powers of `t^2`.
+const ATANH: [f64; 11] = [
+    1.0,
+    1.0 / 3.0,
+    1.0 / 5.0,
+    1.0 / 7.0,
+    1.0 / 9.0,
+    1.0 / 11.0,
+    1.0 / 13.0,
+    1.0 / 15.0,
+    1.0 / 17.0,
+    1.0 / 19.0,
+    1.0 / 21.0,
+];
+
+/// Numerator and denominator of the rational approximation of `(atan(v) - v) / v^3` in powers of `v^2` for
+/// `|v| <= 0.66` (from the Cephes library).
+const ATAN_P: [f64; 5] = [
+    -6.485_021_904_942_025e1,
+    -1.228_866_684_490_136_2e2,
+    -7.500_855_792_314_705e1,
+    -1.615_753_718_733_365e1,
+    -8.750_608_600_031_904e-1,
+];
+const ATAN_Q: [f64; 6] = [
+    1.945_506_571_482_614e2,
+    4.853_903_996_359_137e2,
+    4.328_810_604_912_903e2,
+    1.650_270_098_316_988_5e2,
+    2.485_846_490_142_306_3e1,
+    1.0,
+];
+
+/// Taylor coefficients of `exp(r)`.
+const EXP: [f64; 14] = [
+    1.0,
+    1.0,
+    1.0 / 2.0,
+    1.0 / 6.0,
+    1.0 / 24.0,
+    1.0 / 120.0,
+    1.0 / 720.0,
+    1.0 / 5_040.0,
+    1.0 / 40_320.0,
+    1.0 / 362_880.0,
+    1.0 / 3_628_800.0,
+    1.0 / 39_916_800.0,
+    1.0 / 479_001_600.0,
+    1.0 / 6_227_020_800.0,
+];
+
+/// Projects `[lon, lat]` degrees into `[x, y]`. Invalid points are set to `NaN`.
+pub(super) fn project(semimajor: f64, input: &[[f64; 2]], output: &mut [[f64; 2]]) {
+    #[cfg(target_arch = "x86_64")]
+    if std::arch::is_x86_feature_detected!("avx2") {
+        // SAFETY: the function only requires AVX2, which is supported by the CPU.
+        unsafe { project_avx2(semimajor, input, output) };
+        return;
+    }
+
+    project_generic(semimajor, input, output);
+}
+
+/// Unprojects `[x, y]` into `[lon, lat]` degrees. Invalid points are set to `NaN`.
+pub(super) fn unproject(semimajor: f64, input: &[[f64; 2]], output: &mut [[f64; 2]]) {
+    #[cfg(target_arch = "x86_64")]
+    if std::arch::is_x86_feature_detected!("avx2") {
+        // SAFETY: the function only requires AVX2, which is supported by the CPU.
+        unsafe { unproject_avx2(semimajor, input, output) };
+        return;
+    }
+
+    unproject_generic(semimajor, input, output);
+}
+
+// Builds for x86_64 only use SSE2 by default, which processes two numbers at once. Versions compiled with AVX2 are
+// selected at runtime when the CPU supports it.
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+unsafe fn project_avx2(semimajor: f64, input: &[[f64; 2]], output: &mut [[f64; 2]]) {
+    project_generic(semimajor, input, output)
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+unsafe fn unproject_avx2(semimajor: f64, input: &[[f64; 2]], output: &mut [[f64; 2]]) {
+    unproject_generic(semimajor, input, output)
+}
+
+#[inline(always)]
+fn project_generic(semimajor: f64, input: &[[f64; 2]], output: &mut [[f64; 2]]) {
+    for_each_chunk(input, output, |input, output| {
+        project_chunk(semimajor, input, output)
+    });
+}
+
+#[inline(always)]
+fn unproject_generic(semimajor: f64, input: &[[f64; 2]], output: &mut [[f64; 2]]) {
+    for_each_chunk(input, output, |input, output| {
+        unproject_chunk(semimajor, input, output)
+    });
+}
+
+#[inline(always)]
+fn for_each_chunk(
+    input: &[[f64; 2]],
+    output: &mut [[f64; 2]],
+    process: impl Fn(&Chunk, &mut Chunk),
+) {
+    let mut input_chunks = input.chunks_exact(LANES);
+    let mut output_chunks = output.chunks_exact_mut(LANES);
+    for (input, output) in (&mut input_chunks).zip(&mut output_chunks) {
+        process(
+            input.try_into().expect("chunk size is LANES"),
+            output.try_into().expect("chunk size is LANES"),
+        );
+    }
+
+    let rest = input_chunks.remainder();
+    if !rest.is_empty() {
+        let mut input_buffer = [[0.0; 2]; LANES];
+        let mut output_buffer = [[0.0; 2]; LANES];
+        input_buffer[..rest.len()].copy_from_slice(rest);
+        process(&input_buffer, &mut output_buffer);
+        output_chunks
+            .into_remainder()
+            .copy_from_slice(&output_buffer[..rest.len()]);
+    }
+}
+
+#[inline(always)]
+fn project_chunk(semimajor: f64, input: &Chunk, output: &mut Chunk) {
+    for i in 0..LANES {
+        let lon = input[i][0].to_radians();
+        let lat = input[i][1].to_radians();
+
+        // ln(tan(π/4 + lat/2)) = ln((1 + sin(lat)) / cos(lat))
+        let (sin, cos) = (sin(lat), cos(lat));
+        let q = (1.0 + sin) / cos.abs().max(f64::MIN_POSITIVE);
+
+        let x = semimajor * lon;
+        let y = semimajor * ln(q);
+
+        let valid = lat.abs() <= FRAC_PI_2 && q > 0.0 && q.is_finite() && x.is_finite();
+        output[i] = if valid { [x, y] } else { [f64::NAN; 2] };
+    }
+}
+
+#[inline(always)]
+fn unproject_chunk(semimajor: f64, input: &Chunk, output: &mut Chunk) {
+    for i in 0..LANES {
+        let lon = input[i][0] / semimajor;
+        let lat = 2.0 * atan_positive(exp(input[i][1] / semimajor)) - FRAC_PI_2;
+
+        let valid = lon.is_finite() && lat.is_finite();
+        output[i] = if valid {
+            [lon.to_degrees(), lat.to_degrees()]
+        } else {
+            [f64::NAN; 2]
+        };
+    }
+}
+
+#[inline(always)]
+fn horner<const N: usize>(x: f64, coefficients: &[f64; N]) -> f64 {
+    let mut result = coefficients[N - 1];
+    for coefficient in coefficients[..N - 1].iter().rev() {
+        result = result * x + coefficient;
+    }
+
+    result
+}
+
+/// Sine for `|x| <= π/2`.
+#[inline(always)]
+fn sin(x: f64) -> f64 {
+    x * horner(x * x, &SIN)
+}
+
+/// Cosine for `|x| <= π/2`.
+#[inline(always)]
+fn cos(x: f64) -> f64 {
+    horner(x * x, &COS)
+}
+
+/// Natural logarithm for positive finite normal `x`.
+#[inline(always)]
+fn ln(x: f64) -> f64 {
+    let bits = x.to_bits();
+    let mantissa = f64::from_bits((bits & 0x000f_ffff_ffff_ffff) | 0x3ff0_0000_0000_0000);
+    // Integer to float conversion is done through the bits, as it has no SIMD instruction on many platforms.
+    let exponent = f64::from_bits((bits >> 52) | SHIFTER_BITS) - (2f64.powi(52) + 1023.0);
+
+    // Move the mantissa into [1/√2, √2) range, so that the series converges fast.
+    let adjust = mantissa > SQRT_2;
+    let m = if adjust { mantissa * 0.5 } else { mantissa };
+    let e = exponent + if adjust { 1.0 } else { 0.0 };
+
+    // ln(m) = 2 * atanh((m - 1) / (m + 1))
+    let t = (m - 1.0) / (m + 1.0);
+    2.0 * t * horner(t * t, &ATANH) + e * LN_2
+}
+
+/// Exponent. Arguments are clamped into the range where the result is a normal number.
+#[inline(always)]
+fn exp(x: f64) -> f64 {
+    let x = x.clamp(-708.0, 708.0);
+
+    // Adding the shifter rounds `x / ln(2)` to an integer `n` and puts it into the low bits of the mantissa, from
+    // where it is moved into the exponent of `2^n`.
+    let shifted = x * LOG2_E + SHIFTER;
+    let n = shifted - SHIFTER;
+    let r = x - n * LN_2_HI - n * LN_2_LO;
+    let scale = f64::from_bits((shifted.to_bits().wrapping_add(1023)) << 52);
+
+    horner(r, &EXP) * scale
+}
+
+/// Arctangent for non-negative `x`.
+#[inline(always)]
+fn atan_positive(x: f64) -> f64 {
+    // Reduce the argument into [-0.66, 0.66] range with atan(x) = π/2 + atan(-1/x) and
+    // atan(x) = π/4 + atan((x - 1) / (x + 1)).
+    let large = x > TAN_3_PI_8;
+    let medium = x > 0.66;
+    let large_arg = -1.0 / x;
+    let medium_arg = (x - 1.0) / (x + 1.0);
+    let (base, v) = if large {
+        (FRAC_PI_2, large_arg)
+    } else if medium {
+        (FRAC_PI_4, medium_arg)
+    } else {
+        (0.0, x)
+    };
+
+    let z = v * v;
+    base + v + v * z * horner(z, &ATAN_P) / horner(z, &ATAN_Q)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const R: f64 = 6_378_137.0;
+
+    fn scalar_project(lon: f64, lat: f64) -> [f64; 2] {
+        [
+            R * lon.to_radians(),
+            R * (std::f64::consts::FRAC_PI_4 + lat.to_radians() / 2.0)
+                .tan()
+                .ln(),
+        ]
+    }
+
+    #[test]
+    fn matches_scalar_projection() {
+        let input: Vec<[f64; 2]> = (0..=340)
+            .flat_map(|i| (0..=36).map(move |j| [-180.0 + j as f64 * 10.0, -85.0 + i as f64 * 0.5]))
+            .collect();
+        let mut projected = vec![[0.0; 2]; input.len()];
+        project(R, &input, &mut projected);
+
+        for (point, result) in input.iter().zip(&projected) {
+            let expected = scalar_project(point[0], point[1]);
+            assert!((result[0] - expected[0]).abs() < 1e-6, "{point:?}");
+            assert!((result[1] - expected[1]).abs() < 1e-6, "{point:?}");
+        }
+
+        let mut unprojected = vec![[0.0; 2]; input.len()];
+        unproject(R, &projected, &mut unprojected);
+        for (point, result) in input.iter().zip(&unprojected) {
+            assert!((result[0] - point[0]).abs() < 1e-10, "{point:?}");
+            assert!((result[1] - point[1]).abs() < 1e-10, "{point:?}");
+        }
+    }
+
+    #[test]
+    fn invalid_points() {
+        let input = [
+            [10.0, 20.0],
+            [f64::NAN, 20.0],
+            [10.0, 91.0],
+            [f64::INFINITY, 0.0],
+        ];
+        let mut output = [[0.0; 2]; 4];
+        project(R, &input, &mut output);
+
+        assert!(output[0][0].is_finite() && output[0][1].is_finite());
+        for result in &output[1..] {
+            assert!(result[0].is_nan() && result[1].is_nan(), "{result:?}");
+        }
+
+        let mut output = [[0.0; 2]; 2];
+        unproject(R, &[[f64::NAN, 0.0], [0.0, 1e300]], &mut output);
+        assert!(output[0][0].is_nan());
+        assert!((output[1][1] - 90.0).abs() < 1e-10);
+    }
+}
@@ -1,6 +1,7 @@
 //! Implementations for some of the common projections.
 mod dimensions;
 mod identity;
+mod mercator_batch;
 mod web_mercator;
 
 pub use dimensions::AddDimensionProjection;
@@ -1,3 +1,4 @@
+use super::mercator_batch;
 use crate::cartesian::NewCartesianPoint2d;
 use crate::geo::datum::Datum;
 use crate::geo::traits::point::NewGeoPoint;
@@ -22,6 +23,52 @@ impl<In, Out> WebMercator<In, Out> {
             phantom_out: Default::default(),
         }
     }
+
+    /// Projects geographic coordinates given as `[longitude, latitude]` in degrees into `[x, y]` coordinates of the
+    /// projection, writing the results into `output`.
+    ///
+    /// The points are processed in batches with SIMD instructions, which is considerably faster than projecting them
+    /// one by one with [`Projection::project`] when converting many points. Points that cannot be projected (with
+    /// non-finite coordinates or latitude outside of `[-90, 90]` range) are written as `[NaN, NaN]`.
+    ///
+    /// ```
+    /// use galileo_types::cartesian::Point2d;
+    /// use galileo_types::geo::impls::projection::WebMercator;
+    /// use galileo_types::geo::impls::GeoPoint2d;
+    ///
+    /// let projection = WebMercator::<GeoPoint2d, Point2d>::default();
+    /// let lonlat = vec![[37.6, 55.7]; 10_000];
+    /// let mut projected = vec![[0.0; 2]; lonlat.len()];
+    /// projection.project_coords(&lonlat, &mut projected);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `input` and `output` have different lengths.
+    pub fn project_coords(&self, input: &[[f64; 2]], output: &mut [[f64; 2]]) {
+        assert_eq!(
+            input.len(),
+            output.len(),
+            "input and output must have the same length"
+        );
+        mercator_batch::project(self.datum.semimajor(), input, output);
+    }
+
+    /// Converts `[x, y]` coordinates of the projection into `[longitude, latitude]` in degrees, writing the results
+    /// into `output`. This is the reverse of [`WebMercator::project_coords`]. Points with non-finite coordinates are
+    /// written as `[NaN, NaN]`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `input` and `output` have different lengths.
+    pub fn unproject_coords(&self, input: &[[f64; 2]], output: &mut [[f64; 2]]) {
+        assert_eq!(
+            input.len(),
+            output.len(),
+            "input and output must have the same length"
+        );
+        mercator_batch::unproject(self.datum.semimajor(), input, output);
+    }
 }
 
 impl<In, Out> Default for WebMercator<In, Out> {
@@ -63,4 +110,95 @@ impl<In: NewGeoPoint<f64>, Out: NewCartesianPoint2d<f64>> Projection for WebMerc
 
         Some(Self::InPoint::latlon(lat.to_degrees(), lon.to_degrees()))
     }
+
+    fn project_slice(&self, input: &[Self::InPoint]) -> Option<Vec<Self::OutPoint>> {
+        convert_in_batches(
+            input,
+            |p| [p.lon(), p.lat()],
+            |coords, projected| self.project_coords(coords, projected),
+            |[x, y]| (x.is_finite() && y.is_finite()).then(|| Self::OutPoint::new(x, y)),
+        )
+    }
+
+    fn unproject_slice(&self, input: &[Self::OutPoint]) -> Option<Vec<Self::InPoint>> {
+        convert_in_batches(
+            input,
+            |p| [p.x(), p.y()],
+            |coords, unprojected| self.unproject_coords(coords, unprojected),
+            |[lon, lat]| {
+                (lon.is_finite() && lat.is_finite()).then(|| Self::InPoint::latlon(lat, lon))
+            },
+        )
+    }
+}
+
+/// Number of points converted at once by [`convert_in_batches`]. The coordinates of a batch are kept in stack
+/// buffers, so that the only allocation of a slice conversion is the output vector.
+const BATCH_SIZE: usize = 256;
+
+fn convert_in_batches<In, Out>(
+    input: &[In],
+    to_coords: impl Fn(&In) -> [f64; 2],
+    kernel: impl Fn(&[[f64; 2]], &mut [[f64; 2]]),
+    from_coords: impl Fn([f64; 2]) -> Option<Out>,
+) -> Option<Vec<Out>> {
+    let mut coords = [[0.0; 2]; BATCH_SIZE];
+    let mut converted = [[0.0; 2]; BATCH_SIZE];
+    let mut output = Vec::with_capacity(input.len());
+
+    for batch in input.chunks(BATCH_SIZE) {
+        let coords = &mut coords[..batch.len()];
+        let converted = &mut converted[..batch.len()];
+        for (coord, point) in coords.iter_mut().zip(batch) {
+            *coord = to_coords(point);
+        }
+
+        kernel(coords, converted);
+
+        for coord in converted.iter() {
+            output.push(from_coords(*coord)?);
+        }
+    }
+
+    Some(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cartesian::Point2d;
+    use crate::geo::impls::GeoPoint2d;
+    use crate::geo::GeoPoint;
+
+    #[test]
+    fn project_slice_matches_project() {
+        let projection = WebMercator::<GeoPoint2d, Point2d>::default();
+        // More points than in one batch, with the last batch incomplete.
+        let points: Vec<GeoPoint2d> = (0..BATCH_SIZE * 2 + 20)
+            .map(|i| i % 20)
+            .map(|i| GeoPoint2d::latlon(-80.0 + i as f64 * 8.0, -170.0 + i as f64 * 17.0))
+            .collect();
+
+        let projected = projection.project_slice(&points).expect("valid points");
+        assert_eq!(projected.len(), points.len());
+        for (point, result) in points.iter().zip(&projected) {
+            let expected = projection.project(point).expect("valid point");
+            assert!((expected.x - result.x).abs() < 1e-6);
+            assert!((expected.y - result.y).abs() < 1e-6);
+        }
+
+        let unprojected = projection
+            .unproject_slice(&projected)
+            .expect("valid points");
+        for (point, result) in points.iter().zip(&unprojected) {
+            assert!((point.lat() - result.lat()).abs() < 1e-9);
+            assert!((point.lon() - result.lon()).abs() < 1e-9);
+        }
+
+        let invalid = [
+            GeoPoint2d::latlon(10.0, 10.0),
+            GeoPoint2d::latlon(95.0, 10.0),
+        ];
+        assert!(projection.project_slice(&invalid).is_none());
+    }
 }
@@ -3,6 +3,7 @@
 
 mod crs;
 mod datum;
+pub mod format;
 pub mod impls;
 mod traits;
 
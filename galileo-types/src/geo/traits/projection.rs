@@ -10,6 +10,19 @@ pub trait Projection {
     /// Convert point backwards.
     fn unproject(&self, input: &Self::OutPoint) -> Option<Self::InPoint>;
 
+    /// Convert all the points of the slice. Returns `None` if any of the points cannot be converted.
+    ///
+    /// The default implementation calls [`Projection::project`] for every point. Projections that can convert many
+    /// points at once faster (like [`WebMercator`](crate::geo::impls::projection::WebMercator)) override it.
+    fn project_slice(&self, input: &[Self::InPoint]) -> Option<Vec<Self::OutPoint>> {
+        input.iter().map(|p| self.project(p)).collect()
+    }
+
+    /// Convert all the points of the slice backwards. Returns `None` if any of the points cannot be converted.
+    fn unproject_slice(&self, input: &[Self::OutPoint]) -> Option<Vec<Self::InPoint>> {
+        input.iter().map(|p| self.unproject(p)).collect()
+    }
+
     /// Return inverse projection, e.g. a projection for which `project` does `unproject` and `unproject` does `project`.
     fn inverse(self: Box<Self>) -> InvertedProjection<Self::InPoint, Self::OutPoint>
     where
@@ -42,6 +55,14 @@ impl<IN, OUT> Projection for InvertedProjection<IN, OUT> {
     fn unproject(&self, input: &Self::OutPoint) -> Option<Self::InPoint> {
         self.inner.project(input)
     }
+
+    fn project_slice(&self, input: &[Self::InPoint]) -> Option<Vec<Self::OutPoint>> {
+        self.inner.unproject_slice(input)
+    }
+
+    fn unproject_slice(&self, input: &[Self::OutPoint]) -> Option<Vec<Self::InPoint>> {
+        self.inner.project_slice(input)
+    }
 }
 
 /// Chain two projections together.
@@ -75,4 +96,13 @@ impl<IN, MID, OUT> Projection for ChainProjection<IN, MID, OUT> {
     fn unproject(&self, input: &Self::OutPoint) -> Option<Self::InPoint> {
         self.first.unproject(&self.second.unproject(input)?)
     }
+
+    fn project_slice(&self, input: &[Self::InPoint]) -> Option<Vec<Self::OutPoint>> {
+        self.second.project_slice(&self.first.project_slice(input)?)
+    }
+
+    fn unproject_slice(&self, input: &[Self::OutPoint]) -> Option<Vec<Self::InPoint>> {
+        self.first
+            .unproject_slice(&self.second.unproject_slice(input)?)
+    }
 }
@@ -16,6 +16,14 @@ impl<T: CoordNum> Contour for LineString<T> {
             self.0.iter()
         }
     }
+
+    fn points_slice(&self) -> Option<&[Self::Point]> {
+        if self.is_closed() {
+            Some(&self.0[..(self.0.len().max(1) - 1)])
+        } else {
+            Some(&self.0)
+        }
+    }
 }
 
 impl<T: CoordNum> GeometryType for LineString<T> {
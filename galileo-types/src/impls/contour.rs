@@ -48,11 +48,7 @@ impl<Point> Contour<Point> {
     where
         Proj: Projection<InPoint = Point, OutPoint = P> + ?Sized,
     {
-        let points = self
-            .points
-            .iter()
-            .map(|p| projection.project(p))
-            .collect::<Option<Vec<P>>>()?;
+        let points = projection.project_slice(&self.points)?;
         Some(Contour {
             points,
             is_closed: self.is_closed,
@@ -78,11 +74,7 @@ impl<Point> ClosedContour<Point> {
     where
         Proj: Projection<InPoint = Point, OutPoint = P> + ?Sized,
     {
-        let points = self
-            .points
-            .iter()
-            .map(|p| projection.project(p))
-            .collect::<Option<Vec<P>>>()?;
+        let points = projection.project_slice(&self.points)?;
         Some(ClosedContour { points })
     }
 }
@@ -102,6 +94,10 @@ impl<P> crate::contour::ClosedContour for ClosedContour<P> {
     fn iter_points(&self) -> impl Iterator<Item = &'_ P> {
         self.points.iter()
     }
+
+    fn points_slice(&self) -> Option<&[P]> {
+        Some(&self.points)
+    }
 }
 
 impl<P> crate::contour::Contour for Contour<P> {
@@ -114,6 +110,10 @@ impl<P> crate::contour::Contour for Contour<P> {
     fn iter_points(&self) -> impl Iterator<Item = &P> {
         self.points.iter()
     }
+
+    fn points_slice(&self) -> Option<&[P]> {
+        Some(&self.points)
+    }
 }
 
 impl<P: GeometryType> GeometryType for Contour<P> {
@@ -94,8 +94,11 @@ pub mod impls;
 mod multi_contour;
 mod multi_point;
 mod multi_polygon;
+pub mod normalize;
 mod polygon;
 mod segment;
+pub mod validate;
+pub mod wkb;
 
 #[cfg(feature = "geo-types")]
 mod geo_types;
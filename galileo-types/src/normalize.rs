@@ -0,0 +1,450 @@
+//! Fixing of common problems of polygon geometries before they are tessellated.
+//!
+//! Polygons from real-world sources (GeoJSON files especially) are often sloppy: rings are not closed, have wrong
+//! winding, repeat vertices or touch themselves. Such polygons are rendered with missing or inverted parts, or cannot
+//! be tessellated at all. The functions of this module fix these problems and return a [`NormalizationReport`] with
+//! the number of fixes applied, so that an application can warn about bad input data.
+//!
+//! The following fixes are applied:
+//! * unclosed rings are closed (only for the formats where rings must be closed explicitly, like GeoJSON);
+//! * consecutive duplicate vertices are removed;
+//! * self-touching rings (rings that pass the same vertex more than once) are split into separate rings. Parts of
+//!   the outer ring that lie inside the rest of it become holes, the others become separate polygons;
+//! * rings with less than three distinct vertices or zero area, and holes that lie outside the outer ring, are
+//!   removed;
+//! * outer rings are oriented according to [`NormalizeOptions::outer_winding`], and holes in the opposite direction.
+
+use crate::cartesian::CartesianPolygon as _;
+use crate::cartesian::{
+    CartesianClosedContour, CartesianPoint2d, Point2d, PolygonLabelPoint, Winding,
+};
+use crate::impls::{ClosedContour, MultiPolygon, Polygon};
+use crate::polygon::Polygon as _;
+
+/// Options of geometry normalization.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NormalizeOptions {
+    /// Winding of the outer rings of polygons. Holes get the opposite winding.
+    ///
+    /// Defaults to [`Winding::CounterClockwise`], as required by
+    /// [RFC 7946](https://datatracker.ietf.org/doc/html/rfc7946#section-3.1.6).
+    pub outer_winding: Winding,
+}
+
+impl Default for NormalizeOptions {
+    fn default() -> Self {
+        Self {
+            outer_winding: Winding::CounterClockwise,
+        }
+    }
+}
+
+impl NormalizeOptions {
+    /// Returns options with the given winding of outer rings.
+    pub fn with_outer_winding(&self, outer_winding: Winding) -> Self {
+        Self { outer_winding }
+    }
+}
+
+/// Number of fixes applied to a geometry by normalization.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct NormalizationReport {
+    /// Number of rings that were not closed.
+    pub closed_rings: usize,
+    /// Number of removed vertices that repeated the previous one.
+    pub removed_duplicate_vertices: usize,
+    /// Number of rings with reversed winding.
+    pub reversed_rings: usize,
+    /// Number of times a self-touching ring was split in two.
+    pub split_rings: usize,
+    /// Number of removed rings that had no area or were holes outside of the outer ring.
+    pub removed_degenerate_rings: usize,
+}
+
+impl NormalizationReport {
+    /// Returns true if the geometry did not need any fixes.
+    pub fn is_clean(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Adds the numbers of the `other` report to this one.
+    pub fn merge(&mut self, other: &NormalizationReport) {
+        self.closed_rings += other.closed_rings;
+        self.removed_duplicate_vertices += other.removed_duplicate_vertices;
+        self.reversed_rings += other.reversed_rings;
+        self.split_rings += other.split_rings;
+        self.removed_degenerate_rings += other.removed_degenerate_rings;
+    }
+}
+
+/// Normalizes the polygon. See [module documentation](self) for the list of fixes.
+///
+/// Since splitting of self-touching rings can produce several polygons, the result is a multipolygon. It is empty if
+/// the outer ring of the polygon is degenerate.
+pub fn normalize_polygon<P>(
+    polygon: &Polygon<P>,
+    options: &NormalizeOptions,
+) -> (MultiPolygon<P>, NormalizationReport)
+where
+    P: CartesianPoint2d<Num = f64> + Clone + PartialEq,
+{
+    let mut report = NormalizationReport::default();
+    let rings = polygon
+        .iter_contours()
+        .map(|contour| contour.points.clone())
+        .collect();
+    let polygons = normalize_rings(
+        rings,
+        &|p: &P| Point2d::new(p.x(), p.y()),
+        options,
+        &mut report,
+    );
+
+    (polygons.into(), report)
+}
+
+/// Normalizes the polygon given as a list of rings, the first of which is the outer one.
+fn normalize_rings<P: Clone + PartialEq>(
+    rings: Vec<Vec<P>>,
+    xy: &impl Fn(&P) -> Point2d,
+    options: &NormalizeOptions,
+    report: &mut NormalizationReport,
+) -> Vec<Polygon<P>> {
+    let mut outers = vec![];
+    let mut holes = vec![];
+
+    for (index, ring) in rings.into_iter().enumerate() {
+        for ring in split_ring(remove_duplicates(ring, report), report) {
+            match Ring::new(ring, xy) {
+                Some(ring) if index == 0 => outers.push(ring),
+                Some(ring) => holes.push(ring),
+                None => report.removed_degenerate_rings += 1,
+            }
+        }
+    }
+
+    outers.sort_by(|a, b| b.area.total_cmp(&a.area));
+
+    let mut polygons: Vec<(Ring<P>, Vec<Ring<P>>)> = vec![];
+    for outer in outers {
+        match polygons
+            .iter_mut()
+            .find(|(parent, _)| parent.contains(&outer))
+        {
+            Some((_, parent_holes)) => parent_holes.push(outer),
+            None => polygons.push((outer, vec![])),
+        }
+    }
+
+    for hole in holes {
+        // Polygons are sorted by area, so the last containing polygon is the smallest one.
+        match polygons
+            .iter_mut()
+            .rev()
+            .find(|(parent, _)| parent.contains(&hole))
+        {
+            Some((_, parent_holes)) => parent_holes.push(hole),
+            None => report.removed_degenerate_rings += 1,
+        }
+    }
+
+    let hole_winding = match options.outer_winding {
+        Winding::Clockwise => Winding::CounterClockwise,
+        Winding::CounterClockwise => Winding::Clockwise,
+    };
+
+    polygons
+        .into_iter()
+        .map(|(outer, holes)| {
+            Polygon::new(
+                outer.into_contour(options.outer_winding, report),
+                holes
+                    .into_iter()
+                    .map(|hole| hole.into_contour(hole_winding, report))
+                    .collect(),
+            )
+        })
+        .collect()
+}
+
+fn remove_duplicates<P: PartialEq>(mut ring: Vec<P>, report: &mut NormalizationReport) -> Vec<P> {
+    let len = ring.len();
+    ring.dedup();
+    while ring.len() > 1 && ring.first() == ring.last() {
+        ring.pop();
+    }
+
+    report.removed_duplicate_vertices += len - ring.len();
+    ring
+}
+
+/// Splits the ring into simple loops at the vertices the ring passes more than once.
+fn split_ring<P: PartialEq>(ring: Vec<P>, report: &mut NormalizationReport) -> Vec<Vec<P>> {
+    let mut loops = vec![];
+    let mut current: Vec<P> = Vec::with_capacity(ring.len());
+
+    for point in ring {
+        match current.iter().position(|p| *p == point) {
+            Some(index) => {
+                // The loop starts and ends at the repeated vertex, which stays in the current ring.
+                loops.push(current.split_off(index));
+                current.push(point);
+                report.split_rings += 1;
+            }
+            None => current.push(point),
+        }
+    }
+
+    loops.push(current);
+    loops
+}
+
+struct Ring<P> {
+    points: Vec<P>,
+    shape: Polygon<Point2d>,
+    area: f64,
+    winding: Winding,
+    /// A point strictly inside the ring.
+    inner_point: Point2d,
+}
+
+impl<P> Ring<P> {
+    /// Returns `None` if the ring is degenerate.
+    fn new(points: Vec<P>, xy: &impl Fn(&P) -> Point2d) -> Option<Self> {
+        if points.len() < 3 {
+            return None;
+        }
+
+        let contour = ClosedContour::new(points.iter().map(xy).collect());
+        let area_signed = contour.area_signed();
+        if !area_signed.is_normal() {
+            return None;
+        }
+
+        let winding = contour.winding();
+        let shape = Polygon::from(contour);
+        let inner_point = shape.label_point(area_signed.abs().sqrt() * 1e-3)?;
+
+        Some(Self {
+            points,
+            shape,
+            area: area_signed.abs(),
+            winding,
+            inner_point: Point2d::new(inner_point.x, inner_point.y),
+        })
+    }
+
+    fn contains(&self, other: &Ring<P>) -> bool {
+        self.shape.contains_point(&other.inner_point)
+    }
+
+    fn into_contour(
+        mut self,
+        winding: Winding,
+        report: &mut NormalizationReport,
+    ) -> ClosedContour<P> {
+        if self.winding != winding {
+            self.points.reverse();
+            report.reversed_rings += 1;
+        }
+
+        ClosedContour::new(self.points)
+    }
+}
+
+/// Normalizes the polygons of the GeoJSON geometry in place. See [module documentation](self) for the list of fixes.
+///
+/// Polygons that are split into several parts are replaced with multipolygons, and geometry collections are
+/// normalized recursively. Other geometry types are left untouched.
+///
+/// ```
+/// use galileo_types::normalize::{normalize_geojson, NormalizeOptions};
+/// use geojson::{Geometry, Value};
+///
+/// // Unclosed clockwise ring with a repeated vertex.
+/// let mut geometry = Geometry::new(Value::Polygon(vec![vec![
+///     vec![0.0, 0.0],
+///     vec![0.0, 1.0],
+///     vec![0.0, 1.0],
+///     vec![1.0, 1.0],
+///     vec![1.0, 0.0],
+/// ]]));
+///
+/// let report = normalize_geojson(&mut geometry, &NormalizeOptions::default());
+/// assert_eq!(report.closed_rings, 1);
+/// assert_eq!(report.removed_duplicate_vertices, 1);
+/// assert_eq!(report.reversed_rings, 1);
+/// ```
+#[cfg(feature = "geojson")]
+pub fn normalize_geojson(
+    geometry: &mut geojson::Geometry,
+    options: &NormalizeOptions,
+) -> NormalizationReport {
+    let mut report = NormalizationReport::default();
+    normalize_geojson_value(&mut geometry.value, options, &mut report);
+    report
+}
+
+#[cfg(feature = "geojson")]
+fn normalize_geojson_value(
+    value: &mut geojson::Value,
+    options: &NormalizeOptions,
+    report: &mut NormalizationReport,
+) {
+    use geojson::Value;
+
+    match value {
+        Value::Polygon(rings) => {
+            let mut polygons = normalize_geojson_polygon(std::mem::take(rings), options, report);
+            *value = if polygons.len() == 1 {
+                Value::Polygon(polygons.remove(0))
+            } else {
+                Value::MultiPolygon(polygons)
+            };
+        }
+        Value::MultiPolygon(polygons) => {
+            *polygons = std::mem::take(polygons)
+                .into_iter()
+                .flat_map(|rings| normalize_geojson_polygon(rings, options, report))
+                .collect();
+        }
+        Value::GeometryCollection(geometries) => {
+            for geometry in geometries {
+                normalize_geojson_value(&mut geometry.value, options, report);
+            }
+        }
+        Value::Point(_)
+        | Value::MultiPoint(_)
+        | Value::LineString(_)
+        | Value::MultiLineString(_) => {}
+    }
+}
+
+#[cfg(feature = "geojson")]
+fn normalize_geojson_polygon(
+    rings: geojson::PolygonType,
+    options: &NormalizeOptions,
+    report: &mut NormalizationReport,
+) -> Vec<geojson::PolygonType> {
+    report.closed_rings += rings
+        .iter()
+        .filter(|ring| ring.len() > 1 && ring.first() != ring.last())
+        .count();
+
+    let xy = |p: &geojson::Position| {
+        Point2d::new(
+            p.first().copied().unwrap_or_default(),
+            p.get(1).copied().unwrap_or_default(),
+        )
+    };
+
+    // Closing points of GeoJSON rings are not duplicates, so they are removed before normalization and added back
+    // after it.
+    let rings = rings
+        .into_iter()
+        .map(|mut ring| {
+            if ring.len() > 1 && ring.first() == ring.last() {
+                ring.pop();
+            }
+            ring
+        })
+        .collect();
+
+    normalize_rings(rings, &xy, options, report)
+        .into_iter()
+        .map(|polygon| {
+            std::iter::once(polygon.outer_contour)
+                .chain(polygon.inner_contours)
+                .map(|contour| {
+                    let mut ring = contour.points;
+                    ring.push(ring[0].clone());
+                    ring
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cartesian::CartesianClosedContour;
+
+    fn ring(points: &[(f64, f64)]) -> ClosedContour<Point2d> {
+        ClosedContour::new(points.iter().map(|&(x, y)| Point2d::new(x, y)).collect())
+    }
+
+    #[test]
+    fn clean_polygon_is_not_changed() {
+        let polygon = Polygon::new(
+            ring(&[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)]),
+            vec![ring(&[(2.0, 2.0), (2.0, 8.0), (8.0, 8.0), (8.0, 2.0)])],
+        );
+        let (normalized, report) = normalize_polygon(&polygon, &NormalizeOptions::default());
+        assert!(report.is_clean());
+        assert_eq!(normalized.parts(), &[polygon]);
+    }
+
+    #[test]
+    fn fixes_winding_and_duplicates() {
+        let polygon = Polygon::new(
+            ring(&[
+                (0.0, 0.0),
+                (0.0, 10.0),
+                (10.0, 10.0),
+                (10.0, 10.0),
+                (10.0, 0.0),
+                (0.0, 0.0),
+            ]),
+            vec![ring(&[(2.0, 2.0), (8.0, 2.0), (8.0, 8.0), (2.0, 8.0)])],
+        );
+        let (normalized, report) = normalize_polygon(&polygon, &NormalizeOptions::default());
+        assert_eq!(report.removed_duplicate_vertices, 2);
+        assert_eq!(report.reversed_rings, 2);
+
+        let result = &normalized.parts()[0];
+        assert_eq!(result.outer_contour.points.len(), 4);
+        assert_eq!(result.outer_contour.winding(), Winding::CounterClockwise);
+        assert_eq!(result.inner_contours[0].winding(), Winding::Clockwise);
+    }
+
+    #[test]
+    fn self_touching_ring_is_split() {
+        // Square with a triangular hole touching its corner, drawn as a single ring, and a separate triangle
+        // touching the opposite corner.
+        let polygon = Polygon::from(ring(&[
+            (0.0, 0.0),
+            (5.0, 2.0),
+            (2.0, 5.0),
+            (0.0, 0.0),
+            (0.0, 10.0),
+            (10.0, 10.0),
+            (12.0, 11.0),
+            (11.0, 12.0),
+            (10.0, 10.0),
+            (10.0, 0.0),
+        ]));
+        let (normalized, report) = normalize_polygon(&polygon, &NormalizeOptions::default());
+        assert_eq!(report.split_rings, 2);
+
+        let parts = normalized.parts();
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].inner_contours.len(), 1);
+        assert_eq!(parts[1].inner_contours.len(), 0);
+        assert_eq!(parts[1].outer_contour.winding(), Winding::CounterClockwise);
+    }
+
+    #[test]
+    fn degenerate_rings_are_removed() {
+        let polygon = Polygon::new(
+            ring(&[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)]),
+            vec![
+                ring(&[(2.0, 2.0), (3.0, 3.0), (4.0, 4.0)]),
+                ring(&[(20.0, 20.0), (20.0, 21.0), (21.0, 21.0)]),
+            ],
+        );
+        let (normalized, report) = normalize_polygon(&polygon, &NormalizeOptions::default());
+        assert_eq!(report.removed_degenerate_rings, 2);
+        assert!(normalized.parts()[0].inner_contours.is_empty());
+    }
+}
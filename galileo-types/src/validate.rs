@@ -0,0 +1,241 @@
+//! Detection of invalid coordinates in input data.
+//!
+//! Real-world data often contains coordinates that cannot be displayed correctly: `NaN` values from broken
+//! conversions, coordinates outside of the valid range of the coordinate system (e.g. swapped latitude and longitude)
+//! and `0, 0` points (the so-called *Null Island*) produced by geocoders and databases that store missing values as
+//! zeros. Such points are either rendered as garbage (lines stretching across the whole map) or break projection and
+//! tessellation code.
+//!
+//! [`CoordinateValidation`] checks the points and decides what to do with the invalid ones according to its
+//! [`InvalidCoordinateAction`]. Every found problem is counted in a [`ValidationReport`], so that an application can
+//! warn about bad input data.
+
+use crate::cartesian::Rect;
+
+/// What to do with invalid coordinates found by [`CoordinateValidation`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum InvalidCoordinateAction {
+    /// Only count the invalid coordinates in the report and leave them as they are.
+    Report,
+    /// Drop the whole geometry that contains an invalid point.
+    #[default]
+    Drop,
+    /// Move the points outside of the domain to its nearest edge. Points that cannot be clamped (`NaN` values and
+    /// the Null Island) are dropped.
+    Clamp,
+}
+
+/// Problem of a point found by [`CoordinateValidation::check`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoordinateIssue {
+    /// One of the coordinates is `NaN` or infinite.
+    NonFinite,
+    /// The point is outside of the valid domain of the coordinate system.
+    OutOfDomain,
+    /// The point is exactly at `0, 0`.
+    NullIsland,
+}
+
+/// Rules of validation of coordinates.
+///
+/// ```
+/// use galileo_types::cartesian::Rect;
+/// use galileo_types::validate::{CoordinateValidation, InvalidCoordinateAction, ValidationReport};
+///
+/// let validation = CoordinateValidation::new(Some(Rect::new(-180.0, -90.0, 180.0, 90.0)))
+///     .with_action(InvalidCoordinateAction::Clamp);
+/// let mut report = ValidationReport::default();
+///
+/// assert_eq!(validation.validate(10.0, 95.0, &mut report), Some((10.0, 90.0)));
+/// assert_eq!(validation.validate(0.0, 0.0, &mut report), None);
+/// assert_eq!(report.out_of_domain, 1);
+/// assert_eq!(report.null_island, 1);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CoordinateValidation {
+    /// What to do with invalid points. Defaults to [`InvalidCoordinateAction::Drop`].
+    pub action: InvalidCoordinateAction,
+    /// Valid range of `x` and `y` (or longitude and latitude) values. If `None`, any finite values are valid.
+    pub domain: Option<Rect>,
+    /// If set to true, points at exactly `0, 0` are considered invalid. Defaults to `true`.
+    pub reject_null_island: bool,
+}
+
+impl CoordinateValidation {
+    /// Creates validation with the given domain that drops invalid geometries.
+    pub fn new(domain: Option<Rect>) -> Self {
+        Self {
+            action: InvalidCoordinateAction::Drop,
+            domain,
+            reject_null_island: true,
+        }
+    }
+
+    /// Validation of geographic coordinates, with longitude as `x` and latitude as `y`.
+    pub fn geographic() -> Self {
+        Self::new(Some(Rect::new(-180.0, -90.0, 180.0, 90.0)))
+    }
+
+    /// Returns validation with the given action.
+    pub fn with_action(&self, action: InvalidCoordinateAction) -> Self {
+        Self { action, ..*self }
+    }
+
+    /// Returns validation with the given domain.
+    pub fn with_domain(&self, domain: Option<Rect>) -> Self {
+        Self { domain, ..*self }
+    }
+
+    /// Returns validation that does or does not consider `0, 0` points invalid.
+    pub fn with_reject_null_island(&self, reject_null_island: bool) -> Self {
+        Self {
+            reject_null_island,
+            ..*self
+        }
+    }
+
+    /// Returns the problem of the point, or `None` if the point is valid.
+    pub fn check(&self, x: f64, y: f64) -> Option<CoordinateIssue> {
+        if !x.is_finite() || !y.is_finite() {
+            return Some(CoordinateIssue::NonFinite);
+        }
+
+        if self.reject_null_island && x == 0.0 && y == 0.0 {
+            return Some(CoordinateIssue::NullIsland);
+        }
+
+        match self.domain {
+            Some(domain)
+                if x < domain.x_min()
+                    || x > domain.x_max()
+                    || y < domain.y_min()
+                    || y > domain.y_max() =>
+            {
+                Some(CoordinateIssue::OutOfDomain)
+            }
+            _ => None,
+        }
+    }
+
+    /// Checks the point and counts its problem in the `report`. Returns the coordinates that should be used instead
+    /// of the given ones, or `None` if the geometry containing the point must be dropped.
+    ///
+    /// Dropped geometries are not counted in the report, since a geometry can contain many points. Use
+    /// [`ValidationReport::dropped_geometries`] to count them.
+    pub fn validate(&self, x: f64, y: f64, report: &mut ValidationReport) -> Option<(f64, f64)> {
+        let Some(issue) = self.check(x, y) else {
+            return Some((x, y));
+        };
+
+        report.add_issue(issue);
+        match (self.action, issue) {
+            (InvalidCoordinateAction::Report, _) => Some((x, y)),
+            (InvalidCoordinateAction::Clamp, CoordinateIssue::OutOfDomain) => {
+                let domain = self.domain?;
+                report.clamped_points += 1;
+                Some((
+                    x.clamp(domain.x_min(), domain.x_max()),
+                    y.clamp(domain.y_min(), domain.y_max()),
+                ))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Default for CoordinateValidation {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+/// Number of invalid coordinates found by [`CoordinateValidation`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ValidationReport {
+    /// Number of points with `NaN` or infinite coordinates.
+    pub non_finite: usize,
+    /// Number of points outside of the domain.
+    pub out_of_domain: usize,
+    /// Number of points at `0, 0`.
+    pub null_island: usize,
+    /// Number of points moved to the edge of the domain.
+    pub clamped_points: usize,
+    /// Number of geometries dropped because of invalid points.
+    pub dropped_geometries: usize,
+}
+
+impl ValidationReport {
+    /// Returns true if no invalid coordinates were found.
+    pub fn is_clean(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Total number of invalid points.
+    pub fn invalid_points(&self) -> usize {
+        self.non_finite + self.out_of_domain + self.null_island
+    }
+
+    /// Adds the numbers of the `other` report to this one.
+    pub fn merge(&mut self, other: &ValidationReport) {
+        self.non_finite += other.non_finite;
+        self.out_of_domain += other.out_of_domain;
+        self.null_island += other.null_island;
+        self.clamped_points += other.clamped_points;
+        self.dropped_geometries += other.dropped_geometries;
+    }
+
+    fn add_issue(&mut self, issue: CoordinateIssue) {
+        match issue {
+            CoordinateIssue::NonFinite => self.non_finite += 1,
+            CoordinateIssue::OutOfDomain => self.out_of_domain += 1,
+            CoordinateIssue::NullIsland => self.null_island += 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn actions() {
+        let validation = CoordinateValidation::geographic();
+        let mut report = ValidationReport::default();
+
+        assert_eq!(
+            validation.validate(10.0, 20.0, &mut report),
+            Some((10.0, 20.0))
+        );
+        assert_eq!(validation.validate(f64::NAN, 20.0, &mut report), None);
+        assert_eq!(validation.validate(200.0, 20.0, &mut report), None);
+        assert_eq!(
+            validation
+                .with_action(InvalidCoordinateAction::Report)
+                .validate(200.0, 20.0, &mut report),
+            Some((200.0, 20.0))
+        );
+        assert_eq!(
+            validation
+                .with_action(InvalidCoordinateAction::Clamp)
+                .validate(f64::INFINITY, 20.0, &mut report),
+            None
+        );
+        assert_eq!(
+            validation
+                .with_reject_null_island(false)
+                .validate(0.0, 0.0, &mut report),
+            Some((0.0, 0.0))
+        );
+
+        assert_eq!(
+            report,
+            ValidationReport {
+                non_finite: 2,
+                out_of_domain: 2,
+                null_island: 0,
+                clamped_points: 0,
+                dropped_geometries: 0,
+            }
+        );
+    }
+}
@@ -0,0 +1,326 @@
+//! Decoding of geometries from Well-Known Binary (WKB) format.
+//!
+//! Both ISO WKB and extended (PostGIS) WKB are supported. `Z` and `M` coordinates are read but discarded, since the
+//! geometries of this crate are two-dimensional. Geometry collections are not supported.
+
+use crate::error::GalileoTypesError;
+use crate::geometry::Geom;
+use crate::impls::{ClosedContour, Contour, MultiContour, MultiPoint, MultiPolygon, Polygon};
+
+const EWKB_Z_FLAG: u32 = 0x8000_0000;
+const EWKB_M_FLAG: u32 = 0x4000_0000;
+const EWKB_SRID_FLAG: u32 = 0x2000_0000;
+
+/// Decodes a geometry from WKB bytes.
+///
+/// Points are constructed with the `new_point` function from their `x` and `y` coordinates. For geographic
+/// coordinates `x` is longitude and `y` is latitude.
+///
+/// ```
+/// use galileo_types::cartesian::Point2d;
+/// use galileo_types::geometry::Geom;
+/// use galileo_types::wkb::decode_wkb;
+///
+/// let bytes = [
+///     0x01, 0x01, 0x00, 0x00, 0x00, // little endian, point
+///     0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xf0, 0x3f, // 1.0
+///     0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x40, // 2.0
+/// ];
+/// let geometry = decode_wkb(&bytes, Point2d::new).expect("valid WKB");
+/// assert_eq!(geometry, Geom::Point(Point2d::new(1.0, 2.0)));
+/// ```
+pub fn decode_wkb<P>(
+    bytes: &[u8],
+    new_point: impl Fn(f64, f64) -> P,
+) -> Result<Geom<P>, GalileoTypesError> {
+    let mut reader = WkbReader {
+        bytes,
+        position: 0,
+        new_point: &new_point,
+    };
+
+    reader.read_geometry()
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum WkbType {
+    Point,
+    LineString,
+    Polygon,
+    MultiPoint,
+    MultiLineString,
+    MultiPolygon,
+}
+
+struct GeometryHeader {
+    little_endian: bool,
+    geometry_type: WkbType,
+    dimensions: usize,
+}
+
+struct WkbReader<'a, F> {
+    bytes: &'a [u8],
+    position: usize,
+    new_point: &'a F,
+}
+
+impl<P, F: Fn(f64, f64) -> P> WkbReader<'_, F> {
+    fn read_geometry(&mut self) -> Result<Geom<P>, GalileoTypesError> {
+        let header = self.read_header()?;
+        self.read_body(&header)
+    }
+
+    fn read_body(&mut self, header: &GeometryHeader) -> Result<Geom<P>, GalileoTypesError> {
+        Ok(match header.geometry_type {
+            WkbType::Point => Geom::Point(self.read_point(header)?),
+            WkbType::LineString => Geom::Contour(Contour::open(self.read_points(header)?)),
+            WkbType::Polygon => Geom::Polygon(self.read_polygon(header)?),
+            WkbType::MultiPoint => {
+                let points = self.read_parts(header, WkbType::Point, |reader, part| {
+                    reader.read_point(part)
+                })?;
+                Geom::MultiPoint(MultiPoint::from(points))
+            }
+            WkbType::MultiLineString => {
+                let contours = self.read_parts(header, WkbType::LineString, |reader, part| {
+                    Ok(Contour::open(reader.read_points(part)?))
+                })?;
+                Geom::MultiContour(MultiContour::from(contours))
+            }
+            WkbType::MultiPolygon => {
+                let polygons = self.read_parts(header, WkbType::Polygon, |reader, part| {
+                    reader.read_polygon(part)
+                })?;
+                Geom::MultiPolygon(MultiPolygon::from(polygons))
+            }
+        })
+    }
+
+    fn read_parts<T>(
+        &mut self,
+        header: &GeometryHeader,
+        part_type: WkbType,
+        read_part: impl Fn(&mut Self, &GeometryHeader) -> Result<T, GalileoTypesError>,
+    ) -> Result<Vec<T>, GalileoTypesError> {
+        let count = self.read_u32(header.little_endian)?;
+        let mut parts = Vec::with_capacity((count as usize).min(self.remaining()));
+        for _ in 0..count {
+            let part_header = self.read_header()?;
+            if part_header.geometry_type != part_type {
+                return Err(error(format!(
+                    "unexpected part type {:?} in {:?}",
+                    part_header.geometry_type, header.geometry_type
+                )));
+            }
+
+            parts.push(read_part(self, &part_header)?);
+        }
+
+        Ok(parts)
+    }
+
+    fn read_header(&mut self) -> Result<GeometryHeader, GalileoTypesError> {
+        let little_endian = match self.read_byte()? {
+            0 => false,
+            1 => true,
+            v => return Err(error(format!("invalid byte order marker {v}"))),
+        };
+
+        let type_code = self.read_u32(little_endian)?;
+        let mut has_z = type_code & EWKB_Z_FLAG != 0;
+        let mut has_m = type_code & EWKB_M_FLAG != 0;
+        if type_code & EWKB_SRID_FLAG != 0 {
+            self.read_u32(little_endian)?;
+        }
+
+        let iso_code = type_code & 0x0FFF_FFFF;
+        match iso_code / 1000 {
+            0 => {}
+            1 => has_z = true,
+            2 => has_m = true,
+            3 => {
+                has_z = true;
+                has_m = true;
+            }
+            _ => return Err(error(format!("unsupported geometry type {type_code}"))),
+        }
+
+        let geometry_type = match iso_code % 1000 {
+            1 => WkbType::Point,
+            2 => WkbType::LineString,
+            3 => WkbType::Polygon,
+            4 => WkbType::MultiPoint,
+            5 => WkbType::MultiLineString,
+            6 => WkbType::MultiPolygon,
+            _ => return Err(error(format!("unsupported geometry type {type_code}"))),
+        };
+
+        Ok(GeometryHeader {
+            little_endian,
+            geometry_type,
+            dimensions: 2 + has_z as usize + has_m as usize,
+        })
+    }
+
+    fn read_point(&mut self, header: &GeometryHeader) -> Result<P, GalileoTypesError> {
+        let x = self.read_f64(header.little_endian)?;
+        let y = self.read_f64(header.little_endian)?;
+        for _ in 2..header.dimensions {
+            self.read_f64(header.little_endian)?;
+        }
+
+        Ok((self.new_point)(x, y))
+    }
+
+    fn read_points(&mut self, header: &GeometryHeader) -> Result<Vec<P>, GalileoTypesError> {
+        let count = self.read_u32(header.little_endian)? as usize;
+        let mut points = Vec::with_capacity(count.min(self.remaining()));
+        for _ in 0..count {
+            points.push(self.read_point(header)?);
+        }
+
+        Ok(points)
+    }
+
+    fn read_polygon(&mut self, header: &GeometryHeader) -> Result<Polygon<P>, GalileoTypesError> {
+        let count = self.read_u32(header.little_endian)?;
+        let mut rings = Vec::with_capacity((count as usize).min(self.remaining()));
+        for _ in 0..count {
+            let mut points = self.read_points(header)?;
+            // WKB rings repeat the first point at the end, closed contours don't.
+            if points.len() > 1 {
+                points.pop();
+            }
+            rings.push(ClosedContour::new(points));
+        }
+
+        let mut rings = rings.into_iter();
+        let outer = rings.next().ok_or_else(|| error("polygon without rings"))?;
+        Ok(Polygon::new(outer, rings.collect()))
+    }
+
+    fn remaining(&self) -> usize {
+        self.bytes.len().saturating_sub(self.position)
+    }
+
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], GalileoTypesError> {
+        let bytes = self
+            .bytes
+            .get(self.position..self.position + N)
+            .ok_or_else(|| error("unexpected end of WKB data"))?;
+        self.position += N;
+
+        let mut result = [0; N];
+        result.copy_from_slice(bytes);
+        Ok(result)
+    }
+
+    fn read_byte(&mut self) -> Result<u8, GalileoTypesError> {
+        Ok(self.take::<1>()?[0])
+    }
+
+    fn read_u32(&mut self, little_endian: bool) -> Result<u32, GalileoTypesError> {
+        let bytes = self.take()?;
+        Ok(if little_endian {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        })
+    }
+
+    fn read_f64(&mut self, little_endian: bool) -> Result<f64, GalileoTypesError> {
+        let bytes = self.take()?;
+        Ok(if little_endian {
+            f64::from_le_bytes(bytes)
+        } else {
+            f64::from_be_bytes(bytes)
+        })
+    }
+}
+
+fn error(message: impl Into<String>) -> GalileoTypesError {
+    GalileoTypesError::Conversion(message.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cartesian::Point2d;
+    use crate::MultiContour as _;
+
+    fn header(bytes: &mut Vec<u8>, type_code: u32) {
+        bytes.push(1);
+        bytes.extend_from_slice(&type_code.to_le_bytes());
+    }
+
+    fn coords(bytes: &mut Vec<u8>, values: &[f64]) {
+        for v in values {
+            bytes.extend_from_slice(&v.to_le_bytes());
+        }
+    }
+
+    #[test]
+    fn decode_big_endian_point() {
+        let mut bytes = vec![0];
+        bytes.extend_from_slice(&1u32.to_be_bytes());
+        bytes.extend_from_slice(&3.0f64.to_be_bytes());
+        bytes.extend_from_slice(&4.0f64.to_be_bytes());
+
+        let geometry = decode_wkb(&bytes, Point2d::new).expect("valid WKB");
+        assert_eq!(geometry, Geom::Point(Point2d::new(3.0, 4.0)));
+    }
+
+    #[test]
+    fn decode_polygon_z() {
+        let mut bytes = vec![];
+        header(&mut bytes, 1003);
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        bytes.extend_from_slice(&4u32.to_le_bytes());
+        coords(
+            &mut bytes,
+            &[0.0, 0.0, 5.0, 1.0, 0.0, 5.0, 1.0, 1.0, 5.0, 0.0, 0.0, 5.0],
+        );
+
+        let geometry = decode_wkb(&bytes, Point2d::new).expect("valid WKB");
+        let Geom::Polygon(polygon) = geometry else {
+            panic!("invalid geometry type");
+        };
+        assert_eq!(
+            polygon.outer_contour,
+            ClosedContour::new(vec![
+                Point2d::new(0.0, 0.0),
+                Point2d::new(1.0, 0.0),
+                Point2d::new(1.0, 1.0)
+            ])
+        );
+        assert!(polygon.inner_contours.is_empty());
+    }
+
+    #[test]
+    fn decode_multi_line_string_with_srid() {
+        let mut bytes = vec![];
+        header(&mut bytes, 5 | EWKB_SRID_FLAG);
+        bytes.extend_from_slice(&4326u32.to_le_bytes());
+        bytes.extend_from_slice(&2u32.to_le_bytes());
+        for i in 0..2 {
+            header(&mut bytes, 2);
+            bytes.extend_from_slice(&2u32.to_le_bytes());
+            coords(&mut bytes, &[i as f64, 0.0, i as f64, 1.0]);
+        }
+
+        let geometry = decode_wkb(&bytes, Point2d::new).expect("valid WKB");
+        let Geom::MultiContour(contours) = geometry else {
+            panic!("invalid geometry type");
+        };
+        assert_eq!(contours.contours().count(), 2);
+    }
+
+    #[test]
+    fn decode_truncated() {
+        let mut bytes = vec![];
+        header(&mut bytes, 1);
+        coords(&mut bytes, &[1.0]);
+
+        assert!(decode_wkb(&bytes, Point2d::new).is_err());
+    }
+}
@@ -0,0 +1,88 @@
+//! Benchmarks of the vector tile pipeline over synthetic tiles.
+//!
+//! Run with `cargo bench -p galileo --features bench --bench tile_pipeline`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use galileo::bench::{SyntheticTileSource, TilePipelineBench, ViewScript};
+use galileo::tile_scheme::TileIndex;
+use galileo::MapView;
+use galileo_types::cartesian::Size;
+use galileo_types::latlon;
+
+const SEED: u64 = 0x6A1_1E0;
+
+/// (name, features per layer, vertices per feature)
+const DENSITIES: [(&str, u32, u32); 3] =
+    [("sparse", 10, 8), ("medium", 100, 32), ("dense", 500, 64)];
+
+fn decode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decode");
+    group.throughput(Throughput::Elements(1));
+
+    for (name, features, vertices) in DENSITIES {
+        let bench = TilePipelineBench::new(SyntheticTileSource::new(SEED, features, vertices));
+        let bytes = bench.source().tile_bytes(TileIndex::new(10, 20, 6));
+
+        group.bench_with_input(BenchmarkId::from_parameter(name), &bytes, |b, bytes| {
+            b.iter(|| bench.decode(bytes.clone()).expect("failed to decode tile"))
+        });
+    }
+
+    group.finish();
+}
+
+fn tessellate(c: &mut Criterion) {
+    let mut group = c.benchmark_group("tessellate");
+    group.throughput(Throughput::Elements(1));
+
+    for (name, features, vertices) in DENSITIES {
+        let bench = TilePipelineBench::new(SyntheticTileSource::new(SEED, features, vertices));
+        let index = TileIndex::new(10, 20, 6);
+        let tile = bench
+            .decode(bench.source().tile_bytes(index))
+            .expect("failed to decode tile");
+
+        group.bench_with_input(BenchmarkId::from_parameter(name), &tile, |b, tile| {
+            b.iter(|| {
+                bench
+                    .tessellate(tile, index)
+                    .expect("failed to tessellate tile")
+            })
+        });
+    }
+
+    group.finish();
+}
+
+fn frames(c: &mut Criterion) {
+    let mut group = c.benchmark_group("fly_over");
+    group.sample_size(10);
+
+    let start = MapView::new(&latlon!(48.0, 11.0), 300.0).with_size(Size::new(1024.0, 768.0));
+    let script = ViewScript::fly_over(&start, 240);
+    let bench = TilePipelineBench::new(SyntheticTileSource::new(SEED, 100, 32));
+
+    // Every run of the script loads the same tiles, so a single run gives the amount of work done per iteration.
+    let stats = bench.run(&script).expect("failed to run the script");
+
+    // The same run reported as frames, decoded tiles and tessellated bytes per second.
+    let throughputs = [
+        ("frames", Throughput::Elements(script.views().len() as u64)),
+        ("tiles", Throughput::Elements(stats.tiles_decoded as u64)),
+        (
+            "tessellated",
+            Throughput::Bytes(stats.tessellated_bytes as u64),
+        ),
+    ];
+    for (name, throughput) in throughputs {
+        group.throughput(throughput);
+        group.bench_function(BenchmarkId::new("medium", name), |b| {
+            b.iter(|| bench.run(&script).expect("failed to run the script"))
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, decode, tessellate, frames);
+criterion_main!(benches);
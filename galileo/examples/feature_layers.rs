@@ -5,11 +5,11 @@ use galileo::layer::feature_layer::FeatureLayer;
 use galileo::render::point_paint::PointPaint;
 use galileo::render::render_bundle::RenderPrimitive;
 use galileo::{Color, MapBuilder};
-use galileo_types::cartesian::CartesianPoint3d;
+use galileo_types::cartesian::{CartesianPoint3d, NewCartesianPoint3d};
 use galileo_types::geo::Crs;
 use galileo_types::geometry::Geom;
 use galileo_types::impls::{Contour, Polygon};
-use num_traits::AsPrimitive;
+use num_traits::{AsPrimitive, Float};
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, RwLock};
 
@@ -154,8 +154,8 @@ impl Symbol<Country> for CountrySymbol {
         min_resolution: f64,
     ) -> Vec<RenderPrimitive<'a, N, P, Contour<P>, Polygon<P>>>
     where
-        N: AsPrimitive<f32>,
-        P: CartesianPoint3d<Num = N> + Clone,
+        N: AsPrimitive<f32> + Float,
+        P: NewCartesianPoint3d<N> + Clone,
     {
         self.get_polygon_symbol(feature)
             .render(&(), geometry, min_resolution)
@@ -4,14 +4,14 @@ use galileo::layer::feature_layer::symbol::{SimplePolygonSymbol, Symbol};
 use galileo::layer::feature_layer::FeatureLayer;
 use galileo::render::render_bundle::RenderPrimitive;
 use galileo::{MapBuilder, MapView};
-use galileo_types::cartesian::{CartesianPoint3d, Point2d};
+use galileo_types::cartesian::{NewCartesianPoint3d, Point2d};
 use galileo_types::geo::impls::GeoPoint2d;
 use galileo_types::geo::{
     ChainProjection, Crs, Datum, InvertedProjection, NewGeoPoint, Projection, ProjectionType,
 };
 use galileo_types::geometry::Geom;
 use galileo_types::impls::{Contour, Polygon};
-use num_traits::AsPrimitive;
+use num_traits::{AsPrimitive, Float};
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, RwLock};
 
@@ -117,8 +117,8 @@ impl Symbol<Country> for CountrySymbol {
         min_resolution: f64,
     ) -> Vec<RenderPrimitive<'a, N, P, Contour<P>, Polygon<P>>>
     where
-        N: AsPrimitive<f32>,
-        P: CartesianPoint3d<Num = N> + Clone,
+        N: AsPrimitive<f32> + Float,
+        P: NewCartesianPoint3d<N> + Clone,
     {
         self.get_polygon_symbol(feature)
             .render(&(), geometry, min_resolution)
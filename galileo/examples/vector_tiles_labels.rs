@@ -78,10 +78,12 @@ pub async fn run(builder: MapBuilder, style: VectorTileStyle, api_key: String) {
                     font_color: Color::BLACK,
                     horizontal_alignment: Default::default(),
                     vertical_alignment: Default::default(),
+                    direction: Default::default(),
                 },
             )),
             line: None,
             polygon: None,
+            shield: None,
         },
         background: Default::default(),
     };
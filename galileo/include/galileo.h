@@ -0,0 +1,243 @@
+#ifndef GALILEO_H
+#define GALILEO_H
+
+/* This file is generated by cbindgen from galileo/src/ffi/mod.rs. Do not edit it manually. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Value of [`GalileoEvent::layer_id`] for the events that are not caused by a layer, e.g. a click outside of all
+// features. Ids of layers are never equal to this value.
+#define GALILEO_NO_LAYER 0
+
+// Kind of a [`GalileoEvent`].
+typedef enum GalileoEventKind {
+  // The map must be redrawn with [`galileo_map_render`].
+  GALILEO_EVENT_KIND_REDRAW_REQUESTED = 0,
+  // The map was clicked with the left mouse button. The position of the event is set.
+  GALILEO_EVENT_KIND_CLICK = 1,
+} GalileoEventKind;
+
+// Mouse button of an input event.
+typedef enum GalileoMouseButton {
+  // Left button.
+  GALILEO_MOUSE_BUTTON_LEFT = 0,
+  // Middle button.
+  GALILEO_MOUSE_BUTTON_MIDDLE = 1,
+  // Right button.
+  GALILEO_MOUSE_BUTTON_RIGHT = 2,
+  // Any other button.
+  GALILEO_MOUSE_BUTTON_OTHER = 3,
+} GalileoMouseButton;
+
+// Result of a call of the C interface.
+typedef enum GalileoStatus {
+  // The call succeeded.
+  GALILEO_STATUS_OK = 0,
+  // One of the arguments is a null pointer or has invalid value.
+  GALILEO_STATUS_INVALID_ARGUMENT = 1,
+  // The map is not attached to a window (see [`galileo_map_attach_window`]).
+  GALILEO_STATUS_NO_WINDOW = 2,
+  // The GPU renderer failed.
+  GALILEO_STATUS_RENDERER_ERROR = 3,
+  // The call failed because of an internal error of the library. The map may be left in an inconsistent state.
+  GALILEO_STATUS_INTERNAL_ERROR = 4,
+} GalileoStatus;
+
+// Windowing system of the window handle given to [`galileo_map_attach_window`].
+typedef enum GalileoWindowKind {
+  // Windows `HWND`. The display pointer is ignored.
+  GALILEO_WINDOW_KIND_WIN32 = 0,
+  // X11 `Window` id (cast to a pointer) with the Xlib `Display*` as the display pointer.
+  GALILEO_WINDOW_KIND_XLIB = 1,
+  // XCB `xcb_window_t` id (cast to a pointer) with the `xcb_connection_t*` as the display pointer.
+  GALILEO_WINDOW_KIND_XCB = 2,
+  // Wayland `wl_surface*` with the `wl_display*` as the display pointer.
+  GALILEO_WINDOW_KIND_WAYLAND = 3,
+  // macOS `NSView*`. The display pointer is ignored.
+  GALILEO_WINDOW_KIND_APP_KIT = 4,
+  // iOS `UIView*`. The display pointer is ignored.
+  GALILEO_WINDOW_KIND_UI_KIT = 5,
+} GalileoWindowKind;
+
+// Map with everything needed to draw it to a window and to handle user input. Created with [`galileo_map_new`] and
+// destroyed with [`galileo_map_free`].
+typedef struct GalileoMap GalileoMap;
+
+// Event reported by a map, returned by [`galileo_map_poll_event`].
+typedef struct GalileoEvent {
+  // Kind of the event.
+  enum GalileoEventKind kind;
+  // Horizontal screen position of the event in pixels, or 0.
+  double x;
+  // Vertical screen position of the event in pixels, or 0.
+  double y;
+  // Latitude of the event position, or `NaN` if the event has no position.
+  double lat;
+  // Longitude of the event position, or `NaN` if the event has no position.
+  double lon;
+  // Id of the layer that caused the event (as returned by the `galileo_map_add_*_layer` functions), or
+  // [`GALILEO_NO_LAYER`] if the event is not caused by a layer. For a click it is the id of the topmost visible
+  // layer with a feature under the pointer.
+  uint64_t layer_id;
+} GalileoEvent;
+
+// Function called when the map must be redrawn, with the user data given to [`galileo_map_set_redraw_callback`].
+typedef void (*GalileoRedrawCallback)(void *user_data);
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Creates a new map centered at the given point (in degrees) with the given resolution (in meters per pixel) and
+// size (in pixels). Returns null if the arguments are not valid or the background runtime of the map cannot be
+// started.
+//
+// The map must be destroyed with [`galileo_map_free`].
+struct GalileoMap *galileo_map_new(double lat,
+                                   double lon,
+                                   double resolution,
+                                   uint32_t width,
+                                   uint32_t height);
+
+// Destroys the map created by [`galileo_map_new`].
+//
+// # Safety
+//
+// The pointer must be null or returned by `galileo_map_new`, and must not be used after this call.
+void galileo_map_free(struct GalileoMap *map);
+
+// Attaches the map to a window created by the application. The map is drawn to the window by
+// [`galileo_map_render`]. The size must be the size of the window in physical pixels.
+//
+// See [`GalileoWindowKind`] for the meaning of the `window` and `display` pointers on each platform.
+//
+// # Safety
+//
+// `map` must be returned by `galileo_map_new`. The window and display must stay valid until the map is freed or
+// attached to another window.
+enum GalileoStatus galileo_map_attach_window(struct GalileoMap *map,
+                                             enum GalileoWindowKind kind,
+                                             void *window,
+                                             void *display,
+                                             uint32_t width,
+                                             uint32_t height);
+
+// Sets the size of the map and of its window surface in physical pixels. Must be called when the window is resized.
+//
+// # Safety
+//
+// `map` must be returned by `galileo_map_new`.
+enum GalileoStatus galileo_map_resize(struct GalileoMap *map, uint32_t width, uint32_t height);
+
+// Sets the number of physical pixels per logical pixel of the window.
+//
+// # Safety
+//
+// `map` must be returned by `galileo_map_new`.
+enum GalileoStatus galileo_map_set_scale_factor(struct GalileoMap *map, double scale_factor);
+
+// Draws the map to its window.
+//
+// # Safety
+//
+// `map` must be returned by `galileo_map_new`.
+enum GalileoStatus galileo_map_render(struct GalileoMap *map);
+
+// Moves the map to the given point (in degrees) and resolution (in meters per pixel).
+//
+// # Safety
+//
+// `map` must be returned by `galileo_map_new`.
+enum GalileoStatus galileo_map_set_view(struct GalileoMap *map,
+                                        double lat,
+                                        double lon,
+                                        double resolution);
+
+// Adds a raster tile layer to the top of the map and returns its id, or 0 on error. The tiles are loaded from the
+// URL template with `{z}`, `{x}` and `{y}` placeholders, e.g. `https://tile.openstreetmap.org/{z}/{x}/{y}.png`.
+//
+// # Safety
+//
+// `map` must be returned by `galileo_map_new`, `url_template` must be a null-terminated UTF-8 string.
+uint64_t galileo_map_add_raster_tile_layer(struct GalileoMap *map, const char *url_template);
+
+// Adds a layer with the features of a GeoJSON `FeatureCollection` to the top of the map and returns its id, or 0 if
+// the GeoJSON cannot be parsed.
+//
+// # Safety
+//
+// `map` must be returned by `galileo_map_new`, `geojson` must be a null-terminated UTF-8 string.
+uint64_t galileo_map_add_geojson_layer(struct GalileoMap *map, const char *geojson);
+
+// Removes the layer with the given id from the map. Returns false if the map has no such layer.
+//
+// # Safety
+//
+// `map` must be returned by `galileo_map_new`.
+bool galileo_map_remove_layer(struct GalileoMap *map, uint64_t layer_id);
+
+// Shows or hides the layer with the given id. Returns false if the map has no such layer.
+//
+// # Safety
+//
+// `map` must be returned by `galileo_map_new`.
+bool galileo_map_set_layer_visible(struct GalileoMap *map, uint64_t layer_id, bool visible);
+
+// Sets opacity of the layer with the given id from 0 to 1. Returns false if the map has no such layer.
+//
+// # Safety
+//
+// `map` must be returned by `galileo_map_new`.
+bool galileo_map_set_layer_opacity(struct GalileoMap *map, uint64_t layer_id, float opacity);
+
+// Passes the new position of the mouse pointer (in pixels from the top-left corner of the window) to the map.
+//
+// # Safety
+//
+// `map` must be returned by `galileo_map_new`.
+enum GalileoStatus galileo_map_pointer_moved(struct GalileoMap *map, double x, double y);
+
+// Passes a mouse button press or release to the map.
+//
+// # Safety
+//
+// `map` must be returned by `galileo_map_new`.
+enum GalileoStatus galileo_map_mouse_button(struct GalileoMap *map,
+                                            enum GalileoMouseButton button,
+                                            bool pressed);
+
+// Passes a scroll of the mouse wheel to the map. `lines` is the number of text lines the event would scroll,
+// positive values zoom the map in.
+//
+// # Safety
+//
+// `map` must be returned by `galileo_map_new`.
+enum GalileoStatus galileo_map_scroll(struct GalileoMap *map, double lines);
+
+// Takes the next event of the map. Returns false if there are no events. Events should be polled after every call
+// to the map and when the redraw callback is called.
+//
+// # Safety
+//
+// `map` must be returned by `galileo_map_new`, `event` must point to a writable `GalileoEvent`.
+bool galileo_map_poll_event(struct GalileoMap *map, struct GalileoEvent *event);
+
+// Sets a function that is called every time the map needs to be redrawn, e.g. to wake up the event loop of the
+// application. A `RedrawRequested` event is also added to the event queue. Pass null callback to remove it.
+//
+// # Safety
+//
+// `map` must be returned by `galileo_map_new`. The callback must be safe to call from any thread with the given
+// user data while it is set.
+enum GalileoStatus galileo_map_set_redraw_callback(struct GalileoMap *map,
+                                                   GalileoRedrawCallback callback,
+                                                   void *user_data);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* GALILEO_H */
@@ -0,0 +1,117 @@
+use crate::accessibility::FeatureFocus;
+use crate::layer::Layer;
+use crate::messenger::Messenger;
+use crate::render::point_paint::PointPaint;
+use crate::render::render_bundle::RenderPrimitive;
+use crate::render::{Canvas, RenderOptions};
+use crate::view::MapView;
+use crate::Color;
+use galileo_types::cartesian::{Point2d, Point3d};
+use galileo_types::geo::impls::GeoPoint2d;
+use galileo_types::impls::{Contour, Polygon};
+use std::any::Any;
+
+/// Style of the [`FocusRingLayer`].
+#[derive(Debug, Clone)]
+pub struct FocusRingStyle {
+    /// Color of the ring.
+    pub color: Color,
+    /// Color of the halo around the ring that keeps it visible on any background.
+    pub halo_color: Color,
+    /// Diameter of the ring in pixels.
+    pub diameter: f32,
+    /// Width of the ring line in pixels.
+    pub width: f32,
+}
+
+impl Default for FocusRingStyle {
+    fn default() -> Self {
+        Self {
+            color: Color::rgba(0, 95, 204, 255),
+            halo_color: Color::WHITE,
+            diameter: 32.0,
+            width: 3.0,
+        }
+    }
+}
+
+/// Layer that draws a ring around the feature focused in a [`FeatureFocus`].
+///
+/// The layer is rendered anew every frame, so it always shows the current focus.
+pub struct FocusRingLayer {
+    focus: FeatureFocus,
+    style: FocusRingStyle,
+}
+
+impl FocusRingLayer {
+    /// Creates a new layer.
+    pub fn new(focus: FeatureFocus, style: FocusRingStyle) -> Self {
+        Self { focus, style }
+    }
+
+    /// Focus the layer displays.
+    pub fn focus(&self) -> &FeatureFocus {
+        &self.focus
+    }
+
+    /// Style of the layer.
+    pub fn style(&self) -> &FocusRingStyle {
+        &self.style
+    }
+
+    /// Sets the style of the layer.
+    pub fn set_style(&mut self, style: FocusRingStyle) {
+        self.style = style;
+    }
+}
+
+impl Layer for FocusRingLayer {
+    fn render(&self, view: &MapView, canvas: &mut dyn Canvas) {
+        let Some(feature) = self.focus.focused() else {
+            return;
+        };
+        let Some(center) = view
+            .crs()
+            .get_projection::<GeoPoint2d, Point2d>()
+            .and_then(|projection| projection.project(&feature.location))
+        else {
+            return;
+        };
+        let center = Point3d::new(center.x, center.y, 0.0);
+
+        let mut bundle = canvas.create_bundle();
+        let halo_width = self.style.width + 4.0;
+        for (color, width) in [
+            (self.style.halo_color, halo_width),
+            (self.style.color, self.style.width),
+        ] {
+            bundle.add(
+                RenderPrimitive::<_, _, Contour<Point3d>, Polygon<Point3d>>::new_point(
+                    center,
+                    PointPaint::circle(Color::TRANSPARENT, self.style.diameter)
+                        .with_outline(color, width),
+                ),
+                0.0,
+            );
+        }
+
+        let packed = canvas.pack_bundle(&bundle);
+        canvas.draw_bundles(&[&*packed], RenderOptions::default());
+    }
+
+    fn prepare(&self, _view: &MapView) {
+        // do nothing
+    }
+
+    fn set_messenger(&mut self, messenger: Box<dyn Messenger>) {
+        self.focus.set_messenger(Some(messenger));
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
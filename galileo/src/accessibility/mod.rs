@@ -0,0 +1,340 @@
+//! Keyboard navigation between map features and their descriptions for assistive technologies.
+//!
+//! * [`FeatureFocus`] stores the list of [`FocusableFeature`]s and which of them has the keyboard focus. It handles
+//!   `Tab`/`Shift+Tab` to move the focus, `Enter` or `Space` to center the map on the focused feature and `Escape` to
+//!   clear the focus, so it can be added to the [`EventProcessor`](crate::control::EventProcessor) as a handler.
+//! * Every time the focus changes, the description of the focused feature is given to the callback set with
+//!   [`FeatureFocus::set_announcer`]. The application forwards it to the screen reader, e.g. by updating an AccessKit
+//!   node or an ARIA live region.
+//! * [`FocusRingLayer`] draws a ring around the focused feature.
+
+use crate::control::{EventPropagation, Key, UserEvent, UserEventHandler};
+use crate::messenger::Messenger;
+use crate::Map;
+use galileo_types::cartesian::Point2d;
+use galileo_types::geo::impls::GeoPoint2d;
+use maybe_sync::{MaybeSend, MaybeSync};
+use std::sync::{Arc, RwLock, RwLockWriteGuard};
+use std::time::Duration;
+
+mod focus_ring_layer;
+
+pub use focus_ring_layer::{FocusRingLayer, FocusRingStyle};
+
+/// Callback that is notified about focus changes. `None` is given when the focus is cleared.
+type Announcer = Arc<dyn Fn(Option<&FocusableFeature>) + MaybeSend + MaybeSync>;
+
+/// A feature that can receive the keyboard focus.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FocusableFeature {
+    /// Location of the feature. The focus ring is drawn around this point and the map is moved to it when the feature
+    /// is focused outside the visible area.
+    pub location: GeoPoint2d,
+    /// Text read by the screen reader when the feature is focused.
+    pub description: String,
+}
+
+impl FocusableFeature {
+    /// Creates a new focusable feature.
+    pub fn new(location: GeoPoint2d, description: impl Into<String>) -> Self {
+        Self {
+            location,
+            description: description.into(),
+        }
+    }
+}
+
+/// Keeps track of the feature that has the keyboard focus.
+///
+/// Features are traversed in the order they were given to [`FeatureFocus::set_features`]. The focus wraps around
+/// after the last feature.
+///
+/// The focus is cheaply cloneable, all the clones share the same state.
+#[derive(Clone, Default)]
+pub struct FeatureFocus {
+    state: Arc<RwLock<FocusState>>,
+}
+
+#[derive(Default)]
+struct FocusState {
+    features: Vec<FocusableFeature>,
+    focused: Option<usize>,
+    announcer: Option<Announcer>,
+    messenger: Option<Box<dyn Messenger>>,
+}
+
+impl FeatureFocus {
+    /// Duration of the map animation when the map is moved to the focused feature.
+    const MOVE_ANIMATION_DURATION: Duration = Duration::from_millis(300);
+
+    /// Creates a new focus without features.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces the list of focusable features. The focus is cleared.
+    pub fn set_features(&self, features: Vec<FocusableFeature>) {
+        let mut state = self.state.write().expect("lock is poisoned");
+        state.features = features;
+        self.update_focus(state, None);
+    }
+
+    /// Number of focusable features.
+    pub fn len(&self) -> usize {
+        self.state.read().expect("lock is poisoned").features.len()
+    }
+
+    /// Returns true if there are no focusable features.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Index of the focused feature.
+    pub fn focused_index(&self) -> Option<usize> {
+        self.state.read().expect("lock is poisoned").focused
+    }
+
+    /// The focused feature.
+    pub fn focused(&self) -> Option<FocusableFeature> {
+        let state = self.state.read().expect("lock is poisoned");
+        state.focused.map(|index| state.features[index].clone())
+    }
+
+    /// Moves the focus to the feature with the given index. Returns false if there is no such feature.
+    pub fn focus(&self, index: usize) -> bool {
+        let state = self.state.write().expect("lock is poisoned");
+        if index >= state.features.len() {
+            return false;
+        }
+
+        self.update_focus(state, Some(index));
+        true
+    }
+
+    /// Moves the focus to the next feature. If no feature is focused, the first one gets the focus.
+    pub fn focus_next(&self) -> Option<FocusableFeature> {
+        let state = self.state.write().expect("lock is poisoned");
+        let count = state.features.len();
+        if count == 0 {
+            return None;
+        }
+
+        let index = state.focused.map_or(0, |index| (index + 1) % count);
+        self.update_focus(state, Some(index))
+    }
+
+    /// Moves the focus to the previous feature. If no feature is focused, the last one gets the focus.
+    pub fn focus_previous(&self) -> Option<FocusableFeature> {
+        let state = self.state.write().expect("lock is poisoned");
+        let count = state.features.len();
+        if count == 0 {
+            return None;
+        }
+
+        let index = state
+            .focused
+            .map_or(count - 1, |index| (index + count - 1) % count);
+        self.update_focus(state, Some(index))
+    }
+
+    /// Removes the focus from the focused feature.
+    pub fn clear_focus(&self) {
+        let state = self.state.write().expect("lock is poisoned");
+        self.update_focus(state, None);
+    }
+
+    /// Sets the callback that is called with the newly focused feature every time the focus changes, or with `None`
+    /// when the focus is cleared.
+    pub fn set_announcer(
+        &self,
+        announcer: impl Fn(Option<&FocusableFeature>) + MaybeSend + MaybeSync + 'static,
+    ) {
+        self.state.write().expect("lock is poisoned").announcer = Some(Arc::new(announcer));
+    }
+
+    /// Sets the messenger to notify when the focus changes.
+    pub fn set_messenger(&self, messenger: Option<Box<dyn Messenger>>) {
+        self.state.write().expect("lock is poisoned").messenger = messenger;
+    }
+
+    fn update_focus(
+        &self,
+        mut state: RwLockWriteGuard<FocusState>,
+        focused: Option<usize>,
+    ) -> Option<FocusableFeature> {
+        if state.focused == focused {
+            return focused.map(|index| state.features[index].clone());
+        }
+
+        state.focused = focused;
+        let feature = focused.map(|index| state.features[index].clone());
+        if let Some(messenger) = &state.messenger {
+            messenger.request_redraw();
+        }
+
+        // The announcer is called without the lock, so that it can read the state of the focus.
+        let announcer = state.announcer.clone();
+        drop(state);
+        if let Some(announcer) = announcer {
+            announcer(feature.as_ref());
+        }
+
+        feature
+    }
+
+    /// Moves the map to the feature if it is outside the visible area.
+    fn reveal(&self, feature: &FocusableFeature, map: &mut Map) {
+        let view = map.target_view();
+        let visible = view
+            .crs()
+            .get_projection::<GeoPoint2d, Point2d>()
+            .and_then(|projection| projection.project(&feature.location))
+            .zip(view.get_bbox())
+            .is_some_and(|(point, bbox)| bbox.contains(&point));
+
+        if !visible {
+            self.center(feature, map);
+        }
+    }
+
+    fn center(&self, feature: &FocusableFeature, map: &mut Map) {
+        let target = map.target_view().with_position(&feature.location);
+        map.animate_to(target, Self::MOVE_ANIMATION_DURATION);
+    }
+}
+
+impl UserEventHandler for FeatureFocus {
+    fn handle(&self, event: &UserEvent, map: &mut Map) -> EventPropagation {
+        let UserEvent::KeyPressed(key, modifiers) = event else {
+            return EventPropagation::Propagate;
+        };
+
+        match key {
+            Key::Tab => {
+                let focused = if modifiers.shift {
+                    self.focus_previous()
+                } else {
+                    self.focus_next()
+                };
+
+                match focused {
+                    Some(feature) => {
+                        self.reveal(&feature, map);
+                        EventPropagation::Stop
+                    }
+                    None => EventPropagation::Propagate,
+                }
+            }
+            Key::Enter | Key::Space => match self.focused() {
+                Some(feature) => {
+                    self.center(&feature, map);
+                    EventPropagation::Stop
+                }
+                None => EventPropagation::Propagate,
+            },
+            Key::Escape if self.focused_index().is_some() => {
+                self.clear_focus();
+                EventPropagation::Stop
+            }
+            _ => EventPropagation::Propagate,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::control::Modifiers;
+    use crate::view::MapView;
+    use galileo_types::cartesian::Size;
+    use galileo_types::geo::GeoPoint;
+    use galileo_types::latlon;
+    use std::sync::Mutex;
+
+    fn features() -> Vec<FocusableFeature> {
+        vec![
+            FocusableFeature::new(latlon!(0.0, 0.0), "First"),
+            FocusableFeature::new(latlon!(10.0, 10.0), "Second"),
+            FocusableFeature::new(latlon!(20.0, 20.0), "Third"),
+        ]
+    }
+
+    #[test]
+    fn traversal_wraps_and_announces() {
+        let focus = FeatureFocus::new();
+        focus.set_features(features());
+
+        let announced = Arc::new(Mutex::new(vec![]));
+        {
+            let announced = announced.clone();
+            focus.set_announcer(move |feature| {
+                announced
+                    .lock()
+                    .expect("mutex is poisoned")
+                    .push(feature.map(|f| f.description.clone()));
+            });
+        }
+
+        assert_eq!(
+            focus.focus_previous().map(|f| f.description),
+            Some("Third".into())
+        );
+        assert_eq!(
+            focus.focus_next().map(|f| f.description),
+            Some("First".into())
+        );
+        assert_eq!(
+            focus.focus_next().map(|f| f.description),
+            Some("Second".into())
+        );
+        focus.clear_focus();
+        assert!(!focus.focus(3));
+
+        assert_eq!(
+            *announced.lock().expect("mutex is poisoned"),
+            vec![
+                Some("Third".to_string()),
+                Some("First".to_string()),
+                Some("Second".to_string()),
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn keyboard_moves_focus_and_map() {
+        let view = MapView::new(&latlon!(0.0, 0.0), 10.0).with_size(Size::new(100.0, 100.0));
+        let mut map = Map::new(view, vec![], None::<crate::DummyMessenger>);
+        let focus = FeatureFocus::new();
+        focus.set_features(features());
+
+        let shift_tab = UserEvent::KeyPressed(
+            Key::Tab,
+            Modifiers {
+                shift: true,
+                ..Default::default()
+            },
+        );
+        assert!(matches!(
+            focus.handle(&shift_tab, &mut map),
+            EventPropagation::Stop
+        ));
+        assert_eq!(focus.focused_index(), Some(2));
+
+        // The feature is far outside the view, so the map is moved to it.
+        let target = map.target_view().position().expect("view has position");
+        assert!((target.lat() - 20.0).abs() < 1e-6);
+        assert!((target.lon() - 20.0).abs() < 1e-6);
+
+        let escape = UserEvent::KeyPressed(Key::Escape, Modifiers::default());
+        assert!(matches!(
+            focus.handle(&escape, &mut map),
+            EventPropagation::Stop
+        ));
+        assert_eq!(focus.focused_index(), None);
+        assert!(matches!(
+            focus.handle(&escape, &mut map),
+            EventPropagation::Propagate
+        ));
+    }
+}
@@ -0,0 +1,283 @@
+//! Audit of heap allocations done while a frame is rendered.
+//!
+//! Real-time and embedded applications often need stable frame times, which is hard to guarantee if every frame
+//! allocates and frees memory. Galileo reuses its buffers between frames when drawing layers, so once all the data
+//! for the current view is loaded and prepared (the *steady state*), rendering a frame should not allocate. This
+//! module allows to check that this is the case for a specific application.
+//!
+//! To count allocations, install [`CountingAllocator`] as the global allocator of the application and set the audit
+//! mode of the map with [`Map::set_allocation_audit`](crate::Map::set_allocation_audit):
+//!
+//! ```no_run
+//! use galileo::alloc_audit::{AllocationAudit, CountingAllocator};
+//! use std::alloc::System;
+//!
+//! #[global_allocator]
+//! static ALLOCATOR: CountingAllocator<System> = CountingAllocator::new(System);
+//!
+//! # fn configure(map: &mut galileo::Map) {
+//! map.set_allocation_audit(AllocationAudit::Assert);
+//! # }
+//! ```
+//!
+//! Only the allocations done by the thread that renders the frame are counted, so background loading of tiles does
+//! not affect the audit. Allocations inside the GPU backend (`wgpu` command encoding) are outside of Galileo's control
+//! and are not counted either.
+
+use std::alloc::{GlobalAlloc, Layout};
+use std::cell::Cell;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+thread_local! {
+    static ALLOCATIONS: Cell<u64> = const { Cell::new(0) };
+    static PAUSED: Cell<bool> = const { Cell::new(false) };
+}
+
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+/// Global allocator that counts allocations of every thread, forwarding them to the inner allocator.
+///
+/// See [module documentation](self) for usage.
+#[derive(Debug, Default)]
+pub struct CountingAllocator<A> {
+    inner: A,
+}
+
+impl<A> CountingAllocator<A> {
+    /// Creates a new counting allocator that uses the `inner` allocator to manage memory.
+    pub const fn new(inner: A) -> Self {
+        Self { inner }
+    }
+
+    fn count() {
+        INSTALLED.store(true, Ordering::Relaxed);
+        // The thread locals can be already destroyed when the thread is shutting down.
+        let _ = PAUSED.try_with(|paused| {
+            if !paused.get() {
+                let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+            }
+        });
+    }
+}
+
+// SAFETY: all the calls are forwarded to the inner allocator without changes.
+unsafe impl<A: GlobalAlloc> GlobalAlloc for CountingAllocator<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        Self::count();
+        self.inner.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.inner.dealloc(ptr, layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        Self::count();
+        self.inner.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        Self::count();
+        self.inner.realloc(ptr, layout, new_size)
+    }
+}
+
+/// Returns true if [`CountingAllocator`] is installed as the global allocator and has counted some allocations.
+pub fn is_counting() -> bool {
+    INSTALLED.load(Ordering::Relaxed)
+}
+
+/// Number of allocations (including reallocations) done by the current thread since it started. Always returns 0
+/// if [`CountingAllocator`] is not installed.
+pub fn thread_allocations() -> u64 {
+    ALLOCATIONS.try_with(Cell::get).unwrap_or_default()
+}
+
+/// Runs `f` without counting the allocations it does on the current thread. Used to exclude code outside of the
+/// control of the caller (like GPU drivers) from the audit.
+pub fn without_counting<T>(f: impl FnOnce() -> T) -> T {
+    let was_paused = PAUSED
+        .try_with(|paused| paused.replace(true))
+        .unwrap_or(true);
+    let result = f();
+    let _ = PAUSED.try_with(|paused| paused.set(was_paused));
+
+    result
+}
+
+/// Mode of the allocation audit of a [`Map`](crate::Map).
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum AllocationAudit {
+    /// Allocations are not counted.
+    #[default]
+    Off,
+    /// Allocations of every frame are counted and can be read with
+    /// [`Map::last_frame_allocations`](crate::Map::last_frame_allocations).
+    Count,
+    /// Same as [`AllocationAudit::Count`], but a frame that allocates fails a debug assertion (or logs an error in
+    /// release builds). Enable this mode when the map is in the steady state, e.g. after all the tiles of the view are
+    /// loaded.
+    Assert,
+}
+
+/// Allocation audit state of a map.
+#[derive(Debug, Default)]
+pub(crate) struct FrameAudit {
+    mode: AllocationAudit,
+    state: Mutex<FrameAuditState>,
+}
+
+#[derive(Debug, Default)]
+struct FrameAuditState {
+    frame_start: Option<u64>,
+    last_frame: Option<u64>,
+}
+
+impl FrameAudit {
+    pub(crate) fn set_mode(&mut self, mode: AllocationAudit) {
+        if mode != AllocationAudit::Off && !is_counting() {
+            log::warn!("Allocation audit is enabled, but CountingAllocator is not installed as the global allocator");
+        }
+
+        self.mode = mode;
+        *self.state.lock().expect("mutex is poisoned") = FrameAuditState::default();
+    }
+
+    pub(crate) fn last_frame(&self) -> Option<u64> {
+        self.state.lock().expect("mutex is poisoned").last_frame
+    }
+
+    pub(crate) fn begin_frame(&self) {
+        if self.mode == AllocationAudit::Off {
+            return;
+        }
+
+        self.state.lock().expect("mutex is poisoned").frame_start = Some(thread_allocations());
+    }
+
+    pub(crate) fn end_frame(&self, frame_number: u64) {
+        if self.mode == AllocationAudit::Off {
+            return;
+        }
+
+        let allocations = {
+            let mut state = self.state.lock().expect("mutex is poisoned");
+            let Some(start) = state.frame_start.take() else {
+                return;
+            };
+
+            let allocations = thread_allocations().saturating_sub(start);
+            state.last_frame = Some(allocations);
+            allocations
+        };
+
+        if self.mode == AllocationAudit::Assert && allocations > 0 {
+            if cfg!(debug_assertions) {
+                panic!("frame {frame_number} did {allocations} heap allocations in steady state");
+            }
+
+            log::error!("Frame {frame_number} did {allocations} heap allocations in steady state");
+        }
+    }
+}
+
+/// Buffer that keeps its capacity between frames.
+///
+/// [`ScratchBuffer::take`] returns an empty vector with the capacity left from the previous use, and
+/// [`ScratchBuffer::recycle`] returns it back after the frame. The vector can have elements of any type with the same
+/// size and alignment as `T` (this is checked at compile time), which allows to reuse buffers for references that live
+/// only during the frame, e.g. a `ScratchBuffer<&'static dyn PackedBundle>` can store a `Vec<&'a dyn PackedBundle>`.
+#[derive(Debug)]
+pub(crate) struct ScratchBuffer<T> {
+    buffer: Mutex<Vec<T>>,
+}
+
+impl<T> Default for ScratchBuffer<T> {
+    fn default() -> Self {
+        Self {
+            buffer: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+impl<T> ScratchBuffer<T> {
+    pub(crate) fn take<U>(&self) -> Vec<U> {
+        reuse_vec(std::mem::take(
+            &mut *self.buffer.lock().expect("mutex is poisoned"),
+        ))
+    }
+
+    pub(crate) fn recycle<U>(&self, buffer: Vec<U>) {
+        let buffer = reuse_vec(buffer);
+        let mut stored = self.buffer.lock().expect("mutex is poisoned");
+        if buffer.capacity() > stored.capacity() {
+            *stored = buffer;
+        }
+    }
+}
+
+/// Converts the vector into an empty vector of another type, keeping its allocation.
+///
+/// Fails to compile if `T` and `U` have different size or alignment.
+fn reuse_vec<T, U>(mut vec: Vec<T>) -> Vec<U> {
+    const {
+        assert!(
+            size_of::<T>() == size_of::<U>() && align_of::<T>() == align_of::<U>(),
+            "scratch buffer element types must have the same layout"
+        );
+    }
+
+    vec.clear();
+    let mut vec = std::mem::ManuallyDrop::new(vec);
+    let capacity = vec.capacity();
+    let ptr = vec.as_mut_ptr().cast::<U>();
+
+    // SAFETY: the allocation was made by a `Vec` for `capacity` elements of `T`, which have the same size and
+    // alignment as `U`, so it has the right layout for `capacity` elements of `U`. The vector is empty, so no values
+    // are reinterpreted, and the original vector is not dropped, so the allocation has a single owner.
+    unsafe { Vec::from_raw_parts(ptr, 0, capacity) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scratch_buffer_keeps_capacity() {
+        let scratch = ScratchBuffer::<u32>::default();
+        let mut buffer = scratch.take();
+        buffer.extend(0..100u32);
+        let ptr = buffer.as_ptr();
+        scratch.recycle(buffer);
+
+        let mut buffer: Vec<u32> = scratch.take();
+        assert!(buffer.is_empty());
+        assert!(buffer.capacity() >= 100);
+        assert_eq!(buffer.as_ptr(), ptr);
+        buffer.extend(0..100u32);
+        scratch.recycle(buffer);
+
+        let values = [1u32, 2, 3];
+        let mut references: Vec<&u32> = ScratchBuffer::<&'static u32>::default().take();
+        references.extend(values.iter());
+        let ptr = references.as_ptr().cast::<()>();
+        let scratch = ScratchBuffer::<&'static u32>::default();
+        scratch.recycle(references);
+        let references: Vec<&u32> = scratch.take();
+        assert!(references.capacity() >= 3);
+        assert_eq!(references.as_ptr().cast::<()>(), ptr);
+    }
+
+    #[test]
+    fn frame_audit_without_counting_allocator() {
+        let audit = FrameAudit {
+            mode: AllocationAudit::Count,
+            ..Default::default()
+        };
+        audit.begin_frame();
+        let _allocated = Box::new(1u8);
+        audit.end_frame(0);
+        assert_eq!(audit.last_frame(), Some(0));
+    }
+}
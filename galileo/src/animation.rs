@@ -0,0 +1,344 @@
+//! Easing functions and the animation clock of a [`Map`](crate::Map).
+//!
+//! Every map has an [`AnimationClock`] (see [`Map::clock`](crate::Map::clock)), which is advanced once per frame by
+//! [`Map::animate`](crate::Map::animate). Animations created from the clock read the time of the current frame, so
+//! all of them (and the animation of the map view) move in sync, no matter how long it takes to render the frame:
+//!
+//! ```no_run
+//! use galileo::animation::{Animation, StandardEasing};
+//! use std::time::Duration;
+//!
+//! # fn example(map: &galileo::Map) {
+//! let fade_in: Animation = map
+//!     .clock()
+//!     .animate(Duration::from_millis(300), StandardEasing::CubicOut);
+//!
+//! // in a render hook or before the map is rendered
+//! let opacity = fade_in.interpolate(0.0, 1.0);
+//! # }
+//! ```
+//!
+//! While any animation of the clock is running, the map requests redraw on every frame.
+
+use maybe_sync::{MaybeSend, MaybeSync};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use web_time::SystemTime;
+
+/// Easing function maps the linear progress of an animation (from 0 to 1) into the progress of the animated value.
+///
+/// The function should return 0 for 0 and 1 for 1, but may go outside of this range in between (e.g. for
+/// overshooting animations). Any `Fn(f64) -> f64` closure can be used as an easing.
+pub trait Easing: MaybeSend + MaybeSync {
+    /// Returns the progress of the animated value for the progress of time `t`.
+    fn ease(&self, t: f64) -> f64;
+}
+
+impl<T: Fn(f64) -> f64 + MaybeSend + MaybeSync> Easing for T {
+    fn ease(&self, t: f64) -> f64 {
+        self(t)
+    }
+}
+
+/// Commonly used easing functions.
+///
+/// *In* easings start slowly and accelerate, *out* easings decelerate towards the end, and *in-out* easings do both.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum StandardEasing {
+    /// Constant speed.
+    #[default]
+    Linear,
+    /// Quadratic acceleration.
+    QuadIn,
+    /// Quadratic deceleration.
+    QuadOut,
+    /// Quadratic acceleration and deceleration.
+    QuadInOut,
+    /// Cubic acceleration.
+    CubicIn,
+    /// Cubic deceleration.
+    CubicOut,
+    /// Cubic acceleration and deceleration.
+    CubicInOut,
+    /// Sinusoidal acceleration and deceleration.
+    SineInOut,
+    /// Exponential deceleration.
+    ExpoOut,
+    /// Deceleration that slightly overshoots the target and comes back.
+    BackOut,
+}
+
+impl Easing for StandardEasing {
+    fn ease(&self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Self::Linear => t,
+            Self::QuadIn => t * t,
+            Self::QuadOut => 1.0 - (1.0 - t).powi(2),
+            Self::QuadInOut => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(2) / 2.0
+                }
+            }
+            Self::CubicIn => t.powi(3),
+            Self::CubicOut => 1.0 - (1.0 - t).powi(3),
+            Self::CubicInOut => {
+                if t < 0.5 {
+                    4.0 * t.powi(3)
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+            Self::SineInOut => -((std::f64::consts::PI * t).cos() - 1.0) / 2.0,
+            Self::ExpoOut => {
+                if t == 1.0 {
+                    1.0
+                } else {
+                    1.0 - 2f64.powf(-10.0 * t)
+                }
+            }
+            Self::BackOut => {
+                const C1: f64 = 1.70158;
+                const C3: f64 = C1 + 1.0;
+                1.0 + C3 * (t - 1.0).powi(3) + C1 * (t - 1.0).powi(2)
+            }
+        }
+    }
+}
+
+/// Easing defined by a cubic Bézier curve from `(0, 0)` to `(1, 1)` with the given control points, same as the
+/// `cubic-bezier()` timing function of CSS.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CubicBezier {
+    x1: f64,
+    y1: f64,
+    x2: f64,
+    y2: f64,
+}
+
+impl CubicBezier {
+    /// Creates a new curve with the control points `(x1, y1)` and `(x2, y2)`. The `x` values are clamped into
+    /// `[0, 1]` range.
+    pub fn new(x1: f64, y1: f64, x2: f64, y2: f64) -> Self {
+        Self {
+            x1: x1.clamp(0.0, 1.0),
+            y1,
+            x2: x2.clamp(0.0, 1.0),
+            y2,
+        }
+    }
+
+    fn coordinate(a: f64, b: f64, s: f64) -> f64 {
+        let inv = 1.0 - s;
+        3.0 * inv * inv * s * a + 3.0 * inv * s * s * b + s * s * s
+    }
+}
+
+impl Easing for CubicBezier {
+    fn ease(&self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+
+        // `x` of the curve grows monotonically with its parameter, so the parameter is found by bisection.
+        let (mut low, mut high) = (0.0, 1.0);
+        for _ in 0..32 {
+            let mid = (low + high) / 2.0;
+            if Self::coordinate(self.x1, self.x2, mid) < t {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+
+        Self::coordinate(self.y1, self.y2, (low + high) / 2.0)
+    }
+}
+
+#[derive(Debug)]
+struct ClockState {
+    now: SystemTime,
+    running_until: SystemTime,
+}
+
+/// Clock that provides the time of the current frame to animations. See [module documentation](self) for details.
+///
+/// The clock can be cloned cheaply, and all the clones show the same time.
+#[derive(Debug, Clone)]
+pub struct AnimationClock {
+    state: Arc<Mutex<ClockState>>,
+}
+
+impl Default for AnimationClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AnimationClock {
+    /// Creates a new clock set to the current time.
+    pub fn new() -> Self {
+        let now = SystemTime::now();
+        Self {
+            state: Arc::new(Mutex::new(ClockState {
+                now,
+                running_until: now,
+            })),
+        }
+    }
+
+    /// Time of the current frame.
+    pub fn now(&self) -> SystemTime {
+        self.state.lock().expect("mutex is poisoned").now
+    }
+
+    /// Advances the clock to the current system time. Called by [`Map::animate`](crate::Map::animate) before every
+    /// frame.
+    pub fn tick(&self) {
+        self.set_time(SystemTime::now());
+    }
+
+    /// Sets the time of the clock. Can be used to render animations frame by frame with a fixed frame rate, e.g. when
+    /// recording a video. The time should not go backwards.
+    pub fn set_time(&self, time: SystemTime) {
+        self.state.lock().expect("mutex is poisoned").now = time;
+    }
+
+    /// Returns true if any animation created from this clock has not finished yet.
+    pub fn is_running(&self) -> bool {
+        let state = self.state.lock().expect("mutex is poisoned");
+        state.now < state.running_until
+    }
+
+    /// Starts a new animation at the current time of the clock.
+    pub fn animate(&self, duration: Duration, easing: impl Easing + 'static) -> Animation {
+        self.animate_from(self.now(), duration, easing)
+    }
+
+    /// Starts a new animation at the given time. If the time is later than the current time of the clock, the
+    /// animation stays at its start until the clock reaches that time.
+    pub fn animate_from(
+        &self,
+        start: SystemTime,
+        duration: Duration,
+        easing: impl Easing + 'static,
+    ) -> Animation {
+        let mut state = self.state.lock().expect("mutex is poisoned");
+        state.running_until = state.running_until.max(start + duration);
+
+        Animation {
+            clock: self.clone(),
+            start,
+            duration,
+            easing: Box::new(easing),
+        }
+    }
+}
+
+/// Animation of a value started with [`AnimationClock::animate`].
+///
+/// The animation does not store the animated value. Call [`Animation::value`] or [`Animation::interpolate`] every
+/// frame to get the value for the current time of the clock.
+pub struct Animation {
+    clock: AnimationClock,
+    start: SystemTime,
+    duration: Duration,
+    easing: Box<dyn Easing>,
+}
+
+impl Animation {
+    /// Linear progress of the animation at the current time of the clock, from 0 to 1.
+    pub fn progress(&self) -> f64 {
+        if self.duration.is_zero() {
+            return 1.0;
+        }
+
+        let elapsed = self
+            .clock
+            .now()
+            .duration_since(self.start)
+            .unwrap_or_default();
+        (elapsed.as_secs_f64() / self.duration.as_secs_f64()).min(1.0)
+    }
+
+    /// Eased progress of the animation at the current time of the clock.
+    pub fn value(&self) -> f64 {
+        let progress = self.progress();
+        if progress >= 1.0 {
+            1.0
+        } else {
+            self.easing.ease(progress)
+        }
+    }
+
+    /// Returns the value between `from` and `to` for the current time of the clock.
+    pub fn interpolate(&self, from: f64, to: f64) -> f64 {
+        from + (to - from) * self.value()
+    }
+
+    /// Returns true if the animation has reached its end.
+    pub fn is_finished(&self) -> bool {
+        self.progress() >= 1.0
+    }
+
+    /// Duration of the animation.
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+}
+
+impl std::fmt::Debug for Animation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Animation")
+            .field("start", &self.start)
+            .field("duration", &self.duration)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn standard_easings_start_and_end() {
+        let easings = [
+            StandardEasing::Linear,
+            StandardEasing::QuadIn,
+            StandardEasing::QuadOut,
+            StandardEasing::QuadInOut,
+            StandardEasing::CubicIn,
+            StandardEasing::CubicOut,
+            StandardEasing::CubicInOut,
+            StandardEasing::SineInOut,
+            StandardEasing::ExpoOut,
+            StandardEasing::BackOut,
+        ];
+        for easing in easings {
+            assert!(easing.ease(0.0).abs() < 1e-3, "{easing:?}");
+            assert!((easing.ease(1.0) - 1.0).abs() < 1e-9, "{easing:?}");
+        }
+
+        let ease = CubicBezier::new(0.25, 0.1, 0.25, 1.0);
+        assert!((ease.ease(0.5) - 0.8024).abs() < 1e-3);
+        assert!((CubicBezier::new(0.0, 0.0, 1.0, 1.0).ease(0.3) - 0.3).abs() < 1e-6);
+    }
+
+    #[test]
+    fn animation_follows_clock() {
+        let clock = AnimationClock::new();
+        let start = clock.now();
+        let animation = clock.animate(Duration::from_millis(100), StandardEasing::QuadIn);
+        assert!(clock.is_running());
+        assert_eq!(animation.value(), 0.0);
+
+        clock.set_time(start + Duration::from_millis(50));
+        assert_eq!(animation.progress(), 0.5);
+        assert_eq!(animation.interpolate(10.0, 20.0), 12.5);
+
+        clock.set_time(start + Duration::from_millis(150));
+        assert!(animation.is_finished());
+        assert!(!clock.is_running());
+        assert_eq!(animation.value(), 1.0);
+    }
+}
@@ -0,0 +1,528 @@
+//! Deterministic harness for measuring performance of the vector tile pipeline.
+//!
+//! The harness does not use network or GPU, so the results depend only on the performance of tile decoding and
+//! tessellation, and are repeatable between runs and machines:
+//! * [`SyntheticTileSource`] generates vector tiles from a seed. The same seed and index always produce the same tile.
+//!   The source also implements [`VectorTileLoader`], so it can be used as a drop-in replacement of a web loader.
+//! * [`ViewScript`] is a sequence of map views, one per frame. It can be generated or created from a recorded
+//!   [`EventScript`].
+//! * [`TilePipelineBench`] runs a view script through the decode and tessellation steps and collects
+//!   [`PipelineStats`].
+//!
+//! Criterion benchmarks built on this module are in the `benches` directory of the crate and are run with
+//! `cargo bench --features bench`.
+
+use crate::control::recording::{EventPlayer, EventScript};
+use crate::control::{EventProcessor, MapController};
+use crate::error::GalileoError;
+use crate::layer::vector_tile_layer::style::{
+    StyleRule, VectorTileLineSymbol, VectorTilePolygonSymbol, VectorTileStyle, VectorTileSymbol,
+};
+use crate::layer::vector_tile_layer::tile_provider::loader::{TileLoadError, VectorTileLoader};
+use crate::layer::vector_tile_layer::tile_provider::VtProcessor;
+use crate::render::point_paint::PointPaint;
+use crate::render::render_bundle::tessellating::TessellatingRenderBundle;
+use crate::render::render_bundle::{RenderBundle, RenderBundleType};
+use crate::render::{LineCap, LineJoin};
+use crate::tile_scheme::TileIndex;
+use crate::view::MapView;
+use crate::{Color, Map, TileSchema};
+use bytes::Bytes;
+use galileo_mvt::MvtTile;
+use geozero::mvt::{tile, Message, Tile};
+use std::collections::HashSet;
+use std::time::Duration;
+use web_time::Instant;
+
+/// Extent of the generated tiles.
+const TILE_EXTENT: u32 = 4096;
+
+/// Generator of deterministic synthetic vector tiles.
+///
+/// Every tile contains three layers: `water` with polygons, `roads` with lines and `places` with points. Features of
+/// each layer have a `kind` property with one of the values `major` or `minor`.
+#[derive(Debug, Clone)]
+pub struct SyntheticTileSource {
+    seed: u64,
+    features_per_layer: u32,
+    vertices_per_feature: u32,
+}
+
+impl SyntheticTileSource {
+    /// Creates a new source.
+    ///
+    /// `vertices_per_feature` is the number of vertices in every polygon and line, and defines how heavy the
+    /// tessellation of the tile is.
+    pub fn new(seed: u64, features_per_layer: u32, vertices_per_feature: u32) -> Self {
+        Self {
+            seed,
+            features_per_layer,
+            vertices_per_feature: vertices_per_feature.max(3),
+        }
+    }
+
+    /// Encodes the tile with the given index into MVT format.
+    pub fn tile_bytes(&self, index: TileIndex) -> Bytes {
+        let mut rng = SplitMix64::new(
+            self.seed
+                ^ (index.x as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+                ^ (index.y as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F)
+                ^ (index.z as u64).wrapping_mul(0x1656_67B1_9E37_79F9),
+        );
+
+        let tile = Tile {
+            layers: vec![
+                self.layer("water", &mut rng, |rng, vertices| {
+                    (tile::GeomType::Polygon, polygon_geometry(rng, vertices))
+                }),
+                self.layer("roads", &mut rng, |rng, vertices| {
+                    (tile::GeomType::Linestring, line_geometry(rng, vertices))
+                }),
+                self.layer("places", &mut rng, |rng, _| {
+                    (tile::GeomType::Point, point_geometry(rng))
+                }),
+            ],
+        };
+
+        Bytes::from(tile.encode_to_vec())
+    }
+
+    /// Style that draws all the layers of the synthetic tiles.
+    pub fn style(&self) -> VectorTileStyle {
+        let rule = |layer_name: &str, symbol: VectorTileSymbol| StyleRule {
+            layer_name: Some(layer_name.into()),
+            properties: Default::default(),
+            symbol,
+            ..Default::default()
+        };
+
+        VectorTileStyle {
+            rules: vec![
+                rule(
+                    "water",
+                    VectorTileSymbol {
+                        polygon: Some(VectorTilePolygonSymbol {
+                            fill_color: Color::rgba(160, 200, 240, 255),
+                        }),
+                        ..Default::default()
+                    },
+                ),
+                rule(
+                    "roads",
+                    VectorTileSymbol {
+                        line: Some(VectorTileLineSymbol {
+                            width: 2.0,
+                            stroke_color: Color::rgba(120, 120, 120, 255),
+                            line_cap: LineCap::Round,
+                            line_join: LineJoin::Round,
+                        }),
+                        ..Default::default()
+                    },
+                ),
+                rule(
+                    "places",
+                    VectorTileSymbol {
+                        point: Some(PointPaint::circle(Color::rgba(200, 50, 50, 255), 6.0)),
+                        ..Default::default()
+                    },
+                ),
+            ],
+            default_symbol: Default::default(),
+            background: Color::WHITE,
+        }
+    }
+
+    fn layer(
+        &self,
+        name: &str,
+        rng: &mut SplitMix64,
+        geometry: impl Fn(&mut SplitMix64, u32) -> (tile::GeomType, Vec<u32>),
+    ) -> tile::Layer {
+        let features = (0..self.features_per_layer)
+            .map(|id| {
+                let (geom_type, geometry) = geometry(rng, self.vertices_per_feature);
+                tile::Feature {
+                    id: Some(id as u64),
+                    tags: vec![0, (id % 2)],
+                    r#type: Some(geom_type as i32),
+                    geometry,
+                }
+            })
+            .collect();
+
+        tile::Layer {
+            version: 2,
+            name: name.into(),
+            features,
+            keys: vec!["kind".into()],
+            values: ["major", "minor"]
+                .into_iter()
+                .map(|value| tile::Value {
+                    string_value: Some(value.into()),
+                    ..Default::default()
+                })
+                .collect(),
+            extent: Some(TILE_EXTENT),
+        }
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+impl VectorTileLoader for SyntheticTileSource {
+    async fn load(&self, index: TileIndex) -> Result<MvtTile, TileLoadError> {
+        MvtTile::decode(self.tile_bytes(index), false).map_err(|_| TileLoadError::Decoding)
+    }
+}
+
+/// Small deterministic random number generator, so that the generated tiles do not depend on external crates.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Random value in `[min, max)`.
+    fn range(&mut self, min: i32, max: i32) -> i32 {
+        min + (self.next_u64() % (max - min) as u64) as i32
+    }
+}
+
+/// Encoder of MVT geometry commands.
+#[derive(Default)]
+struct GeometryEncoder {
+    data: Vec<u32>,
+    cursor: (i32, i32),
+}
+
+impl GeometryEncoder {
+    const MOVE_TO: u32 = 1;
+    const LINE_TO: u32 = 2;
+    const CLOSE_PATH: u32 = 7;
+
+    fn command(&mut self, id: u32, count: usize) {
+        self.data.push((id & 0x7) | ((count as u32) << 3));
+    }
+
+    fn points(&mut self, points: &[(i32, i32)]) {
+        for &(x, y) in points {
+            self.data.push(zigzag(x - self.cursor.0));
+            self.data.push(zigzag(y - self.cursor.1));
+            self.cursor = (x, y);
+        }
+    }
+
+    fn path(&mut self, points: &[(i32, i32)]) {
+        self.command(Self::MOVE_TO, 1);
+        self.points(&points[..1]);
+        self.command(Self::LINE_TO, points.len() - 1);
+        self.points(&points[1..]);
+    }
+}
+
+fn zigzag(value: i32) -> u32 {
+    ((value << 1) ^ (value >> 31)) as u32
+}
+
+fn point_geometry(rng: &mut SplitMix64) -> Vec<u32> {
+    let mut encoder = GeometryEncoder::default();
+    encoder.command(GeometryEncoder::MOVE_TO, 1);
+    let extent = TILE_EXTENT as i32;
+    encoder.points(&[(rng.range(0, extent), rng.range(0, extent))]);
+    encoder.data
+}
+
+fn line_geometry(rng: &mut SplitMix64, vertices: u32) -> Vec<u32> {
+    let extent = TILE_EXTENT as i32;
+    let mut point = (rng.range(0, extent), rng.range(0, extent));
+    let mut points = vec![point];
+    for _ in 1..vertices {
+        point = (
+            (point.0 + rng.range(-200, 200)).clamp(0, extent),
+            (point.1 + rng.range(-200, 200)).clamp(0, extent),
+        );
+        points.push(point);
+    }
+
+    let mut encoder = GeometryEncoder::default();
+    encoder.path(&points);
+    encoder.data
+}
+
+/// Star-shaped polygon around a random center. The ring has positive area in tile coordinates (y-axis pointing
+/// down), as required for exterior rings by the MVT specification.
+fn polygon_geometry(rng: &mut SplitMix64, vertices: u32) -> Vec<u32> {
+    let extent = TILE_EXTENT as i32;
+    let center = (rng.range(500, extent - 500), rng.range(500, extent - 500));
+    let points: Vec<_> = (0..vertices)
+        .map(|i| {
+            let angle = std::f64::consts::TAU * i as f64 / vertices as f64;
+            let radius = rng.range(100, 500) as f64;
+            (
+                center.0 + (radius * angle.cos()) as i32,
+                center.1 + (radius * angle.sin()) as i32,
+            )
+        })
+        .collect();
+
+    let mut encoder = GeometryEncoder::default();
+    encoder.path(&points);
+    encoder.command(GeometryEncoder::CLOSE_PATH, 1);
+    encoder.data
+}
+
+/// Sequence of map views, one per rendered frame.
+#[derive(Debug, Clone, Default)]
+pub struct ViewScript {
+    views: Vec<MapView>,
+}
+
+impl ViewScript {
+    /// Creates a script from the given views.
+    pub fn from_views(views: Vec<MapView>) -> Self {
+        Self { views }
+    }
+
+    /// Generates a script that pans the map to the east while zooming in and out, starting from the `start` view.
+    ///
+    /// Every frame moves the map by 1/50 of the screen width, and the resolution changes by up to 4 times over a
+    /// period of 120 frames.
+    pub fn fly_over(start: &MapView, frames: usize) -> Self {
+        let step = start.size().width() * start.resolution() / 50.0;
+        let views = (0..frames)
+            .map(|frame| {
+                let phase = std::f64::consts::TAU * frame as f64 / 120.0;
+                let resolution = start.resolution() * 2f64.powf(phase.sin() * 2.0);
+                start
+                    .translate(nalgebra::Vector2::new(-step * frame as f64, 0.0))
+                    .with_resolution(resolution)
+            })
+            .collect();
+
+        Self { views }
+    }
+
+    /// Creates a script by replaying the recorded user interactions on a map with the `initial` view, controlled by
+    /// a [`MapController`]. The view is sampled every `frame_interval`.
+    pub fn from_recording(
+        script: &EventScript,
+        initial: MapView,
+        frame_interval: Duration,
+    ) -> Self {
+        let frame_interval = frame_interval.max(Duration::from_millis(1));
+        let mut map = Map::new(initial, vec![], None::<crate::DummyMessenger>);
+        let mut processor = EventProcessor::default();
+        processor.add_handler(MapController::default());
+        let mut player = EventPlayer::new(script.clone());
+
+        let mut views = vec![];
+        let mut elapsed = Duration::ZERO;
+        loop {
+            let finished = player.play_until(elapsed, &mut processor, &mut map);
+            // Target view is used instead of the current one, as animations depend on the wall clock time.
+            views.push(map.target_view().clone());
+
+            if finished {
+                break;
+            }
+            elapsed += frame_interval;
+        }
+
+        Self { views }
+    }
+
+    /// Views of the script.
+    pub fn views(&self) -> &[MapView] {
+        &self.views
+    }
+}
+
+/// Results of a [`TilePipelineBench`] run.
+#[derive(Debug, Clone, Default)]
+pub struct PipelineStats {
+    /// Number of decoded tiles.
+    pub tiles_decoded: usize,
+    /// Total time spent decoding tiles.
+    pub decode_time: Duration,
+    /// Total time spent tessellating decoded tiles.
+    pub tessellation_time: Duration,
+    /// Total size of the tessellated buffers in bytes.
+    pub tessellated_bytes: usize,
+    /// Time it took to prepare every frame of the script.
+    pub frame_times: Vec<Duration>,
+}
+
+impl PipelineStats {
+    /// Number of tiles decoded per second.
+    pub fn tiles_per_second(&self) -> f64 {
+        per_second(self.tiles_decoded as f64, self.decode_time)
+    }
+
+    /// Number of bytes of vertex and index buffers produced by tessellation per second.
+    pub fn tessellated_bytes_per_second(&self) -> f64 {
+        per_second(self.tessellated_bytes as f64, self.tessellation_time)
+    }
+
+    /// Mean frame preparation time.
+    pub fn mean_frame_time(&self) -> Duration {
+        if self.frame_times.is_empty() {
+            return Duration::ZERO;
+        }
+
+        self.frame_times.iter().sum::<Duration>() / self.frame_times.len() as u32
+    }
+
+    /// Frame time at the given percentile (`0.0..=1.0`), e.g. `0.99` for the 99th percentile.
+    pub fn frame_time_percentile(&self, percentile: f64) -> Duration {
+        let mut times = self.frame_times.clone();
+        times.sort();
+
+        let Some(last) = times.len().checked_sub(1) else {
+            return Duration::ZERO;
+        };
+        let index = (percentile.clamp(0.0, 1.0) * last as f64).round() as usize;
+        times[index]
+    }
+}
+
+fn per_second(value: f64, time: Duration) -> f64 {
+    if time.is_zero() {
+        return 0.0;
+    }
+
+    value / time.as_secs_f64()
+}
+
+/// Runs the decode and tessellation steps of the vector tile pipeline over synthetic tiles.
+pub struct TilePipelineBench {
+    source: SyntheticTileSource,
+    tile_schema: TileSchema,
+    style: VectorTileStyle,
+}
+
+impl TilePipelineBench {
+    /// Creates a new bench with the standard web tile schema and the [`SyntheticTileSource::style`].
+    pub fn new(source: SyntheticTileSource) -> Self {
+        let style = source.style();
+        Self {
+            source,
+            tile_schema: TileSchema::web(18),
+            style,
+        }
+    }
+
+    /// Tile source of the bench.
+    pub fn source(&self) -> &SyntheticTileSource {
+        &self.source
+    }
+
+    /// Tile schema of the bench.
+    pub fn tile_schema(&self) -> &TileSchema {
+        &self.tile_schema
+    }
+
+    /// Decodes a tile.
+    pub fn decode(&self, bytes: Bytes) -> Result<MvtTile, GalileoError> {
+        Ok(MvtTile::decode(bytes, false)?)
+    }
+
+    /// Tessellates a decoded tile into a new render bundle.
+    pub fn tessellate(
+        &self,
+        tile: &MvtTile,
+        index: TileIndex,
+    ) -> Result<RenderBundle, GalileoError> {
+        let mut bundle = RenderBundle(RenderBundleType::Tessellating(
+            TessellatingRenderBundle::new(),
+        ));
+        VtProcessor::prepare(tile, &mut bundle, index, &self.style, &self.tile_schema)?;
+
+        Ok(bundle)
+    }
+
+    /// Prepares all the frames of the script as a vector tile layer would: tiles that are visible in a frame and were
+    /// not prepared for previous frames are decoded and tessellated.
+    ///
+    /// Generation of the tile bytes is not included in the measured times.
+    pub fn run(&self, script: &ViewScript) -> Result<PipelineStats, GalileoError> {
+        let mut stats = PipelineStats::default();
+        let mut prepared = HashSet::new();
+
+        for view in script.views() {
+            let mut frame_time = Duration::ZERO;
+            let Some(tiles) = self.tile_schema.iter_tiles(view) else {
+                stats.frame_times.push(frame_time);
+                continue;
+            };
+
+            for index in tiles {
+                if !prepared.insert(index) {
+                    continue;
+                }
+
+                let bytes = self.source.tile_bytes(index);
+
+                let start = Instant::now();
+                let tile = self.decode(bytes)?;
+                let decoded = Instant::now();
+                let bundle = self.tessellate(&tile, index)?;
+                let tessellated = Instant::now();
+
+                stats.tiles_decoded += 1;
+                stats.decode_time += decoded - start;
+                stats.tessellation_time += tessellated - decoded;
+                stats.tessellated_bytes += bundle.approx_buffer_size();
+                frame_time += tessellated - start;
+            }
+
+            stats.frame_times.push(frame_time);
+        }
+
+        Ok(stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use galileo_types::cartesian::Size;
+    use galileo_types::latlon;
+
+    #[test]
+    fn synthetic_tiles_are_deterministic() {
+        let source = SyntheticTileSource::new(42, 10, 16);
+        let index = TileIndex::new(3, 5, 4);
+
+        assert_eq!(source.tile_bytes(index), source.tile_bytes(index));
+        assert_ne!(
+            source.tile_bytes(index),
+            source.tile_bytes(TileIndex::new(4, 5, 4))
+        );
+
+        let tile = MvtTile::decode(source.tile_bytes(index), false).unwrap();
+        assert_eq!(tile.layers.len(), 3);
+        assert!(tile.layers.iter().all(|layer| layer.features.len() == 10));
+    }
+
+    #[test]
+    fn run_fly_over() {
+        let bench = TilePipelineBench::new(SyntheticTileSource::new(1, 5, 8));
+        let start = MapView::new(&latlon!(50.0, 10.0), 150.0).with_size(Size::new(512.0, 512.0));
+        let script = ViewScript::fly_over(&start, 10);
+
+        let stats = bench.run(&script).unwrap();
+        assert_eq!(stats.frame_times.len(), 10);
+        assert!(stats.tiles_decoded > 0);
+        assert!(stats.tessellated_bytes > 0);
+        assert!(stats.frame_time_percentile(1.0) >= stats.frame_time_percentile(0.0));
+    }
+}
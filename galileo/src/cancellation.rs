@@ -0,0 +1,276 @@
+//! Cancellation of background work, see [`CancellationToken`].
+
+use crate::error::GalileoError;
+use futures::future::Either;
+use std::fmt::{Debug, Formatter};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::task::{Context, Poll, Waker};
+
+/// Token used to cancel asynchronous background work, e.g. loading and decoding of tiles.
+///
+/// Tokens form a hierarchy: cancelling a token also cancels all the tokens created with
+/// [`CancellationToken::child_token`] from it, their children and so on, but doesn't affect its parent. Layers own a
+/// token that is cancelled when the layer is dropped, and give child tokens to their tile providers, so that dropping
+/// a layer or a map stops all the work started for it.
+///
+/// Cancellation is cooperative: a task stops at its next `.await` point (see
+/// [`CancellationToken::run_until_cancelled`]) or when it checks [`CancellationToken::is_cancelled`].
+///
+/// Clones of a token are the same token: cancelling any of them cancels all of them.
+#[derive(Clone, Default)]
+pub struct CancellationToken {
+    inner: Arc<TokenInner>,
+}
+
+#[derive(Default)]
+struct TokenInner {
+    is_cancelled: AtomicBool,
+    state: Mutex<TokenState>,
+}
+
+#[derive(Default)]
+struct TokenState {
+    children: Vec<Weak<TokenInner>>,
+    waiters: Vec<(u64, Waker)>,
+}
+
+impl CancellationToken {
+    /// Creates a new token that is not cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a token that is cancelled when this token is cancelled. Cancelling the child token doesn't cancel
+    /// this token.
+    ///
+    /// If this token is already cancelled, the child token is created cancelled.
+    pub fn child_token(&self) -> Self {
+        let child = Self::new();
+        let mut state = self.inner.state.lock().expect("mutex is poisoned");
+        if self.is_cancelled() {
+            child.inner.is_cancelled.store(true, Ordering::Release);
+        } else {
+            state.children.retain(|child| child.strong_count() > 0);
+            state.children.push(Arc::downgrade(&child.inner));
+        }
+
+        child
+    }
+
+    /// Cancels the token and all its descendants, waking up all the tasks waiting for the cancellation.
+    pub fn cancel(&self) {
+        let mut to_cancel = vec![self.inner.clone()];
+        while let Some(inner) = to_cancel.pop() {
+            // The flag is set before the lock is taken, so a child created concurrently is either registered before
+            // the children are taken below or is created cancelled.
+            if inner.is_cancelled.swap(true, Ordering::AcqRel) {
+                continue;
+            }
+
+            let state = std::mem::take(&mut *inner.state.lock().expect("mutex is poisoned"));
+            for (_, waker) in state.waiters {
+                waker.wake();
+            }
+            to_cancel.extend(state.children.iter().filter_map(Weak::upgrade));
+        }
+    }
+
+    /// Returns `true` if the token or any of its ancestors was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.is_cancelled.load(Ordering::Acquire)
+    }
+
+    /// Returns [`GalileoError::Cancelled`] if the token was cancelled. Useful to stop synchronous work between its
+    /// steps with `?`.
+    pub fn check(&self) -> Result<(), GalileoError> {
+        if self.is_cancelled() {
+            Err(GalileoError::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Returns a future that completes when the token is cancelled.
+    pub fn cancelled(&self) -> WaitForCancellation {
+        WaitForCancellation {
+            token: self.clone(),
+            waiter_id: None,
+        }
+    }
+
+    /// Runs the future until it completes or the token is cancelled, whichever happens first. Returns `None` if the
+    /// token was cancelled. The future is dropped on cancellation without being polled again.
+    pub async fn run_until_cancelled<F: Future>(&self, future: F) -> Option<F::Output> {
+        if self.is_cancelled() {
+            return None;
+        }
+
+        let future = std::pin::pin!(future);
+        let cancelled = std::pin::pin!(self.cancelled());
+        match futures::future::select(future, cancelled).await {
+            Either::Left((output, _)) => Some(output),
+            Either::Right(_) => None,
+        }
+    }
+
+    /// Returns a guard that cancels the token when dropped.
+    pub fn drop_guard(self) -> CancelOnDrop {
+        CancelOnDrop { token: self }
+    }
+}
+
+impl Debug for CancellationToken {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CancellationToken")
+            .field("is_cancelled", &self.is_cancelled())
+            .finish()
+    }
+}
+
+/// Future returned by [`CancellationToken::cancelled`].
+pub struct WaitForCancellation {
+    token: CancellationToken,
+    waiter_id: Option<u64>,
+}
+
+impl Future for WaitForCancellation {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        static NEXT_WAITER_ID: AtomicU64 = AtomicU64::new(0);
+
+        if self.token.is_cancelled() {
+            return Poll::Ready(());
+        }
+
+        let waiter_id = *self
+            .waiter_id
+            .get_or_insert_with(|| NEXT_WAITER_ID.fetch_add(1, Ordering::Relaxed));
+        let mut state = self.token.inner.state.lock().expect("mutex is poisoned");
+        // The state is taken by `cancel` after the flag is set, so the check under the lock guarantees that the waker
+        // is either registered before the cancellation or not needed.
+        if self.token.is_cancelled() {
+            return Poll::Ready(());
+        }
+
+        match state.waiters.iter_mut().find(|(id, _)| *id == waiter_id) {
+            Some((_, waker)) => {
+                if !waker.will_wake(cx.waker()) {
+                    *waker = cx.waker().clone();
+                }
+            }
+            None => state.waiters.push((waiter_id, cx.waker().clone())),
+        }
+
+        Poll::Pending
+    }
+}
+
+impl Drop for WaitForCancellation {
+    fn drop(&mut self) {
+        if let Some(waiter_id) = self.waiter_id {
+            self.token
+                .inner
+                .state
+                .lock()
+                .expect("mutex is poisoned")
+                .waiters
+                .retain(|(id, _)| *id != waiter_id);
+        }
+    }
+}
+
+/// Guard that cancels the token when dropped. Created with [`CancellationToken::drop_guard`].
+#[derive(Debug)]
+pub struct CancelOnDrop {
+    token: CancellationToken,
+}
+
+impl CancelOnDrop {
+    /// The guarded token.
+    pub fn token(&self) -> &CancellationToken {
+        &self.token
+    }
+
+    /// Returns the token without cancelling it.
+    pub fn disarm(mut self) -> CancellationToken {
+        // The guard cancels the new unrelated token left in its place.
+        std::mem::take(&mut self.token)
+    }
+}
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.token.cancel();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cancels_descendants() {
+        let root = CancellationToken::new();
+        let child = root.child_token();
+        let grandchild = child.child_token();
+        let sibling = root.child_token();
+
+        child.cancel();
+        assert!(child.is_cancelled());
+        assert!(grandchild.is_cancelled());
+        assert!(!root.is_cancelled());
+        assert!(!sibling.is_cancelled());
+
+        root.cancel();
+        assert!(sibling.is_cancelled());
+        assert!(root.child_token().is_cancelled());
+        assert!(matches!(root.check(), Err(GalileoError::Cancelled)));
+    }
+
+    #[test]
+    fn drop_guard_cancels_token() {
+        let token = CancellationToken::new();
+        let child = token.child_token();
+        drop(token.clone().drop_guard());
+        assert!(child.is_cancelled());
+
+        let token = CancellationToken::new();
+        let token = token.drop_guard().disarm();
+        assert!(!token.is_cancelled());
+    }
+
+    #[test]
+    fn run_until_cancelled() {
+        use futures::executor::block_on;
+
+        let root = CancellationToken::new();
+        let token = root.child_token();
+        assert_eq!(block_on(token.run_until_cancelled(async { 42 })), Some(42));
+
+        let mut yielded = false;
+        let yield_once = futures::future::poll_fn(move |cx| {
+            if yielded {
+                Poll::Ready(42)
+            } else {
+                yielded = true;
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+        });
+        assert_eq!(block_on(token.run_until_cancelled(yield_once)), Some(42));
+        // Waiters of completed futures are removed, so that long-living tokens don't accumulate them.
+        assert!(token.inner.state.lock().unwrap().waiters.is_empty());
+
+        let cancel = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            root.cancel();
+        });
+        let output = block_on(token.run_until_cancelled(std::future::pending::<()>()));
+        assert_eq!(output, None);
+        cancel.join().unwrap();
+    }
+}
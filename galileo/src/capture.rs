@@ -0,0 +1,324 @@
+//! Offscreen capture of scripted camera flights, e.g. for promotional videos or visual QA of the map content.
+//!
+//! A [`CameraPath`] defines the views of the map at the given moments of time (keyframes). [`SequenceCapture`] renders
+//! the path at a fixed frame rate with a headless renderer and hands every frame to a callback, or writes the frames
+//! as a numbered PNG image sequence that can be converted into a video with any encoder (e.g.
+//! `ffmpeg -framerate 30 -i frame_%05d.png video.mp4`).
+//!
+//! ```no_run
+//! # use galileo::capture::{CameraPath, SequenceCapture};
+//! # use galileo_types::cartesian::Size;
+//! # use std::time::Duration;
+//! # async fn capture(map: &mut galileo::Map) -> Result<(), galileo::error::GalileoError> {
+//! let start = map.view().clone();
+//! let path = CameraPath::new(start.clone())
+//!     .with_keyframe(Duration::from_secs(3), start.with_resolution(start.resolution() / 16.0))
+//!     .with_keyframe(Duration::from_secs(5), start.with_resolution(start.resolution() / 16.0).with_rotation(0.8, 1.0));
+//! let frames_written = SequenceCapture::new(path, Size::new(1280, 720))
+//!     .with_frame_rate(30.0)
+//!     .capture_to_folder(map, "frames")
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::GalileoError;
+use crate::render::WgpuRenderer;
+use crate::view::MapView;
+use crate::Map;
+use galileo_types::cartesian::Size;
+use image::codecs::png::PngEncoder;
+use image::{ColorType, ImageEncoder};
+use std::path::Path;
+use std::time::Duration;
+use web_time::SystemTime;
+
+/// Default number of frames per second of the capture.
+const DEFAULT_FRAME_RATE: f64 = 30.0;
+
+/// Default time given to the layers to load their data for each frame.
+const DEFAULT_LOAD_WAIT: Duration = Duration::from_millis(500);
+
+/// View of the map at a moment of a [`CameraPath`].
+#[derive(Debug, Clone)]
+pub struct CameraKeyframe {
+    /// Time since the start of the path.
+    pub time: Duration,
+    /// View of the map at this time.
+    pub view: MapView,
+}
+
+/// Keyframed camera flight over the map.
+///
+/// Between the keyframes the position and rotation of the view are interpolated linearly, and the resolution is
+/// interpolated geometrically, so that zooming in and out looks uniform.
+#[derive(Debug, Clone)]
+pub struct CameraPath {
+    keyframes: Vec<CameraKeyframe>,
+}
+
+impl CameraPath {
+    /// Creates a new path starting with the given view.
+    pub fn new(start: MapView) -> Self {
+        Self {
+            keyframes: vec![CameraKeyframe {
+                time: Duration::ZERO,
+                view: start,
+            }],
+        }
+    }
+
+    /// Adds a keyframe to the path. If there already is a keyframe with the same time, it is replaced.
+    pub fn with_keyframe(mut self, time: Duration, view: MapView) -> Self {
+        let keyframe = CameraKeyframe { time, view };
+        match self.keyframes.binary_search_by_key(&time, |k| k.time) {
+            Ok(index) => self.keyframes[index] = keyframe,
+            Err(index) => self.keyframes.insert(index, keyframe),
+        }
+
+        self
+    }
+
+    /// Keyframes of the path, ordered by time.
+    pub fn keyframes(&self) -> &[CameraKeyframe] {
+        &self.keyframes
+    }
+
+    /// Time of the last keyframe of the path.
+    pub fn duration(&self) -> Duration {
+        self.keyframes.last().map(|k| k.time).unwrap_or_default()
+    }
+
+    /// Returns the view of the map at the given time since the start of the path. Before the first and after the last
+    /// keyframe the views of those keyframes are returned.
+    pub fn view_at(&self, time: Duration) -> MapView {
+        let next_index = self.keyframes.partition_point(|k| k.time <= time);
+        let Some(next) = self.keyframes.get(next_index) else {
+            return self.keyframes[self.keyframes.len() - 1].view.clone();
+        };
+        if next_index == 0 {
+            return next.view.clone();
+        }
+
+        let prev = &self.keyframes[next_index - 1];
+        let k = (time - prev.time).as_secs_f64() / (next.time - prev.time).as_secs_f64();
+        let lerp = |from: f64, to: f64| from + (to - from) * k;
+
+        let resolution =
+            (prev.view.resolution().ln() * (1.0 - k) + next.view.resolution().ln() * k).exp();
+        prev.view
+            .interpolate(&next.view, k)
+            .with_resolution(resolution)
+            .with_rotation(
+                lerp(prev.view.rotation_x(), next.view.rotation_x()),
+                lerp(prev.view.rotation_z(), next.view.rotation_z()),
+            )
+    }
+}
+
+/// A rendered frame of a [`SequenceCapture`].
+#[derive(Debug, Clone)]
+pub struct CapturedFrame {
+    /// Sequential number of the frame, starting from 0.
+    pub index: u32,
+    /// Time of the frame since the start of the camera path.
+    pub time: Duration,
+    /// Size of the frame in pixels.
+    pub size: Size<u32>,
+    /// Pixels of the frame in RGBA8 format, row by row from top to bottom.
+    pub rgba: Vec<u8>,
+}
+
+/// Renders a [`CameraPath`] offscreen at a fixed frame rate.
+///
+/// The layers of the map load their data asynchronously, so for every frame the capture calls [`Map::load_layers`]
+/// and then waits for [`SequenceCapture::with_load_wait`] before rendering it. Raster tile layers should have their
+/// fade in duration set to zero, otherwise the tiles may appear half-transparent in the frames.
+#[derive(Debug, Clone)]
+pub struct SequenceCapture {
+    path: CameraPath,
+    size: Size<u32>,
+    frame_rate: f64,
+    load_wait: Duration,
+}
+
+impl SequenceCapture {
+    /// Creates a new capture of the `path` with frames of the given size in pixels.
+    pub fn new(path: CameraPath, size: Size<u32>) -> Self {
+        Self {
+            path,
+            size,
+            frame_rate: DEFAULT_FRAME_RATE,
+            load_wait: DEFAULT_LOAD_WAIT,
+        }
+    }
+
+    /// Sets the number of frames per second. Default value is 30.
+    pub fn with_frame_rate(&self, frame_rate: f64) -> Self {
+        Self {
+            frame_rate,
+            ..self.clone()
+        }
+    }
+
+    /// Sets the time given to the layers to load their data before each frame is rendered.
+    pub fn with_load_wait(&self, load_wait: Duration) -> Self {
+        Self {
+            load_wait,
+            ..self.clone()
+        }
+    }
+
+    /// Number of frames in the capture, including the first and the last keyframes of the path.
+    pub fn frame_count(&self) -> u32 {
+        if self.frame_rate <= 0.0 {
+            return 1;
+        }
+
+        (self.path.duration().as_secs_f64() * self.frame_rate + 1e-9).floor() as u32 + 1
+    }
+
+    /// Time of the frame with the given index since the start of the path.
+    pub fn frame_time(&self, index: u32) -> Duration {
+        if self.frame_rate <= 0.0 {
+            return Duration::ZERO;
+        }
+
+        Duration::from_secs_f64(index as f64 / self.frame_rate)
+    }
+
+    /// Renders all frames of the capture and calls `on_frame` for each of them in order. If the callback returns an
+    /// error, the capture is stopped and the error is returned. Returns the number of rendered frames.
+    ///
+    /// The view of the map is changed during the capture and restored when it is done. The [clock](Map::clock) of the
+    /// map is advanced by the frame time for every frame instead of the system time, so animations created from the
+    /// clock look the same in every capture, no matter how long it takes to load and render the frames.
+    pub async fn capture(
+        &self,
+        map: &mut Map,
+        mut on_frame: impl FnMut(CapturedFrame) -> Result<(), GalileoError>,
+    ) -> Result<u32, GalileoError> {
+        let renderer = WgpuRenderer::new_with_texture_rt(self.size)
+            .await
+            .ok_or_else(|| GalileoError::Generic("failed to create headless renderer".into()))?;
+
+        let original_view = map.view().clone();
+        let result = self.render_frames(map, &renderer, &mut on_frame).await;
+        map.set_view(original_view);
+
+        result
+    }
+
+    /// Renders all frames of the capture and writes them into the `folder` as PNG images named `frame_00000.png`,
+    /// `frame_00001.png` and so on. The folder is created if it does not exist. Returns the number of written frames.
+    pub async fn capture_to_folder(
+        &self,
+        map: &mut Map,
+        folder: impl AsRef<Path>,
+    ) -> Result<u32, GalileoError> {
+        let folder = folder.as_ref();
+        std::fs::create_dir_all(folder)?;
+
+        self.capture(map, |frame| {
+            let png = encode_png(&frame.rgba, frame.size)?;
+            std::fs::write(folder.join(format!("frame_{:05}.png", frame.index)), png)?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn render_frames(
+        &self,
+        map: &mut Map,
+        renderer: &WgpuRenderer,
+        on_frame: &mut impl FnMut(CapturedFrame) -> Result<(), GalileoError>,
+    ) -> Result<u32, GalileoError> {
+        let start = map.clock().now();
+        let frame_count = self.frame_count();
+        for index in 0..frame_count {
+            let time = self.prepare_frame(map, start, index);
+            map.load_layers();
+            tokio::time::sleep(self.load_wait).await;
+
+            renderer
+                .render(map)
+                .map_err(|err| GalileoError::Generic(format!("failed to render frame: {err}")))?;
+            let rgba = renderer.get_image().await.map_err(|err| {
+                GalileoError::Generic(format!("failed to read frame image: {err}"))
+            })?;
+
+            on_frame(CapturedFrame {
+                index,
+                time,
+                size: self.size,
+                rgba,
+            })?;
+        }
+
+        Ok(frame_count)
+    }
+
+    /// Sets the view and the clock time of the map for the frame with the given index. Returns the frame time.
+    fn prepare_frame(&self, map: &mut Map, start: SystemTime, index: u32) -> Duration {
+        let time = self.frame_time(index);
+        map.clock().set_time(start + time);
+        map.set_view(self.path.view_at(time).with_size(self.size.cast()));
+
+        time
+    }
+}
+
+fn encode_png(rgba: &[u8], size: Size<u32>) -> Result<Vec<u8>, GalileoError> {
+    let mut bytes = vec![];
+    PngEncoder::new(&mut bytes)
+        .write_image(rgba, size.width(), size.height(), ColorType::Rgba8)
+        .map_err(|err| GalileoError::Generic(format!("failed to encode frame image: {err}")))?;
+
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+    use galileo_types::cartesian::Point2d;
+
+    fn path() -> CameraPath {
+        CameraPath::new(MapView::new_projected(&Point2d::new(0.0, 0.0), 100.0)).with_keyframe(
+            Duration::from_secs(2),
+            MapView::new_projected(&Point2d::new(200.0, 0.0), 1.0).with_rotation(0.0, 1.0),
+        )
+    }
+
+    #[test]
+    fn view_is_interpolated_between_keyframes() {
+        let view = path().view_at(Duration::from_secs(1));
+        assert_abs_diff_eq!(view.resolution(), 10.0, epsilon = 1e-9);
+        assert_abs_diff_eq!(view.rotation_z(), 0.5, epsilon = 1e-9);
+        assert_abs_diff_eq!(view.projected_position().expect("position is set").x, 100.0);
+
+        let end = path().view_at(Duration::from_secs(5));
+        assert_abs_diff_eq!(end.resolution(), 1.0);
+    }
+
+    #[test]
+    fn frame_count_includes_last_keyframe() {
+        let capture = SequenceCapture::new(path(), Size::new(10, 10)).with_frame_rate(30.0);
+        assert_eq!(capture.frame_count(), 61);
+        assert_eq!(capture.frame_time(60), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn clock_follows_frame_time() {
+        let capture = SequenceCapture::new(path(), Size::new(10, 10)).with_frame_rate(4.0);
+        let mut map = Map::new_detached(path().view_at(Duration::ZERO), vec![]);
+        let start = map.clock().now();
+
+        assert_eq!(
+            capture.prepare_frame(&mut map, start, 3),
+            Duration::from_millis(750)
+        );
+        assert_eq!(map.clock().now(), start + Duration::from_millis(750));
+        assert_eq!(map.view().size(), Size::new(10.0, 10.0));
+    }
+}
@@ -53,6 +53,20 @@ impl Color {
         ]
     }
 
+    /// Converts the color into f32 array with color channels converted from sRGB into linear space. Alpha channel is
+    /// not changed.
+    ///
+    /// Colors are always specified in sRGB, but render targets that encode colors themselves (e.g. sRGB textures)
+    /// expect the values in linear space.
+    pub fn to_linear_f32_array(&self) -> [f32; 4] {
+        [
+            srgb_to_linear(self.r),
+            srgb_to_linear(self.g),
+            srgb_to_linear(self.b),
+            self.a as f32 / 255.0,
+        ]
+    }
+
     /// Converts the color into u8 array (RGBA).
     pub fn to_u8_array(&self) -> [u8; 4] {
         [self.r, self.g, self.b, self.a]
@@ -113,6 +127,29 @@ impl Color {
     pub fn is_transparent(&self) -> bool {
         self.a == 0
     }
+
+    /// Linearly interpolates between this color and the `other` one. `k == 0.0` returns this color, `k == 1.0`
+    /// returns the `other`.
+    pub fn interpolate(&self, other: Color, k: f64) -> Self {
+        let k = k.clamp(0.0, 1.0);
+        let channel = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * k).round() as u8;
+
+        Self {
+            r: channel(self.r, other.r),
+            g: channel(self.g, other.g),
+            b: channel(self.b, other.b),
+            a: channel(self.a, other.a),
+        }
+    }
+}
+
+fn srgb_to_linear(channel: u8) -> f32 {
+    let v = channel as f32 / 255.0;
+    if v <= 0.04045 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
 }
 
 const fn decode_byte(chars: &[u8]) -> u8 {
@@ -144,4 +181,19 @@ mod tests {
 
         assert_eq!(Color::from_hex(&hex), color);
     }
+
+    #[test]
+    fn linear_conversion() {
+        assert_eq!(Color::WHITE.to_linear_f32_array(), [1.0, 1.0, 1.0, 1.0]);
+        assert_eq!(
+            Color::TRANSPARENT.to_linear_f32_array(),
+            [0.0, 0.0, 0.0, 0.0]
+        );
+
+        let [r, g, b, a] = Color::rgba(128, 128, 128, 128).to_linear_f32_array();
+        assert!((r - 0.2158).abs() < 1e-3);
+        assert_eq!(r, g);
+        assert_eq!(r, b);
+        assert!((a - 128.0 / 255.0).abs() < 1e-6);
+    }
 }
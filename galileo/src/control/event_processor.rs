@@ -1,6 +1,6 @@
 use crate::control::{
-    EventPropagation, MouseButton, MouseButtonsState, MouseEvent, RawUserEvent, TouchId, UserEvent,
-    UserEventHandler,
+    EventPropagation, Modifiers, MouseButton, MouseButtonsState, MouseEvent, RawUserEvent, TouchId,
+    UserEvent, UserEventHandler,
 };
 use crate::map::Map;
 use galileo_types::cartesian::{CartesianPoint2d, Point2d};
@@ -28,6 +28,7 @@ pub struct EventProcessor {
     touches: Vec<TouchInfo>,
 
     buttons_state: MouseButtonsState,
+    modifiers: Modifiers,
 
     last_pressed_time: SystemTime,
     last_click_time: SystemTime,
@@ -43,6 +44,7 @@ impl Default for EventProcessor {
             pointer_pressed_position: Default::default(),
             touches: Vec::new(),
             buttons_state: Default::default(),
+            modifiers: Default::default(),
             last_pressed_time: SystemTime::UNIX_EPOCH,
             last_click_time: SystemTime::UNIX_EPOCH,
             drag_target: None,
@@ -63,7 +65,12 @@ impl EventProcessor {
 
     /// Handles the event.
     pub fn handle(&mut self, event: RawUserEvent, map: &mut Map) {
-        if let Some(user_events) = self.process(event) {
+        self.handle_at(event, map, SystemTime::now());
+    }
+
+    /// Handles the event as if it happened at the given time. Used to replay recorded events deterministically.
+    pub(crate) fn handle_at(&mut self, event: RawUserEvent, map: &mut Map, now: SystemTime) {
+        if let Some(user_events) = self.process(event, now) {
             for user_event in user_events {
                 let mut drag_start_target = None;
 
@@ -114,8 +121,7 @@ impl EventProcessor {
         }
     }
 
-    fn process(&mut self, event: RawUserEvent) -> Option<Vec<UserEvent>> {
-        let now = SystemTime::now();
+    fn process(&mut self, event: RawUserEvent, now: SystemTime) -> Option<Vec<UserEvent>> {
         match event {
             RawUserEvent::ButtonPressed(button) => {
                 self.buttons_state.set_pressed(button);
@@ -271,6 +277,14 @@ impl EventProcessor {
 
                 Some(events)
             }
+            RawUserEvent::KeyPressed(key) => Some(vec![UserEvent::KeyPressed(key, self.modifiers)]),
+            RawUserEvent::ModifiersChanged(modifiers) => {
+                self.modifiers = modifiers;
+                None
+            }
+            RawUserEvent::ScaleFactorChanged(scale_factor) => {
+                Some(vec![UserEvent::ScaleFactorChanged(scale_factor)])
+            }
         }
     }
 
@@ -5,6 +5,10 @@ use nalgebra::Vector2;
 use std::time::Duration;
 
 const DEFAULT_ZOOM_DURATION: Duration = Duration::from_millis(50);
+const DEFAULT_SNAP_DURATION: Duration = Duration::from_millis(200);
+
+/// Relative tolerance used to decide if the view resolution is already at a zoom level.
+const SNAP_TOLERANCE: f64 = 1e-6;
 
 /// Event handler of a map, providing panning, zooming and tilting capabilities.
 #[derive(Default)]
@@ -12,11 +16,27 @@ pub struct MapController {
     parameters: MapControllerParameters,
 }
 
+/// Defines how [`MapController`] zooms the map with mouse wheel.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum ZoomSnapping {
+    /// The resolution of the map changes continuously by the wheel delta.
+    #[default]
+    Continuous,
+    /// Every wheel step moves the map to the next zoom level reported by the visible layers for the CRS of the map
+    /// (see [`Layer::zoom_levels`](crate::layer::Layer::zoom_levels)), so that e.g. raster tiles are always drawn
+    /// crisp. If none of the visible layers have preferred zoom levels, the zoom is continuous.
+    Layers,
+    /// Every wheel step moves the map to the next of the given resolutions.
+    Resolutions(Vec<f64>),
+}
+
 pub struct MapControllerParameters {
     zoom_duration: Duration,
     zoom_speed: f64,
     min_resolution: f64,
     max_resolution: f64,
+    zoom_snapping: ZoomSnapping,
+    snap_duration: Duration,
 
     rotation_speed: f64,
     max_rotation_x: f64,
@@ -29,6 +49,8 @@ impl Default for MapControllerParameters {
             zoom_speed: 0.2,
             max_resolution: 156543.03392800014 / 8.0,
             min_resolution: 156543.03392800014 / 8.0 / 2.0f64.powi(16),
+            zoom_snapping: ZoomSnapping::default(),
+            snap_duration: DEFAULT_SNAP_DURATION,
             rotation_speed: 0.005,
             max_rotation_x: 80f64.to_radians(),
         }
@@ -63,6 +85,20 @@ impl UserEventHandler for MapController {
                 _ => EventPropagation::Propagate,
             },
             UserEvent::Scroll(delta, mouse_event) => {
+                if let Some(levels) = self.zoom_levels(map) {
+                    let target_view = map.target_view();
+                    let current = target_view.resolution();
+                    let target = current * self.get_zoom(*delta, current);
+                    let snapped = snap_resolution(&levels, current, target);
+                    if snapped != current {
+                        let target = target_view
+                            .zoom(snapped / current, mouse_event.screen_pointer_position);
+                        map.animate_to(target, self.parameters.snap_duration);
+                    }
+
+                    return EventPropagation::Stop;
+                }
+
                 let zoom = self.get_zoom(*delta, map.view().resolution());
                 let target = map
                     .target_view()
@@ -77,12 +113,53 @@ impl UserEventHandler for MapController {
 
                 EventPropagation::Stop
             }
+            UserEvent::ScaleFactorChanged(scale_factor) => {
+                map.set_scale_factor(*scale_factor);
+
+                EventPropagation::Propagate
+            }
             _ => EventPropagation::Propagate,
         }
     }
 }
 
 impl MapController {
+    /// Sets the way the mouse wheel zooms the map. Default is [`ZoomSnapping::Continuous`].
+    pub fn with_zoom_snapping(mut self, zoom_snapping: ZoomSnapping) -> Self {
+        self.parameters.zoom_snapping = zoom_snapping;
+        self
+    }
+
+    /// Sets the duration of the animated transition between snapped zoom levels. Default value is 200 ms.
+    pub fn with_snap_duration(mut self, duration: Duration) -> Self {
+        self.parameters.snap_duration = duration;
+        self
+    }
+
+    /// Resolutions to snap the wheel zoom to, limited by the min and max resolution of the controller. `None` if the
+    /// zoom is continuous.
+    fn zoom_levels(&self, map: &Map) -> Option<Vec<f64>> {
+        let mut levels = match &self.parameters.zoom_snapping {
+            ZoomSnapping::Continuous => return None,
+            ZoomSnapping::Resolutions(resolutions) => resolutions.clone(),
+            ZoomSnapping::Layers => map
+                .layers()
+                .iter_visible()
+                .filter_map(|layer| layer.zoom_levels(map.view().crs()))
+                .flatten()
+                // Layers report resolutions per logical pixel, while the view resolution is per physical pixel.
+                .map(|resolution| resolution / map.view().scale_factor())
+                .collect(),
+        };
+
+        levels.retain(|resolution| {
+            *resolution >= self.parameters.min_resolution
+                && *resolution <= self.parameters.max_resolution
+        });
+
+        (!levels.is_empty()).then_some(levels)
+    }
+
     fn get_zoom(&self, delta: f64, current_resolution: f64) -> f64 {
         let zoom = (self.parameters.zoom_speed + 1.0).powf(-delta);
         let target_resolution = current_resolution * zoom;
@@ -110,3 +187,66 @@ impl MapController {
         curr_view.with_rotation(rotation_x, rotation_z)
     }
 }
+
+/// Selects the zoom level to move to from the `current` resolution, when the continuous zoom would change it to
+/// `target`. At least one level in the direction of the zoom is passed, and then the level closest to `target` is
+/// selected. Returns `current` if there are no levels in that direction.
+fn snap_resolution(levels: &[f64], current: f64, target: f64) -> f64 {
+    let zoom_in = target < current;
+    let candidates = levels.iter().copied().filter(|resolution| {
+        if zoom_in {
+            *resolution < current * (1.0 - SNAP_TOLERANCE)
+        } else {
+            *resolution > current * (1.0 + SNAP_TOLERANCE)
+        }
+    });
+
+    let distance = |resolution: f64| (resolution.ln() - target.ln()).abs();
+    let closest = candidates.min_by(|a, b| distance(*a).total_cmp(&distance(*b)));
+
+    match closest {
+        Some(resolution) if target != current => resolution,
+        _ => current,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::control::{EventProcessor, RawUserEvent};
+    use galileo_types::cartesian::Size;
+    use galileo_types::latlon;
+
+    const LEVELS: [f64; 4] = [8.0, 4.0, 2.0, 1.0];
+
+    #[test]
+    fn snap_moves_at_least_one_level() {
+        assert_eq!(snap_resolution(&LEVELS, 4.0, 3.9), 2.0);
+        assert_eq!(snap_resolution(&LEVELS, 4.0, 4.1), 8.0);
+        assert_eq!(snap_resolution(&LEVELS, 8.0, 1.1), 1.0);
+    }
+
+    #[test]
+    fn snap_from_between_levels() {
+        assert_eq!(snap_resolution(&LEVELS, 3.0, 2.9), 2.0);
+        assert_eq!(snap_resolution(&LEVELS, 3.0, 3.1), 4.0);
+    }
+
+    #[test]
+    fn snap_stops_at_last_level() {
+        assert_eq!(snap_resolution(&LEVELS, 1.0, 0.5), 1.0);
+        assert_eq!(snap_resolution(&LEVELS, 8.0, 8.0), 8.0);
+    }
+
+    #[test]
+    fn scale_factor_changed_rescales_view() {
+        let view = MapView::new(&latlon!(10.0, 20.0), 10.0).with_size(Size::new(100.0, 100.0));
+        let mut map = Map::new_detached(view, vec![]);
+        let mut processor = EventProcessor::default();
+        processor.add_handler(MapController::default());
+
+        processor.handle(RawUserEvent::ScaleFactorChanged(2.0), &mut map);
+        assert_eq!(map.view().resolution(), 5.0);
+        assert_eq!(map.view().logical_resolution(), 10.0);
+    }
+}
@@ -8,6 +8,8 @@
 //!    way to handle user interactions for the application.
 //! 3. `EventProcessor` has a list of [`UserEventHandler`]s, which change the state of application based on the events.
 //!
+//! User interactions can be recorded and replayed with the types from the [`recording`] module.
+//!
 //! To write a user interaction logic, the app must provide an implementation of [`UserEventHandler`] trait and add it
 //! to the `EventProcessor` handler list.
 
@@ -15,12 +17,15 @@ use crate::map::Map;
 use galileo_types::cartesian::Point2d;
 use maybe_sync::{MaybeSend, MaybeSync};
 use nalgebra::Vector2;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 mod event_processor;
 mod map;
+pub mod recording;
 
 pub use event_processor::EventProcessor;
-pub use map::MapController;
+pub use map::{MapController, ZoomSnapping};
 
 /// User input handler.
 pub trait UserEventHandler {
@@ -57,6 +62,13 @@ pub enum RawUserEvent {
     TouchMove(TouchEvent),
     /// Existing touch was released.
     TouchEnd(TouchEvent),
+    /// A keyboard key was pressed.
+    KeyPressed(Key),
+    /// State of the keyboard modifiers changed.
+    ModifiersChanged(Modifiers),
+    /// Number of physical pixels per logical pixel changed (e.g. the window was moved to another monitor or the
+    /// browser page was zoomed).
+    ScaleFactorChanged(f64),
 }
 
 /// User interaction event. This is the main type that the application would use through [`UserEventHandler`]s.
@@ -93,6 +105,13 @@ pub enum UserEvent {
     /// Zoom is called around a point. This is different from [`UserEvent::Scroll`], as it is not produced by a mouse
     /// but rather by multi-tough gestures. The first parameter is zoom delta value.
     Zoom(f64, Point2d),
+
+    /// A keyboard key was pressed. The second parameter is the state of the modifiers at the moment of the event.
+    KeyPressed(Key, Modifiers),
+
+    /// Number of physical pixels per logical pixel of the screen changed. [`MapController`] updates the
+    /// [scale factor](crate::view::MapView::scale_factor) of the map on this event and propagates it further.
+    ScaleFactorChanged(f64),
 }
 
 /// Value returned by an [`UserEventHandler`] to indicate the status of the event.
@@ -109,6 +128,7 @@ pub enum EventPropagation {
 
 /// Mouse button enum.
 #[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum MouseButton {
     /// The button you click when you want to shoot.
     Left,
@@ -120,6 +140,45 @@ pub enum MouseButton {
     Other,
 }
 
+/// Keyboard key.
+///
+/// Only the keys that are used to interact with the map are distinguished, all other keys are reported as
+/// [`Key::Other`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Key {
+    /// Tab key.
+    Tab,
+    /// Enter key.
+    Enter,
+    /// Space bar.
+    Space,
+    /// Escape key.
+    Escape,
+    /// Left arrow key.
+    ArrowLeft,
+    /// Right arrow key.
+    ArrowRight,
+    /// Up arrow key.
+    ArrowUp,
+    /// Down arrow key.
+    ArrowDown,
+    /// Any other key.
+    Other,
+}
+
+/// State of the keyboard modifiers.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Modifiers {
+    /// Shift key is pressed.
+    pub shift: bool,
+    /// Control key is pressed.
+    pub ctrl: bool,
+    /// Alt (Option) key is pressed.
+    pub alt: bool,
+}
+
 /// State of the mouse at the moment of the event.
 #[derive(Debug, Clone)]
 pub struct MouseEvent {
@@ -0,0 +1,442 @@
+//! Recording of user interactions and their deterministic playback.
+//!
+//! [`EventRecorder`] captures raw input events, map size changes and map views together with the time they happened
+//! at into an [`EventScript`]. With the `serde` feature the script can be serialized and attached to a bug report or
+//! stored as a demo or benchmark scenario.
+//!
+//! [`EventPlayer`] replays a script through an [`EventProcessor`]. The events are given to the processor with the
+//! recorded timestamps rather than the current time, so clicks, double clicks and drags are recognized exactly as
+//! they were during recording, regardless of how fast the script is played.
+
+use crate::control::{
+    EventProcessor, Key, Modifiers, MouseButton, RawUserEvent, TouchEvent, TouchId,
+};
+use crate::map::Map;
+use crate::view::MapView;
+use galileo_types::cartesian::{Point2d, Size};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use web_time::SystemTime;
+
+/// Recorded sequence of user interactions.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct EventScript {
+    /// Entries of the script, ordered by their offset.
+    pub entries: Vec<ScriptEntry>,
+}
+
+impl EventScript {
+    /// Total duration of the script.
+    pub fn duration(&self) -> Duration {
+        self.entries
+            .last()
+            .map(|entry| entry.offset)
+            .unwrap_or_default()
+    }
+}
+
+/// A single action of an [`EventScript`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ScriptEntry {
+    /// Time since the start of recording.
+    pub offset: Duration,
+    /// Recorded action.
+    pub action: ScriptAction,
+}
+
+/// Action recorded into an [`EventScript`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ScriptAction {
+    /// A mouse button was pressed.
+    ButtonPressed(MouseButton),
+    /// A mouse button was released.
+    ButtonReleased(MouseButton),
+    /// Mouse pointer was moved to the given screen pixel position.
+    PointerMoved {
+        /// X coordinate in pixels from the left edge of the map.
+        x: f64,
+        /// Y coordinate in pixels from the top edge of the map.
+        y: f64,
+    },
+    /// Scroll by the given number of lines.
+    Scroll(f64),
+    /// New touch started.
+    TouchStart(RecordedTouch),
+    /// Existing touch moved.
+    TouchMove(RecordedTouch),
+    /// Existing touch was released.
+    TouchEnd(RecordedTouch),
+    /// A keyboard key was pressed.
+    KeyPressed(Key),
+    /// State of the keyboard modifiers changed.
+    ModifiersChanged(Modifiers),
+    /// Number of physical pixels per logical pixel of the screen changed.
+    ScaleFactorChanged(f64),
+    /// Map was resized.
+    Resize {
+        /// New width in pixels.
+        width: f64,
+        /// New height in pixels.
+        height: f64,
+    },
+    /// Map view was changed.
+    SetView(RecordedView),
+}
+
+/// Touch event in an [`EventScript`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RecordedTouch {
+    /// Id of the touch.
+    pub id: TouchId,
+    /// X coordinate in pixels from the left edge of the map.
+    pub x: f64,
+    /// Y coordinate in pixels from the top edge of the map.
+    pub y: f64,
+}
+
+impl From<&TouchEvent> for RecordedTouch {
+    fn from(value: &TouchEvent) -> Self {
+        Self {
+            id: value.touch_id,
+            x: value.position.x,
+            y: value.position.y,
+        }
+    }
+}
+
+impl From<RecordedTouch> for TouchEvent {
+    fn from(value: RecordedTouch) -> Self {
+        Self {
+            touch_id: value.id,
+            position: Point2d::new(value.x, value.y),
+        }
+    }
+}
+
+/// Map view in an [`EventScript`]. Position is given in the projected coordinates of the map CRS.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RecordedView {
+    /// X coordinate of the map center.
+    pub x: f64,
+    /// Y coordinate of the map center.
+    pub y: f64,
+    /// Resolution of the map.
+    pub resolution: f64,
+    /// Rotation of the map around the x-axis.
+    pub rotation_x: f64,
+    /// Rotation of the map around the z-axis.
+    pub rotation_z: f64,
+}
+
+impl RecordedView {
+    /// Creates a record of the view. Returns `None` if the view position is not set.
+    pub fn from_view(view: &MapView) -> Option<Self> {
+        let position = view.projected_position()?;
+        Some(Self {
+            x: position.x,
+            y: position.y,
+            resolution: view.resolution(),
+            rotation_x: view.rotation_x(),
+            rotation_z: view.rotation_z(),
+        })
+    }
+
+    /// Creates a map view from the record. CRS and size are taken from the `base` view.
+    pub fn to_view(&self, base: &MapView) -> MapView {
+        MapView::new_projected_with_crs(
+            &Point2d::new(self.x, self.y),
+            self.resolution,
+            base.crs().clone(),
+        )
+        .with_rotation(self.rotation_x, self.rotation_z)
+        .with_size(base.size())
+    }
+}
+
+impl From<&RawUserEvent> for ScriptAction {
+    fn from(value: &RawUserEvent) -> Self {
+        match value {
+            RawUserEvent::ButtonPressed(button) => Self::ButtonPressed(*button),
+            RawUserEvent::ButtonReleased(button) => Self::ButtonReleased(*button),
+            RawUserEvent::PointerMoved(position) => Self::PointerMoved {
+                x: position.x,
+                y: position.y,
+            },
+            RawUserEvent::Scroll(delta) => Self::Scroll(*delta),
+            RawUserEvent::TouchStart(touch) => Self::TouchStart(touch.into()),
+            RawUserEvent::TouchMove(touch) => Self::TouchMove(touch.into()),
+            RawUserEvent::TouchEnd(touch) => Self::TouchEnd(touch.into()),
+            RawUserEvent::KeyPressed(key) => Self::KeyPressed(*key),
+            RawUserEvent::ModifiersChanged(modifiers) => Self::ModifiersChanged(*modifiers),
+            RawUserEvent::ScaleFactorChanged(scale_factor) => {
+                Self::ScaleFactorChanged(*scale_factor)
+            }
+        }
+    }
+}
+
+impl ScriptAction {
+    fn to_raw_event(&self) -> Option<RawUserEvent> {
+        Some(match self {
+            Self::ButtonPressed(button) => RawUserEvent::ButtonPressed(*button),
+            Self::ButtonReleased(button) => RawUserEvent::ButtonReleased(*button),
+            Self::PointerMoved { x, y } => RawUserEvent::PointerMoved(Point2d::new(*x, *y)),
+            Self::Scroll(delta) => RawUserEvent::Scroll(*delta),
+            Self::TouchStart(touch) => RawUserEvent::TouchStart((*touch).into()),
+            Self::TouchMove(touch) => RawUserEvent::TouchMove((*touch).into()),
+            Self::TouchEnd(touch) => RawUserEvent::TouchEnd((*touch).into()),
+            Self::KeyPressed(key) => RawUserEvent::KeyPressed(*key),
+            Self::ModifiersChanged(modifiers) => RawUserEvent::ModifiersChanged(*modifiers),
+            Self::ScaleFactorChanged(scale_factor) => {
+                RawUserEvent::ScaleFactorChanged(*scale_factor)
+            }
+            Self::Resize { .. } | Self::SetView(_) => return None,
+        })
+    }
+}
+
+/// Records user interactions into an [`EventScript`].
+///
+/// The application should give every raw event to the recorder before passing it to the [`EventProcessor`]. Map
+/// views can be recorded in addition to the events (e.g. once per frame), which allows to check that the replayed
+/// interactions result in the same views, and to replay view changes that are not caused by the input.
+pub struct EventRecorder {
+    start: SystemTime,
+    script: EventScript,
+    last_view: Option<RecordedView>,
+}
+
+impl Default for EventRecorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventRecorder {
+    /// Starts a new recording.
+    pub fn new() -> Self {
+        Self {
+            start: SystemTime::now(),
+            script: EventScript::default(),
+            last_view: None,
+        }
+    }
+
+    /// Records a raw input event.
+    pub fn record_event(&mut self, event: &RawUserEvent) {
+        self.push(event.into());
+    }
+
+    /// Records a change of the map size.
+    pub fn record_resize(&mut self, size: Size) {
+        self.push(ScriptAction::Resize {
+            width: size.width(),
+            height: size.height(),
+        });
+    }
+
+    /// Records the map view. Nothing is recorded if the view did not change since the last recorded view.
+    pub fn record_view(&mut self, view: &MapView) {
+        let Some(view) = RecordedView::from_view(view) else {
+            return;
+        };
+
+        if self.last_view != Some(view) {
+            self.last_view = Some(view);
+            self.push(ScriptAction::SetView(view));
+        }
+    }
+
+    /// Script recorded so far.
+    pub fn script(&self) -> &EventScript {
+        &self.script
+    }
+
+    /// Stops the recording and returns the script.
+    pub fn finish(self) -> EventScript {
+        self.script
+    }
+
+    fn push(&mut self, action: ScriptAction) {
+        let offset = SystemTime::now()
+            .duration_since(self.start)
+            .unwrap_or_default();
+        self.script.entries.push(ScriptEntry { offset, action });
+    }
+}
+
+/// Replays an [`EventScript`].
+///
+/// The player does not measure time itself: the application tells it how much time has passed since the start of
+/// the playback with [`EventPlayer::play_until`], or plays the whole script at once with [`EventPlayer::play_all`].
+/// Either way the result is the same, as the events are processed with their recorded timestamps.
+pub struct EventPlayer {
+    script: EventScript,
+    next_entry: usize,
+    base_time: SystemTime,
+}
+
+impl EventPlayer {
+    /// Creates a new player for the script.
+    pub fn new(script: EventScript) -> Self {
+        Self {
+            script,
+            next_entry: 0,
+            base_time: SystemTime::now(),
+        }
+    }
+
+    /// Returns true if all entries of the script were played.
+    pub fn is_finished(&self) -> bool {
+        self.next_entry >= self.script.entries.len()
+    }
+
+    /// Plays all the entries with offset not greater than `elapsed`, that were not played yet. Returns true if the
+    /// script is finished.
+    pub fn play_until(
+        &mut self,
+        elapsed: Duration,
+        processor: &mut EventProcessor,
+        map: &mut Map,
+    ) -> bool {
+        while let Some(entry) = self.script.entries.get(self.next_entry) {
+            if entry.offset > elapsed {
+                break;
+            }
+
+            let time = self.base_time + entry.offset;
+            match &entry.action {
+                ScriptAction::Resize { width, height } => map.set_size(Size::new(*width, *height)),
+                ScriptAction::SetView(view) => {
+                    let view = view.to_view(map.view());
+                    map.set_view(view);
+                }
+                action => {
+                    if let Some(event) = action.to_raw_event() {
+                        processor.handle_at(event, map, time);
+                    }
+                }
+            }
+
+            self.next_entry += 1;
+        }
+
+        self.is_finished()
+    }
+
+    /// Plays all the remaining entries of the script.
+    pub fn play_all(&mut self, processor: &mut EventProcessor, map: &mut Map) {
+        self.play_until(Duration::MAX, processor, map);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::control::{EventPropagation, UserEvent};
+    use galileo_types::geo::GeoPoint;
+    use galileo_types::latlon;
+    use std::sync::{Arc, Mutex};
+
+    fn entry(offset_ms: u64, action: ScriptAction) -> ScriptEntry {
+        ScriptEntry {
+            offset: Duration::from_millis(offset_ms),
+            action,
+        }
+    }
+
+    fn test_map() -> Map {
+        let view = MapView::new(&latlon!(0.0, 0.0), 100.0).with_size(Size::new(200.0, 100.0));
+        Map::new(view, vec![], None::<crate::DummyMessenger>)
+    }
+
+    #[test]
+    fn playback_uses_recorded_timestamps() {
+        let script = EventScript {
+            entries: vec![
+                entry(0, ScriptAction::PointerMoved { x: 10.0, y: 10.0 }),
+                entry(0, ScriptAction::ButtonPressed(MouseButton::Left)),
+                entry(100, ScriptAction::ButtonReleased(MouseButton::Left)),
+                entry(200, ScriptAction::ButtonPressed(MouseButton::Left)),
+                entry(300, ScriptAction::ButtonReleased(MouseButton::Left)),
+                // too slow to be a click
+                entry(1000, ScriptAction::ButtonPressed(MouseButton::Left)),
+                entry(2000, ScriptAction::ButtonReleased(MouseButton::Left)),
+            ],
+        };
+
+        let events = Arc::new(Mutex::new(vec![]));
+        let mut processor = EventProcessor::default();
+        let handler_events = events.clone();
+        processor.add_handler(move |event: &UserEvent, _map: &mut Map| {
+            let name = match event {
+                UserEvent::Click(..) => "click",
+                UserEvent::DoubleClick(..) => "double_click",
+                _ => return EventPropagation::Propagate,
+            };
+            handler_events.lock().unwrap().push(name);
+            EventPropagation::Propagate
+        });
+
+        let mut map = test_map();
+        let mut player = EventPlayer::new(script);
+        assert!(!player.play_until(Duration::from_millis(150), &mut processor, &mut map));
+        assert_eq!(*events.lock().unwrap(), vec!["click"]);
+
+        player.play_all(&mut processor, &mut map);
+        assert!(player.is_finished());
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec!["click", "click", "double_click"]
+        );
+    }
+
+    #[test]
+    fn view_is_restored() {
+        let mut recorder = EventRecorder::new();
+        let view = MapView::new(&latlon!(10.0, 20.0), 50.0).with_rotation(0.5, 1.0);
+        recorder.record_view(&view);
+        recorder.record_view(&view);
+        recorder.record_resize(Size::new(300.0, 300.0));
+
+        let script = recorder.finish();
+        assert_eq!(script.entries.len(), 2);
+
+        let mut map = test_map();
+        EventPlayer::new(script).play_all(&mut EventProcessor::default(), &mut map);
+
+        let position = map.view().position().unwrap();
+        assert!((position.lat() - 10.0).abs() < 1e-6);
+        assert!((position.lon() - 20.0).abs() < 1e-6);
+        assert_eq!(map.view().resolution(), 50.0);
+        assert_eq!(map.view().rotation_z(), 1.0);
+        assert_eq!(map.view().size(), Size::new(300.0, 300.0));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn script_serialization() {
+        let script = EventScript {
+            entries: vec![
+                entry(5, ScriptAction::Scroll(-1.0)),
+                entry(
+                    10,
+                    ScriptAction::TouchStart(RecordedTouch {
+                        id: 1,
+                        x: 1.0,
+                        y: 2.0,
+                    }),
+                ),
+            ],
+        };
+
+        let json = serde_json::to_string(&script).unwrap();
+        assert_eq!(serde_json::from_str::<EventScript>(&json).unwrap(), script);
+    }
+}
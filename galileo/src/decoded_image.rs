@@ -14,7 +14,7 @@ use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::fmt::Formatter;
 
 /// An image that has been loaded into memory.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct DecodedImage {
     /// Raw bytes of the image, in RGBA order.
     pub(crate) bytes: Vec<u8>,
@@ -0,0 +1,308 @@
+//! Decoding of elevation data (digital elevation models) from raster tiles.
+//!
+//! Elevation is distributed by different providers in different formats. Most common are raster tiles with the
+//! elevation value encoded into the color channels of the image (e.g. Mapbox Terrain-RGB or Terrarium), but some
+//! providers use their own binary formats (e.g. LERC).
+//!
+//! To make terrain functionality independent of the provider, all formats are decoded into an [`ElevationGrid`] by an
+//! [`ElevationDecoder`]:
+//! * for image based formats, use [`ImageElevationDecoder`] with an [`ElevationEncoding`], e.g. one of the
+//!   predefined [`LinearEncoding`]s;
+//! * for other formats, implement [`ElevationDecoder`] trait directly.
+//!
+//! [`DemProcessor`] wraps any decoder into a [`DataProcessor`], so elevation tiles can be loaded with any data
+//! provider that uses data processors, e.g. [`UrlDataProvider`](crate::layer::data_provider::UrlDataProvider).
+
+use crate::decoded_image::DecodedImage;
+use crate::error::GalileoError;
+use crate::layer::data_provider::DataProcessor;
+use bytes::Bytes;
+use maybe_sync::{MaybeSend, MaybeSync};
+
+/// Rectangular grid of elevation values, usually corresponding to one tile.
+///
+/// Values are stored row by row starting from the top left corner of the tile. Cells without data contain `NaN`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ElevationGrid {
+    width: u32,
+    height: u32,
+    values: Vec<f32>,
+}
+
+impl ElevationGrid {
+    /// Creates a new grid. Returns an error if the number of values does not correspond to the grid size.
+    pub fn new(width: u32, height: u32, values: Vec<f32>) -> Result<Self, GalileoError> {
+        if values.len() != width as usize * height as usize {
+            return Err(GalileoError::Generic(
+                "invalid elevation grid dimensions for values count".into(),
+            ));
+        }
+
+        Ok(Self {
+            width,
+            height,
+            values,
+        })
+    }
+
+    /// Decodes elevation values from the pixels of the image using the given encoding.
+    pub fn from_image(image: &DecodedImage, encoding: &(impl ElevationEncoding + ?Sized)) -> Self {
+        let values = image
+            .bytes()
+            .chunks_exact(4)
+            .map(|pixel| {
+                encoding
+                    .decode_pixel([pixel[0], pixel[1], pixel[2], pixel[3]])
+                    .unwrap_or(f32::NAN)
+            })
+            .collect();
+
+        Self {
+            width: image.width(),
+            height: image.height(),
+            values,
+        }
+    }
+
+    /// Width of the grid.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Height of the grid.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Elevation values of the grid.
+    pub fn values(&self) -> &[f32] {
+        &self.values
+    }
+
+    /// Returns the elevation of the cell at the given column and row, or `None` if the cell is outside of the grid or
+    /// has no data.
+    pub fn get(&self, x: u32, y: u32) -> Option<f32> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        let value = self.values[(y * self.width + x) as usize];
+        (!value.is_nan()).then_some(value)
+    }
+
+    /// Returns bilinearly interpolated elevation at the given relative position in the grid, where `(0.0, 0.0)` is
+    /// the top left corner and `(1.0, 1.0)` is the bottom right corner of the grid.
+    ///
+    /// Returns `None` if the position is outside of the grid or if any of the neighbouring cells has no data.
+    pub fn sample(&self, u: f64, v: f64) -> Option<f32> {
+        if !(0.0..=1.0).contains(&u) || !(0.0..=1.0).contains(&v) || self.values.is_empty() {
+            return None;
+        }
+
+        let x = u * (self.width - 1) as f64;
+        let y = v * (self.height - 1) as f64;
+
+        let x0 = x.floor() as u32;
+        let y0 = y.floor() as u32;
+        let x1 = (x0 + 1).min(self.width - 1);
+        let y1 = (y0 + 1).min(self.height - 1);
+
+        let dx = (x - x0 as f64) as f32;
+        let dy = (y - y0 as f64) as f32;
+
+        let top = self.get(x0, y0)? * (1.0 - dx) + self.get(x1, y0)? * dx;
+        let bottom = self.get(x0, y1)? * (1.0 - dx) + self.get(x1, y1)? * dx;
+
+        Some(top * (1.0 - dy) + bottom * dy)
+    }
+
+    /// Returns minimum and maximum elevation values in the grid, or `None` if the grid has no data.
+    pub fn min_max(&self) -> Option<(f32, f32)> {
+        self.values
+            .iter()
+            .filter(|v| !v.is_nan())
+            .fold(None, |acc, &v| match acc {
+                None => Some((v, v)),
+                Some((min, max)) => Some((min.min(v), max.max(v))),
+            })
+    }
+}
+
+/// Decodes raw tile data into an [`ElevationGrid`].
+pub trait ElevationDecoder: MaybeSend + MaybeSync {
+    /// Decodes the data.
+    fn decode(&self, bytes: Bytes) -> Result<ElevationGrid, GalileoError>;
+}
+
+/// Encoding of the elevation value in the color channels of an image pixel.
+pub trait ElevationEncoding: MaybeSend + MaybeSync {
+    /// Returns the elevation encoded in the given RGBA pixel, or `None` if the pixel contains no data.
+    fn decode_pixel(&self, pixel: [u8; 4]) -> Option<f32>;
+}
+
+/// Encoding where the elevation is a linear combination of color channels:
+///
+/// `elevation = r * weights[0] + g * weights[1] + b * weights[2] + a * weights[3] + offset`
+///
+/// Pixels with zero alpha channel are treated as having no data.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct LinearEncoding {
+    /// Weights of the `r`, `g`, `b` and `a` channels.
+    pub weights: [f64; 4],
+    /// Value added to the weighted sum of channels.
+    pub offset: f64,
+}
+
+impl LinearEncoding {
+    /// Mapbox Terrain-RGB encoding: `-10000 + (r * 256 * 256 + g * 256 + b) * 0.1`.
+    pub fn mapbox_rgb() -> Self {
+        Self {
+            weights: [256.0 * 256.0 * 0.1, 256.0 * 0.1, 0.1, 0.0],
+            offset: -10000.0,
+        }
+    }
+
+    /// Terrarium encoding: `r * 256 + g + b / 256 - 32768`.
+    pub fn terrarium() -> Self {
+        Self {
+            weights: [256.0, 1.0, 1.0 / 256.0, 0.0],
+            offset: -32768.0,
+        }
+    }
+
+    /// Encoding that uses a single channel of the image with the given scale and offset.
+    pub fn single_channel(channel: usize, scale: f64, offset: f64) -> Self {
+        let mut weights = [0.0; 4];
+        if let Some(weight) = weights.get_mut(channel) {
+            *weight = scale;
+        }
+
+        Self { weights, offset }
+    }
+}
+
+impl ElevationEncoding for LinearEncoding {
+    fn decode_pixel(&self, pixel: [u8; 4]) -> Option<f32> {
+        if pixel[3] == 0 {
+            return None;
+        }
+
+        let value = pixel
+            .iter()
+            .zip(self.weights)
+            .fold(self.offset, |acc, (&channel, weight)| {
+                acc + channel as f64 * weight
+            });
+
+        Some(value as f32)
+    }
+}
+
+/// Decodes elevation from image tiles (PNG, WebP etc.) with the given [`ElevationEncoding`].
+#[derive(Debug, Clone)]
+pub struct ImageElevationDecoder<E> {
+    encoding: E,
+}
+
+impl<E: ElevationEncoding> ImageElevationDecoder<E> {
+    /// Creates a new decoder.
+    pub fn new(encoding: E) -> Self {
+        Self { encoding }
+    }
+
+    /// Encoding used by the decoder.
+    pub fn encoding(&self) -> &E {
+        &self.encoding
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<E: ElevationEncoding> ElevationDecoder for ImageElevationDecoder<E> {
+    fn decode(&self, bytes: Bytes) -> Result<ElevationGrid, GalileoError> {
+        let image = DecodedImage::new(&bytes)?;
+        Ok(ElevationGrid::from_image(&image, &self.encoding))
+    }
+}
+
+/// Data processor that decodes elevation tiles with the given [`ElevationDecoder`].
+pub struct DemProcessor {
+    decoder: Box<dyn ElevationDecoder>,
+}
+
+impl DemProcessor {
+    /// Creates a new processor.
+    pub fn new(decoder: impl ElevationDecoder + 'static) -> Self {
+        Self {
+            decoder: Box::new(decoder),
+        }
+    }
+}
+
+impl DataProcessor for DemProcessor {
+    type Input = Bytes;
+    type Output = ElevationGrid;
+    type Context = ();
+
+    fn process(&self, input: Bytes, _context: ()) -> Result<ElevationGrid, GalileoError> {
+        self.decoder.decode(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn mapbox_rgb_encoding() {
+        let encoding = LinearEncoding::mapbox_rgb();
+        assert_abs_diff_eq!(
+            encoding.decode_pixel([1, 134, 160, 255]).unwrap(),
+            0.0,
+            epsilon = 0.001
+        );
+        assert_abs_diff_eq!(
+            encoding.decode_pixel([1, 134, 170, 255]).unwrap(),
+            1.0,
+            epsilon = 0.001
+        );
+        assert_eq!(encoding.decode_pixel([1, 134, 170, 0]), None);
+    }
+
+    #[test]
+    fn terrarium_encoding() {
+        let encoding = LinearEncoding::terrarium();
+        assert_eq!(encoding.decode_pixel([128, 0, 0, 255]), Some(0.0));
+        assert_eq!(encoding.decode_pixel([128, 100, 128, 255]), Some(100.5));
+    }
+
+    #[test]
+    fn grid_from_image() {
+        let image = DecodedImage::from_raw(
+            vec![128, 0, 0, 255, 128, 10, 0, 255, 0, 0, 0, 0, 128, 30, 0, 255],
+            2,
+            2,
+        )
+        .unwrap();
+        let grid = ElevationGrid::from_image(&image, &LinearEncoding::terrarium());
+
+        assert_eq!(grid.get(0, 0), Some(0.0));
+        assert_eq!(grid.get(1, 0), Some(10.0));
+        assert_eq!(grid.get(0, 1), None);
+        assert_eq!(grid.get(2, 0), None);
+        assert_eq!(grid.min_max(), Some((0.0, 30.0)));
+    }
+
+    #[test]
+    fn grid_sample() {
+        let grid = ElevationGrid::new(2, 2, vec![0.0, 10.0, 20.0, 30.0]).unwrap();
+
+        assert_eq!(grid.sample(0.0, 0.0), Some(0.0));
+        assert_eq!(grid.sample(1.0, 1.0), Some(30.0));
+        assert_eq!(grid.sample(0.5, 0.5), Some(15.0));
+        assert_eq!(grid.sample(1.5, 0.5), None);
+
+        let grid = ElevationGrid::new(2, 1, vec![0.0, f32::NAN]).unwrap();
+        assert_eq!(grid.sample(0.5, 0.0), None);
+    }
+}
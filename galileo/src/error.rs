@@ -29,6 +29,9 @@ pub enum GalileoError {
     /// Error reading/writing data to the FS.
     #[error("failed to read file")]
     FsIo,
+    /// Operation was cancelled with a [`CancellationToken`](crate::CancellationToken).
+    #[error("operation was cancelled")]
+    Cancelled,
 }
 
 #[cfg(not(target_arch = "wasm32"))]
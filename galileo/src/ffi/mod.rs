@@ -0,0 +1,1004 @@
+//! C interface of the library, used to embed Galileo into applications written in other languages (C, C++/Qt,
+//! Swift and others).
+//!
+//! The C header for this module is `galileo/include/galileo.h`. It is generated from this module with
+//! [cbindgen](https://github.com/mozilla/cbindgen) and must be regenerated when the interface changes:
+//!
+//! ```sh
+//! cbindgen --config galileo/cbindgen.toml --crate galileo --output galileo/include/galileo.h
+//! ```
+//!
+//! A typical application:
+//!
+//! ```c
+//! GalileoMap *map = galileo_map_new(52.52, 13.40, 150.0, width, height);
+//! galileo_map_attach_window(map, GALILEO_WINDOW_KIND_WIN32, hwnd, NULL, width, height);
+//! galileo_map_add_raster_tile_layer(map, "https://tile.openstreetmap.org/{z}/{x}/{y}.png");
+//!
+//! // in the event loop of the application
+//! GalileoEvent event;
+//! while (galileo_map_poll_event(map, &event)) {
+//!     if (event.kind == GALILEO_EVENT_KIND_REDRAW_REQUESTED) {
+//!         galileo_map_render(map);
+//!     }
+//! }
+//!
+//! galileo_map_free(map);
+//! ```
+//!
+//! All the functions taking a [`GalileoMap`] must be called from the same thread, except for the redraw callback set
+//! with [`galileo_map_set_redraw_callback`], which is called from the background threads loading the layer data.
+//! Every map owns an async runtime that loads the layer data in background.
+
+use std::collections::VecDeque;
+use std::ffi::{c_char, c_void, CStr};
+use std::num::{NonZeroIsize, NonZeroU32};
+use std::panic::AssertUnwindSafe;
+use std::ptr::NonNull;
+use std::sync::{Arc, Mutex};
+
+use galileo_types::cartesian::{CartesianPoint2d, Point2d, Size};
+use galileo_types::geo::{Crs, GeoPoint};
+use galileo_types::geometry::Geometry;
+use galileo_types::geometry_type::GeoSpace2d;
+use galileo_types::latlon;
+use raw_window_handle::{
+    AppKitDisplayHandle, AppKitWindowHandle, DisplayHandle, HandleError, HasDisplayHandle,
+    HasWindowHandle, RawDisplayHandle, RawWindowHandle, UiKitDisplayHandle, UiKitWindowHandle,
+    WaylandDisplayHandle, WaylandWindowHandle, Win32WindowHandle, WindowHandle,
+    WindowsDisplayHandle, XcbDisplayHandle, XcbWindowHandle, XlibDisplayHandle, XlibWindowHandle,
+};
+
+use crate::control::{
+    EventProcessor, EventPropagation, MapController, MouseButton, RawUserEvent, UserEvent,
+};
+use crate::layer::data_provider::UrlImageProvider;
+use crate::layer::feature_layer::symbol::ArbitraryGeometrySymbol;
+use crate::layer::{FeatureLayer, Layer, RasterTileLayer};
+use crate::messenger::Messenger;
+use crate::render::WgpuRenderer;
+use crate::tile_scheme::{TileIndex, TileSchema};
+use crate::{LayerId, Map, MapView};
+
+/// Value of [`GalileoEvent::layer_id`] for the events that are not caused by a layer, e.g. a click outside of all
+/// features. Ids of layers are never equal to this value.
+pub const GALILEO_NO_LAYER: u64 = 0;
+
+/// Distance in pixels from a feature at which a click still hits the feature.
+const CLICK_TOLERANCE: f64 = 3.0;
+
+/// Layer created by [`galileo_map_add_geojson_layer`].
+type GeoJsonLayer = FeatureLayer<
+    <geojson::Geometry as Geometry>::Point,
+    geojson::Feature,
+    ArbitraryGeometrySymbol,
+    GeoSpace2d,
+>;
+
+/// Map with everything needed to draw it to a window and to handle user input. Created with [`galileo_map_new`] and
+/// destroyed with [`galileo_map_free`].
+pub struct GalileoMap {
+    map: Map,
+    renderer: Option<WgpuRenderer>,
+    event_processor: EventProcessor,
+    events: Arc<EventQueue>,
+    runtime: tokio::runtime::Runtime,
+}
+
+/// Result of a call of the C interface.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GalileoStatus {
+    /// The call succeeded.
+    Ok = 0,
+    /// One of the arguments is a null pointer or has invalid value.
+    InvalidArgument = 1,
+    /// The map is not attached to a window (see [`galileo_map_attach_window`]).
+    NoWindow = 2,
+    /// The GPU renderer failed.
+    RendererError = 3,
+    /// The call failed because of an internal error of the library. The map may be left in an inconsistent state.
+    InternalError = 4,
+}
+
+/// Windowing system of the window handle given to [`galileo_map_attach_window`].
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GalileoWindowKind {
+    /// Windows `HWND`. The display pointer is ignored.
+    Win32 = 0,
+    /// X11 `Window` id (cast to a pointer) with the Xlib `Display*` as the display pointer.
+    Xlib = 1,
+    /// XCB `xcb_window_t` id (cast to a pointer) with the `xcb_connection_t*` as the display pointer.
+    Xcb = 2,
+    /// Wayland `wl_surface*` with the `wl_display*` as the display pointer.
+    Wayland = 3,
+    /// macOS `NSView*`. The display pointer is ignored.
+    AppKit = 4,
+    /// iOS `UIView*`. The display pointer is ignored.
+    UiKit = 5,
+}
+
+/// Mouse button of an input event.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GalileoMouseButton {
+    /// Left button.
+    Left = 0,
+    /// Middle button.
+    Middle = 1,
+    /// Right button.
+    Right = 2,
+    /// Any other button.
+    Other = 3,
+}
+
+/// Kind of a [`GalileoEvent`].
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GalileoEventKind {
+    /// The map must be redrawn with [`galileo_map_render`].
+    RedrawRequested = 0,
+    /// The map was clicked with the left mouse button. The position of the event is set.
+    Click = 1,
+}
+
+/// Event reported by a map, returned by [`galileo_map_poll_event`].
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct GalileoEvent {
+    /// Kind of the event.
+    pub kind: GalileoEventKind,
+    /// Horizontal screen position of the event in pixels, or 0.
+    pub x: f64,
+    /// Vertical screen position of the event in pixels, or 0.
+    pub y: f64,
+    /// Latitude of the event position, or `NaN` if the event has no position.
+    pub lat: f64,
+    /// Longitude of the event position, or `NaN` if the event has no position.
+    pub lon: f64,
+    /// Id of the layer that caused the event (as returned by the `galileo_map_add_*_layer` functions), or
+    /// [`GALILEO_NO_LAYER`] if the event is not caused by a layer. For a click it is the id of the topmost visible
+    /// layer with a feature under the pointer.
+    pub layer_id: u64,
+}
+
+impl GalileoEvent {
+    fn new(kind: GalileoEventKind) -> Self {
+        Self {
+            kind,
+            x: 0.0,
+            y: 0.0,
+            lat: f64::NAN,
+            lon: f64::NAN,
+            layer_id: GALILEO_NO_LAYER,
+        }
+    }
+}
+
+/// Function called when the map must be redrawn, with the user data given to [`galileo_map_set_redraw_callback`].
+pub type GalileoRedrawCallback = extern "C" fn(user_data: *mut c_void);
+
+#[derive(Default)]
+struct EventQueue {
+    events: Mutex<VecDeque<GalileoEvent>>,
+    redraw_callback: Mutex<Option<RedrawCallback>>,
+}
+
+#[derive(Copy, Clone)]
+struct RedrawCallback {
+    callback: GalileoRedrawCallback,
+    user_data: *mut c_void,
+}
+
+// SAFETY: the user data is never dereferenced by the library, and the caller of `galileo_map_set_redraw_callback`
+// guarantees that the callback can be called from any thread.
+unsafe impl Send for RedrawCallback {}
+
+impl EventQueue {
+    fn push(&self, event: GalileoEvent) {
+        let mut events = self.events.lock().expect("mutex is poisoned");
+        if event.kind == GalileoEventKind::RedrawRequested
+            && events.iter().any(|queued| {
+                queued.kind == GalileoEventKind::RedrawRequested
+                    && queued.layer_id == event.layer_id
+            })
+        {
+            return;
+        }
+
+        events.push_back(event);
+    }
+
+    fn pop(&self) -> Option<GalileoEvent> {
+        self.events.lock().expect("mutex is poisoned").pop_front()
+    }
+
+    fn push_redraw(&self, layer_id: u64) {
+        self.push(GalileoEvent {
+            layer_id,
+            ..GalileoEvent::new(GalileoEventKind::RedrawRequested)
+        });
+
+        let callback = *self.redraw_callback.lock().expect("mutex is poisoned");
+        if let Some(RedrawCallback {
+            callback,
+            user_data,
+        }) = callback
+        {
+            callback(user_data);
+        }
+    }
+}
+
+impl Messenger for EventQueue {
+    fn request_redraw(&self) {
+        self.push_redraw(GALILEO_NO_LAYER);
+    }
+
+    fn request_layer_redraw(&self, layer: LayerId) {
+        self.push_redraw(layer.to_raw());
+    }
+}
+
+/// Raw handles of a window created by the application.
+struct RawWindow {
+    window: RawWindowHandle,
+    display: RawDisplayHandle,
+}
+
+// SAFETY: the caller of `galileo_map_attach_window` guarantees that the window is valid while the map is attached to
+// it. The handles are used only by the GPU backend, that is responsible for accessing the window from the correct
+// thread.
+unsafe impl Send for RawWindow {}
+// SAFETY: see above.
+unsafe impl Sync for RawWindow {}
+
+impl HasWindowHandle for RawWindow {
+    fn window_handle(&self) -> Result<WindowHandle<'_>, HandleError> {
+        // SAFETY: the window is valid while it is attached to the map.
+        Ok(unsafe { WindowHandle::borrow_raw(self.window) })
+    }
+}
+
+impl HasDisplayHandle for RawWindow {
+    fn display_handle(&self) -> Result<DisplayHandle<'_>, HandleError> {
+        // SAFETY: the display is valid while the window is attached to the map.
+        Ok(unsafe { DisplayHandle::borrow_raw(self.display) })
+    }
+}
+
+impl RawWindow {
+    fn new(kind: GalileoWindowKind, window: *mut c_void, display: *mut c_void) -> Option<Self> {
+        let (window, display) = match kind {
+            GalileoWindowKind::Win32 => (
+                RawWindowHandle::Win32(Win32WindowHandle::new(NonZeroIsize::new(window as isize)?)),
+                RawDisplayHandle::Windows(WindowsDisplayHandle::new()),
+            ),
+            GalileoWindowKind::Xlib => (
+                RawWindowHandle::Xlib(XlibWindowHandle::new(window as usize as _)),
+                RawDisplayHandle::Xlib(XlibDisplayHandle::new(Some(NonNull::new(display)?), 0)),
+            ),
+            GalileoWindowKind::Xcb => (
+                RawWindowHandle::Xcb(XcbWindowHandle::new(NonZeroU32::new(
+                    u32::try_from(window as usize).ok()?,
+                )?)),
+                RawDisplayHandle::Xcb(XcbDisplayHandle::new(Some(NonNull::new(display)?), 0)),
+            ),
+            GalileoWindowKind::Wayland => (
+                RawWindowHandle::Wayland(WaylandWindowHandle::new(NonNull::new(window)?)),
+                RawDisplayHandle::Wayland(WaylandDisplayHandle::new(NonNull::new(display)?)),
+            ),
+            GalileoWindowKind::AppKit => (
+                RawWindowHandle::AppKit(AppKitWindowHandle::new(NonNull::new(window)?)),
+                RawDisplayHandle::AppKit(AppKitDisplayHandle::new()),
+            ),
+            GalileoWindowKind::UiKit => (
+                RawWindowHandle::UiKit(UiKitWindowHandle::new(NonNull::new(window)?)),
+                RawDisplayHandle::UiKit(UiKitDisplayHandle::new()),
+            ),
+        };
+
+        Some(Self { window, display })
+    }
+}
+
+impl GalileoMap {
+    fn add_layer(&mut self, layer: impl Layer + 'static) -> u64 {
+        let layers = self.map.layers_mut();
+        let id = layers.push(layer);
+        layers.set_layer_messenger(id, self.events.clone());
+        self.map.redraw();
+
+        id.to_raw()
+    }
+
+    fn handle_input(&mut self, event: RawUserEvent) {
+        let _guard = self.runtime.enter();
+        self.event_processor.handle(event, &mut self.map);
+    }
+}
+
+/// Returns the id of the topmost visible feature layer that has a feature at the given screen position.
+fn layer_at(map: &Map, position: Point2d) -> Option<LayerId> {
+    let view = map.view();
+    let point = view.screen_to_map(position)?;
+    let tolerance = view.resolution() * CLICK_TOLERANCE;
+    let layers = map.layers();
+    let ids: Vec<_> = layers.ids().collect();
+
+    // Layers are drawn in the order of the collection, so the last one is on top.
+    ids.into_iter().rev().find(|&id| {
+        layers.visibility(id) == Some(true)
+            && layers
+                .get_by_id(id)
+                .and_then(|layer| layer.as_any().downcast_ref::<GeoJsonLayer>())
+                .is_some_and(|layer| {
+                    layer
+                        .get_features_at_projected(&point, view.crs(), tolerance)
+                        .next()
+                        .is_some()
+                })
+    })
+}
+
+/// Runs the body of a function of the C interface, returning `on_panic` if the body panics. Unwinding into the code of
+/// the application is undefined behavior, so every function of the interface must be wrapped into this.
+fn guard<T>(on_panic: T, body: impl FnOnce() -> T) -> T {
+    match std::panic::catch_unwind(AssertUnwindSafe(body)) {
+        Ok(value) => value,
+        Err(_) => {
+            log::error!("Galileo C interface call panicked");
+            on_panic
+        }
+    }
+}
+
+/// Returns true if the position (in degrees) and resolution can be set to a map view.
+fn is_valid_view(lat: f64, lon: f64, resolution: f64) -> bool {
+    (-90.0..=90.0).contains(&lat)
+        && (-180.0..=180.0).contains(&lon)
+        && resolution.is_finite()
+        && resolution > 0.0
+}
+
+/// # Safety
+///
+/// The pointer must be null or returned by `galileo_map_new` and not freed.
+unsafe fn map_ref<'a>(map: *mut GalileoMap) -> Option<&'a mut GalileoMap> {
+    map.as_mut()
+}
+
+/// # Safety
+///
+/// The pointer must be null or point to a null-terminated string.
+unsafe fn str_ref<'a>(string: *const c_char) -> Option<&'a str> {
+    if string.is_null() {
+        return None;
+    }
+
+    CStr::from_ptr(string).to_str().ok()
+}
+
+/// Creates a new map centered at the given point (in degrees) with the given resolution (in meters per pixel) and
+/// size (in pixels). Returns null if the arguments are not valid or the background runtime of the map cannot be
+/// started.
+///
+/// The map must be destroyed with [`galileo_map_free`].
+#[no_mangle]
+pub extern "C" fn galileo_map_new(
+    lat: f64,
+    lon: f64,
+    resolution: f64,
+    width: u32,
+    height: u32,
+) -> *mut GalileoMap {
+    guard(std::ptr::null_mut(), || {
+        if !is_valid_view(lat, lon, resolution) {
+            return std::ptr::null_mut();
+        }
+
+        let runtime = match tokio::runtime::Runtime::new() {
+            Ok(runtime) => runtime,
+            Err(err) => {
+                log::error!("Failed to start async runtime: {err}");
+                return std::ptr::null_mut();
+            }
+        };
+
+        let events = Arc::new(EventQueue::default());
+        let view = MapView::new(&latlon!(lat, lon), resolution)
+            .with_size(Size::new(width as f64, height as f64));
+        let mut map = Map::new_detached(view, vec![]);
+        map.set_messenger(Some(events.clone()));
+
+        let mut event_processor = EventProcessor::default();
+        let click_events = events.clone();
+        event_processor.add_handler(move |event: &UserEvent, map: &mut Map| {
+            if let UserEvent::Click(MouseButton::Left, mouse_event) = event {
+                let position = mouse_event.screen_pointer_position;
+                let location = map.view().screen_to_map_geo(position);
+                click_events.push(GalileoEvent {
+                    kind: GalileoEventKind::Click,
+                    x: position.x(),
+                    y: position.y(),
+                    lat: location.map_or(f64::NAN, |p| p.lat()),
+                    lon: location.map_or(f64::NAN, |p| p.lon()),
+                    layer_id: layer_at(map, position).map_or(GALILEO_NO_LAYER, LayerId::to_raw),
+                });
+            }
+
+            EventPropagation::Propagate
+        });
+        event_processor.add_handler(MapController::default());
+
+        Box::into_raw(Box::new(GalileoMap {
+            map,
+            renderer: None,
+            event_processor,
+            events,
+            runtime,
+        }))
+    })
+}
+
+/// Destroys the map created by [`galileo_map_new`].
+///
+/// # Safety
+///
+/// The pointer must be null or returned by `galileo_map_new`, and must not be used after this call.
+#[no_mangle]
+pub unsafe extern "C" fn galileo_map_free(map: *mut GalileoMap) {
+    guard((), || {
+        if !map.is_null() {
+            drop(Box::from_raw(map));
+        }
+    })
+}
+
+/// Attaches the map to a window created by the application. The map is drawn to the window by
+/// [`galileo_map_render`]. The size must be the size of the window in physical pixels.
+///
+/// See [`GalileoWindowKind`] for the meaning of the `window` and `display` pointers on each platform.
+///
+/// # Safety
+///
+/// `map` must be returned by `galileo_map_new`. The window and display must stay valid until the map is freed or
+/// attached to another window.
+#[no_mangle]
+pub unsafe extern "C" fn galileo_map_attach_window(
+    map: *mut GalileoMap,
+    kind: GalileoWindowKind,
+    window: *mut c_void,
+    display: *mut c_void,
+    width: u32,
+    height: u32,
+) -> GalileoStatus {
+    guard(GalileoStatus::InternalError, || {
+        let Some(map) = map_ref(map) else {
+            return GalileoStatus::InvalidArgument;
+        };
+        let Some(raw_window) = RawWindow::new(kind, window, display) else {
+            return GalileoStatus::InvalidArgument;
+        };
+
+        let size = Size::new(width, height);
+        let renderer = map
+            .runtime
+            .block_on(WgpuRenderer::new_with_window(Arc::new(raw_window), size));
+        let Some(renderer) = renderer else {
+            return GalileoStatus::RendererError;
+        };
+
+        map.renderer = Some(renderer);
+        map.map.set_size(Size::new(width as f64, height as f64));
+        map.map.redraw();
+
+        GalileoStatus::Ok
+    })
+}
+
+/// Sets the size of the map and of its window surface in physical pixels. Must be called when the window is resized.
+///
+/// # Safety
+///
+/// `map` must be returned by `galileo_map_new`.
+#[no_mangle]
+pub unsafe extern "C" fn galileo_map_resize(
+    map: *mut GalileoMap,
+    width: u32,
+    height: u32,
+) -> GalileoStatus {
+    guard(GalileoStatus::InternalError, || {
+        let Some(map) = map_ref(map) else {
+            return GalileoStatus::InvalidArgument;
+        };
+
+        if let Some(renderer) = &mut map.renderer {
+            renderer.resize(Size::new(width, height));
+        }
+
+        map.map.set_size(Size::new(width as f64, height as f64));
+        GalileoStatus::Ok
+    })
+}
+
+/// Sets the number of physical pixels per logical pixel of the window.
+///
+/// # Safety
+///
+/// `map` must be returned by `galileo_map_new`.
+#[no_mangle]
+pub unsafe extern "C" fn galileo_map_set_scale_factor(
+    map: *mut GalileoMap,
+    scale_factor: f64,
+) -> GalileoStatus {
+    guard(GalileoStatus::InternalError, || {
+        let Some(map) = map_ref(map) else {
+            return GalileoStatus::InvalidArgument;
+        };
+
+        map.map.set_scale_factor(scale_factor);
+        GalileoStatus::Ok
+    })
+}
+
+/// Draws the map to its window.
+///
+/// # Safety
+///
+/// `map` must be returned by `galileo_map_new`.
+#[no_mangle]
+pub unsafe extern "C" fn galileo_map_render(map: *mut GalileoMap) -> GalileoStatus {
+    guard(GalileoStatus::InternalError, || {
+        let Some(map) = map_ref(map) else {
+            return GalileoStatus::InvalidArgument;
+        };
+        let Some(renderer) = &map.renderer else {
+            return GalileoStatus::NoWindow;
+        };
+
+        let _guard = map.runtime.enter();
+        map.map.animate();
+        map.map.load_layers();
+        match renderer.render(&map.map) {
+            Ok(()) => GalileoStatus::Ok,
+            Err(err) => {
+                log::error!("Render error: {err:?}");
+                GalileoStatus::RendererError
+            }
+        }
+    })
+}
+
+/// Moves the map to the given point (in degrees) and resolution (in meters per pixel).
+///
+/// # Safety
+///
+/// `map` must be returned by `galileo_map_new`.
+#[no_mangle]
+pub unsafe extern "C" fn galileo_map_set_view(
+    map: *mut GalileoMap,
+    lat: f64,
+    lon: f64,
+    resolution: f64,
+) -> GalileoStatus {
+    guard(GalileoStatus::InternalError, || {
+        let Some(map) = map_ref(map) else {
+            return GalileoStatus::InvalidArgument;
+        };
+        if !is_valid_view(lat, lon, resolution) {
+            return GalileoStatus::InvalidArgument;
+        }
+
+        let view = map
+            .map
+            .view()
+            .with_position(&latlon!(lat, lon))
+            .with_resolution(resolution);
+        map.map.set_view(view);
+
+        GalileoStatus::Ok
+    })
+}
+
+/// Adds a raster tile layer to the top of the map and returns its id, or 0 on error. The tiles are loaded from the
+/// URL template with `{z}`, `{x}` and `{y}` placeholders, e.g. `https://tile.openstreetmap.org/{z}/{x}/{y}.png`.
+///
+/// # Safety
+///
+/// `map` must be returned by `galileo_map_new`, `url_template` must be a null-terminated UTF-8 string.
+#[no_mangle]
+pub unsafe extern "C" fn galileo_map_add_raster_tile_layer(
+    map: *mut GalileoMap,
+    url_template: *const c_char,
+) -> u64 {
+    guard(0, || {
+        let (Some(map), Some(url_template)) = (map_ref(map), str_ref(url_template)) else {
+            return 0;
+        };
+
+        let url_template = url_template.to_string();
+        let tile_provider = UrlImageProvider::new(move |index: &TileIndex| {
+            url_template
+                .replace("{z}", &index.z.to_string())
+                .replace("{x}", &index.x.to_string())
+                .replace("{y}", &index.y.to_string())
+        });
+
+        map.add_layer(RasterTileLayer::new(
+            TileSchema::web(18),
+            tile_provider,
+            None,
+        ))
+    })
+}
+
+/// Adds a layer with the features of a GeoJSON `FeatureCollection` to the top of the map and returns its id, or 0 if
+/// the GeoJSON cannot be parsed.
+///
+/// # Safety
+///
+/// `map` must be returned by `galileo_map_new`, `geojson` must be a null-terminated UTF-8 string.
+#[no_mangle]
+pub unsafe extern "C" fn galileo_map_add_geojson_layer(
+    map: *mut GalileoMap,
+    geojson: *const c_char,
+) -> u64 {
+    guard(0, || {
+        let (Some(map), Some(geojson)) = (map_ref(map), str_ref(geojson)) else {
+            return 0;
+        };
+
+        let collection = match geojson
+            .parse::<geojson::GeoJson>()
+            .and_then(geojson::FeatureCollection::try_from)
+        {
+            Ok(collection) => collection,
+            Err(err) => {
+                log::error!("Failed to parse GeoJSON: {err}");
+                return 0;
+            }
+        };
+
+        map.add_layer(FeatureLayer::new(
+            collection.features,
+            ArbitraryGeometrySymbol::default(),
+            Crs::WGS84,
+        ))
+    })
+}
+
+/// Removes the layer with the given id from the map. Returns false if the map has no such layer.
+///
+/// # Safety
+///
+/// `map` must be returned by `galileo_map_new`.
+#[no_mangle]
+pub unsafe extern "C" fn galileo_map_remove_layer(map: *mut GalileoMap, layer_id: u64) -> bool {
+    guard(false, || {
+        let Some(map) = map_ref(map) else {
+            return false;
+        };
+
+        let removed = map
+            .map
+            .layers_mut()
+            .remove_by_id(LayerId::from_raw(layer_id))
+            .is_some();
+        map.map.redraw();
+        removed
+    })
+}
+
+/// Shows or hides the layer with the given id. Returns false if the map has no such layer.
+///
+/// # Safety
+///
+/// `map` must be returned by `galileo_map_new`.
+#[no_mangle]
+pub unsafe extern "C" fn galileo_map_set_layer_visible(
+    map: *mut GalileoMap,
+    layer_id: u64,
+    visible: bool,
+) -> bool {
+    guard(false, || {
+        let Some(map) = map_ref(map) else {
+            return false;
+        };
+
+        let changed = map
+            .map
+            .layers_mut()
+            .set_visible(LayerId::from_raw(layer_id), visible);
+        map.map.redraw();
+        changed
+    })
+}
+
+/// Sets opacity of the layer with the given id from 0 to 1. Returns false if the map has no such layer.
+///
+/// # Safety
+///
+/// `map` must be returned by `galileo_map_new`.
+#[no_mangle]
+pub unsafe extern "C" fn galileo_map_set_layer_opacity(
+    map: *mut GalileoMap,
+    layer_id: u64,
+    opacity: f32,
+) -> bool {
+    guard(false, || {
+        let Some(map) = map_ref(map) else {
+            return false;
+        };
+
+        let changed = map
+            .map
+            .layers_mut()
+            .set_opacity(LayerId::from_raw(layer_id), opacity);
+        map.map.redraw();
+        changed
+    })
+}
+
+/// Passes the new position of the mouse pointer (in pixels from the top-left corner of the window) to the map.
+///
+/// # Safety
+///
+/// `map` must be returned by `galileo_map_new`.
+#[no_mangle]
+pub unsafe extern "C" fn galileo_map_pointer_moved(
+    map: *mut GalileoMap,
+    x: f64,
+    y: f64,
+) -> GalileoStatus {
+    guard(GalileoStatus::InternalError, || {
+        let Some(map) = map_ref(map) else {
+            return GalileoStatus::InvalidArgument;
+        };
+
+        map.handle_input(RawUserEvent::PointerMoved(Point2d::new(x, y)));
+        GalileoStatus::Ok
+    })
+}
+
+/// Passes a mouse button press or release to the map.
+///
+/// # Safety
+///
+/// `map` must be returned by `galileo_map_new`.
+#[no_mangle]
+pub unsafe extern "C" fn galileo_map_mouse_button(
+    map: *mut GalileoMap,
+    button: GalileoMouseButton,
+    pressed: bool,
+) -> GalileoStatus {
+    guard(GalileoStatus::InternalError, || {
+        let Some(map) = map_ref(map) else {
+            return GalileoStatus::InvalidArgument;
+        };
+
+        let button = match button {
+            GalileoMouseButton::Left => MouseButton::Left,
+            GalileoMouseButton::Middle => MouseButton::Middle,
+            GalileoMouseButton::Right => MouseButton::Right,
+            GalileoMouseButton::Other => MouseButton::Other,
+        };
+        let event = if pressed {
+            RawUserEvent::ButtonPressed(button)
+        } else {
+            RawUserEvent::ButtonReleased(button)
+        };
+
+        map.handle_input(event);
+        GalileoStatus::Ok
+    })
+}
+
+/// Passes a scroll of the mouse wheel to the map. `lines` is the number of text lines the event would scroll,
+/// positive values zoom the map in.
+///
+/// # Safety
+///
+/// `map` must be returned by `galileo_map_new`.
+#[no_mangle]
+pub unsafe extern "C" fn galileo_map_scroll(map: *mut GalileoMap, lines: f64) -> GalileoStatus {
+    guard(GalileoStatus::InternalError, || {
+        let Some(map) = map_ref(map) else {
+            return GalileoStatus::InvalidArgument;
+        };
+
+        map.handle_input(RawUserEvent::Scroll(lines));
+        GalileoStatus::Ok
+    })
+}
+
+/// Takes the next event of the map. Returns false if there are no events. Events should be polled after every call
+/// to the map and when the redraw callback is called.
+///
+/// # Safety
+///
+/// `map` must be returned by `galileo_map_new`, `event` must point to a writable `GalileoEvent`.
+#[no_mangle]
+pub unsafe extern "C" fn galileo_map_poll_event(
+    map: *mut GalileoMap,
+    event: *mut GalileoEvent,
+) -> bool {
+    guard(false, || {
+        let Some(map) = map_ref(map) else {
+            return false;
+        };
+        if event.is_null() {
+            return false;
+        }
+
+        let Some(next) = map.events.pop() else {
+            return false;
+        };
+
+        event.write(next);
+        true
+    })
+}
+
+/// Sets a function that is called every time the map needs to be redrawn, e.g. to wake up the event loop of the
+/// application. A `RedrawRequested` event is also added to the event queue. Pass null callback to remove it.
+///
+/// # Safety
+///
+/// `map` must be returned by `galileo_map_new`. The callback must be safe to call from any thread with the given
+/// user data while it is set.
+#[no_mangle]
+pub unsafe extern "C" fn galileo_map_set_redraw_callback(
+    map: *mut GalileoMap,
+    callback: Option<GalileoRedrawCallback>,
+    user_data: *mut c_void,
+) -> GalileoStatus {
+    guard(GalileoStatus::InternalError, || {
+        let Some(map) = map_ref(map) else {
+            return GalileoStatus::InvalidArgument;
+        };
+
+        *map.events
+            .redraw_callback
+            .lock()
+            .expect("mutex is poisoned") = callback.map(|callback| RedrawCallback {
+            callback,
+            user_data,
+        });
+        GalileoStatus::Ok
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+
+    #[test]
+    fn invalid_map_arguments() {
+        assert!(galileo_map_new(f64::NAN, 13.0, 100.0, 200, 100).is_null());
+        assert!(galileo_map_new(52.0, 200.0, 100.0, 200, 100).is_null());
+        assert!(galileo_map_new(52.0, 13.0, 0.0, 200, 100).is_null());
+        assert!(galileo_map_new(52.0, 13.0, f64::INFINITY, 200, 100).is_null());
+    }
+
+    #[test]
+    fn panics_do_not_unwind() {
+        assert_eq!(
+            guard(GalileoStatus::InternalError, || panic!("test panic")),
+            GalileoStatus::InternalError
+        );
+    }
+
+    #[test]
+    fn map_without_window() {
+        let map = galileo_map_new(52.0, 13.0, 100.0, 200, 100);
+        assert!(!map.is_null());
+
+        unsafe {
+            assert_eq!(galileo_map_render(map), GalileoStatus::NoWindow);
+            assert_eq!(
+                galileo_map_set_view(map, 10.0, 20.0, -1.0),
+                GalileoStatus::InvalidArgument
+            );
+            assert_eq!(
+                galileo_map_set_view(map, 100.0, 20.0, 50.0),
+                GalileoStatus::InvalidArgument
+            );
+            assert_eq!(
+                galileo_map_set_view(map, 10.0, 20.0, 50.0),
+                GalileoStatus::Ok
+            );
+
+            let geojson = CString::new(
+                r#"{"type":"FeatureCollection","features":[{"type":"Feature","properties":{},"geometry":{"type":"Point","coordinates":[20.0,10.0]}}]}"#,
+            )
+            .expect("no zero bytes");
+            let id = galileo_map_add_geojson_layer(map, geojson.as_ptr());
+            assert_ne!(id, 0);
+
+            let invalid = CString::new("{").expect("no zero bytes");
+            assert_eq!(galileo_map_add_geojson_layer(map, invalid.as_ptr()), 0);
+
+            let mut event = GalileoEvent::new(GalileoEventKind::Click);
+            assert!(galileo_map_poll_event(map, &mut event));
+            assert_eq!(event.kind, GalileoEventKind::RedrawRequested);
+            assert_eq!(event.layer_id, 0);
+            assert!(!galileo_map_poll_event(map, &mut event));
+
+            let events = (*map).events.clone();
+            events.request_layer_redraw(LayerId::from_raw(id));
+            events.request_layer_redraw(LayerId::from_raw(id));
+            assert!(galileo_map_poll_event(map, &mut event));
+            assert_eq!(event.kind, GalileoEventKind::RedrawRequested);
+            assert_eq!(event.layer_id, id);
+            assert!(!galileo_map_poll_event(map, &mut event));
+
+            galileo_map_pointer_moved(map, 100.0, 50.0);
+            galileo_map_mouse_button(map, GalileoMouseButton::Left, true);
+            galileo_map_mouse_button(map, GalileoMouseButton::Left, false);
+            let mut events = vec![];
+            while galileo_map_poll_event(map, &mut event) {
+                events.push(event);
+            }
+            let click = events
+                .iter()
+                .find(|event| event.kind == GalileoEventKind::Click)
+                .expect("click is reported");
+            assert!((click.lat - 10.0).abs() < 1e-6);
+            assert!((click.lon - 20.0).abs() < 1e-6);
+            assert_eq!(click.layer_id, id);
+
+            assert!(galileo_map_set_layer_visible(map, id, false));
+            assert!(galileo_map_remove_layer(map, id));
+            assert!(!galileo_map_remove_layer(map, id));
+
+            galileo_map_free(map);
+        }
+    }
+
+    unsafe fn click(map: *mut GalileoMap, x: f64, y: f64) -> GalileoEvent {
+        galileo_map_pointer_moved(map, x, y);
+        galileo_map_mouse_button(map, GalileoMouseButton::Left, true);
+        galileo_map_mouse_button(map, GalileoMouseButton::Left, false);
+
+        let mut event = GalileoEvent::new(GalileoEventKind::RedrawRequested);
+        let mut click = None;
+        while galileo_map_poll_event(map, &mut event) {
+            if event.kind == GalileoEventKind::Click {
+                click = Some(event);
+            }
+        }
+
+        click.expect("click is reported")
+    }
+
+    #[test]
+    fn click_reports_layer_id() {
+        let map = galileo_map_new(0.0, 0.0, 1000.0, 200, 100);
+        let feature = |lon: f64| {
+            CString::new(format!(
+                r#"{{"type":"FeatureCollection","features":[{{"type":"Feature","properties":{{}},"geometry":{{"type":"Point","coordinates":[{lon},0.0]}}}}]}}"#,
+            ))
+            .expect("no zero bytes")
+        };
+
+        unsafe {
+            let lower = galileo_map_add_geojson_layer(map, feature(0.0).as_ptr());
+            let upper = galileo_map_add_geojson_layer(map, feature(0.0).as_ptr());
+            let side = galileo_map_add_geojson_layer(map, feature(0.5).as_ptr());
+
+            assert_eq!(click(map, 100.0, 50.0).layer_id, upper);
+            assert_eq!(click(map, 10.0, 10.0).layer_id, GALILEO_NO_LAYER);
+
+            // 0.5 degrees of longitude is about 55 pixels at this resolution.
+            assert_eq!(click(map, 155.0, 50.0).layer_id, side);
+
+            assert!(galileo_map_set_layer_visible(map, upper, false));
+            assert_eq!(click(map, 100.0, 50.0).layer_id, lower);
+
+            galileo_map_free(map);
+        }
+    }
+}
@@ -7,11 +7,11 @@ use crate::render::WgpuRenderer;
 use crate::tile_scheme::{TileIndex, TileSchema};
 use crate::view::MapView;
 use crate::winit::{WinitInputHandler, WinitMessenger};
-use crate::Messenger;
 use galileo_types::cartesian::Size;
 use galileo_types::geo::impls::GeoPoint2d;
 use maybe_sync::{MaybeSend, MaybeSync};
 use std::sync::{Arc, RwLock};
+use web_time::SystemTime;
 use winit::application::ApplicationHandler;
 use winit::dpi::PhysicalSize;
 use winit::event::WindowEvent;
@@ -34,6 +34,8 @@ pub struct GalileoMap {
     event_processor: EventProcessor,
     input_handler: WinitInputHandler,
     event_loop: Option<EventLoop<()>>,
+    #[cfg(target_arch = "wasm32")]
+    url_hash: Option<crate::platform::web::url_hash::UrlHashSync>,
 }
 
 impl ApplicationHandler for GalileoMap {
@@ -67,9 +69,12 @@ impl ApplicationHandler for GalileoMap {
             }
 
             *backend.write().expect("poisoned lock") = Some(renderer);
-            map.write()
-                .expect("poisoned lock")
-                .set_size(Size::new(size.width as f64, size.height as f64));
+            {
+                let mut map = map.write().expect("poisoned lock");
+                map.set_size(Size::new(size.width as f64, size.height as f64));
+                let view = map.view().with_scale_factor(window.scale_factor());
+                map.set_view(view);
+            }
             window.request_redraw();
         });
     }
@@ -102,12 +107,32 @@ impl ApplicationHandler for GalileoMap {
                 }
             }
             WindowEvent::RedrawRequested => {
+                if self.restart_lost_renderer() {
+                    return;
+                }
+
                 if let Some(backend) = self.backend.read().expect("lock is poisoned").as_ref() {
-                    let map = self.map.read().expect("lock is poisoned");
-                    map.load_layers();
-                    if let Err(err) = backend.render(&map) {
-                        log::error!("Render error: {err:?}");
+                    let started = SystemTime::now();
+                    {
+                        let map = self.map.read().expect("lock is poisoned");
+                        map.load_layers();
+                        if let Err(err) = backend.render(&map) {
+                            log::error!("Render error: {err:?}");
+                        }
                     }
+
+                    let frame_time = SystemTime::now()
+                        .duration_since(started)
+                        .unwrap_or_default();
+                    self.map
+                        .write()
+                        .expect("lock is poisoned")
+                        .record_frame_time(frame_time);
+                }
+
+                #[cfg(target_arch = "wasm32")]
+                if let Some(url_hash) = &self.url_hash {
+                    url_hash.update();
                 }
             }
             other => {
@@ -137,10 +162,46 @@ impl GalileoMap {
         map.set_messenger(messenger.clone());
 
         if let Some(messenger) = messenger {
-            for layer in map.layers_mut().iter_mut() {
-                let boxed: Box<dyn Messenger> = Box::new(messenger.clone());
-                layer.set_messenger(boxed);
+            map.layers_mut().set_messenger(Arc::new(messenger));
+        }
+    }
+
+    /// If the GPU device of the renderer was lost, starts recreating the renderer in background and returns `true`.
+    /// The map is redrawn when the renderer is ready.
+    fn restart_lost_renderer(&mut self) -> bool {
+        let mut backend = self.backend.write().expect("lock is poisoned");
+        if !backend.as_ref().is_some_and(|b| b.is_device_lost()) {
+            return false;
+        }
+
+        let Some(mut renderer) = backend.take() else {
+            return false;
+        };
+
+        let backend = self.backend.clone();
+        let map = self.map.clone();
+        crate::async_runtime::spawn(async move {
+            match renderer.restart().await {
+                Ok(event) => {
+                    *backend.write().expect("lock is poisoned") = Some(renderer);
+                    map.read()
+                        .expect("lock is poisoned")
+                        .handle_renderer_event(event);
+                }
+                Err(err) => log::error!("Failed to restart the renderer: {err}"),
             }
+        });
+
+        true
+    }
+
+    /// Keeps the URL of the page in sync with the map position and visible layers, so that the page link opens the
+    /// map at the same place. The map is moved to the position stored in the current URL, if any.
+    #[cfg(target_arch = "wasm32")]
+    pub fn sync_url_hash(&mut self) {
+        match crate::platform::web::url_hash::UrlHashSync::new(self.map.clone()) {
+            Ok(sync) => self.url_hash = Some(sync),
+            Err(err) => log::error!("Failed to set up URL synchronization: {err}"),
         }
     }
 
@@ -203,6 +264,8 @@ impl MapBuilder {
             event_processor,
             input_handler,
             event_loop: Some(event_loop),
+            #[cfg(target_arch = "wasm32")]
+            url_hash: None,
         }
     }
 
@@ -264,18 +327,15 @@ impl MapBuilder {
         self
     }
 
-    fn build_map(mut self, messenger: Option<WinitMessenger>) -> Arc<RwLock<Map>> {
-        if let Some(ref messenger) = messenger {
-            for layer in self.layers.iter_mut() {
-                layer.set_messenger(Box::new(messenger.clone()))
-            }
-        }
-
+    fn build_map(self, messenger: Option<WinitMessenger>) -> Arc<RwLock<Map>> {
         let view = self
             .view
             .unwrap_or_else(|| MapView::new(&self.position, self.resolution));
 
-        let map = Map::new(view, self.layers, messenger);
+        let mut map = Map::new(view, self.layers, messenger.clone());
+        if let Some(messenger) = messenger {
+            map.layers_mut().set_messenger(Arc::new(messenger));
+        }
 
         Arc::new(RwLock::new(map))
     }
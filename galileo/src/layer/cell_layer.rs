@@ -0,0 +1,336 @@
+//! Visualization of discrete global grid cells (such as [H3](https://h3geo.org) hexagons or
+//! [S2](https://s2geometry.io) cells).
+//!
+//! Edges of grid cells are geodesics (great circle arcs), so when a cell boundary is converted into a polygon, its
+//! edges are densified to follow the arcs in any projection. See [`cell_polygon`].
+//!
+//! [`CellCoverageLayer`] renders a set of cells colored by the value associated with each cell, which is the usual way
+//! to display analytical data keyed by grid cells.
+//!
+//! Support for specific grid systems is enabled with `h3` and `s2` features, that implement [`GridCell`] trait for
+//! [`h3o::CellIndex`] and [`s2::cellid::CellID`] correspondingly. Other grids can be supported by implementing
+//! [`GridCell`] trait.
+
+use crate::layer::feature_layer::symbol::Symbol;
+use crate::layer::feature_layer::{Feature, FeatureLayer};
+use crate::render::render_bundle::RenderPrimitive;
+use crate::render::{LineCap, LineJoin, LinePaint, PolygonPaint, SizeUnits};
+use crate::Color;
+use galileo_types::cartesian::CartesianPoint3d;
+use galileo_types::geo::impls::GeoPoint2d;
+use galileo_types::geo::{Crs, GeoPoint, NewGeoPoint};
+use galileo_types::geometry::Geom;
+use galileo_types::geometry_type::GeoSpace2d;
+use galileo_types::impls::{ClosedContour, Contour, Polygon};
+use num_traits::AsPrimitive;
+
+/// Default maximum length of a cell edge segment after densification, in degrees of arc.
+pub const DEFAULT_MAX_SEGMENT_ANGLE: f64 = 1.0;
+
+/// A cell of a discrete global grid.
+pub trait GridCell {
+    /// Vertices of the cell boundary in order. Consecutive vertices are connected by geodesics.
+    fn boundary(&self) -> Vec<GeoPoint2d>;
+}
+
+#[cfg(feature = "h3")]
+impl GridCell for h3o::CellIndex {
+    fn boundary(&self) -> Vec<GeoPoint2d> {
+        h3o::CellIndex::boundary(*self)
+            .iter()
+            .map(|vertex| GeoPoint2d::latlon(vertex.lat(), vertex.lng()))
+            .collect()
+    }
+}
+
+#[cfg(feature = "s2")]
+impl GridCell for s2::cellid::CellID {
+    fn boundary(&self) -> Vec<GeoPoint2d> {
+        let cell = s2::cell::Cell::from(self);
+        (0..4)
+            .map(|k| {
+                let vertex = s2::latlng::LatLng::from(cell.vertex(k));
+                GeoPoint2d::latlon(vertex.lat.deg(), vertex.lng.deg())
+            })
+            .collect()
+    }
+}
+
+/// Converts the boundary of the cell into a polygon.
+///
+/// Edges of the cell are split into segments not longer than `max_segment_angle` (in degrees of arc) along great
+/// circles. Cells crossing the antimeridian are returned with longitudes continuing over `180` degrees, so that the
+/// polygon does not wrap around the whole globe.
+pub fn cell_polygon(cell: &impl GridCell, max_segment_angle: f64) -> Polygon<GeoPoint2d> {
+    let mut points = densify_geodesic(&cell.boundary(), max_segment_angle);
+
+    let crosses_antimeridian = points
+        .windows(2)
+        .any(|pair| (pair[0].lon() - pair[1].lon()).abs() > 180.0);
+    if crosses_antimeridian {
+        for point in &mut points {
+            if point.lon() < 0.0 {
+                *point = GeoPoint2d::latlon(point.lat(), point.lon() + 360.0);
+            }
+        }
+    }
+
+    Polygon::new(ClosedContour::new(points), vec![])
+}
+
+/// Densifies a closed ring of points connected by geodesics, so that no segment is longer than `max_segment_angle`
+/// degrees of arc.
+pub fn densify_geodesic(ring: &[GeoPoint2d], max_segment_angle: f64) -> Vec<GeoPoint2d> {
+    let max_angle = max_segment_angle.max(f64::EPSILON).to_radians();
+    let mut result = Vec::with_capacity(ring.len());
+
+    for (i, from) in ring.iter().enumerate() {
+        let to = &ring[(i + 1) % ring.len()];
+        result.push(*from);
+
+        let a = to_unit_vector(from);
+        let b = to_unit_vector(to);
+        let angle = dot(a, b).clamp(-1.0, 1.0).acos();
+        // Tolerance keeps rounding errors from adding a segment when the angle is a multiple of the maximum.
+        let segments = (angle / max_angle - 1e-9).ceil() as usize;
+
+        for step in 1..segments {
+            let k = step as f64 / segments as f64;
+            result.push(slerp(a, b, angle, k));
+        }
+    }
+
+    result
+}
+
+fn to_unit_vector(point: &GeoPoint2d) -> [f64; 3] {
+    let lat = point.lat().to_radians();
+    let lon = point.lon().to_radians();
+    [lat.cos() * lon.cos(), lat.cos() * lon.sin(), lat.sin()]
+}
+
+fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn slerp(a: [f64; 3], b: [f64; 3], angle: f64, k: f64) -> GeoPoint2d {
+    let sin = angle.sin();
+    let ka = ((1.0 - k) * angle).sin() / sin;
+    let kb = (k * angle).sin() / sin;
+    let [x, y, z] = [0, 1, 2].map(|i| a[i] * ka + b[i] * kb);
+
+    GeoPoint2d::latlon(
+        z.atan2((x * x + y * y).sqrt()).to_degrees(),
+        y.atan2(x).to_degrees(),
+    )
+}
+
+/// A grid cell with an associated value, used as a feature of a [`CellCoverageLayer`].
+#[derive(Debug, Clone)]
+pub struct CellFeature<C> {
+    /// The cell.
+    pub cell: C,
+    /// Value associated with the cell.
+    pub value: f64,
+    polygon: Polygon<GeoPoint2d>,
+}
+
+impl<C: GridCell> CellFeature<C> {
+    /// Creates a new feature, densifying the cell edges with [`DEFAULT_MAX_SEGMENT_ANGLE`].
+    pub fn new(cell: C, value: f64) -> Self {
+        Self::with_max_segment_angle(cell, value, DEFAULT_MAX_SEGMENT_ANGLE)
+    }
+
+    /// Creates a new feature, densifying the cell edges with the given max segment angle (see [`cell_polygon`]).
+    pub fn with_max_segment_angle(cell: C, value: f64, max_segment_angle: f64) -> Self {
+        let polygon = cell_polygon(&cell, max_segment_angle);
+        Self {
+            cell,
+            value,
+            polygon,
+        }
+    }
+}
+
+impl<C> Feature for CellFeature<C> {
+    type Geom = Polygon<GeoPoint2d>;
+
+    fn geometry(&self) -> &Self::Geom {
+        &self.polygon
+    }
+}
+
+/// Symbol for [`CellFeature`]s that fills the cells with colors from a color ramp based on the cell value.
+#[derive(Debug, Clone)]
+pub struct CellCoverageSymbol {
+    stops: Vec<(f64, Color)>,
+    stroke_color: Color,
+    stroke_width: f64,
+}
+
+impl CellCoverageSymbol {
+    /// Creates a new symbol with the given color ramp.
+    ///
+    /// Colors of the cells with values between two stops are interpolated linearly. Cells with values outside of
+    /// the ramp get the color of the nearest stop.
+    pub fn new(stops: impl IntoIterator<Item = (f64, Color)>) -> Self {
+        let mut stops: Vec<_> = stops.into_iter().collect();
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        Self {
+            stops,
+            stroke_color: Color::TRANSPARENT,
+            stroke_width: 0.0,
+        }
+    }
+
+    /// Creates a new instance from a copy of the current, but with the given cell outline.
+    pub fn with_stroke(&self, stroke_color: Color, stroke_width: f64) -> Self {
+        Self {
+            stroke_color,
+            stroke_width,
+            ..self.clone()
+        }
+    }
+
+    /// Returns the fill color for the given value.
+    pub fn color(&self, value: f64) -> Color {
+        let Some(first) = self.stops.first() else {
+            return Color::TRANSPARENT;
+        };
+
+        if value <= first.0 {
+            return first.1;
+        }
+
+        for pair in self.stops.windows(2) {
+            let (from, to) = (pair[0], pair[1]);
+            if value <= to.0 {
+                let k = (value - from.0) / (to.0 - from.0);
+                return from.1.interpolate(to.1, k);
+            }
+        }
+
+        self.stops[self.stops.len() - 1].1
+    }
+}
+
+impl<C> Symbol<CellFeature<C>> for CellCoverageSymbol {
+    fn render<'a, N, P>(
+        &self,
+        feature: &CellFeature<C>,
+        geometry: &'a Geom<P>,
+        _min_resolution: f64,
+    ) -> Vec<RenderPrimitive<'a, N, P, Contour<P>, Polygon<P>>>
+    where
+        N: AsPrimitive<f32>,
+        P: CartesianPoint3d<Num = N> + Clone,
+    {
+        let Geom::Polygon(polygon) = geometry else {
+            return vec![];
+        };
+
+        let mut primitives = vec![RenderPrimitive::new_polygon_ref(
+            polygon,
+            PolygonPaint {
+                color: self.color(feature.value),
+            },
+        )];
+
+        if self.stroke_width > 0.0 && !self.stroke_color.is_transparent() {
+            primitives.push(RenderPrimitive::new_contour(
+                polygon.outer_contour.clone().into(),
+                LinePaint {
+                    color: self.stroke_color,
+                    width: self.stroke_width,
+                    offset: 0.0,
+                    line_cap: LineCap::Butt,
+                    line_join: LineJoin::Round,
+                    miter_limit: LinePaint::DEFAULT_MITER_LIMIT,
+                    units: SizeUnits::Pixels,
+                },
+            ));
+        }
+
+        primitives
+    }
+}
+
+/// Layer that renders a coverage of grid cells colored by their values.
+pub type CellCoverageLayer<C> =
+    FeatureLayer<GeoPoint2d, CellFeature<C>, CellCoverageSymbol, GeoSpace2d>;
+
+impl<C: GridCell> FeatureLayer<GeoPoint2d, CellFeature<C>, CellCoverageSymbol, GeoSpace2d> {
+    /// Creates a new layer from a map of cells to values.
+    pub fn from_cells(
+        cells: impl IntoIterator<Item = (C, f64)>,
+        symbol: CellCoverageSymbol,
+    ) -> Self {
+        let features = cells
+            .into_iter()
+            .map(|(cell, value)| CellFeature::new(cell, value))
+            .collect();
+        Self::new(features, symbol, Crs::WGS84)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+    use galileo_types::contour::ClosedContour as _;
+    use galileo_types::latlon;
+
+    struct TestCell(Vec<GeoPoint2d>);
+
+    impl GridCell for TestCell {
+        fn boundary(&self) -> Vec<GeoPoint2d> {
+            self.0.clone()
+        }
+    }
+
+    #[test]
+    fn densify_follows_great_circle() {
+        let ring = [latlon!(0.0, 0.0), latlon!(0.0, 10.0)];
+        let densified = densify_geodesic(&ring, 1.0);
+
+        // 10 segments for each of the two (identical) edges
+        assert_eq!(densified.len(), 20);
+        for point in &densified {
+            assert_abs_diff_eq!(point.lat(), 0.0, epsilon = 1e-9);
+        }
+        assert_abs_diff_eq!(densified[5].lon(), 5.0, epsilon = 1e-9);
+
+        // Geodesic between two points at the same latitude bulges towards the pole.
+        let densified = densify_geodesic(&[latlon!(60.0, 0.0), latlon!(60.0, 90.0)], 1.0);
+        let middle = densified[densified.len() / 4];
+        assert!(middle.lat() > 60.0);
+    }
+
+    #[test]
+    fn polygon_across_antimeridian() {
+        let cell = TestCell(vec![
+            latlon!(0.0, 179.0),
+            latlon!(0.0, -179.0),
+            latlon!(1.0, -179.0),
+            latlon!(1.0, 179.0),
+        ]);
+        let polygon = cell_polygon(&cell, 10.0);
+
+        for point in polygon.outer_contour.iter_points() {
+            assert!(point.lon() >= 179.0 && point.lon() <= 181.0);
+        }
+    }
+
+    #[test]
+    fn color_ramp() {
+        let symbol = CellCoverageSymbol::new([
+            (10.0, Color::rgba(0, 0, 0, 255)),
+            (0.0, Color::rgba(0, 0, 0, 0)),
+        ]);
+
+        assert_eq!(symbol.color(-1.0), Color::rgba(0, 0, 0, 0));
+        assert_eq!(symbol.color(5.0), Color::rgba(0, 0, 0, 128));
+        assert_eq!(symbol.color(20.0), Color::rgba(0, 0, 0, 255));
+    }
+}
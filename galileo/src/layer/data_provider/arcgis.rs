@@ -0,0 +1,745 @@
+//! Clients for ArcGIS REST services.
+//!
+//! * [`ArcGisMapServer`] loads tiles from cached `MapServer` services. The tiling scheme of the service is described
+//!   by its info JSON, which can be converted into a [`TileSchema`] with [`ArcGisMapServerInfo::tile_schema`].
+//! * [`ArcGisFeatureServer`] queries features from `FeatureServer` (or `MapServer`) layers. Features are loaded page
+//!   by page in GeoJSON or esriJSON format and returned as [`geojson::Feature`]s, which can be used directly in a
+//!   [`FeatureLayer`](crate::layer::FeatureLayer).
+
+use crate::error::GalileoError;
+use crate::layer::data_provider::{normalize_features, UrlSource};
+use crate::lod::Lod;
+use crate::platform::{PlatformService, PlatformServiceImpl};
+use crate::tile_scheme::{TileIndex, VerticalDirection};
+use crate::TileSchema;
+use galileo_types::cartesian::{Point2d, Rect};
+use galileo_types::geo::Crs;
+use galileo_types::normalize::NormalizeOptions;
+use geojson::{FeatureCollection, Geometry, JsonObject, Value};
+use serde::Deserialize;
+
+/// Spatial reference of an ArcGIS service.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArcGisSpatialReference {
+    /// Well-known id of the spatial reference.
+    pub wkid: Option<u32>,
+    /// Latest well-known id of the spatial reference.
+    pub latest_wkid: Option<u32>,
+}
+
+impl ArcGisSpatialReference {
+    /// Returns the CRS corresponding to the spatial reference, if it is supported.
+    pub fn crs(&self) -> Option<Crs> {
+        self.latest_wkid
+            .and_then(crs_from_wkid)
+            .or_else(|| self.wkid.and_then(crs_from_wkid))
+    }
+}
+
+fn crs_from_wkid(wkid: u32) -> Option<Crs> {
+    match wkid {
+        3857 | 102100 | 102113 | 900913 => Some(Crs::EPSG3857),
+        4326 => Some(Crs::WGS84),
+        _ => None,
+    }
+}
+
+/// Point in the ArcGIS JSON format.
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct ArcGisPoint {
+    /// X coordinate.
+    pub x: f64,
+    /// Y coordinate.
+    pub y: f64,
+}
+
+/// Extent (bounding rectangle) in the ArcGIS JSON format.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArcGisExtent {
+    /// Minimum X coordinate.
+    pub xmin: f64,
+    /// Minimum Y coordinate.
+    pub ymin: f64,
+    /// Maximum X coordinate.
+    pub xmax: f64,
+    /// Maximum Y coordinate.
+    pub ymax: f64,
+    /// Spatial reference of the extent.
+    pub spatial_reference: Option<ArcGisSpatialReference>,
+}
+
+/// Level of detail of a cached ArcGIS service.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ArcGisLod {
+    /// Z-level of the tiles.
+    pub level: u32,
+    /// Resolution of the level in map units per pixel.
+    pub resolution: f64,
+    /// Scale denominator of the level.
+    pub scale: f64,
+}
+
+/// Tiling scheme of a cached ArcGIS service (`tileInfo` property of the service info).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArcGisTileInfo {
+    /// Height of a tile in pixels.
+    pub rows: u32,
+    /// Width of a tile in pixels.
+    pub cols: u32,
+    /// Top left corner of the tile with index `(0, 0)` at every level.
+    pub origin: ArcGisPoint,
+    /// Spatial reference of the tiles.
+    pub spatial_reference: Option<ArcGisSpatialReference>,
+    /// Levels of detail of the service.
+    pub lods: Vec<ArcGisLod>,
+}
+
+impl ArcGisTileInfo {
+    /// Converts the tiling info into a [`TileSchema`] with the given bounds and CRS.
+    pub fn to_tile_schema(&self, bounds: Rect, crs: Crs) -> Result<TileSchema, GalileoError> {
+        let lods = self
+            .lods
+            .iter()
+            .map(|lod| {
+                Lod::new(lod.resolution, lod.level).ok_or_else(|| {
+                    GalileoError::Generic(format!("invalid resolution of lod {}", lod.level))
+                })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(TileSchema {
+            origin: Point2d::new(self.origin.x, self.origin.y),
+            bounds,
+            lods,
+            tile_width: self.cols,
+            tile_height: self.rows,
+            y_direction: VerticalDirection::TopToBottom,
+            crs,
+        })
+    }
+}
+
+/// Info of an ArcGIS `MapServer` service, as returned by `{service_url}?f=json` request.
+///
+/// Only the properties needed to display the service are parsed.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArcGisMapServerInfo {
+    /// Whether the service has a tile cache.
+    #[serde(default)]
+    pub single_fused_map_cache: bool,
+    /// Tiling scheme of the service, if the service is cached.
+    pub tile_info: Option<ArcGisTileInfo>,
+    /// Spatial reference of the service.
+    pub spatial_reference: Option<ArcGisSpatialReference>,
+    /// Full extent of the service data.
+    pub full_extent: Option<ArcGisExtent>,
+}
+
+impl ArcGisMapServerInfo {
+    /// Parses the service info JSON.
+    pub fn from_json(json: &[u8]) -> Result<Self, GalileoError> {
+        serde_json::from_slice(json)
+            .map_err(|err| GalileoError::Generic(format!("invalid map server info: {err}")))
+    }
+
+    /// Creates a tile schema for the tiles of the service.
+    ///
+    /// Returns an error if the service is not cached, or if its spatial reference is not supported.
+    pub fn tile_schema(&self) -> Result<TileSchema, GalileoError> {
+        let Some(tile_info) = &self.tile_info else {
+            return Err(GalileoError::Generic(
+                "map server does not have a tile cache".into(),
+            ));
+        };
+
+        let crs = tile_info
+            .spatial_reference
+            .as_ref()
+            .or(self.spatial_reference.as_ref())
+            .and_then(|sr| sr.crs())
+            .ok_or_else(|| {
+                GalileoError::Generic("spatial reference of the map server is not supported".into())
+            })?;
+
+        let bounds = match &self.full_extent {
+            Some(extent) => Rect::new(extent.xmin, extent.ymin, extent.xmax, extent.ymax),
+            None if crs == Crs::EPSG3857 => TileSchema::web(1).bounds,
+            None => {
+                return Err(GalileoError::Generic(
+                    "map server info does not contain full extent".into(),
+                ))
+            }
+        };
+
+        tile_info.to_tile_schema(bounds, crs)
+    }
+}
+
+/// Client for a cached ArcGIS `MapServer` service.
+///
+/// ```no_run
+/// use galileo::layer::data_provider::arcgis::ArcGisMapServer;
+/// use galileo::layer::data_provider::UrlImageProvider;
+/// use galileo::layer::RasterTileLayer;
+///
+/// # tokio_test::block_on(async {
+/// let service = ArcGisMapServer::new(
+///     "https://services.arcgisonline.com/ArcGIS/rest/services/World_Imagery/MapServer",
+/// );
+/// let tile_schema = service.load_info().await?.tile_schema()?;
+/// let provider = UrlImageProvider::new(service.url_source());
+/// let layer = RasterTileLayer::new(tile_schema, provider, None);
+/// # Ok::<(), galileo::error::GalileoError>(())
+/// # });
+/// ```
+#[derive(Debug, Clone)]
+pub struct ArcGisMapServer {
+    url: String,
+}
+
+impl ArcGisMapServer {
+    /// Creates a new client for the service at the given URL (e.g. `https://server/arcgis/rest/services/Name/MapServer`).
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into().trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// URL of the service.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// URL of the service info JSON.
+    pub fn info_url(&self) -> String {
+        format!("{}?f=json", self.url)
+    }
+
+    /// URL of the tile with the given index.
+    pub fn tile_url(&self, index: &TileIndex) -> String {
+        tile_url(&self.url, index)
+    }
+
+    /// Returns a URL source that can be used with
+    /// [`UrlImageProvider`](crate::layer::data_provider::UrlImageProvider) to load the tiles of the service.
+    pub fn url_source(&self) -> impl UrlSource<TileIndex> {
+        let url = self.url.clone();
+        move |index: &TileIndex| tile_url(&url, index)
+    }
+
+    /// Loads and parses the service info.
+    pub async fn load_info(&self) -> Result<ArcGisMapServerInfo, GalileoError> {
+        let bytes = PlatformServiceImpl::new()
+            .load_bytes_from_url(&self.info_url())
+            .await?;
+        ArcGisMapServerInfo::from_json(&bytes)
+    }
+}
+
+fn tile_url(url: &str, index: &TileIndex) -> String {
+    format!("{url}/tile/{}/{}/{}", index.z, index.y, index.x)
+}
+
+/// Format of the features returned by a `FeatureServer` query.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum ArcGisFeatureFormat {
+    /// GeoJSON (`f=geojson`). Supported by ArcGIS Server 10.4 and later.
+    #[default]
+    GeoJson,
+    /// Esri JSON (`f=json`). Supported by all versions of ArcGIS Server.
+    EsriJson,
+}
+
+impl ArcGisFeatureFormat {
+    fn param(&self) -> &'static str {
+        match self {
+            ArcGisFeatureFormat::GeoJson => "geojson",
+            ArcGisFeatureFormat::EsriJson => "json",
+        }
+    }
+}
+
+/// Parameters of a `FeatureServer` query.
+#[derive(Debug, Clone)]
+pub struct ArcGisFeatureQuery {
+    /// SQL `where` clause to filter features.
+    pub where_clause: String,
+    /// Attributes to include into the features. Empty list means all attributes.
+    pub out_fields: Vec<String>,
+    /// Well-known id of the spatial reference of the returned geometries.
+    pub out_wkid: u32,
+    /// If set, only the features intersecting this envelope (in `out_wkid` spatial reference) are returned.
+    pub envelope: Option<Rect>,
+}
+
+impl Default for ArcGisFeatureQuery {
+    fn default() -> Self {
+        Self {
+            where_clause: "1=1".into(),
+            out_fields: vec![],
+            out_wkid: 4326,
+            envelope: None,
+        }
+    }
+}
+
+impl ArcGisFeatureQuery {
+    /// CRS of the returned geometries, if it is supported.
+    pub fn out_crs(&self) -> Option<Crs> {
+        crs_from_wkid(self.out_wkid)
+    }
+}
+
+/// Client for a layer of an ArcGIS `FeatureServer` service.
+///
+/// Services limit the number of features returned by a single request, so the features are requested page by page
+/// until the service reports that all of them are loaded.
+///
+/// ```no_run
+/// use galileo::layer::data_provider::arcgis::{ArcGisFeatureQuery, ArcGisFeatureServer};
+/// use galileo::layer::FeatureLayer;
+/// use galileo::symbol::SimpleContourSymbol;
+/// use galileo::Color;
+/// use galileo_types::geometry_type::GeoSpace2d;
+///
+/// # tokio_test::block_on(async {
+/// let service = ArcGisFeatureServer::new("https://server/arcgis/rest/services/Roads/FeatureServer/0");
+/// let query = ArcGisFeatureQuery::default();
+/// let features = service.query(&query).await?;
+/// let layer: FeatureLayer<_, _, _, GeoSpace2d> = FeatureLayer::new(
+///     features,
+///     SimpleContourSymbol::new(Color::RED, 2.0),
+///     query.out_crs().expect("supported crs"),
+/// );
+/// # Ok::<(), galileo::error::GalileoError>(())
+/// # });
+/// ```
+#[derive(Debug, Clone)]
+pub struct ArcGisFeatureServer {
+    url: String,
+    format: ArcGisFeatureFormat,
+    page_size: Option<u32>,
+    normalize: Option<NormalizeOptions>,
+}
+
+impl ArcGisFeatureServer {
+    /// Creates a new client for the layer at the given URL (e.g.
+    /// `https://server/arcgis/rest/services/Name/FeatureServer/0`).
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into().trim_end_matches('/').to_string(),
+            format: ArcGisFeatureFormat::default(),
+            page_size: None,
+            normalize: None,
+        }
+    }
+
+    /// Sets the format the features are requested in.
+    pub fn with_format(&self, format: ArcGisFeatureFormat) -> Self {
+        Self {
+            format,
+            ..self.clone()
+        }
+    }
+
+    /// Sets the number of features requested with one request. If not set, the maximum number allowed by the
+    /// service is requested.
+    pub fn with_page_size(&self, page_size: u32) -> Self {
+        Self {
+            page_size: Some(page_size),
+            ..self.clone()
+        }
+    }
+
+    /// Enables normalization of the loaded geometries: invalid polygons (unclosed rings, wrong winding, duplicate
+    /// vertices etc.) are fixed before they are returned. See [`galileo_types::normalize`] for details.
+    pub fn with_normalization(&self, options: NormalizeOptions) -> Self {
+        Self {
+            normalize: Some(options),
+            ..self.clone()
+        }
+    }
+
+    /// URL of the layer.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// URL of the query request for the page of features starting with `offset`.
+    pub fn query_url(&self, query: &ArcGisFeatureQuery, offset: usize) -> String {
+        let out_fields = if query.out_fields.is_empty() {
+            "*".to_string()
+        } else {
+            query.out_fields.join(",")
+        };
+
+        let mut url = format!(
+            "{}/query?where={}&outFields={}&outSR={}&returnGeometry=true&resultOffset={offset}&f={}",
+            self.url,
+            encode_param(&query.where_clause),
+            encode_param(&out_fields),
+            query.out_wkid,
+            self.format.param(),
+        );
+
+        if let Some(page_size) = self.page_size {
+            url += &format!("&resultRecordCount={page_size}");
+        }
+
+        if let Some(envelope) = query.envelope {
+            url += &format!(
+                "&geometry={}&geometryType=esriGeometryEnvelope&inSR={}&spatialRel=esriSpatialRelIntersects",
+                encode_param(&format!(
+                    "{},{},{},{}",
+                    envelope.x_min(),
+                    envelope.y_min(),
+                    envelope.x_max(),
+                    envelope.y_max()
+                )),
+                query.out_wkid,
+            );
+        }
+
+        url
+    }
+
+    /// Loads all the features matching the query.
+    pub async fn query(
+        &self,
+        query: &ArcGisFeatureQuery,
+    ) -> Result<Vec<geojson::Feature>, GalileoError> {
+        let platform_service = PlatformServiceImpl::new();
+        let mut features = vec![];
+        // Offset of the next page in the records of the server. Records without geometry are not added to the
+        // features, but still count for the offset.
+        let mut offset = 0;
+
+        loop {
+            let url = self.query_url(query, offset);
+            let bytes = platform_service.load_bytes_from_url(&url).await?;
+            let page = parse_page(&bytes, self.format)?;
+
+            offset += page.record_count;
+            features.extend(page.features);
+
+            // An empty page cannot move the offset, so requesting the next one would loop forever.
+            if !page.exceeded_transfer_limit || page.record_count == 0 {
+                break;
+            }
+        }
+
+        if let Some(options) = &self.normalize {
+            normalize_features(&mut features, options);
+        }
+
+        Ok(features)
+    }
+}
+
+fn encode_param(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'*' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded += &format!("%{byte:02X}"),
+        }
+    }
+
+    encoded
+}
+
+struct FeaturePage {
+    features: Vec<geojson::Feature>,
+    /// Number of records in the page, including the records without geometry that are not added to `features`.
+    record_count: usize,
+    exceeded_transfer_limit: bool,
+}
+
+fn parse_page(bytes: &[u8], format: ArcGisFeatureFormat) -> Result<FeaturePage, GalileoError> {
+    if let Ok(error) = serde_json::from_slice::<EsriErrorResponse>(bytes) {
+        return Err(GalileoError::Generic(format!(
+            "feature server error {}: {}",
+            error.error.code, error.error.message
+        )));
+    }
+
+    match format {
+        ArcGisFeatureFormat::GeoJson => parse_geojson_page(bytes),
+        ArcGisFeatureFormat::EsriJson => parse_esri_page(bytes),
+    }
+}
+
+fn parse_geojson_page(bytes: &[u8]) -> Result<FeaturePage, GalileoError> {
+    let collection: FeatureCollection = serde_json::from_slice(bytes)
+        .map_err(|err| GalileoError::Generic(format!("invalid GeoJSON response: {err}")))?;
+
+    // ArcGIS puts the flag either at the top level of the collection or into its `properties` member.
+    let exceeded_transfer_limit = collection
+        .foreign_members
+        .as_ref()
+        .and_then(|members| {
+            members.get("exceededTransferLimit").or_else(|| {
+                members
+                    .get("properties")
+                    .and_then(|properties| properties.get("exceededTransferLimit"))
+            })
+        })
+        .and_then(|value| value.as_bool())
+        .unwrap_or(false);
+
+    Ok(FeaturePage {
+        record_count: collection.features.len(),
+        features: collection
+            .features
+            .into_iter()
+            .filter(|feature| feature.geometry.is_some())
+            .collect(),
+        exceeded_transfer_limit,
+    })
+}
+
+#[derive(Deserialize)]
+struct EsriErrorResponse {
+    error: EsriError,
+}
+
+#[derive(Deserialize)]
+struct EsriError {
+    code: i32,
+    #[serde(default)]
+    message: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EsriFeatureSet {
+    features: Vec<EsriFeature>,
+    #[serde(default)]
+    exceeded_transfer_limit: bool,
+}
+
+#[derive(Deserialize)]
+struct EsriFeature {
+    geometry: Option<EsriGeometry>,
+    #[serde(default)]
+    attributes: JsonObject,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum EsriGeometry {
+    Point { x: f64, y: f64 },
+    MultiPoint { points: Vec<Vec<f64>> },
+    Polyline { paths: Vec<Vec<Vec<f64>>> },
+    Polygon { rings: Vec<Vec<Vec<f64>>> },
+}
+
+impl EsriGeometry {
+    fn into_geojson(self) -> Value {
+        match self {
+            EsriGeometry::Point { x, y } => Value::Point(vec![x, y]),
+            EsriGeometry::MultiPoint { points } => Value::MultiPoint(points),
+            EsriGeometry::Polyline { mut paths } => {
+                if paths.len() == 1 {
+                    Value::LineString(paths.remove(0))
+                } else {
+                    Value::MultiLineString(paths)
+                }
+            }
+            EsriGeometry::Polygon { rings } => {
+                // Outer rings in esriJSON are clockwise, holes are counter-clockwise and follow their outer ring.
+                let mut polygons: Vec<Vec<Vec<Vec<f64>>>> = vec![];
+                for ring in rings {
+                    match polygons.last_mut() {
+                        Some(polygon) if !is_clockwise(&ring) => polygon.push(ring),
+                        _ => polygons.push(vec![ring]),
+                    }
+                }
+
+                if polygons.len() == 1 {
+                    Value::Polygon(polygons.remove(0))
+                } else {
+                    Value::MultiPolygon(polygons)
+                }
+            }
+        }
+    }
+}
+
+fn is_clockwise(ring: &[Vec<f64>]) -> bool {
+    let doubled_area: f64 = ring
+        .windows(2)
+        .map(|w| match (&w[0][..], &w[1][..]) {
+            ([x1, y1, ..], [x2, y2, ..]) => x1 * y2 - x2 * y1,
+            _ => 0.0,
+        })
+        .sum();
+
+    doubled_area < 0.0
+}
+
+fn parse_esri_page(bytes: &[u8]) -> Result<FeaturePage, GalileoError> {
+    let feature_set: EsriFeatureSet = serde_json::from_slice(bytes)
+        .map_err(|err| GalileoError::Generic(format!("invalid esriJSON response: {err}")))?;
+
+    let record_count = feature_set.features.len();
+    let features = feature_set
+        .features
+        .into_iter()
+        .filter_map(|feature| {
+            let geometry = feature.geometry?;
+            Some(geojson::Feature {
+                bbox: None,
+                geometry: Some(Geometry::new(geometry.into_geojson())),
+                id: None,
+                properties: Some(feature.attributes),
+                foreign_members: None,
+            })
+        })
+        .collect();
+
+    Ok(FeaturePage {
+        features,
+        record_count,
+        exceeded_transfer_limit: feature_set.exceeded_transfer_limit,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::view::MapView;
+    use galileo_types::cartesian::Size;
+
+    const MAP_SERVER_INFO: &str = r#"{
+        "singleFusedMapCache": true,
+        "spatialReference": { "wkid": 102100, "latestWkid": 3857 },
+        "tileInfo": {
+            "rows": 256,
+            "cols": 256,
+            "origin": { "x": -1000.0, "y": 3000.0 },
+            "spatialReference": { "wkid": 102100, "latestWkid": 3857 },
+            "lods": [
+                { "level": 0, "resolution": 8.0, "scale": 30000.0 },
+                { "level": 1, "resolution": 4.0, "scale": 15000.0 }
+            ]
+        },
+        "fullExtent": { "xmin": -1000.0, "ymin": 952.0, "xmax": 1048.0, "ymax": 3000.0 }
+    }"#;
+
+    #[test]
+    fn map_server_tile_schema() {
+        let info = ArcGisMapServerInfo::from_json(MAP_SERVER_INFO.as_bytes()).unwrap();
+        let schema = info.tile_schema().unwrap();
+
+        assert_eq!(schema.crs, Crs::EPSG3857);
+        assert_eq!(schema.tile_width, 256);
+        assert_eq!(schema.lod_resolution(1), Some(4.0));
+        assert_eq!(schema.y_direction, VerticalDirection::TopToBottom);
+
+        let view = MapView::new_projected(&Point2d::new(24.0, 1976.0), 4.0)
+            .with_size(Size::new(512.0, 512.0));
+        let mut tiles: Vec<_> = schema
+            .iter_tiles(&view)
+            .unwrap()
+            .map(|index| (index.x, index.y, index.z))
+            .collect();
+        tiles.sort();
+        assert_eq!(tiles, vec![(0, 0, 1), (0, 1, 1), (1, 0, 1), (1, 1, 1)]);
+    }
+
+    #[test]
+    fn map_server_tile_url() {
+        let service = ArcGisMapServer::new("https://server/rest/services/Test/MapServer/");
+        assert_eq!(
+            service.tile_url(&TileIndex::new(3, 5, 7)),
+            "https://server/rest/services/Test/MapServer/tile/7/5/3"
+        );
+    }
+
+    #[test]
+    fn feature_query_url() {
+        let service =
+            ArcGisFeatureServer::new("https://server/FeatureServer/0").with_page_size(100);
+        let query = ArcGisFeatureQuery {
+            where_clause: "TYPE = 'road'".into(),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            service.query_url(&query, 200),
+            "https://server/FeatureServer/0/query?where=TYPE%20%3D%20%27road%27&outFields=*&outSR=4326\
+            &returnGeometry=true&resultOffset=200&f=geojson&resultRecordCount=100"
+        );
+    }
+
+    #[test]
+    fn parse_geojson_response() {
+        let response = r#"{
+            "type": "FeatureCollection",
+            "features": [
+                { "type": "Feature", "geometry": { "type": "Point", "coordinates": [1.0, 2.0] }, "properties": {} },
+                { "type": "Feature", "geometry": null, "properties": {} }
+            ],
+            "properties": { "exceededTransferLimit": true }
+        }"#;
+
+        let page = parse_page(response.as_bytes(), ArcGisFeatureFormat::GeoJson).unwrap();
+        assert_eq!(page.features.len(), 1);
+        assert_eq!(page.record_count, 2);
+        assert!(page.exceeded_transfer_limit);
+    }
+
+    #[test]
+    fn parse_esri_response() {
+        let response = r#"{
+            "features": [
+                { "attributes": { "NAME": "a" }, "geometry": { "x": 1.0, "y": 2.0 } },
+                {
+                    "attributes": { "NAME": "b" },
+                    "geometry": { "rings": [
+                        [[0, 0], [0, 10], [10, 10], [10, 0], [0, 0]],
+                        [[2, 2], [4, 2], [4, 4], [2, 4], [2, 2]],
+                        [[20, 0], [20, 10], [30, 10], [30, 0], [20, 0]]
+                    ] }
+                },
+                { "attributes": { "NAME": "c" } }
+            ]
+        }"#;
+
+        let page = parse_page(response.as_bytes(), ArcGisFeatureFormat::EsriJson).unwrap();
+        assert_eq!(page.features.len(), 2);
+        assert_eq!(page.record_count, 3);
+        assert!(!page.exceeded_transfer_limit);
+
+        assert_eq!(
+            page.features[0].geometry.as_ref().unwrap().value,
+            Value::Point(vec![1.0, 2.0])
+        );
+        assert_eq!(
+            page.features[0].property("NAME").and_then(|v| v.as_str()),
+            Some("a")
+        );
+
+        match &page.features[1].geometry.as_ref().unwrap().value {
+            Value::MultiPolygon(polygons) => {
+                assert_eq!(polygons.len(), 2);
+                assert_eq!(polygons[0].len(), 2);
+                assert_eq!(polygons[1].len(), 1);
+            }
+            other => panic!("unexpected geometry {other:?}"),
+        }
+    }
+
+    #[test]
+    fn parse_error_response() {
+        let response = r#"{ "error": { "code": 400, "message": "Invalid query" } }"#;
+        assert!(parse_page(response.as_bytes(), ArcGisFeatureFormat::GeoJson).is_err());
+    }
+}
@@ -0,0 +1,434 @@
+//! Loading point features from CSV and TSV tables.
+//!
+//! [`CsvReader`] reads a table with a header row, takes the point coordinates from the given columns and turns the
+//! rest of the columns into the attributes of the features. Types of the attributes are inferred for every column: if
+//! all the non-empty values of a column are numbers (or booleans), they are read as numbers (or booleans), otherwise
+//! as strings. Empty values are treated as missing attributes.
+//!
+//! Coordinates can be given either as latitude and longitude, or as X and Y in a projected CRS:
+//!
+//! ```no_run
+//! use galileo::layer::data_provider::csv::{CsvGeoLayer, CsvProjectedLayer, CsvReader};
+//! use galileo::layer::FeatureLayer;
+//! use galileo::symbol::CirclePointSymbol;
+//! use galileo::Color;
+//! use galileo_types::geo::Crs;
+//!
+//! let text = std::fs::read_to_string("stations.csv")?;
+//! let features = CsvReader::new().read_latlon(&text, "latitude", "longitude")?;
+//! let layer: CsvGeoLayer<_> =
+//!     FeatureLayer::new(features, CirclePointSymbol::new(Color::RED, 6.0), Crs::WGS84);
+//!
+//! let text = std::fs::read_to_string("wells.tsv")?;
+//! let features = CsvReader::new().with_delimiter('\t').read_xy(&text, "easting", "northing")?;
+//! let layer: CsvProjectedLayer<_> =
+//!     FeatureLayer::new(features, CirclePointSymbol::new(Color::BLUE, 6.0), Crs::EPSG3857);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! Rows with missing or unparsable coordinates are skipped, and the number of skipped rows is logged. To also check
+//! the parsed coordinates for `NaN` values, `0, 0` points and values out of range, use
+//! [`CsvReader::with_coordinate_validation`].
+
+use crate::error::GalileoError;
+use crate::layer::feature_layer::{AttributeValue, Feature};
+use crate::layer::FeatureLayer;
+use galileo_types::cartesian::Point2d;
+use galileo_types::geo::impls::GeoPoint2d;
+use galileo_types::geo::NewGeoPoint;
+use galileo_types::geometry::Geometry;
+use galileo_types::geometry_type::{CartesianSpace2d, GeoSpace2d};
+use galileo_types::validate::{CoordinateValidation, ValidationReport};
+use std::sync::Arc;
+
+/// Delimiters recognized when the delimiter is not set explicitly.
+const DELIMITERS: [char; 4] = [',', ';', '\t', '|'];
+
+/// Feature layer with the features read by [`CsvReader::read_latlon`].
+pub type CsvGeoLayer<S> = FeatureLayer<GeoPoint2d, CsvFeature<GeoPoint2d>, S, GeoSpace2d>;
+
+/// Feature layer with the features read by [`CsvReader::read_xy`].
+pub type CsvProjectedLayer<S> = FeatureLayer<Point2d, CsvFeature<Point2d>, S, CartesianSpace2d>;
+
+/// Point feature read from a row of a CSV table.
+#[derive(Debug, Clone)]
+pub struct CsvFeature<P> {
+    point: P,
+    columns: Arc<[String]>,
+    values: Vec<Option<AttributeValue>>,
+}
+
+impl<P> CsvFeature<P> {
+    /// Location of the feature.
+    pub fn point(&self) -> &P {
+        &self.point
+    }
+
+    /// Returns the value of the given column (other than the coordinate columns). Returns `None` if the column does not
+    /// exist or the value is empty.
+    pub fn property(&self, name: &str) -> Option<&AttributeValue> {
+        let index = self.columns.iter().position(|column| column == name)?;
+        self.values[index].as_ref()
+    }
+}
+
+impl<P: Geometry> Feature for CsvFeature<P> {
+    type Geom = P;
+
+    fn geometry(&self) -> &Self::Geom {
+        &self.point
+    }
+
+    fn attribute(&self, name: &str) -> Option<AttributeValue> {
+        self.property(name).cloned()
+    }
+
+    fn attribute_names(&self) -> Vec<&str> {
+        self.columns
+            .iter()
+            .zip(&self.values)
+            .filter(|(_, value)| value.is_some())
+            .map(|(column, _)| column.as_str())
+            .collect()
+    }
+}
+
+/// Reads point features from CSV (or TSV) text. See [module documentation](self) for details.
+///
+/// The text must have a header row with the names of the columns. Values can be quoted with `"`, quoted values can
+/// contain delimiters, line breaks and quotes (doubled), as described in RFC 4180.
+#[derive(Debug, Clone, Default)]
+pub struct CsvReader {
+    delimiter: Option<char>,
+    validation: Option<CoordinateValidation>,
+}
+
+impl CsvReader {
+    /// Creates a new reader that detects the delimiter from the header row.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the delimiter of the values. By default, the most frequent of `,`, `;`, tab and `|` in the header row is
+    /// used.
+    pub fn with_delimiter(mut self, delimiter: char) -> Self {
+        self.delimiter = Some(delimiter);
+        self
+    }
+
+    /// Sets up checking of the coordinates of the rows. Rows with invalid coordinates are dropped or clamped according
+    /// to the [action](CoordinateValidation::action) of the validation, and the numbers of the invalid coordinates
+    /// are logged.
+    ///
+    /// If the domain of the validation is not set, the range of geographic coordinates is used by
+    /// [`CsvReader::read_latlon`], and no range check is done by [`CsvReader::read_xy`].
+    pub fn with_coordinate_validation(mut self, validation: CoordinateValidation) -> Self {
+        self.validation = Some(validation);
+        self
+    }
+
+    /// Reads features with the coordinates given in degrees of latitude and longitude in the given columns. Column
+    /// names are case-insensitive.
+    pub fn read_latlon(
+        &self,
+        text: &str,
+        lat_column: &str,
+        lon_column: &str,
+    ) -> Result<Vec<CsvFeature<GeoPoint2d>>, GalileoError> {
+        let validation = self.validation.map(|validation| match validation.domain {
+            Some(_) => validation,
+            None => validation.with_domain(CoordinateValidation::geographic().domain),
+        });
+        self.read(text, lon_column, lat_column, validation, |lon, lat| {
+            GeoPoint2d::latlon(lat, lon)
+        })
+    }
+
+    /// Reads features with the projected coordinates given in the `x_column` and `y_column`. Column names are
+    /// case-insensitive.
+    pub fn read_xy(
+        &self,
+        text: &str,
+        x_column: &str,
+        y_column: &str,
+    ) -> Result<Vec<CsvFeature<Point2d>>, GalileoError> {
+        self.read(text, x_column, y_column, self.validation, Point2d::new)
+    }
+
+    fn read<P>(
+        &self,
+        text: &str,
+        x_column: &str,
+        y_column: &str,
+        validation: Option<CoordinateValidation>,
+        new_point: impl Fn(f64, f64) -> P,
+    ) -> Result<Vec<CsvFeature<P>>, GalileoError> {
+        let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+        let delimiter = self.delimiter.unwrap_or_else(|| detect_delimiter(text));
+        let mut rows = parse_rows(text, delimiter).into_iter();
+        let header = rows
+            .next()
+            .ok_or_else(|| GalileoError::Generic("CSV table has no header row".into()))?;
+
+        let find_column = |name: &str| {
+            header
+                .iter()
+                .position(|column| column.trim().eq_ignore_ascii_case(name))
+                .ok_or_else(|| GalileoError::Generic(format!("CSV table has no column {name}")))
+        };
+        let x_index = find_column(x_column)?;
+        let y_index = find_column(y_column)?;
+
+        let attribute_indices: Vec<usize> = (0..header.len())
+            .filter(|index| *index != x_index && *index != y_index)
+            .collect();
+        let columns: Arc<[String]> = attribute_indices
+            .iter()
+            .map(|index| header[*index].trim().to_string())
+            .collect();
+
+        let rows: Vec<Vec<String>> = rows
+            .filter(|row| !(row.len() == 1 && row[0].trim().is_empty()))
+            .collect();
+        let column_types: Vec<ColumnType> = attribute_indices
+            .iter()
+            .map(|index| ColumnType::infer(rows.iter().filter_map(|row| row.get(*index))))
+            .collect();
+
+        let mut features = Vec::with_capacity(rows.len());
+        let mut skipped = 0;
+        let mut report = ValidationReport::default();
+        for row in &rows {
+            let coordinate = |index: usize| row.get(index)?.trim().parse::<f64>().ok();
+            let (Some(x), Some(y)) = (coordinate(x_index), coordinate(y_index)) else {
+                skipped += 1;
+                continue;
+            };
+
+            let (x, y) = match &validation {
+                Some(validation) => match validation.validate(x, y, &mut report) {
+                    Some(coordinates) => coordinates,
+                    None => {
+                        report.dropped_geometries += 1;
+                        continue;
+                    }
+                },
+                None => (x, y),
+            };
+
+            features.push(CsvFeature {
+                point: new_point(x, y),
+                columns: columns.clone(),
+                values: attribute_indices
+                    .iter()
+                    .zip(&column_types)
+                    .map(|(index, column_type)| column_type.parse(row.get(*index)?))
+                    .collect(),
+            });
+        }
+
+        if skipped > 0 {
+            log::warn!("{skipped} rows of the CSV table were skipped due to invalid coordinates");
+        }
+
+        if !report.is_clean() {
+            log::warn!("Invalid coordinates found in the CSV table: {report:?}");
+        }
+
+        Ok(features)
+    }
+}
+
+/// Type of the values of a column.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum ColumnType {
+    Number,
+    Bool,
+    String,
+}
+
+impl ColumnType {
+    fn infer<'a>(values: impl Iterator<Item = &'a String>) -> Self {
+        let mut is_number = true;
+        let mut is_bool = true;
+        for value in values.map(|v| v.trim()).filter(|v| !v.is_empty()) {
+            is_number = is_number && parse_number(value).is_some();
+            is_bool = is_bool && parse_bool(value).is_some();
+            if !is_number && !is_bool {
+                return Self::String;
+            }
+        }
+
+        match (is_number, is_bool) {
+            (true, _) => Self::Number,
+            (_, true) => Self::Bool,
+            _ => Self::String,
+        }
+    }
+
+    fn parse(&self, value: &str) -> Option<AttributeValue> {
+        let trimmed = value.trim();
+        if trimmed.is_empty() {
+            return None;
+        }
+
+        match self {
+            Self::Number => parse_number(trimmed).map(AttributeValue::Number),
+            Self::Bool => parse_bool(trimmed).map(AttributeValue::Bool),
+            Self::String => Some(AttributeValue::String(value.to_string())),
+        }
+    }
+}
+
+fn parse_number(value: &str) -> Option<f64> {
+    // Values like zip codes or identifiers with leading zeros are not numbers.
+    let digits = value.trim_start_matches(['-', '+']);
+    if digits.len() > 1 && digits.starts_with('0') && !digits.starts_with("0.") {
+        return None;
+    }
+
+    value.parse().ok().filter(|v: &f64| v.is_finite())
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    if value.eq_ignore_ascii_case("true") {
+        Some(true)
+    } else if value.eq_ignore_ascii_case("false") {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+fn detect_delimiter(text: &str) -> char {
+    let header = text.lines().next().unwrap_or_default();
+    DELIMITERS
+        .into_iter()
+        .max_by_key(|delimiter| header.matches(*delimiter).count())
+        .filter(|delimiter| header.contains(*delimiter))
+        .unwrap_or(',')
+}
+
+/// Splits the text into rows of values, handling quoted values.
+fn parse_rows(text: &str, delimiter: char) -> Vec<Vec<String>> {
+    let mut rows = vec![];
+    let mut row = vec![];
+    let mut value = String::new();
+    let mut in_quotes = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes => {
+                if chars.peek() == Some(&'"') {
+                    value.push('"');
+                    chars.next();
+                } else {
+                    in_quotes = false;
+                }
+            }
+            '"' if value.is_empty() => in_quotes = true,
+            '\r' | '\n' if !in_quotes => {
+                if c == '\r' && chars.peek() == Some(&'\n') {
+                    chars.next();
+                }
+
+                row.push(std::mem::take(&mut value));
+                rows.push(std::mem::take(&mut row));
+            }
+            c if c == delimiter && !in_quotes => row.push(std::mem::take(&mut value)),
+            c => value.push(c),
+        }
+    }
+
+    if !value.is_empty() || !row.is_empty() {
+        row.push(value);
+        rows.push(row);
+    }
+
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use galileo_types::geo::GeoPoint;
+    use galileo_types::validate::InvalidCoordinateAction;
+
+    #[test]
+    fn rows_with_quotes() {
+        let rows = parse_rows("a;\"b;\"\"c\"\"\"\r\n1;\"line\nbreak\"\n", ';');
+        assert_eq!(
+            rows,
+            vec![
+                vec!["a".to_string(), "b;\"c\"".to_string()],
+                vec!["1".to_string(), "line\nbreak".to_string()]
+            ]
+        );
+    }
+
+    #[test]
+    fn latlon_features_with_inferred_types() {
+        let text = "Name,Lat,Lon,Population,Capital,Zip\n\
+                    Rome,41.9,12.5,2800000,true,00118\n\
+                    Nowhere,,12.5,1,false,1\n\
+                    Milan,45.46,9.19,,false,20121\n";
+        let features = CsvReader::new()
+            .read_latlon(text, "lat", "lon")
+            .expect("valid table");
+
+        assert_eq!(features.len(), 2);
+        assert_eq!(features[0].point().lat(), 41.9);
+        assert_eq!(features[1].point().lon(), 9.19);
+        assert_eq!(
+            features[0].attribute("Population"),
+            Some(AttributeValue::Number(2_800_000.0))
+        );
+        assert_eq!(
+            features[0].attribute("Capital"),
+            Some(AttributeValue::Bool(true))
+        );
+        assert_eq!(features[0].attribute("Zip"), Some("00118".into()));
+        assert_eq!(features[1].attribute("Population"), None);
+        assert_eq!(
+            features[1].attribute_names(),
+            vec!["Name", "Capital", "Zip"]
+        );
+    }
+
+    #[test]
+    fn tsv_is_detected() {
+        let features = CsvReader::new()
+            .read_xy("x\ty\tid\n100\t200\ta\n", "X", "Y")
+            .expect("valid table");
+        assert_eq!(features.len(), 1);
+        assert_eq!(*features[0].point(), Point2d::new(100.0, 200.0));
+        assert_eq!(features[0].attribute("id"), Some("a".into()));
+    }
+
+    #[test]
+    fn invalid_coordinates_are_dropped() {
+        let text = "lat,lon\n45,10\n0,0\n95,10\nNaN,10\n";
+        let features = CsvReader::new()
+            .with_coordinate_validation(CoordinateValidation::default())
+            .read_latlon(text, "lat", "lon")
+            .expect("valid table");
+        assert_eq!(features.len(), 1);
+
+        let features = CsvReader::new()
+            .with_coordinate_validation(
+                CoordinateValidation::default().with_action(InvalidCoordinateAction::Clamp),
+            )
+            .read_latlon(text, "lat", "lon")
+            .expect("valid table");
+        assert_eq!(features.len(), 2);
+        assert_eq!(features[1].point().lat(), 90.0);
+    }
+
+    #[test]
+    fn missing_column_is_error() {
+        assert!(CsvReader::new()
+            .read_latlon("a,b\n1,2\n", "lat", "lon")
+            .is_err());
+    }
+}
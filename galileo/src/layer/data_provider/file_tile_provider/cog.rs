@@ -0,0 +1,626 @@
+use super::{AsyncFileSystem, TileFileSource};
+use crate::error::GalileoError;
+use crate::layer::data_provider::DataSource;
+use crate::tile_scheme::TileIndex;
+use bytes::Bytes;
+use std::ops::Range;
+
+/// Size of the first read of the file. Headers of most COG files fit into it.
+const HEADER_READ_SIZE: u64 = 16 * 1024;
+
+/// Reads of tiles that are closer to each other than this are merged into one read.
+const MAX_READ_GAP: u64 = 64 * 1024;
+
+/// Maximum size of a merged read.
+const MAX_MERGED_READ: u64 = 4 * 1024 * 1024;
+
+/// Merged byte range and the tiles (with their indices in the request) it contains.
+type ReadGroup = (Range<u64>, Vec<(usize, Range<u64>)>);
+
+/// Protection against cyclic IFD chains in broken files.
+const MAX_IFD_COUNT: usize = 64;
+
+/// Maximum number of entries in one IFD. Real files have a few dozens of them.
+const MAX_IFD_ENTRIES: u64 = 4096;
+
+/// Maximum size of a tag value in bytes, enough for the tile offsets of an image with 8 million tiles.
+const MAX_VALUE_LENGTH: u64 = 64 * 1024 * 1024;
+
+const TAG_NEW_SUBFILE_TYPE: u16 = 254;
+const TAG_IMAGE_WIDTH: u16 = 256;
+const TAG_IMAGE_LENGTH: u16 = 257;
+const TAG_COMPRESSION: u16 = 259;
+const TAG_TILE_WIDTH: u16 = 322;
+const TAG_TILE_LENGTH: u16 = 323;
+const TAG_TILE_OFFSETS: u16 = 324;
+const TAG_TILE_BYTE_COUNTS: u16 = 325;
+
+/// `NewSubfileType` flag of transparency mask images.
+const SUBFILE_MASK: u64 = 4;
+
+/// Tiles of a [Cloud Optimized GeoTIFF](https://cogeo.org/) file.
+///
+/// Every image of the file (the full resolution image and its overviews) is a zoom level: zoom level 0 is the
+/// smallest overview, and the full resolution image has the [`max_zoom`](CogTiles::max_zoom) level. Tile `x` and `y`
+/// are the column and the row of the tile in the image.
+///
+/// The tiles are returned as they are stored in the file, i.e. compressed with the
+/// [`compression`](CogTiles::compression) of the file. The decoder of the provider must decompress them. Tiles that
+/// are read together are fetched with a single range request when they are stored close to each other.
+pub struct CogTiles<Fs> {
+    fs: Fs,
+    path: String,
+    levels: Vec<CogLevel>,
+    compression: u16,
+}
+
+#[derive(Debug)]
+struct CogLevel {
+    width: u64,
+    height: u64,
+    tile_width: u64,
+    tile_height: u64,
+    offsets: Vec<u64>,
+    byte_counts: Vec<u64>,
+}
+
+impl CogLevel {
+    fn tiles_across(&self) -> u64 {
+        self.width.div_ceil(self.tile_width)
+    }
+
+    fn tiles_down(&self) -> u64 {
+        self.height.div_ceil(self.tile_height)
+    }
+}
+
+impl<Fs: AsyncFileSystem> CogTiles<Fs> {
+    /// Reads the headers of the COG file with the given path.
+    pub async fn open(fs: Fs, path: impl Into<String>) -> Result<Self, GalileoError> {
+        let path = path.into();
+        let header = fs.read_range(&path, 0..HEADER_READ_SIZE).await?;
+        let reader = TiffReader {
+            fs: &fs,
+            path: &path,
+            header,
+        };
+
+        let (levels, compression) = reader.read_levels().await?;
+        if levels.is_empty() {
+            return Err(decoding_error("file has no tiled images"));
+        }
+
+        Ok(Self {
+            fs,
+            path,
+            levels,
+            compression,
+        })
+    }
+
+    /// Zoom level of the full resolution image.
+    pub fn max_zoom(&self) -> u32 {
+        self.levels.len() as u32 - 1
+    }
+
+    /// Width and height of the tiles of the full resolution image in pixels.
+    pub fn tile_size(&self) -> (u32, u32) {
+        let level = &self.levels[self.levels.len() - 1];
+        (level.tile_width as u32, level.tile_height as u32)
+    }
+
+    /// Value of the TIFF `Compression` tag of the full resolution image, e.g. 1 for uncompressed data, 7 for JPEG or
+    /// 8 for Deflate.
+    pub fn compression(&self) -> u16 {
+        self.compression
+    }
+
+    fn tile_range(&self, index: TileIndex) -> Result<Range<u64>, GalileoError> {
+        let level = self
+            .levels
+            .get(index.z as usize)
+            .ok_or(GalileoError::NotFound)?;
+        let (Ok(x), Ok(y)) = (u64::try_from(index.x), u64::try_from(index.y)) else {
+            return Err(GalileoError::NotFound);
+        };
+        if x >= level.tiles_across() || y >= level.tiles_down() {
+            return Err(GalileoError::NotFound);
+        }
+
+        let number = y
+            .checked_mul(level.tiles_across())
+            .and_then(|n| n.checked_add(x))
+            .and_then(|n| usize::try_from(n).ok())
+            .ok_or(GalileoError::NotFound)?;
+        let (Some(&offset), Some(&byte_count)) =
+            (level.offsets.get(number), level.byte_counts.get(number))
+        else {
+            return Err(GalileoError::NotFound);
+        };
+
+        // Tiles that are not stored in the file have zero size.
+        if byte_count == 0 {
+            return Err(GalileoError::NotFound);
+        }
+
+        let end = offset
+            .checked_add(byte_count)
+            .ok_or_else(|| decoding_error("tile is out of the file bounds"))?;
+        Ok(offset..end)
+    }
+}
+
+impl<Fs: AsyncFileSystem> TileFileSource for CogTiles<Fs> {
+    async fn read_tile(&self, index: TileIndex) -> Result<Bytes, GalileoError> {
+        let range = self.tile_range(index)?;
+        self.fs.read_range(&self.path, range).await
+    }
+
+    async fn read_tiles(&self, indices: &[TileIndex]) -> Vec<Result<Bytes, GalileoError>> {
+        let mut results: Vec<Result<Bytes, GalileoError>> =
+            vec![Err(GalileoError::NotFound); indices.len()];
+
+        let mut ranges: Vec<(usize, Range<u64>)> = vec![];
+        for (i, index) in indices.iter().enumerate() {
+            match self.tile_range(*index) {
+                Ok(range) => ranges.push((i, range)),
+                Err(err) => results[i] = Err(err),
+            }
+        }
+        ranges.sort_by_key(|(_, range)| range.start);
+
+        // Tiles stored close to each other are read with one request.
+        let mut groups: Vec<ReadGroup> = vec![];
+        for (i, range) in ranges {
+            match groups.last_mut() {
+                Some((merged, tiles))
+                    if range.start <= merged.end + MAX_READ_GAP
+                        && range.end.max(merged.end) - merged.start <= MAX_MERGED_READ =>
+                {
+                    merged.end = merged.end.max(range.end);
+                    tiles.push((i, range));
+                }
+                _ => groups.push((range.clone(), vec![(i, range)])),
+            }
+        }
+
+        let reads = futures::future::join_all(
+            groups
+                .iter()
+                .map(|(merged, _)| self.fs.read_range(&self.path, merged.clone())),
+        )
+        .await;
+
+        for ((merged, tiles), read) in groups.into_iter().zip(reads) {
+            for (i, range) in tiles {
+                results[i] = match &read {
+                    Ok(bytes) => {
+                        let start = (range.start - merged.start) as usize;
+                        let end = (range.end - merged.start) as usize;
+                        if end <= bytes.len() {
+                            Ok(bytes.slice(start..end))
+                        } else {
+                            Err(decoding_error("tile data is outside of the file"))
+                        }
+                    }
+                    Err(err) => Err(err.clone()),
+                };
+            }
+        }
+
+        results
+    }
+
+    /// Tiles are read in the order they are stored in the file.
+    fn sort_for_reading(&self, indices: &mut [TileIndex]) {
+        indices.sort_by_key(|index| {
+            self.tile_range(*index)
+                .map(|range| range.start)
+                .unwrap_or(u64::MAX)
+        });
+    }
+
+    fn data_source(&self) -> DataSource {
+        self.fs.data_source()
+    }
+}
+
+fn decoding_error(message: &str) -> GalileoError {
+    GalileoError::Generic(format!("invalid COG file: {message}"))
+}
+
+/// Reads TIFF structures, using the first bytes of the file read on opening when possible.
+struct TiffReader<'a, Fs> {
+    fs: &'a Fs,
+    path: &'a str,
+    header: Bytes,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct TiffFormat {
+    little_endian: bool,
+    big_tiff: bool,
+}
+
+impl<Fs: AsyncFileSystem> TiffReader<'_, Fs> {
+    async fn read(&self, offset: u64, len: u64) -> Result<Bytes, GalileoError> {
+        let end = offset
+            .checked_add(len)
+            .ok_or_else(|| decoding_error("data is out of the file bounds"))?;
+        if end <= self.header.len() as u64 {
+            return Ok(self.header.slice(offset as usize..end as usize));
+        }
+
+        let bytes = self.fs.read_range(self.path, offset..end).await?;
+        if (bytes.len() as u64) < len {
+            return Err(decoding_error("unexpected end of file"));
+        }
+
+        Ok(bytes)
+    }
+
+    async fn read_levels(&self) -> Result<(Vec<CogLevel>, u16), GalileoError> {
+        let header = self.read(0, 16).await?;
+        let little_endian = match &header[0..2] {
+            b"II" => true,
+            b"MM" => false,
+            _ => return Err(decoding_error("not a TIFF file")),
+        };
+        let mut format = TiffFormat {
+            little_endian,
+            big_tiff: false,
+        };
+
+        let mut next_ifd = match format.u16(&header[2..4]) {
+            42 => format.u32(&header[4..8]) as u64,
+            43 => {
+                format.big_tiff = true;
+                format.u64(&header[8..16])
+            }
+            _ => return Err(decoding_error("not a TIFF file")),
+        };
+
+        let mut levels = vec![];
+        let mut compression = 1;
+        let mut ifd_count = 0;
+        while next_ifd != 0 && ifd_count < MAX_IFD_COUNT {
+            ifd_count += 1;
+            let (entries, next) = self.read_ifd(format, next_ifd).await?;
+            next_ifd = next;
+
+            let mut subfile_type = 0;
+            let mut level = CogLevel {
+                width: 0,
+                height: 0,
+                tile_width: 0,
+                tile_height: 0,
+                offsets: vec![],
+                byte_counts: vec![],
+            };
+            let mut image_compression = 1;
+            for entry in &entries {
+                match entry.tag {
+                    TAG_NEW_SUBFILE_TYPE => subfile_type = self.first_value(format, entry).await?,
+                    TAG_IMAGE_WIDTH => level.width = self.first_value(format, entry).await?,
+                    TAG_IMAGE_LENGTH => level.height = self.first_value(format, entry).await?,
+                    TAG_COMPRESSION => {
+                        image_compression = self.first_value(format, entry).await? as u16
+                    }
+                    TAG_TILE_WIDTH => level.tile_width = self.first_value(format, entry).await?,
+                    TAG_TILE_LENGTH => level.tile_height = self.first_value(format, entry).await?,
+                    TAG_TILE_OFFSETS => level.offsets = self.values(format, entry).await?,
+                    TAG_TILE_BYTE_COUNTS => level.byte_counts = self.values(format, entry).await?,
+                    _ => {}
+                }
+            }
+
+            if subfile_type & SUBFILE_MASK != 0 || level.tile_width == 0 || level.tile_height == 0 {
+                continue;
+            }
+
+            if levels.is_empty() {
+                compression = image_compression;
+            }
+
+            levels.push(level);
+        }
+
+        levels.sort_by_key(|level| level.width);
+        Ok((levels, compression))
+    }
+
+    async fn read_ifd(
+        &self,
+        format: TiffFormat,
+        offset: u64,
+    ) -> Result<(Vec<IfdEntry>, u64), GalileoError> {
+        let (count_size, entry_size, next_size) = if format.big_tiff {
+            (8, 20, 8)
+        } else {
+            (2, 12, 4)
+        };
+
+        let count_bytes = self.read(offset, count_size).await?;
+        let count = if format.big_tiff {
+            format.u64(&count_bytes)
+        } else {
+            format.u16(&count_bytes) as u64
+        };
+        if count > MAX_IFD_ENTRIES {
+            return Err(decoding_error("too many IFD entries"));
+        }
+
+        // The entry count is limited, so the sizes cannot overflow.
+        let entries_size = count * entry_size;
+        let entries_offset = offset
+            .checked_add(count_size)
+            .ok_or_else(|| decoding_error("IFD is out of the file bounds"))?;
+        let bytes = self.read(entries_offset, entries_size + next_size).await?;
+        let entries = bytes[..entries_size as usize]
+            .chunks_exact(entry_size as usize)
+            .map(|entry| {
+                let value_size = if format.big_tiff { 8 } else { 4 };
+                IfdEntry {
+                    tag: format.u16(&entry[0..2]),
+                    field_type: format.u16(&entry[2..4]),
+                    count: if format.big_tiff {
+                        format.u64(&entry[4..12])
+                    } else {
+                        format.u32(&entry[4..8]) as u64
+                    },
+                    value: Bytes::copy_from_slice(&entry[entry.len() - value_size..]),
+                }
+            })
+            .collect();
+
+        let next_bytes = &bytes[entries_size as usize..];
+        let next = if format.big_tiff {
+            format.u64(next_bytes)
+        } else {
+            format.u32(next_bytes) as u64
+        };
+
+        Ok((entries, next))
+    }
+
+    async fn first_value(&self, format: TiffFormat, entry: &IfdEntry) -> Result<u64, GalileoError> {
+        self.values(format, entry)
+            .await?
+            .first()
+            .copied()
+            .ok_or_else(|| decoding_error("empty tag value"))
+    }
+
+    async fn values(&self, format: TiffFormat, entry: &IfdEntry) -> Result<Vec<u64>, GalileoError> {
+        let value_size = match entry.field_type {
+            // BYTE
+            1 => 1,
+            // SHORT
+            3 => 2,
+            // LONG, IFD
+            4 | 13 => 4,
+            // LONG8, IFD8
+            16 | 18 => 8,
+            _ => return Err(decoding_error("unsupported tag type")),
+        };
+
+        let len = entry
+            .count
+            .checked_mul(value_size)
+            .filter(|len| *len <= MAX_VALUE_LENGTH)
+            .ok_or_else(|| decoding_error("tag value is too large"))?;
+        let bytes = if len <= entry.value.len() as u64 {
+            entry.value.slice(..len as usize)
+        } else {
+            let offset = if format.big_tiff {
+                format.u64(&entry.value)
+            } else {
+                format.u32(&entry.value) as u64
+            };
+            self.read(offset, len).await?
+        };
+
+        Ok(bytes
+            .chunks_exact(value_size as usize)
+            .map(|value| match value_size {
+                1 => value[0] as u64,
+                2 => format.u16(value) as u64,
+                4 => format.u32(value) as u64,
+                _ => format.u64(value),
+            })
+            .collect())
+    }
+}
+
+struct IfdEntry {
+    tag: u16,
+    field_type: u16,
+    count: u64,
+    /// Value of the entry if it fits into the entry, or the offset of the value otherwise.
+    value: Bytes,
+}
+
+impl TiffFormat {
+    fn u16(&self, bytes: &[u8]) -> u16 {
+        let bytes = [bytes[0], bytes[1]];
+        if self.little_endian {
+            u16::from_le_bytes(bytes)
+        } else {
+            u16::from_be_bytes(bytes)
+        }
+    }
+
+    fn u32(&self, bytes: &[u8]) -> u32 {
+        let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+        if self.little_endian {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        }
+    }
+
+    fn u64(&self, bytes: &[u8]) -> u64 {
+        let bytes: [u8; 8] = bytes[..8].try_into().expect("slice has 8 bytes");
+        if self.little_endian {
+            u64::from_le_bytes(bytes)
+        } else {
+            u64::from_be_bytes(bytes)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// File system with a single file, that counts the reads.
+    struct MemoryFs {
+        data: Bytes,
+        reads: AtomicUsize,
+    }
+
+    impl AsyncFileSystem for MemoryFs {
+        async fn read(&self, _path: &str) -> Result<Bytes, GalileoError> {
+            self.reads.fetch_add(1, Ordering::Relaxed);
+            Ok(self.data.clone())
+        }
+    }
+
+    /// Writes a little-endian classic TIFF with a 512x512 image of 256x256 tiles and its 256x256 overview. Every tile
+    /// contains 4 bytes with the tile number in the file.
+    fn test_tiff() -> Vec<u8> {
+        fn entry(data: &mut Vec<u8>, tag: u16, field_type: u16, count: u32, value: u32) {
+            data.extend_from_slice(&tag.to_le_bytes());
+            data.extend_from_slice(&field_type.to_le_bytes());
+            data.extend_from_slice(&count.to_le_bytes());
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+
+        let mut data = b"II".to_vec();
+        data.extend_from_slice(&42u16.to_le_bytes());
+        data.extend_from_slice(&8u32.to_le_bytes());
+
+        // Full resolution image: 9 entries, offsets and byte counts arrays of 4 values after the IFD.
+        let ifd_size = 2 + 9 * 12 + 4;
+        let offsets_at = 8 + ifd_size;
+        let counts_at = offsets_at + 16;
+        let overview_at = counts_at + 16;
+        let overview_size = 2 + 8 * 12 + 4;
+        let tiles_at = overview_at + overview_size;
+
+        data.extend_from_slice(&9u16.to_le_bytes());
+        entry(&mut data, TAG_NEW_SUBFILE_TYPE, 4, 1, 0);
+        entry(&mut data, TAG_IMAGE_WIDTH, 3, 1, 512);
+        entry(&mut data, TAG_IMAGE_LENGTH, 3, 1, 512);
+        entry(&mut data, TAG_COMPRESSION, 3, 1, 8);
+        entry(&mut data, TAG_TILE_WIDTH, 3, 1, 256);
+        entry(&mut data, TAG_TILE_LENGTH, 3, 1, 256);
+        entry(&mut data, TAG_TILE_OFFSETS, 4, 4, offsets_at);
+        entry(&mut data, TAG_TILE_BYTE_COUNTS, 4, 4, counts_at);
+        entry(&mut data, 277, 3, 1, 1);
+        data.extend_from_slice(&overview_at.to_le_bytes());
+
+        for tile in 0..4u32 {
+            data.extend_from_slice(&(tiles_at + 4 + tile * 4).to_le_bytes());
+        }
+        for _ in 0..4 {
+            data.extend_from_slice(&4u32.to_le_bytes());
+        }
+
+        // Overview with one tile.
+        data.extend_from_slice(&8u16.to_le_bytes());
+        entry(&mut data, TAG_NEW_SUBFILE_TYPE, 4, 1, 1);
+        entry(&mut data, TAG_IMAGE_WIDTH, 3, 1, 256);
+        entry(&mut data, TAG_IMAGE_LENGTH, 3, 1, 256);
+        entry(&mut data, TAG_COMPRESSION, 3, 1, 8);
+        entry(&mut data, TAG_TILE_WIDTH, 3, 1, 256);
+        entry(&mut data, TAG_TILE_LENGTH, 3, 1, 256);
+        entry(&mut data, TAG_TILE_OFFSETS, 4, 1, tiles_at);
+        entry(&mut data, TAG_TILE_BYTE_COUNTS, 4, 1, 4);
+        data.extend_from_slice(&0u32.to_le_bytes());
+
+        assert_eq!(data.len() as u32, tiles_at);
+        for tile in 0..5u32 {
+            data.extend_from_slice(&tile.to_le_bytes());
+        }
+
+        data
+    }
+
+    #[test]
+    fn reads_cog_tiles() {
+        let fs = MemoryFs {
+            data: Bytes::from(test_tiff()),
+            reads: AtomicUsize::new(0),
+        };
+
+        tokio_test::block_on(async {
+            let cog = CogTiles::open(fs, "image.tif").await.expect("valid file");
+            assert_eq!(cog.max_zoom(), 1);
+            assert_eq!(cog.tile_size(), (256, 256));
+            assert_eq!(cog.compression(), 8);
+
+            assert_eq!(
+                cog.read_tile(TileIndex::new(0, 0, 0))
+                    .await
+                    .expect("tile exists"),
+                Bytes::from(0u32.to_le_bytes().to_vec())
+            );
+            assert!(matches!(
+                cog.read_tile(TileIndex::new(2, 0, 1)).await,
+                Err(GalileoError::NotFound)
+            ));
+
+            cog.fs.reads.store(0, Ordering::Relaxed);
+            let mut indices: Vec<_> = (0..4).map(|i| TileIndex::new(i % 2, i / 2, 1)).collect();
+            indices.reverse();
+            let tiles = cog.read_tiles(&indices).await;
+            assert_eq!(cog.fs.reads.load(Ordering::Relaxed), 1);
+            for (index, tile) in indices.iter().zip(tiles) {
+                let number = (index.y * 2 + index.x + 1) as u32;
+                assert_eq!(
+                    tile.expect("tile exists"),
+                    Bytes::from(number.to_le_bytes().to_vec())
+                );
+            }
+        });
+    }
+
+    #[test]
+    fn rejects_corrupt_files() {
+        let open_error = |data: Vec<u8>| {
+            let fs = MemoryFs {
+                data: Bytes::from(data),
+                reads: AtomicUsize::new(0),
+            };
+            match tokio_test::block_on(CogTiles::open(fs, "image.tif")) {
+                Err(GalileoError::Generic(message)) => message,
+                _ => panic!("file must be rejected"),
+            }
+        };
+
+        // Tile offsets tag with 4 billion values.
+        let mut data = test_tiff();
+        let count_at = 8 + 2 + 6 * 12 + 4;
+        data[count_at..count_at + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(open_error(data).contains("tag value is too large"));
+
+        // BigTIFF with an IFD near the end of the address space.
+        let mut data = b"II".to_vec();
+        data.extend_from_slice(&43u16.to_le_bytes());
+        data.extend_from_slice(&8u16.to_le_bytes());
+        data.extend_from_slice(&0u16.to_le_bytes());
+        data.extend_from_slice(&(u64::MAX - 4).to_le_bytes());
+        assert!(open_error(data).contains("out of the file bounds"));
+
+        // BigTIFF with a huge number of IFD entries.
+        let mut data = b"II".to_vec();
+        data.extend_from_slice(&43u16.to_le_bytes());
+        data.extend_from_slice(&8u16.to_le_bytes());
+        data.extend_from_slice(&0u16.to_le_bytes());
+        data.extend_from_slice(&16u64.to_le_bytes());
+        data.extend_from_slice(&u64::MAX.to_le_bytes());
+        assert!(open_error(data).contains("too many IFD entries"));
+    }
+}
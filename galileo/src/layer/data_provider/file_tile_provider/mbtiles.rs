@@ -0,0 +1,120 @@
+use super::TileFileSource;
+use crate::error::GalileoError;
+use crate::tile_scheme::TileIndex;
+use bytes::Bytes;
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// Highest zoom level, number of rows of which fits into the `i64` row column of the archive.
+const MAX_ZOOM: u32 = 62;
+
+/// Tiles of an [MBTiles](https://github.com/mapbox/mbtiles-spec) archive.
+///
+/// Tile indices follow the XYZ scheme used by the rest of the library. Rows are converted into the TMS scheme of the
+/// archive when reading. Tiles are returned as they are stored in the archive (e.g. PNG images or gzipped vector
+/// tiles).
+pub struct MbTiles {
+    connection: Arc<Mutex<Connection>>,
+}
+
+impl MbTiles {
+    /// Opens the archive file for reading.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, GalileoError> {
+        let connection = Connection::open_with_flags(
+            path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )
+        .map_err(sqlite_error)?;
+        Ok(Self::new(connection))
+    }
+
+    /// Creates a source reading the archive through the given connection.
+    pub fn new(connection: Connection) -> Self {
+        Self {
+            connection: Arc::new(Mutex::new(connection)),
+        }
+    }
+}
+
+impl TileFileSource for MbTiles {
+    async fn read_tile(&self, index: TileIndex) -> Result<Bytes, GalileoError> {
+        self.read_tiles(&[index])
+            .await
+            .pop()
+            .unwrap_or(Err(GalileoError::NotFound))
+    }
+
+    /// All the tiles are read with one blocking task.
+    async fn read_tiles(&self, indices: &[TileIndex]) -> Vec<Result<Bytes, GalileoError>> {
+        let connection = self.connection.clone();
+        let indices = indices.to_vec();
+        let count = indices.len();
+        tokio::task::spawn_blocking(move || {
+            let connection = connection.lock().expect("mutex is poisoned");
+            let mut statement = match connection.prepare_cached(
+                "SELECT tile_data FROM tiles WHERE zoom_level = ?1 AND tile_column = ?2 AND tile_row = ?3",
+            ) {
+                Ok(statement) => statement,
+                Err(err) => return vec![Err(sqlite_error(err)); indices.len()],
+            };
+
+            indices
+                .iter()
+                .map(|index| {
+                    if index.z > MAX_ZOOM {
+                        return Err(GalileoError::NotFound);
+                    }
+
+                    let row = (1i64 << index.z) - 1 - index.y as i64;
+                    statement
+                        .query_row(params![index.z, index.x, row], |row| row.get::<_, Vec<u8>>(0))
+                        .optional()
+                        .map_err(sqlite_error)?
+                        .map(Bytes::from)
+                        .ok_or(GalileoError::NotFound)
+                })
+                .collect()
+        })
+        .await
+        .unwrap_or_else(|_| vec![Err(GalileoError::FsIo); count])
+    }
+
+    /// Tiles are read in the order of the primary key of the `tiles` table.
+    fn sort_for_reading(&self, indices: &mut [TileIndex]) {
+        indices.sort_by_key(|index| (index.z, index.x, -index.y));
+    }
+}
+
+fn sqlite_error(err: rusqlite::Error) -> GalileoError {
+    GalileoError::Generic(format!("failed to read MBTiles archive: {err}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_mbtiles() {
+        let connection = Connection::open_in_memory().expect("in-memory database");
+        connection
+            .execute_batch(
+                "CREATE TABLE tiles (zoom_level INTEGER, tile_column INTEGER, tile_row INTEGER, tile_data BLOB);
+                 INSERT INTO tiles VALUES (1, 1, 1, x'0102');",
+            )
+            .expect("create tiles table");
+        let mbtiles = MbTiles::new(connection);
+
+        let tiles = tokio_test::block_on(
+            mbtiles.read_tiles(&[TileIndex::new(1, 0, 1), TileIndex::new(1, 1, 1)]),
+        );
+        assert_eq!(
+            tiles[0].as_ref().expect("tile exists"),
+            &Bytes::from(vec![1, 2])
+        );
+        assert!(matches!(tiles[1], Err(GalileoError::NotFound)));
+
+        let tiles = tokio_test::block_on(mbtiles.read_tiles(&[TileIndex::new(0, 0, 63)]));
+        assert!(matches!(tiles[0], Err(GalileoError::NotFound)));
+    }
+}
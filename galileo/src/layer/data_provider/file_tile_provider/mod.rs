@@ -0,0 +1,588 @@
+use crate::error::GalileoError;
+use crate::layer::data_provider::{DataProcessor, DataProvider, DataSource, LoadInfo, UrlSource};
+use crate::platform::{slice_range, PlatformService, PlatformServiceImpl};
+use crate::tile_scheme::TileIndex;
+use bytes::Bytes;
+use futures::channel::oneshot;
+use maybe_sync::{MaybeSend, MaybeSync};
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::ops::Range;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+mod cog;
+#[cfg(all(feature = "sqlite", not(target_arch = "wasm32")))]
+mod mbtiles;
+
+pub use cog::CogTiles;
+#[cfg(all(feature = "sqlite", not(target_arch = "wasm32")))]
+pub use mbtiles::MbTiles;
+
+/// Default number of files read in parallel by the read-ahead.
+const DEFAULT_BATCH_SIZE: usize = 8;
+
+/// Default maximum number of tiles kept in the read-ahead buffer.
+const DEFAULT_READ_AHEAD_LIMIT: usize = 256;
+
+/// Asynchronous read access to files.
+///
+/// File-based providers read their data through this trait, so the same provider logic works on native targets
+/// (see [`NativeFileSystem`]) and in browsers, where files are usually served by the web server (see
+/// [`UrlFileSystem`]).
+pub trait AsyncFileSystem: MaybeSend + MaybeSync {
+    /// Reads the whole content of the file with the given path.
+    ///
+    /// Returns [`GalileoError::NotFound`] if the file does not exist.
+    fn read(&self, path: &str) -> impl Future<Output = Result<Bytes, GalileoError>> + MaybeSend;
+
+    /// Reads the given byte range of the file. If the range extends past the end of the file, the returned data is
+    /// shorter.
+    ///
+    /// Default implementation reads the whole file and returns the requested part of it. Implementations should
+    /// override it if they can read parts of files, since file-based tile formats (like [COG](CogTiles)) read small
+    /// parts of large files.
+    fn read_range(
+        &self,
+        path: &str,
+        range: Range<u64>,
+    ) -> impl Future<Output = Result<Bytes, GalileoError>> + MaybeSend {
+        async move { Ok(slice_range(&self.read(path).await?, range)) }
+    }
+
+    /// Where the files are read from, reported in the [tile diagnostics](crate::layer::tile_diagnostics). Default
+    /// value is [`DataSource::Disk`].
+    fn data_source(&self) -> DataSource {
+        DataSource::Disk
+    }
+}
+
+/// Reads files from the local disk. Files are read in the blocking thread pool of the async runtime, so several
+/// files are read in parallel.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone)]
+pub struct NativeFileSystem {
+    root: PathBuf,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl NativeFileSystem {
+    /// Creates a new instance. Paths of the read files are resolved relative to the `root` folder.
+    pub fn new(root: impl AsRef<Path>) -> Self {
+        Self {
+            root: root.as_ref().into(),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl AsyncFileSystem for NativeFileSystem {
+    async fn read(&self, path: &str) -> Result<Bytes, GalileoError> {
+        let path = self.root.join(path);
+        let result = tokio::task::spawn_blocking(move || std::fs::read(path))
+            .await
+            .map_err(|_| GalileoError::FsIo)?;
+
+        match result {
+            Ok(bytes) => Ok(bytes.into()),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Err(GalileoError::NotFound),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn read_range(&self, path: &str, range: Range<u64>) -> Result<Bytes, GalileoError> {
+        use std::io::{Read, Seek, SeekFrom};
+
+        let path = self.root.join(path);
+        let result = tokio::task::spawn_blocking(move || {
+            let mut file = std::fs::File::open(path)?;
+            file.seek(SeekFrom::Start(range.start))?;
+            let mut bytes = vec![];
+            file.take(range.end.saturating_sub(range.start))
+                .read_to_end(&mut bytes)?;
+            Ok::<_, std::io::Error>(bytes)
+        })
+        .await
+        .map_err(|_| GalileoError::FsIo)?;
+
+        match result {
+            Ok(bytes) => Ok(bytes.into()),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Err(GalileoError::NotFound),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+/// Reads files over HTTP, relative to the given base URL. Can be used to serve the same file-based data on the web
+/// as on native targets.
+#[derive(Debug, Clone)]
+pub struct UrlFileSystem {
+    base_url: String,
+    platform_service: PlatformServiceImpl,
+}
+
+impl UrlFileSystem {
+    /// Creates a new instance. Paths of the read files are appended to the `base_url`.
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            platform_service: PlatformServiceImpl::new(),
+        }
+    }
+}
+
+impl AsyncFileSystem for UrlFileSystem {
+    async fn read(&self, path: &str) -> Result<Bytes, GalileoError> {
+        let url = format!("{}/{}", self.base_url, path.trim_start_matches('/'));
+        self.platform_service.load_bytes_from_url(&url).await
+    }
+
+    async fn read_range(&self, path: &str, range: Range<u64>) -> Result<Bytes, GalileoError> {
+        let url = format!("{}/{}", self.base_url, path.trim_start_matches('/'));
+        self.platform_service
+            .load_bytes_range_from_url(&url, range)
+            .await
+    }
+
+    fn data_source(&self) -> DataSource {
+        DataSource::Network
+    }
+}
+
+/// Storage of tiles that can be read by a [`FileTileProvider`].
+///
+/// The library provides these sources:
+/// * [`DirectoryTiles`] - tiles stored as separate files, e.g. in a `{z}/{x}/{y}.png` folder structure;
+/// * [`CogTiles`] - tiles of a [Cloud Optimized GeoTIFF](https://cogeo.org/) file;
+/// * [`MbTiles`] - tiles of an [MBTiles](https://github.com/mapbox/mbtiles-spec) archive (`sqlite` feature, not
+///   available in browsers).
+pub trait TileFileSource: MaybeSend + MaybeSync {
+    /// Reads the data of the tile.
+    ///
+    /// Returns [`GalileoError::NotFound`] if the source has no such tile.
+    fn read_tile(
+        &self,
+        index: TileIndex,
+    ) -> impl Future<Output = Result<Bytes, GalileoError>> + MaybeSend;
+
+    /// Reads the data of several tiles at once, returning the results in the same order as the `indices`. Used by the
+    /// read-ahead of the [`FileTileProvider`].
+    ///
+    /// Default implementation reads all the tiles in parallel with [`TileFileSource::read_tile`]. Sources that can
+    /// read several tiles faster with one request (e.g. by merging reads of neighbouring parts of a file) override
+    /// it.
+    fn read_tiles(
+        &self,
+        indices: &[TileIndex],
+    ) -> impl Future<Output = Vec<Result<Bytes, GalileoError>>> + MaybeSend {
+        futures::future::join_all(indices.iter().map(|index| self.read_tile(*index)))
+    }
+
+    /// Sorts the tiles in the order they are read by the read-ahead. Tiles that are stored close to each other
+    /// should be placed next to each other, so that they are read one after another.
+    ///
+    /// Default implementation sorts the tiles by zoom level, row and column.
+    fn sort_for_reading(&self, indices: &mut [TileIndex]) {
+        indices.sort_by_key(|index| (index.z, index.y, index.x));
+    }
+
+    /// Where the tiles are read from, reported in the [tile diagnostics](crate::layer::tile_diagnostics). Default
+    /// value is [`DataSource::Disk`].
+    fn data_source(&self) -> DataSource {
+        DataSource::Disk
+    }
+}
+
+/// Tiles stored as separate files (e.g. a `{z}/{x}/{y}.png` folder structure) of an [`AsyncFileSystem`].
+pub struct DirectoryTiles<Fs> {
+    fs: Fs,
+    path_source: Box<dyn UrlSource<TileIndex>>,
+}
+
+impl<Fs: AsyncFileSystem> DirectoryTiles<Fs> {
+    /// Creates a new source. `path_source` returns the path of the tile file relative to the root of the file
+    /// system.
+    pub fn new(fs: Fs, path_source: impl UrlSource<TileIndex> + 'static) -> Self {
+        Self {
+            fs,
+            path_source: Box::new(path_source),
+        }
+    }
+}
+
+impl<Fs: AsyncFileSystem> TileFileSource for DirectoryTiles<Fs> {
+    async fn read_tile(&self, index: TileIndex) -> Result<Bytes, GalileoError> {
+        self.fs.read(&(self.path_source)(&index)).await
+    }
+
+    /// Tiles are read in the order of their paths, so that neighbouring files are read one after another.
+    fn sort_for_reading(&self, indices: &mut [TileIndex]) {
+        indices.sort_by_cached_key(|index| (self.path_source)(index));
+    }
+
+    fn data_source(&self) -> DataSource {
+        self.fs.data_source()
+    }
+}
+
+/// Loads tiles from a [`TileFileSource`]: a folder of tile files, a COG file or an MBTiles archive.
+///
+/// # Read-ahead
+///
+/// Reading a lot of small tiles one by one is slow, especially from spinning disks and network shares. When a
+/// layer knows the list of tiles it needs for a view (see [`TileSchema::iter_tiles`](crate::TileSchema::iter_tiles)),
+/// it calls [`DataProvider::read_ahead`] with it. The provider then reads the tiles in the background, in batches of
+/// [`FileTileProvider::with_batch_size`] tiles (read in parallel or with a single request, depending on the source)
+/// and in the order the tiles are stored in (see [`TileFileSource::sort_for_reading`]).
+///
+/// Requests for tiles that are being read ahead wait for the read instead of reading the tile again. Tiles that were
+/// read ahead before they were requested are kept in memory until they are requested by [`DataProvider::load`].
+///
+/// ```no_run
+/// use galileo::layer::data_provider::{FileTileProvider, NativeFileSystem};
+/// use galileo::layer::RasterTileLayer;
+/// use galileo::tile_scheme::TileIndex;
+/// use galileo::TileSchema;
+/// # use galileo::decoded_image::DecodedImage;
+/// # use galileo::layer::data_provider::DataProcessor;
+/// # struct ImageDecoder;
+/// # impl DataProcessor for ImageDecoder {
+/// #     type Input = bytes::Bytes;
+/// #     type Output = DecodedImage;
+/// #     type Context = ();
+/// #     fn process(&self, input: bytes::Bytes, _: ()) -> Result<DecodedImage, galileo::error::GalileoError> {
+/// #         DecodedImage::new(&input)
+/// #     }
+/// # }
+///
+/// let provider = FileTileProvider::new(
+///     NativeFileSystem::new("/data/tiles"),
+///     |index: &TileIndex| format!("{}/{}/{}.png", index.z, index.x, index.y),
+///     ImageDecoder,
+/// );
+/// let layer = RasterTileLayer::new(TileSchema::web(18), provider, None);
+/// ```
+pub struct FileTileProvider<Source, Decoder> {
+    source: Arc<Source>,
+    decoder: Decoder,
+    buffer: Arc<Mutex<ReadAheadBuffer>>,
+    batch_size: usize,
+    read_ahead_limit: usize,
+}
+
+impl<Fs, Decoder> FileTileProvider<DirectoryTiles<Fs>, Decoder>
+where
+    Fs: AsyncFileSystem + 'static,
+    Decoder: DataProcessor<Input = Bytes>,
+{
+    /// Creates a new provider for tiles stored as separate files (see [`DirectoryTiles`]). `path_source` returns the
+    /// path of the tile file relative to the root of the file system.
+    pub fn new(fs: Fs, path_source: impl UrlSource<TileIndex> + 'static, decoder: Decoder) -> Self {
+        Self::from_source(DirectoryTiles::new(fs, path_source), decoder)
+    }
+}
+
+impl<Source, Decoder> FileTileProvider<Source, Decoder>
+where
+    Source: TileFileSource + 'static,
+    Decoder: DataProcessor<Input = Bytes>,
+{
+    /// Creates a new provider that reads the tiles from the given source.
+    pub fn from_source(source: Source, decoder: Decoder) -> Self {
+        Self {
+            source: Arc::new(source),
+            decoder,
+            buffer: Arc::default(),
+            batch_size: DEFAULT_BATCH_SIZE,
+            read_ahead_limit: DEFAULT_READ_AHEAD_LIMIT,
+        }
+    }
+
+    /// Sets the number of tiles read together by the read-ahead. Default value is 8.
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Sets the maximum number of tiles kept in memory by the read-ahead. When the limit is reached, the tiles read
+    /// earliest are dropped. Setting the limit to 0 disables the read-ahead. Default value is 256.
+    pub fn with_read_ahead_limit(mut self, limit: usize) -> Self {
+        self.read_ahead_limit = limit;
+        self
+    }
+
+    /// Tiles taken from the read-ahead buffer are reported as loaded from [memory](DataSource::Memory).
+    async fn load_raw_with_source(
+        &self,
+        key: &TileIndex,
+    ) -> Result<(Bytes, DataSource), GalileoError> {
+        let pending = {
+            let mut buffer = self.buffer.lock().expect("mutex is poisoned");
+            if let Some(bytes) = buffer.take(key) {
+                return Ok((bytes, DataSource::Memory));
+            }
+
+            buffer.wait_for(key)
+        };
+
+        if let Some(pending) = pending {
+            if let Ok(Some(bytes)) = pending.await {
+                return Ok((bytes, self.source.data_source()));
+            }
+
+            // The read-ahead failed, the tile is read again to get the error.
+        }
+
+        let bytes = self.source.read_tile(*key).await?;
+        Ok((bytes, self.source.data_source()))
+    }
+}
+
+impl<Source, Decoder> DataProvider<TileIndex, Decoder::Output, Decoder::Context>
+    for FileTileProvider<Source, Decoder>
+where
+    Source: TileFileSource + 'static,
+    Decoder: DataProcessor<Input = Bytes> + MaybeSend + MaybeSync,
+    Decoder::Context: MaybeSend + MaybeSync,
+{
+    async fn load_raw(&self, key: &TileIndex) -> Result<Bytes, GalileoError> {
+        self.load_raw_with_source(key).await.map(|(bytes, _)| bytes)
+    }
+
+    fn decode(
+        &self,
+        bytes: Bytes,
+        context: Decoder::Context,
+    ) -> Result<Decoder::Output, GalileoError> {
+        self.decoder.process(bytes, context)
+    }
+
+    async fn load_with_info(
+        &self,
+        key: &TileIndex,
+        context: Decoder::Context,
+    ) -> (Result<Decoder::Output, GalileoError>, LoadInfo) {
+        match self.load_raw_with_source(key).await {
+            Ok((bytes, source)) => {
+                let info = LoadInfo {
+                    source: Some(source),
+                    byte_size: Some(bytes.len()),
+                };
+                (self.decode(bytes, context), info)
+            }
+            Err(err) => (Err(err), LoadInfo::default()),
+        }
+    }
+
+    fn read_ahead(&self, keys: &[TileIndex]) {
+        let mut to_read: Vec<TileIndex> = {
+            let mut buffer = self.buffer.lock().expect("mutex is poisoned");
+            let available = self
+                .read_ahead_limit
+                .saturating_sub(buffer.tiles.len() + buffer.pending.len());
+            keys.iter()
+                .filter(|index| buffer.start_reading(**index))
+                .take(available)
+                .copied()
+                .collect()
+        };
+
+        if to_read.is_empty() {
+            return;
+        }
+
+        self.source.sort_for_reading(&mut to_read);
+
+        let source = self.source.clone();
+        let buffer = self.buffer.clone();
+        let batch_size = self.batch_size;
+        let limit = self.read_ahead_limit;
+        crate::async_runtime::spawn(async move {
+            for batch in to_read.chunks(batch_size) {
+                let results = source.read_tiles(batch).await;
+
+                let mut buffered = buffer.lock().expect("mutex is poisoned");
+                for (index, result) in batch.iter().zip(results) {
+                    buffered.finish_reading(*index, result.ok(), limit);
+                }
+            }
+        });
+    }
+}
+
+/// Tiles read ahead of the requests.
+#[derive(Debug, Default)]
+struct ReadAheadBuffer {
+    tiles: HashMap<TileIndex, Bytes>,
+    /// Order in which the tiles were read, used to drop the oldest tiles when the limit is reached.
+    order: VecDeque<TileIndex>,
+    /// Tiles that are being read at the moment, with the requests waiting for them.
+    pending: HashMap<TileIndex, Vec<oneshot::Sender<Option<Bytes>>>>,
+}
+
+impl ReadAheadBuffer {
+    /// Marks the tile as being read. Returns false if the tile is already read or being read.
+    fn start_reading(&mut self, index: TileIndex) -> bool {
+        if self.tiles.contains_key(&index) || self.pending.contains_key(&index) {
+            return false;
+        }
+
+        self.pending.insert(index, vec![]);
+        true
+    }
+
+    /// Passes the result of reading the tile to the requests waiting for it, or stores it in the buffer if the tile
+    /// was not requested yet.
+    fn finish_reading(&mut self, index: TileIndex, bytes: Option<Bytes>, limit: usize) {
+        let waiting = self.pending.remove(&index).unwrap_or_default();
+        if waiting.is_empty() {
+            if let Some(bytes) = bytes {
+                self.insert(index, bytes, limit);
+            }
+
+            return;
+        }
+
+        for sender in waiting {
+            let _ = sender.send(bytes.clone());
+        }
+    }
+
+    /// If the tile is being read, returns the receiver of the read result.
+    fn wait_for(&mut self, index: &TileIndex) -> Option<oneshot::Receiver<Option<Bytes>>> {
+        let waiting = self.pending.get_mut(index)?;
+        let (sender, receiver) = oneshot::channel();
+        waiting.push(sender);
+        Some(receiver)
+    }
+
+    fn insert(&mut self, index: TileIndex, bytes: Bytes, limit: usize) {
+        while self.tiles.len() >= limit {
+            let Some(oldest) = self.order.pop_front() else {
+                break;
+            };
+            self.tiles.remove(&oldest);
+        }
+
+        if limit > 0 && self.tiles.insert(index, bytes).is_none() {
+            self.order.push_back(index);
+        }
+    }
+
+    fn take(&mut self, index: &TileIndex) -> Option<Bytes> {
+        let bytes = self.tiles.remove(index)?;
+        self.order.retain(|v| v != index);
+        Some(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// File system with a single file of every path, that counts the reads.
+    #[derive(Default)]
+    struct CountingFs {
+        reads: AtomicUsize,
+    }
+
+    impl AsyncFileSystem for CountingFs {
+        async fn read(&self, path: &str) -> Result<Bytes, GalileoError> {
+            self.reads.fetch_add(1, Ordering::Relaxed);
+            Ok(Bytes::from(path.as_bytes().to_vec()))
+        }
+    }
+
+    struct RawBytes;
+
+    impl DataProcessor for RawBytes {
+        type Input = Bytes;
+        type Output = Bytes;
+        type Context = ();
+
+        fn process(&self, input: Bytes, _context: ()) -> Result<Bytes, GalileoError> {
+            Ok(input)
+        }
+    }
+
+    #[test]
+    fn read_ahead_buffer_drops_oldest_tiles() {
+        let mut buffer = ReadAheadBuffer::default();
+        for x in 0..3 {
+            buffer.insert(TileIndex::new(x, 0, 2), Bytes::from(vec![x as u8]), 2);
+        }
+
+        assert_eq!(buffer.take(&TileIndex::new(0, 0, 2)), None);
+        assert_eq!(
+            buffer.take(&TileIndex::new(1, 0, 2)),
+            Some(Bytes::from(vec![1]))
+        );
+        assert_eq!(buffer.take(&TileIndex::new(1, 0, 2)), None);
+        assert_eq!(buffer.tiles.len(), 1);
+    }
+
+    #[test]
+    fn requests_wait_for_read_ahead() {
+        let provider = FileTileProvider::new(
+            CountingFs::default(),
+            |index: &TileIndex| format!("{}/{}/{}", index.z, index.x, index.y),
+            RawBytes,
+        );
+        let indices = [TileIndex::new(0, 0, 1), TileIndex::new(1, 0, 1)];
+
+        let sources = tokio_test::block_on(async {
+            provider.read_ahead(&indices);
+            let mut sources = vec![];
+            for index in &indices {
+                let (result, info) = provider.load_with_info(index, ()).await;
+                assert_eq!(
+                    result.expect("tile is loaded"),
+                    Bytes::from(format!("1/{}/0", index.x))
+                );
+                sources.push(info.source);
+            }
+
+            sources
+        });
+
+        // The first request waits for the read-ahead, the second one takes the tile from the buffer.
+        assert_eq!(sources, [Some(DataSource::Disk), Some(DataSource::Memory)]);
+
+        assert_eq!(provider.source.fs.reads.load(Ordering::Relaxed), 2);
+        let buffer = provider.buffer.lock().expect("mutex is poisoned");
+        assert!(buffer.tiles.is_empty());
+        assert!(buffer.pending.is_empty());
+    }
+
+    #[test]
+    fn native_file_system_reads_files() {
+        let root = std::env::temp_dir().join(format!(
+            "galileo_native_file_system_test_{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(root.join("2/1")).expect("create folder");
+        std::fs::write(root.join("2/1/3.bin"), [1, 2, 3]).expect("write file");
+
+        let fs = NativeFileSystem::new(&root);
+        tokio_test::block_on(async {
+            assert_eq!(
+                fs.read("2/1/3.bin").await.expect("file exists"),
+                Bytes::from(vec![1, 2, 3])
+            );
+            assert_eq!(
+                fs.read_range("2/1/3.bin", 1..10)
+                    .await
+                    .expect("file exists"),
+                Bytes::from(vec![2, 3])
+            );
+            assert!(matches!(
+                fs.read("2/1/4.bin").await,
+                Err(GalileoError::NotFound)
+            ));
+        });
+
+        std::fs::remove_dir_all(&root).expect("remove folder");
+    }
+}
@@ -0,0 +1,612 @@
+//! Loading features from [GeoParquet](https://geoparquet.org) files.
+//!
+//! [`GeoParquetReader`] reads the features of a GeoParquet file into [`GeoParquetFeature`]s, which can be added to a
+//! [`FeatureLayer`](crate::layer::FeatureLayer) with [`Crs::WGS84`](galileo_types::geo::Crs::WGS84). To reduce the
+//! amount of data read from large files:
+//! * only the columns listed with [`GeoParquetReader::with_columns`] are read (the geometry column is always read);
+//! * with [`GeoParquetReader::with_bbox`], row groups whose bounding box (taken from the statistics of the `bbox`
+//!   covering column, if the file has one) does not intersect the given one are skipped entirely, and the features of
+//!   other row groups are filtered by their geometry.
+//!
+//! Only WKB geometry encoding and geographic coordinates (`OGC:CRS84`, which is the default CRS of GeoParquet) are
+//! supported.
+//!
+//! ```no_run
+//! use galileo::layer::data_provider::geoparquet::GeoParquetReader;
+//! use galileo::layer::FeatureLayer;
+//! use galileo::symbol::SimplePolygonSymbol;
+//! use galileo::Color;
+//! use galileo_types::cartesian::Rect;
+//! use galileo_types::geo::Crs;
+//!
+//! let features = GeoParquetReader::open("buildings.parquet")?
+//!     .with_columns(&["height"])
+//!     .with_bbox(Rect::new(13.3, 52.4, 13.5, 52.6))
+//!     .read()?;
+//! let layer = FeatureLayer::new(features, SimplePolygonSymbol::new(Color::BLUE), Crs::WGS84);
+//! # Ok::<(), galileo::error::GalileoError>(())
+//! ```
+
+use crate::error::GalileoError;
+use crate::layer::feature_layer::{AttributeValue, Feature};
+use arrow_array::cast::AsArray;
+use arrow_array::types::{
+    Float32Type, Float64Type, Int16Type, Int32Type, Int64Type, Int8Type, UInt16Type, UInt32Type,
+    UInt64Type, UInt8Type,
+};
+use arrow_array::{Array, RecordBatch};
+use arrow_schema::DataType;
+use galileo_types::cartesian::Rect;
+use galileo_types::geo::impls::GeoPoint2d;
+use galileo_types::geo::NewGeoPoint;
+use galileo_types::geometry::Geom;
+use galileo_types::wkb::decode_wkb;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::arrow::ProjectionMask;
+use parquet::file::metadata::RowGroupMetaData;
+use parquet::file::reader::ChunkReader;
+use parquet::file::statistics::Statistics;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::cell::Cell;
+use std::collections::HashMap;
+
+/// Feature read from a GeoParquet file.
+#[derive(Debug, Clone)]
+pub struct GeoParquetFeature {
+    geometry: Geom<GeoPoint2d>,
+    /// Values of the non-geometry columns of the feature row.
+    pub properties: Map<String, Value>,
+}
+
+impl GeoParquetFeature {
+    /// Returns the value of the given column.
+    pub fn property(&self, name: &str) -> Option<&Value> {
+        self.properties.get(name)
+    }
+}
+
+impl Feature for GeoParquetFeature {
+    type Geom = Geom<GeoPoint2d>;
+
+    fn geometry(&self) -> &Self::Geom {
+        &self.geometry
+    }
+
+    fn attribute(&self, name: &str) -> Option<AttributeValue> {
+        match self.property(name)? {
+            Value::Null => None,
+            Value::Bool(v) => Some(AttributeValue::Bool(*v)),
+            Value::Number(v) => v.as_f64().map(AttributeValue::Number),
+            Value::String(v) => Some(AttributeValue::String(v.clone())),
+            v @ (Value::Array(_) | Value::Object(_)) => Some(AttributeValue::String(v.to_string())),
+        }
+    }
+
+    fn attribute_names(&self) -> Vec<&str> {
+        self.properties.keys().map(String::as_str).collect()
+    }
+}
+
+/// GeoParquet file metadata (the `geo` key of the file key-value metadata).
+#[derive(Debug, Clone, Deserialize)]
+pub struct GeoParquetMetadata {
+    /// Version of the GeoParquet specification.
+    pub version: String,
+    /// Name of the primary geometry column.
+    pub primary_column: String,
+    /// Metadata of the geometry columns.
+    pub columns: HashMap<String, GeoParquetColumn>,
+}
+
+/// Metadata of a geometry column.
+#[derive(Debug, Clone, Deserialize)]
+pub struct GeoParquetColumn {
+    /// Encoding of the geometries.
+    pub encoding: String,
+    /// PROJJSON definition of the CRS. If not set, `OGC:CRS84` is used.
+    #[serde(default)]
+    pub crs: Option<Value>,
+    /// Bounding box of all the geometries of the column.
+    #[serde(default)]
+    pub bbox: Option<Vec<f64>>,
+    /// Columns that contain simplified representations of the geometries.
+    #[serde(default)]
+    pub covering: Option<GeoParquetCovering>,
+}
+
+/// Columns with simplified representations of the geometries.
+#[derive(Debug, Clone, Deserialize)]
+pub struct GeoParquetCovering {
+    /// Paths to the bounding box columns of the geometries.
+    pub bbox: GeoParquetBboxCovering,
+}
+
+/// Paths to the bounding box columns of the geometries, e.g. `["bbox", "xmin"]`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct GeoParquetBboxCovering {
+    /// Path to the minimum X column.
+    pub xmin: Vec<String>,
+    /// Path to the minimum Y column.
+    pub ymin: Vec<String>,
+    /// Path to the maximum X column.
+    pub xmax: Vec<String>,
+    /// Path to the maximum Y column.
+    pub ymax: Vec<String>,
+}
+
+impl GeoParquetColumn {
+    fn is_geographic(&self) -> bool {
+        let Some(crs) = &self.crs else {
+            return true;
+        };
+
+        let Some(id) = crs.get("id") else {
+            return false;
+        };
+
+        matches!(
+            (
+                id.get("authority").and_then(Value::as_str),
+                id.get("code")
+                    .map(|code| code.to_string().trim_matches('"').to_string())
+                    .as_deref()
+            ),
+            (Some("OGC"), Some("CRS84")) | (Some("EPSG"), Some("4326"))
+        )
+    }
+}
+
+/// Reads features from a GeoParquet file.
+pub struct GeoParquetReader<R: ChunkReader> {
+    builder: ParquetRecordBatchReaderBuilder<R>,
+    metadata: GeoParquetMetadata,
+    columns: Option<Vec<String>>,
+    bbox: Option<Rect>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl GeoParquetReader<std::fs::File> {
+    /// Opens the file at the given path.
+    pub fn open(path: impl AsRef<std::path::Path>) -> Result<Self, GalileoError> {
+        Self::new(std::fs::File::open(path)?)
+    }
+}
+
+impl<R: ChunkReader + 'static> GeoParquetReader<R> {
+    /// Creates a new reader. Returns an error if the data is not a valid GeoParquet file, or if the primary geometry
+    /// column is not supported.
+    pub fn new(reader: R) -> Result<Self, GalileoError> {
+        let builder = ParquetRecordBatchReaderBuilder::try_new(reader).map_err(parquet_error)?;
+
+        let geo = builder
+            .metadata()
+            .file_metadata()
+            .key_value_metadata()
+            .and_then(|kv| kv.iter().find(|entry| entry.key == "geo"))
+            .and_then(|entry| entry.value.as_deref())
+            .ok_or_else(|| GalileoError::Generic("file has no GeoParquet metadata".into()))?;
+        let metadata: GeoParquetMetadata = serde_json::from_str(geo)
+            .map_err(|err| GalileoError::Generic(format!("invalid GeoParquet metadata: {err}")))?;
+
+        let column = metadata
+            .columns
+            .get(&metadata.primary_column)
+            .ok_or_else(|| GalileoError::Generic("primary geometry column not found".into()))?;
+        if column.encoding != "WKB" {
+            return Err(GalileoError::Generic(format!(
+                "geometry encoding {} is not supported",
+                column.encoding
+            )));
+        }
+
+        if !column.is_geographic() {
+            return Err(GalileoError::Generic(
+                "only geographic (OGC:CRS84) GeoParquet files are supported".into(),
+            ));
+        }
+
+        Ok(Self {
+            builder,
+            metadata,
+            columns: None,
+            bbox: None,
+        })
+    }
+
+    /// GeoParquet metadata of the file.
+    pub fn metadata(&self) -> &GeoParquetMetadata {
+        &self.metadata
+    }
+
+    /// Only reads the given columns (in addition to the geometry column) into the feature properties.
+    pub fn with_columns(mut self, columns: &[&str]) -> Self {
+        self.columns = Some(columns.iter().map(|c| c.to_string()).collect());
+        self
+    }
+
+    /// Only reads the features intersecting the given bounding box (in longitude/latitude).
+    pub fn with_bbox(mut self, bbox: Rect) -> Self {
+        self.bbox = Some(bbox);
+        self
+    }
+
+    /// Reads the features. Rows with invalid geometries are skipped with a warning in the log.
+    pub fn read(self) -> Result<Vec<GeoParquetFeature>, GalileoError> {
+        let geometry_column = self.metadata.primary_column.clone();
+        let mut builder = self.builder;
+
+        if let Some(columns) = &self.columns {
+            let schema = builder.schema().clone();
+            let indices = schema
+                .fields()
+                .iter()
+                .enumerate()
+                .filter(|(_, field)| {
+                    field.name() == &geometry_column || columns.contains(field.name())
+                })
+                .map(|(index, _)| index);
+            let mask = ProjectionMask::roots(builder.parquet_schema(), indices);
+            builder = builder.with_projection(mask);
+        }
+
+        if let Some(bbox) = self.bbox {
+            let covering = self
+                .metadata
+                .columns
+                .get(&geometry_column)
+                .and_then(|column| column.covering.as_ref());
+            if let Some(covering) = covering {
+                let row_groups = builder
+                    .metadata()
+                    .row_groups()
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, row_group)| row_group_intersects(row_group, &covering.bbox, bbox))
+                    .map(|(index, _)| index)
+                    .collect();
+                builder = builder.with_row_groups(row_groups);
+            }
+        }
+
+        let mut features = vec![];
+        for batch in builder.build().map_err(parquet_error)? {
+            let batch = batch.map_err(|err| GalileoError::Generic(err.to_string()))?;
+            read_batch(&batch, &geometry_column, self.bbox, &mut features)?;
+        }
+
+        Ok(features)
+    }
+}
+
+fn parquet_error(err: parquet::errors::ParquetError) -> GalileoError {
+    GalileoError::Generic(format!("failed to read parquet file: {err}"))
+}
+
+fn row_group_intersects(
+    row_group: &RowGroupMetaData,
+    covering: &GeoParquetBboxCovering,
+    bbox: Rect,
+) -> bool {
+    let stat = |path: &[String], max: bool| -> Option<f64> {
+        let path = path.join(".");
+        let column = row_group
+            .columns()
+            .iter()
+            .find(|column| column.column_path().string() == path)?;
+        match column.statistics()? {
+            Statistics::Double(stats) if max => stats.max_opt().copied(),
+            Statistics::Double(stats) => stats.min_opt().copied(),
+            Statistics::Float(stats) if max => stats.max_opt().map(|v| *v as f64),
+            Statistics::Float(stats) => stats.min_opt().map(|v| *v as f64),
+            _ => None,
+        }
+    };
+
+    // If the statistics are not available, the row group cannot be skipped.
+    let x_min = stat(&covering.xmin, false).unwrap_or(f64::NEG_INFINITY);
+    let y_min = stat(&covering.ymin, false).unwrap_or(f64::NEG_INFINITY);
+    let x_max = stat(&covering.xmax, true).unwrap_or(f64::INFINITY);
+    let y_max = stat(&covering.ymax, true).unwrap_or(f64::INFINITY);
+
+    x_min <= bbox.x_max() && x_max >= bbox.x_min() && y_min <= bbox.y_max() && y_max >= bbox.y_min()
+}
+
+fn read_batch(
+    batch: &RecordBatch,
+    geometry_column: &str,
+    bbox: Option<Rect>,
+    features: &mut Vec<GeoParquetFeature>,
+) -> Result<(), GalileoError> {
+    let geometries = batch
+        .column_by_name(geometry_column)
+        .ok_or_else(|| GalileoError::Generic("geometry column not found".into()))?;
+    let schema = batch.schema();
+
+    for row in 0..batch.num_rows() {
+        let Some(wkb) = binary_value(geometries.as_ref(), row) else {
+            continue;
+        };
+
+        let extent = Cell::new(None::<Rect>);
+        let geometry = match decode_wkb(wkb, |x, y| {
+            let point_rect = Rect::new(x, y, x, y);
+            extent.set(Some(match extent.get() {
+                Some(rect) => rect.merge(point_rect),
+                None => point_rect,
+            }));
+            GeoPoint2d::latlon(y, x)
+        }) {
+            Ok(geometry) => geometry,
+            Err(err) => {
+                // One broken row should not make the rest of the file unreadable.
+                log::warn!("Skipping GeoParquet row {row} with invalid geometry: {err}");
+                continue;
+            }
+        };
+
+        if let (Some(bbox), Some(extent)) = (bbox, extent.get()) {
+            if !bbox.intersects(extent) {
+                continue;
+            }
+        }
+
+        let properties = schema
+            .fields()
+            .iter()
+            .zip(batch.columns())
+            .filter(|(field, _)| field.name() != geometry_column)
+            .map(|(field, column)| (field.name().clone(), json_value(column.as_ref(), row)))
+            .collect();
+
+        features.push(GeoParquetFeature {
+            geometry,
+            properties,
+        });
+    }
+
+    Ok(())
+}
+
+fn binary_value(array: &dyn Array, row: usize) -> Option<&[u8]> {
+    if array.is_null(row) {
+        return None;
+    }
+
+    match array.data_type() {
+        DataType::Binary => Some(array.as_binary::<i32>().value(row)),
+        DataType::LargeBinary => Some(array.as_binary::<i64>().value(row)),
+        _ => None,
+    }
+}
+
+fn json_value(array: &dyn Array, row: usize) -> Value {
+    if array.is_null(row) {
+        return Value::Null;
+    }
+
+    match array.data_type() {
+        DataType::Boolean => Value::from(array.as_boolean().value(row)),
+        DataType::Int8 => Value::from(array.as_primitive::<Int8Type>().value(row)),
+        DataType::Int16 => Value::from(array.as_primitive::<Int16Type>().value(row)),
+        DataType::Int32 => Value::from(array.as_primitive::<Int32Type>().value(row)),
+        DataType::Int64 => Value::from(array.as_primitive::<Int64Type>().value(row)),
+        DataType::UInt8 => Value::from(array.as_primitive::<UInt8Type>().value(row)),
+        DataType::UInt16 => Value::from(array.as_primitive::<UInt16Type>().value(row)),
+        DataType::UInt32 => Value::from(array.as_primitive::<UInt32Type>().value(row)),
+        DataType::UInt64 => Value::from(array.as_primitive::<UInt64Type>().value(row)),
+        DataType::Float32 => Value::from(array.as_primitive::<Float32Type>().value(row)),
+        DataType::Float64 => Value::from(array.as_primitive::<Float64Type>().value(row)),
+        DataType::Utf8 => Value::from(array.as_string::<i32>().value(row)),
+        DataType::LargeUtf8 => Value::from(array.as_string::<i64>().value(row)),
+        _ => Value::Null,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::{ArrayRef, BinaryArray, Float64Array, StringArray, StructArray};
+    use arrow_schema::Field;
+    use bytes::Bytes;
+    use galileo_types::geo::GeoPoint;
+    use parquet::arrow::ArrowWriter;
+    use parquet::file::properties::WriterProperties;
+    use parquet::format::KeyValue;
+    use std::sync::Arc;
+
+    const GEO_METADATA: &str = r#"{
+        "version": "1.1.0",
+        "primary_column": "geometry",
+        "columns": {
+            "geometry": {
+                "encoding": "WKB",
+                "covering": {
+                    "bbox": {
+                        "xmin": ["bbox", "xmin"],
+                        "ymin": ["bbox", "ymin"],
+                        "xmax": ["bbox", "xmax"],
+                        "ymax": ["bbox", "ymax"]
+                    }
+                }
+            }
+        }
+    }"#;
+
+    fn wkb_point(x: f64, y: f64) -> Vec<u8> {
+        let mut bytes = vec![1];
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        bytes.extend_from_slice(&x.to_le_bytes());
+        bytes.extend_from_slice(&y.to_le_bytes());
+        bytes
+    }
+
+    /// Each row is `(name, wkb, covering bbox)`.
+    fn batch(rows: &[(&str, Vec<u8>, [f64; 4])]) -> RecordBatch {
+        let bbox_column = |index: usize| -> (Arc<Field>, ArrayRef) {
+            let name = ["xmin", "ymin", "xmax", "ymax"][index];
+            (
+                Arc::new(Field::new(name, DataType::Float64, false)),
+                Arc::new(Float64Array::from_iter_values(
+                    rows.iter().map(|(_, _, bbox)| bbox[index]),
+                )) as ArrayRef,
+            )
+        };
+
+        RecordBatch::try_from_iter([
+            (
+                "name",
+                Arc::new(StringArray::from_iter_values(
+                    rows.iter().map(|(name, _, _)| *name),
+                )) as ArrayRef,
+            ),
+            (
+                "geometry",
+                Arc::new(BinaryArray::from_iter_values(
+                    rows.iter().map(|(_, wkb, _)| wkb.as_slice()),
+                )) as ArrayRef,
+            ),
+            (
+                "bbox",
+                Arc::new(StructArray::from(
+                    (0..4).map(bbox_column).collect::<Vec<_>>(),
+                )) as ArrayRef,
+            ),
+        ])
+        .unwrap()
+    }
+
+    /// Writes a file with two row groups. The covering bbox of the second one is deliberately wrong, so that it can
+    /// only be read if the row group is not skipped by its statistics.
+    fn fixture() -> Bytes {
+        let first = batch(&[
+            ("a", wkb_point(10.0, 50.0), [10.0, 50.0, 10.0, 50.0]),
+            ("b", wkb_point(100.0, 0.0), [100.0, 0.0, 100.0, 0.0]),
+        ]);
+        let second = batch(&[
+            ("c", wkb_point(11.0, 51.0), [-170.0, -80.0, -160.0, -70.0]),
+            (
+                "broken",
+                vec![1, 1, 0, 0, 0, 0],
+                [-170.0, -80.0, -160.0, -70.0],
+            ),
+        ]);
+
+        let properties = WriterProperties::builder()
+            .set_key_value_metadata(Some(vec![KeyValue::new(
+                "geo".to_string(),
+                GEO_METADATA.to_string(),
+            )]))
+            .build();
+        let mut buffer = vec![];
+        let mut writer =
+            ArrowWriter::try_new(&mut buffer, first.schema(), Some(properties)).unwrap();
+        writer.write(&first).unwrap();
+        writer.flush().unwrap();
+        writer.write(&second).unwrap();
+        writer.close().unwrap();
+
+        Bytes::from(buffer)
+    }
+
+    fn names(features: &[GeoParquetFeature]) -> Vec<&str> {
+        features
+            .iter()
+            .map(|f| f.property("name").and_then(Value::as_str).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn read_features() {
+        let features = GeoParquetReader::new(fixture()).unwrap().read().unwrap();
+        assert_eq!(names(&features), ["a", "b", "c"]);
+
+        let Geom::Point(point) = features[0].geometry() else {
+            panic!("expected a point");
+        };
+        assert_eq!(point.lat(), 50.0);
+        assert_eq!(point.lon(), 10.0);
+    }
+
+    #[test]
+    fn read_selected_columns() {
+        let features = GeoParquetReader::new(fixture())
+            .unwrap()
+            .with_columns(&["name"])
+            .read()
+            .unwrap();
+
+        assert_eq!(features.len(), 3);
+        for feature in &features {
+            assert_eq!(feature.attribute_names(), ["name"]);
+        }
+    }
+
+    #[test]
+    fn read_features_in_bbox() {
+        let features = GeoParquetReader::new(fixture())
+            .unwrap()
+            .with_bbox(Rect::new(0.0, 40.0, 20.0, 60.0))
+            .read()
+            .unwrap();
+
+        // `b` is outside of the bbox, and the row group with `c` is skipped by its covering statistics.
+        assert_eq!(names(&features), ["a"]);
+    }
+
+    #[test]
+    fn geographic_crs_detection() {
+        let column = |crs: Option<Value>| GeoParquetColumn {
+            encoding: "WKB".into(),
+            crs,
+            bbox: None,
+            covering: None,
+        };
+
+        assert!(column(None).is_geographic());
+        assert!(column(Some(serde_json::json!({
+            "id": { "authority": "OGC", "code": "CRS84" }
+        })))
+        .is_geographic());
+        assert!(column(Some(serde_json::json!({
+            "id": { "authority": "EPSG", "code": 4326 }
+        })))
+        .is_geographic());
+        assert!(!column(Some(serde_json::json!({
+            "id": { "authority": "EPSG", "code": 3857 }
+        })))
+        .is_geographic());
+    }
+
+    #[test]
+    fn parse_metadata() {
+        let metadata: GeoParquetMetadata = serde_json::from_str(
+            r#"{
+                "version": "1.1.0",
+                "primary_column": "geometry",
+                "columns": {
+                    "geometry": {
+                        "encoding": "WKB",
+                        "geometry_types": ["Polygon"],
+                        "covering": {
+                            "bbox": {
+                                "xmin": ["bbox", "xmin"],
+                                "ymin": ["bbox", "ymin"],
+                                "xmax": ["bbox", "xmax"],
+                                "ymax": ["bbox", "ymax"]
+                            }
+                        }
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let column = &metadata.columns["geometry"];
+        assert!(column.is_geographic());
+        assert_eq!(
+            column.covering.as_ref().unwrap().bbox.xmax,
+            vec!["bbox".to_string(), "xmax".to_string()]
+        );
+    }
+}
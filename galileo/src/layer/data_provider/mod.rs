@@ -1,8 +1,25 @@
 //! Data sources for layers.
 
+#[cfg(feature = "arcgis")]
+pub mod arcgis;
+pub mod csv;
+mod file_tile_provider;
+#[cfg(feature = "geoparquet")]
+pub mod geoparquet;
+#[cfg(feature = "ogc-api")]
+pub mod ogc_api;
+mod procedural;
 mod url_data_provider;
 mod url_image_provider;
 
+#[cfg(all(feature = "sqlite", not(target_arch = "wasm32")))]
+pub use file_tile_provider::MbTiles;
+#[cfg(not(target_arch = "wasm32"))]
+pub use file_tile_provider::NativeFileSystem;
+pub use file_tile_provider::{
+    AsyncFileSystem, CogTiles, DirectoryTiles, FileTileProvider, TileFileSource, UrlFileSystem,
+};
+pub use procedural::{ProceduralTile, ProceduralTileProvider, TileGenerator};
 pub use url_data_provider::UrlDataProvider;
 pub use url_image_provider::UrlImageProvider;
 
@@ -14,6 +31,8 @@ pub use file_cache::FileCacheController;
 
 use crate::error::GalileoError;
 use bytes::Bytes;
+#[cfg(feature = "geojson")]
+use galileo_types::normalize::{normalize_geojson, NormalizationReport, NormalizeOptions};
 use maybe_sync::{MaybeSend, MaybeSync};
 use std::future::Future;
 
@@ -48,6 +67,64 @@ where
             self.decode(raw, context)
         }
     }
+
+    /// Load and decode the data, and report where the data was loaded from. Layers use this method to collect
+    /// [tile diagnostics](crate::layer::tile_diagnostics).
+    ///
+    /// The information is returned even if loading fails, as far as it is known. Default implementation calls
+    /// [`DataProvider::load`] and reports nothing.
+    fn load_with_info(
+        &self,
+        key: &Key,
+        context: Context,
+    ) -> impl Future<Output = (Result<Data, GalileoError>, LoadInfo)> + MaybeSend {
+        async { (self.load(key, context).await, LoadInfo::default()) }
+    }
+
+    /// Hints the provider that the data items with the given keys will be requested soon, so it can start loading
+    /// them in the background (e.g. read them from disk in a batch). Layers call this method with the list of tiles
+    /// they need for the current view before loading them.
+    ///
+    /// Default implementation does nothing.
+    fn read_ahead(&self, _keys: &[Key])
+    where
+        Key: Sized,
+    {
+    }
+}
+
+/// Place a data item was loaded from by a [`DataProvider`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DataSource {
+    /// In-memory cache or buffer of the provider.
+    Memory,
+    /// Local disk, including persistent caches.
+    Disk,
+    /// Network.
+    Network,
+    /// The data was generated by the provider.
+    Generated,
+}
+
+impl std::fmt::Display for DataSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            DataSource::Memory => "memory",
+            DataSource::Disk => "disk",
+            DataSource::Network => "network",
+            DataSource::Generated => "generated",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Information about loading of a data item, reported by [`DataProvider::load_with_info`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct LoadInfo {
+    /// Where the data was loaded from, if known.
+    pub source: Option<DataSource>,
+    /// Size of the raw (not decoded) data in bytes, if known.
+    pub byte_size: Option<usize>,
 }
 
 /// Data processors are used to decode raw loaded data into something useful by a layer.
@@ -76,8 +153,18 @@ pub trait PersistentCacheController<Key: ?Sized, Data> {
 }
 
 /// Method that constructs URL address to load a data item using the data key.
-pub trait UrlSource<Key: ?Sized>: (Fn(&Key) -> String) + MaybeSend + MaybeSync {}
-impl<Key: ?Sized, T: Fn(&Key) -> String> UrlSource<Key> for T where T: MaybeSend + MaybeSync {}
+///
+/// Data providers also accept methods returning `Option<String>`, where `None` means that there is no data for the
+/// key. Such keys are reported as [not found](GalileoError::NotFound) without sending any requests.
+pub trait UrlSource<Key: ?Sized, Url = String>: (Fn(&Key) -> Url) + MaybeSend + MaybeSync {}
+impl<Key: ?Sized, Url, T: Fn(&Key) -> Url> UrlSource<Key, Url> for T where T: MaybeSend + MaybeSync {}
+
+/// Converts a URL source returning either `String` or `Option<String>` into the one returning `Option<String>`.
+pub(crate) fn optional_url_source<Key: ?Sized, Url: Into<Option<String>>>(
+    url_source: impl UrlSource<Key, Url> + 'static,
+) -> Box<dyn UrlSource<Key, Option<String>>> {
+    Box::new(move |key: &Key| url_source(key).into())
+}
 
 pub(crate) mod dummy {
     use crate::error::GalileoError;
@@ -99,3 +186,16 @@ pub(crate) mod dummy {
 }
 
 pub use dummy::DummyCacheController;
+
+/// Normalizes the geometries of the loaded features and logs a warning if any of them had to be fixed.
+#[cfg(feature = "geojson")]
+fn normalize_features(features: &mut [geojson::Feature], options: &NormalizeOptions) {
+    let mut report = NormalizationReport::default();
+    for geometry in features.iter_mut().filter_map(|f| f.geometry.as_mut()) {
+        report.merge(&normalize_geojson(geometry, options));
+    }
+
+    if !report.is_clean() {
+        log::warn!("Invalid geometries of loaded features were fixed: {report:?}");
+    }
+}
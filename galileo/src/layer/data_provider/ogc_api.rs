@@ -0,0 +1,704 @@
+//! Clients for [OGC API](https://ogcapi.ogc.org/) services.
+//!
+//! * [`OgcApiClient`] discovers the content of a service from its landing page: collections and tile matrix sets.
+//! * [`OgcApiTiles`] constructs tile URLs from an OGC API – Tiles URL template. Tile matrix sets can be converted into
+//!   a [`TileSchema`] with [`TileMatrixSet::tile_schema`].
+//! * [`OgcApiFeatures`] loads features of a collection from an OGC API – Features service, page by page.
+
+use crate::cancellation::CancellationToken;
+use crate::error::GalileoError;
+use crate::layer::data_provider::{normalize_features, UrlSource};
+use crate::lod::Lod;
+use crate::platform::{PlatformService, PlatformServiceImpl};
+use crate::tile_scheme::{TileIndex, VerticalDirection};
+use crate::TileSchema;
+use futures::Stream;
+use galileo_types::cartesian::{Point2d, Rect};
+use galileo_types::geo::Crs;
+use galileo_types::normalize::NormalizeOptions;
+use geojson::FeatureCollection;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+/// Link to a related resource of an OGC API service.
+#[derive(Debug, Clone, Deserialize)]
+pub struct OgcLink {
+    /// URL of the resource.
+    pub href: String,
+    /// Relation of the resource to the current one (e.g. `data`, `tiling-schemes`, `next`).
+    pub rel: String,
+    /// Media type of the resource.
+    #[serde(rename = "type")]
+    pub media_type: Option<String>,
+    /// Title of the resource.
+    pub title: Option<String>,
+    /// Whether the `href` is a URL template.
+    #[serde(default)]
+    pub templated: bool,
+}
+
+fn find_link<'a>(links: &'a [OgcLink], rel: &str) -> Option<&'a OgcLink> {
+    links.iter().find(|link| link.rel == rel)
+}
+
+/// Landing page of an OGC API service.
+#[derive(Debug, Clone, Deserialize)]
+pub struct OgcLandingPage {
+    /// Title of the service.
+    pub title: Option<String>,
+    /// Description of the service.
+    pub description: Option<String>,
+    /// Links to the resources of the service.
+    pub links: Vec<OgcLink>,
+}
+
+impl OgcLandingPage {
+    /// Returns the first link with the given relation.
+    pub fn link(&self, rel: &str) -> Option<&OgcLink> {
+        find_link(&self.links, rel)
+    }
+}
+
+/// Spatial extent of a collection.
+#[derive(Debug, Clone, Deserialize)]
+pub struct OgcSpatialExtent {
+    /// Bounding boxes of the collection in `[xmin, ymin, xmax, ymax]` format. The first one contains all the data.
+    pub bbox: Vec<Vec<f64>>,
+}
+
+/// Extent of a collection.
+#[derive(Debug, Clone, Deserialize)]
+pub struct OgcExtent {
+    /// Spatial extent.
+    pub spatial: Option<OgcSpatialExtent>,
+}
+
+/// Description of a data collection of an OGC API service.
+#[derive(Debug, Clone, Deserialize)]
+pub struct OgcCollection {
+    /// Id of the collection.
+    pub id: String,
+    /// Title of the collection.
+    pub title: Option<String>,
+    /// Description of the collection.
+    pub description: Option<String>,
+    /// Extent of the collection data.
+    pub extent: Option<OgcExtent>,
+    /// Links to the resources of the collection.
+    #[serde(default)]
+    pub links: Vec<OgcLink>,
+}
+
+impl OgcCollection {
+    /// Returns the first link with the given relation.
+    pub fn link(&self, rel: &str) -> Option<&OgcLink> {
+        find_link(&self.links, rel)
+    }
+
+    /// Bounding box of all the collection data, if specified.
+    pub fn bbox(&self) -> Option<Rect> {
+        match &self.extent.as_ref()?.spatial.as_ref()?.bbox.first()?[..] {
+            [x_min, y_min, x_max, y_max] => Some(Rect::new(*x_min, *y_min, *x_max, *y_max)),
+            // 3D bounding box
+            [x_min, y_min, _, x_max, y_max, _] => Some(Rect::new(*x_min, *y_min, *x_max, *y_max)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct OgcCollections {
+    collections: Vec<OgcCollection>,
+}
+
+/// Short description of a tile matrix set from the list of tile matrix sets of a service.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TileMatrixSetRef {
+    /// Id of the tile matrix set.
+    pub id: Option<String>,
+    /// Title of the tile matrix set.
+    pub title: Option<String>,
+    /// Links to the tile matrix set definition.
+    #[serde(default)]
+    pub links: Vec<OgcLink>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TileMatrixSets {
+    tile_matrix_sets: Vec<TileMatrixSetRef>,
+}
+
+/// Single level of a [`TileMatrixSet`].
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TileMatrix {
+    /// Id of the level, used as `{tileMatrix}` parameter of tile URLs.
+    pub id: String,
+    /// Scale denominator of the level.
+    pub scale_denominator: f64,
+    /// Size of a pixel in CRS units. If not set, it is calculated from the scale denominator.
+    pub cell_size: Option<f64>,
+    /// Position of the corner of the tile `(0, 0)`.
+    pub point_of_origin: [f64; 2],
+    /// Corner of the tile matrix the origin is at: `topLeft` (default) or `bottomLeft`.
+    pub corner_of_origin: Option<String>,
+    /// Width of a tile in pixels.
+    pub tile_width: u32,
+    /// Height of a tile in pixels.
+    pub tile_height: u32,
+    /// Number of tiles along X axis.
+    pub matrix_width: u32,
+    /// Number of tiles along Y axis.
+    pub matrix_height: u32,
+}
+
+impl TileMatrix {
+    /// Standardized size of a pixel used to convert scale denominators to resolutions, in meters.
+    const PIXEL_SIZE: f64 = 0.00028;
+
+    /// Resolution of the level in CRS units per pixel.
+    pub fn resolution(&self) -> f64 {
+        self.cell_size
+            .unwrap_or(self.scale_denominator * Self::PIXEL_SIZE)
+    }
+
+    fn vertical_direction(&self) -> VerticalDirection {
+        match self.corner_of_origin.as_deref() {
+            Some("bottomLeft") => VerticalDirection::BottomToTop,
+            _ => VerticalDirection::TopToBottom,
+        }
+    }
+}
+
+/// OGC Two Dimensional Tile Matrix Set definition.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TileMatrixSet {
+    /// Id of the tile matrix set.
+    pub id: Option<String>,
+    /// URI of the CRS of the tile matrix set.
+    pub crs: String,
+    /// Levels of the tile matrix set, from the lowest to the highest resolution.
+    pub tile_matrices: Vec<TileMatrix>,
+}
+
+impl TileMatrixSet {
+    /// Parses a tile matrix set JSON definition.
+    pub fn from_json(json: &[u8]) -> Result<Self, GalileoError> {
+        parse_json(json, "tile matrix set")
+    }
+
+    /// Returns the CRS of the tile matrix set, if it is supported.
+    pub fn get_crs(&self) -> Option<Crs> {
+        crs_from_uri(&self.crs)
+    }
+
+    /// Converts the tile matrix set into a [`TileSchema`].
+    ///
+    /// Z-levels of the schema are the indices of the tile matrices in the set, so
+    /// [`OgcApiTiles::tile_url`] must be used with the same tile matrix set to construct tile URLs.
+    ///
+    /// Returns an error if the tile matrix set is empty, if its CRS is not supported or if the tile matrices have
+    /// different origins or tile sizes.
+    pub fn tile_schema(&self) -> Result<TileSchema, GalileoError> {
+        let crs = self.get_crs().ok_or_else(|| {
+            GalileoError::Generic(format!("tile matrix set CRS {} is not supported", self.crs))
+        })?;
+
+        let Some(first) = self.tile_matrices.first() else {
+            return Err(GalileoError::Generic("tile matrix set is empty".into()));
+        };
+
+        if self.tile_matrices.iter().any(|matrix| {
+            matrix.point_of_origin != first.point_of_origin
+                || matrix.tile_width != first.tile_width
+                || matrix.tile_height != first.tile_height
+                || matrix.vertical_direction() != first.vertical_direction()
+        }) {
+            return Err(GalileoError::Generic(
+                "tile matrices with different origins or tile sizes are not supported".into(),
+            ));
+        }
+
+        let lods = self
+            .tile_matrices
+            .iter()
+            .enumerate()
+            .map(|(z, matrix)| {
+                Lod::new(matrix.resolution(), z as u32).ok_or_else(|| {
+                    GalileoError::Generic(format!(
+                        "invalid resolution of tile matrix {}",
+                        matrix.id
+                    ))
+                })
+            })
+            .collect::<Result<_, _>>()?;
+
+        let [x, y] = first.point_of_origin;
+        let width = first.matrix_width as f64 * first.tile_width as f64 * first.resolution();
+        let height = first.matrix_height as f64 * first.tile_height as f64 * first.resolution();
+        let y_direction = first.vertical_direction();
+        let bounds = match y_direction {
+            VerticalDirection::TopToBottom => Rect::new(x, y - height, x + width, y),
+            VerticalDirection::BottomToTop => Rect::new(x, y, x + width, y + height),
+        };
+
+        Ok(TileSchema {
+            origin: Point2d::new(x, y),
+            bounds,
+            lods,
+            tile_width: first.tile_width,
+            tile_height: first.tile_height,
+            y_direction,
+            crs,
+        })
+    }
+}
+
+fn crs_from_uri(uri: &str) -> Option<Crs> {
+    let uri = uri.trim_end_matches('/');
+    if uri.ends_with("OGC/1.3/CRS84") || uri.ends_with("OGC/0/CRS84") {
+        Some(Crs::WGS84)
+    } else if uri.ends_with("EPSG/0/3857") {
+        Some(Crs::EPSG3857)
+    } else {
+        None
+    }
+}
+
+fn parse_json<T: DeserializeOwned>(json: &[u8], what: &str) -> Result<T, GalileoError> {
+    serde_json::from_slice(json)
+        .map_err(|err| GalileoError::Generic(format!("invalid {what}: {err}")))
+}
+
+fn with_query(url: &str, query: &str) -> String {
+    let separator = if url.contains('?') { '&' } else { '?' };
+    format!("{url}{separator}{query}")
+}
+
+async fn load_json<T: DeserializeOwned>(url: &str, what: &str) -> Result<T, GalileoError> {
+    let bytes = PlatformServiceImpl::new()
+        .load_bytes_from_url(&with_query(url, "f=json"))
+        .await?;
+    parse_json(&bytes, what)
+}
+
+/// Client for discovering the content of an OGC API service.
+///
+/// ```no_run
+/// use galileo::layer::data_provider::ogc_api::OgcApiClient;
+/// use galileo::layer::data_provider::UrlImageProvider;
+/// use galileo::layer::RasterTileLayer;
+///
+/// # tokio_test::block_on(async {
+/// let client = OgcApiClient::new("https://maps.example.com/ogcapi");
+/// let tile_matrix_set = client.load_tile_matrix_set("WebMercatorQuad").await?;
+/// let tiles = client.collection_map_tiles("satellite", &tile_matrix_set);
+///
+/// let layer = RasterTileLayer::new(
+///     tile_matrix_set.tile_schema()?,
+///     UrlImageProvider::new(tiles.url_source()),
+///     None,
+/// );
+/// # Ok::<(), galileo::error::GalileoError>(())
+/// # });
+/// ```
+#[derive(Debug, Clone)]
+pub struct OgcApiClient {
+    url: String,
+}
+
+impl OgcApiClient {
+    /// Creates a new client for the service with the given landing page URL.
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into().trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// URL of the landing page of the service.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Loads the landing page of the service.
+    pub async fn load_landing_page(&self) -> Result<OgcLandingPage, GalileoError> {
+        load_json(&self.url, "landing page").await
+    }
+
+    /// Loads the list of collections of the service.
+    pub async fn load_collections(&self) -> Result<Vec<OgcCollection>, GalileoError> {
+        let collections: OgcCollections =
+            load_json(&format!("{}/collections", self.url), "collections").await?;
+        Ok(collections.collections)
+    }
+
+    /// Loads the description of the collection with the given id.
+    pub async fn load_collection(
+        &self,
+        collection_id: &str,
+    ) -> Result<OgcCollection, GalileoError> {
+        load_json(
+            &format!("{}/collections/{collection_id}", self.url),
+            "collection",
+        )
+        .await
+    }
+
+    /// Loads the list of tile matrix sets supported by the service.
+    pub async fn load_tile_matrix_sets(&self) -> Result<Vec<TileMatrixSetRef>, GalileoError> {
+        let sets: TileMatrixSets =
+            load_json(&format!("{}/tileMatrixSets", self.url), "tile matrix sets").await?;
+        Ok(sets.tile_matrix_sets)
+    }
+
+    /// Loads the definition of the tile matrix set with the given id.
+    pub async fn load_tile_matrix_set(&self, id: &str) -> Result<TileMatrixSet, GalileoError> {
+        load_json(
+            &format!("{}/tileMatrixSets/{id}", self.url),
+            "tile matrix set",
+        )
+        .await
+    }
+
+    /// Returns a client for the map (raster) tiles of the collection in the given tile matrix set.
+    pub fn collection_map_tiles(
+        &self,
+        collection_id: &str,
+        tile_matrix_set: &TileMatrixSet,
+    ) -> OgcApiTiles {
+        self.tiles(
+            &format!("collections/{collection_id}/map/tiles"),
+            tile_matrix_set,
+        )
+    }
+
+    /// Returns a client for the data (e.g. vector) tiles of the collection in the given tile matrix set.
+    pub fn collection_tiles(
+        &self,
+        collection_id: &str,
+        tile_matrix_set: &TileMatrixSet,
+    ) -> OgcApiTiles {
+        self.tiles(
+            &format!("collections/{collection_id}/tiles"),
+            tile_matrix_set,
+        )
+    }
+
+    /// Returns a client for the features of the collection with the given id.
+    pub fn collection_features(&self, collection_id: &str) -> OgcApiFeatures {
+        OgcApiFeatures::new(format!("{}/collections/{collection_id}/items", self.url))
+    }
+
+    fn tiles(&self, path: &str, tile_matrix_set: &TileMatrixSet) -> OgcApiTiles {
+        let id = tile_matrix_set.id.as_deref().unwrap_or_default();
+        OgcApiTiles::new(
+            format!(
+                "{}/{path}/{id}/{{tileMatrix}}/{{tileRow}}/{{tileCol}}",
+                self.url
+            ),
+            tile_matrix_set,
+        )
+    }
+}
+
+/// Constructs tile URLs for an OGC API – Tiles tileset.
+#[derive(Debug, Clone)]
+pub struct OgcApiTiles {
+    url_template: String,
+    tile_matrix_ids: Vec<String>,
+}
+
+impl OgcApiTiles {
+    /// Creates a new instance with the given URL template. The template must contain `{tileMatrix}`, `{tileRow}` and
+    /// `{tileCol}` parameters.
+    pub fn new(url_template: impl Into<String>, tile_matrix_set: &TileMatrixSet) -> Self {
+        Self {
+            url_template: url_template.into(),
+            tile_matrix_ids: tile_matrix_set
+                .tile_matrices
+                .iter()
+                .map(|matrix| matrix.id.clone())
+                .collect(),
+        }
+    }
+
+    /// URL of the tile with the given index, or `None` if the tile matrix set does not have the z-level of the index.
+    pub fn tile_url(&self, index: &TileIndex) -> Option<String> {
+        let tile_matrix = self.tile_matrix_ids.get(index.z as usize)?;
+        Some(
+            self.url_template
+                .replace("{tileMatrix}", tile_matrix)
+                .replace("{tileRow}", &index.y.to_string())
+                .replace("{tileCol}", &index.x.to_string()),
+        )
+    }
+
+    /// Returns a URL source that can be used with
+    /// [`UrlImageProvider`](crate::layer::data_provider::UrlImageProvider) or
+    /// [`UrlDataProvider`](crate::layer::data_provider::UrlDataProvider) to load the tiles. Tiles of the z-levels
+    /// missing in the tile matrix set are reported as not found without sending any requests.
+    pub fn url_source(&self) -> impl UrlSource<TileIndex, Option<String>> {
+        let tiles = self.clone();
+        move |index: &TileIndex| tiles.tile_url(index)
+    }
+}
+
+/// Query parameters of an OGC API – Features request.
+#[derive(Debug, Clone, Default)]
+pub struct OgcFeatureQuery {
+    /// If set, only the features intersecting this bounding box (in WGS84 longitude/latitude) are returned.
+    pub bbox: Option<Rect>,
+    /// Maximum number of features in a single page. If not set, the default limit of the service is used.
+    pub limit: Option<u32>,
+}
+
+/// Client for the features of a collection of an OGC API – Features service.
+///
+/// Features are returned page by page. The next page is requested using the `next` link of the previous page, so
+/// paging works with any paging mechanism the server uses.
+///
+/// Features are returned in WGS84 coordinates.
+#[derive(Debug, Clone)]
+pub struct OgcApiFeatures {
+    items_url: String,
+    normalize: Option<NormalizeOptions>,
+    cancellation: Option<CancellationToken>,
+}
+
+impl OgcApiFeatures {
+    /// Creates a new client with the given URL of the collection items (`{service}/collections/{id}/items`).
+    pub fn new(items_url: impl Into<String>) -> Self {
+        Self {
+            items_url: items_url.into(),
+            normalize: None,
+            cancellation: None,
+        }
+    }
+
+    /// Enables normalization of the loaded geometries: invalid polygons (unclosed rings, wrong winding, duplicate
+    /// vertices etc.) are fixed before they are returned. See [`galileo_types::normalize`] for details.
+    pub fn with_normalization(&self, options: NormalizeOptions) -> Self {
+        Self {
+            normalize: Some(options),
+            ..self.clone()
+        }
+    }
+
+    /// Sets the token that cancels feature requests. When the token is cancelled, the page being loaded is dropped,
+    /// the page stream returns [`GalileoError::Cancelled`] and ends.
+    pub fn with_cancellation_token(&self, token: CancellationToken) -> Self {
+        Self {
+            cancellation: Some(token),
+            ..self.clone()
+        }
+    }
+
+    /// URL of the first page of features for the query.
+    pub fn items_url(&self, query: &OgcFeatureQuery) -> String {
+        let mut params = vec!["f=json".to_string()];
+        if let Some(bbox) = query.bbox {
+            params.push(format!(
+                "bbox={},{},{},{}",
+                bbox.x_min(),
+                bbox.y_min(),
+                bbox.x_max(),
+                bbox.y_max()
+            ));
+        }
+
+        if let Some(limit) = query.limit {
+            params.push(format!("limit={limit}"));
+        }
+
+        with_query(&self.items_url, &params.join("&"))
+    }
+
+    /// Returns a stream of feature pages for the query. Pages are loaded lazily when the stream is polled.
+    pub fn pages(
+        &self,
+        query: &OgcFeatureQuery,
+    ) -> impl Stream<Item = Result<Vec<geojson::Feature>, GalileoError>> {
+        let first_url = Some(self.items_url(query));
+        let normalize = self.normalize;
+        let cancellation = self.cancellation.clone();
+        futures::stream::unfold(first_url, move |url| {
+            let cancellation = cancellation.clone();
+            async move {
+                let url = url?;
+                let page = match &cancellation {
+                    Some(token) => token
+                        .run_until_cancelled(load_page(&url))
+                        .await
+                        .unwrap_or(Err(GalileoError::Cancelled)),
+                    None => load_page(&url).await,
+                };
+                match page {
+                    Ok(mut page) => {
+                        if let Some(options) = &normalize {
+                            normalize_features(&mut page.features, options);
+                        }
+                        Some((Ok(page.features), page.next))
+                    }
+                    Err(err) => Some((Err(err), None)),
+                }
+            }
+        })
+    }
+
+    /// Loads all features matching the query.
+    pub async fn load_all(
+        &self,
+        query: &OgcFeatureQuery,
+    ) -> Result<Vec<geojson::Feature>, GalileoError> {
+        use futures::StreamExt;
+
+        let mut features = vec![];
+        let mut pages = std::pin::pin!(self.pages(query));
+        while let Some(page) = pages.next().await {
+            features.extend(page?);
+        }
+
+        Ok(features)
+    }
+}
+
+struct FeaturePage {
+    features: Vec<geojson::Feature>,
+    next: Option<String>,
+}
+
+async fn load_page(url: &str) -> Result<FeaturePage, GalileoError> {
+    let bytes = PlatformServiceImpl::new().load_bytes_from_url(url).await?;
+    parse_page(&bytes)
+}
+
+fn parse_page(bytes: &[u8]) -> Result<FeaturePage, GalileoError> {
+    let collection: FeatureCollection = parse_json(bytes, "feature collection")?;
+
+    let next = collection
+        .foreign_members
+        .as_ref()
+        .and_then(|members| members.get("links"))
+        .and_then(|links| serde_json::from_value::<Vec<OgcLink>>(links.clone()).ok())
+        .and_then(|links| find_link(&links, "next").map(|link| link.href.clone()));
+
+    let features: Vec<_> = collection
+        .features
+        .into_iter()
+        .filter(|feature| feature.geometry.is_some())
+        .collect();
+
+    // Some servers keep returning `next` link for the empty page after the last one.
+    let next = if features.is_empty() { None } else { next };
+
+    Ok(FeaturePage { features, next })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TILE_MATRIX_SET: &str = r#"{
+        "id": "WebMercatorQuad",
+        "crs": "http://www.opengis.net/def/crs/EPSG/0/3857",
+        "tileMatrices": [
+            {
+                "id": "0",
+                "scaleDenominator": 559082264.0287178,
+                "cellSize": 156543.03392804097,
+                "pointOfOrigin": [-20037508.3427892, 20037508.3427892],
+                "tileWidth": 256,
+                "tileHeight": 256,
+                "matrixWidth": 1,
+                "matrixHeight": 1
+            },
+            {
+                "id": "1",
+                "scaleDenominator": 279541132.0143589,
+                "cellSize": 78271.51696402048,
+                "pointOfOrigin": [-20037508.3427892, 20037508.3427892],
+                "tileWidth": 256,
+                "tileHeight": 256,
+                "matrixWidth": 2,
+                "matrixHeight": 2
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn tile_matrix_set_to_tile_schema() {
+        let set = TileMatrixSet::from_json(TILE_MATRIX_SET.as_bytes()).unwrap();
+        let schema = set.tile_schema().unwrap();
+        let web = TileSchema::web(2);
+
+        assert_eq!(schema.crs, Crs::EPSG3857);
+        assert_eq!(schema.y_direction, VerticalDirection::TopToBottom);
+        assert_eq!(schema.lods.len(), 2);
+        assert!((schema.lod_resolution(1).unwrap() - web.lod_resolution(1).unwrap()).abs() < 1e-6);
+        assert!((schema.bounds.x_max() - web.bounds.x_max()).abs() < 1e-3);
+        assert!((schema.bounds.y_min() - web.bounds.y_min()).abs() < 1e-3);
+    }
+
+    #[test]
+    fn tile_url_from_template() {
+        let set = TileMatrixSet::from_json(TILE_MATRIX_SET.as_bytes()).unwrap();
+        let client = OgcApiClient::new("https://example.com/ogcapi/");
+        let tiles = client.collection_map_tiles("satellite", &set);
+
+        assert_eq!(
+            tiles.tile_url(&TileIndex::new(1, 0, 1)).unwrap(),
+            "https://example.com/ogcapi/collections/satellite/map/tiles/WebMercatorQuad/1/0/1"
+        );
+        assert_eq!(tiles.tile_url(&TileIndex::new(0, 0, 2)), None);
+    }
+
+    #[test]
+    fn missing_tile_matrix_is_not_requested() {
+        use crate::layer::data_provider::{DataProvider, UrlImageProvider};
+
+        let set = TileMatrixSet::from_json(TILE_MATRIX_SET.as_bytes()).unwrap();
+        let tiles =
+            OgcApiClient::new("https://example.com").collection_map_tiles("satellite", &set);
+        let provider = UrlImageProvider::new(tiles.url_source());
+
+        let result = futures::executor::block_on(provider.load_raw(&TileIndex::new(0, 0, 2)));
+        assert!(matches!(result, Err(GalileoError::NotFound)));
+    }
+
+    #[test]
+    fn items_url() {
+        let features = OgcApiClient::new("https://example.com").collection_features("roads");
+        let query = OgcFeatureQuery {
+            bbox: Some(Rect::new(1.0, 2.0, 3.0, 4.0)),
+            limit: Some(100),
+        };
+
+        assert_eq!(
+            features.items_url(&query),
+            "https://example.com/collections/roads/items?f=json&bbox=1,2,3,4&limit=100"
+        );
+    }
+
+    #[test]
+    fn parse_page_with_next_link() {
+        let page = r#"{
+            "type": "FeatureCollection",
+            "features": [
+                { "type": "Feature", "geometry": { "type": "Point", "coordinates": [1.0, 2.0] }, "properties": {} }
+            ],
+            "links": [
+                { "href": "https://example.com/items?f=json", "rel": "self" },
+                { "href": "https://example.com/items?f=json&offset=1", "rel": "next" }
+            ]
+        }"#;
+
+        let page = parse_page(page.as_bytes()).unwrap();
+        assert_eq!(page.features.len(), 1);
+        assert_eq!(
+            page.next.as_deref(),
+            Some("https://example.com/items?f=json&offset=1")
+        );
+    }
+}
@@ -0,0 +1,222 @@
+use crate::decoded_image::DecodedImage;
+use crate::error::GalileoError;
+use crate::layer::data_provider::{DataProvider, DataSource, LoadInfo};
+use crate::tile_scheme::{TileIndex, TileSchema};
+use crate::Color;
+use bytes::Bytes;
+use galileo_types::cartesian::{Point2d, Rect};
+use maybe_sync::{MaybeSend, MaybeSync};
+use std::future::Future;
+
+/// Description of a tile to be generated by a [`TileGenerator`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ProceduralTile {
+    /// Index of the tile.
+    pub index: TileIndex,
+    /// Bounding box of the tile in the projected coordinates of the tile schema.
+    pub bbox: Rect,
+    /// Width of the tile image in pixels.
+    pub width: u32,
+    /// Height of the tile image in pixels.
+    pub height: u32,
+}
+
+impl ProceduralTile {
+    /// Resolution of the tile (size of one pixel in map units).
+    pub fn resolution(&self) -> f64 {
+        self.bbox.width() / self.width as f64
+    }
+
+    /// Returns projected coordinates of the center of the pixel with the given coordinates. Pixel `(0, 0)` is the top
+    /// left pixel of the tile.
+    pub fn pixel_center(&self, x: u32, y: u32) -> Point2d {
+        let pixel_width = self.bbox.width() / self.width as f64;
+        let pixel_height = self.bbox.height() / self.height as f64;
+
+        Point2d::new(
+            self.bbox.x_min() + (x as f64 + 0.5) * pixel_width,
+            self.bbox.y_max() - (y as f64 + 0.5) * pixel_height,
+        )
+    }
+
+    /// Creates an image of the tile by calling `color_fn` for every pixel with the pixel position in the tile and
+    /// projected coordinates of its center.
+    ///
+    /// This is the simplest way to render computed data (fractals, simulation output, coverage maps) as tiles.
+    pub fn fill(&self, mut color_fn: impl FnMut(u32, u32, Point2d) -> Color) -> DecodedImage {
+        let mut bytes = Vec::with_capacity(self.width as usize * self.height as usize * 4);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                bytes.extend_from_slice(&color_fn(x, y, self.pixel_center(x, y)).to_u8_array());
+            }
+        }
+
+        DecodedImage::from_raw(bytes, self.width, self.height)
+            .expect("buffer size matches the tile size")
+    }
+}
+
+/// Generator of tile images for [`ProceduralTileProvider`].
+///
+/// The trait is implemented for all async functions and closures that take [`ProceduralTile`] and return
+/// `Result<DecodedImage, GalileoError>`.
+pub trait TileGenerator: MaybeSend + MaybeSync {
+    /// Creates an image for the given tile. The image should have the size of the tile
+    /// ([`ProceduralTile::width`] x [`ProceduralTile::height`]).
+    fn generate(
+        &self,
+        tile: ProceduralTile,
+    ) -> impl Future<Output = Result<DecodedImage, GalileoError>> + MaybeSend;
+}
+
+impl<F, Fut> TileGenerator for F
+where
+    F: Fn(ProceduralTile) -> Fut + MaybeSend + MaybeSync,
+    Fut: Future<Output = Result<DecodedImage, GalileoError>> + MaybeSend,
+{
+    fn generate(
+        &self,
+        tile: ProceduralTile,
+    ) -> impl Future<Output = Result<DecodedImage, GalileoError>> + MaybeSend {
+        self(tile)
+    }
+}
+
+/// Data provider for a [`RasterTileLayer`](crate::layer::RasterTileLayer) that generates tile images with the given
+/// [`TileGenerator`] instead of loading them.
+///
+/// Generated tiles go through the same pipeline as loaded ones, so they are cached, substituted with tiles of other
+/// levels while being generated and faded in when ready.
+///
+/// ```no_run
+/// # use galileo::Color;
+/// # use galileo::layer::data_provider::{ProceduralTile, ProceduralTileProvider};
+/// # use galileo::layer::RasterTileLayer;
+/// # use galileo::tile_scheme::TileSchema;
+/// let tile_schema = TileSchema::web(18);
+/// let provider = ProceduralTileProvider::new(tile_schema.clone(), |tile: ProceduralTile| async move {
+///     Ok(tile.fill(|x, y, _| {
+///         if (x / 32 + y / 32) % 2 == 0 {
+///             Color::BLACK
+///         } else {
+///             Color::WHITE
+///         }
+///     }))
+/// });
+/// let layer = RasterTileLayer::new(tile_schema, provider, None);
+/// ```
+pub struct ProceduralTileProvider<G> {
+    tile_schema: TileSchema,
+    generator: G,
+}
+
+impl<G: TileGenerator> ProceduralTileProvider<G> {
+    /// Creates a new provider generating tiles of the given tile schema.
+    pub fn new(tile_schema: TileSchema, generator: G) -> Self {
+        Self {
+            tile_schema,
+            generator,
+        }
+    }
+
+    /// Returns the description of the tile with the given index, or `None` if the index is not valid for the tile
+    /// schema.
+    pub fn tile(&self, index: TileIndex) -> Option<ProceduralTile> {
+        Some(ProceduralTile {
+            index,
+            bbox: self.tile_schema.tile_bbox(index)?,
+            width: self.tile_schema.tile_width(),
+            height: self.tile_schema.tile_height(),
+        })
+    }
+}
+
+impl<G: TileGenerator> DataProvider<TileIndex, DecodedImage, ()> for ProceduralTileProvider<G> {
+    fn load_raw(
+        &self,
+        _key: &TileIndex,
+    ) -> impl Future<Output = Result<Bytes, GalileoError>> + MaybeSend {
+        std::future::ready(Err(GalileoError::Generic(
+            "procedural tiles have no raw data".into(),
+        )))
+    }
+
+    fn decode(&self, _bytes: Bytes, _context: ()) -> Result<DecodedImage, GalileoError> {
+        Err(GalileoError::Generic(
+            "procedural tiles have no raw data".into(),
+        ))
+    }
+
+    async fn load(&self, key: &TileIndex, _context: ()) -> Result<DecodedImage, GalileoError> {
+        let tile = self.tile(*key).ok_or(GalileoError::NotFound)?;
+        let image = self.generator.generate(tile).await?;
+
+        if image.width() != tile.width || image.height() != tile.height {
+            log::warn!(
+                "Generated tile {key:?} has size {}x{}, expected {}x{}",
+                image.width(),
+                image.height(),
+                tile.width,
+                tile.height
+            );
+        }
+
+        Ok(image)
+    }
+
+    async fn load_with_info(
+        &self,
+        key: &TileIndex,
+        context: (),
+    ) -> (Result<DecodedImage, GalileoError>, LoadInfo) {
+        let info = LoadInfo {
+            source: Some(DataSource::Generated),
+            byte_size: None,
+        };
+        (self.load(key, context).await, info)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use galileo_types::cartesian::CartesianPoint2d;
+
+    #[test]
+    fn generates_tile_pixels() {
+        let provider =
+            ProceduralTileProvider::new(TileSchema::web(18), |tile: ProceduralTile| async move {
+                Ok(tile.fill(|_, _, point| {
+                    if point.x() < 0.0 {
+                        Color::BLACK
+                    } else {
+                        Color::WHITE
+                    }
+                }))
+            });
+
+        let image = futures::executor::block_on(provider.load(&TileIndex::new(0, 0, 1), ()))
+            .expect("failed to generate tile");
+
+        assert_eq!(image.width(), 256);
+        assert_eq!(image.height(), 256);
+        assert_eq!(&image.bytes()[0..4], &Color::BLACK.to_u8_array());
+    }
+
+    #[test]
+    fn pixel_center_is_inside_tile() {
+        let provider =
+            ProceduralTileProvider::new(TileSchema::web(18), |tile: ProceduralTile| async move {
+                Ok(tile.fill(|_, _, _| Color::BLACK))
+            });
+        let tile = provider
+            .tile(TileIndex::new(1, 1, 1))
+            .expect("tile index is valid");
+
+        let top_left = tile.pixel_center(0, 0);
+        let bottom_right = tile.pixel_center(tile.width - 1, tile.height - 1);
+        assert!(tile.bbox.contains(&top_left));
+        assert!(tile.bbox.contains(&bottom_right));
+        assert!(top_left.y() > bottom_right.y());
+    }
+}
@@ -1,7 +1,7 @@
 use crate::error::GalileoError;
 use crate::layer::data_provider::dummy::DummyCacheController;
 use crate::layer::data_provider::{
-    DataProcessor, DataProvider, PersistentCacheController, UrlSource,
+    optional_url_source, DataProcessor, DataProvider, PersistentCacheController, UrlSource,
 };
 use crate::platform::{PlatformService, PlatformServiceImpl};
 use bytes::Bytes;
@@ -15,7 +15,7 @@ where
     Decoder: DataProcessor<Input = Bytes>,
     Cache: PersistentCacheController<str, Bytes>,
 {
-    url_source: Box<dyn UrlSource<Key>>,
+    url_source: Box<dyn UrlSource<Key, Option<String>>>,
     decoder: Decoder,
     cache: Option<Cache>,
     offline_mode: bool,
@@ -29,9 +29,12 @@ where
     Decoder: DataProcessor<Input = Bytes>,
 {
     /// Creates a new instance without persistent cache.
-    pub fn new(url_source: impl UrlSource<Key> + 'static, decoder: Decoder) -> Self {
+    pub fn new<Url: Into<Option<String>>>(
+        url_source: impl UrlSource<Key, Url> + 'static,
+        decoder: Decoder,
+    ) -> Self {
         Self {
-            url_source: Box::new(url_source),
+            url_source: optional_url_source(url_source),
             decoder,
             cache: None,
             offline_mode: false,
@@ -49,13 +52,13 @@ where
     Cache: PersistentCacheController<str, Bytes> + MaybeSend + MaybeSync,
 {
     /// Creates a new instance with persistent cache.
-    pub fn new_cached(
-        url_source: impl UrlSource<Key> + 'static,
+    pub fn new_cached<Url: Into<Option<String>>>(
+        url_source: impl UrlSource<Key, Url> + 'static,
         decoder: Decoder,
         cache: Cache,
     ) -> Self {
         Self {
-            url_source: Box::new(url_source),
+            url_source: optional_url_source(url_source),
             decoder,
             cache: Some(cache),
             offline_mode: false,
@@ -94,7 +97,7 @@ where
     Cache: PersistentCacheController<str, Bytes> + MaybeSend + MaybeSync,
 {
     async fn load_raw(&self, key: &Key) -> Result<Bytes, GalileoError> {
-        let url = (self.url_source)(key);
+        let url = (self.url_source)(key).ok_or(GalileoError::NotFound)?;
         if let Some(cache) = &self.cache {
             if let Some(data) = cache.get(&url) {
                 return Ok(data);
@@ -1,7 +1,9 @@
 use crate::decoded_image::DecodedImage;
 use crate::error::GalileoError;
 use crate::layer::data_provider::dummy::DummyCacheController;
-use crate::layer::data_provider::{DataProvider, PersistentCacheController, UrlSource};
+use crate::layer::data_provider::{
+    optional_url_source, DataProvider, DataSource, LoadInfo, PersistentCacheController, UrlSource,
+};
 use crate::platform::{PlatformService, PlatformServiceImpl};
 use bytes::Bytes;
 use maybe_sync::{MaybeSend, MaybeSync};
@@ -13,7 +15,7 @@ use std::future::Future;
 /// Loads an image from Internet and uses `Cache` persistent cache controller to save it locally.
 #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
 pub struct UrlImageProvider<Key, Cache = DummyCacheController> {
-    url_source: Box<dyn UrlSource<Key>>,
+    url_source: Box<dyn UrlSource<Key, Option<String>>>,
     cache: Option<Cache>,
     platform_service: PlatformServiceImpl,
     offline_mode: bool,
@@ -22,9 +24,9 @@ pub struct UrlImageProvider<Key, Cache = DummyCacheController> {
 
 impl<Key> UrlImageProvider<Key, DummyCacheController> {
     /// Creates a new instance without persistent cache.
-    pub fn new(url_source: impl UrlSource<Key> + 'static) -> Self {
+    pub fn new<Url: Into<Option<String>>>(url_source: impl UrlSource<Key, Url> + 'static) -> Self {
         Self {
-            url_source: Box::new(url_source),
+            url_source: optional_url_source(url_source),
             cache: None,
             platform_service: PlatformServiceImpl::new(),
             offline_mode: false,
@@ -35,9 +37,12 @@ impl<Key> UrlImageProvider<Key, DummyCacheController> {
 
 impl<Key, Cache> UrlImageProvider<Key, Cache> {
     /// Creates a new instance with persistent cache.
-    pub fn new_cached(url_source: impl UrlSource<Key> + 'static, cache: Cache) -> Self {
+    pub fn new_cached<Url: Into<Option<String>>>(
+        url_source: impl UrlSource<Key, Url> + 'static,
+        cache: Cache,
+    ) -> Self {
         Self {
-            url_source: Box::new(url_source),
+            url_source: optional_url_source(url_source),
             cache: Some(cache),
             platform_service: PlatformServiceImpl::new(),
             offline_mode: false,
@@ -74,11 +79,43 @@ where
     Cache: PersistentCacheController<str, Bytes> + MaybeSend + MaybeSync,
 {
     async fn load_raw(&self, key: &Key) -> Result<Bytes, GalileoError> {
-        let url = (self.url_source)(key);
+        self.load_raw_with_source(key).await.map(|(data, _)| data)
+    }
+
+    fn decode(&self, bytes: Bytes, _context: ()) -> Result<DecodedImage, GalileoError> {
+        DecodedImage::new(&bytes)
+    }
+
+    async fn load_with_info(
+        &self,
+        key: &Key,
+        context: (),
+    ) -> (Result<DecodedImage, GalileoError>, LoadInfo) {
+        match self.load_raw_with_source(key).await {
+            Ok((data, source)) => {
+                let info = LoadInfo {
+                    source: Some(source),
+                    byte_size: Some(data.len()),
+                };
+                (self.decode(data, context), info)
+            }
+            Err(err) => (Err(err), LoadInfo::default()),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<Key, Cache> UrlImageProvider<Key, Cache>
+where
+    Cache: PersistentCacheController<str, Bytes>,
+{
+    /// Entries of the persistent cache are reported as loaded from [disk](DataSource::Disk).
+    async fn load_raw_with_source(&self, key: &Key) -> Result<(Bytes, DataSource), GalileoError> {
+        let url = (self.url_source)(key).ok_or(GalileoError::NotFound)?;
 
         if let Some(cache) = &self.cache {
             if let Some(data) = cache.get(&url) {
-                return Ok(data);
+                return Ok((data, DataSource::Disk));
             }
         }
 
@@ -93,11 +130,7 @@ where
             }
         }
 
-        Ok(data)
-    }
-
-    fn decode(&self, bytes: Bytes, _context: ()) -> Result<DecodedImage, GalileoError> {
-        DecodedImage::new(&bytes)
+        Ok((data, DataSource::Network))
     }
 }
 
@@ -119,7 +152,21 @@ where
     }
 
     async fn load(&self, key: &Key, _context: ()) -> Result<DecodedImage, GalileoError> {
-        let url = (self.url_source)(key);
+        let url = (self.url_source)(key).ok_or(GalileoError::NotFound)?;
         self.platform_service.load_image_url(&url).await
     }
+
+    /// Images are loaded by the browser, so whether they come from the browser cache or the size of the data is not
+    /// known.
+    async fn load_with_info(
+        &self,
+        key: &Key,
+        context: (),
+    ) -> (Result<DecodedImage, GalileoError>, LoadInfo) {
+        let info = LoadInfo {
+            source: Some(DataSource::Network),
+            byte_size: None,
+        };
+        (self.load(key, context).await, info)
+    }
 }
@@ -0,0 +1,251 @@
+//! [`DynamicOverlay`] layer for geometries that change every frame.
+
+use crate::layer::{Layer, LayerMemoryUsage};
+use crate::messenger::Messenger;
+use crate::render::point_paint::PointPaint;
+use crate::render::render_bundle::{RenderBundle, RenderPrimitive};
+use crate::render::{Canvas, LinePaint, PackedBundle, PolygonPaint, RenderOptions};
+use crate::view::MapView;
+use galileo_types::cartesian::{Point2d, Point3d};
+use galileo_types::impls::{ClosedContour, Contour, Polygon};
+use std::any::Any;
+use std::sync::Mutex;
+
+/// Shape drawn by a [`DynamicOverlay`]. Coordinates are given in the projected coordinates of the map CRS, e.g. as
+/// returned by [`MapView::screen_to_map`].
+#[derive(Debug, Clone)]
+pub enum OverlayShape {
+    /// A point symbol.
+    Point {
+        /// Position of the point.
+        position: Point2d,
+        /// Symbol of the point.
+        paint: PointPaint<'static>,
+    },
+    /// An open line.
+    Line {
+        /// Vertices of the line.
+        points: Vec<Point2d>,
+        /// Style of the line.
+        paint: LinePaint,
+    },
+    /// A polygon without holes.
+    Polygon {
+        /// Vertices of the polygon outline.
+        points: Vec<Point2d>,
+        /// Style of the polygon.
+        paint: PolygonPaint,
+    },
+}
+
+/// Lightweight layer for geometries that change on every frame, like previews of editing tools, rubber bands of
+/// selection rectangles or crosshairs following the pointer.
+///
+/// Unlike [`FeatureLayer`](super::FeatureLayer), the overlay does not index or cache its features: every change
+/// replaces the whole set of shapes, and the shapes are tessellated anew on the next render. The GPU buffers are kept
+/// between the renders and rewritten in place (see [`Canvas::repack_bundle`]), so updating the overlay on every pointer
+/// move does not allocate new GPU memory.
+///
+/// To update the overlay from an event handler, store it in the map wrapped in `Arc<RwLock<_>>`.
+#[derive(Default)]
+pub struct DynamicOverlay {
+    shapes: Vec<OverlayShape>,
+    version: u64,
+    packed: Mutex<Option<PackedOverlay>>,
+    messenger: Option<Box<dyn Messenger>>,
+}
+
+struct PackedOverlay {
+    version: u64,
+    bundle: Box<dyn PackedBundle>,
+}
+
+impl DynamicOverlay {
+    /// Creates a new empty overlay.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Shapes currently displayed by the overlay.
+    pub fn shapes(&self) -> &[OverlayShape] {
+        &self.shapes
+    }
+
+    /// Replaces the shapes of the overlay and requests redraw of the map.
+    pub fn set_shapes(&mut self, shapes: Vec<OverlayShape>) {
+        self.shapes = shapes;
+        self.changed();
+    }
+
+    /// Modifies the shapes of the overlay in place and requests redraw of the map.
+    pub fn update(&mut self, f: impl FnOnce(&mut Vec<OverlayShape>)) {
+        f(&mut self.shapes);
+        self.changed();
+    }
+
+    /// Removes all shapes from the overlay.
+    pub fn clear(&mut self) {
+        if !self.shapes.is_empty() {
+            self.set_shapes(vec![]);
+        }
+    }
+
+    fn changed(&mut self) {
+        self.version += 1;
+        if let Some(messenger) = &self.messenger {
+            messenger.request_redraw();
+        }
+    }
+
+    fn fill_bundle(&self, bundle: &mut RenderBundle) {
+        let to_3d = |p: &Point2d| Point3d::new(p.x, p.y, 0.0);
+
+        for shape in &self.shapes {
+            match shape {
+                OverlayShape::Point { position, paint } => {
+                    bundle.add(
+                        RenderPrimitive::<_, _, Contour<Point3d>, Polygon<Point3d>>::new_point(
+                            to_3d(position),
+                            paint.clone(),
+                        ),
+                        0.0,
+                    );
+                }
+                OverlayShape::Line { points, paint } if points.len() > 1 => {
+                    bundle.add(
+                        RenderPrimitive::<_, _, _, Polygon<Point3d>>::new_contour(
+                            Contour::open(points.iter().map(to_3d).collect()),
+                            *paint,
+                        ),
+                        0.0,
+                    );
+                }
+                OverlayShape::Polygon { points, paint } if points.len() > 2 => {
+                    bundle.add(
+                        RenderPrimitive::<_, _, Contour<Point3d>, _>::new_polygon(
+                            Polygon::new(
+                                ClosedContour::new(points.iter().map(to_3d).collect()),
+                                vec![],
+                            ),
+                            *paint,
+                        ),
+                        0.0,
+                    );
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Layer for DynamicOverlay {
+    fn render(&self, _view: &MapView, canvas: &mut dyn Canvas) {
+        if self.shapes.is_empty() {
+            return;
+        }
+
+        let mut packed = self.packed.lock().expect("mutex is poisoned");
+        if packed.as_ref().map(|p| p.version) != Some(self.version) {
+            let mut bundle = canvas.create_bundle();
+            self.fill_bundle(&mut bundle);
+
+            match &mut *packed {
+                Some(packed) => {
+                    canvas.repack_bundle(&mut packed.bundle, &bundle);
+                    packed.version = self.version;
+                }
+                None => {
+                    *packed = Some(PackedOverlay {
+                        version: self.version,
+                        bundle: canvas.pack_bundle(&bundle),
+                    })
+                }
+            }
+        }
+
+        if let Some(packed) = &*packed {
+            canvas.draw_bundles(&[&*packed.bundle], RenderOptions::default());
+        }
+    }
+
+    fn prepare(&self, _view: &MapView) {
+        // do nothing
+    }
+
+    fn set_messenger(&mut self, messenger: Box<dyn Messenger>) {
+        self.messenger = Some(messenger);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn memory_usage(&self) -> LayerMemoryUsage {
+        let gpu = self
+            .packed
+            .lock()
+            .expect("mutex is poisoned")
+            .as_ref()
+            .map_or(0, |packed| packed.bundle.gpu_size());
+
+        LayerMemoryUsage {
+            gpu,
+            ..Default::default()
+        }
+    }
+
+    fn invalidate_gpu_resources(&self) {
+        *self.packed.lock().expect("mutex is poisoned") = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::render_bundle::tessellating::TessellatingRenderBundle;
+    use crate::render::render_bundle::RenderBundleType;
+    use crate::Color;
+
+    #[test]
+    fn degenerate_shapes_are_skipped() {
+        let mut overlay = DynamicOverlay::new();
+        overlay.set_shapes(vec![
+            OverlayShape::Line {
+                points: vec![Point2d::new(0.0, 0.0)],
+                paint: LinePaint {
+                    color: Color::RED,
+                    width: 2.0,
+                    offset: 0.0,
+                    line_cap: Default::default(),
+                    line_join: Default::default(),
+                    miter_limit: LinePaint::DEFAULT_MITER_LIMIT,
+                    units: Default::default(),
+                },
+            },
+            OverlayShape::Polygon {
+                points: vec![Point2d::new(0.0, 0.0), Point2d::new(1.0, 1.0)],
+                paint: PolygonPaint { color: Color::RED },
+            },
+        ]);
+
+        let mut bundle = RenderBundle(RenderBundleType::Tessellating(
+            TessellatingRenderBundle::new(),
+        ));
+        overlay.fill_bundle(&mut bundle);
+        assert!(bundle.is_empty());
+
+        overlay.update(|shapes| {
+            shapes.push(OverlayShape::Point {
+                position: Point2d::new(1.0, 1.0),
+                paint: PointPaint::circle(Color::BLUE, 10.0),
+            })
+        });
+        overlay.fill_bundle(&mut bundle);
+        assert!(!bundle.is_empty());
+        assert_eq!(overlay.version, 2);
+    }
+}
@@ -0,0 +1,341 @@
+use crate::error::GalileoError;
+use crate::layer::feature_layer::{AttributeValue, Feature, FeatureStore};
+use std::cmp::Ordering;
+use std::io::Write;
+
+/// Direction of sorting of an [`AttributeQuery`].
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum SortOrder {
+    /// From the smallest to the largest value.
+    #[default]
+    Ascending,
+    /// From the largest to the smallest value.
+    Descending,
+}
+
+type FeatureFilter<'a, F> = dyn Fn(&F) -> bool + 'a;
+
+/// Query of the attributes of the features of a [`FeatureLayer`](super::FeatureLayer), used to build an
+/// [`AttributeTable`] with [`FeatureLayer::query_attributes`](super::FeatureLayer::query_attributes).
+///
+/// By default, the query returns all the attributes of all the features (including hidden ones) in the order they
+/// are stored in the layer.
+pub struct AttributeQuery<'a, F> {
+    columns: Option<Vec<String>>,
+    filter: Option<Box<FeatureFilter<'a, F>>>,
+    sort: Vec<(String, SortOrder)>,
+    offset: usize,
+    limit: Option<usize>,
+}
+
+impl<F> Default for AttributeQuery<'_, F> {
+    fn default() -> Self {
+        Self {
+            columns: None,
+            filter: None,
+            sort: vec![],
+            offset: 0,
+            limit: None,
+        }
+    }
+}
+
+impl<'a, F: Feature> AttributeQuery<'a, F> {
+    /// Creates a query that returns all the attributes of all the features.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the attributes to be included in the table, in the given order. By default, all the attributes of the
+    /// features are included (see [`AttributeTable::columns`]).
+    pub fn with_columns(mut self, columns: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.columns = Some(columns.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Includes only the features for which the `filter` returns `true`.
+    pub fn with_filter(mut self, filter: impl Fn(&F) -> bool + 'a) -> Self {
+        self.filter = Some(Box::new(filter));
+        self
+    }
+
+    /// Sorts the rows by the value of the attribute. If called several times, the rows are sorted by the first
+    /// attribute, then the rows with equal values by the second one and so on.
+    ///
+    /// Numbers are compared numerically, strings lexicographically. Values of different types are ordered as booleans,
+    /// numbers, strings. Features without the attribute are always placed last.
+    pub fn with_sort(mut self, column: impl Into<String>, order: SortOrder) -> Self {
+        self.sort.push((column.into(), order));
+        self
+    }
+
+    /// Skips the given number of rows (after filtering and sorting), e.g. to show the table page by page.
+    pub fn with_offset(mut self, offset: usize) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Sets the maximum number of rows in the table.
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub(super) fn execute(&self, features: &FeatureStore<F>) -> AttributeTable {
+        let mut matching: Vec<(usize, &F)> = features
+            .iter_features()
+            .enumerate()
+            .filter(|(_, feature)| self.filter.as_ref().is_none_or(|filter| filter(feature)))
+            .collect();
+
+        if !self.sort.is_empty() {
+            matching.sort_by(|(_, a), (_, b)| {
+                self.sort
+                    .iter()
+                    .map(|(column, order)| {
+                        let ordering =
+                            compare_values(a.attribute(column), b.attribute(column), *order);
+                        match order {
+                            SortOrder::Ascending => ordering,
+                            SortOrder::Descending => ordering.reverse(),
+                        }
+                    })
+                    .find(|ordering| ordering.is_ne())
+                    .unwrap_or(Ordering::Equal)
+            });
+        }
+
+        let columns = match &self.columns {
+            Some(columns) => columns.clone(),
+            None => attribute_columns(matching.iter().map(|(_, feature)| *feature)),
+        };
+
+        let total_rows = matching.len();
+        let rows = matching
+            .into_iter()
+            .skip(self.offset)
+            .take(self.limit.unwrap_or(usize::MAX))
+            .map(|(feature_index, feature)| AttributeRow {
+                feature_index,
+                values: columns
+                    .iter()
+                    .map(|column| feature.attribute(column))
+                    .collect(),
+            })
+            .collect();
+
+        AttributeTable {
+            columns,
+            rows,
+            total_rows,
+        }
+    }
+}
+
+/// Names of all the attributes of the features in the order of their first appearance.
+pub(super) fn attribute_columns<'a, F: Feature + 'a>(
+    features: impl IntoIterator<Item = &'a F>,
+) -> Vec<String> {
+    let mut columns: Vec<String> = vec![];
+    for feature in features {
+        for name in feature.attribute_names() {
+            if !columns.iter().any(|column| column == name) {
+                columns.push(name.to_string());
+            }
+        }
+    }
+
+    columns
+}
+
+/// Compares the values for sorting. Missing values are placed last regardless of the sort `order`.
+fn compare_values(
+    a: Option<AttributeValue>,
+    b: Option<AttributeValue>,
+    order: SortOrder,
+) -> Ordering {
+    let type_rank = |value: &AttributeValue| match value {
+        AttributeValue::Bool(_) => 0,
+        AttributeValue::Number(_) => 1,
+        AttributeValue::String(_) => 2,
+    };
+
+    match (a, b) {
+        (None, None) => Ordering::Equal,
+        // Reversed for the descending order, so that missing values stay last after the reverse.
+        (None, Some(_)) => match order {
+            SortOrder::Ascending => Ordering::Greater,
+            SortOrder::Descending => Ordering::Less,
+        },
+        (Some(_), None) => match order {
+            SortOrder::Ascending => Ordering::Less,
+            SortOrder::Descending => Ordering::Greater,
+        },
+        (Some(a), Some(b)) => match (&a, &b) {
+            (AttributeValue::Number(a), AttributeValue::Number(b)) => a.total_cmp(b),
+            (AttributeValue::String(a), AttributeValue::String(b)) => a.cmp(b),
+            (AttributeValue::Bool(a), AttributeValue::Bool(b)) => a.cmp(b),
+            _ => type_rank(&a).cmp(&type_rank(&b)),
+        },
+    }
+}
+
+/// Attributes of the features of a layer, returned by
+/// [`FeatureLayer::query_attributes`](super::FeatureLayer::query_attributes).
+///
+/// The table can be used to back an attribute table UI, or be exported with [`AttributeTable::write_csv`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttributeTable {
+    /// Names of the attributes, one for each value of the rows.
+    pub columns: Vec<String>,
+    /// Rows of the table, one for each feature.
+    pub rows: Vec<AttributeRow>,
+    /// Number of features that matched the query filter, before the offset and the limit were applied.
+    pub total_rows: usize,
+}
+
+/// Attributes of one feature of an [`AttributeTable`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttributeRow {
+    /// Index of the feature in the [`FeatureStore`] of the layer.
+    pub feature_index: usize,
+    /// Values of the attributes in the order of [`AttributeTable::columns`]. `None` if the feature does not have the
+    /// attribute.
+    pub values: Vec<Option<AttributeValue>>,
+}
+
+impl AttributeTable {
+    /// Returns the value of the given column in the given row.
+    pub fn value(&self, row: usize, column: &str) -> Option<&AttributeValue> {
+        let column_index = self.columns.iter().position(|c| c == column)?;
+        self.rows.get(row)?.values.get(column_index)?.as_ref()
+    }
+
+    /// Writes the table in CSV format (RFC 4180) with a header row. Missing values are written as empty fields.
+    pub fn write_csv(&self, mut writer: impl Write) -> Result<(), GalileoError> {
+        write_csv_row(&mut writer, self.columns.iter().map(String::as_str))?;
+        for row in &self.rows {
+            let values: Vec<String> = row
+                .values
+                .iter()
+                .map(|value| value.as_ref().map(|v| v.to_string()).unwrap_or_default())
+                .collect();
+            write_csv_row(&mut writer, values.iter().map(String::as_str))?;
+        }
+
+        Ok(())
+    }
+}
+
+fn write_csv_row<'a>(
+    writer: &mut impl Write,
+    fields: impl Iterator<Item = &'a str>,
+) -> Result<(), GalileoError> {
+    let line: Vec<String> = fields
+        .map(|field| {
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.to_string()
+            }
+        })
+        .collect();
+    write!(writer, "{}\r\n", line.join(","))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use galileo_types::geo::impls::GeoPoint2d;
+
+    struct City {
+        location: GeoPoint2d,
+        name: &'static str,
+        population: Option<f64>,
+    }
+
+    impl Feature for City {
+        type Geom = GeoPoint2d;
+
+        fn geometry(&self) -> &Self::Geom {
+            &self.location
+        }
+
+        fn attribute(&self, name: &str) -> Option<AttributeValue> {
+            match name {
+                "name" => Some(self.name.into()),
+                "population" => self.population.map(AttributeValue::Number),
+                _ => None,
+            }
+        }
+
+        fn attribute_names(&self) -> Vec<&str> {
+            match self.population {
+                Some(_) => vec!["name", "population"],
+                None => vec!["name"],
+            }
+        }
+    }
+
+    fn cities() -> FeatureStore<City> {
+        let city = |name, population| City {
+            location: GeoPoint2d::default(),
+            name,
+            population,
+        };
+        FeatureStore::new(
+            vec![
+                city("Rome", Some(2.8)),
+                city("Atlantis", None),
+                city("Paris, France", Some(2.1)),
+                city("Berlin", Some(3.6)),
+            ]
+            .into_iter(),
+        )
+    }
+
+    #[test]
+    fn query_filters_and_sorts() {
+        let table = AttributeQuery::new()
+            .with_filter(|city: &City| city.name != "Rome")
+            .with_sort("population", SortOrder::Descending)
+            .execute(&cities());
+
+        assert_eq!(table.columns, vec!["name", "population"]);
+        assert_eq!(table.total_rows, 3);
+        assert_eq!(
+            table
+                .rows
+                .iter()
+                .map(|row| row.feature_index)
+                .collect::<Vec<_>>(),
+            vec![3, 2, 1]
+        );
+
+        let page = AttributeQuery::new()
+            .with_sort("name", SortOrder::Ascending)
+            .with_offset(1)
+            .with_limit(2)
+            .execute(&cities());
+        assert_eq!(page.total_rows, 4);
+        assert_eq!(page.value(0, "name"), Some(&"Berlin".into()));
+        assert_eq!(page.rows.len(), 2);
+    }
+
+    #[test]
+    fn csv_export() {
+        let table = AttributeQuery::new()
+            .with_columns(["name", "population"])
+            .with_limit(3)
+            .execute(&cities());
+        let mut csv = vec![];
+        table.write_csv(&mut csv).expect("write to vec");
+
+        assert_eq!(
+            String::from_utf8(csv).expect("valid utf8"),
+            "name,population\r\nRome,2.8\r\nAtlantis,\r\n\"Paris, France\",2.1\r\n"
+        );
+    }
+}
@@ -11,6 +11,77 @@ pub trait Feature {
     type Geom: Geometry;
     /// Returns the geometry of the feature.
     fn geometry(&self) -> &Self::Geom;
+
+    /// Returns the value of the attribute with the given name, or `None` if the feature doesn't have this attribute.
+    ///
+    /// Attributes are used to calculate [statistics](super::FeatureLayer::attribute_stats) of the layer. Default
+    /// implementation returns `None` for all attributes.
+    fn attribute(&self, _name: &str) -> Option<AttributeValue> {
+        None
+    }
+
+    /// Returns the names of all the attributes of the feature.
+    ///
+    /// Names are used to build [attribute tables](super::AttributeTable) of the layer. Default implementation returns
+    /// an empty list.
+    fn attribute_names(&self) -> Vec<&str> {
+        vec![]
+    }
+}
+
+/// Value of a feature attribute.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttributeValue {
+    /// Numeric value.
+    Number(f64),
+    /// Text value.
+    String(String),
+    /// Boolean value.
+    Bool(bool),
+}
+
+impl AttributeValue {
+    /// Returns the numeric value, if the value is a number.
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            Self::Number(v) => Some(*v),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for AttributeValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Number(v) => write!(f, "{v}"),
+            Self::String(v) => write!(f, "{v}"),
+            Self::Bool(v) => write!(f, "{v}"),
+        }
+    }
+}
+
+impl From<f64> for AttributeValue {
+    fn from(value: f64) -> Self {
+        Self::Number(value)
+    }
+}
+
+impl From<String> for AttributeValue {
+    fn from(value: String) -> Self {
+        Self::String(value)
+    }
+}
+
+impl From<&str> for AttributeValue {
+    fn from(value: &str) -> Self {
+        Self::String(value.to_string())
+    }
+}
+
+impl From<bool> for AttributeValue {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
 }
 
 macro_rules! impl_feature {
@@ -1,4 +1,5 @@
-use crate::layer::feature_layer::feature::Feature;
+use crate::layer::feature_layer::feature::{AttributeValue, Feature};
+use geojson::JsonValue;
 
 impl Feature for geojson::Feature {
     type Geom = geojson::Geometry;
@@ -8,4 +9,24 @@ impl Feature for geojson::Feature {
             .as_ref()
             .expect("GeoJSON Feature has no geometry")
     }
+
+    fn attribute(&self, name: &str) -> Option<AttributeValue> {
+        match self.property(name)? {
+            JsonValue::Null => None,
+            JsonValue::Bool(v) => Some(AttributeValue::Bool(*v)),
+            JsonValue::Number(v) => v.as_f64().map(AttributeValue::Number),
+            JsonValue::String(v) => Some(AttributeValue::String(v.clone())),
+            v @ (JsonValue::Array(_) | JsonValue::Object(_)) => {
+                Some(AttributeValue::String(v.to_string()))
+            }
+        }
+    }
+
+    fn attribute_names(&self) -> Vec<&str> {
+        self.properties
+            .iter()
+            .flat_map(|properties| properties.keys())
+            .map(String::as_str)
+            .collect()
+    }
 }
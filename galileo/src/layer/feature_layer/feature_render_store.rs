@@ -1,3 +1,4 @@
+use crate::layer::LayerMemoryUsage;
 use crate::render::render_bundle::{RenderBundle, RenderPrimitive};
 use crate::render::{Canvas, PackedBundle, PrimitiveId};
 use galileo_types::cartesian::Point3d;
@@ -84,10 +85,13 @@ impl FeatureRenderStore {
         primitives: Vec<RenderPrimitive<f64, Point3d, Contour<Point3d>, Polygon<Point3d>>>,
     ) -> usize {
         let curr_bundle_index = self.curr_bundle_index();
+        let bundle = &mut self.render_bundles[curr_bundle_index];
         let ids = primitives
             .into_iter()
-            .map(|primitive| {
-                self.render_bundles[curr_bundle_index].add(primitive, self.min_resolution)
+            .map(|primitive| match primitive {
+                // Points of a layer are usually drawn with a few symbols, so they share instanced shapes.
+                RenderPrimitive::Point(point, paint) => bundle.add_instanced_point(&*point, &paint),
+                primitive => bundle.add(primitive, self.min_resolution),
             })
             .collect();
 
@@ -133,12 +137,55 @@ impl FeatureRenderStore {
         self.bundle_indices_to_pack.insert(*bundle_index);
     }
 
+    pub fn set_opacity(&mut self, render_index: usize, opacity: f32) {
+        let Some(RenderMapEntry {
+            bundle_index,
+            primitive_ids,
+        }) = self.feature_render_map.get(&render_index)
+        else {
+            return;
+        };
+
+        for id in primitive_ids {
+            if let Err(err) = self.render_bundles[*bundle_index].set_opacity(*id, opacity) {
+                log::warn!("Failed to set feature opacity: {err:?}");
+            }
+        }
+
+        self.bundle_indices_to_pack.insert(*bundle_index);
+    }
+
     pub fn pack(&mut self, canvas: &dyn Canvas) {
         for index in self.bundle_indices_to_pack.drain() {
             self.packed_bundles[index] = Some(canvas.pack_bundle(&self.render_bundles[index]));
         }
     }
 
+    pub fn needs_packing(&self) -> bool {
+        !self.bundle_indices_to_pack.is_empty()
+    }
+
+    pub fn invalidate_packed(&mut self) {
+        for packed in &mut self.packed_bundles {
+            *packed = None;
+        }
+
+        self.bundle_indices_to_pack
+            .extend(0..self.render_bundles.len());
+    }
+
+    pub fn memory_usage(&self) -> LayerMemoryUsage {
+        LayerMemoryUsage {
+            cpu_cache: self
+                .render_bundles
+                .iter()
+                .map(|bundle| bundle.approx_buffer_size())
+                .sum(),
+            gpu: self.bundles().iter().map(|bundle| bundle.gpu_size()).sum(),
+            cached_tiles: 0,
+        }
+    }
+
     pub fn bundles(&self) -> Vec<&dyn PackedBundle> {
         self.packed_bundles
             .iter()
@@ -146,3 +193,26 @@ impl FeatureRenderStore {
             .collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::render_bundle::tessellating::TessellatingRenderBundle;
+    use crate::render::render_bundle::RenderBundleType;
+
+    #[test]
+    fn invalidated_bundles_are_packed_again() {
+        let mut store = FeatureRenderStore::new(0, 1.0, 1000);
+        store.init_bundle(|| {
+            RenderBundle(RenderBundleType::Tessellating(
+                TessellatingRenderBundle::new(),
+            ))
+        });
+        store.bundle_indices_to_pack.clear();
+        assert!(!store.needs_packing());
+
+        store.invalidate_packed();
+        assert!(store.needs_packing());
+        assert!(store.bundles().is_empty());
+    }
+}
@@ -57,6 +57,32 @@ impl<'a, F> FeatureContainerMut<'a, F> {
         self.entry.is_hidden
     }
 
+    /// Opacity the feature is drawn with, from 0 (invisible) to 1 (default).
+    pub fn opacity(&self) -> f32 {
+        self.entry.opacity
+    }
+
+    /// Sets the opacity the feature is drawn with, from 0 (invisible) to 1.
+    ///
+    /// Unlike [`FeatureContainerMut::hide`], which removes the feature from the rendered data of the layer, the
+    /// opacity is applied to the already rendered primitives of the feature. This makes fading features in and out
+    /// or switching their visibility many times (e.g. when filtering the features in UI) much cheaper.
+    pub fn set_opacity(&mut self, opacity: f32) {
+        let opacity = opacity.clamp(0.0, 1.0);
+        if self.entry.opacity == opacity {
+            return;
+        }
+
+        self.entry.opacity = opacity;
+        if !self.is_updated && !self.entry.is_hidden {
+            self.pending_updates.lock().expect("poisoned mutex").push(
+                FeatureUpdate::UpdateOpacity {
+                    feature_index: self.feature_index,
+                },
+            );
+        }
+    }
+
     /// Notifies the layer that after the feature is modified, the geometry will not be changed and only the style
     /// is to be updated. If geometry might change, use [container.as_mut()](AsMut::as_mut) instead.
     pub fn edit_style(self) -> &'a mut F {
@@ -73,6 +99,9 @@ impl<'a, F> FeatureContainerMut<'a, F> {
     }
 
     /// Hides the feature from the map, but leaves it in the features list.
+    ///
+    /// The rendered data of the feature is dropped and created again when the feature is shown. To toggle visibility
+    /// often, use [`FeatureContainerMut::set_opacity`] instead.
     pub fn hide(&mut self) {
         if self.is_hidden() {
             return;
@@ -143,6 +172,7 @@ impl<F> AsMut<F> for FeatureContainerMut<'_, F> {
 pub(super) enum FeatureUpdate {
     Update { feature_index: usize },
     UpdateStyle { feature_index: usize },
+    UpdateOpacity { feature_index: usize },
     Delete { render_indices: Vec<Option<usize>> },
 }
 
@@ -191,6 +221,28 @@ impl<F> FeatureStore<F> {
         })
     }
 
+    /// Sets the opacity of the feature with the given index. See [`FeatureContainerMut::set_opacity`] for details.
+    ///
+    /// Does nothing if a feature with the given index does not exist.
+    pub fn set_opacity(&mut self, index: usize, opacity: f32) {
+        if let Some(mut feature) = self.get_mut(index) {
+            feature.set_opacity(opacity);
+        }
+    }
+
+    /// Sets the opacity of every feature to the value returned by `opacity`. Can be used to show only the features
+    /// matching a filter without rebuilding the render data of the layer:
+    ///
+    /// ```ignore
+    /// layer.features_mut().set_opacity_by(|feature| if feature.category == "X" { 1.0 } else { 0.0 });
+    /// ```
+    pub fn set_opacity_by(&mut self, opacity: impl Fn(&F) -> f32) {
+        for mut feature in self.iter_mut() {
+            let value = opacity(feature.as_ref());
+            feature.set_opacity(value);
+        }
+    }
+
     /// Removes the feature with the given returning the feature.
     ///
     /// # Panics
@@ -199,8 +251,8 @@ impl<F> FeatureStore<F> {
     pub fn remove(&mut self, index: usize) -> F {
         let FeatureEntry {
             feature,
-            is_hidden: _is_hidden,
             render_indices,
+            ..
         } = self.features.remove(index);
         self.pending_updates
             .lock()
@@ -232,6 +284,10 @@ impl<F> FeatureStore<F> {
             })
     }
 
+    pub(super) fn iter_features(&self) -> impl Iterator<Item = &F> {
+        self.features.iter().map(|entry| &entry.feature)
+    }
+
     /// Iterates over mutable containers of the features.
     pub fn iter_mut(&mut self) -> impl Iterator<Item = FeatureContainerMut<F>> {
         self.features
@@ -249,6 +305,7 @@ impl<F> FeatureStore<F> {
 pub(super) struct FeatureEntry<F> {
     feature: F,
     is_hidden: bool,
+    opacity: f32,
     render_indices: Mutex<Vec<Option<usize>>>,
 }
 
@@ -257,6 +314,7 @@ impl<F> FeatureEntry<F> {
         Self {
             feature,
             is_hidden: false,
+            opacity: 1.0,
             render_indices: Mutex::new(vec![]),
         }
     }
@@ -265,6 +323,7 @@ impl<F> FeatureEntry<F> {
         Self {
             feature,
             is_hidden: true,
+            opacity: 1.0,
             render_indices: Mutex::new(vec![]),
         }
     }
@@ -273,6 +332,10 @@ impl<F> FeatureEntry<F> {
         &self.feature
     }
 
+    pub fn opacity(&self) -> f32 {
+        self.opacity
+    }
+
     pub fn render_index(&self, render_store_id: usize) -> Option<usize> {
         self.render_indices
             .lock()
@@ -322,4 +385,21 @@ mod tests {
 
         assert_eq!(store.get(0).expect("no feature"), &"F12".to_string());
     }
+
+    #[test]
+    fn opacity_update() {
+        let mut store = FeatureStore::new(["A", "B"].into_iter());
+        store.drain_updates();
+
+        store.set_opacity_by(|feature| if *feature == "A" { 1.0 } else { 0.0 });
+        let pending_updates = store.drain_updates();
+        assert_eq!(pending_updates.len(), 1);
+        assert_matches!(
+            pending_updates[0],
+            FeatureUpdate::UpdateOpacity { feature_index: 1 }
+        );
+
+        let feature = store.get_mut(1).expect("no feature");
+        assert_eq!(feature.opacity(), 0.0);
+    }
 }
@@ -1,9 +1,10 @@
 //! [`FeatureLayer`] stores features in a [`FeatureStore`] and renders them with a [`Symbol`].
 
-use crate::layer::Layer;
+use crate::layer::{Layer, LayerMemoryUsage};
 use crate::messenger::Messenger;
-use crate::render::{Canvas, RenderOptions};
+use crate::render::{Canvas, CustomShader, RenderOptions};
 use crate::view::MapView;
+use crate::RenderQuality;
 use feature_render_store::FeatureRenderStore;
 use galileo_types::cartesian::{
     CartesianPoint2d, NewCartesianPoint2d, NewCartesianPoint3d, Point2d, Point3d, Rect,
@@ -13,27 +14,37 @@ use galileo_types::geo::impls::GeoPoint2d;
 use galileo_types::geo::{ChainProjection, Crs, InvertedProjection, NewGeoPoint, Projection};
 use galileo_types::geometry::{CartesianGeometry2d, Geom, Geometry};
 use galileo_types::geometry_type::{CartesianSpace2d, CartesianSpace3d, GeoSpace2d};
+use galileo_types::validate::{CoordinateValidation, ValidationReport};
 use maybe_sync::{MaybeSend, MaybeSync};
 use num_traits::AsPrimitive;
+use stats::StatsCache;
 use std::any::Any;
 use std::marker::PhantomData;
 use std::ops::Deref;
 use std::sync::{Mutex, RwLock};
+use validation::{PointValidator, ValidatingProjection};
 
+mod attribute_table;
 mod feature;
 mod feature_render_store;
 mod feature_store;
+mod stats;
 pub mod symbol;
+mod validation;
 
-pub use feature::Feature;
+pub use attribute_table::{AttributeQuery, AttributeRow, AttributeTable, SortOrder};
+pub use feature::{AttributeValue, Feature};
 pub use feature_store::*;
+pub use stats::AttributeStats;
 pub use symbol::Symbol;
 
 /// Feature layers render a set of [features](Feature) using [symbols](Symbol).
 ///
 /// After the layer is created, the [internal features storage](FeatureStore) can be accessed through [FeatureLayer::features] and
 /// [FeatureLayer::features_mut] methods. This storage provides methods to edit features or hide/show them without
-/// deleting from the layer.
+/// deleting from the layer. Features can also be faded or made invisible with
+/// [`FeatureStore::set_opacity`], which is applied to the already rendered data and so is cheap enough to be used for
+/// interactive filtering.
 ///
 /// All features added to the layer must be in the `CRS` of the layer. Layer will not attempt to convert geometries
 /// from incorrect CRS (as there's no way for the layer to know which CRS the geometry is projected to). On the other
@@ -42,6 +53,9 @@ pub use symbol::Symbol;
 ///
 /// Feature layer can render features differently at different resolutions. See [`FeatureLayer::with_lods`] for
 /// details.
+///
+/// Features with invalid coordinates (`NaN` values, points outside of the CRS domain or at `0, 0`) can be detected,
+/// reported and dropped or clamped when they are rendered. See [`FeatureLayer::with_coordinate_validation`].
 pub struct FeatureLayer<P, F, S, Space>
 where
     F: Feature,
@@ -53,10 +67,17 @@ where
     lods: Vec<Lod>,
     messenger: RwLock<Option<Box<dyn Messenger>>>,
     options: FeatureLayerOptions,
+    stats: Mutex<StatsCache>,
+    custom_shader: Option<CustomShader>,
+    validator: Option<PointValidator<P>>,
+    validation_report: Mutex<ValidationReport>,
+    validation_handler: Option<ValidationHandler>,
 
     space: PhantomData<Space>,
 }
 
+type ValidationHandler = Box<dyn Fn(&ValidationReport) + MaybeSend + MaybeSync>;
+
 /// Configuration of a [FeatureLayer].
 #[derive(Debug, Copy, Clone)]
 pub struct FeatureLayerOptions {
@@ -81,6 +102,14 @@ pub struct FeatureLayerOptions {
     /// If set to true, the layer will be rendered with anti-aliasing. It makes rendered lines look smoother but is a
     /// little less performant.
     pub use_antialiasing: bool,
+
+    /// If set to true, lines and polygons of the layer write their depth into the depth buffer. Such layer is rendered
+    /// in the opaque pass (see [`Layer::is_opaque`]), so translucent layers are correctly hidden behind its
+    /// 3D geometries.
+    ///
+    /// Use this for opaque 3D content like building extrusions. Translucent features should not write depth, as
+    /// features behind them would become invisible.
+    pub write_depth: bool,
 }
 
 impl Default for FeatureLayerOptions {
@@ -89,6 +118,7 @@ impl Default for FeatureLayerOptions {
             sort_by_depth: false,
             buffer_size_limit: 10_000_000,
             use_antialiasing: true,
+            write_depth: false,
         }
     }
 }
@@ -127,6 +157,11 @@ where
             messenger: RwLock::new(None),
             lods: vec![Lod::new(0, 1.0, options.buffer_size_limit)],
             options,
+            stats: Mutex::default(),
+            custom_shader: None,
+            validator: None,
+            validation_report: Mutex::default(),
+            validation_handler: None,
             space: Default::default(),
         }
     }
@@ -150,6 +185,11 @@ where
             messenger: RwLock::new(None),
             lods,
             options,
+            stats: Mutex::default(),
+            custom_shader: None,
+            validator: None,
+            validation_report: Mutex::default(),
+            validation_handler: None,
             space: Default::default(),
         }
     }
@@ -166,6 +206,47 @@ where
         self
     }
 
+    /// Sets the shader the polygons and lines of the layer are drawn with. See [`CustomShader`] for details.
+    pub fn with_custom_shader(mut self, shader: CustomShader) -> Self {
+        self.custom_shader = Some(shader);
+        self
+    }
+
+    /// Sets the callback called every time the layer finds invalid coordinates while processing added or modified
+    /// features. The callback receives the numbers of the invalid points found in the last batch of features.
+    ///
+    /// Has no effect unless coordinate validation is set up with [`FeatureLayer::with_coordinate_validation`].
+    pub fn on_invalid_coordinates(
+        mut self,
+        handler: impl Fn(&ValidationReport) + MaybeSend + MaybeSync + 'static,
+    ) -> Self {
+        self.validation_handler = Some(Box::new(handler));
+        self
+    }
+
+    /// Numbers of invalid coordinates found in the features of the layer since it was created.
+    pub fn validation_report(&self) -> ValidationReport {
+        *self.validation_report.lock().expect("mutex is poisoned")
+    }
+
+    fn set_validator(
+        &mut self,
+        validation: CoordinateValidation,
+        xy: fn(&P) -> (f64, f64),
+        new_point: fn(f64, f64) -> P,
+    ) {
+        let validation = match validation.domain {
+            Some(_) => validation,
+            None => validation.with_domain(self.crs.domain()),
+        };
+        self.validator = Some(PointValidator::new(validation, xy, new_point));
+    }
+
+    /// Sets or removes the shader the polygons and lines of the layer are drawn with.
+    pub fn set_custom_shader(&mut self, shader: Option<CustomShader>) {
+        self.custom_shader = shader;
+    }
+
     /// Returns a reference to the feature store.
     pub fn features(&self) -> &FeatureStore<F> {
         &self.features
@@ -173,6 +254,7 @@ where
 
     /// Returns a mutable reference to the feature store.
     pub fn features_mut(&mut self) -> &mut FeatureStore<F> {
+        self.reset_stats();
         &mut self.features
     }
 
@@ -180,6 +262,57 @@ where
     pub fn crs(&self) -> &Crs {
         &self.crs
     }
+
+    /// Returns the number of features in the layer, including hidden ones.
+    pub fn feature_count(&self) -> usize {
+        self.features.iter().count()
+    }
+
+    /// Returns statistics of the attribute with the given name over all features of the layer (including hidden
+    /// ones). Returns `None` if none of the features has this attribute. See [`Feature::attribute`].
+    ///
+    /// Statistics are calculated on the first request and cached until the features of the layer are modified.
+    pub fn attribute_stats(&self, name: &str) -> Option<AttributeStats> {
+        self.stats
+            .lock()
+            .expect("mutex is poisoned")
+            .attributes
+            .entry(name.to_string())
+            .or_insert_with(|| {
+                AttributeStats::from_values(
+                    self.features
+                        .iter()
+                        .filter_map(|f| f.as_ref().attribute(name)),
+                )
+            })
+            .clone()
+    }
+
+    /// Returns the names of all the attributes of the features of the layer, in the order of their first appearance.
+    /// See [`Feature::attribute_names`].
+    pub fn attribute_columns(&self) -> Vec<String> {
+        attribute_table::attribute_columns(self.features.iter_features())
+    }
+
+    /// Returns the table of the attributes of the features of the layer that match the query, e.g. to display them in
+    /// an attribute table UI or to export them to CSV.
+    ///
+    /// ```ignore
+    /// let table = layer.query_attributes(
+    ///     &AttributeQuery::new()
+    ///         .with_filter(|feature| feature.population > 1_000_000.0)
+    ///         .with_sort("population", SortOrder::Descending)
+    ///         .with_limit(50),
+    /// );
+    /// table.write_csv(std::fs::File::create("cities.csv")?)?;
+    /// ```
+    pub fn query_attributes(&self, query: &AttributeQuery<F>) -> AttributeTable {
+        query.execute(&self.features)
+    }
+
+    fn reset_stats(&mut self) {
+        *self.stats.get_mut().expect("mutex is poisoned") = StatsCache::default();
+    }
 }
 
 impl<P, F, S> FeatureLayer<P, F, S, GeoSpace2d>
@@ -192,13 +325,41 @@ where
     ///
     /// If the layer doesn't contain any features, or if at least one of them cannot be projected into the given
     /// CRS, `None` will be returned.
+    ///
+    /// The extent is calculated on the first request for the CRS and cached until the features of the layer are
+    /// modified, so it can be requested every time the map must be fitted to the layer.
     pub fn extent_projected(&self, crs: &Crs) -> Option<Rect> {
-        let projection = crs.get_projection::<P, Point2d>()?;
-        self.features
-            .iter()
-            .filter_map(|f| f.as_ref().geometry().project(&*projection))
-            .filter_map(|g| g.bounding_rectangle())
-            .collect()
+        self.stats
+            .lock()
+            .expect("mutex is poisoned")
+            .extent(crs, || {
+                let projection = crs.get_projection::<P, Point2d>()?;
+                self.features
+                    .iter()
+                    .filter_map(|f| f.as_ref().geometry().project(&*projection))
+                    .filter_map(|g| g.bounding_rectangle())
+                    .collect()
+            })
+    }
+
+    /// Returns an iterator of features that are withing `tolerance` units from the `point`. The `point` and the
+    /// `tolerance` are set in the given projected CRS (usually the CRS of the map view), and the features are
+    /// projected into it before checking.
+    ///
+    /// Features that cannot be projected into the CRS are skipped.
+    pub fn get_features_at_projected<'a>(
+        &'a self,
+        point: &'a Point2d,
+        crs: &Crs,
+        tolerance: f64,
+    ) -> impl Iterator<Item = FeatureContainer<'a, F>> + 'a {
+        let projection = crs.get_projection::<P, Point2d>();
+        self.features.iter().filter(move |f| {
+            projection
+                .as_ref()
+                .and_then(|projection| f.as_ref().geometry().project(&**projection))
+                .is_some_and(|geometry| geometry.is_point_inside(point, tolerance))
+        })
     }
 }
 
@@ -208,6 +369,26 @@ where
     F: Feature,
     F::Geom: Geometry<Point = P>,
 {
+    /// Extent (bounding rectangle) of the layer in the CRS of the layer. Returns `None` if the layer doesn't contain
+    /// any features.
+    ///
+    /// The extent is calculated on the first request and cached until the features of the layer are modified.
+    pub fn extent(&self) -> Option<Rect>
+    where
+        P: CartesianPoint2d<Num = f64>,
+        F::Geom: CartesianGeometry2d<P>,
+    {
+        self.stats
+            .lock()
+            .expect("mutex is poisoned")
+            .extent(&self.crs, || {
+                self.features
+                    .iter()
+                    .filter_map(|f| f.as_ref().geometry().bounding_rectangle())
+                    .collect()
+            })
+    }
+
     /// Returns an iterator of features that are withing `tolerance` units from the `point`. Note that the `point` is
     /// expected to be set in the layer's CRS.
     ///
@@ -241,6 +422,7 @@ where
     where
         F::Geom: CartesianGeometry2d<P>,
     {
+        *self.stats.get_mut().expect("mutex is poisoned") = StatsCache::default();
         self.features
             .iter_mut()
             .filter(move |f| f.as_ref().geometry().is_point_inside(point, tolerance))
@@ -253,16 +435,45 @@ where
     F::Geom: Geometry<Point = P>,
     S: Symbol<F>,
 {
-    fn select_lod(&self, resolution: f64) -> &Mutex<FeatureRenderStore> {
-        debug_assert!(!self.lods.is_empty());
+    fn lods_memory_usage(&self) -> LayerMemoryUsage {
+        self.lods
+            .iter()
+            .map(|lod| {
+                lod.contents
+                    .lock()
+                    .expect("mutex is poisoned")
+                    .memory_usage()
+            })
+            .fold(LayerMemoryUsage::default(), |acc, usage| acc + usage)
+    }
 
+    fn invalidate_packed_lods(&self) {
         for lod in &self.lods {
-            if lod.min_resolution < resolution {
-                return &lod.contents;
-            }
+            lod.contents
+                .lock()
+                .expect("mutex is poisoned")
+                .invalidate_packed();
         }
+    }
+
+    fn select_lod(&self, resolution: f64, quality: RenderQuality) -> &Mutex<FeatureRenderStore> {
+        debug_assert!(!self.lods.is_empty());
+
+        let index = self
+            .lods
+            .iter()
+            .position(|lod| lod.min_resolution < resolution)
+            .unwrap_or(self.lods.len() - 1);
+
+        // Lods are sorted from the coarsest to the finest, so when the device cannot keep up with the frame rate a
+        // coarser lod (with more simplified geometries) is used.
+        let index = if quality == RenderQuality::Minimal {
+            index.saturating_sub(1)
+        } else {
+            index
+        };
 
-        &self.lods[self.lods.len() - 1].contents
+        &self.lods[index].contents
     }
 
     fn render_with_projection<Proj: Projection<InPoint = P, OutPoint = Point3d> + ?Sized>(
@@ -276,17 +487,25 @@ where
             self.update_feature_renders(canvas, projection, &updates);
         }
 
-        let lod = self
-            .select_lod(view.resolution())
+        let mut lod = self
+            .select_lod(view.resolution(), canvas.quality())
             .lock()
             .expect("mutex is poisoned");
 
-        canvas.draw_bundles(
-            &lod.bundles(),
-            RenderOptions {
-                antialias: self.options.use_antialiasing,
-            },
-        );
+        // Bundles can be left unpacked if the renderer dropped its GPU resources.
+        if lod.needs_packing() {
+            lod.pack(canvas);
+        }
+
+        let options = RenderOptions {
+            antialias: self.options.use_antialiasing,
+            write_depth: self.options.write_depth,
+            ..Default::default()
+        };
+        match &self.custom_shader {
+            Some(shader) => canvas.draw_bundles_with_shader(&lod.bundles(), options, shader),
+            None => canvas.draw_bundles(&lod.bundles(), options),
+        }
     }
 
     fn update_feature_renders<Proj: Projection<InPoint = P, OutPoint = Point3d> + ?Sized>(
@@ -311,8 +530,10 @@ where
             }
         }
 
+        let mut validation_report = None;
         for lod in &self.lods {
             let mut lod = lod.contents.lock().expect("mutex is poisoned");
+            let mut lod_report = ValidationReport::default();
 
             for update in updates {
                 lod.init_bundle(|| canvas.create_bundle());
@@ -328,7 +549,7 @@ where
                             lod.remove_render(render_index);
                         }
 
-                        self.render_feature(feature_entry, &*projection, &mut lod);
+                        self.render_feature(feature_entry, &*projection, &mut lod, &mut lod_report);
                     }
                     FeatureUpdate::UpdateStyle { feature_index } => {
                         let Some(feature_entry) = self.features.get_entry(*feature_index) else {
@@ -345,22 +566,74 @@ where
                             );
                         }
                     }
+                    FeatureUpdate::UpdateOpacity { feature_index } => {
+                        let Some(feature_entry) = self.features.get_entry(*feature_index) else {
+                            log::warn!("Feature {feature_index} is not present in the store");
+                            continue;
+                        };
+
+                        if let Some(render_index) = feature_entry.render_index(lod.id()) {
+                            lod.set_opacity(render_index, feature_entry.opacity());
+                        }
+                    }
                     _ => {}
                 }
             }
 
             lod.pack(canvas);
+
+            // Every lod renders the same features, so invalid coordinates are counted only once.
+            validation_report.get_or_insert(lod_report);
+        }
+
+        if let Some(report) = validation_report.filter(|report| !report.is_clean()) {
+            self.report_invalid_coordinates(&report);
         }
     }
 
+    fn report_invalid_coordinates(&self, report: &ValidationReport) {
+        log::warn!("Features with invalid coordinates found in the layer: {report:?}");
+        self.validation_report
+            .lock()
+            .expect("mutex is poisoned")
+            .merge(report);
+
+        if let Some(handler) = &self.validation_handler {
+            handler(report);
+        }
+    }
+
+    /// Projects the geometry of the feature, applying the coordinate validation of the layer if it is set.
+    fn project_feature<Proj: Projection<InPoint = P, OutPoint = Point3d> + ?Sized>(
+        &self,
+        feature: &F,
+        projection: &Proj,
+        report: &mut ValidationReport,
+    ) -> Option<Geom<Point3d>> {
+        let Some(validator) = &self.validator else {
+            return feature.geometry().project(projection);
+        };
+
+        let validating = ValidatingProjection::new(validator, projection);
+        let projected = feature.geometry().project(&validating);
+        let feature_report = validating.into_report();
+        report.merge(&feature_report);
+        if projected.is_none() && feature_report.invalid_points() > 0 {
+            report.dropped_geometries += 1;
+        }
+
+        projected
+    }
+
     fn render_feature<Proj: Projection<InPoint = P, OutPoint = Point3d> + ?Sized>(
         &self,
         feature_entry: &FeatureEntry<F>,
         projection: &Proj,
         lod: &mut FeatureRenderStore,
+        report: &mut ValidationReport,
     ) {
         let feature = feature_entry.feature();
-        let Some(projected): Option<Geom<Point3d>> = feature.geometry().project(projection) else {
+        let Some(projected) = self.project_feature(feature, projection, report) else {
             return;
         };
 
@@ -368,6 +641,10 @@ where
             .symbol
             .render(feature, &projected, lod.min_resolution());
         let index = lod.add_primitives(primitives);
+        if feature_entry.opacity() < 1.0 {
+            lod.set_opacity(index, feature_entry.opacity());
+        }
+
         feature_entry.set_render_index(index, lod.id());
     }
 
@@ -378,7 +655,10 @@ where
         render_index: usize,
         lod: &mut FeatureRenderStore,
     ) {
-        let Some(projected): Option<Geom<Point3d>> = feature.geometry().project(projection) else {
+        // Style updates do not change geometries, so the invalid coordinates are already counted.
+        let Some(projected) =
+            self.project_feature(feature, projection, &mut ValidationReport::default())
+        else {
             return;
         };
 
@@ -396,6 +676,28 @@ where
     F::Geom: Geometry<Point = P>,
     S: Symbol<F> + MaybeSend + MaybeSync + 'static,
 {
+    /// Sets up checking of the coordinates of the features when they are added to the layer or modified. Invalid
+    /// points are counted in [`FeatureLayer::validation_report`] and passed to the
+    /// [`FeatureLayer::on_invalid_coordinates`] callback, and the features are dropped or clamped according to the
+    /// [action](CoordinateValidation::action) of the validation.
+    ///
+    /// The features are kept in the feature store as they are, only their rendering is affected. If the domain of
+    /// the validation is not set, the range of geographic coordinates is used, with longitude as `x`.
+    ///
+    /// ```ignore
+    /// let layer = FeatureLayer::new(features, symbol, Crs::WGS84)
+    ///     .with_coordinate_validation(CoordinateValidation::default())
+    ///     .on_invalid_coordinates(|report| eprintln!("Invalid points: {}", report.invalid_points()));
+    /// ```
+    pub fn with_coordinate_validation(mut self, validation: CoordinateValidation) -> Self {
+        self.set_validator(
+            validation,
+            |p| (p.lon(), p.lat()),
+            |lon, lat| P::latlon(lat, lon),
+        );
+        self
+    }
+
     fn get_projection(
         &self,
         crs: &Crs,
@@ -436,6 +738,18 @@ where
     fn as_any_mut(&mut self) -> &mut dyn Any {
         self
     }
+
+    fn is_opaque(&self) -> bool {
+        self.options.write_depth
+    }
+
+    fn memory_usage(&self) -> LayerMemoryUsage {
+        self.lods_memory_usage()
+    }
+
+    fn invalidate_gpu_resources(&self) {
+        self.invalidate_packed_lods();
+    }
 }
 
 impl<P, F, S> FeatureLayer<P, F, S, CartesianSpace2d>
@@ -445,6 +759,18 @@ where
     F::Geom: Geometry<Point = P>,
     S: Symbol<F> + MaybeSend + MaybeSync + 'static,
 {
+    /// Sets up checking of the coordinates of the features when they are added to the layer or modified. Invalid
+    /// points are counted in [`FeatureLayer::validation_report`] and passed to the
+    /// [`FeatureLayer::on_invalid_coordinates`] callback, and the features are dropped or clamped according to the
+    /// [action](CoordinateValidation::action) of the validation.
+    ///
+    /// The features are kept in the feature store as they are, only their rendering is affected. If the domain of
+    /// the validation is not set, the domain of the layer CRS is used (see [`Crs::domain`]).
+    pub fn with_coordinate_validation(mut self, validation: CoordinateValidation) -> Self {
+        self.set_validator(validation, |p| (p.x(), p.y()), P::new);
+        self
+    }
+
     fn get_projection(
         &self,
         crs: &Crs,
@@ -495,6 +821,18 @@ where
     fn as_any_mut(&mut self) -> &mut dyn Any {
         self
     }
+
+    fn is_opaque(&self) -> bool {
+        self.options.write_depth
+    }
+
+    fn memory_usage(&self) -> LayerMemoryUsage {
+        self.lods_memory_usage()
+    }
+
+    fn invalidate_gpu_resources(&self) {
+        self.invalidate_packed_lods();
+    }
 }
 
 impl<P, F, S> FeatureLayer<P, F, S, CartesianSpace3d>
@@ -543,4 +881,16 @@ where
     fn as_any_mut(&mut self) -> &mut dyn Any {
         self
     }
+
+    fn is_opaque(&self) -> bool {
+        self.options.write_depth
+    }
+
+    fn memory_usage(&self) -> LayerMemoryUsage {
+        self.lods_memory_usage()
+    }
+
+    fn invalidate_gpu_resources(&self) {
+        self.invalidate_packed_lods();
+    }
 }
@@ -0,0 +1,115 @@
+use crate::layer::feature_layer::AttributeValue;
+use galileo_types::cartesian::Rect;
+use galileo_types::geo::Crs;
+use std::collections::HashMap;
+
+/// Statistics of an attribute over all features of a [`FeatureLayer`](super::FeatureLayer).
+///
+/// Can be used to set up classification of features by the attribute value (e.g. a color ramp from `min` to `max`, or
+/// a color for each of the `unique_values`) and to generate legends.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttributeStats {
+    /// Number of features that have a value of the attribute.
+    pub count: usize,
+    /// Minimum numeric value of the attribute. `None` if none of the values are numbers.
+    pub min: Option<f64>,
+    /// Maximum numeric value of the attribute. `None` if none of the values are numbers.
+    pub max: Option<f64>,
+    /// Distinct values of the attribute in the order of their first appearance. `None` if there are more than
+    /// [`AttributeStats::MAX_UNIQUE_VALUES`] of them, since the attribute is not categorical then.
+    pub unique_values: Option<Vec<AttributeValue>>,
+}
+
+impl AttributeStats {
+    /// Maximum number of unique values collected for an attribute.
+    pub const MAX_UNIQUE_VALUES: usize = 256;
+
+    /// Calculates statistics of the given values. Returns `None` if there are no values.
+    pub fn from_values(values: impl IntoIterator<Item = AttributeValue>) -> Option<Self> {
+        let mut count = 0;
+        let mut min: Option<f64> = None;
+        let mut max: Option<f64> = None;
+        let mut unique_values = Some(vec![]);
+
+        for value in values {
+            count += 1;
+
+            if let Some(number) = value.as_number().filter(|v| !v.is_nan()) {
+                min = Some(min.map_or(number, |v| v.min(number)));
+                max = Some(max.map_or(number, |v| v.max(number)));
+            }
+
+            if let Some(unique) = &mut unique_values {
+                if !unique.contains(&value) {
+                    if unique.len() == Self::MAX_UNIQUE_VALUES {
+                        unique_values = None;
+                    } else {
+                        unique.push(value);
+                    }
+                }
+            }
+        }
+
+        (count > 0).then_some(Self {
+            count,
+            min,
+            max,
+            unique_values,
+        })
+    }
+}
+
+/// Lazily calculated statistics of the layer. Reset every time the features of the layer may be modified.
+#[derive(Debug, Default)]
+pub(super) struct StatsCache {
+    pub(super) extents: Vec<(Crs, Option<Rect>)>,
+    pub(super) attributes: HashMap<String, Option<AttributeStats>>,
+}
+
+impl StatsCache {
+    pub(super) fn extent(
+        &mut self,
+        crs: &Crs,
+        calculate: impl FnOnce() -> Option<Rect>,
+    ) -> Option<Rect> {
+        if let Some((_, extent)) = self.extents.iter().find(|(c, _)| c == crs) {
+            return *extent;
+        }
+
+        let extent = calculate();
+        self.extents.push((crs.clone(), extent));
+        extent
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numeric_stats() {
+        let stats = AttributeStats::from_values([3.0, 1.0, 2.0, 1.0].map(AttributeValue::from))
+            .expect("stats");
+        assert_eq!(stats.count, 4);
+        assert_eq!(stats.min, Some(1.0));
+        assert_eq!(stats.max, Some(3.0));
+        assert_eq!(
+            stats.unique_values,
+            Some(vec![3.0.into(), 1.0.into(), 2.0.into()])
+        );
+    }
+
+    #[test]
+    fn too_many_unique_values() {
+        let values =
+            (0..=AttributeStats::MAX_UNIQUE_VALUES).map(|v| AttributeValue::from(v as f64));
+        let stats = AttributeStats::from_values(values).expect("stats");
+        assert_eq!(stats.max, Some(AttributeStats::MAX_UNIQUE_VALUES as f64));
+        assert_eq!(stats.unique_values, None);
+    }
+
+    #[test]
+    fn no_values() {
+        assert_eq!(AttributeStats::from_values(vec![]), None);
+    }
+}
@@ -1,10 +1,10 @@
 use crate::render::render_bundle::RenderPrimitive;
 use crate::symbol::{CirclePointSymbol, SimpleContourSymbol, SimplePolygonSymbol, Symbol};
 use crate::Color;
-use galileo_types::cartesian::CartesianPoint3d;
+use galileo_types::cartesian::NewCartesianPoint3d;
 use galileo_types::geometry::Geom;
 use galileo_types::impls::{Contour, Polygon};
-use num_traits::AsPrimitive;
+use num_traits::{AsPrimitive, Float};
 
 /// Renders any type of the geometry with the set inner symbols.
 #[derive(Debug, Clone)]
@@ -47,8 +47,8 @@ impl<F> Symbol<F> for ArbitraryGeometrySymbol {
         min_resolution: f64,
     ) -> Vec<RenderPrimitive<'a, N, P, Contour<P>, Polygon<P>>>
     where
-        N: AsPrimitive<f32>,
-        P: CartesianPoint3d<Num = N> + Clone,
+        N: AsPrimitive<f32> + Float,
+        P: NewCartesianPoint3d<N> + Clone,
     {
         match geometry {
             Geom::Point(_) => self.point.render(feature, geometry, min_resolution),
@@ -1,6 +1,6 @@
 use crate::layer::feature_layer::symbol::Symbol;
 use crate::render::render_bundle::RenderPrimitive;
-use crate::render::{LineCap, LinePaint};
+use crate::render::{LineCap, LineJoin, LinePaint, SizeUnits};
 use crate::Color;
 use galileo_types::cartesian::CartesianPoint3d;
 use galileo_types::geometry::Geom;
@@ -13,14 +13,48 @@ use num_traits::AsPrimitive;
 pub struct SimpleContourSymbol {
     /// Color of the line.
     pub color: Color,
-    /// Width of the line in pixels.
+    /// Width of the line in `units`.
     pub width: f64,
+    /// Type of the cap of the line.
+    pub line_cap: LineCap,
+    /// Type of the joins between line segments.
+    pub line_join: LineJoin,
+    /// Miter limit for [`LineJoin::Miter`] joins.
+    pub miter_limit: f32,
+    /// Units of the line width.
+    pub units: SizeUnits,
 }
 
 impl SimpleContourSymbol {
     /// Creates a new instance.
     pub fn new(color: Color, width: f64) -> Self {
-        Self { color, width }
+        Self {
+            color,
+            width,
+            line_cap: LineCap::Butt,
+            line_join: LineJoin::Round,
+            miter_limit: LinePaint::DEFAULT_MITER_LIMIT,
+            units: SizeUnits::Pixels,
+        }
+    }
+
+    /// Creates a new instance from a copy of the current, but with the given line cap.
+    pub fn with_line_cap(&self, line_cap: LineCap) -> Self {
+        Self { line_cap, ..*self }
+    }
+
+    /// Creates a new instance from a copy of the current, but with the width given in the specified units.
+    pub fn with_units(&self, units: SizeUnits) -> Self {
+        Self { units, ..*self }
+    }
+
+    /// Creates a new instance from a copy of the current, but with the given line join and miter limit.
+    pub fn with_line_join(&self, line_join: LineJoin, miter_limit: f32) -> Self {
+        Self {
+            line_join,
+            miter_limit,
+            ..*self
+        }
     }
 }
 
@@ -39,7 +73,10 @@ impl<F> Symbol<F> for SimpleContourSymbol {
             color: self.color,
             width: self.width,
             offset: 0.0,
-            line_cap: LineCap::Butt,
+            line_cap: self.line_cap,
+            line_join: self.line_join,
+            miter_limit: self.miter_limit,
+            units: self.units,
         };
 
         match geometry {
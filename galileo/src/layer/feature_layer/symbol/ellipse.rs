@@ -0,0 +1,250 @@
+use crate::layer::feature_layer::symbol::{SimplePolygonSymbol, Symbol};
+use crate::render::render_bundle::RenderPrimitive;
+use crate::render::{LineCap, LineJoin, LinePaint, PolygonPaint, SizeUnits};
+use galileo_types::cartesian::{NewCartesianPoint3d, Point2d};
+use galileo_types::geo::impls::GeoPoint2d;
+use galileo_types::geo::{Crs, GeoPoint, NewGeoPoint};
+use galileo_types::geometry::Geom;
+use galileo_types::impls::{ClosedContour, Contour, Polygon};
+use galileo_types::MultiPoint;
+use maybe_sync::{MaybeSend, MaybeSync};
+use num_traits::{AsPrimitive, Float, NumCast};
+use std::f64::consts::PI;
+
+/// Mean radius of the Earth in meters.
+const EARTH_RADIUS: f64 = 6_371_008.8;
+
+/// Default number of vertices in the rendered ellipse.
+const DEFAULT_SEGMENTS: usize = 64;
+
+/// Ellipse on the surface of the Earth with the axes in meters, e.g. an error ellipse of a GPS position.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct GeodesicEllipse {
+    /// Length of the semi-major axis in meters.
+    pub semi_major: f64,
+    /// Length of the semi-minor axis in meters.
+    pub semi_minor: f64,
+    /// Direction of the major axis in degrees clockwise from the north.
+    pub orientation: f64,
+}
+
+impl GeodesicEllipse {
+    /// Creates a new ellipse.
+    pub fn new(semi_major: f64, semi_minor: f64, orientation: f64) -> Self {
+        Self {
+            semi_major,
+            semi_minor,
+            orientation,
+        }
+    }
+
+    /// Creates a circle with the given radius in meters.
+    pub fn circle(radius: f64) -> Self {
+        Self::new(radius, radius, 0.0)
+    }
+
+    /// Distance from the center to the ellipse in the direction of the given bearing (in radians).
+    fn radius_at(&self, bearing: f64) -> f64 {
+        let angle = bearing - self.orientation.to_radians();
+        let (a, b) = (self.semi_major, self.semi_minor);
+        a * b / ((b * angle.cos()).powi(2) + (a * angle.sin()).powi(2)).sqrt()
+    }
+}
+
+/// Renders a point as a circle or an ellipse with the size given in meters on the ground, e.g. accuracy of a GPS
+/// position, blast radius or coverage area of an antenna.
+///
+/// Unlike [`CirclePointSymbol`](super::CirclePointSymbol), whose size is fixed in pixels, the ellipse is calculated
+/// on the surface of the Earth and then projected into the map CRS, so it has correct size at any latitude (e.g. in
+/// Web Mercator a circle of 1 km near the poles looks much larger than at the equator).
+///
+/// Since symbols receive the geometries already projected, the symbol must know the CRS of the feature layer it is
+/// used in. By default it is `EPSG:3857`. For geometries that cannot be unprojected, nothing is rendered.
+pub struct GeodesicEllipseSymbol<F> {
+    /// Fill and outline of the ellipse.
+    pub polygon: SimplePolygonSymbol,
+    crs: Crs,
+    segments: usize,
+    ellipse: Box<EllipseFn<F>>,
+}
+
+type EllipseFn<F> = dyn Fn(&F) -> Option<GeodesicEllipse> + MaybeSend + MaybeSync;
+
+impl<F> GeodesicEllipseSymbol<F> {
+    /// Creates a new symbol. The `ellipse` function returns the ellipse for every feature. Features for which it
+    /// returns `None` are not rendered.
+    pub fn new(
+        polygon: SimplePolygonSymbol,
+        ellipse: impl Fn(&F) -> Option<GeodesicEllipse> + MaybeSend + MaybeSync + 'static,
+    ) -> Self {
+        Self {
+            polygon,
+            crs: Crs::EPSG3857,
+            segments: DEFAULT_SEGMENTS,
+            ellipse: Box::new(ellipse),
+        }
+    }
+
+    /// Creates a new symbol that renders the same ellipse for all features.
+    pub fn fixed(polygon: SimplePolygonSymbol, ellipse: GeodesicEllipse) -> Self {
+        Self::new(polygon, move |_| Some(ellipse))
+    }
+
+    /// Sets the CRS of the feature layer the symbol is used with.
+    pub fn with_crs(mut self, crs: Crs) -> Self {
+        self.crs = crs;
+        self
+    }
+
+    /// Sets the number of vertices of the rendered ellipse. Default value is 64.
+    pub fn with_segments(mut self, segments: usize) -> Self {
+        self.segments = segments.max(3);
+        self
+    }
+
+    fn ellipse_polygon<N, P>(&self, center: &P, ellipse: &GeodesicEllipse) -> Option<Polygon<P>>
+    where
+        N: Float,
+        P: NewCartesianPoint3d<N>,
+    {
+        let projection = self.crs.get_projection::<GeoPoint2d, Point2d>()?;
+        let geo_center =
+            projection.unproject(&Point2d::new(center.x().to_f64()?, center.y().to_f64()?))?;
+
+        let points = geodesic_ellipse(&geo_center, ellipse, self.segments)
+            .iter()
+            .map(|point| {
+                let projected = projection.project(point)?;
+                Some(P::new(
+                    <N as NumCast>::from(projected.x)?,
+                    <N as NumCast>::from(projected.y)?,
+                    center.z(),
+                ))
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        Some(Polygon::new(ClosedContour::new(points), vec![]))
+    }
+}
+
+impl<F> Symbol<F> for GeodesicEllipseSymbol<F> {
+    fn render<'a, N, P>(
+        &self,
+        feature: &F,
+        geometry: &'a Geom<P>,
+        _min_resolution: f64,
+    ) -> Vec<RenderPrimitive<'a, N, P, Contour<P>, Polygon<P>>>
+    where
+        N: AsPrimitive<f32> + Float,
+        P: NewCartesianPoint3d<N> + Clone,
+    {
+        let Some(ellipse) = (self.ellipse)(feature) else {
+            return vec![];
+        };
+        if ellipse.semi_major <= 0.0 || ellipse.semi_minor <= 0.0 {
+            return vec![];
+        }
+
+        let centers: Vec<&P> = match geometry {
+            Geom::Point(point) => vec![point],
+            Geom::MultiPoint(points) => points.iter_points().collect(),
+            _ => vec![],
+        };
+
+        let line_paint = LinePaint {
+            color: self.polygon.stroke_color,
+            width: self.polygon.stroke_width,
+            offset: self.polygon.stroke_offset,
+            line_cap: LineCap::Butt,
+            line_join: LineJoin::Round,
+            miter_limit: LinePaint::DEFAULT_MITER_LIMIT,
+            units: SizeUnits::Pixels,
+        };
+
+        let mut primitives = vec![];
+        for center in centers {
+            let Some(polygon) = self.ellipse_polygon(center, &ellipse) else {
+                continue;
+            };
+
+            let outline = polygon.outer_contour.clone();
+            primitives.push(RenderPrimitive::new_polygon(
+                polygon,
+                PolygonPaint {
+                    color: self.polygon.fill_color,
+                },
+            ));
+
+            if self.polygon.stroke_width > 0.0 {
+                primitives.push(RenderPrimitive::new_contour(outline.into(), line_paint));
+            }
+        }
+
+        primitives
+    }
+}
+
+/// Returns points of an ellipse around the center on the surface of the Earth, starting from the north and going
+/// clockwise.
+pub(crate) fn geodesic_ellipse(
+    center: &GeoPoint2d,
+    ellipse: &GeodesicEllipse,
+    segments: usize,
+) -> Vec<GeoPoint2d> {
+    let lat = center.lat().to_radians();
+    let lon = center.lon().to_radians();
+
+    (0..segments)
+        .map(|i| {
+            let bearing = 2.0 * PI * i as f64 / segments as f64;
+            let distance = ellipse.radius_at(bearing) / EARTH_RADIUS;
+            let point_lat =
+                (lat.sin() * distance.cos() + lat.cos() * distance.sin() * bearing.cos()).asin();
+            let point_lon = lon
+                + (bearing.sin() * distance.sin() * lat.cos())
+                    .atan2(distance.cos() - lat.sin() * point_lat.sin());
+            GeoPoint2d::latlon(point_lat.to_degrees(), point_lon.to_degrees())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use galileo_types::cartesian::Point3d;
+
+    #[test]
+    fn ellipse_axes() {
+        let center = GeoPoint2d::latlon(0.0, 0.0);
+        let points = geodesic_ellipse(&center, &GeodesicEllipse::new(2000.0, 1000.0, 90.0), 4);
+
+        // Major axis points to the east, minor axis to the north.
+        assert!((points[0].lat() - 0.008_993).abs() < 1e-5);
+        assert!((points[1].lon() - 0.017_986).abs() < 1e-5);
+    }
+
+    #[test]
+    fn circle_is_larger_in_mercator_at_high_latitudes() {
+        let symbol = GeodesicEllipseSymbol::<()>::fixed(
+            SimplePolygonSymbol::new(Default::default()),
+            GeodesicEllipse::circle(1000.0),
+        );
+        let projection = Crs::EPSG3857
+            .get_projection::<GeoPoint2d, Point2d>()
+            .expect("projection exists");
+
+        let projected_radius = |lat: f64| {
+            let center = projection
+                .project(&GeoPoint2d::latlon(lat, 0.0))
+                .expect("valid point");
+            let center = Point3d::new(center.x, center.y, 0.0);
+            let polygon = symbol
+                .ellipse_polygon(&center, &GeodesicEllipse::circle(1000.0))
+                .expect("valid ellipse");
+            polygon.outer_contour.points[0].y - center.y
+        };
+
+        let ratio = projected_radius(60.0) / projected_radius(0.0);
+        assert!((ratio - 2.0).abs() < 0.01, "ratio: {ratio}");
+    }
+}
@@ -2,20 +2,23 @@
 //! [`Symbol`] trait is designed to be easy to implement, so an application may provide rendering logic for the
 //! features it uses. But a few simple implementations are provided for convenience.
 
-use num_traits::AsPrimitive;
+use num_traits::{AsPrimitive, Float};
 
 mod arbitrary;
 mod contour;
+mod ellipse;
 mod point;
 mod polygon;
 
 pub use arbitrary::ArbitraryGeometrySymbol;
 pub use contour::SimpleContourSymbol;
+pub(crate) use ellipse::geodesic_ellipse;
+pub use ellipse::{GeodesicEllipse, GeodesicEllipseSymbol};
 pub use point::{CirclePointSymbol, ImagePointSymbol};
-pub use polygon::SimplePolygonSymbol;
+pub use polygon::{LabeledPolygonSymbol, SimplePolygonSymbol};
 
 use crate::render::render_bundle::RenderPrimitive;
-use galileo_types::cartesian::CartesianPoint3d;
+use galileo_types::cartesian::NewCartesianPoint3d;
 use galileo_types::geometry::Geom;
 use galileo_types::impls::{Contour, Polygon};
 
@@ -31,6 +34,8 @@ pub trait Symbol<F> {
     /// The `min_resolution` argument specifies the minimum map resolution that the returned primitives will be
     /// rendered with. This can be use to choose tolerances or pick entirely different rendering strategy. For example,
     /// a building may be rendered as a polygon at high resolution or as a point at low resolutions.
+    ///
+    /// Points of the `geometry` can be constructed by the symbol, e.g. to place a label inside a polygon.
     fn render<'a, N, P>(
         &self,
         feature: &F,
@@ -38,6 +43,6 @@ pub trait Symbol<F> {
         min_resolution: f64,
     ) -> Vec<RenderPrimitive<'a, N, P, Contour<P>, Polygon<P>>>
     where
-        N: AsPrimitive<f32>,
-        P: CartesianPoint3d<Num = N> + Clone;
+        N: AsPrimitive<f32> + Float,
+        P: NewCartesianPoint3d<N> + Clone;
 }
@@ -1,12 +1,17 @@
 use crate::layer::feature_layer::symbol::Symbol;
+use crate::render::point_paint::PointPaint;
 use crate::render::render_bundle::RenderPrimitive;
-use crate::render::{LineCap, LinePaint, PolygonPaint};
+use crate::render::text::TextStyle;
+use crate::render::{LineCap, LineJoin, LinePaint, PolygonPaint, SizeUnits};
 use crate::Color;
-use galileo_types::cartesian::CartesianPoint3d;
+use galileo_types::cartesian::{
+    CartesianPoint3d, MultiPolygonLabelPoint, NewCartesianPoint3d, Point2d,
+};
 use galileo_types::geometry::Geom;
 use galileo_types::impls::Contour;
 use galileo_types::{MultiPolygon, Polygon};
-use num_traits::AsPrimitive;
+use maybe_sync::{MaybeSend, MaybeSync};
+use num_traits::{AsPrimitive, Float, NumCast};
 
 /// Renders a polygon geometry as a filled polygon with an outline.
 #[derive(Debug, Clone, Copy)]
@@ -78,6 +83,9 @@ impl SimplePolygonSymbol {
             width: self.stroke_width,
             offset: self.stroke_offset,
             line_cap: LineCap::Butt,
+            line_join: LineJoin::Round,
+            miter_limit: LinePaint::DEFAULT_MITER_LIMIT,
+            units: SizeUnits::Pixels,
         };
 
         for contour in polygon.iter_contours() {
@@ -112,3 +120,94 @@ impl<F> Symbol<F> for SimplePolygonSymbol {
         }
     }
 }
+
+/// Renders a polygon geometry with a [`SimplePolygonSymbol`] and a text label at the visual center of the polygon.
+///
+/// The label is placed at the pole of inaccessibility of the polygon (see
+/// [`PolygonLabelPoint`](galileo_types::cartesian::PolygonLabelPoint)), so it stays inside concave polygons and
+/// polygons with holes. Multipolygons get a single label inside their largest part.
+pub struct LabeledPolygonSymbol<F> {
+    /// Symbol of the polygon itself.
+    pub polygon: SimplePolygonSymbol,
+    /// Style of the label text.
+    pub text_style: TextStyle,
+    label: Box<LabelFn<F>>,
+}
+
+type LabelFn<F> = dyn Fn(&F) -> Option<String> + MaybeSend + MaybeSync;
+
+impl<F> LabeledPolygonSymbol<F> {
+    /// Creates a new instance. The `label` function returns the text of the label for a feature, or `None` if the
+    /// feature should not be labeled.
+    pub fn new(
+        polygon: SimplePolygonSymbol,
+        text_style: TextStyle,
+        label: impl Fn(&F) -> Option<String> + MaybeSend + MaybeSync + 'static,
+    ) -> Self {
+        Self {
+            polygon,
+            text_style,
+            label: Box::new(label),
+        }
+    }
+
+    fn label_point<N, P>(geometry: &Geom<P>, precision: f64) -> Option<P>
+    where
+        N: Float,
+        P: NewCartesianPoint3d<N>,
+    {
+        let to_2d = |p: &P| {
+            Point2d::new(
+                p.x().to_f64().unwrap_or_default(),
+                p.y().to_f64().unwrap_or_default(),
+            )
+        };
+
+        let point = match geometry {
+            Geom::Polygon(polygon) => {
+                galileo_types::impls::MultiPolygon::from(vec![polygon.cast_points(to_2d)])
+                    .label_point(precision)
+            }
+            Geom::MultiPolygon(polygons) => galileo_types::impls::MultiPolygon::from(
+                polygons
+                    .polygons()
+                    .map(|polygon| polygon.cast_points(to_2d))
+                    .collect::<Vec<_>>(),
+            )
+            .label_point(precision),
+            _ => None,
+        }?;
+
+        Some(P::new(
+            <N as NumCast>::from(point.x)?,
+            <N as NumCast>::from(point.y)?,
+            N::zero(),
+        ))
+    }
+}
+
+impl<F> Symbol<F> for LabeledPolygonSymbol<F> {
+    fn render<'a, N, P>(
+        &self,
+        feature: &F,
+        geometry: &'a Geom<P>,
+        min_resolution: f64,
+    ) -> Vec<RenderPrimitive<'a, N, P, Contour<P>, galileo_types::impls::Polygon<P>>>
+    where
+        N: AsPrimitive<f32> + Float,
+        P: NewCartesianPoint3d<N> + Clone,
+    {
+        let mut primitives = self.polygon.render(feature, geometry, min_resolution);
+
+        if let Some(text) = (self.label)(feature) {
+            if let Some(point) = Self::label_point(geometry, min_resolution) {
+                primitives.push(RenderPrimitive::new_point(
+                    point,
+                    PointPaint::label_owed(text, self.text_style.clone()),
+                ));
+            }
+        }
+
+        primitives
+    }
+}
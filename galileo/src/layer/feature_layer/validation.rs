@@ -0,0 +1,120 @@
+use galileo_types::geo::Projection;
+use galileo_types::validate::{CoordinateValidation, ValidationReport};
+use std::cell::Cell;
+
+/// Coordinate validation of a [`FeatureLayer`](super::FeatureLayer) together with the way to access coordinates of
+/// the points of the layer.
+pub(super) struct PointValidator<P> {
+    validation: CoordinateValidation,
+    xy: fn(&P) -> (f64, f64),
+    new_point: fn(f64, f64) -> P,
+}
+
+impl<P> PointValidator<P> {
+    pub(super) fn new(
+        validation: CoordinateValidation,
+        xy: fn(&P) -> (f64, f64),
+        new_point: fn(f64, f64) -> P,
+    ) -> Self {
+        Self {
+            validation,
+            xy,
+            new_point,
+        }
+    }
+}
+
+/// Projection that validates the points before passing them to the inner projection, counting the invalid ones.
+pub(super) struct ValidatingProjection<'a, P, Proj: ?Sized> {
+    validator: &'a PointValidator<P>,
+    inner: &'a Proj,
+    report: Cell<ValidationReport>,
+}
+
+impl<'a, P, Proj: ?Sized> ValidatingProjection<'a, P, Proj> {
+    pub(super) fn new(validator: &'a PointValidator<P>, inner: &'a Proj) -> Self {
+        Self {
+            validator,
+            inner,
+            report: Cell::new(ValidationReport::default()),
+        }
+    }
+
+    pub(super) fn into_report(self) -> ValidationReport {
+        self.report.into_inner()
+    }
+}
+
+impl<P, Proj> Projection for ValidatingProjection<'_, P, Proj>
+where
+    Proj: Projection<InPoint = P> + ?Sized,
+{
+    type InPoint = P;
+    type OutPoint = Proj::OutPoint;
+
+    fn project(&self, input: &P) -> Option<Self::OutPoint> {
+        let (x, y) = (self.validator.xy)(input);
+        let mut report = self.report.get();
+        let validated = self.validator.validation.validate(x, y, &mut report);
+        self.report.set(report);
+
+        match validated? {
+            (vx, vy) if vx == x && vy == y => self.inner.project(input),
+            (vx, vy) => self.inner.project(&(self.validator.new_point)(vx, vy)),
+        }
+    }
+
+    fn unproject(&self, input: &Self::OutPoint) -> Option<P> {
+        self.inner.unproject(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use galileo_types::cartesian::{Point2d, Rect};
+    use galileo_types::geo::impls::projection::IdentityProjection;
+    use galileo_types::geometry::{Geom, Geometry};
+    use galileo_types::geometry_type::CartesianSpace2d;
+    use galileo_types::impls::Contour;
+    use galileo_types::validate::InvalidCoordinateAction;
+
+    #[test]
+    fn projection_counts_invalid_points() {
+        let validator = PointValidator::new(
+            CoordinateValidation::new(Some(Rect::new(-10.0, -10.0, 10.0, 10.0))),
+            |p: &Point2d| (p.x, p.y),
+            Point2d::new,
+        );
+        let identity = IdentityProjection::<Point2d, Point2d, CartesianSpace2d>::new();
+        let contour = Contour::open(vec![
+            Point2d::new(1.0, 1.0),
+            Point2d::new(20.0, 1.0),
+            Point2d::new(f64::NAN, 1.0),
+        ]);
+
+        let projection = ValidatingProjection::new(&validator, &identity);
+        assert!(contour.project(&projection).is_none());
+        let report = projection.into_report();
+        assert_eq!(report.out_of_domain, 1);
+
+        let validator = PointValidator::new(
+            validator
+                .validation
+                .with_action(InvalidCoordinateAction::Clamp),
+            |p: &Point2d| (p.x, p.y),
+            Point2d::new,
+        );
+        let contour = Contour::open(vec![Point2d::new(1.0, 1.0), Point2d::new(20.0, 1.0)]);
+        let projection = ValidatingProjection::new(&validator, &identity);
+        let projected = contour.project(&projection).expect("clamped contour");
+        assert_eq!(
+            projected,
+            Geom::Contour(Contour::open(vec![
+                Point2d::new(1.0, 1.0),
+                Point2d::new(10.0, 1.0)
+            ]))
+        );
+        assert_eq!(projection.into_report().clamped_points, 1);
+    }
+}
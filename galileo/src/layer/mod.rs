@@ -2,19 +2,28 @@
 
 use crate::messenger::Messenger;
 use crate::render::Canvas;
+use crate::tile_scheme::TileIndex;
 use crate::view::MapView;
+use galileo_types::geo::Crs;
 use maybe_sync::{MaybeSend, MaybeSync};
 use std::any::Any;
+use std::ops::{Add, AddAssign};
 use std::sync::{Arc, RwLock};
 
+pub mod cell_layer;
 pub mod data_provider;
+pub mod dynamic_overlay;
 pub mod feature_layer;
 mod raster_tile_layer;
+pub mod tile_diagnostics;
 pub mod vector_tile_layer;
+pub mod wind_layer;
 
+pub use dynamic_overlay::DynamicOverlay;
 pub use feature_layer::FeatureLayer;
 pub use raster_tile_layer::RasterTileLayer;
 pub use vector_tile_layer::VectorTileLayer;
+pub use wind_layer::WindLayer;
 
 /// Layers specify a data source and the way the data should be rendered to the map.
 ///
@@ -23,6 +32,18 @@ pub use vector_tile_layer::VectorTileLayer;
 /// * [`VectorTileLayer`] - downloads vector tiles (in MVT format) from an Internet source and draws them using the
 ///   provided stylesheet.
 /// * [`FeatureLayer`] - draws custom set of geographic objects with the given [`feature_layer::Symbol`];
+///
+/// # Rendering order
+///
+/// Layers are rendered in two passes:
+/// 1. All the layers that return `true` from [`Layer::is_opaque`] are rendered in the order they are stored in the
+///    map's [`LayerCollection`](crate::LayerCollection).
+/// 2. Then all other (translucent) layers are rendered in the same order, i.e. from back to front.
+///
+/// Opaque layers usually draw their primitives with
+/// [`RenderOptions::write_depth`](crate::render::RenderOptions::write_depth) set, so the translucent layers rendered
+/// after them are correctly hidden behind 3D objects like extrusions. Inside one layer, primitives are drawn in the
+/// order they were added to the render bundles, unless the layer sorts them by depth explicitly.
 pub trait Layer: MaybeSend + MaybeSync {
     /// Renders the layer to the given canvas.
     fn render(&self, view: &MapView, canvas: &mut dyn Canvas);
@@ -36,6 +57,85 @@ pub trait Layer: MaybeSend + MaybeSync {
     fn as_any(&self) -> &dyn Any;
     /// A map stores layers as trait objects. This method can be used to convert the trait object into the concrete type.
     fn as_any_mut(&mut self) -> &mut dyn Any;
+    /// If true, the layer is rendered in the opaque pass, before all translucent layers. See
+    /// [rendering order](Layer#rendering-order).
+    fn is_opaque(&self) -> bool {
+        false
+    }
+    /// Approximate amount of memory used by the layer caches. Layers that do not cache any data return zero usage.
+    fn memory_usage(&self) -> LayerMemoryUsage {
+        LayerMemoryUsage::default()
+    }
+    /// Drops the data the layer can restore later (e.g. cached tiles), to free memory. The dropped data is loaded
+    /// again on the next [`Layer::prepare`] call if it is needed.
+    fn trim_memory(&self) {}
+    /// Drops all [packed bundles](crate::render::PackedBundle) held by the layer, so they are packed again on the next
+    /// render. Called after the renderer recreated its GPU state (see
+    /// [`RendererEvent::RendererRestarted`](crate::render::RendererEvent::RendererRestarted)).
+    ///
+    /// The default implementation calls [`Layer::trim_memory`].
+    fn invalidate_gpu_resources(&self) {
+        self.trim_memory()
+    }
+    /// Resolutions the layer looks best at when rendered in the given CRS, e.g. the resolutions of the levels of its
+    /// tile schema. [`MapController`](crate::control::MapController) can snap zooming to these resolutions (see
+    /// [`ZoomSnapping::Layers`](crate::control::ZoomSnapping::Layers)).
+    ///
+    /// Layers that look the same at any resolution (the default) return `None`.
+    fn zoom_levels(&self, _crs: &Crs) -> Option<Vec<f64>> {
+        None
+    }
+    /// Indices of the tiles the layer has drawn recently, from the least to the most recently used. Used to build a
+    /// [`TileManifest`](crate::TileManifest) that allows loading these tiles on the next start of the application.
+    ///
+    /// Layers that do not consist of tiles (the default) return an empty list.
+    fn recent_tiles(&self) -> Vec<TileIndex> {
+        vec![]
+    }
+    /// Starts loading the given tiles in the background, so they are drawn without delay when the map shows them,
+    /// e.g. the tiles of a [`TileManifest`](crate::TileManifest) saved in a previous session. Tile layers with a
+    /// persistent cache read the tiles from the cache.
+    ///
+    /// Default implementation does nothing.
+    fn warm_up(&self, _tiles: &[TileIndex]) {}
+}
+
+/// Approximate amount of memory used by a layer.
+///
+/// All the values are estimates, since the layers do not track exact sizes of the allocations.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct LayerMemoryUsage {
+    /// Size of the CPU-side caches (decoded tiles, tessellated geometries, images) in bytes.
+    pub cpu_cache: usize,
+    /// Size of the GPU buffers and textures in bytes.
+    pub gpu: usize,
+    /// Number of tiles stored in the layer cache. Zero for non-tile layers.
+    pub cached_tiles: usize,
+}
+
+impl LayerMemoryUsage {
+    /// Total number of bytes used by the layer, both on CPU and GPU side.
+    pub fn total_bytes(&self) -> usize {
+        self.cpu_cache + self.gpu
+    }
+}
+
+impl Add for LayerMemoryUsage {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self {
+            cpu_cache: self.cpu_cache + rhs.cpu_cache,
+            gpu: self.gpu + rhs.gpu,
+            cached_tiles: self.cached_tiles + rhs.cached_tiles,
+        }
+    }
+}
+
+impl AddAssign for LayerMemoryUsage {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
 }
 
 impl<T: Layer + 'static> Layer for Arc<RwLock<T>> {
@@ -62,6 +162,36 @@ impl<T: Layer + 'static> Layer for Arc<RwLock<T>> {
     fn as_any_mut(&mut self) -> &mut dyn Any {
         self
     }
+
+    fn is_opaque(&self) -> bool {
+        self.read().expect("lock is poisoned").is_opaque()
+    }
+
+    fn memory_usage(&self) -> LayerMemoryUsage {
+        self.read().expect("lock is poisoned").memory_usage()
+    }
+
+    fn trim_memory(&self) {
+        self.read().expect("lock is poisoned").trim_memory()
+    }
+
+    fn invalidate_gpu_resources(&self) {
+        self.read()
+            .expect("lock is poisoned")
+            .invalidate_gpu_resources()
+    }
+
+    fn zoom_levels(&self, crs: &Crs) -> Option<Vec<f64>> {
+        self.read().expect("lock is poisoned").zoom_levels(crs)
+    }
+
+    fn recent_tiles(&self) -> Vec<TileIndex> {
+        self.read().expect("lock is poisoned").recent_tiles()
+    }
+
+    fn warm_up(&self, tiles: &[TileIndex]) {
+        self.read().expect("lock is poisoned").warm_up(tiles)
+    }
 }
 
 /// Used for doc-tests
@@ -1,20 +1,40 @@
+use crate::alloc_audit::ScratchBuffer;
+use crate::cancellation::{CancelOnDrop, CancellationToken};
 use crate::decoded_image::DecodedImage;
 use crate::layer::data_provider::DataProvider;
+use crate::layer::tile_diagnostics::{TileDebugOverlay, TileDiagnostics};
 use crate::messenger::Messenger;
 use crate::render::render_bundle::RenderBundle;
-use crate::render::{Canvas, ImagePaint, PackedBundle, PrimitiveId, RenderOptions};
+use crate::render::{
+    draw_bundles_with_opacity, Canvas, CustomShader, ImagePaint, PackedBundle, RenderOptions,
+};
 use crate::tile_scheme::{TileIndex, TileSchema};
 use crate::view::MapView;
+use galileo_types::cartesian::Rect;
+use galileo_types::geo::Crs;
 use maybe_sync::{MaybeSend, MaybeSync, Mutex};
 use quick_cache::sync::Cache;
 use std::any::Any;
 use std::collections::HashSet;
 use std::sync::Arc;
-use web_time::{Duration, SystemTime};
+use web_time::{Duration, Instant, SystemTime};
 
-use super::Layer;
+use super::{Layer, LayerMemoryUsage};
+
+/// Maximum number of tiles returned by [`Layer::recent_tiles`].
+const RECENT_TILES_CAPACITY: usize = 256;
 
 /// Raster tile layers load prerender tile sets using [`Provider`](DataProvider) and render them to the map.
+///
+/// # Blending of tiles
+///
+/// While the tiles of the current zoom level are loading, they are substituted by the tiles of other levels, drawn
+/// under them. Newly loaded tiles fade in over the substitutes (see [`RasterTileLayer::set_fade_in_duration`]), so
+/// the change of resolution is not noticeable as a jump.
+///
+/// Every tile is also extended by a narrow skirt over its neighbours (see [`RasterTileLayer::set_tile_skirt`]). This
+/// hides the hairline seams that otherwise appear between the tiles due to rounding when the map is drawn at a
+/// fractional zoom level.
 pub struct RasterTileLayer<Provider>
 where
     Provider: DataProvider<TileIndex, DecodedImage, ()> + MaybeSync + MaybeSend,
@@ -22,9 +42,29 @@ where
     tile_provider: Arc<Provider>,
     tile_scheme: TileSchema,
     fade_in_duration: Duration,
+    tile_skirt: f64,
+    zoom_snapping: bool,
+    custom_shader: Option<CustomShader>,
     tiles: Arc<Cache<TileIndex, Arc<TileState>>>,
+    diagnostics: Arc<Cache<TileIndex, TileDiagnostics>>,
+    debug_overlay: Option<TileDebugOverlay>,
     prev_drawn_tiles: Mutex<Vec<TileIndex>>,
+    recent_tiles: Mutex<Vec<TileIndex>>,
+    scratch: FrameScratch,
     messenger: Option<Arc<dyn Messenger>>,
+    cancellation: CancelOnDrop,
+}
+
+/// Buffers reused between frames, so that rendering of the layer does not allocate in steady state (see
+/// [`alloc_audit`](crate::alloc_audit)).
+#[derive(Default)]
+struct FrameScratch {
+    tiles: ScratchBuffer<(TileIndex, Arc<TileState>)>,
+    substitute_tiles: ScratchBuffer<(TileIndex, Arc<TileState>)>,
+    substitute_indices: Mutex<HashSet<TileIndex>>,
+    to_substitute: ScratchBuffer<TileIndex>,
+    drawn: ScratchBuffer<TileIndex>,
+    bundles: ScratchBuffer<(Arc<dyn PackedBundle>, f32)>,
 }
 
 enum TileState {
@@ -36,10 +76,10 @@ enum TileState {
 
 struct RenderedTile {
     render_bundle: RenderBundle,
-    packed_bundle: Box<dyn PackedBundle>,
+    packed_bundle: Arc<dyn PackedBundle>,
     first_drawn: SystemTime,
+    opacity: f32,
     is_opaque: bool,
-    primitive_id: PrimitiveId,
 }
 
 impl<Provider> RasterTileLayer<Provider>
@@ -56,24 +96,77 @@ where
             tile_provider: Arc::new(tile_provider),
             tile_scheme,
             prev_drawn_tiles: Mutex::new(vec![]),
+            recent_tiles: Mutex::new(vec![]),
+            scratch: FrameScratch::default(),
             fade_in_duration: Duration::from_millis(300),
+            tile_skirt: 0.5,
+            zoom_snapping: true,
+            custom_shader: None,
             tiles: Arc::new(Cache::new(5000)),
+            diagnostics: Arc::new(Cache::new(5000)),
+            debug_overlay: None,
             messenger,
+            cancellation: CancellationToken::new().drop_guard(),
         }
     }
 
+    /// Token that cancels loading of the tiles of the layer. It is cancelled when the layer is dropped.
+    ///
+    /// After the token is cancelled, the layer doesn't load new tiles.
+    pub fn cancellation_token(&self) -> &CancellationToken {
+        self.cancellation.token()
+    }
+
     /// Sets fade in duration for newly loaded tiles.
     pub fn set_fade_in_duration(&mut self, duration: Duration) {
         self.fade_in_duration = duration;
     }
 
+    /// Sets the width (in pixels of the tile image) by which every tile overlaps its neighbours. Default value is
+    /// `0.5`. Set it to zero to draw the tiles exactly within their bounds, e.g. for semi-transparent tiles, which
+    /// would be darker on the overlaps.
+    ///
+    /// The skirt is applied to the tiles rendered after this call.
+    pub fn set_tile_skirt(&mut self, skirt: f64) {
+        self.tile_skirt = skirt.max(0.0);
+    }
+
+    /// Sets whether the layer asks the map to snap zoom to the levels of its tile schema (see
+    /// [`Layer::zoom_levels`]), so the tiles are drawn without scaling and look crisp. Default value is `true`.
+    pub fn set_zoom_snapping(&mut self, zoom_snapping: bool) {
+        self.zoom_snapping = zoom_snapping;
+    }
+
+    /// Sets or removes the shader the tiles are drawn with, e.g. to apply a colormap to the tile images. See
+    /// [`CustomShader`] for details.
+    pub fn set_custom_shader(&mut self, shader: Option<CustomShader>) {
+        self.custom_shader = shader;
+    }
+
+    /// Shows or hides the overlay with the borders and [loading diagnostics](crate::layer::tile_diagnostics) of the
+    /// displayed tiles.
+    pub fn set_debug_overlay(&mut self, overlay: Option<TileDebugOverlay>) {
+        self.debug_overlay = overlay;
+        if let Some(messenger) = &self.messenger {
+            messenger.request_redraw();
+        }
+    }
+
+    /// Returns the diagnostics of the last loading of the tile, or `None` if the tile was not loaded yet.
+    pub fn tile_diagnostics(&self, index: TileIndex) -> Option<TileDiagnostics> {
+        self.diagnostics.get(&index)
+    }
+
+    /// Returns the tiles to draw for the `view`, with substitutes first. The returned vector should be given back to
+    /// `self.scratch.substitute_tiles` after the frame.
     fn get_tiles_to_draw(&self, view: &MapView) -> Vec<(TileIndex, Arc<TileState>)> {
-        let mut tiles = vec![];
+        let mut substitute_tiles = self.scratch.substitute_tiles.take();
         let Some(tile_iter) = self.tile_scheme.iter_tiles(view) else {
-            return vec![];
+            return substitute_tiles;
         };
 
-        let mut to_substitute = vec![];
+        let mut tiles = self.scratch.tiles.take();
+        let mut to_substitute = self.scratch.to_substitute.take();
         for index in tile_iter {
             self.tiles.get(&index);
 
@@ -97,9 +190,10 @@ where
         }
 
         let prev_drawn = self.prev_drawn_tiles.lock();
-        let mut substitute_indices: HashSet<_> = tiles.iter().map(|(index, _)| *index).collect();
-        let mut substitute_tiles = vec![];
-        for index in to_substitute {
+        let mut substitute_indices = self.scratch.substitute_indices.lock();
+        substitute_indices.clear();
+        substitute_indices.extend(tiles.iter().map(|(index, _)| *index));
+        for index in to_substitute.drain(..) {
             let mut next_level = index;
             let mut substituted = false;
 
@@ -160,12 +254,23 @@ where
         substitute_tiles.sort_unstable_by(|(index_a, _), (index_b, _)| index_a.z.cmp(&index_b.z));
         substitute_tiles.append(&mut tiles);
         substitute_tiles.dedup_by(|a, b| a.0 == b.0);
+
+        self.scratch.tiles.recycle(tiles);
+        self.scratch.to_substitute.recycle(to_substitute);
         substitute_tiles
     }
 
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "pack_raster_tiles", skip_all, fields(tiles = tiles.len()))
+    )]
     fn prepare_tile_renders(&self, tiles: &[(TileIndex, Arc<TileState>)], canvas: &mut dyn Canvas) {
         let mut requires_redraw = false;
 
+        // Fade-in animation requires redrawing the map for several frames, so it is skipped if the device cannot
+        // keep up with the frame rate.
+        let skip_fade_in = self.fade_in_duration.is_zero() || canvas.quality().is_reduced();
+
         let now = SystemTime::now();
         for (index, tile) in tiles {
             match &**tile {
@@ -175,34 +280,22 @@ where
                         continue;
                     }
 
-                    let first_drawn = rendered.first_drawn;
-                    let primitive_id = rendered.primitive_id;
-
+                    // The opacity is applied when the tile is drawn, so the packed bundle is not changed during the
+                    // fade in.
                     let since_drawn = now
-                        .duration_since(first_drawn)
+                        .duration_since(rendered.first_drawn)
                         .unwrap_or(Duration::from_millis(0));
-                    let opacity = if self.fade_in_duration.is_zero() {
-                        255
+                    rendered.opacity = if skip_fade_in {
+                        1.0
                     } else {
-                        ((since_drawn.as_secs_f64() / self.fade_in_duration.as_secs_f64()).min(1.0)
-                            * 255.0) as u8
+                        (since_drawn.as_secs_f64() / self.fade_in_duration.as_secs_f64()).min(1.0)
+                            as f32
                     };
 
-                    let is_opaque = opacity == 255;
-                    if !is_opaque {
+                    rendered.is_opaque = rendered.opacity >= 1.0;
+                    if !rendered.is_opaque {
                         requires_redraw = true;
                     }
-
-                    if let Err(err) = rendered
-                        .render_bundle
-                        .modify_image(primitive_id, ImagePaint { opacity })
-                    {
-                        log::warn!("Failed to update image style: {err}");
-                    }
-
-                    let packed = canvas.pack_bundle(&rendered.render_bundle);
-                    rendered.packed_bundle = packed;
-                    rendered.is_opaque = is_opaque;
                 }
                 TileState::Loaded(decoded_image) => {
                     let mut bundle = canvas.create_bundle();
@@ -213,31 +306,41 @@ where
                         DecodedImage::from_raw(vec![], 0, 0).expect("empty image is always ok"),
                     );
 
-                    let opacity = if self.fade_in_duration.is_zero() {
-                        255
-                    } else {
-                        0
-                    };
+                    let opacity = if skip_fade_in { 1.0 } else { 0.0 };
 
-                    let Some(tile_bbox) = self.tile_scheme.tile_bbox(*index) else {
+                    let (Some(tile_bbox), Some(lod_resolution)) = (
+                        self.tile_scheme.tile_bbox(*index),
+                        self.tile_scheme.lod_resolution(index.z),
+                    ) else {
                         log::warn!("Failed to get bbox for tile {index:?}");
                         continue;
                     };
 
-                    let id = bundle.add_image(
+                    // The image is placed relative to the tile center to keep precision at high zoom levels.
+                    bundle.set_origin(tile_bbox.center());
+                    let local_bbox = Rect::new(
+                        -tile_bbox.half_width(),
+                        -tile_bbox.half_height(),
+                        tile_bbox.half_width(),
+                        tile_bbox.half_height(),
+                    );
+
+                    // Negative shrink extends the tile over its neighbours.
+                    let skirt = self.tile_skirt * lod_resolution;
+                    bundle.add_image(
                         owned,
-                        tile_bbox.into_quadrangle(),
-                        ImagePaint { opacity },
+                        local_bbox.shrink(-skirt).into_quadrangle(),
+                        ImagePaint { opacity: 255 },
                     );
                     let packed = canvas.pack_bundle(&bundle);
                     self.tiles.insert(
                         *index,
                         Arc::new(TileState::Rendered(Box::new(Mutex::new(RenderedTile {
                             render_bundle: bundle,
-                            packed_bundle: packed,
+                            packed_bundle: Arc::from(packed),
                             first_drawn: now,
-                            is_opaque: false,
-                            primitive_id: id,
+                            opacity,
+                            is_opaque: skip_fade_in,
                         })))),
                     );
 
@@ -254,17 +357,43 @@ where
         }
     }
 
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "load_raster_tile",
+            skip_all,
+            fields(x = index.x, y = index.y, z = index.z)
+        )
+    )]
     async fn load_tile(
         index: TileIndex,
         tile_provider: Arc<Provider>,
         tiles: &Cache<TileIndex, Arc<TileState>>,
+        diagnostics: &Cache<TileIndex, TileDiagnostics>,
         messenger: Option<Arc<dyn Messenger>>,
+        cancellation: &CancellationToken,
     ) {
+        if cancellation.is_cancelled() {
+            return;
+        }
+
         match tiles.get_value_or_guard_async(&index).await {
             Ok(_) => {}
             Err(guard) => {
                 let _ = guard.insert(Arc::new(TileState::Loading));
-                let load_result = tile_provider.load(&index, ()).await;
+                let started = Instant::now();
+                let Some((load_result, info)) = cancellation
+                    .run_until_cancelled(tile_provider.load_with_info(&index, ()))
+                    .await
+                else {
+                    // The tile is not left in the loading state, so it can be loaded again if needed.
+                    tiles.remove(&index);
+                    return;
+                };
+                diagnostics.insert(
+                    index,
+                    TileDiagnostics::new(started.elapsed(), info, &load_result),
+                );
 
                 match load_result {
                     Ok(decoded_image) => {
@@ -292,16 +421,79 @@ where
     /// Preload tiles for the given `view`.
     pub async fn load_tiles(&self, view: &MapView) {
         if let Some(iter) = self.tile_scheme.iter_tiles(view) {
-            for index in iter {
-                let tile_provider = self.tile_provider.clone();
-                let tiles = self.tiles.clone();
-                let messenger = self.messenger.clone();
-                Self::load_tile(index, tile_provider, &tiles, messenger).await;
-            }
+            self.load_tile_indices(&iter.collect::<Vec<_>>()).await;
+        }
+    }
+
+    /// Preload the tiles with the given indices, e.g. the tiles of a [`TileManifest`](crate::TileManifest) saved in a
+    /// previous session. Unlike [`Layer::warm_up`], this method can be awaited to make sure the tiles are loaded
+    /// before the first frame is drawn.
+    pub async fn load_tile_indices(&self, indices: &[TileIndex]) {
+        self.tile_provider.read_ahead(indices);
+        for index in indices {
+            let tile_provider = self.tile_provider.clone();
+            let tiles = self.tiles.clone();
+            let diagnostics = self.diagnostics.clone();
+            let messenger = self.messenger.clone();
+            Self::load_tile(
+                *index,
+                tile_provider,
+                &tiles,
+                &diagnostics,
+                messenger,
+                self.cancellation.token(),
+            )
+            .await;
+        }
+    }
+
+    fn update_recent_tiles(&self, drawn: &[TileIndex]) {
+        let mut prev_drawn = self.prev_drawn_tiles.lock();
+        if *prev_drawn != drawn {
+            touch_recent_tiles(&mut self.recent_tiles.lock(), drawn, RECENT_TILES_CAPACITY);
+            prev_drawn.clear();
+            prev_drawn.extend_from_slice(drawn);
+        }
+    }
+}
+
+impl<Provider> RasterTileLayer<Provider>
+where
+    Provider: DataProvider<TileIndex, DecodedImage, ()> + MaybeSync + MaybeSend + 'static,
+{
+    fn spawn_load(&self, indices: Vec<TileIndex>) {
+        self.tile_provider.read_ahead(&indices);
+
+        for index in indices {
+            let tile_provider = self.tile_provider.clone();
+            let tiles = self.tiles.clone();
+            let diagnostics = self.diagnostics.clone();
+            let messenger = self.messenger.clone();
+            let cancellation = self.cancellation.token().clone();
+            crate::async_runtime::spawn(async move {
+                Self::load_tile(
+                    index,
+                    tile_provider,
+                    &tiles,
+                    &diagnostics,
+                    messenger,
+                    &cancellation,
+                )
+                .await;
+            });
         }
     }
 }
 
+/// Moves the `drawn` tiles to the end of the `recent` list, keeping at most `capacity` most recent tiles.
+fn touch_recent_tiles(recent: &mut Vec<TileIndex>, drawn: &[TileIndex], capacity: usize) {
+    recent.retain(|index| !drawn.contains(index));
+    recent.extend_from_slice(drawn);
+    if recent.len() > capacity {
+        recent.drain(..recent.len() - capacity);
+    }
+}
+
 impl<Provider> Layer for RasterTileLayer<Provider>
 where
     Provider: DataProvider<TileIndex, DecodedImage, ()> + MaybeSync + MaybeSend + 'static,
@@ -310,37 +502,56 @@ where
         let tiles = self.get_tiles_to_draw(view);
         self.prepare_tile_renders(&tiles, canvas);
 
-        let updated_tiles: Vec<_> = tiles
-            .iter()
-            .filter_map(|(index, _)| self.tiles.get(index))
-            .collect();
-        let mut to_draw = Vec::new();
-        for tile in &updated_tiles {
-            if let TileState::Rendered(rendered) = tile.as_ref() {
-                to_draw.push(rendered.lock());
+        // The packed bundles are cloned out of the tile states, so that the tile locks are not held while drawing.
+        let mut to_draw = self.scratch.bundles.take();
+        for (index, _) in &tiles {
+            if let Some(tile) = self.tiles.get(index) {
+                if let TileState::Rendered(rendered) = tile.as_ref() {
+                    let rendered = rendered.lock();
+                    to_draw.push((rendered.packed_bundle.clone(), rendered.opacity));
+                }
             }
         }
 
-        canvas.draw_bundles(
-            &to_draw
+        thread_local! {
+            static BUNDLES: ScratchBuffer<(&'static dyn PackedBundle, f32)> = ScratchBuffer::default();
+        }
+
+        let mut bundles: Vec<(&dyn PackedBundle, f32)> = BUNDLES.with(ScratchBuffer::take);
+        bundles.extend(
+            to_draw
                 .iter()
-                .map(|guard| &*guard.packed_bundle)
-                .collect::<Vec<_>>(),
+                .map(|(bundle, opacity)| (&**bundle, *opacity)),
+        );
+        draw_bundles_with_opacity(
+            canvas,
+            &bundles,
             RenderOptions::default(),
+            self.custom_shader.as_ref(),
         );
-        *self.prev_drawn_tiles.lock() = tiles.iter().map(|(index, _)| *index).collect();
+        BUNDLES.with(|buffer| buffer.recycle(bundles));
+        self.scratch.bundles.recycle(to_draw);
+
+        let mut drawn = self.scratch.drawn.take();
+        drawn.extend(tiles.iter().map(|(index, _)| *index));
+        self.update_recent_tiles(&drawn);
+        self.scratch.drawn.recycle(drawn);
+        self.scratch.substitute_tiles.recycle(tiles);
+
+        if let Some(overlay) = &self.debug_overlay {
+            if let Some(tile_iter) = self.tile_scheme.iter_tiles(view) {
+                let tiles = tile_iter.map(|index| (index, self.diagnostics.get(&index)));
+                overlay.render(view, canvas, &self.tile_scheme, tiles);
+            }
+        }
     }
 
     fn prepare(&self, view: &MapView) {
         if let Some(iter) = self.tile_scheme.iter_tiles(view) {
-            for index in iter {
-                let tile_provider = self.tile_provider.clone();
-                let tiles = self.tiles.clone();
-                let messenger = self.messenger.clone();
-                crate::async_runtime::spawn(async move {
-                    Self::load_tile(index, tile_provider, &tiles, messenger).await;
-                });
-            }
+            let indices: Vec<_> = iter
+                .filter(|index| !self.tiles.contains_key(index))
+                .collect();
+            self.spawn_load(indices);
         }
     }
 
@@ -355,4 +566,119 @@ where
     fn as_any_mut(&mut self) -> &mut dyn Any {
         self
     }
+
+    fn zoom_levels(&self, crs: &Crs) -> Option<Vec<f64>> {
+        if !self.zoom_snapping || *crs != self.tile_scheme.crs {
+            return None;
+        }
+
+        Some(
+            self.tile_scheme
+                .lods
+                .iter()
+                .map(|lod| lod.resolution())
+                .collect(),
+        )
+    }
+
+    fn memory_usage(&self) -> LayerMemoryUsage {
+        // Only the tiles drawn in the last frame are measured. Sizes of other cached tiles are extrapolated from them,
+        // as all the tiles of the layer are images of the same size.
+        let cached_tiles = self.tiles.len();
+        let mut measured = LayerMemoryUsage::default();
+        for index in self.prev_drawn_tiles.lock().iter() {
+            let Some(tile) = self.tiles.peek(index) else {
+                continue;
+            };
+
+            match &*tile {
+                TileState::Loaded(image) => measured.cpu_cache += image.lock().bytes.len(),
+                TileState::Rendered(rendered) => {
+                    let rendered = rendered.lock();
+                    measured.cpu_cache += rendered.render_bundle.approx_buffer_size();
+                    measured.gpu += rendered.packed_bundle.gpu_size();
+                }
+                TileState::Loading | TileState::Error => {}
+            }
+
+            measured.cached_tiles += 1;
+        }
+
+        if measured.cached_tiles == 0 {
+            let tile_size = self.tile_scheme.tile_width() as usize
+                * self.tile_scheme.tile_height() as usize
+                * 4;
+            return LayerMemoryUsage {
+                cpu_cache: tile_size * cached_tiles,
+                gpu: 0,
+                cached_tiles,
+            };
+        }
+
+        LayerMemoryUsage {
+            cpu_cache: measured.cpu_cache * cached_tiles / measured.cached_tiles,
+            gpu: measured.gpu * cached_tiles / measured.cached_tiles,
+            cached_tiles,
+        }
+    }
+
+    fn trim_memory(&self) {
+        self.tiles.clear();
+        self.prev_drawn_tiles.lock().clear();
+    }
+
+    fn recent_tiles(&self) -> Vec<TileIndex> {
+        self.recent_tiles.lock().clone()
+    }
+
+    fn warm_up(&self, tiles: &[TileIndex]) {
+        let indices: Vec<_> = tiles
+            .iter()
+            .filter(|index| {
+                self.tile_scheme.tile_bbox(**index).is_some() && !self.tiles.contains_key(*index)
+            })
+            .copied()
+            .collect();
+        self.spawn_load(indices);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recent_tiles_keep_most_recent() {
+        let index = |x| TileIndex::new(1, x, 0);
+        let mut recent = vec![];
+        touch_recent_tiles(&mut recent, &[index(0), index(1)], 3);
+        touch_recent_tiles(&mut recent, &[index(2), index(0)], 3);
+        assert_eq!(recent, vec![index(1), index(2), index(0)]);
+
+        touch_recent_tiles(&mut recent, &[index(3)], 3);
+        assert_eq!(recent, vec![index(2), index(0), index(3)]);
+    }
+
+    #[test]
+    fn cancelled_tile_is_not_left_loading() {
+        use crate::error::GalileoError;
+        use crate::layer::data_provider::{ProceduralTile, ProceduralTileProvider};
+
+        let provider = ProceduralTileProvider::new(TileSchema::web(18), |_: ProceduralTile| {
+            std::future::pending::<Result<DecodedImage, GalileoError>>()
+        });
+        let layer = RasterTileLayer::new(TileSchema::web(18), provider, None);
+        let index = TileIndex::new(0, 0, 1);
+
+        let token = layer.cancellation_token().clone();
+        let cancel = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(10));
+            token.cancel();
+        });
+        futures::executor::block_on(layer.load_tile_indices(&[index]));
+        cancel.join().unwrap();
+
+        assert!(layer.tiles.get(&index).is_none());
+        assert!(layer.tile_diagnostics(index).is_none());
+    }
 }
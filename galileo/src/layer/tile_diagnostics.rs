@@ -0,0 +1,232 @@
+//! Diagnostics of tile loading, for finding problems with tile providers and caches.
+//!
+//! Tile layers record for every loaded tile how long the loading took, where the data came from (see
+//! [`DataSource`]), the size of the data and the error if loading failed. The diagnostics can be read from the layer
+//! (e.g. [`RasterTileLayer::tile_diagnostics`](super::RasterTileLayer::tile_diagnostics)) or drawn over the tiles
+//! with a [`TileDebugOverlay`], which can be switched on and off at any time:
+//!
+//! ```no_run
+//! # use galileo::layer::RasterTileLayer;
+//! # use galileo::layer::data_provider::UrlImageProvider;
+//! # use galileo::tile_scheme::TileIndex;
+//! use galileo::layer::tile_diagnostics::TileDebugOverlay;
+//!
+//! # fn toggle(layer: &mut RasterTileLayer<UrlImageProvider<TileIndex>>) {
+//! layer.set_debug_overlay(Some(TileDebugOverlay::default()));
+//! // ...
+//! layer.set_debug_overlay(None);
+//! # }
+//! ```
+
+use crate::error::GalileoError;
+use crate::layer::data_provider::{DataSource, LoadInfo};
+use crate::render::point_paint::PointPaint;
+use crate::render::render_bundle::RenderPrimitive;
+use crate::render::text::TextStyle;
+use crate::render::{Canvas, LineCap, LineJoin, LinePaint, RenderOptions, SizeUnits};
+use crate::tile_scheme::{TileIndex, TileSchema};
+use crate::view::MapView;
+use crate::Color;
+use galileo_types::cartesian::{Point2d, Point3d};
+use galileo_types::impls::{Contour, Polygon};
+use nalgebra::Vector2;
+use web_time::Duration;
+
+/// Diagnostics of loading of a single tile.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TileDiagnostics {
+    /// Time from the start of loading of the tile until its data was decoded or the loading failed.
+    pub load_time: Duration,
+    /// Where the tile data came from, if reported by the data provider.
+    pub source: Option<DataSource>,
+    /// Size of the raw tile data in bytes, if reported by the data provider.
+    pub byte_size: Option<usize>,
+    /// Error message, if loading of the tile failed.
+    pub error: Option<String>,
+}
+
+impl TileDiagnostics {
+    pub(crate) fn new<T>(
+        load_time: Duration,
+        info: LoadInfo,
+        result: &Result<T, GalileoError>,
+    ) -> Self {
+        Self {
+            load_time,
+            source: info.source,
+            byte_size: info.byte_size,
+            error: result.as_ref().err().map(|err| err.to_string()),
+        }
+    }
+
+    /// Lines of text describing the tile, as drawn by the [`TileDebugOverlay`].
+    pub fn summary(&self) -> Vec<String> {
+        let source = self
+            .source
+            .map_or_else(|| "unknown source".to_string(), |s| s.to_string());
+        let mut lines = vec![match self.byte_size {
+            Some(size) => format!("{source}, {}", format_size(size)),
+            None => source,
+        }];
+        lines.push(format!("{} ms", self.load_time.as_millis()));
+        if let Some(error) = &self.error {
+            lines.push(format!("error: {error}"));
+        }
+
+        lines
+    }
+}
+
+fn format_size(bytes: usize) -> String {
+    const KB: f64 = 1024.0;
+    let bytes_f = bytes as f64;
+    if bytes_f < KB {
+        format!("{bytes} B")
+    } else if bytes_f < KB * KB {
+        format!("{:.1} KB", bytes_f / KB)
+    } else {
+        format!("{:.1} MB", bytes_f / KB / KB)
+    }
+}
+
+/// Overlay that draws the border, index and [diagnostics](TileDiagnostics) of every displayed tile over a tile
+/// layer.
+///
+/// The overlay is re-created every frame, so it should only be used for debugging.
+#[derive(Debug, Clone)]
+pub struct TileDebugOverlay {
+    /// Color of the tile borders and text.
+    pub color: Color,
+    /// Color of the borders and text of the tiles that failed to load.
+    pub error_color: Color,
+    /// Style of the text. The color of the style is replaced by [`TileDebugOverlay::color`] or
+    /// [`TileDebugOverlay::error_color`].
+    pub text_style: TextStyle,
+}
+
+impl Default for TileDebugOverlay {
+    fn default() -> Self {
+        Self {
+            color: Color::rgba(0, 0, 160, 255),
+            error_color: Color::RED,
+            text_style: TextStyle {
+                font_name: "Noto Sans".to_string(),
+                font_size: 12.0,
+                font_color: Color::BLACK,
+                horizontal_alignment: Default::default(),
+                vertical_alignment: Default::default(),
+                direction: Default::default(),
+            },
+        }
+    }
+}
+
+impl TileDebugOverlay {
+    /// Draws the overlay for the given tiles. Tiles without diagnostics are shown as loading.
+    pub(crate) fn render(
+        &self,
+        view: &MapView,
+        canvas: &mut dyn Canvas,
+        tile_scheme: &TileSchema,
+        tiles: impl IntoIterator<Item = (TileIndex, Option<TileDiagnostics>)>,
+    ) {
+        let Some(position) = view.projected_position() else {
+            return;
+        };
+        let origin = Point2d::new(position.x, position.y);
+
+        let mut bundle = canvas.create_bundle();
+        // The coordinates are relative to the view center to keep precision at high zoom levels.
+        bundle.set_origin(origin);
+        let to_local = |x: f64, y: f64| Point3d::new(x - origin.x, y - origin.y, 0.0);
+
+        let line_height = self.text_style.font_size * 1.2;
+        for (index, diagnostics) in tiles {
+            let Some(bbox) = tile_scheme.tile_bbox(index) else {
+                continue;
+            };
+
+            let is_error = diagnostics.as_ref().is_some_and(|d| d.error.is_some());
+            let color = if is_error {
+                self.error_color
+            } else {
+                self.color
+            };
+
+            let border = Contour::closed(vec![
+                to_local(bbox.x_min(), bbox.y_min()),
+                to_local(bbox.x_min(), bbox.y_max()),
+                to_local(bbox.x_max(), bbox.y_max()),
+                to_local(bbox.x_max(), bbox.y_min()),
+            ]);
+            bundle.add(
+                RenderPrimitive::<_, _, _, Polygon<Point3d>>::new_contour(
+                    border,
+                    LinePaint {
+                        color,
+                        width: 1.0,
+                        offset: 0.0,
+                        line_cap: LineCap::Butt,
+                        line_join: LineJoin::Miter,
+                        miter_limit: LinePaint::DEFAULT_MITER_LIMIT,
+                        units: SizeUnits::Pixels,
+                    },
+                ),
+                0.0,
+            );
+
+            let mut lines = vec![format!("{}/{}/{}", index.z, index.x, index.y)];
+            match &diagnostics {
+                Some(diagnostics) => lines.extend(diagnostics.summary()),
+                None => lines.push("loading".to_string()),
+            }
+
+            let text_style = TextStyle {
+                font_color: color,
+                ..self.text_style.clone()
+            };
+            let center = bbox.center();
+            let top_offset = line_height * (lines.len() - 1) as f32 / 2.0;
+            for (line_index, line) in lines.into_iter().enumerate() {
+                let paint = PointPaint::label_owed(line, text_style.clone()).with_offset(
+                    Vector2::new(0.0, top_offset - line_height * line_index as f32),
+                );
+                bundle.add(
+                    RenderPrimitive::<_, _, Contour<Point3d>, Polygon<Point3d>>::new_point(
+                        to_local(center.x, center.y),
+                        paint,
+                    ),
+                    0.0,
+                );
+            }
+        }
+
+        let packed = canvas.pack_bundle(&bundle);
+        canvas.draw_bundles(&[&*packed], RenderOptions::default());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diagnostics_summary() {
+        let info = LoadInfo {
+            source: Some(DataSource::Disk),
+            byte_size: Some(2560),
+        };
+        let loaded = TileDiagnostics::new(Duration::from_millis(42), info, &Ok(()));
+        assert_eq!(loaded.summary(), vec!["disk, 2.5 KB", "42 ms"]);
+
+        let failed = TileDiagnostics::new::<()>(
+            Duration::from_millis(7),
+            LoadInfo::default(),
+            &Err(GalileoError::NotFound),
+        );
+        assert_eq!(
+            failed.summary(),
+            vec!["unknown source", "7 ms", "error: item not found"]
+        );
+    }
+}
@@ -1,10 +1,11 @@
 //! [Vector tile layers](VectorTileLayer) load prepared vector tiles using a [data provider](VectorTileProviderT)
 //! and draw them to the map with the given [`VectorTileStyle`].
 
-use maybe_sync::{MaybeSend, MaybeSync};
+use maybe_sync::{MaybeSend, MaybeSync, Mutex};
 use std::any::Any;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use web_time::{Duration, SystemTime};
 
 use nalgebra::Point2;
 
@@ -13,14 +14,16 @@ use galileo_types::cartesian::CartesianPoint2d;
 use galileo_types::geometry::CartesianGeometry2d;
 pub use vector_tile::VectorTile;
 
-use crate::layer::vector_tile_layer::style::VectorTileStyle;
+use crate::alloc_audit::ScratchBuffer;
+use crate::cancellation::{CancelOnDrop, CancellationToken};
+use crate::layer::vector_tile_layer::style::{StylePatch, VectorTileStyle};
 use crate::layer::vector_tile_layer::tile_provider::loader::VectorTileLoader;
 use crate::layer::vector_tile_layer::tile_provider::processor::VectorTileProcessor;
 use crate::layer::vector_tile_layer::tile_provider::{VectorTileProvider, VtStyleId};
-use crate::layer::Layer;
+use crate::layer::{Layer, LayerMemoryUsage};
 use crate::messenger::Messenger;
-use crate::render::{Canvas, PackedBundle, RenderOptions};
-use crate::tile_scheme::TileSchema;
+use crate::render::{draw_bundles_with_opacity, Canvas, PackedBundle, RenderOptions};
+use crate::tile_scheme::{TileIndex, TileSchema};
 use crate::view::MapView;
 
 pub mod style;
@@ -29,14 +32,66 @@ mod vector_tile;
 
 /// Vector tile layers use [`Providers`](VectorTileProviderT) to load prepared vector tiles, and then render them using
 /// specified [styles](VectorTileStyle).
+///
+/// # Multiple sources
+///
+/// A layer can combine tiles from several sources (e.g. a basemap tileset and a custom overlay tileset), each with its
+/// own provider and tile scheme. The sources are added with [`VectorTileLayer::add_source`] and share the style of
+/// the layer: a [rule](style::StyleRule) with the [`source`](style::StyleRule::source) field set is only applied to the
+/// features of that source. Sources are drawn in the order they were added, so the first source is at the bottom.
+///
+/// # Fade transitions
+///
+/// Newly displayed tiles (e.g. tiles of a new zoom level) fade in over the tiles that were drawn before them, so the
+/// labels and symbols of the map are replaced gradually instead of popping in and out. The duration of the transition
+/// is set with [`VectorTileLayer::set_fade_in_duration`].
 pub struct VectorTileLayer<Loader, Processor>
 where
     Loader: VectorTileLoader + MaybeSend + MaybeSync + 'static,
     Processor: VectorTileProcessor + MaybeSend + MaybeSync + 'static,
 {
+    sources: Vec<TileSource<Loader, Processor>>,
+    style: Arc<VectorTileStyle>,
+    base_style: Arc<VectorTileStyle>,
+    style_patch: Option<StylePatch>,
+    fade_in_duration: Duration,
+    messenger: Option<Arc<dyn Messenger>>,
+    to_draw: ScratchBuffer<(Arc<dyn PackedBundle>, f32)>,
+    cancellation: CancelOnDrop,
+}
+
+struct TileSource<Loader, Processor>
+where
+    Loader: VectorTileLoader + MaybeSend + MaybeSync + 'static,
+    Processor: VectorTileProcessor + MaybeSend + MaybeSync + 'static,
+{
+    name: String,
     tile_provider: VectorTileProvider<Loader, Processor>,
     tile_scheme: TileSchema,
     style_id: VtStyleId,
+    fade: Mutex<TileFade>,
+    scratch: SourceScratch,
+}
+
+/// Buffers reused between frames, so that rendering of the source does not allocate in steady state (see
+/// [`alloc_audit`](crate::alloc_audit)).
+#[derive(Default)]
+struct SourceScratch {
+    indices: ScratchBuffer<TileIndex>,
+    to_substitute: ScratchBuffer<TileIndex>,
+    tiles: ScratchBuffer<(TileIndex, Arc<dyn PackedBundle>)>,
+    under: ScratchBuffer<(TileIndex, Arc<dyn PackedBundle>)>,
+    faded: ScratchBuffer<(TileIndex, Arc<dyn PackedBundle>, f32)>,
+    index_set: Mutex<HashSet<TileIndex>>,
+}
+
+/// State of the fade in transitions of the tiles of a source.
+#[derive(Default)]
+struct TileFade {
+    /// Time each of the currently displayed tiles was drawn for the first time.
+    first_drawn: HashMap<TileIndex, SystemTime>,
+    /// Fully opaque tiles drawn in the previous frame. They are drawn under the tiles that are fading in.
+    prev_drawn: Vec<TileIndex>,
 }
 
 impl<Loader, Processor> Layer for VectorTileLayer<Loader, Processor>
@@ -45,22 +100,58 @@ where
     Processor: VectorTileProcessor + MaybeSend + MaybeSync + 'static,
 {
     fn render(&self, view: &MapView, canvas: &mut dyn Canvas) {
-        let tiles = self.get_tiles_to_draw(view, canvas);
-        let to_render: Vec<&dyn PackedBundle> = tiles.iter().map(|v| &**v).collect();
+        // Fade-in animation requires redrawing the map for several frames, so it is skipped if the device cannot
+        // keep up with the frame rate.
+        let fade_in_duration = if canvas.quality().is_reduced() {
+            Duration::ZERO
+        } else {
+            self.fade_in_duration
+        };
+
+        let now = SystemTime::now();
+        let mut requires_redraw = false;
+        let mut tiles = self.to_draw.take();
+        for source in &self.sources {
+            let source_tiles = source.get_tiles_to_draw(view, canvas);
+            requires_redraw |= source.fade_tiles(source_tiles, now, fade_in_duration, &mut tiles);
+        }
+
+        thread_local! {
+            static TO_RENDER: ScratchBuffer<(&'static dyn PackedBundle, f32)> = ScratchBuffer::default();
+        }
 
-        canvas.draw_bundles(&to_render, RenderOptions::default());
+        let mut to_render: Vec<(&dyn PackedBundle, f32)> = TO_RENDER.with(ScratchBuffer::take);
+        to_render.extend(tiles.iter().map(|(tile, opacity)| (&**tile, *opacity)));
+        draw_bundles_with_opacity(canvas, &to_render, RenderOptions::default(), None);
+        TO_RENDER.with(|buffer| buffer.recycle(to_render));
+        self.to_draw.recycle(tiles);
+
+        if requires_redraw {
+            if let Some(messenger) = &self.messenger {
+                messenger.request_redraw();
+            }
+        }
     }
 
     fn prepare(&self, view: &MapView) {
-        if let Some(iter) = self.tile_scheme.iter_tiles(view) {
-            for index in iter {
-                self.tile_provider.load_tile(index, self.style_id);
+        for source in &self.sources {
+            if let Some(iter) = source.tile_scheme.iter_tiles(view) {
+                for index in iter {
+                    source.tile_provider.load_tile(index, source.style_id);
+                }
             }
         }
     }
 
     fn set_messenger(&mut self, messenger: Box<dyn Messenger>) {
-        self.tile_provider.set_messenger(messenger);
+        let messenger: Arc<dyn Messenger> = messenger.into();
+        for source in &mut self.sources {
+            source
+                .tile_provider
+                .set_messenger(Box::new(messenger.clone()));
+        }
+
+        self.messenger = Some(messenger);
     }
 
     fn as_any(&self) -> &dyn Any {
@@ -70,6 +161,20 @@ where
     fn as_any_mut(&mut self) -> &mut dyn Any {
         self
     }
+
+    fn memory_usage(&self) -> LayerMemoryUsage {
+        self.sources
+            .iter()
+            .fold(LayerMemoryUsage::default(), |acc, source| {
+                acc + source.tile_provider.memory_usage()
+            })
+    }
+
+    fn trim_memory(&self) {
+        for source in &self.sources {
+            source.tile_provider.clear();
+        }
+    }
 }
 
 impl<Loader, Processor> VectorTileLayer<Loader, Processor>
@@ -77,38 +182,197 @@ where
     Loader: VectorTileLoader + MaybeSend + MaybeSync + 'static,
     Processor: VectorTileProcessor + MaybeSend + MaybeSync + 'static,
 {
-    /// Style of the layer.
+    /// Style of the layer, with the [style patch](VectorTileLayer::set_style_patch) applied.
     pub fn style(&self) -> Arc<VectorTileStyle> {
-        self.tile_provider
-            .get_style(self.style_id)
-            .unwrap_or_default()
+        self.style.clone()
+    }
+
+    /// Style of the layer without the [style patch](VectorTileLayer::set_style_patch).
+    pub fn base_style(&self) -> Arc<VectorTileStyle> {
+        self.base_style.clone()
+    }
+
+    /// Patch applied on top of the base style of the layer.
+    pub fn style_patch(&self) -> Option<&StylePatch> {
+        self.style_patch.as_ref()
+    }
+
+    /// Token that cancels loading of the tiles of all the sources of the layer. It is cancelled when the layer is
+    /// dropped. Tiles of a source are also cancelled when the source is removed from the layer.
+    ///
+    /// After the token is cancelled, the layer doesn't load new tiles.
+    pub fn cancellation_token(&self) -> &CancellationToken {
+        self.cancellation.token()
+    }
+
+    /// Sets fade in duration for newly displayed tiles. Set it to zero to disable the transitions.
+    pub fn set_fade_in_duration(&mut self, duration: Duration) {
+        self.fade_in_duration = duration;
     }
 
     /// Creates a new layer with the given url source.
+    ///
+    /// The source is named [`DEFAULT_SOURCE`](style::DEFAULT_SOURCE).
     pub async fn from_url(
-        mut tile_provider: VectorTileProvider<Loader, Processor>,
+        tile_provider: VectorTileProvider<Loader, Processor>,
         style: VectorTileStyle,
         tile_scheme: TileSchema,
     ) -> Self {
-        let style_id = tile_provider.add_style(style).await;
-        Self {
+        let style = Arc::new(style);
+        let mut layer = Self {
+            sources: vec![],
+            style: style.clone(),
+            base_style: style,
+            style_patch: None,
+            fade_in_duration: Duration::from_millis(300),
+            messenger: None,
+            to_draw: ScratchBuffer::default(),
+            cancellation: CancellationToken::new().drop_guard(),
+        };
+        layer
+            .add_source(style::DEFAULT_SOURCE, tile_provider, tile_scheme)
+            .await;
+
+        layer
+    }
+
+    /// Adds a new source of tiles to the layer. The tiles of the source are drawn on top of the tiles of the sources
+    /// added before.
+    ///
+    /// If a source with the same name already exists in the layer, it is replaced.
+    ///
+    /// Each source must use its own provider, as tiles of a provider are identified only by their index.
+    pub async fn add_source(
+        &mut self,
+        name: impl Into<String>,
+        mut tile_provider: VectorTileProvider<Loader, Processor>,
+        tile_scheme: TileSchema,
+    ) {
+        let name = name.into();
+        let style_id = tile_provider.add_style(self.style.for_source(&name)).await;
+
+        if let Some(messenger) = &self.messenger {
+            tile_provider.set_messenger(Box::new(messenger.clone()));
+        }
+        tile_provider.set_cancellation_token(self.cancellation.token().child_token());
+
+        let source = TileSource {
+            name,
             tile_provider,
             tile_scheme,
             style_id,
+            fade: Mutex::new(TileFade::default()),
+            scratch: SourceScratch::default(),
+        };
+
+        match self.sources.iter_mut().find(|s| s.name == source.name) {
+            Some(existing) => {
+                existing.tile_provider.cancellation_token().cancel();
+                existing.tile_provider.drop_style(existing.style_id).await;
+                *existing = source;
+            }
+            None => self.sources.push(source),
+        }
+    }
+
+    /// Removes the source with the given name from the layer. Returns `false` if there is no such source.
+    pub async fn remove_source(&mut self, name: &str) -> bool {
+        let Some(position) = self.sources.iter().position(|s| s.name == name) else {
+            return false;
+        };
+
+        let mut source = self.sources.remove(position);
+        source.tile_provider.cancellation_token().cancel();
+        source.tile_provider.drop_style(source.style_id).await;
+
+        true
+    }
+
+    /// Names of the tile sources of the layer in the order they are drawn.
+    pub fn source_names(&self) -> impl Iterator<Item = &str> + '_ {
+        self.sources.iter().map(|source| source.name.as_str())
+    }
+
+    /// Change style of the layer and redraw it.
+    ///
+    /// If the layer has a [style patch](VectorTileLayer::set_style_patch), it is applied on top of the new style.
+    pub async fn update_style(&mut self, style: VectorTileStyle) {
+        self.base_style = Arc::new(style);
+        self.apply_style().await;
+    }
+
+    /// Applies the patch on top of the base style of the layer and redraws it, replacing the previous patch if any.
+    ///
+    /// Only the tiles of the sources, for which the patch actually changes the style, are re-rendered. Tile data is
+    /// not loaded again.
+    pub async fn set_style_patch(&mut self, patch: StylePatch) {
+        self.style_patch = Some(patch);
+        self.apply_style().await;
+    }
+
+    /// Removes the style patch and redraws the layer with its base style.
+    pub async fn clear_style_patch(&mut self) {
+        if self.style_patch.take().is_some() {
+            self.apply_style().await;
+        }
+    }
+
+    async fn apply_style(&mut self) {
+        let style = match &self.style_patch {
+            Some(patch) => Arc::new(self.base_style.patched(patch)),
+            None => self.base_style.clone(),
+        };
+
+        for source in &mut self.sources {
+            let source_style = style.for_source(&source.name);
+            let current_style = source.tile_provider.get_style(source.style_id);
+            if current_style.as_deref() == Some(&source_style) {
+                continue;
+            }
+
+            source.style_id = source.tile_provider.add_style(source_style).await;
+        }
+
+        self.style = style;
+        if let Some(messenger) = &self.messenger {
+            messenger.request_redraw();
         }
     }
 
-    fn get_tiles_to_draw(&self, view: &MapView, canvas: &dyn Canvas) -> Vec<Arc<dyn PackedBundle>> {
-        let mut tiles = vec![];
+    /// Returns features, visible in the layer at the given point with the given map view.
+    pub fn get_features_at(
+        &self,
+        point: &impl CartesianPoint2d<Num = f64>,
+        view: &MapView,
+    ) -> Vec<(String, MvtFeature)> {
+        self.sources
+            .iter()
+            .flat_map(|source| source.get_features_at(point, view))
+            .collect()
+    }
+}
+
+impl<Loader, Processor> TileSource<Loader, Processor>
+where
+    Loader: VectorTileLoader + MaybeSend + MaybeSync + 'static,
+    Processor: VectorTileProcessor + MaybeSend + MaybeSync + 'static,
+{
+    fn get_tiles_to_draw(
+        &self,
+        view: &MapView,
+        canvas: &dyn Canvas,
+    ) -> Vec<(TileIndex, Arc<dyn PackedBundle>)> {
+        let mut tiles = self.scratch.tiles.take();
         let Some(tile_iter) = self.tile_scheme.iter_tiles(view) else {
-            return vec![];
+            return tiles;
         };
 
-        let indices: Vec<_> = tile_iter.collect();
+        let mut indices = self.scratch.indices.take();
+        indices.extend(tile_iter);
         self.tile_provider
             .pack_tiles(&indices, self.style_id, canvas);
 
-        let mut to_substitute = vec![];
+        let mut to_substitute = self.scratch.to_substitute.take();
         for index in &indices {
             match self.tile_provider.get_tile(*index, self.style_id) {
                 None => to_substitute.push(*index),
@@ -116,8 +380,9 @@ where
             }
         }
 
-        let mut substitute_indices = HashSet::new();
-        for index in to_substitute {
+        let mut substitute_indices = self.scratch.index_set.lock();
+        substitute_indices.clear();
+        for index in to_substitute.drain(..) {
             let mut substitute_index = index;
             while let Some(mut subst) = self.tile_scheme.get_substitutes(substitute_index) {
                 substitute_index = match subst.next() {
@@ -137,17 +402,76 @@ where
         }
 
         tiles.sort_unstable_by(|(index_a, _), (index_b, _)| index_a.z.cmp(&index_b.z));
-        tiles.into_iter().map(|(_, tile)| tile).collect()
+
+        self.scratch.indices.recycle(indices);
+        self.scratch.to_substitute.recycle(to_substitute);
+        tiles
     }
 
-    /// Change style of the layer and redraw it.
-    pub async fn update_style(&mut self, style: VectorTileStyle) {
-        let new_style_id = self.tile_provider.add_style(style).await;
-        self.style_id = new_style_id;
+    /// Calculates the opacity of the tiles and adds them to `output`, preceded by the previously drawn tiles that
+    /// must stay under the fading ones. Returns true if some of the tiles are still fading in.
+    fn fade_tiles(
+        &self,
+        mut tiles: Vec<(TileIndex, Arc<dyn PackedBundle>)>,
+        now: SystemTime,
+        fade_in_duration: Duration,
+        output: &mut Vec<(Arc<dyn PackedBundle>, f32)>,
+    ) -> bool {
+        let mut fade = self.fade.lock();
+
+        let mut is_fading = false;
+        let mut faded = self.scratch.faded.take();
+        for (index, tile) in tiles.drain(..) {
+            let first_drawn = *fade.first_drawn.entry(index).or_insert(now);
+            let opacity = fade_in_opacity(now, first_drawn, fade_in_duration);
+            is_fading |= opacity < 1.0;
+            faded.push((index, tile, opacity));
+        }
+        self.scratch.tiles.recycle(tiles);
+
+        let mut under = self.scratch.under.take();
+        if is_fading {
+            for index in &fade.prev_drawn {
+                if faded.iter().any(|(drawn, ..)| drawn == index) {
+                    continue;
+                }
+
+                if let Some(tile) = self.tile_provider.get_tile(*index, self.style_id) {
+                    under.push((*index, tile));
+                }
+            }
+            under.sort_unstable_by_key(|(index, _)| index.z);
+        }
+
+        let mut drawn = self.scratch.index_set.lock();
+        drawn.clear();
+        drawn.extend(
+            under
+                .iter()
+                .map(|(index, _)| *index)
+                .chain(faded.iter().map(|(index, ..)| *index)),
+        );
+        fade.first_drawn.retain(|index, _| drawn.contains(index));
+
+        fade.prev_drawn.clear();
+        fade.prev_drawn.extend(
+            under.iter().map(|(index, _)| *index).chain(
+                faded
+                    .iter()
+                    .filter(|(_, _, opacity)| *opacity >= 1.0)
+                    .map(|(index, ..)| *index),
+            ),
+        );
+
+        output.extend(under.drain(..).map(|(_, tile)| (tile, 1.0)));
+        output.extend(faded.drain(..).map(|(_, tile, opacity)| (tile, opacity)));
+        self.scratch.under.recycle(under);
+        self.scratch.faded.recycle(faded);
+
+        is_fading
     }
 
-    /// Returns features, visible in the layer at the given point with the given map view.
-    pub fn get_features_at(
+    fn get_features_at(
         &self,
         point: &impl CartesianPoint2d<Num = f64>,
         view: &MapView,
@@ -202,3 +526,32 @@ where
         features
     }
 }
+
+fn fade_in_opacity(now: SystemTime, first_drawn: SystemTime, fade_in_duration: Duration) -> f32 {
+    if fade_in_duration.is_zero() {
+        return 1.0;
+    }
+
+    let since_drawn = now.duration_since(first_drawn).unwrap_or_default();
+    (since_drawn.as_secs_f64() / fade_in_duration.as_secs_f64()).min(1.0) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fade_in_opacity_grows_linearly() {
+        let start = SystemTime::now();
+        let duration = Duration::from_millis(300);
+
+        assert_eq!(fade_in_opacity(start, start, duration), 0.0);
+        let half = fade_in_opacity(start + Duration::from_millis(150), start, duration);
+        assert!((half - 0.5).abs() < 1e-6);
+        assert_eq!(
+            fade_in_opacity(start + Duration::from_secs(1), start, duration),
+            1.0
+        );
+        assert_eq!(fade_in_opacity(start, start, Duration::ZERO), 1.0);
+    }
+}
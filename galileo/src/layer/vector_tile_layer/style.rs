@@ -1,15 +1,25 @@
 //! See [`VectorTileStyle`].
 
-use crate::render::point_paint::PointPaint;
+use crate::error::GalileoError;
+use crate::layer::data_provider::PersistentCacheController;
+use crate::render::point_paint::{NinePatch, PointPaint};
+use crate::render::text::TextStyle;
+use crate::render::{LineCap, LineJoin};
 use crate::Color;
-use galileo_mvt::MvtFeature;
+use bytes::Bytes;
+use galileo_mvt::{MvtFeature, MvtValue};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use strfmt::strfmt;
+
+/// Name of the tile source of a [`VectorTileLayer`](super::VectorTileLayer) created with
+/// [`VectorTileLayer::from_url`](super::VectorTileLayer::from_url).
+pub const DEFAULT_SOURCE: &str = "default";
 
 /// Style of a vector tile layer. This specifies how each feature in a tile should be rendered.
 ///
 /// <div class="warning">This exact type is experimental and is likely to change in near future.</div>
-#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
 pub struct VectorTileStyle {
     /// Rules for feature to be drawn. Rules are traversed in sequence until a rule that corresponds to a current feature
     /// is found, and that rule is used for drawing. If no rule corresponds to the feature, default symbol is used.
@@ -23,6 +33,98 @@ pub struct VectorTileStyle {
 }
 
 impl VectorTileStyle {
+    /// Loads the style JSON from the URL, using the persistent `cache` to avoid loading it again on next starts of the
+    /// application. To keep the styles in a [`Storage`](crate::storage::Storage), use a
+    /// [`StorageCacheController`](crate::storage::StorageCacheController) with the
+    /// [`STYLES_NAMESPACE`](crate::storage::STYLES_NAMESPACE).
+    #[cfg(feature = "serde")]
+    pub async fn load_cached(
+        url: &str,
+        cache: &impl PersistentCacheController<str, Bytes>,
+    ) -> Result<Self, GalileoError> {
+        let data = crate::storage::load_cached(url, cache).await?;
+        serde_json::from_slice(&data)
+            .map_err(|err| GalileoError::Generic(format!("invalid vector tile style: {err}")))
+    }
+
+    /// Returns a copy of the style that only contains the rules applicable to the tile source with the given name.
+    pub fn for_source(&self, source: &str) -> Self {
+        Self {
+            rules: self
+                .rules
+                .iter()
+                .filter(|rule| rule.source.as_deref().is_none_or(|name| name == source))
+                .cloned()
+                .collect(),
+            default_symbol: self.default_symbol.clone(),
+            background: self.background,
+        }
+    }
+
+    /// Returns a copy of the style with the changes of the patch applied.
+    ///
+    /// The changes are applied in this order:
+    /// 1. Colors and fonts are replaced in all the rules and in the default symbol.
+    /// 2. Rule overrides are applied to the rules with the corresponding ids. Ids of the patch not found in the style
+    ///    are ignored with a warning.
+    /// 3. Rules hiding the features of the [hidden layers](StylePatch::hidden_layers) are added before all other rules.
+    pub fn patched(&self, patch: &StylePatch) -> Self {
+        let mut style = self.clone();
+
+        let replace_color = |color: Color| {
+            patch
+                .colors
+                .iter()
+                .find(|replacement| replacement.from == color)
+                .map_or(color, |replacement| replacement.to)
+        };
+        for symbol in std::iter::once(&mut style.default_symbol)
+            .chain(style.rules.iter_mut().map(|rule| &mut rule.symbol))
+        {
+            symbol.map_colors(replace_color);
+            if let Some(point) = &mut symbol.point {
+                if let Some(font_name) = point.font_name().and_then(|name| patch.fonts.get(name)) {
+                    point.set_font_name(font_name);
+                }
+            }
+            if let Some(shield) = &mut symbol.shield {
+                if let Some(font_name) = patch.fonts.get(&shield.text_style.font_name) {
+                    shield.text_style.font_name = font_name.clone();
+                }
+            }
+        }
+
+        for (id, rule_override) in &patch.rules {
+            let mut found = false;
+            for rule in style
+                .rules
+                .iter_mut()
+                .filter(|rule| rule.id.as_ref() == Some(id))
+            {
+                rule_override.apply(&mut rule.symbol);
+                found = true;
+            }
+
+            if !found {
+                log::warn!("Style patch overrides rule {id}, which is not found in the style");
+            }
+        }
+
+        if !patch.hidden_layers.is_empty() {
+            let hiding_rules = patch.hidden_layers.iter().map(|layer_name| StyleRule {
+                layer_name: Some(layer_name.clone()),
+                ..Default::default()
+            });
+            style.rules.splice(0..0, hiding_rules);
+        }
+
+        if let Some(background) = patch.background {
+            style.background = background;
+        }
+
+        style
+    }
+
     /// Get a rule for the given feature.
     pub fn get_style_rule(&self, layer_name: &str, feature: &MvtFeature) -> Option<&StyleRule> {
         self.rules.iter().find(|&rule| {
@@ -41,8 +143,11 @@ impl VectorTileStyle {
 }
 
 /// A rule that specifies what kind of features can be drawing with the given symbol.
-#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
 pub struct StyleRule {
+    /// Identifier of the rule, used to change the rule with a [`StylePatch`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
     /// If set, a feature must belong to the set layer. If not set, layer is not checked.
     pub layer_name: Option<String>,
     /// Specifies a set of attibutes of a feature that must have the given values for this rule to be applied.
@@ -50,10 +155,15 @@ pub struct StyleRule {
     pub properties: HashMap<String, String>,
     /// Symbol to draw a feature with.
     pub symbol: VectorTileSymbol,
+    /// If set, the rule is only applied to the features from the tile source with this name (see
+    /// [`VectorTileLayer::add_source`](super::VectorTileLayer::add_source)). If not set, the rule is applied to
+    /// features of all sources.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
 }
 
 /// Symbol to draw a vector tile feature.
-#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
 pub struct VectorTileSymbol {
     /// If set, points will be drawn with this symbol.
     pub point: Option<PointPaint<'static>>,
@@ -61,6 +171,9 @@ pub struct VectorTileSymbol {
     pub line: Option<VectorTileLineSymbol>,
     /// If set, polygons will be drawn with this symbol.
     pub polygon: Option<VectorTilePolygonSymbol>,
+    /// If set, a shield is drawn at every point and in the middle of every line.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shield: Option<VectorTileShieldSymbol>,
 }
 
 impl VectorTileSymbol {
@@ -70,12 +183,28 @@ impl VectorTileSymbol {
             point: None,
             line: None,
             polygon: Some(VectorTilePolygonSymbol { fill_color: color }),
+            shield: None,
+        }
+    }
+
+    fn map_colors(&mut self, f: impl Fn(Color) -> Color) {
+        if let Some(point) = &mut self.point {
+            point.map_colors(&f);
+        }
+        if let Some(line) = &mut self.line {
+            line.stroke_color = f(line.stroke_color);
+        }
+        if let Some(polygon) = &mut self.polygon {
+            polygon.fill_color = f(polygon.fill_color);
+        }
+        if let Some(shield) = &mut self.shield {
+            shield.text_style.font_color = f(shield.text_style.font_color);
         }
     }
 }
 
 /// Symbol for point geometries.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct VectorTilePointSymbol {
     /// Size of the point.
     pub size: f64,
@@ -84,17 +213,317 @@ pub struct VectorTilePointSymbol {
 }
 
 /// Symbol for line geometries.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct VectorTileLineSymbol {
     /// Width of the line in pixels.
     pub width: f64,
     /// Color of the line in pixels.
     pub stroke_color: Color,
+    /// Type of the cap of the line.
+    #[serde(default)]
+    pub line_cap: LineCap,
+    /// Type of the joins between line segments.
+    #[serde(default)]
+    pub line_join: LineJoin,
 }
 
 /// Symbol for polygon geometries.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct VectorTilePolygonSymbol {
     /// Color of the fill of polygon.
     pub fill_color: Color,
 }
+
+/// Shield (e.g. a highway shield or a transit badge) drawn for point and line features. See
+/// [`PointPaint::shield`].
+///
+/// The background of the shield is selected by the value of the [class property](Self::class_property) of the
+/// feature, so that a single rule can draw e.g. motorway and trunk road shields differently:
+///
+/// ```json
+/// {
+///     "text": "{ref}",
+///     "text_style": { "font_name": "Noto Sans", "font_size": 11.0 },
+///     "class_property": "class",
+///     "backgrounds": {
+///         "motorway": { "image": { ... }, "insets": [4, 4, 4, 4], "padding": [4.0, 2.0] },
+///         "trunk": { "image": { ... }, "insets": [4, 4, 4, 4], "padding": [4.0, 2.0] }
+///     }
+/// }
+/// ```
+///
+/// Shields of a tile are placed in the order of the features, and a shield that would overlap a shield placed before
+/// it is not drawn.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VectorTileShieldSymbol {
+    /// Text of the shield. Properties of the feature can be inserted with `{property_name}`, e.g. `{ref}`. Features
+    /// missing the properties or with empty text get no shield.
+    pub text: String,
+    /// Style of the text.
+    pub text_style: TextStyle,
+    /// Name of the feature property, value of which selects the background from
+    /// [`backgrounds`](Self::backgrounds).
+    #[serde(default)]
+    pub class_property: Option<String>,
+    /// Backgrounds by the value of the class property.
+    #[serde(default)]
+    pub backgrounds: HashMap<String, NinePatch>,
+    /// Background of the features, class of which has no background in [`backgrounds`](Self::backgrounds). If not
+    /// set, such features get no shield.
+    #[serde(default)]
+    pub default_background: Option<NinePatch>,
+}
+
+impl VectorTileShieldSymbol {
+    /// Returns the paint of the shield for a feature with the given properties, or `None` if the feature gets no
+    /// shield.
+    pub fn paint(&self, properties: &HashMap<String, MvtValue>) -> Option<PointPaint<'static>> {
+        let background = self
+            .class_property
+            .as_ref()
+            .and_then(|name| properties.get(name))
+            .and_then(|class| self.backgrounds.get(&class.to_string()))
+            .or(self.default_background.as_ref())?;
+
+        let text = strfmt(&self.text, properties).ok()?;
+        if text.trim().is_empty() {
+            return None;
+        }
+
+        Some(PointPaint::shield(
+            text,
+            self.text_style.clone(),
+            background.clone(),
+        ))
+    }
+}
+
+/// Small set of changes to a [`VectorTileStyle`], e.g. to re-brand a basemap style provided by a vendor without
+/// maintaining a full copy of it. The patch is applied with [`VectorTileStyle::patched`] or
+/// [`VectorTileLayer::set_style_patch`](super::VectorTileLayer::set_style_patch).
+///
+/// All fields are optional in serialized form, so a patch only lists what it changes:
+///
+/// ```json
+/// {
+///     "colors": [{ "from": "#1a73e8ff", "to": "#e4002bff" }],
+///     "fonts": { "Noto Sans": "Brand Sans" },
+///     "hidden_layers": ["poi"],
+///     "rules": { "highway": { "stroke_color": "#ffcc00ff", "line_width": 3.0 } }
+/// }
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StylePatch {
+    /// Colors to replace in all the symbols of the style, including outlines and labels.
+    #[serde(default)]
+    pub colors: Vec<ColorReplacement>,
+    /// Fonts of the labels to replace, from the font name in the style to the new font name.
+    #[serde(default)]
+    pub fonts: HashMap<String, String>,
+    /// Names of the tile layers, features of which are not drawn.
+    #[serde(default)]
+    pub hidden_layers: Vec<String>,
+    /// Changes to the rules with the given [ids](StyleRule::id).
+    #[serde(default)]
+    pub rules: HashMap<String, RuleOverride>,
+    /// New background color of the tiles.
+    #[serde(default)]
+    pub background: Option<Color>,
+}
+
+/// Replacement of a color in a [`StylePatch`].
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ColorReplacement {
+    /// Color of the original style.
+    pub from: Color,
+    /// Color to use instead.
+    pub to: Color,
+}
+
+/// Changes to a single [rule](StyleRule) of a style in a [`StylePatch`]. Property overrides only change the symbols
+/// that the rule already has, e.g. setting a `fill_color` does not make a rule without a polygon symbol draw
+/// polygons.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RuleOverride {
+    /// If set, the features matching the rule are not drawn.
+    #[serde(default)]
+    pub hidden: bool,
+    /// If set, replaces the whole symbol of the rule. Other overrides are applied on top of it.
+    #[serde(default)]
+    pub symbol: Option<VectorTileSymbol>,
+    /// New fill color of polygons.
+    #[serde(default)]
+    pub fill_color: Option<Color>,
+    /// New color of lines.
+    #[serde(default)]
+    pub stroke_color: Option<Color>,
+    /// New width of lines in pixels.
+    #[serde(default)]
+    pub line_width: Option<f64>,
+}
+
+impl RuleOverride {
+    fn apply(&self, symbol: &mut VectorTileSymbol) {
+        if self.hidden {
+            *symbol = VectorTileSymbol::default();
+            return;
+        }
+
+        if let Some(new_symbol) = &self.symbol {
+            *symbol = new_symbol.clone();
+        }
+        if let (Some(polygon), Some(color)) = (&mut symbol.polygon, self.fill_color) {
+            polygon.fill_color = color;
+        }
+        if let Some(line) = &mut symbol.line {
+            if let Some(color) = self.stroke_color {
+                line.stroke_color = color;
+            }
+            if let Some(width) = self.line_width {
+                line.width = width;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(source: Option<&str>) -> StyleRule {
+        StyleRule {
+            source: source.map(|s| s.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn for_source_filters_rules() {
+        let style = VectorTileStyle {
+            rules: vec![
+                rule(None),
+                rule(Some("overlay")),
+                rule(Some(DEFAULT_SOURCE)),
+            ],
+            ..Default::default()
+        };
+
+        let overlay = style.for_source("overlay");
+        assert_eq!(overlay.rules.len(), 2);
+        assert_eq!(overlay.rules[1].source.as_deref(), Some("overlay"));
+
+        let other = style.for_source("other");
+        assert_eq!(other.rules.len(), 1);
+        assert!(other.rules[0].source.is_none());
+    }
+
+    #[test]
+    fn patch_style() {
+        let brand = Color::rgba(10, 20, 30, 255);
+        let line = |color: Color| VectorTileSymbol {
+            line: Some(VectorTileLineSymbol {
+                width: 1.0,
+                stroke_color: color,
+                line_cap: LineCap::default(),
+                line_join: LineJoin::default(),
+            }),
+            ..Default::default()
+        };
+        let style = VectorTileStyle {
+            rules: vec![
+                StyleRule {
+                    id: Some("roads".into()),
+                    layer_name: Some("transportation".into()),
+                    symbol: line(Color::RED),
+                    ..Default::default()
+                },
+                StyleRule {
+                    id: Some("water".into()),
+                    symbol: VectorTileSymbol::polygon(Color::BLUE),
+                    ..Default::default()
+                },
+            ],
+            default_symbol: VectorTileSymbol::polygon(Color::RED),
+            background: Color::WHITE,
+        };
+
+        let patch: StylePatch = serde_json::from_str(
+            r##"{
+                "colors": [{ "from": "#ff0000ff", "to": "#0a141eff" }],
+                "hidden_layers": ["poi"],
+                "rules": {
+                    "roads": { "line_width": 3.0 },
+                    "water": { "hidden": true },
+                    "missing": { "hidden": true }
+                }
+            }"##,
+        )
+        .expect("valid patch");
+        let patched = style.patched(&patch);
+
+        assert_eq!(patched.rules.len(), 3);
+        assert_eq!(patched.rules[0].layer_name.as_deref(), Some("poi"));
+        assert_eq!(patched.rules[0].symbol, VectorTileSymbol::default());
+
+        let roads = patched.rules[1].symbol.line.as_ref().expect("line symbol");
+        assert_eq!(roads.stroke_color, brand);
+        assert_eq!(roads.width, 3.0);
+        assert_eq!(patched.rules[2].symbol, VectorTileSymbol::default());
+        assert_eq!(patched.default_symbol, VectorTileSymbol::polygon(brand));
+        assert_eq!(patched.background, Color::WHITE);
+
+        assert_eq!(style.patched(&StylePatch::default()), style);
+    }
+
+    #[test]
+    fn shield_background_by_class() {
+        use crate::decoded_image::DecodedImage;
+        use crate::render::point_paint::PointShape;
+        use std::sync::Arc;
+
+        let background = |size: u32| {
+            let image = DecodedImage::from_raw(vec![0u8; (size * size * 4) as usize], size, size);
+            NinePatch::new(Arc::new(image.unwrap()), [2; 4])
+        };
+        let symbol = VectorTileShieldSymbol {
+            text: "{ref}".into(),
+            text_style: TextStyle {
+                font_name: "Noto Sans".into(),
+                font_size: 11.0,
+                font_color: Color::WHITE,
+                horizontal_alignment: Default::default(),
+                vertical_alignment: Default::default(),
+                direction: Default::default(),
+            },
+            class_property: Some("class".into()),
+            backgrounds: HashMap::from([("motorway".into(), background(8))]),
+            default_background: Some(background(4)),
+        };
+
+        let properties = |class: &str, reference: &str| {
+            HashMap::from([
+                ("class".to_string(), MvtValue::String(class.into())),
+                ("ref".to_string(), MvtValue::String(reference.into())),
+            ])
+        };
+        let shield = |properties| match symbol.paint(&properties).map(|paint| paint.shape) {
+            Some(PointShape::Shield {
+                text, background, ..
+            }) => Some((text.into_owned(), background.image.width())),
+            _ => None,
+        };
+
+        assert_eq!(shield(properties("motorway", "A1")), Some(("A1".into(), 8)));
+        assert_eq!(shield(properties("primary", "B2")), Some(("B2".into(), 4)));
+        assert_eq!(shield(properties("motorway", " ")), None);
+        assert_eq!(shield(HashMap::new()), None);
+
+        let without_default = VectorTileShieldSymbol {
+            default_background: None,
+            ..symbol.clone()
+        };
+        assert!(without_default
+            .paint(&properties("primary", "B2"))
+            .is_none());
+    }
+}
@@ -1,7 +1,7 @@
 //! Vector tile loader stuff.
 
 use crate::error::GalileoError;
-use crate::layer::data_provider::{PersistentCacheController, UrlSource};
+use crate::layer::data_provider::{optional_url_source, PersistentCacheController, UrlSource};
 use crate::platform::{PlatformService, PlatformServiceImpl};
 use crate::tile_scheme::TileIndex;
 use bytes::Bytes;
@@ -33,7 +33,7 @@ where
 {
     platform_service: PlatformServiceImpl,
     cache: Cache,
-    url_source: Box<dyn UrlSource<TileIndex>>,
+    url_source: Box<dyn UrlSource<TileIndex, Option<String>>>,
 }
 
 impl<Cache> WebVtLoader<Cache>
@@ -41,15 +41,15 @@ where
     Cache: PersistentCacheController<str, Bytes> + MaybeSend + MaybeSync,
 {
     /// Create a new instance.
-    pub fn new(
+    pub fn new<Url: Into<Option<String>>>(
         platform_service: PlatformServiceImpl,
         cache: Cache,
-        url_source: impl UrlSource<TileIndex> + 'static,
+        url_source: impl UrlSource<TileIndex, Url> + 'static,
     ) -> Self {
         Self {
             platform_service,
             cache,
-            url_source: Box::new(url_source),
+            url_source: optional_url_source(url_source),
         }
     }
 
@@ -85,7 +85,7 @@ where
     Cache: PersistentCacheController<str, Bytes> + MaybeSend + MaybeSync,
 {
     async fn load(&self, index: TileIndex) -> Result<MvtTile, TileLoadError> {
-        let url = (self.url_source)(&index);
+        let url = (self.url_source)(&index).ok_or(TileLoadError::DoesNotExist)?;
 
         log::trace!("Loading tile {index:?} from url {url}");
         let bytes = self.load_raw(&url).await?;
@@ -1,7 +1,9 @@
 //! Vector tile layer tile providers
 
+use crate::cancellation::CancellationToken;
 use crate::layer::vector_tile_layer::style::VectorTileStyle;
 use crate::layer::vector_tile_layer::vector_tile::VectorTile;
+use crate::layer::LayerMemoryUsage;
 use crate::messenger::Messenger;
 use crate::render::render_bundle::RenderBundle;
 use crate::render::{Canvas, PackedBundle};
@@ -50,6 +52,7 @@ where
     loader: Arc<Loader>,
     processor: Arc<Processor>,
     messenger: Option<Arc<dyn Messenger>>,
+    cancellation: CancellationToken,
 }
 
 impl<Loader, Processor> Clone for VectorTileProvider<Loader, Processor>
@@ -63,6 +66,7 @@ where
             loader: self.loader.clone(),
             processor: self.processor.clone(),
             messenger: self.messenger.clone(),
+            cancellation: self.cancellation.clone(),
         }
     }
 }
@@ -79,6 +83,7 @@ where
             loader,
             processor,
             messenger: None,
+            cancellation: CancellationToken::new(),
         }
     }
 
@@ -104,6 +109,10 @@ where
     ///
     /// A style with given id must first be registerred in the provider.
     pub fn load_tile(&self, index: TileIndex, style_id: VtStyleId) {
+        if self.cancellation.is_cancelled() {
+            return;
+        }
+
         if !self.processor.has_style(style_id) {
             log::warn!("Requested tile loading with non-existing style");
             return;
@@ -123,6 +132,7 @@ where
         let processor = self.processor.clone();
         let data_provider = self.loader.clone();
         let messenger = self.messenger.clone();
+        let cancellation = self.cancellation.clone();
 
         crate::async_runtime::spawn(async move {
             let cell = {
@@ -134,13 +144,27 @@ where
                 store.start_loading_tile(index, style_id)
             };
 
-            let tile_state = cell
-                .get_or_init(|| async { Self::download(index, data_provider).await })
-                .await;
+            let tile_state = cancellation
+                .run_until_cancelled(async {
+                    let tile_state = cell
+                        .get_or_init(|| async { Self::download(index, data_provider).await })
+                        .await;
 
-            log::debug!("Tile {index:?} is loaded. Preparing.");
+                    log::debug!("Tile {index:?} is loaded. Preparing.");
 
-            let tile_state = Self::prepare_tile(tile_state, index, style_id, processor).await;
+                    Self::prepare_tile(tile_state, index, style_id, processor).await
+                })
+                .await;
+
+            let Some(tile_state) = tile_state else {
+                log::debug!("Loading of tile {index:?} is cancelled.");
+                // The tile is not left in the loading state, so it can be loaded again if needed.
+                tile_store
+                    .write()
+                    .expect("lock is poisoned")
+                    .remove(index, style_id);
+                return;
+            };
 
             log::debug!("tile {index:?} is prepared.");
 
@@ -192,11 +216,48 @@ where
             .get_mvt_tile(index)
     }
 
+    /// Approximate memory used by the cached tiles.
+    pub fn memory_usage(&self) -> LayerMemoryUsage {
+        let store = self.tiles.read().expect("lock is poisoned");
+        LayerMemoryUsage {
+            cpu_cache: store.cpu_size(),
+            gpu: store.gpu_size(),
+            cached_tiles: store.len(),
+        }
+    }
+
+    /// Removes all cached tiles. Tiles that are being loaded at the moment will still be added to the cache when
+    /// ready.
+    pub fn clear(&self) {
+        self.tiles.write().expect("lock is poisoned").clear();
+    }
+
     /// Set messenger to use to notify about tile updates.
     pub fn set_messenger(&mut self, messenger: Box<dyn Messenger>) {
         self.messenger = Some(messenger.into());
     }
 
+    /// Sets the token that cancels loading, decoding and tessellation of the tiles of the provider. A
+    /// [`VectorTileLayer`](super::VectorTileLayer) sets a child of its own token when the provider is added to it.
+    ///
+    /// Tiles that are being loaded with the previous token are not affected.
+    pub fn set_cancellation_token(&mut self, token: CancellationToken) {
+        self.cancellation = token;
+    }
+
+    /// Token that cancels loading of the tiles of the provider.
+    pub fn cancellation_token(&self) -> &CancellationToken {
+        &self.cancellation
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "load_vector_tile",
+            skip_all,
+            fields(x = tile_index.x, y = tile_index.y, z = tile_index.z)
+        )
+    )]
     async fn download(tile_index: TileIndex, loader: Arc<Loader>) -> MvtTileState {
         match loader.load(tile_index).await {
             Ok(mvt_tile) => MvtTileState::Loaded(Arc::new(mvt_tile)),
@@ -204,6 +265,14 @@ where
         }
     }
 
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "prepare_vector_tile",
+            skip_all,
+            fields(x = index.x, y = index.y, z = index.z)
+        )
+    )]
     async fn prepare_tile(
         mvt_tile_state: &MvtTileState,
         index: TileIndex,
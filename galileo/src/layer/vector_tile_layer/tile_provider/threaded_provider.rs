@@ -1,3 +1,4 @@
+use crate::cancellation::CancellationToken;
 use crate::error::GalileoError;
 use crate::layer::data_provider::DataProvider;
 use crate::layer::vector_tile_layer::style::VectorTileStyle;
@@ -27,6 +28,7 @@ where
     data_provider: Arc<Provider>,
     tiles: Arc<Mutex<Cache<TileIndex, TileState>>>,
     empty_bundle: RenderBundle,
+    cancellation: CancellationToken,
 }
 
 impl<Provider> Clone for ThreadedProvider<Provider>
@@ -43,6 +45,7 @@ where
             data_provider: self.data_provider.clone(),
             tiles: self.tiles.clone(),
             empty_bundle: self.empty_bundle.clone(),
+            cancellation: self.cancellation.clone(),
         }
     }
 }
@@ -55,6 +58,10 @@ where
         + 'static,
 {
     fn load_tile(&self, index: TileIndex, style: &VectorTileStyle) {
+        if self.cancellation.is_cancelled() {
+            return;
+        }
+
         if self.set_loading_state(index) {
             self.load_tile_internal(index, style);
         }
@@ -116,9 +123,21 @@ where
             data_provider: Arc::new(data_provider),
             tiles: Arc::new(Mutex::new(Cache::new(1000))),
             empty_bundle,
+            cancellation: CancellationToken::new(),
         }
     }
 
+    /// Sets the token that cancels loading and decoding of the tiles of the provider. After the token is cancelled,
+    /// the provider doesn't load new tiles, and the results of the tiles being loaded are discarded.
+    pub fn set_cancellation_token(&mut self, token: CancellationToken) {
+        self.cancellation = token;
+    }
+
+    /// Token that cancels loading of the tiles of the provider.
+    pub fn cancellation_token(&self) -> &CancellationToken {
+        &self.cancellation
+    }
+
     fn set_loading_state(&self, index: TileIndex) -> bool {
         let mut tiles = self.tiles.lock().expect("tile store mutex is poisoned");
         let has_entry = tiles.peek(&index).is_some();
@@ -147,8 +166,26 @@ where
         let provider: ThreadedProvider<Provider> = (*self).clone();
         let style = style.clone();
         crate::async_runtime::spawn(async move {
-            match provider.clone().load_tile_async(index, style).await {
-                Ok(tile) => {
+            let result = provider
+                .cancellation
+                .run_until_cancelled(provider.clone().load_tile_async(index, style))
+                .await;
+            match result {
+                None => {
+                    log::debug!("Loading of tile {index:?} is cancelled.");
+                    let mut tiles = provider.tiles.lock().expect("tile store mutex is poisoned");
+                    // Leave the tile in the state it was before loading, so it can be loaded again if needed.
+                    match tiles.remove(&index) {
+                        Some((_, TileState::Updating(tile))) => {
+                            tiles.insert(index, TileState::Outdated(tile));
+                        }
+                        Some((_, TileState::Loading)) | None => {}
+                        Some((_, state)) => {
+                            tiles.insert(index, state);
+                        }
+                    }
+                }
+                Some(Ok(tile)) => {
                     let mut tiles = provider.tiles.lock().expect("tile store mutex is poisoned");
                     tiles.insert(index, TileState::Loaded(Box::new(tile)));
                     if let Some(messenger) = &*provider
@@ -159,7 +196,7 @@ where
                         messenger.request_redraw();
                     }
                 }
-                Err(err) => {
+                Some(Err(err)) => {
                     log::info!("Failed to load tile: {err:?}");
                     let mut tiles = provider.tiles.lock().expect("tile store mutex is poisoned");
                     tiles.insert(index, TileState::Error);
@@ -168,19 +205,32 @@ where
         });
     }
 
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "load_vector_tile",
+            skip_all,
+            fields(x = index.x, y = index.y, z = index.z)
+        )
+    )]
     async fn load_tile_async(
         self,
         index: TileIndex,
         style: VectorTileStyle,
     ) -> Result<UnpackedVectorTile, GalileoError> {
         let bytes = self.download_tile(index).await?;
-        tokio::task::spawn_blocking(move || self.try_prepare_tile(bytes, index, &style))
-            .await
-            .unwrap_or_else(|err| {
-                Err(GalileoError::Generic(format!(
-                    "Failed to load tile: {err:?}"
-                )))
-            })
+        self.cancellation.check()?;
+        tokio::task::spawn_blocking(move || {
+            // The task might wait in the blocking pool queue for a while.
+            self.cancellation.check()?;
+            self.try_prepare_tile(bytes, index, &style)
+        })
+        .await
+        .unwrap_or_else(|err| {
+            Err(GalileoError::Generic(format!(
+                "Failed to load tile: {err:?}"
+            )))
+        })
     }
 
     fn try_prepare_tile(
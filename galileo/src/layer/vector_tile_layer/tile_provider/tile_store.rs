@@ -44,6 +44,15 @@ struct TileStoreEntry {
     prepared_tile: PreparedTileState,
 }
 
+impl TileStoreEntry {
+    fn gpu_size(&self) -> usize {
+        match &self.prepared_tile {
+            PreparedTileState::Packed(bundle) => bundle.gpu_size(),
+            _ => 0,
+        }
+    }
+}
+
 pub(super) struct TileStore {
     mvt_tiles: HashMap<TileIndex, Weak<OnceCell<MvtTileState>>, ahash::RandomState>,
     processed: Cache<
@@ -53,6 +62,7 @@ pub(super) struct TileStore {
         DefaultHashBuilder,
         TileStoreLc,
     >,
+    packed_size: usize,
 }
 
 impl Default for TileStore {
@@ -66,6 +76,7 @@ impl Default for TileStore {
                 DefaultHashBuilder::default(),
                 TileStoreLc,
             ),
+            packed_size: 0,
         }
     }
 }
@@ -86,6 +97,7 @@ struct TileStoreLc;
 #[derive(Debug, Default, Clone)]
 struct TileStoreLcState {
     evicted: Vec<TileIndex>,
+    evicted_gpu_size: usize,
 }
 
 impl Lifecycle<(TileIndex, VtStyleId), TileStoreEntry> for TileStoreLc {
@@ -99,9 +111,10 @@ impl Lifecycle<(TileIndex, VtStyleId), TileStoreEntry> for TileStoreLc {
         &self,
         state: &mut Self::RequestState,
         key: (TileIndex, VtStyleId),
-        _val: TileStoreEntry,
+        val: TileStoreEntry,
     ) {
-        state.evicted.push(key.0)
+        state.evicted.push(key.0);
+        state.evicted_gpu_size += val.gpu_size();
     }
 }
 
@@ -161,6 +174,13 @@ impl TileStore {
         self.insert_entry(tile_index, style_id, entry);
     }
 
+    /// Removes the tile prepared with the given style, e.g. if its loading was cancelled.
+    pub fn remove(&mut self, tile_index: TileIndex, style_id: VtStyleId) {
+        if let Some((_, entry)) = self.processed.remove(&(tile_index, style_id)) {
+            self.packed_size = self.packed_size.saturating_sub(entry.gpu_size());
+        }
+    }
+
     pub fn get_prepared(
         &self,
         index: TileIndex,
@@ -201,10 +221,44 @@ impl TileStore {
         }
     }
 
+    pub fn cpu_size(&self) -> usize {
+        self.processed.weight() as usize
+    }
+
+    pub fn gpu_size(&self) -> usize {
+        self.packed_size
+    }
+
+    pub fn len(&self) -> usize {
+        self.processed.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.processed.clear();
+        self.mvt_tiles.clear();
+        self.packed_size = 0;
+    }
+
     fn insert_entry(&mut self, index: TileIndex, style_id: VtStyleId, entry: TileStoreEntry) {
+        if let Some(prev) = self.processed.peek(&(index, style_id)) {
+            self.packed_size = self.packed_size.saturating_sub(prev.gpu_size());
+        }
+        self.packed_size += entry.gpu_size();
+
         let lc = self
             .processed
             .insert_with_lifecycle((index, style_id), entry);
+        self.packed_size = self.packed_size.saturating_sub(lc.evicted_gpu_size);
+
+        #[cfg(feature = "tracing")]
+        if !lc.evicted.is_empty() {
+            tracing::debug!(
+                evicted = lc.evicted.len(),
+                cached = self.processed.len(),
+                weight = self.processed.weight(),
+                "vector tile cache eviction"
+            );
+        }
 
         for index in lc.evicted {
             self.on_bundle_evicted(index)
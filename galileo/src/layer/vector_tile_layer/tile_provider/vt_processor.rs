@@ -1,16 +1,18 @@
 use crate::error::GalileoError;
 use crate::layer::data_provider::DataProcessor;
 use crate::layer::vector_tile_layer::style::VectorTileStyle;
-use crate::render::point_paint::{PointPaint, PointShape};
+use crate::render::point_paint::PointPaint;
 use crate::render::render_bundle::{RenderBundle, RenderPrimitive};
-use crate::render::{LineCap, LinePaint, PolygonPaint};
+use crate::render::{LinePaint, PolygonPaint, SizeUnits};
 use crate::tile_scheme::TileIndex;
 use crate::TileSchema;
 use bytes::Bytes;
 use galileo_mvt::{MvtFeature, MvtGeometry, MvtTile};
-use galileo_types::cartesian::{CartesianPoint2d, Point3d, Rect};
+use galileo_types::cartesian::{
+    CartesianClosedContour, CartesianPoint2d, Point3d, PolygonLabelPoint, Rect,
+};
 use galileo_types::impls::{ClosedContour, Polygon};
-use galileo_types::Contour;
+use galileo_types::{Contour, Polygon as _};
 use num_traits::ToPrimitive;
 use strfmt::strfmt;
 
@@ -34,6 +36,20 @@ impl DataProcessor for VtProcessor {
     type Output = (RenderBundle, MvtTile);
     type Context = VectorTileDecodeContext;
 
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "decode_vector_tile",
+            skip_all,
+            fields(
+                x = context.index.x,
+                y = context.index.y,
+                z = context.index.z,
+                decode_ms = tracing::field::Empty,
+                tessellate_ms = tracing::field::Empty
+            )
+        )
+    )]
     fn process(
         &self,
         input: Self::Input,
@@ -51,6 +67,11 @@ impl DataProcessor for VtProcessor {
         Self::prepare(&mvt_tile, &mut bundle, index, &style, &tile_scheme)?;
         let prerendered_in = start.elapsed() - mvt_decoded_in;
 
+        #[cfg(feature = "tracing")]
+        tracing::Span::current()
+            .record("decode_ms", mvt_decoded_in.as_secs_f64() * 1000.0)
+            .record("tessellate_ms", prerendered_in.as_secs_f64() * 1000.0);
+
         log::info!(
             "Decoded tile in {} ms, prerendered in {} ms",
             mvt_decoded_in.as_millis(),
@@ -63,6 +84,14 @@ impl DataProcessor for VtProcessor {
 
 impl VtProcessor {
     /// Pre-render the given tile into the given `bundle`.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "tessellate_vector_tile",
+            skip_all,
+            fields(x = index.x, y = index.y, z = index.z)
+        )
+    )]
     pub fn prepare(
         mvt_tile: &MvtTile,
         bundle: &mut RenderBundle,
@@ -78,12 +107,17 @@ impl VtProcessor {
         })?;
         let tile_resolution = lod_resolution * tile_scheme.tile_width() as f64;
 
+        // Tile geometries are stored relative to the tile center to keep precision at high zoom levels.
+        bundle.set_origin(bbox.center());
+        let half_width = bbox.half_width();
+        let half_height = bbox.half_height();
+
         let bounds = Polygon::new(
             ClosedContour::new(vec![
-                Point3d::new(bbox.x_min(), bbox.y_min(), 0.0),
-                Point3d::new(bbox.x_min(), bbox.y_max(), 0.0),
-                Point3d::new(bbox.x_max(), bbox.y_max(), 0.0),
-                Point3d::new(bbox.x_max(), bbox.y_min(), 0.0),
+                Point3d::new(-half_width, -half_height, 0.0),
+                Point3d::new(-half_width, half_height, 0.0),
+                Point3d::new(half_width, half_height, 0.0),
+                Point3d::new(half_width, -half_height, 0.0),
             ]),
             vec![],
         );
@@ -99,8 +133,12 @@ impl VtProcessor {
             lod_resolution,
         );
 
+        let mut shields = ShieldPlacement::new(bbox, lod_resolution);
+
         for layer in &mvt_tile.layers {
             for feature in &layer.features {
+                let shield = Self::get_shield_paint(style, &layer.name, feature);
+
                 match &feature.geometry {
                     MvtGeometry::Point(points) => {
                         // let label = if feature.properties.contains_key("name") {
@@ -122,6 +160,16 @@ impl VtProcessor {
                         //     vertical_alignment: VerticalAlignment::Top,
                         // };
 
+                        if let Some(shield) = &shield {
+                            for point in points {
+                                shields.add(
+                                    bundle,
+                                    Self::transform_point(point, bbox, tile_resolution),
+                                    shield,
+                                );
+                            }
+                        }
+
                         let Some(paint) = Self::get_point_symbol(style, &layer.name, feature)
                         else {
                             continue;
@@ -129,7 +177,22 @@ impl VtProcessor {
 
                         for point in points {
                             // let paint = PointPaint::label(&label, &style);
-                            bundle.add(RenderPrimitive::<_, _, galileo_types::impls::Contour<_>, Polygon<_>>::new_point_ref(&Self::transform_point(point, bbox, tile_resolution), &paint), lod_resolution);
+                            let point = Self::transform_point(point, bbox, tile_resolution);
+                            if paint.is_shield() {
+                                shields.add(bundle, point, &paint);
+                            } else {
+                                bundle.add(
+                                    RenderPrimitive::<
+                                        _,
+                                        _,
+                                        galileo_types::impls::Contour<_>,
+                                        Polygon<_>,
+                                    >::new_point_ref(
+                                        &point, &paint
+                                    ),
+                                    lod_resolution,
+                                );
+                            }
                         }
                     }
                     MvtGeometry::LineString(contours) => {
@@ -152,6 +215,18 @@ impl VtProcessor {
                                 );
                             }
                         }
+
+                        if let Some(shield) = &shield {
+                            for contour in contours {
+                                let points: Vec<_> = contour
+                                    .iter_points()
+                                    .map(|p| Self::transform_point(p, bbox, tile_resolution))
+                                    .collect();
+                                if let Some(midpoint) = line_midpoint(&points) {
+                                    shields.add(bundle, midpoint, shield);
+                                }
+                            }
+                        }
                     }
                     MvtGeometry::Polygon(polygons) => {
                         if let Some(paint) = Self::get_polygon_symbol(style, &layer.name, feature) {
@@ -167,6 +242,35 @@ impl VtProcessor {
                                 );
                             }
                         }
+
+                        if let Some(paint) = Self::get_polygon_label(style, &layer.name, feature) {
+                            // One label per feature, placed inside its largest part, with the
+                            // precision of one pixel of the tile.
+                            let label_point = polygons
+                                .iter()
+                                .map(|polygon| {
+                                    (polygon.outer_contour().area_signed().abs(), polygon)
+                                })
+                                .max_by(|(a, _), (b, _)| a.total_cmp(b))
+                                .and_then(|(_, polygon)| {
+                                    polygon.label_point(1.0 / tile_scheme.tile_width() as f32)
+                                });
+
+                            if let Some(point) = label_point {
+                                bundle.add(
+                                    RenderPrimitive::<
+                                        _,
+                                        _,
+                                        galileo_types::impls::Contour<_>,
+                                        Polygon<_>,
+                                    >::new_point_ref(
+                                        &Self::transform_point(&point, bbox, tile_resolution),
+                                        &paint,
+                                    ),
+                                    lod_resolution,
+                                );
+                            }
+                        }
                     }
                 }
             }
@@ -181,7 +285,7 @@ impl VtProcessor {
         feature: &MvtFeature,
     ) -> Option<PointPaint<'a>> {
         let mut paint = Self::get_point_paint(style, layer_name, feature)?.clone();
-        if let PointShape::Label { text, .. } = &mut paint.shape {
+        if let Some(text) = paint.text_mut() {
             let formatted = strfmt(text, &feature.properties).ok()?;
             *text.to_mut() = formatted;
         }
@@ -189,6 +293,19 @@ impl VtProcessor {
         Some(paint)
     }
 
+    fn get_shield_paint(
+        style: &VectorTileStyle,
+        layer_name: &str,
+        feature: &MvtFeature,
+    ) -> Option<PointPaint<'static>> {
+        let symbol = match style.get_style_rule(layer_name, feature) {
+            Some(rule) => rule.symbol.shield.as_ref()?,
+            None => style.default_symbol.shield.as_ref()?,
+        };
+
+        symbol.paint(&feature.properties)
+    }
+
     fn get_point_paint<'a>(
         style: &'a VectorTileStyle,
         layer_name: &str,
@@ -201,6 +318,21 @@ impl VtProcessor {
         rule.symbol.point.as_ref()
     }
 
+    /// Label of a polygon feature is only drawn if the style rule for the feature has a point symbol. The default
+    /// point symbol of the style is not applied to polygons.
+    fn get_polygon_label<'a>(
+        style: &'a VectorTileStyle,
+        layer_name: &str,
+        feature: &MvtFeature,
+    ) -> Option<PointPaint<'a>> {
+        style
+            .get_style_rule(layer_name, feature)?
+            .symbol
+            .point
+            .as_ref()?;
+        Self::get_point_symbol(style, layer_name, feature)
+    }
+
     fn get_line_symbol(
         style: &VectorTileStyle,
         layer_name: &str,
@@ -212,7 +344,10 @@ impl VtProcessor {
                 width: symbol.width,
                 color: symbol.stroke_color,
                 offset: 0.0,
-                line_cap: LineCap::Butt,
+                line_cap: symbol.line_cap,
+                line_join: symbol.line_join,
+                miter_limit: LinePaint::DEFAULT_MITER_LIMIT,
+                units: SizeUnits::Pixels,
             });
         };
 
@@ -222,7 +357,10 @@ impl VtProcessor {
             width: symbol.width,
             color: symbol.stroke_color,
             offset: 0.0,
-            line_cap: LineCap::Butt,
+            line_cap: symbol.line_cap,
+            line_join: symbol.line_join,
+            miter_limit: LinePaint::DEFAULT_MITER_LIMIT,
+            units: SizeUnits::Pixels,
         })
     }
 
@@ -242,13 +380,131 @@ impl VtProcessor {
         })
     }
 
+    /// Converts a point in tile pixel coordinates into map coordinates relative to the center of the tile.
     fn transform_point<Num: num_traits::Float + ToPrimitive>(
         p_in: &impl CartesianPoint2d<Num = Num>,
         tile_bbox: Rect,
         tile_resolution: f64,
     ) -> Point3d {
-        let x = tile_bbox.x_min() + p_in.x().to_f64().expect("double overflow") * tile_resolution;
-        let y = tile_bbox.y_max() - p_in.y().to_f64().expect("double overflow") * tile_resolution;
+        let x =
+            p_in.x().to_f64().expect("double overflow") * tile_resolution - tile_bbox.half_width();
+        let y =
+            tile_bbox.half_height() - p_in.y().to_f64().expect("double overflow") * tile_resolution;
         Point3d::new(x, y, 0.0)
     }
 }
+
+/// Places the shields of a tile, skipping the shields that would overlap the shields placed before them. The icon and
+/// the text of a shield are checked for collisions as a single rectangle.
+///
+/// Only the shields with the anchor point inside the tile are placed, so that features in the buffer area of tiles
+/// don't get a shield in every tile.
+struct ShieldPlacement {
+    half_width: f64,
+    half_height: f64,
+    lod_resolution: f64,
+    placed: Vec<Rect>,
+}
+
+impl ShieldPlacement {
+    fn new(tile_bbox: Rect, lod_resolution: f64) -> Self {
+        Self {
+            half_width: tile_bbox.half_width(),
+            half_height: tile_bbox.half_height(),
+            lod_resolution,
+            placed: vec![],
+        }
+    }
+
+    /// Adds the shield to the bundle if it is inside the tile and doesn't overlap other shields.
+    fn add(&mut self, bundle: &mut RenderBundle, point: Point3d, paint: &PointPaint) {
+        if point.x.abs() > self.half_width || point.y.abs() > self.half_height {
+            return;
+        }
+
+        let Some(bounds) = paint.shield_bounds() else {
+            return;
+        };
+        if !self.try_place(point, bounds) {
+            return;
+        }
+
+        bundle.add(
+            RenderPrimitive::<_, _, galileo_types::impls::Contour<_>, Polygon<_>>::new_point_ref(
+                &point, paint,
+            ),
+            self.lod_resolution,
+        );
+    }
+
+    /// Reserves the place for a shield with the given pixel bounds at the point, if it is free.
+    fn try_place(&mut self, point: Point3d, bounds: Rect<f32>) -> bool {
+        let to_map = |v: f32| v as f64 * self.lod_resolution;
+        let rect = Rect::new(
+            point.x + to_map(bounds.x_min()),
+            point.y + to_map(bounds.y_min()),
+            point.x + to_map(bounds.x_max()),
+            point.y + to_map(bounds.y_max()),
+        );
+        let overlaps = |placed: &Rect| {
+            placed.x_min() < rect.x_max()
+                && rect.x_min() < placed.x_max()
+                && placed.y_min() < rect.y_max()
+                && rect.y_min() < placed.y_max()
+        };
+        if self.placed.iter().any(overlaps) {
+            return false;
+        }
+
+        self.placed.push(rect);
+        true
+    }
+}
+
+/// Point in the middle of the length of the line.
+fn line_midpoint(points: &[Point3d]) -> Option<Point3d> {
+    let length: f64 = points.windows(2).map(|s| (s[1] - s[0]).norm()).sum();
+    let mut remaining = length / 2.0;
+    for segment in points.windows(2) {
+        let segment_length = (segment[1] - segment[0]).norm();
+        if segment_length >= remaining && segment_length > 0.0 {
+            return Some(segment[0] + (segment[1] - segment[0]) * (remaining / segment_length));
+        }
+        remaining -= segment_length;
+    }
+
+    points.first().copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn midpoint_of_line() {
+        let points = [
+            Point3d::new(0.0, 0.0, 0.0),
+            Point3d::new(2.0, 0.0, 0.0),
+            Point3d::new(2.0, 6.0, 0.0),
+        ];
+        assert_eq!(line_midpoint(&points), Some(Point3d::new(2.0, 2.0, 0.0)));
+        assert_eq!(
+            line_midpoint(&points[..1]),
+            Some(Point3d::new(0.0, 0.0, 0.0))
+        );
+        assert_eq!(line_midpoint(&[]), None);
+    }
+
+    #[test]
+    fn overlapping_shields_are_skipped() {
+        let mut placement = ShieldPlacement::new(Rect::new(0.0, 0.0, 256.0, 256.0), 2.0);
+        let bounds = Rect::new(-10.0, -5.0, 10.0, 5.0);
+
+        assert!(placement.try_place(Point3d::new(0.0, 0.0, 0.0), bounds));
+        // 30 map units is 15 pixels, less than the width of the shield.
+        assert!(!placement.try_place(Point3d::new(30.0, 0.0, 0.0), bounds));
+        assert!(placement.try_place(Point3d::new(50.0, 0.0, 0.0), bounds));
+        assert!(!placement.try_place(Point3d::new(0.0, 15.0, 0.0), bounds));
+        assert!(placement.try_place(Point3d::new(0.0, 30.0, 0.0), bounds));
+    }
+}
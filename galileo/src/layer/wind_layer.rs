@@ -0,0 +1,617 @@
+//! [`WindLayer`] animates particles moving with a wind (or water current) velocity field.
+
+use crate::cancellation::{CancelOnDrop, CancellationToken};
+use crate::decoded_image::DecodedImage;
+use crate::layer::data_provider::DataProvider;
+use crate::layer::{Layer, LayerMemoryUsage};
+use crate::messenger::Messenger;
+use crate::render::{
+    Canvas, FieldVelocity, PackedParticles, ParticleField, ParticleFrame, RenderOptions,
+};
+use crate::tile_scheme::{TileIndex, TileSchema};
+use crate::view::MapView;
+use crate::Color;
+use galileo_types::cartesian::{CartesianPoint2d, Point2d, Rect};
+use galileo_types::geo::impls::GeoPoint2d;
+use galileo_types::geo::{GeoPoint, NewGeoPoint, Projection};
+use maybe_sync::{MaybeSend, MaybeSync, Mutex};
+use quick_cache::sync::Cache;
+use std::any::Any;
+use std::sync::Arc;
+use web_time::{Duration, SystemTime};
+
+/// Meters in one degree of latitude.
+const METERS_PER_DEGREE: f64 = 111_320.0;
+
+/// Maximum time step of one animation frame. Larger gaps between frames (e.g. when the application was in the
+/// background) would make particles jump across the map.
+const MAX_FRAME_STEP: Duration = Duration::from_millis(100);
+
+/// Encoding of the velocity in the pixels of the tiles of a [`WindLayer`].
+///
+/// The `u` (eastward) component of the velocity is encoded in the red channel and the `v` (northward) component in the
+/// green channel, both in meters per second: channel value `0` corresponds to the minimum of the range and `255` to
+/// the maximum. Pixels with zero alpha have no data. This is the format commonly used to ship wind data to the
+/// browsers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VelocityEncoding {
+    /// Range of the `u` component.
+    pub u_range: (f32, f32),
+    /// Range of the `v` component.
+    pub v_range: (f32, f32),
+}
+
+impl VelocityEncoding {
+    /// Decodes `(u, v)` velocity from the pixel. Returns `None` if the pixel has no data.
+    pub fn decode(&self, pixel: [u8; 4]) -> Option<(f32, f32)> {
+        if pixel[3] == 0 {
+            return None;
+        }
+
+        let decode = |value: u8, (min, max): (f32, f32)| min + (max - min) * value as f32 / 255.0;
+        Some((
+            decode(pixel[0], self.u_range),
+            decode(pixel[1], self.v_range),
+        ))
+    }
+}
+
+/// Appearance and behaviour of the particles of a [`WindLayer`].
+#[derive(Debug, Clone)]
+pub struct WindStyle {
+    /// Number of particles on the screen.
+    pub particle_count: usize,
+    /// Time acceleration of the animation: with the value of `1.0` particles move with the real speed of the wind.
+    pub speed_factor: f64,
+    /// Color of the particles moving with the maximum speed of the field. Slower particles are drawn with
+    /// [`WindStyle::slow_color`], and colors in between are interpolated.
+    pub color: Color,
+    /// Color of the still particles.
+    pub slow_color: Color,
+    /// Width of the particle trails in pixels.
+    pub line_width: f64,
+    /// Number of positions stored in the particle trail. The trail fades out towards its end.
+    pub trail_length: usize,
+    /// Maximum age of a particle in frames. After that the particle is moved to a random position, so that the
+    /// particles do not gather in the convergence zones of the field.
+    pub max_age: u32,
+}
+
+impl Default for WindStyle {
+    fn default() -> Self {
+        Self {
+            particle_count: 2000,
+            speed_factor: 3000.0,
+            color: Color::WHITE,
+            slow_color: Color::rgba(255, 255, 255, 100),
+            line_width: 1.5,
+            trail_length: 8,
+            max_age: 100,
+        }
+    }
+}
+
+enum WindTile {
+    Loading,
+    Loaded(DecodedImage),
+    Error,
+}
+
+struct ParticleState {
+    particles: Option<Box<dyn PackedParticles>>,
+    /// Tiles the velocity field of the particles was built from.
+    field_tiles: Vec<TileIndex>,
+    field_bbox: Rect,
+    max_magnitude: f32,
+    last_frame: Option<SystemTime>,
+}
+
+impl ParticleState {
+    fn new() -> Self {
+        Self {
+            particles: None,
+            field_tiles: vec![],
+            field_bbox: Rect::default(),
+            max_magnitude: 0.0,
+            last_frame: None,
+        }
+    }
+}
+
+/// Layer that animates particles moving with the velocities of wind or water current, leaving fading trails behind.
+///
+/// Velocities are loaded as raster tiles by the `Provider`, with the `u` and `v` components encoded into the color
+/// channels of the images (see [`VelocityEncoding`]). The visible tiles are joined into a velocity field, that is
+/// uploaded to the GPU together with the state of the particles, so the particles are moved and drawn by the
+/// renderer without any work on the CPU (see [`Canvas::create_particles`]). The layer requests a redraw of the map
+/// after every frame for as long as it is visible. Density, speed and colors of the particles are configured with
+/// [`WindStyle`].
+///
+/// Nothing is drawn if the renderer does not support particle animation.
+pub struct WindLayer<Provider>
+where
+    Provider: DataProvider<TileIndex, DecodedImage, ()> + MaybeSync + MaybeSend,
+{
+    tile_provider: Arc<Provider>,
+    tile_schema: TileSchema,
+    encoding: VelocityEncoding,
+    style: WindStyle,
+    tiles: Arc<Cache<TileIndex, Arc<WindTile>>>,
+    state: Mutex<ParticleState>,
+    messenger: Option<Arc<dyn Messenger>>,
+    cancellation: CancelOnDrop,
+}
+
+impl<Provider> WindLayer<Provider>
+where
+    Provider: DataProvider<TileIndex, DecodedImage, ()> + MaybeSync + MaybeSend,
+{
+    /// Creates a new layer.
+    pub fn new(
+        tile_schema: TileSchema,
+        tile_provider: Provider,
+        encoding: VelocityEncoding,
+        style: WindStyle,
+    ) -> Self {
+        Self {
+            tile_provider: Arc::new(tile_provider),
+            tile_schema,
+            encoding,
+            style,
+            tiles: Arc::new(Cache::new(256)),
+            state: Mutex::new(ParticleState::new()),
+            messenger: None,
+            cancellation: CancellationToken::new().drop_guard(),
+        }
+    }
+
+    /// Token that cancels loading of the tiles of the layer. It is cancelled when the layer is dropped.
+    pub fn cancellation_token(&self) -> &CancellationToken {
+        self.cancellation.token()
+    }
+
+    /// Replaces the provider of the velocity tiles, e.g. with the one loading the data for the next forecast hour.
+    /// Particles keep their positions, and move with the new velocities as soon as the new tiles are loaded.
+    pub fn set_tile_provider(&mut self, tile_provider: Provider) {
+        self.tile_provider = Arc::new(tile_provider);
+        self.tiles = Arc::new(Cache::new(256));
+        self.state.get_mut().field_tiles.clear();
+    }
+
+    /// Encoding of the velocity in the tiles.
+    pub fn encoding(&self) -> VelocityEncoding {
+        self.encoding
+    }
+
+    /// Style of the layer.
+    pub fn style(&self) -> &WindStyle {
+        &self.style
+    }
+
+    /// Sets the style of the layer.
+    pub fn set_style(&mut self, style: WindStyle) {
+        if style.particle_count != self.style.particle_count
+            || style.trail_length != self.style.trail_length
+        {
+            // The particles are created again with the new size.
+            *self.state.get_mut() = ParticleState::new();
+        }
+
+        self.style = style;
+    }
+
+    async fn load_tile(
+        index: TileIndex,
+        tile_provider: Arc<Provider>,
+        tiles: &Cache<TileIndex, Arc<WindTile>>,
+        messenger: Option<Arc<dyn Messenger>>,
+        cancellation: &CancellationToken,
+    ) {
+        if cancellation.is_cancelled() {
+            return;
+        }
+
+        if let Err(guard) = tiles.get_value_or_guard_async(&index).await {
+            let _ = guard.insert(Arc::new(WindTile::Loading));
+            let Some(result) = cancellation
+                .run_until_cancelled(tile_provider.load(&index, ()))
+                .await
+            else {
+                tiles.remove(&index);
+                return;
+            };
+
+            match result {
+                Ok(image) => {
+                    tiles.insert(index, Arc::new(WindTile::Loaded(image)));
+                    if let Some(messenger) = messenger {
+                        messenger.request_redraw();
+                    }
+                }
+                Err(err) => {
+                    log::warn!("Failed to load velocity tile {index:?}: {err:?}");
+                    tiles.insert(index, Arc::new(WindTile::Error));
+                }
+            }
+        }
+    }
+
+    /// Preload velocity tiles for the given `view`.
+    pub async fn load_tiles(&self, view: &MapView) {
+        let Some(iter) = self.tile_schema.iter_tiles(view) else {
+            return;
+        };
+
+        for index in iter {
+            Self::load_tile(
+                index,
+                self.tile_provider.clone(),
+                &self.tiles,
+                self.messenger.clone(),
+                self.cancellation.token(),
+            )
+            .await;
+        }
+    }
+
+    /// Rebuilds the velocity field of the particles from the given loaded tiles, creating the particles if they do not
+    /// exist yet.
+    fn update_field(
+        &self,
+        state: &mut ParticleState,
+        indices: Vec<TileIndex>,
+        view: &MapView,
+        canvas: &dyn Canvas,
+    ) {
+        let Some(projection) = view.crs().get_projection::<GeoPoint2d, Point2d>() else {
+            return;
+        };
+
+        let tiles: Vec<_> = indices
+            .iter()
+            .filter_map(|index| Some((*index, self.tiles.get(index)?)))
+            .collect();
+        let images: Vec<_> = tiles
+            .iter()
+            .filter_map(|(index, tile)| match tile.as_ref() {
+                WindTile::Loaded(image) => Some((*index, image)),
+                _ => None,
+            })
+            .collect();
+        let Some(field) = build_field(&self.tile_schema, &images, self.encoding, &*projection)
+        else {
+            return;
+        };
+
+        match &mut state.particles {
+            Some(particles) => canvas.update_particle_field(&mut **particles, &field),
+            None => {
+                state.particles = canvas.create_particles(
+                    self.style.particle_count as u32,
+                    self.style.trail_length as u32,
+                    &field,
+                );
+                if state.particles.is_none() {
+                    log::warn!(
+                        "Renderer does not support particle animation, wind layer is not drawn."
+                    );
+                }
+            }
+        }
+
+        state.field_tiles = indices;
+        state.field_bbox = field.bbox();
+        state.max_magnitude = field.max_magnitude();
+    }
+
+    /// Returns the loaded tiles out of the given ones, or `None` if some of them are still loading. Building the field
+    /// reprojects every cell of it, so it is rebuilt once all the tiles of the view are available instead of every
+    /// time one of them is loaded.
+    fn field_tiles(&self, indices: impl Iterator<Item = TileIndex>) -> Option<Vec<TileIndex>> {
+        let mut loaded = vec![];
+        for index in indices {
+            match self.tiles.get(&index).as_deref() {
+                Some(WindTile::Loaded(_)) => loaded.push(index),
+                Some(WindTile::Error) => {}
+                Some(WindTile::Loading) | None => return None,
+            }
+        }
+
+        Some(loaded)
+    }
+
+    /// Time the particles should move by in this frame.
+    fn time_step(&self, state: &mut ParticleState, now: SystemTime) -> f32 {
+        let step = state
+            .last_frame
+            .and_then(|last| now.duration_since(last).ok())
+            .unwrap_or_default()
+            .min(MAX_FRAME_STEP);
+        state.last_frame = Some(now);
+
+        (step.as_secs_f64() * self.style.speed_factor) as f32
+    }
+}
+
+impl<Provider> WindLayer<Provider>
+where
+    Provider: DataProvider<TileIndex, DecodedImage, ()> + MaybeSync + MaybeSend + 'static,
+{
+    fn spawn_load(&self, indices: Vec<TileIndex>) {
+        self.tile_provider.read_ahead(&indices);
+
+        for index in indices {
+            let tile_provider = self.tile_provider.clone();
+            let tiles = self.tiles.clone();
+            let messenger = self.messenger.clone();
+            let cancellation = self.cancellation.token().clone();
+            crate::async_runtime::spawn(async move {
+                Self::load_tile(index, tile_provider, &tiles, messenger, &cancellation).await;
+            });
+        }
+    }
+}
+
+impl<Provider> Layer for WindLayer<Provider>
+where
+    Provider: DataProvider<TileIndex, DecodedImage, ()> + MaybeSync + MaybeSend + 'static,
+{
+    fn render(&self, view: &MapView, canvas: &mut dyn Canvas) {
+        let Some(tile_iter) = self.tile_schema.iter_tiles(view) else {
+            return;
+        };
+        let field_tiles = self.field_tiles(tile_iter);
+
+        let mut state = self.state.lock();
+        if let Some(loaded) = field_tiles {
+            if !loaded.is_empty() && loaded != state.field_tiles {
+                self.update_field(&mut state, loaded, view, canvas);
+            }
+        }
+
+        let time_step = self.time_step(&mut state, SystemTime::now());
+        let field_bbox = state.field_bbox;
+        let area = match view.get_bbox() {
+            Some(bbox) => bbox.limit(field_bbox),
+            // Part of the view is above the horizon.
+            None => field_bbox,
+        };
+        if area.width() <= 0.0 || area.height() <= 0.0 {
+            // The field is outside of the view.
+            return;
+        }
+
+        let frame = ParticleFrame {
+            area,
+            time_step,
+            max_age: self.style.max_age,
+            line_width: self.style.line_width as f32,
+            color: self.style.color,
+            slow_color: self.style.slow_color,
+            max_magnitude: state.max_magnitude,
+        };
+        let Some(particles) = &mut state.particles else {
+            return;
+        };
+
+        canvas.draw_particles(&mut **particles, &frame, RenderOptions::default());
+
+        if let Some(messenger) = &self.messenger {
+            messenger.request_redraw();
+        }
+    }
+
+    fn prepare(&self, view: &MapView) {
+        if let Some(iter) = self.tile_schema.iter_tiles(view) {
+            let indices: Vec<_> = iter
+                .filter(|index| !self.tiles.contains_key(index))
+                .collect();
+            self.spawn_load(indices);
+        }
+    }
+
+    fn set_messenger(&mut self, messenger: Box<dyn Messenger>) {
+        self.messenger = Some(Arc::from(messenger));
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn memory_usage(&self) -> LayerMemoryUsage {
+        let cached_tiles = self.tiles.len();
+        let tile_size =
+            self.tile_schema.tile_width() as usize * self.tile_schema.tile_height() as usize * 4;
+        let state = self.state.lock();
+
+        LayerMemoryUsage {
+            cpu_cache: tile_size * cached_tiles,
+            gpu: state
+                .particles
+                .as_ref()
+                .map_or(0, |particles| particles.gpu_size()),
+            cached_tiles,
+        }
+    }
+
+    fn trim_memory(&self) {
+        self.tiles.clear();
+    }
+
+    fn invalidate_gpu_resources(&self) {
+        *self.state.lock() = ParticleState::new();
+    }
+}
+
+/// Joins the tiles (all of the same level) into one velocity field in map coordinates. Returns `None` if there are no
+/// tiles.
+///
+/// Velocities on the ground are converted into map units by projecting the displacement of the center of every cell
+/// of the field, so the particles move with the right speed in any projection.
+fn build_field(
+    tile_schema: &TileSchema,
+    tiles: &[(TileIndex, &DecodedImage)],
+    encoding: VelocityEncoding,
+    projection: &dyn Projection<InPoint = GeoPoint2d, OutPoint = Point2d>,
+) -> Option<ParticleField> {
+    let bboxes: Vec<_> = tiles
+        .iter()
+        .filter_map(|(index, image)| Some((tile_schema.tile_bbox(*index)?, *image)))
+        .collect();
+    let bbox = bboxes
+        .iter()
+        .map(|(bbox, _)| *bbox)
+        .reduce(|a, b| a.merge(b))?;
+    let (tile_bbox, _) = bboxes.first()?;
+
+    let tile_width = tile_schema.tile_width();
+    let tile_height = tile_schema.tile_height();
+    let width = (bbox.width() / tile_bbox.width()).round() as u32 * tile_width;
+    let height = (bbox.height() / tile_bbox.height()).round() as u32 * tile_height;
+    let cell_width = bbox.width() / width as f64;
+    let cell_height = bbox.height() / height as f64;
+
+    let mut cells = vec![None; width as usize * height as usize];
+    for (tile_bbox, image) in &bboxes {
+        let column = ((tile_bbox.x_min() - bbox.x_min()) / cell_width).round() as u32;
+        let row = ((bbox.y_max() - tile_bbox.y_max()) / cell_height).round() as u32;
+
+        for y in 0..tile_height {
+            for x in 0..tile_width {
+                let pixel_x = (x * image.width() / tile_width) as usize;
+                let pixel_y = (y * image.height() / tile_height) as usize;
+                let offset = (pixel_y * image.width() as usize + pixel_x) * 4;
+                let Some(pixel) = image.bytes().get(offset..offset + 4) else {
+                    continue;
+                };
+                let Some((u, v)) = encoding.decode([pixel[0], pixel[1], pixel[2], pixel[3]]) else {
+                    continue;
+                };
+
+                let (column, row) = (column + x, row + y);
+                let center = Point2d::new(
+                    bbox.x_min() + (column as f64 + 0.5) * cell_width,
+                    bbox.y_max() - (row as f64 + 0.5) * cell_height,
+                );
+                cells[(row * width + column) as usize] = map_velocity(projection, center, u, v);
+            }
+        }
+    }
+
+    ParticleField::new(bbox, width, height, cells).ok()
+}
+
+/// Converts `(u, v)` velocity in meters per second at the given point into map units per second.
+fn map_velocity(
+    projection: &dyn Projection<InPoint = GeoPoint2d, OutPoint = Point2d>,
+    point: Point2d,
+    u: f32,
+    v: f32,
+) -> Option<FieldVelocity> {
+    let position = projection.unproject(&point)?;
+    let meters_per_lon_degree = METERS_PER_DEGREE * position.lat().to_radians().cos().max(0.01);
+    let moved = projection.project(&GeoPoint2d::latlon(
+        position.lat() + v as f64 / METERS_PER_DEGREE,
+        position.lon() + u as f64 / meters_per_lon_degree,
+    ))?;
+
+    Some(FieldVelocity {
+        x: (moved.x() - point.x()) as f32,
+        y: (moved.y() - point.y()) as f32,
+        magnitude: (u * u + v * v).sqrt(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layer::data_provider::UrlImageProvider;
+    use galileo_types::geo::Crs;
+
+    const ENCODING: VelocityEncoding = VelocityEncoding {
+        u_range: (0.0, 25.5),
+        v_range: (0.0, 25.5),
+    };
+
+    #[test]
+    fn encoding_skips_pixels_without_data() {
+        assert_eq!(ENCODING.decode([100, 0, 0, 255]), Some((10.0, 0.0)));
+        assert_eq!(ENCODING.decode([100, 0, 0, 0]), None);
+    }
+
+    #[test]
+    fn field_waits_for_all_tiles() {
+        let layer = WindLayer::new(
+            TileSchema::web(2),
+            UrlImageProvider::new(|_: &TileIndex| None::<String>),
+            ENCODING,
+            WindStyle::default(),
+        );
+        let image =
+            || WindTile::Loaded(DecodedImage::from_raw(vec![0; 4], 1, 1).expect("valid image"));
+        let indices = [
+            TileIndex::new(0, 0, 1),
+            TileIndex::new(1, 0, 1),
+            TileIndex::new(0, 1, 1),
+        ];
+
+        layer.tiles.insert(indices[0], Arc::new(image()));
+        layer.tiles.insert(indices[1], Arc::new(WindTile::Loading));
+        assert_eq!(layer.field_tiles(indices.into_iter()), None);
+
+        layer.tiles.insert(indices[1], Arc::new(WindTile::Error));
+        assert_eq!(layer.field_tiles(indices.into_iter()), None);
+
+        layer.tiles.insert(indices[2], Arc::new(image()));
+        assert_eq!(
+            layer.field_tiles(indices.into_iter()),
+            Some(vec![indices[0], indices[2]])
+        );
+    }
+
+    #[test]
+    fn field_is_built_in_map_units() {
+        let schema = TileSchema::web(2);
+        // Top row has no data, bottom row has 10 m/s eastward wind.
+        let image = DecodedImage::from_raw(
+            vec![0, 0, 0, 0, 0, 0, 0, 0, 100, 0, 0, 255, 100, 0, 0, 255],
+            2,
+            2,
+        )
+        .expect("valid image");
+        let projection = Crs::EPSG3857
+            .get_projection::<GeoPoint2d, Point2d>()
+            .expect("projection exists");
+
+        let field = build_field(
+            &schema,
+            &[(TileIndex::new(0, 0, 0), &image)],
+            ENCODING,
+            &*projection,
+        )
+        .expect("field is built");
+
+        assert_eq!(
+            Some(field.bbox()),
+            schema.tile_bbox(TileIndex::new(0, 0, 0))
+        );
+        assert_eq!((field.width(), field.height()), (256, 256));
+        assert!(field.cells()[0].is_none());
+
+        // Mercator projection stretches distances by `1 / cos(lat)`.
+        let (column, row) = (64, 192);
+        let velocity = field.cells()[row * 256 + column].expect("cell has data");
+        let center = Point2d::new(
+            field.bbox().x_min() + (column as f64 + 0.5) * field.bbox().width() / 256.0,
+            field.bbox().y_max() - (row as f64 + 0.5) * field.bbox().height() / 256.0,
+        );
+        let lat = projection.unproject(&center).expect("valid point").lat();
+        assert!((velocity.x as f64 * lat.to_radians().cos() - 10.0).abs() < 0.01);
+        assert!(velocity.y.abs() < 1e-3);
+        assert!((field.max_magnitude() - 10.0).abs() < 1e-5);
+    }
+}
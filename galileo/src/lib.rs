@@ -68,17 +68,35 @@
 #![warn(clippy::unwrap_used)]
 #![warn(missing_docs)]
 
+pub mod accessibility;
+pub mod alloc_audit;
+pub mod animation;
 pub(crate) mod async_runtime;
+#[cfg(feature = "bench")]
+pub mod bench;
+mod cancellation;
+#[cfg(all(feature = "capture", not(target_arch = "wasm32")))]
+pub mod capture;
 mod color;
 pub mod control;
 pub mod decoded_image;
+pub mod dem;
 pub mod error;
+#[cfg(all(feature = "ffi", not(target_arch = "wasm32")))]
+pub mod ffi;
 pub mod layer;
+pub mod localization;
+pub mod location;
 mod lod;
 mod map;
+#[cfg(all(feature = "mbtiles", not(target_arch = "wasm32")))]
+pub mod mbtiles;
 mod messenger;
 pub mod platform;
 pub mod render;
+#[cfg(feature = "satellite")]
+pub mod satellite;
+pub mod storage;
 pub mod tile_scheme;
 mod view;
 
@@ -90,13 +108,18 @@ mod galileo_map;
 #[cfg(all(feature = "winit", feature = "wgpu"))]
 pub use galileo_map::{GalileoMap, MapBuilder};
 
+pub use cancellation::{CancelOnDrop, CancellationToken, WaitForCancellation};
 pub use color::Color;
 pub use layer::feature_layer::symbol;
 pub use lod::Lod;
-pub use map::{LayerCollection, Map};
-pub use messenger::{DummyMessenger, Messenger};
+pub use map::{
+    FrameBudget, FrameGovernor, FrameInfo, LayerCollection, LayerId, LayerMemoryReport, Map,
+    MapHashState, MapSessionState, MemoryReport, RenderHookId, RenderQuality, ScaleBar,
+    TileManifest, ViewLink, ViewSync,
+};
+pub use messenger::{DummyMessenger, LayerMessenger, Messenger};
 pub use tile_scheme::TileSchema;
-pub use view::MapView;
+pub use view::{MapView, ViewPadding};
 
 // Reexport galileo_types
 pub use galileo_types;
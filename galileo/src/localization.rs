@@ -0,0 +1,300 @@
+//! Locale-aware formatting of numbers and measurements shown on the map.
+//!
+//! Built-in overlays (e.g. the [`ScaleBar`](crate::ScaleBar)) do not format numbers themselves, but ask the
+//! [`Localizer`] of the [`Map`](crate::Map) to do it. The default localizer is [`Locale::default`], which uses metric
+//! units and a dot as the decimal separator. Applications can set a different [`Locale`] with
+//! [`Map::set_localizer`](crate::Map::set_localizer), or implement the [`Localizer`] trait to use the formatting
+//! facilities of their UI framework.
+//!
+//! The [`LayoutDirection`] of the map tells overlays and applications whether the interface around the map should be
+//! mirrored for right-to-left languages.
+
+use crate::render::text::TextDirection;
+use maybe_sync::{MaybeSend, MaybeSync};
+
+/// Number of meters in an international mile.
+const METERS_IN_MILE: f64 = 1609.344;
+/// Number of meters in a foot.
+const METERS_IN_FOOT: f64 = 0.3048;
+/// Number of meters in a nautical mile.
+const METERS_IN_NAUTICAL_MILE: f64 = 1852.0;
+
+/// System of units used to display distances and areas.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum UnitSystem {
+    /// Meters and kilometers.
+    #[default]
+    Metric,
+    /// Feet and miles.
+    Imperial,
+    /// Nautical miles.
+    Nautical,
+}
+
+impl UnitSystem {
+    /// Returns the unit a distance should be displayed in as the number of meters in the unit and the unit symbol.
+    pub fn distance_unit(&self, meters: f64) -> (f64, &'static str) {
+        match self {
+            UnitSystem::Metric if meters.abs() < 1000.0 => (1.0, "m"),
+            UnitSystem::Metric => (1000.0, "km"),
+            UnitSystem::Imperial if meters.abs() < 0.1 * METERS_IN_MILE => (METERS_IN_FOOT, "ft"),
+            UnitSystem::Imperial => (METERS_IN_MILE, "mi"),
+            UnitSystem::Nautical => (METERS_IN_NAUTICAL_MILE, "NM"),
+        }
+    }
+
+    /// Returns the unit an area should be displayed in as the number of square meters in the unit and the unit
+    /// symbol.
+    pub fn area_unit(&self, square_meters: f64) -> (f64, &'static str) {
+        match self {
+            UnitSystem::Metric if square_meters.abs() < 1e6 => (1.0, "m²"),
+            UnitSystem::Metric => (1e6, "km²"),
+            UnitSystem::Imperial
+                if square_meters.abs() < 0.01 * METERS_IN_MILE * METERS_IN_MILE =>
+            {
+                (METERS_IN_FOOT * METERS_IN_FOOT, "ft²")
+            }
+            UnitSystem::Imperial => (METERS_IN_MILE * METERS_IN_MILE, "mi²"),
+            UnitSystem::Nautical => (METERS_IN_NAUTICAL_MILE * METERS_IN_NAUTICAL_MILE, "NM²"),
+        }
+    }
+}
+
+/// Formats numbers and measurements for display on the map.
+pub trait Localizer: MaybeSend + MaybeSync {
+    /// Units the measurements are displayed in.
+    fn unit_system(&self) -> UnitSystem;
+
+    /// Formats a number with at most `max_decimals` fractional digits. Trailing zeros of the fractional part are
+    /// omitted.
+    fn format_number(&self, value: f64, max_decimals: usize) -> String;
+
+    /// Formats a distance given in meters, e.g. `1.5 km` or `500 ft`.
+    fn format_distance(&self, meters: f64) -> String {
+        let (scale, unit) = self.unit_system().distance_unit(meters);
+        let value = meters / scale;
+        format!("{} {unit}", self.format_number(value, decimals_for(value)))
+    }
+
+    /// Formats an area given in square meters, e.g. `2.4 km²`.
+    fn format_area(&self, square_meters: f64) -> String {
+        let (scale, unit) = self.unit_system().area_unit(square_meters);
+        let value = square_meters / scale;
+        format!("{} {unit}", self.format_number(value, decimals_for(value)))
+    }
+}
+
+/// Number of fractional digits to show for a measurement value, so that it has about 2-3 significant digits.
+fn decimals_for(value: f64) -> usize {
+    match value.abs() {
+        v if v < 1.0 => 2,
+        v if v < 10.0 => 1,
+        _ => 0,
+    }
+}
+
+/// Localizer with configurable unit system and number separators.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Locale {
+    /// Units the measurements are displayed in.
+    pub unit_system: UnitSystem,
+    /// Character that separates the fractional part of a number.
+    pub decimal_separator: char,
+    /// Character that separates groups of thousands. `None` if the digits are not grouped.
+    pub group_separator: Option<char>,
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Self {
+            unit_system: UnitSystem::Metric,
+            decimal_separator: '.',
+            group_separator: None,
+        }
+    }
+}
+
+impl Locale {
+    /// Creates a locale with the given unit system, dot as the decimal separator and no digit grouping.
+    pub fn new(unit_system: UnitSystem) -> Self {
+        Self {
+            unit_system,
+            ..Default::default()
+        }
+    }
+
+    /// Returns a copy of the locale with the given number separators.
+    pub fn with_separators(&self, decimal_separator: char, group_separator: Option<char>) -> Self {
+        Self {
+            decimal_separator,
+            group_separator,
+            ..*self
+        }
+    }
+
+    /// Returns a copy of the locale with the given unit system.
+    pub fn with_unit_system(&self, unit_system: UnitSystem) -> Self {
+        Self {
+            unit_system,
+            ..*self
+        }
+    }
+
+    /// Guesses the locale from a BCP 47 language tag, like `en-US` or `de`, e.g. the value of `navigator.language` in
+    /// a browser.
+    ///
+    /// Only the common conventions are distinguished: imperial units are used for the US, Liberia and Myanmar, and the
+    /// separators follow the language. Use [`Locale::with_separators`] and [`Locale::with_unit_system`] to adjust the
+    /// result.
+    pub fn from_language_tag(tag: &str) -> Self {
+        let mut parts = tag.split(['-', '_']);
+        let language = parts.next().unwrap_or_default().to_ascii_lowercase();
+        let region = parts
+            .find(|part| {
+                part.len() == 2 || (part.len() == 3 && part.chars().all(|c| c.is_ascii_digit()))
+            })
+            .map(|part| part.to_ascii_uppercase());
+
+        let unit_system = match region.as_deref() {
+            Some("US" | "LR" | "MM") => UnitSystem::Imperial,
+            _ => UnitSystem::Metric,
+        };
+
+        let (decimal_separator, group_separator) = match language.as_str() {
+            "en" | "ja" | "ko" | "zh" | "he" | "th" | "hi" => ('.', Some(',')),
+            "de" | "es" | "it" | "nl" | "pt" | "id" | "tr" | "da" | "el" => (',', Some('.')),
+            "fr" | "ru" | "uk" | "pl" | "cs" | "sv" | "fi" | "nb" | "no" | "hu" | "sk" => {
+                (',', Some('\u{202F}'))
+            }
+            "ar" | "fa" => ('٫', Some('٬')),
+            _ => ('.', None),
+        };
+
+        Self {
+            unit_system,
+            decimal_separator,
+            group_separator,
+        }
+    }
+}
+
+/// Direction of the user interface around the map.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum LayoutDirection {
+    /// Left-to-right layout.
+    #[default]
+    LeftToRight,
+    /// Right-to-left layout. Controls are placed mirrored relative to the left-to-right layout.
+    RightToLeft,
+}
+
+impl LayoutDirection {
+    /// Returns the layout direction used for the language of the BCP 47 language tag, like `ar-EG` or `en`.
+    pub fn from_language_tag(tag: &str) -> Self {
+        let language = tag.split(['-', '_']).next().unwrap_or_default();
+        match language.to_ascii_lowercase().as_str() {
+            "ar" | "he" | "iw" | "fa" | "ur" | "ps" | "yi" | "dv" | "ckb" | "sd" | "ug" => {
+                Self::RightToLeft
+            }
+            _ => Self::LeftToRight,
+        }
+    }
+
+    /// Returns true for the right-to-left layout.
+    pub fn is_rtl(&self) -> bool {
+        *self == Self::RightToLeft
+    }
+
+    /// Base direction of the texts in this layout.
+    pub fn text_direction(&self) -> TextDirection {
+        match self {
+            Self::LeftToRight => TextDirection::LeftToRight,
+            Self::RightToLeft => TextDirection::RightToLeft,
+        }
+    }
+
+    /// Returns the horizontal screen position of an element of `width` pixels that is placed at `x` pixels from the
+    /// left edge of the map of `map_width` pixels in the left-to-right layout. In the right-to-left layout the
+    /// element is placed at the same distance from the right edge.
+    pub fn place_x(&self, x: f64, width: f64, map_width: f64) -> f64 {
+        match self {
+            Self::LeftToRight => x,
+            Self::RightToLeft => map_width - x - width,
+        }
+    }
+}
+
+impl Localizer for Locale {
+    fn unit_system(&self) -> UnitSystem {
+        self.unit_system
+    }
+
+    fn format_number(&self, value: f64, max_decimals: usize) -> String {
+        let formatted = format!("{:.max_decimals$}", value.abs());
+        let (integer, fraction) = formatted.split_once('.').unwrap_or((&formatted, ""));
+        let fraction = fraction.trim_end_matches('0');
+
+        let mut result = String::with_capacity(formatted.len() + 4);
+        if value < 0.0 && formatted.chars().any(|c| c != '0' && c != '.') {
+            result.push('-');
+        }
+
+        for (index, digit) in integer.chars().enumerate() {
+            if index > 0 && (integer.len() - index) % 3 == 0 {
+                if let Some(separator) = self.group_separator {
+                    result.push(separator);
+                }
+            }
+            result.push(digit);
+        }
+
+        if !fraction.is_empty() {
+            result.push(self.decimal_separator);
+            result.push_str(fraction);
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_numbers() {
+        let de = Locale::from_language_tag("de-DE");
+        assert_eq!(de.format_number(1234.5, 2), "1.234,5");
+        assert_eq!(de.format_number(-1234567.0, 0), "-1.234.567");
+        assert_eq!(de.format_number(-0.001, 2), "0");
+
+        assert_eq!(Locale::default().format_number(12345.678, 1), "12345.7");
+    }
+
+    #[test]
+    fn formats_measurements_in_unit_system() {
+        let us = Locale::from_language_tag("en-US");
+        assert_eq!(us.unit_system, UnitSystem::Imperial);
+        assert_eq!(us.format_distance(100.0), "328 ft");
+        assert_eq!(us.format_distance(2000.0), "1.2 mi");
+
+        let gb = Locale::from_language_tag("en-GB");
+        assert_eq!(gb.format_distance(850.0), "850 m");
+        assert_eq!(gb.format_distance(12_345.0), "12 km");
+        assert_eq!(gb.format_area(2_500_000.0), "2.5 km²");
+
+        let nautical = gb.with_unit_system(UnitSystem::Nautical);
+        assert_eq!(nautical.format_distance(926.0), "0.5 NM");
+    }
+
+    #[test]
+    fn rtl_layout_mirrors_placement() {
+        let direction = LayoutDirection::from_language_tag("ar-EG");
+        assert!(direction.is_rtl());
+        assert_eq!(direction.place_x(10.0, 100.0, 800.0), 690.0);
+        assert_eq!(
+            LayoutDirection::from_language_tag("en").place_x(10.0, 100.0, 800.0),
+            10.0
+        );
+    }
+}
@@ -0,0 +1,77 @@
+use crate::error::GalileoError;
+use crate::location::{LocationTracker, Position, PositionProvider};
+use galileo_types::geo::impls::GeoPoint2d;
+use galileo_types::geo::NewGeoPoint;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::JsCast;
+use web_sys::{GeolocationPosition, GeolocationPositionError, PositionOptions};
+
+/// Position provider that uses the [Geolocation API](https://developer.mozilla.org/en-US/docs/Web/API/Geolocation_API)
+/// of the browser.
+///
+/// The browser asks the user for the permission to share the location when the provider is started.
+#[derive(Debug, Clone, Default)]
+pub struct BrowserGeolocation {
+    high_accuracy: bool,
+}
+
+impl BrowserGeolocation {
+    /// Creates a new provider.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new instance from a copy of the current, but requesting the best possible accuracy from the device
+    /// (e.g. using GPS instead of network-based positioning).
+    pub fn with_high_accuracy(&self, high_accuracy: bool) -> Self {
+        Self { high_accuracy }
+    }
+}
+
+impl PositionProvider for BrowserGeolocation {
+    fn start(self, tracker: LocationTracker) -> Result<(), GalileoError> {
+        let geolocation = web_sys::window()
+            .ok_or_else(|| GalileoError::Wasm(Some("no window object".into())))?
+            .navigator()
+            .geolocation()?;
+
+        let on_error_tracker = tracker.clone();
+        let on_position =
+            Closure::<dyn FnMut(GeolocationPosition)>::new(move |position: GeolocationPosition| {
+                let coords = position.coords();
+                let mut result =
+                    Position::new(GeoPoint2d::latlon(coords.latitude(), coords.longitude()));
+                result.accuracy = Some(coords.accuracy());
+                result.heading = coords.heading().filter(|v| v.is_finite());
+                result.speed = coords.speed().filter(|v| v.is_finite());
+
+                tracker.set_position(result);
+            });
+        let on_error = Closure::<dyn FnMut(GeolocationPositionError)>::new(
+            move |error: GeolocationPositionError| {
+                log::warn!(
+                    "Failed to get position from the browser: {}",
+                    error.message()
+                );
+                if error.code() == GeolocationPositionError::PERMISSION_DENIED {
+                    on_error_tracker.clear_position();
+                }
+            },
+        );
+
+        let options = PositionOptions::new();
+        options.set_enable_high_accuracy(self.high_accuracy);
+
+        geolocation.watch_position_with_error_callback_and_options(
+            on_position.as_ref().unchecked_ref(),
+            Some(on_error.as_ref().unchecked_ref()),
+            &options,
+        )?;
+
+        // The callbacks are called for as long as the page lives.
+        on_position.forget();
+        on_error.forget();
+
+        Ok(())
+    }
+}
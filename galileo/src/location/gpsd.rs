@@ -0,0 +1,118 @@
+use crate::error::GalileoError;
+use crate::location::{LocationTracker, Position, PositionProvider};
+use galileo_types::geo::impls::GeoPoint2d;
+use galileo_types::geo::NewGeoPoint;
+use serde::Deserialize;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpStream;
+
+/// Default address of the `gpsd` daemon.
+pub const GPSD_DEFAULT_ADDRESS: &str = "127.0.0.1:2947";
+
+const WATCH_COMMAND: &[u8] = b"?WATCH={\"enable\":true,\"json\":true};\n";
+
+/// Position provider that receives positions from a [gpsd](https://gpsd.io) daemon over TCP.
+#[derive(Debug, Clone)]
+pub struct GpsdProvider {
+    address: String,
+}
+
+impl Default for GpsdProvider {
+    fn default() -> Self {
+        Self::new(GPSD_DEFAULT_ADDRESS)
+    }
+}
+
+impl GpsdProvider {
+    /// Creates a new provider connecting to the daemon at the given address (`host:port`).
+    pub fn new(address: impl Into<String>) -> Self {
+        Self {
+            address: address.into(),
+        }
+    }
+
+    /// Address of the daemon.
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+}
+
+impl PositionProvider for GpsdProvider {
+    fn start(self, tracker: LocationTracker) -> Result<(), GalileoError> {
+        let mut stream = TcpStream::connect(&self.address)?;
+        stream.write_all(WATCH_COMMAND)?;
+
+        std::thread::Builder::new()
+            .name("gpsd-position-provider".into())
+            .spawn(move || {
+                for line in BufReader::new(stream).lines() {
+                    match line {
+                        Ok(line) => {
+                            if let Some(position) = parse_report(&line) {
+                                tracker.set_position(position);
+                            }
+                        }
+                        Err(err) => {
+                            log::warn!("Connection to gpsd is lost: {err}");
+                            break;
+                        }
+                    }
+                }
+            })?;
+
+        Ok(())
+    }
+}
+
+/// Time-position-velocity report of gpsd. Only the fields used by the provider are listed.
+#[derive(Debug, Deserialize)]
+struct TpvReport {
+    class: String,
+    lat: Option<f64>,
+    lon: Option<f64>,
+    track: Option<f64>,
+    speed: Option<f64>,
+    eph: Option<f64>,
+    epx: Option<f64>,
+    epy: Option<f64>,
+}
+
+/// Parses a JSON report line. Returns a position for `TPV` reports with a fix.
+fn parse_report(line: &str) -> Option<Position> {
+    let report: TpvReport = serde_json::from_str(line).ok()?;
+    if report.class != "TPV" {
+        return None;
+    }
+
+    let mut position = Position::new(GeoPoint2d::latlon(report.lat?, report.lon?));
+    position.heading = report.track;
+    position.speed = report.speed;
+    position.accuracy = report.eph.or_else(|| match (report.epx, report.epy) {
+        (Some(epx), Some(epy)) => Some((epx * epx + epy * epy).sqrt()),
+        _ => None,
+    });
+
+    Some(position)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use galileo_types::geo::GeoPoint;
+
+    #[test]
+    fn parse_tpv() {
+        let position = parse_report(
+            r#"{"class":"TPV","mode":3,"lat":46.498,"lon":7.568,"track":10.5,"speed":1.2,"epx":3.0,"epy":4.0}"#,
+        )
+        .unwrap();
+
+        assert_eq!(position.location.lat(), 46.498);
+        assert_eq!(position.location.lon(), 7.568);
+        assert_eq!(position.heading, Some(10.5));
+        assert_eq!(position.accuracy, Some(5.0));
+
+        assert!(parse_report(r#"{"class":"TPV","mode":1}"#).is_none());
+        assert!(parse_report(r#"{"class":"SKY","satellites":[]}"#).is_none());
+    }
+}
@@ -0,0 +1,200 @@
+use crate::layer::feature_layer::symbol::{geodesic_ellipse, GeodesicEllipse};
+use crate::layer::Layer;
+use crate::location::{LocationTracker, Position};
+use crate::messenger::Messenger;
+use crate::render::point_paint::PointPaint;
+use crate::render::render_bundle::RenderPrimitive;
+use crate::render::{Canvas, PolygonPaint, RenderOptions};
+use crate::view::MapView;
+use crate::Color;
+use galileo_types::cartesian::{Point2d, Point3d};
+use galileo_types::geo::impls::GeoPoint2d;
+use galileo_types::impls::{ClosedContour, Contour, Polygon};
+use std::any::Any;
+use std::f64::consts::{FRAC_PI_2, PI};
+
+/// Number of vertices in the accuracy circle.
+const ACCURACY_CIRCLE_SEGMENTS: usize = 64;
+
+/// Style of the [`LocationLayer`].
+#[derive(Debug, Clone)]
+pub struct LocationStyle {
+    /// Fill color of the accuracy circle.
+    pub accuracy_fill: Color,
+    /// Color of the position dot.
+    pub dot_color: Color,
+    /// Diameter of the position dot in pixels.
+    pub dot_size: f32,
+    /// Color of the outline of the position dot.
+    pub outline_color: Color,
+    /// Width of the outline of the position dot in pixels.
+    pub outline_width: f32,
+    /// Color of the heading sector.
+    pub heading_color: Color,
+    /// Diameter of the heading sector in pixels.
+    pub heading_size: f32,
+}
+
+impl Default for LocationStyle {
+    fn default() -> Self {
+        Self {
+            accuracy_fill: Color::rgba(30, 136, 229, 50),
+            dot_color: Color::rgba(30, 136, 229, 255),
+            dot_size: 14.0,
+            outline_color: Color::WHITE,
+            outline_width: 2.0,
+            heading_color: Color::rgba(30, 136, 229, 120),
+            heading_size: 48.0,
+        }
+    }
+}
+
+/// Layer that displays the position of a [`LocationTracker`]: a dot at the location, a circle showing the accuracy
+/// of the position, and a sector pointing in the direction of movement.
+///
+/// The layer is rendered anew every frame, so it always shows the last known position of the tracker.
+pub struct LocationLayer {
+    tracker: LocationTracker,
+    style: LocationStyle,
+}
+
+impl LocationLayer {
+    /// Creates a new layer.
+    pub fn new(tracker: LocationTracker, style: LocationStyle) -> Self {
+        Self { tracker, style }
+    }
+
+    /// Tracker the layer displays.
+    pub fn tracker(&self) -> &LocationTracker {
+        &self.tracker
+    }
+
+    /// Style of the layer.
+    pub fn style(&self) -> &LocationStyle {
+        &self.style
+    }
+
+    /// Sets the style of the layer.
+    pub fn set_style(&mut self, style: LocationStyle) {
+        self.style = style;
+    }
+
+    fn accuracy_circle(
+        position: &Position,
+        project: impl Fn(&GeoPoint2d) -> Option<Point2d>,
+    ) -> Option<Polygon<Point3d>> {
+        let accuracy = position.accuracy.filter(|v| *v > 0.0)?;
+        let points = geodesic_circle(&position.location, accuracy)
+            .iter()
+            .map(|p| project(p).map(|p| Point3d::new(p.x, p.y, 0.0)))
+            .collect::<Option<Vec<_>>>()?;
+
+        Some(Polygon::new(ClosedContour::new(points), vec![]))
+    }
+}
+
+/// Returns points of a circle with the given radius (in meters) around the center on the surface of the Earth.
+fn geodesic_circle(center: &GeoPoint2d, radius: f64) -> Vec<GeoPoint2d> {
+    geodesic_ellipse(
+        center,
+        &GeodesicEllipse::circle(radius),
+        ACCURACY_CIRCLE_SEGMENTS,
+    )
+}
+
+impl Layer for LocationLayer {
+    fn render(&self, view: &MapView, canvas: &mut dyn Canvas) {
+        let Some(position) = self.tracker.position() else {
+            return;
+        };
+        let Some(projection) = view.crs().get_projection::<GeoPoint2d, Point2d>() else {
+            return;
+        };
+        let Some(center) = projection.project(&position.location) else {
+            return;
+        };
+        let center = Point3d::new(center.x, center.y, 0.0);
+
+        let mut bundle = canvas.create_bundle();
+
+        if let Some(circle) = Self::accuracy_circle(&position, |p| projection.project(p)) {
+            bundle.add(
+                RenderPrimitive::<_, _, Contour<Point3d>, _>::new_polygon(
+                    circle,
+                    PolygonPaint {
+                        color: self.style.accuracy_fill,
+                    },
+                ),
+                0.0,
+            );
+        }
+
+        if let Some(heading) = position.heading {
+            // Sector angles are counted counterclockwise from the screen x-axis, while heading is counted clockwise
+            // from the north.
+            let direction = (FRAC_PI_2 + view.rotation_z() - heading.to_radians()) as f32;
+            let half_width = (PI / 6.0) as f32;
+            bundle.add(
+                RenderPrimitive::<_, _, Contour<Point3d>, Polygon<Point3d>>::new_point(
+                    center,
+                    PointPaint::sector(
+                        self.style.heading_color,
+                        self.style.heading_size,
+                        direction - half_width,
+                        direction + half_width,
+                    ),
+                ),
+                0.0,
+            );
+        }
+
+        bundle.add(
+            RenderPrimitive::<_, _, Contour<Point3d>, Polygon<Point3d>>::new_point(
+                center,
+                PointPaint::circle(self.style.dot_color, self.style.dot_size)
+                    .with_outline(self.style.outline_color, self.style.outline_width),
+            ),
+            0.0,
+        );
+
+        let packed = canvas.pack_bundle(&bundle);
+        canvas.draw_bundles(&[&*packed], RenderOptions::default());
+    }
+
+    fn prepare(&self, _view: &MapView) {
+        // do nothing
+    }
+
+    fn set_messenger(&mut self, messenger: Box<dyn Messenger>) {
+        self.tracker.set_messenger(Some(messenger));
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use galileo_types::geo::GeoPoint;
+    use galileo_types::latlon;
+
+    #[test]
+    fn geodesic_circle_radius() {
+        let center = latlon!(45.0, 10.0);
+        let circle = geodesic_circle(&center, 1000.0);
+
+        assert_eq!(circle.len(), ACCURACY_CIRCLE_SEGMENTS);
+        // First point is to the north of the center: 1 km is ~0.009 degrees of latitude.
+        assert!((circle[0].lat() - center.lat() - 0.008_993).abs() < 1e-5);
+        assert!((circle[0].lon() - center.lon()).abs() < 1e-9);
+        // Quarter of the circle is to the east, and longitude degrees are shorter at 45 degrees of latitude.
+        let east = circle[ACCURACY_CIRCLE_SEGMENTS / 4];
+        assert!((east.lon() - center.lon() - 0.008_993 / 45f64.to_radians().cos()).abs() < 1e-5);
+    }
+}
@@ -0,0 +1,213 @@
+//! Tracking and displaying the position of the device.
+//!
+//! * [`PositionProvider`]s deliver positions from a positioning device: [`NmeaProvider`] reads NMEA 0183 sentences from
+//!   a serial port or any other stream, `GpsdProvider` connects to a `gpsd` daemon (`gpsd` feature), and
+//!   `BrowserGeolocation` uses the Geolocation API of the browser (on `wasm32` target).
+//! * [`LocationTracker`] stores the last known position. It is shared between the provider, the [`LocationLayer`]
+//!   that displays the position on the map, and the application.
+//! * [`FollowMode`] of the tracker specifies whether the map should follow the position.
+
+use crate::error::GalileoError;
+use crate::messenger::Messenger;
+use crate::Map;
+use galileo_types::geo::impls::GeoPoint2d;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use web_time::SystemTime;
+
+#[cfg(target_arch = "wasm32")]
+mod browser;
+#[cfg(all(feature = "gpsd", not(target_arch = "wasm32")))]
+mod gpsd;
+mod location_layer;
+mod nmea;
+
+#[cfg(target_arch = "wasm32")]
+pub use browser::BrowserGeolocation;
+#[cfg(all(feature = "gpsd", not(target_arch = "wasm32")))]
+pub use gpsd::{GpsdProvider, GPSD_DEFAULT_ADDRESS};
+pub use location_layer::{LocationLayer, LocationStyle};
+pub use nmea::{NmeaParser, NmeaProvider};
+
+/// Position of the device.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Position {
+    /// Geographic location.
+    pub location: GeoPoint2d,
+    /// Horizontal accuracy (radius of the uncertainty circle) in meters.
+    pub accuracy: Option<f64>,
+    /// Direction of movement in degrees clockwise from the true north.
+    pub heading: Option<f64>,
+    /// Speed over ground in meters per second.
+    pub speed: Option<f64>,
+    /// Time the position was determined at.
+    pub timestamp: SystemTime,
+}
+
+impl Position {
+    /// Creates a new position at the given location with the current timestamp and no additional information.
+    pub fn new(location: GeoPoint2d) -> Self {
+        Self {
+            location,
+            accuracy: None,
+            heading: None,
+            speed: None,
+            timestamp: SystemTime::now(),
+        }
+    }
+}
+
+/// Source of device positions.
+pub trait PositionProvider {
+    /// Starts delivering positions to the tracker.
+    ///
+    /// Providers that read from blocking sources start a background thread for this, so this method returns
+    /// immediately.
+    fn start(self, tracker: LocationTracker) -> Result<(), GalileoError>;
+}
+
+/// Specifies whether the map should follow the tracked position.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum FollowMode {
+    /// The map view is not changed by the tracker.
+    #[default]
+    Off,
+    /// The map is centered at the position.
+    Center,
+    /// The map is centered at the position and rotated, so that the heading of the device points up.
+    CenterAndRotate,
+}
+
+/// Stores the last known position of the device.
+///
+/// The tracker is cheaply cloneable, all the clones share the same state.
+#[derive(Clone, Default)]
+pub struct LocationTracker {
+    state: Arc<RwLock<TrackerState>>,
+}
+
+#[derive(Default)]
+struct TrackerState {
+    position: Option<Position>,
+    version: u64,
+    followed_version: u64,
+    follow_mode: FollowMode,
+    messenger: Option<Box<dyn Messenger>>,
+}
+
+impl LocationTracker {
+    /// Duration of the map animation when the map follows a new position.
+    const FOLLOW_ANIMATION_DURATION: Duration = Duration::from_millis(300);
+
+    /// Creates a new tracker without a position.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Last known position.
+    pub fn position(&self) -> Option<Position> {
+        self.state
+            .read()
+            .expect("lock is poisoned")
+            .position
+            .clone()
+    }
+
+    /// Updates the position and requests redraw of the map.
+    pub fn set_position(&self, position: Position) {
+        let mut state = self.state.write().expect("lock is poisoned");
+        state.position = Some(position);
+        state.version += 1;
+
+        if let Some(messenger) = &state.messenger {
+            messenger.request_redraw();
+        }
+    }
+
+    /// Forgets the last known position (e.g. when the signal is lost).
+    pub fn clear_position(&self) {
+        let mut state = self.state.write().expect("lock is poisoned");
+        state.position = None;
+        state.version += 1;
+
+        if let Some(messenger) = &state.messenger {
+            messenger.request_redraw();
+        }
+    }
+
+    /// Current follow mode.
+    pub fn follow_mode(&self) -> FollowMode {
+        self.state.read().expect("lock is poisoned").follow_mode
+    }
+
+    /// Sets the follow mode. The map is moved to the current position on the next call to
+    /// [`LocationTracker::update_map`].
+    pub fn set_follow_mode(&self, mode: FollowMode) {
+        let mut state = self.state.write().expect("lock is poisoned");
+        state.follow_mode = mode;
+        state.followed_version = 0;
+    }
+
+    /// Sets the messenger to notify when the position changes.
+    pub fn set_messenger(&self, messenger: Option<Box<dyn Messenger>>) {
+        self.state.write().expect("lock is poisoned").messenger = messenger;
+    }
+
+    /// Moves the map view to the position according to the follow mode, if the position changed since the last call.
+    ///
+    /// This method should be called by the application before every frame is rendered (before [`Map::animate`]).
+    pub fn update_map(&self, map: &mut Map) {
+        let mut state = self.state.write().expect("lock is poisoned");
+        if state.follow_mode == FollowMode::Off || state.followed_version == state.version {
+            return;
+        }
+
+        state.followed_version = state.version;
+        let Some(position) = &state.position else {
+            return;
+        };
+
+        let mut target = map.target_view().with_position(&position.location);
+        if state.follow_mode == FollowMode::CenterAndRotate {
+            if let Some(heading) = position.heading {
+                target = target.with_rotation_z(heading.to_radians());
+            }
+        }
+
+        map.animate_to(target, Self::FOLLOW_ANIMATION_DURATION);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::view::MapView;
+    use galileo_types::cartesian::Size;
+    use galileo_types::geo::GeoPoint;
+    use galileo_types::latlon;
+
+    #[test]
+    fn follow_mode_moves_map_once_per_position() {
+        let view = MapView::new(&latlon!(0.0, 0.0), 10.0).with_size(Size::new(100.0, 100.0));
+        let mut map = Map::new(view, vec![], None::<crate::DummyMessenger>);
+        let tracker = LocationTracker::new();
+
+        let mut position = Position::new(latlon!(10.0, 20.0));
+        position.heading = Some(90.0);
+        tracker.set_position(position);
+
+        tracker.update_map(&mut map);
+        assert_eq!(map.target_view().rotation_z(), 0.0);
+        assert!(
+            (map.target_view().position().unwrap().lat() - 0.0).abs() < 1e-9,
+            "map must not follow when the mode is off"
+        );
+
+        tracker.set_follow_mode(FollowMode::CenterAndRotate);
+        tracker.update_map(&mut map);
+        let target = map.target_view().position().unwrap();
+        assert!((target.lat() - 10.0).abs() < 1e-6);
+        assert!((target.lon() - 20.0).abs() < 1e-6);
+        assert!((map.target_view().rotation_z() - std::f64::consts::FRAC_PI_2).abs() < 1e-9);
+    }
+}
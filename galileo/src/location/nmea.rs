@@ -0,0 +1,240 @@
+use crate::error::GalileoError;
+use crate::location::{LocationTracker, Position, PositionProvider};
+use galileo_types::geo::impls::GeoPoint2d;
+use galileo_types::geo::NewGeoPoint;
+use std::io::BufRead;
+use web_time::SystemTime;
+
+const KNOTS_TO_MPS: f64 = 0.514444;
+
+/// Approximate error of a single satellite range measurement, used to estimate accuracy from HDOP, in meters.
+const USER_EQUIVALENT_RANGE_ERROR: f64 = 5.0;
+
+/// Parser of NMEA 0183 sentences.
+///
+/// The parser collects information from `GGA` (fix and HDOP), `RMC` (fix, speed and course) and `GST` (error
+/// estimation) sentences of any talker (`GP`, `GN`, `GL` etc). Other sentences are ignored.
+#[derive(Debug, Default, Clone)]
+pub struct NmeaParser {
+    accuracy: Option<f64>,
+    heading: Option<f64>,
+    speed: Option<f64>,
+}
+
+impl NmeaParser {
+    /// Creates a new parser.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses one sentence. Returns a position if the sentence contained a valid fix.
+    ///
+    /// Sentences with invalid checksums are ignored.
+    pub fn parse_sentence(&mut self, sentence: &str) -> Option<Position> {
+        let body = validate_checksum(sentence.trim())?;
+        let mut fields = body.split(',');
+        let sentence_type = fields.next()?.get(2..)?;
+        let fields: Vec<&str> = fields.collect();
+
+        match sentence_type {
+            "GGA" => self.parse_gga(&fields),
+            "RMC" => self.parse_rmc(&fields),
+            "GST" => {
+                self.parse_gst(&fields);
+                None
+            }
+            _ => None,
+        }
+    }
+
+    fn parse_gga(&mut self, fields: &[&str]) -> Option<Position> {
+        // hhmmss, lat, N/S, lon, E/W, quality, satellites, hdop, ...
+        let quality: u32 = fields.get(5)?.parse().ok()?;
+        if quality == 0 {
+            return None;
+        }
+
+        let location = parse_location(fields.get(1..5)?)?;
+        if self.accuracy.is_none() {
+            self.accuracy = fields
+                .get(7)
+                .and_then(|hdop| hdop.parse::<f64>().ok())
+                .map(|hdop| hdop * USER_EQUIVALENT_RANGE_ERROR);
+        }
+
+        Some(self.position(location))
+    }
+
+    fn parse_rmc(&mut self, fields: &[&str]) -> Option<Position> {
+        // hhmmss, status, lat, N/S, lon, E/W, speed (knots), course, ...
+        if *fields.get(1)? != "A" {
+            return None;
+        }
+
+        let location = parse_location(fields.get(2..6)?)?;
+        self.speed = fields
+            .get(6)
+            .and_then(|v| v.parse::<f64>().ok())
+            .map(|knots| knots * KNOTS_TO_MPS);
+        self.heading = fields.get(7).and_then(|v| v.parse::<f64>().ok());
+
+        Some(self.position(location))
+    }
+
+    fn parse_gst(&mut self, fields: &[&str]) {
+        // time, rms, major, minor, orientation, lat error, lon error, alt error
+        let lat_error = fields.get(5).and_then(|v| v.parse::<f64>().ok());
+        let lon_error = fields.get(6).and_then(|v| v.parse::<f64>().ok());
+        if let (Some(lat_error), Some(lon_error)) = (lat_error, lon_error) {
+            self.accuracy = Some((lat_error * lat_error + lon_error * lon_error).sqrt());
+        }
+    }
+
+    fn position(&self, location: GeoPoint2d) -> Position {
+        Position {
+            location,
+            accuracy: self.accuracy,
+            heading: self.heading,
+            speed: self.speed,
+            timestamp: SystemTime::now(),
+        }
+    }
+}
+
+/// Checks the `*hh` checksum of the sentence and returns the part between `$` and `*`.
+fn validate_checksum(sentence: &str) -> Option<&str> {
+    let sentence = sentence.strip_prefix('$')?;
+    let (body, checksum) = sentence.split_once('*')?;
+    let expected = u8::from_str_radix(checksum.get(..2)?, 16).ok()?;
+    let actual = body.bytes().fold(0u8, |acc, b| acc ^ b);
+
+    (expected == actual).then_some(body)
+}
+
+/// Parses `ddmm.mmmm, N/S, dddmm.mmmm, E/W` fields.
+fn parse_location(fields: &[&str]) -> Option<GeoPoint2d> {
+    let lat = parse_degrees(fields[0], 2)?;
+    let lat = match fields[1] {
+        "N" => lat,
+        "S" => -lat,
+        _ => return None,
+    };
+
+    let lon = parse_degrees(fields[2], 3)?;
+    let lon = match fields[3] {
+        "E" => lon,
+        "W" => -lon,
+        _ => return None,
+    };
+
+    Some(GeoPoint2d::latlon(lat, lon))
+}
+
+fn parse_degrees(value: &str, degree_digits: usize) -> Option<f64> {
+    let degrees: f64 = value.get(..degree_digits)?.parse().ok()?;
+    let minutes: f64 = value.get(degree_digits..)?.parse().ok()?;
+    Some(degrees + minutes / 60.0)
+}
+
+/// Position provider that reads NMEA 0183 sentences line by line from a stream, e.g. a serial port of a GPS receiver
+/// or a log file.
+pub struct NmeaProvider<R> {
+    reader: R,
+    parser: NmeaParser,
+}
+
+impl<R: BufRead> NmeaProvider<R> {
+    /// Creates a new provider reading from the given stream.
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            parser: NmeaParser::new(),
+        }
+    }
+
+    /// Blocks until the next position is read from the stream. Returns `None` when the stream ends.
+    pub fn next_position(&mut self) -> Option<Result<Position, GalileoError>> {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            match self.reader.read_line(&mut line) {
+                Ok(0) => return None,
+                Ok(_) => {
+                    if let Some(position) = self.parser.parse_sentence(&line) {
+                        return Some(Ok(position));
+                    }
+                }
+                Err(err) => return Some(Err(err.into())),
+            }
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<R: BufRead + Send + 'static> PositionProvider for NmeaProvider<R> {
+    fn start(mut self, tracker: LocationTracker) -> Result<(), GalileoError> {
+        std::thread::Builder::new()
+            .name("nmea-position-provider".into())
+            .spawn(move || {
+                while let Some(result) = self.next_position() {
+                    match result {
+                        Ok(position) => tracker.set_position(position),
+                        Err(err) => {
+                            log::warn!("Failed to read NMEA stream: {err}");
+                            break;
+                        }
+                    }
+                }
+            })?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use galileo_types::geo::GeoPoint;
+
+    #[test]
+    fn parse_rmc_and_gga() {
+        let mut parser = NmeaParser::new();
+
+        let position = parser
+            .parse_sentence("$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*47")
+            .unwrap();
+        assert!((position.location.lat() - 48.1173).abs() < 1e-9);
+        assert!((position.location.lon() - 11.516_666_666).abs() < 1e-6);
+        assert!((position.accuracy.unwrap() - 4.5).abs() < 1e-9);
+
+        let position = parser
+            .parse_sentence("$GPRMC,123519,A,4807.038,S,01131.000,W,022.4,084.4,230394,003.1,W*65")
+            .unwrap();
+        assert!((position.location.lat() + 48.1173).abs() < 1e-9);
+        assert_eq!(position.heading, Some(84.4));
+        assert!((position.speed.unwrap() - 22.4 * KNOTS_TO_MPS).abs() < 1e-9);
+    }
+
+    #[test]
+    fn invalid_sentences_are_ignored() {
+        let mut parser = NmeaParser::new();
+
+        // wrong checksum
+        assert!(parser
+            .parse_sentence("$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*48")
+            .is_none());
+        // no fix
+        assert!(parser
+            .parse_sentence("$GPRMC,123519,V,4807.038,N,01131.000,E,022.4,084.4,230394,003.1,W*71")
+            .is_none());
+    }
+
+    #[test]
+    fn provider_reads_stream() {
+        let data = "garbage\n$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*47\n";
+        let mut provider = NmeaProvider::new(data.as_bytes());
+
+        assert!(provider.next_position().unwrap().is_ok());
+        assert!(provider.next_position().is_none());
+    }
+}
@@ -0,0 +1,259 @@
+use std::time::Duration;
+use web_time::SystemTime;
+
+/// Level of rendering quality the map currently asks the layers to render with.
+///
+/// Layers can use this value (see [`Canvas::quality`](crate::render::Canvas::quality)) to skip
+/// expensive work when the device cannot keep up with the frame rate.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RenderQuality {
+    /// Strongest degradation: coarser levels of detail, no animations, expensive recalculations
+    /// are postponed.
+    Minimal,
+    /// Animations are disabled and expensive recalculations are postponed.
+    Reduced,
+    /// Everything is rendered with full quality.
+    #[default]
+    Full,
+}
+
+impl RenderQuality {
+    /// Returns true if the quality is lower then [`RenderQuality::Full`].
+    pub fn is_reduced(&self) -> bool {
+        *self < RenderQuality::Full
+    }
+
+    /// Returns true if recalculation of label collisions should be postponed until the quality is restored.
+    ///
+    /// Layers that place labels every frame should keep the previous placement while this returns true, and only
+    /// recalculate it when the map becomes idle or fast again.
+    pub fn postpones_label_collision(&self) -> bool {
+        self.is_reduced()
+    }
+
+    fn lower(self) -> Self {
+        match self {
+            RenderQuality::Full => RenderQuality::Reduced,
+            _ => RenderQuality::Minimal,
+        }
+    }
+
+    fn higher(self) -> Self {
+        match self {
+            RenderQuality::Minimal => RenderQuality::Reduced,
+            _ => RenderQuality::Full,
+        }
+    }
+}
+
+/// Configuration of a [`FrameGovernor`].
+#[derive(Debug, Copy, Clone)]
+pub struct FrameBudget {
+    /// Target duration of a single frame. If frames take longer than that, the quality is lowered.
+    pub frame_duration: Duration,
+    /// Number of consecutive slow (or fast) frames after which the quality is changed.
+    pub frames_to_switch: u32,
+    /// If no frames were rendered for this duration, the map is considered idle and full quality
+    /// is restored.
+    pub idle_timeout: Duration,
+}
+
+impl Default for FrameBudget {
+    fn default() -> Self {
+        Self {
+            frame_duration: Duration::from_millis(33),
+            frames_to_switch: 5,
+            idle_timeout: Duration::from_millis(500),
+        }
+    }
+}
+
+/// Tracks frame render times and decides what [`RenderQuality`] the map should be rendered with.
+///
+/// When frames take longer than the [`FrameBudget::frame_duration`], the quality is lowered step
+/// by step. When frames become fast again, or when the map stays idle for
+/// [`FrameBudget::idle_timeout`], the quality is restored.
+#[derive(Debug, Clone)]
+pub struct FrameGovernor {
+    budget: FrameBudget,
+    quality: RenderQuality,
+    slow_frames: u32,
+    fast_frames: u32,
+    last_frame: Option<SystemTime>,
+}
+
+impl FrameGovernor {
+    /// Creates a new governor with the given budget.
+    pub fn new(budget: FrameBudget) -> Self {
+        Self {
+            budget,
+            quality: RenderQuality::Full,
+            slow_frames: 0,
+            fast_frames: 0,
+            last_frame: None,
+        }
+    }
+
+    /// Budget of the governor.
+    pub fn budget(&self) -> &FrameBudget {
+        &self.budget
+    }
+
+    /// Records the time it took to render the last frame.
+    pub fn record_frame(&mut self, frame_time: Duration) {
+        self.record_frame_at(frame_time, SystemTime::now());
+    }
+
+    /// Quality the next frame should be rendered with.
+    pub fn quality(&self) -> RenderQuality {
+        self.quality_at(SystemTime::now())
+    }
+
+    fn record_frame_at(&mut self, frame_time: Duration, now: SystemTime) {
+        if self.is_idle(now) {
+            self.reset();
+        }
+
+        self.last_frame = Some(now);
+
+        if frame_time > self.budget.frame_duration {
+            self.fast_frames = 0;
+            self.slow_frames += 1;
+            if self.slow_frames >= self.budget.frames_to_switch {
+                self.slow_frames = 0;
+                if self.quality != RenderQuality::Minimal {
+                    log::debug!("Frame budget exceeded, lowering render quality");
+                }
+                self.quality = self.quality.lower();
+            }
+        } else {
+            self.slow_frames = 0;
+            // Restoring quality is only safe if the frame would fit the budget even with more work.
+            if frame_time * 2 <= self.budget.frame_duration {
+                self.fast_frames += 1;
+                if self.fast_frames >= self.budget.frames_to_switch {
+                    self.fast_frames = 0;
+                    self.quality = self.quality.higher();
+                }
+            }
+        }
+    }
+
+    fn quality_at(&self, now: SystemTime) -> RenderQuality {
+        if self.is_idle(now) {
+            RenderQuality::Full
+        } else {
+            self.quality
+        }
+    }
+
+    fn is_idle(&self, now: SystemTime) -> bool {
+        match self.last_frame {
+            Some(last_frame) => {
+                now.duration_since(last_frame).unwrap_or_default() >= self.budget.idle_timeout
+            }
+            None => true,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.quality = RenderQuality::Full;
+        self.slow_frames = 0;
+        self.fast_frames = 0;
+    }
+}
+
+impl Default for FrameGovernor {
+    fn default() -> Self {
+        Self::new(FrameBudget::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn governor() -> FrameGovernor {
+        FrameGovernor::new(FrameBudget {
+            frame_duration: Duration::from_millis(20),
+            frames_to_switch: 2,
+            idle_timeout: Duration::from_millis(500),
+        })
+    }
+
+    #[test]
+    fn quality_degrades_on_slow_frames() {
+        let mut governor = governor();
+        let mut now = SystemTime::UNIX_EPOCH;
+        let slow = Duration::from_millis(50);
+
+        governor.record_frame_at(slow, now);
+        assert_eq!(governor.quality_at(now), RenderQuality::Full);
+
+        now += slow;
+        governor.record_frame_at(slow, now);
+        assert_eq!(governor.quality_at(now), RenderQuality::Reduced);
+
+        now += slow;
+        governor.record_frame_at(slow, now);
+        now += slow;
+        governor.record_frame_at(slow, now);
+        assert_eq!(governor.quality_at(now), RenderQuality::Minimal);
+    }
+
+    #[test]
+    fn quality_restores_on_fast_frames() {
+        let mut governor = governor();
+        let mut now = SystemTime::UNIX_EPOCH;
+        let slow = Duration::from_millis(50);
+        let fast = Duration::from_millis(5);
+
+        for _ in 0..4 {
+            now += slow;
+            governor.record_frame_at(slow, now);
+        }
+        assert_eq!(governor.quality_at(now), RenderQuality::Minimal);
+
+        for _ in 0..2 {
+            now += fast;
+            governor.record_frame_at(fast, now);
+        }
+        assert_eq!(governor.quality_at(now), RenderQuality::Reduced);
+    }
+
+    #[test]
+    fn quality_restores_when_idle() {
+        let mut governor = governor();
+        let mut now = SystemTime::UNIX_EPOCH;
+        let slow = Duration::from_millis(50);
+
+        for _ in 0..2 {
+            now += slow;
+            governor.record_frame_at(slow, now);
+        }
+        assert_eq!(governor.quality_at(now), RenderQuality::Reduced);
+
+        now += Duration::from_secs(1);
+        assert_eq!(governor.quality_at(now), RenderQuality::Full);
+
+        governor.record_frame_at(slow, now);
+        assert_eq!(governor.quality_at(now), RenderQuality::Full);
+    }
+
+    #[test]
+    fn label_collision_is_postponed_under_load() {
+        let mut governor = governor();
+        let mut now = SystemTime::UNIX_EPOCH;
+        let slow = Duration::from_millis(50);
+        assert!(!governor.quality_at(now).postpones_label_collision());
+
+        for _ in 0..2 {
+            now += slow;
+            governor.record_frame_at(slow, now);
+        }
+        assert!(governor.quality_at(now).postpones_label_collision());
+
+        now += Duration::from_secs(1);
+        assert!(!governor.quality_at(now).postpones_label_collision());
+    }
+}
@@ -0,0 +1,245 @@
+use crate::map::Map;
+use galileo_types::geo::impls::GeoPoint2d;
+use galileo_types::geo::{GeoPoint, NewGeoPoint};
+use std::fmt::{Display, Formatter};
+
+/// Resolution of the zoom level 0 of the standard web tile schema.
+const ZOOM_0_RESOLUTION: f64 = 156543.03392800014;
+
+/// Maximum pitch in degrees, the same as the tilt limit of the `MapController`.
+const MAX_PITCH: f64 = 80.0;
+
+const MAP_KEY: &str = "map";
+const LAYERS_KEY: &str = "layers";
+
+/// State of a [`Map`] that can be stored in the fragment part of a URL, so that a link to the map opens it at the same
+/// place.
+///
+/// The state is written as `map=<zoom>/<lat>/<lon>[/<bearing>[/<pitch>]]&layers=<visible layer indices>`, e.g.
+/// `map=12.00/37.56600/126.97840&layers=0,2`, similar to the links of other web map libraries. Bearing and pitch are
+/// in degrees and omitted when zero.
+///
+/// On the web, `UrlHashSync` in the `platform::web` module keeps the URL of the page in sync with the map.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MapHashState {
+    /// Center of the map.
+    pub center: GeoPoint2d,
+    /// Zoom level in terms of the web tile schema.
+    pub zoom: f64,
+    /// Rotation of the map around the vertical axis in degrees.
+    pub bearing: f64,
+    /// Tilt of the map in degrees.
+    pub pitch: f64,
+    /// Indices of the visible layers. `None` if the visibility of the layers should not be changed.
+    pub visible_layers: Option<Vec<usize>>,
+}
+
+impl MapHashState {
+    /// Reads the state of the map. Returns `None` if the map view has no geographic position.
+    pub fn from_map(map: &Map) -> Option<Self> {
+        let view = map.view();
+        let layers = map.layers();
+
+        Some(Self {
+            center: view.position()?,
+            zoom: (ZOOM_0_RESOLUTION / view.resolution()).log2(),
+            bearing: view.rotation_z().to_degrees(),
+            pitch: view.rotation_x().to_degrees(),
+            visible_layers: Some(
+                layers
+                    .ids()
+                    .enumerate()
+                    .filter(|(_, id)| layers.visibility(*id) == Some(true))
+                    .map(|(index, _)| index)
+                    .collect(),
+            ),
+        })
+    }
+
+    /// Sets the view of the map and visibility of its layers to this state.
+    pub fn apply(&self, map: &mut Map) {
+        let view = map
+            .view()
+            .with_position(&self.center)
+            .with_resolution(ZOOM_0_RESOLUTION / 2f64.powf(self.zoom))
+            .with_rotation(self.pitch.to_radians(), self.bearing.to_radians());
+        map.set_view(view);
+
+        if let Some(visible) = &self.visible_layers {
+            let layers = map.layers_mut();
+            let ids: Vec<_> = layers.ids().collect();
+            for (index, id) in ids.into_iter().enumerate() {
+                layers.set_visible(id, visible.contains(&index));
+            }
+        }
+    }
+
+    /// Parses the state from a URL fragment (with or without leading `#`). Parameters other than `map` and `layers` are
+    /// ignored.
+    pub fn from_hash(hash: &str) -> Option<Self> {
+        let mut state = None;
+        let mut visible_layers = None;
+
+        for (key, value) in hash_params(hash) {
+            match key {
+                MAP_KEY => state = Some(Self::parse_map_value(value)?),
+                LAYERS_KEY => {
+                    visible_layers = Some(
+                        value
+                            .split(',')
+                            .filter(|s| !s.is_empty())
+                            .map(|s| s.parse().ok())
+                            .collect::<Option<Vec<usize>>>()?,
+                    )
+                }
+                _ => {}
+            }
+        }
+
+        let mut state: Self = state?;
+        state.visible_layers = visible_layers;
+        Some(state)
+    }
+
+    /// Returns the URL fragment (without leading `#`) with the state written into it. Parameters of the `current`
+    /// fragment that are not related to the map are preserved.
+    pub fn merge_into_hash(&self, current: &str) -> String {
+        let mut params: Vec<String> = hash_params(current)
+            .filter(|(key, _)| *key != MAP_KEY && *key != LAYERS_KEY)
+            .map(|(key, value)| {
+                if value.is_empty() {
+                    key.to_string()
+                } else {
+                    format!("{key}={value}")
+                }
+            })
+            .collect();
+        params.insert(0, self.to_string());
+
+        params.join("&")
+    }
+
+    fn parse_map_value(value: &str) -> Option<Self> {
+        let parts: Vec<f64> = value
+            .split('/')
+            .map(|s| s.parse().ok())
+            .collect::<Option<_>>()?;
+
+        let (zoom, lat, lon) = match parts[..] {
+            [zoom, lat, lon, ..] if parts.len() <= 5 => (zoom, lat, lon),
+            _ => return None,
+        };
+
+        let bearing = parts.get(3).copied().unwrap_or_default();
+        let pitch = parts.get(4).copied().unwrap_or_default();
+
+        let valid = zoom.is_finite()
+            && (0.0..=30.0).contains(&zoom)
+            && (-90.0..=90.0).contains(&lat)
+            && (-180.0..=180.0).contains(&lon)
+            && bearing.is_finite()
+            && pitch.is_finite();
+
+        valid.then(|| Self {
+            center: GeoPoint2d::latlon(lat, lon),
+            zoom,
+            bearing,
+            pitch: pitch.clamp(0.0, MAX_PITCH),
+            visible_layers: None,
+        })
+    }
+}
+
+impl Display for MapHashState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{MAP_KEY}={:.2}/{:.5}/{:.5}",
+            self.zoom,
+            self.center.lat(),
+            self.center.lon()
+        )?;
+
+        let bearing = (self.bearing * 10.0).round() / 10.0;
+        let pitch = (self.pitch * 10.0).round() / 10.0;
+        if bearing != 0.0 || pitch != 0.0 {
+            write!(f, "/{bearing:.1}")?;
+        }
+        if pitch != 0.0 {
+            write!(f, "/{pitch:.1}")?;
+        }
+
+        if let Some(layers) = &self.visible_layers {
+            let layers: Vec<String> = layers.iter().map(|index| index.to_string()).collect();
+            write!(f, "&{LAYERS_KEY}={}", layers.join(","))?;
+        }
+
+        Ok(())
+    }
+}
+
+fn hash_params(hash: &str) -> impl Iterator<Item = (&str, &str)> {
+    hash.trim_start_matches('#')
+        .split('&')
+        .filter(|param| !param.is_empty())
+        .map(|param| param.split_once('=').unwrap_or((param, "")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::view::MapView;
+    use crate::DummyMessenger;
+
+    #[test]
+    fn hash_roundtrip() {
+        let state = MapHashState {
+            center: GeoPoint2d::latlon(37.566, 126.9784),
+            zoom: 12.0,
+            bearing: 30.0,
+            pitch: 0.0,
+            visible_layers: Some(vec![0, 2]),
+        };
+
+        let hash = state.to_string();
+        assert_eq!(hash, "map=12.00/37.56600/126.97840/30.0&layers=0,2");
+        assert_eq!(MapHashState::from_hash(&format!("#{hash}")), Some(state));
+    }
+
+    #[test]
+    fn merge_preserves_other_params() {
+        let state = MapHashState::from_hash("map=3.00/10.00000/20.00000").expect("valid hash");
+        assert_eq!(state.visible_layers, None);
+
+        assert_eq!(
+            state.merge_into_hash("#tab=info&map=1.00/0.00000/0.00000&debug"),
+            "map=3.00/10.00000/20.00000&tab=info&debug"
+        );
+        assert!(MapHashState::from_hash("map=3/95/20").is_none());
+        assert!(MapHashState::from_hash("map=3/10/20/NaN/inf").is_none());
+        assert!(MapHashState::from_hash("map=3/10/20/0/inf").is_none());
+        assert!(MapHashState::from_hash("map=3/10/20/inf").is_none());
+        assert_eq!(
+            MapHashState::from_hash("map=3/10/20/45/120").map(|state| state.pitch),
+            Some(MAX_PITCH)
+        );
+        assert!(MapHashState::from_hash("tab=info").is_none());
+    }
+
+    #[test]
+    fn applies_state_to_map() {
+        let mut map = Map::new(
+            MapView::new(&GeoPoint2d::default(), ZOOM_0_RESOLUTION),
+            vec![],
+            None::<DummyMessenger>,
+        );
+        let state = MapHashState::from_hash("map=4.00/10.00000/20.00000/90.0").expect("valid hash");
+        state.apply(&mut map);
+
+        let restored = MapHashState::from_map(&map).expect("map has position");
+        assert!((restored.zoom - 4.0).abs() < 1e-9);
+        assert!((restored.center.lat() - 10.0).abs() < 1e-6);
+        assert!((restored.center.lon() - 20.0).abs() < 1e-6);
+        assert!((restored.bearing - 90.0).abs() < 1e-9);
+    }
+}
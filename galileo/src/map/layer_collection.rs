@@ -1,13 +1,50 @@
 use crate::layer::Layer;
+use crate::messenger::{LayerMessenger, Messenger};
 use std::ops::{Index, IndexMut, RangeBounds};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+static NEXT_LAYER_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Opaque identifier of a layer in a [`LayerCollection`].
+///
+/// An id is assigned to a layer when it is added to the collection and does not change when other layers are
+/// inserted, removed or reordered, so unlike indices it can be stored by the application to refer to the layer
+/// later. Ids are unique within the process, so an id of a removed layer never refers to another layer.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct LayerId(u64);
+
+impl LayerId {
+    fn next() -> Self {
+        Self(NEXT_LAYER_ID.fetch_add(1, Ordering::Relaxed))
+    }
+
+    /// Returns the numeric value of the id, e.g. to pass it to code written in other languages. The value is never
+    /// zero.
+    pub fn to_raw(self) -> u64 {
+        self.0
+    }
+
+    /// Creates an id from the value returned by [`LayerId::to_raw`]. Values that were not returned by `to_raw` do not
+    /// refer to any layer.
+    pub fn from_raw(raw: u64) -> Self {
+        Self(raw)
+    }
+}
 
 /// Collection of layers with some meta-information.
 ///
 /// When a map is rendered, it draws all visible layers in the order they are stored in the
-/// collection. Any layer can be temporary hidden with the [`LayerCollection::hide`] or
+/// collection. Any layer can be temporary hidden with the [`LayerCollection::set_visible`] or
 /// [`LayerCollection::show_by`] methods. These layers will be ignored by the renderer, but
 /// retain their place in the collection.
 ///
+/// Every layer gets a [`LayerId`] when it is added to the collection. The id stays the same
+/// while the layer is in the collection, so methods that take an id (like
+/// [`LayerCollection::get_by_id`], [`LayerCollection::move_to`] or
+/// [`LayerCollection::set_opacity`]) keep working on the same layer even if other layers were
+/// inserted or removed in the meantime.
+///
 /// Since a map should be able to render anything implementing the [`Layer`] trait, this
 /// collection stores layers as trait objects. You can use downcasting through `Any` trait
 /// to obtain a concrete layer type you work with.
@@ -35,8 +72,10 @@ use std::ops::{Index, IndexMut, RangeBounds};
 pub struct LayerCollection(Vec<LayerEntry>);
 
 struct LayerEntry {
+    id: LayerId,
     layer: Box<dyn Layer>,
     is_hidden: bool,
+    opacity: f32,
 }
 
 impl LayerCollection {
@@ -103,15 +142,18 @@ impl LayerCollection {
     ///     TestLayer("Layer C"),
     /// ]);
     ///
+    /// # #[allow(deprecated)]
     /// let removed = collection.swap_remove(0);
     /// assert_eq!(removed.as_any().downcast_ref(), Some(&TestLayer("Layer A")));
     /// assert_eq!(collection[0].as_any().downcast_ref(), Some(&TestLayer("Layer C")));
     /// ```
+    #[deprecated(note = "layers are addressed by id, use `LayerCollection::remove_by_id` instead")]
     pub fn swap_remove(&mut self, index: usize) -> Box<dyn Layer> {
         self.0.swap_remove(index).layer
     }
 
-    /// Inserts a layer at position `index`, shifting all layers after it to the right.
+    /// Inserts a layer at position `index`, shifting all layers after it to the right. Returns the
+    /// id of the inserted layer.
     ///
     /// # Panics
     ///
@@ -132,8 +174,11 @@ impl LayerCollection {
     /// assert_eq!(collection.len(), 3);
     /// assert_eq!(collection[1].as_any().downcast_ref(), Some(&TestLayer("Layer C")));
     /// assert_eq!(collection[2].as_any().downcast_ref(), Some(&TestLayer("Layer B")));
-    pub fn insert(&mut self, index: usize, layer: impl Layer + 'static) {
-        self.0.insert(index, layer.into());
+    pub fn insert(&mut self, index: usize, layer: impl Layer + 'static) -> LayerId {
+        let entry = LayerEntry::from(layer);
+        let id = entry.id;
+        self.0.insert(index, entry);
+        id
     }
 
     /// Removes a layer at `index`, shifting all layers after it to the left and returning the
@@ -155,11 +200,13 @@ impl LayerCollection {
     ///     TestLayer("Layer C"),
     /// ]);
     ///
+    /// # #[allow(deprecated)]
     /// let removed = collection.remove(1);
     /// assert_eq!(removed.as_any().downcast_ref(), Some(&TestLayer("Layer B")));
     /// assert_eq!(collection.len(), 2);
     /// assert_eq!(collection[1].as_any().downcast_ref(), Some(&TestLayer("Layer C")));
     /// ```
+    #[deprecated(note = "layers are addressed by id, use `LayerCollection::remove_by_id` instead")]
     pub fn remove(&mut self, index: usize) -> Box<dyn Layer> {
         self.0.remove(index).layer
     }
@@ -192,7 +239,7 @@ impl LayerCollection {
         self.0.retain(|entry| f(&*entry.layer))
     }
 
-    /// Adds the layer to the end of the collection.
+    /// Adds the layer to the end of the collection. Returns the id of the added layer.
     ///
     /// # Examples
     ///
@@ -210,8 +257,11 @@ impl LayerCollection {
     /// assert_eq!(collection.len(), 3);
     /// assert_eq!(collection[2].as_any().downcast_ref(), Some(&TestLayer("Layer C")));
     /// ```
-    pub fn push(&mut self, layer: impl Layer + 'static) {
-        self.0.push(layer.into())
+    pub fn push(&mut self, layer: impl Layer + 'static) -> LayerId {
+        let entry = LayerEntry::from(layer);
+        let id = entry.id;
+        self.0.push(entry);
+        id
     }
 
     /// Removes the last layer from the collection and returns it. Returns `None` if the collection
@@ -343,9 +393,11 @@ impl LayerCollection {
     ///     TestLayer("Layer B"),
     /// ]);
     ///
+    /// # #[allow(deprecated)]
     /// assert_eq!(collection.get_mut(1).and_then(|layer| layer.as_any_mut().downcast_ref()), Some(&TestLayer("Layer B")));
     /// assert!(collection.get(2).is_none());
     /// ```
+    #[deprecated(note = "layers are addressed by id, use `LayerCollection::get_by_id_mut` instead")]
     pub fn get_mut(&mut self, index: usize) -> Option<&mut Box<dyn Layer>> {
         self.0.get_mut(index).map(|entry| &mut entry.layer)
     }
@@ -368,11 +420,13 @@ impl LayerCollection {
     ///     TestLayer("Layer C"),
     /// ]);
     ///
+    /// # #[allow(deprecated)]
     /// collection.swap(1, 2);
     ///
     /// assert_eq!(collection[1].as_any().downcast_ref(), Some(&TestLayer("Layer C")));
     /// assert_eq!(collection[2].as_any().downcast_ref(), Some(&TestLayer("Layer B")));
     /// ```
+    #[deprecated(note = "layers are addressed by id, use `LayerCollection::move_to` instead")]
     pub fn swap(&mut self, a: usize, b: usize) {
         self.0.swap(a, b)
     }
@@ -418,7 +472,7 @@ impl LayerCollection {
     }
 
     /// Sets the layer at `index` as invisible. The hidden layer can be later shown with
-    /// [`LayerCollection::show`].
+    /// [`LayerCollection::set_visible`].
     ///
     /// Hidden layers are stored in the layer collection, but are not rendered to a map.
     ///
@@ -437,9 +491,11 @@ impl LayerCollection {
     ///     TestLayer("Layer B"),
     /// ]);
     ///
+    /// # #[allow(deprecated)]
     /// collection.hide(1);
-    /// assert!(!collection.is_visible(1));
+    /// assert_eq!(collection.visibility(collection.id(1).unwrap()), Some(false));
     /// ```
+    #[deprecated(note = "layers are addressed by id, use `LayerCollection::set_visible` instead")]
     pub fn hide(&mut self, index: usize) {
         self.0[index].is_hidden = true;
     }
@@ -463,10 +519,13 @@ impl LayerCollection {
     ///     TestLayer("Layer B"),
     /// ]);
     ///
+    /// # #[allow(deprecated)]
     /// collection.hide(1);
+    /// # #[allow(deprecated)]
     /// collection.show(1);
-    /// assert!(collection.is_visible(1));
+    /// assert_eq!(collection.visibility(collection.id(1).unwrap()), Some(true));
     /// ```
+    #[deprecated(note = "layers are addressed by id, use `LayerCollection::set_visible` instead")]
     pub fn show(&mut self, index: usize) {
         self.0[index].is_hidden = false;
     }
@@ -488,9 +547,8 @@ impl LayerCollection {
     ///
     /// collection.show_by(|layer| layer.as_any().downcast_ref::<TestLayer>().unwrap().0.ends_with("B"));
     ///
-    /// assert!(!collection.is_visible(0));
-    /// assert!(collection.is_visible(1));
-    /// assert!(!collection.is_visible(2));
+    /// let visibility: Vec<_> = collection.ids().map(|id| collection.visibility(id)).collect();
+    /// assert_eq!(visibility, vec![Some(false), Some(true), Some(false)]);
     pub fn show_by<F>(&mut self, mut f: F)
     where
         F: FnMut(&dyn Layer) -> bool,
@@ -519,12 +577,10 @@ impl LayerCollection {
     ///     TestLayer("Layer B"),
     /// ]);
     ///
-    /// assert!(collection.is_visible(1));
-    /// collection.hide(1);
-    /// assert!(!collection.is_visible(1));
-    /// collection.show(1);
+    /// # #[allow(deprecated)]
     /// assert!(collection.is_visible(1));
     /// ```
+    #[deprecated(note = "layers are addressed by id, use `LayerCollection::visibility` instead")]
     pub fn is_visible(&self, index: usize) -> bool {
         !self.0[index].is_hidden
     }
@@ -543,7 +599,7 @@ impl LayerCollection {
     ///     TestLayer("Layer C"),
     /// ]);
     ///
-    /// collection.hide(1);
+    /// collection.set_visible(collection.id(1).unwrap(), false);
     ///
     /// let mut iterator = collection.iter_visible();
     /// assert_eq!(iterator.next().and_then(|layer| layer.as_any().downcast_ref()), Some(&TestLayer("Layer A")));
@@ -556,6 +612,245 @@ impl LayerCollection {
             .filter(|entry| !entry.is_hidden)
             .map(|entry| &*entry.layer)
     }
+
+    /// Iterates over the visible layers that are not fully transparent together with their opacity.
+    pub(crate) fn iter_visible_with_opacity(&self) -> impl Iterator<Item = (&dyn Layer, f32)> + '_ {
+        self.0
+            .iter()
+            .filter(|entry| !entry.is_hidden && entry.opacity > 0.0)
+            .map(|entry| (&*entry.layer, entry.opacity))
+    }
+
+    /// Returns the id of the layer at `index`, or `None` if index is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use galileo::LayerCollection;
+    /// use galileo::layer::TestLayer;
+    ///
+    /// let mut collection = LayerCollection::default();
+    /// let id = collection.push(TestLayer("Layer A"));
+    ///
+    /// assert_eq!(collection.id(0), Some(id));
+    /// assert_eq!(collection.id(1), None);
+    /// ```
+    pub fn id(&self, index: usize) -> Option<LayerId> {
+        self.0.get(index).map(|entry| entry.id)
+    }
+
+    /// Iterates over the ids of all layers in the collection.
+    pub fn ids(&self) -> impl Iterator<Item = LayerId> + '_ {
+        self.0.iter().map(|entry| entry.id)
+    }
+
+    /// Returns the current index of the layer with the given id, or `None` if the layer is not in
+    /// the collection.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use galileo::LayerCollection;
+    /// use galileo::layer::TestLayer;
+    ///
+    /// let mut collection = LayerCollection::default();
+    /// let id = collection.push(TestLayer("Layer A"));
+    /// collection.insert(0, TestLayer("Layer B"));
+    ///
+    /// assert_eq!(collection.index_of(id), Some(1));
+    /// ```
+    pub fn index_of(&self, id: LayerId) -> Option<usize> {
+        self.0.iter().position(|entry| entry.id == id)
+    }
+
+    /// Returns true if the layer with the given id is in the collection.
+    pub fn contains(&self, id: LayerId) -> bool {
+        self.index_of(id).is_some()
+    }
+
+    /// Returns the layer with the given id, or `None` if the layer is not in the collection.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use galileo::LayerCollection;
+    /// use galileo::layer::TestLayer;
+    ///
+    /// let mut collection = LayerCollection::default();
+    /// let id = collection.push(TestLayer("Layer A"));
+    ///
+    /// assert_eq!(collection.get_by_id(id).and_then(|layer| layer.as_any().downcast_ref()), Some(&TestLayer("Layer A")));
+    /// ```
+    pub fn get_by_id(&self, id: LayerId) -> Option<&dyn Layer> {
+        self.entry(id).map(|entry| &*entry.layer)
+    }
+
+    /// Returns a mutable reference to the layer with the given id, or `None` if the layer is not
+    /// in the collection.
+    pub fn get_by_id_mut(&mut self, id: LayerId) -> Option<&mut Box<dyn Layer>> {
+        self.entry_mut(id).map(|entry| &mut entry.layer)
+    }
+
+    /// Sets the messenger of every layer in the collection. Each layer gets a [`LayerMessenger`]
+    /// with its id, so redraw requests of the layers reach the `messenger` through
+    /// [`Messenger::request_layer_redraw`] and the application knows which layer has changed.
+    ///
+    /// Layers added to the collection later do not get the messenger automatically. Use
+    /// [`LayerCollection::set_layer_messenger`] for them.
+    pub fn set_messenger(&mut self, messenger: Arc<dyn Messenger>) {
+        for entry in &mut self.0 {
+            entry
+                .layer
+                .set_messenger(Box::new(LayerMessenger::new(entry.id, messenger.clone())));
+        }
+    }
+
+    /// Sets a [`LayerMessenger`] with the given id to the layer with this id. Returns false if the
+    /// layer is not in the collection.
+    pub fn set_layer_messenger(&mut self, id: LayerId, messenger: Arc<dyn Messenger>) -> bool {
+        let Some(entry) = self.entry_mut(id) else {
+            return false;
+        };
+
+        entry
+            .layer
+            .set_messenger(Box::new(LayerMessenger::new(id, messenger)));
+        true
+    }
+
+    /// Removes the layer with the given id from the collection, shifting all layers after it to
+    /// the left and returning the removed layer. Returns `None` if the layer is not in the
+    /// collection.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use galileo::LayerCollection;
+    /// use galileo::layer::TestLayer;
+    ///
+    /// let mut collection = LayerCollection::default();
+    /// let id = collection.push(TestLayer("Layer A"));
+    /// collection.push(TestLayer("Layer B"));
+    ///
+    /// let removed = collection.remove_by_id(id);
+    /// assert_eq!(removed.unwrap().as_any().downcast_ref(), Some(&TestLayer("Layer A")));
+    /// assert!(collection.remove_by_id(id).is_none());
+    /// assert_eq!(collection.len(), 1);
+    /// ```
+    pub fn remove_by_id(&mut self, id: LayerId) -> Option<Box<dyn Layer>> {
+        let index = self.index_of(id)?;
+        Some(self.0.remove(index).layer)
+    }
+
+    /// Moves the layer with the given id to position `index`, shifting the layers between the old
+    /// and the new position. If `index` is greater than the index of the last layer, the layer is
+    /// moved to the end of the collection.
+    ///
+    /// Returns `false` if the layer is not in the collection.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use galileo::LayerCollection;
+    /// use galileo::layer::TestLayer;
+    ///
+    /// let mut collection = LayerCollection::default();
+    /// let id = collection.push(TestLayer("Layer A"));
+    /// collection.push(TestLayer("Layer B"));
+    /// collection.push(TestLayer("Layer C"));
+    ///
+    /// assert!(collection.move_to(id, 2));
+    /// assert_eq!(collection.index_of(id), Some(2));
+    /// assert_eq!(collection[0].as_any().downcast_ref(), Some(&TestLayer("Layer B")));
+    /// ```
+    pub fn move_to(&mut self, id: LayerId, index: usize) -> bool {
+        let Some(current) = self.index_of(id) else {
+            return false;
+        };
+
+        let entry = self.0.remove(current);
+        let index = index.min(self.0.len());
+        self.0.insert(index, entry);
+        true
+    }
+
+    /// Sets visibility of the layer with the given id. Returns `false` if the layer is not in the
+    /// collection.
+    ///
+    /// Hidden layers are stored in the layer collection, but are not rendered to a map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use galileo::LayerCollection;
+    /// use galileo::layer::TestLayer;
+    ///
+    /// let mut collection = LayerCollection::default();
+    /// let id = collection.push(TestLayer("Layer A"));
+    ///
+    /// assert!(collection.set_visible(id, false));
+    /// assert_eq!(collection.visibility(id), Some(false));
+    /// ```
+    pub fn set_visible(&mut self, id: LayerId, visible: bool) -> bool {
+        match self.entry_mut(id) {
+            Some(entry) => {
+                entry.is_hidden = !visible;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns visibility of the layer with the given id, or `None` if the layer is not in the
+    /// collection.
+    pub fn visibility(&self, id: LayerId) -> Option<bool> {
+        self.entry(id).map(|entry| !entry.is_hidden)
+    }
+
+    /// Sets opacity of the layer with the given id from `0.0` (invisible) to `1.0` (opaque, the
+    /// default value). The opacity is multiplied with the opacity of everything the layer draws.
+    /// Returns `false` if the layer is not in the collection.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use galileo::LayerCollection;
+    /// use galileo::layer::TestLayer;
+    ///
+    /// let mut collection = LayerCollection::default();
+    /// let id = collection.push(TestLayer("Layer A"));
+    ///
+    /// assert_eq!(collection.opacity(id), Some(1.0));
+    /// assert!(collection.set_opacity(id, 0.5));
+    /// assert_eq!(collection.opacity(id), Some(0.5));
+    /// ```
+    pub fn set_opacity(&mut self, id: LayerId, opacity: f32) -> bool {
+        match self.entry_mut(id) {
+            Some(entry) => {
+                entry.opacity = if opacity.is_nan() {
+                    1.0
+                } else {
+                    opacity.clamp(0.0, 1.0)
+                };
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns opacity of the layer with the given id, or `None` if the layer is not in the
+    /// collection.
+    pub fn opacity(&self, id: LayerId) -> Option<f32> {
+        self.entry(id).map(|entry| entry.opacity)
+    }
+
+    fn entry(&self, id: LayerId) -> Option<&LayerEntry> {
+        self.0.iter().find(|entry| entry.id == id)
+    }
+
+    fn entry_mut(&mut self, id: LayerId) -> Option<&mut LayerEntry> {
+        self.0.iter_mut().find(|entry| entry.id == id)
+    }
 }
 
 impl Index<usize> for LayerCollection {
@@ -580,18 +875,58 @@ impl<L: Into<LayerEntry>, T: IntoIterator<Item = L>> From<T> for LayerCollection
 
 impl<T: Layer + 'static> From<T> for LayerEntry {
     fn from(value: T) -> Self {
-        Self {
-            layer: Box::new(value),
-            is_hidden: false,
-        }
+        Self::from(Box::new(value) as Box<dyn Layer>)
     }
 }
 
 impl From<Box<dyn Layer>> for LayerEntry {
     fn from(value: Box<dyn Layer>) -> Self {
         Self {
+            id: LayerId::next(),
             layer: value,
             is_hidden: false,
+            opacity: 1.0,
         }
     }
 }
+
+#[cfg(all(test, feature = "_tests"))]
+mod tests {
+    use super::*;
+    use crate::layer::TestLayer;
+
+    #[test]
+    fn ids_survive_collection_changes() {
+        let mut collection = LayerCollection::from(vec![TestLayer("A"), TestLayer("B")]);
+        let a = collection.id(0).expect("layer exists");
+        let b = collection.id(1).expect("layer exists");
+        assert_ne!(a, b);
+
+        let c = collection.insert(0, TestLayer("C"));
+        collection.move_to(a, 2);
+        assert_eq!(collection.index_of(a), Some(2));
+        assert_eq!(collection.index_of(b), Some(1));
+
+        collection.remove_by_id(c);
+        assert!(!collection.contains(c));
+        assert!(!collection.set_visible(c, false));
+        assert!(collection.set_visible(a, false));
+        assert_eq!(collection.visibility(a), Some(false));
+        assert_eq!(collection.ids().collect::<Vec<_>>(), vec![b, a]);
+
+        let d = collection.push(TestLayer("D"));
+        assert!(d != a && d != b && d != c);
+    }
+
+    #[test]
+    fn layer_opacity() {
+        let mut collection = LayerCollection::from(vec![TestLayer("A"), TestLayer("B")]);
+        let a = collection.id(0).expect("layer exists");
+        collection.set_opacity(a, 2.0);
+        assert_eq!(collection.opacity(a), Some(1.0));
+
+        collection.set_opacity(a, 0.0);
+        assert_eq!(collection.iter_visible_with_opacity().count(), 1);
+        assert_eq!(collection.iter_visible().count(), 2);
+    }
+}
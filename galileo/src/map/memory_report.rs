@@ -0,0 +1,117 @@
+use crate::layer::LayerMemoryUsage;
+use crate::map::{LayerCollection, LayerId};
+
+/// Approximate memory used by the layers of a [`Map`](crate::Map). Created by
+/// [`Map::memory_report`](crate::Map::memory_report).
+///
+/// The report can be used to respond to low memory warnings of the platform by trimming the layers that use the most
+/// memory (see [`Layer::trim_memory`](crate::layer::Layer::trim_memory)).
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MemoryReport {
+    /// Memory usage of each layer of the map, in the same order as in the map's [`LayerCollection`].
+    pub layers: Vec<LayerMemoryReport>,
+}
+
+/// Memory usage of a single layer in a [`MemoryReport`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct LayerMemoryReport {
+    /// Id of the layer in the map's [`LayerCollection`].
+    pub id: LayerId,
+    /// Index of the layer in the map's [`LayerCollection`] at the moment the report was created.
+    pub index: usize,
+    /// Whether the layer is visible.
+    pub visible: bool,
+    /// Memory used by the layer.
+    pub usage: LayerMemoryUsage,
+}
+
+impl MemoryReport {
+    pub(crate) fn new(layers: &LayerCollection) -> Self {
+        Self {
+            layers: layers
+                .iter()
+                .zip(layers.ids())
+                .enumerate()
+                .map(|(index, (layer, id))| LayerMemoryReport {
+                    id,
+                    index,
+                    visible: layers.visibility(id) == Some(true),
+                    usage: layer.memory_usage(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Total memory used by all the layers.
+    pub fn total(&self) -> LayerMemoryUsage {
+        self.layers
+            .iter()
+            .fold(LayerMemoryUsage::default(), |acc, layer| acc + layer.usage)
+    }
+
+    /// Returns the layers ordered by the total number of bytes they use, starting from the largest one.
+    pub fn largest_first(&self) -> Vec<LayerMemoryReport> {
+        let mut layers = self.layers.clone();
+        layers.sort_by_key(|layer| std::cmp::Reverse(layer.usage.total_bytes()));
+        layers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layer::Layer;
+    use crate::messenger::Messenger;
+    use crate::render::Canvas;
+    use crate::view::MapView;
+    use std::any::Any;
+
+    struct SizedLayer(LayerMemoryUsage);
+
+    impl Layer for SizedLayer {
+        fn render(&self, _view: &MapView, _canvas: &mut dyn Canvas) {}
+
+        fn prepare(&self, _view: &MapView) {}
+
+        fn set_messenger(&mut self, _messenger: Box<dyn Messenger>) {}
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+
+        fn as_any_mut(&mut self) -> &mut dyn Any {
+            self
+        }
+
+        fn memory_usage(&self) -> LayerMemoryUsage {
+            self.0
+        }
+    }
+
+    fn usage(cpu_cache: usize, gpu: usize, cached_tiles: usize) -> LayerMemoryUsage {
+        LayerMemoryUsage {
+            cpu_cache,
+            gpu,
+            cached_tiles,
+        }
+    }
+
+    #[test]
+    fn aggregates_layer_usage() {
+        let mut layers = LayerCollection::from(vec![
+            Box::new(SizedLayer(usage(100, 200, 2))) as Box<dyn Layer>,
+            Box::new(SizedLayer(usage(1000, 0, 0))),
+        ]);
+        let hidden = layers.push(SizedLayer(usage(10, 20, 1)));
+        layers.set_visible(hidden, false);
+
+        let report = MemoryReport::new(&layers);
+
+        assert_eq!(report.layers.len(), 3);
+        assert!(!report.layers[2].visible);
+        assert_eq!(report.total(), usage(1110, 220, 3));
+
+        let order: Vec<usize> = report.largest_first().iter().map(|l| l.index).collect();
+        assert_eq!(order, vec![1, 0, 2]);
+    }
+}
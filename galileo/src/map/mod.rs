@@ -1,12 +1,36 @@
+use crate::alloc_audit::{AllocationAudit, FrameAudit};
+use crate::animation::{Animation, AnimationClock, Easing, StandardEasing};
+use crate::error::GalileoError;
 use crate::layer::Layer;
-use crate::messenger::Messenger;
-use crate::view::MapView;
+use crate::localization::{LayoutDirection, Locale, Localizer};
+use crate::messenger::{DummyMessenger, Messenger};
+use crate::render::RendererEvent;
+use crate::tile_scheme::{TileIndex, TileSchema};
+use crate::view::{MapView, ViewPadding};
 use galileo_types::cartesian::Size;
+use maybe_sync::MaybeSend;
+use render_hooks::RenderHooks;
 use std::time::Duration;
 use web_time::SystemTime;
 
+mod frame_governor;
+mod hash_state;
 mod layer_collection;
-pub use layer_collection::LayerCollection;
+mod memory_report;
+mod render_hooks;
+mod scale_bar;
+mod session_state;
+mod tile_manifest;
+mod view_sync;
+pub use frame_governor::{FrameBudget, FrameGovernor, RenderQuality};
+pub use hash_state::MapHashState;
+pub use layer_collection::{LayerCollection, LayerId};
+pub use memory_report::{LayerMemoryReport, MemoryReport};
+pub use render_hooks::{FrameInfo, RenderHookId};
+pub use scale_bar::ScaleBar;
+pub use session_state::MapSessionState;
+pub use tile_manifest::TileManifest;
+pub use view_sync::{ViewLink, ViewSync};
 
 const FRAME_DURATION: Duration = Duration::from_millis(16);
 
@@ -16,13 +40,19 @@ pub struct Map {
     layers: LayerCollection,
     messenger: Option<Box<dyn Messenger>>,
     animation: Option<AnimationParameters>,
+    clock: AnimationClock,
+    governor: FrameGovernor,
+    localizer: Box<dyn Localizer>,
+    layout_direction: LayoutDirection,
+    time_cursor: Option<SystemTime>,
+    render_hooks: RenderHooks,
+    allocation_audit: FrameAudit,
 }
 
 struct AnimationParameters {
     start_view: MapView,
     end_view: MapView,
-    start_time: SystemTime,
-    duration: Duration,
+    animation: Animation,
 }
 
 impl Map {
@@ -42,9 +72,41 @@ impl Map {
             layers: layers.into(),
             messenger,
             animation: None,
+            clock: AnimationClock::new(),
+            governor: FrameGovernor::default(),
+            localizer: Box::new(Locale::default()),
+            layout_direction: LayoutDirection::default(),
+            time_cursor: None,
+            render_hooks: RenderHooks::default(),
+            allocation_audit: FrameAudit::default(),
         }
     }
 
+    /// Creates a map that is not attached to a window or a renderer.
+    ///
+    /// A detached map supports everything that does not require drawing: view calculations, tile queries (see
+    /// [`Map::tiles_in_view`]), hit-testing of the features of the layers (see [`Map::layer`]) and storing of the map
+    /// state (see [`MapSessionState`] and [`MapHashState`]). This is useful for unit tests and command line tools
+    /// that reason about maps without displaying them. Redraw requests of a detached map are ignored.
+    ///
+    /// ```
+    /// use galileo::galileo_types::cartesian::{Point2d, Size};
+    /// use galileo::galileo_types::geo::GeoPoint;
+    /// use galileo::galileo_types::latlon;
+    /// use galileo::tile_scheme::TileSchema;
+    /// use galileo::{Map, MapView};
+    ///
+    /// let view = MapView::new(&latlon!(52.0, 13.0), 100.0).with_size(Size::new(512.0, 512.0));
+    /// let map = Map::new_detached(view, vec![]);
+    ///
+    /// let center = map.view().screen_to_map_geo(Point2d::new(256.0, 256.0)).unwrap();
+    /// assert!((center.lat() - 52.0).abs() < 1e-6);
+    /// assert!(map.tiles_in_view(&TileSchema::web(18)).count() > 0);
+    /// ```
+    pub fn new_detached(view: MapView, layers: Vec<Box<dyn Layer>>) -> Self {
+        Self::new(view, layers, None::<DummyMessenger>)
+    }
+
     /// Current view of the map.
     pub fn view(&self) -> &MapView {
         &self.view
@@ -60,6 +122,115 @@ impl Map {
         &mut self.layers
     }
 
+    /// Returns the layer with the given id, if it is in the map and has type `L`.
+    ///
+    /// This can be used to access the methods of a specific layer type, e.g. to find the features of a
+    /// [`FeatureLayer`](crate::layer::FeatureLayer) under the cursor.
+    pub fn layer<L: Layer + 'static>(&self, id: LayerId) -> Option<&L> {
+        self.layers.get_by_id(id)?.as_any().downcast_ref()
+    }
+
+    /// Returns a mutable reference to the layer with the given id, if it is in the map and has type `L`.
+    pub fn layer_mut<L: Layer + 'static>(&mut self, id: LayerId) -> Option<&mut L> {
+        self.layers.get_by_id_mut(id)?.as_any_mut().downcast_mut()
+    }
+
+    /// Iterates over the tiles of the `tile_schema` that are displayed in the current view of the map. Returns an
+    /// empty iterator if the schema and the view have different CRS.
+    pub fn tiles_in_view(&self, tile_schema: &TileSchema) -> impl Iterator<Item = TileIndex> {
+        tile_schema.iter_tiles(&self.view).into_iter().flatten()
+    }
+
+    /// Time the map content is displayed for. Time-aware layers and applications can use it to select the data to
+    /// show. `None` (default) means the map is not bound to a specific time.
+    pub fn time_cursor(&self) -> Option<SystemTime> {
+        self.time_cursor
+    }
+
+    /// Sets the time the map content is displayed for and requests redraw of the map.
+    pub fn set_time_cursor(&mut self, time: Option<SystemTime>) {
+        self.time_cursor = time;
+        self.redraw();
+    }
+
+    /// Returns the state of the map that can be saved and restored in a later session of the application with
+    /// [`Map::restore_session_state`].
+    pub fn session_state(&self) -> MapSessionState {
+        MapSessionState::from_map(self)
+    }
+
+    /// Restores the view, time cursor and layer visibility saved with [`Map::session_state`]. See
+    /// [`MapSessionState::apply`] for details.
+    pub fn restore_session_state(&mut self, state: &MapSessionState) -> Result<(), GalileoError> {
+        state.apply(self)
+    }
+
+    /// Returns the tiles recently drawn by the layers of the map, that can be loaded in advance in a later session
+    /// of the application with [`Map::warm_up_tiles`].
+    pub fn tile_manifest(&self) -> TileManifest {
+        TileManifest::from_map(self)
+    }
+
+    /// Starts loading the tiles saved with [`Map::tile_manifest`]. See [`TileManifest::apply`] for details.
+    pub fn warm_up_tiles(&self, manifest: &TileManifest) -> Result<(), GalileoError> {
+        manifest.apply(self)
+    }
+
+    /// Adds a callback that is called by the renderer before every frame of the map is rendered.
+    ///
+    /// The hook can be used to update data-driven layers or external animations in sync with the render loop.
+    /// Changes made to the layers in the hook are visible in the frame that is being rendered.
+    pub fn add_before_render_hook(
+        &self,
+        hook: impl FnMut(&FrameInfo) + MaybeSend + 'static,
+    ) -> RenderHookId {
+        self.render_hooks.add_before(Box::new(hook))
+    }
+
+    /// Adds a callback that is called by the renderer after every frame of the map is rendered. At this point all
+    /// the render commands of the frame are submitted to the GPU, but the frame is not presented yet, so the hook
+    /// can be used to copy the frame (e.g. for video capture).
+    pub fn add_after_render_hook(
+        &self,
+        hook: impl FnMut(&FrameInfo) + MaybeSend + 'static,
+    ) -> RenderHookId {
+        self.render_hooks.add_after(Box::new(hook))
+    }
+
+    /// Removes a render hook added with [`Map::add_before_render_hook`] or [`Map::add_after_render_hook`]. Returns
+    /// false if there is no hook with the given id.
+    pub fn remove_render_hook(&self, id: RenderHookId) -> bool {
+        self.render_hooks.remove(id)
+    }
+
+    /// Calls the before-render hooks of the map and returns information about the new frame. Must be called by
+    /// renderers before a frame is rendered, followed by [`Map::end_frame`] after the frame is rendered.
+    ///
+    /// Render hooks must not add or remove hooks of the same map, as that would deadlock.
+    pub fn begin_frame(&self) -> FrameInfo {
+        let frame = self.render_hooks.begin_frame(&self.view);
+        self.allocation_audit.begin_frame();
+        frame
+    }
+
+    /// Calls the after-render hooks of the map. See [`Map::begin_frame`].
+    pub fn end_frame(&self, frame: &FrameInfo) {
+        self.allocation_audit.end_frame(frame.frame_number);
+        self.render_hooks.end_frame(frame);
+    }
+
+    /// Sets the mode of the audit of heap allocations done while the map frames are rendered. Render hooks of the map
+    /// are not included into the audit. See [`alloc_audit`](crate::alloc_audit) module for details.
+    pub fn set_allocation_audit(&mut self, audit: AllocationAudit) {
+        self.allocation_audit.set_mode(audit);
+    }
+
+    /// Number of heap allocations done while the last frame was rendered, or `None` if the allocation audit is off
+    /// (see [`Map::set_allocation_audit`]) or no frames were rendered since it was enabled.
+    pub fn last_frame_allocations(&self) -> Option<u64> {
+        self.allocation_audit.last_frame()
+    }
+
     pub(crate) fn set_view(&mut self, view: MapView) {
         self.view = view;
         if let Some(messenger) = &self.messenger {
@@ -75,6 +246,39 @@ impl Map {
         }
     }
 
+    /// Returns approximate memory usage of all the layers of the map.
+    pub fn memory_report(&self) -> MemoryReport {
+        MemoryReport::new(&self.layers)
+    }
+
+    /// Calls [`Layer::trim_memory`] on all the layers that are not visible at the moment. Use
+    /// [`Map::memory_report`] and [`Layer::trim_memory`] directly for more fine-grained control.
+    pub fn trim_hidden_layers(&self) {
+        for id in self.layers.ids() {
+            if self.layers.visibility(id) == Some(false) {
+                if let Some(layer) = self.layers.get_by_id(id) {
+                    layer.trim_memory();
+                }
+            }
+        }
+    }
+
+    /// Updates the map state after an event reported by the renderer.
+    ///
+    /// After [`RendererEvent::RendererRestarted`] all the layers are asked to drop their GPU resources with
+    /// [`Layer::invalidate_gpu_resources`] and the map is redrawn.
+    pub fn handle_renderer_event(&self, event: RendererEvent) {
+        match event {
+            RendererEvent::RendererRestarted => {
+                for layer in self.layers.iter() {
+                    layer.invalidate_gpu_resources();
+                }
+
+                self.redraw();
+            }
+        }
+    }
+
     /// Request redraw of the map.
     pub fn redraw(&self) {
         if let Some(messenger) = &self.messenger {
@@ -82,26 +286,47 @@ impl Map {
         }
     }
 
-    /// Update the view of the map before the rendering in case [`Map::animate_to`] was called.
+    /// Clock used to animate the map view. User code can create animations from this clock to animate layer
+    /// properties in sync with the map. See [`animation`](crate::animation) module for details.
+    pub fn clock(&self) -> &AnimationClock {
+        &self.clock
+    }
+
+    /// Advances the [clock](Map::clock) of the map and updates the view of the map in case [`Map::animate_to`] was
+    /// called. Must be called before every frame is rendered.
+    ///
+    /// If any animation of the clock is running, redraw of the map is requested.
+    ///
+    /// If the render quality is reduced (see [`Map::quality`]), the animation of the view is skipped and the map jumps
+    /// directly to the target view.
     pub fn animate(&mut self) {
+        self.clock.tick();
+
         let Some(animation) = &self.animation else {
+            if self.clock.is_running() {
+                self.redraw();
+            }
             return;
         };
 
-        let now = SystemTime::now();
-        let k = now
-            .duration_since(animation.start_time)
-            .unwrap_or_default()
-            .as_millis() as f64
-            / animation.duration.as_millis() as f64;
+        if self.governor.quality().is_reduced() {
+            let animation = self
+                .animation
+                .take()
+                .expect("the value was removed unexpectedly");
+            self.view = animation.end_view;
+            self.redraw();
+            return;
+        }
 
-        if k >= 1.0 {
+        if animation.animation.is_finished() {
             let animation = self
                 .animation
                 .take()
                 .expect("the value was removed unexpectedly");
             self.view = animation.end_view;
         } else {
+            let k = animation.animation.value();
             self.view = animation.start_view.interpolate(&animation.end_view, k);
         }
 
@@ -118,12 +343,26 @@ impl Map {
 
     /// Request a gradual change of the map view to the specified view.
     pub fn animate_to(&mut self, target: MapView, duration: Duration) {
+        self.animate_to_with_easing(target, duration, StandardEasing::Linear);
+    }
+
+    /// Request a gradual change of the map view to the specified view, with the speed of the change defined by the
+    /// `easing`.
+    pub fn animate_to_with_easing(
+        &mut self,
+        target: MapView,
+        duration: Duration,
+        easing: impl Easing + 'static,
+    ) {
+        // The clock is not advanced while the map is idle, so the animation starts from the current time, one frame
+        // back to make the first frame of the animation move.
+        let start = self.clock.now().max(SystemTime::now() - FRAME_DURATION);
         self.animation = Some(AnimationParameters {
             start_view: self.view.clone(),
             end_view: target,
-            start_time: SystemTime::now() - FRAME_DURATION,
-            duration,
+            animation: self.clock.animate_from(start, duration, easing),
         });
+        self.redraw();
     }
 
     /// Set the size of the map.
@@ -131,6 +370,83 @@ impl Map {
         self.view = self.view.with_size(new_size);
     }
 
+    /// Sets the padding of the map area covered by the application UI (see [`ViewPadding`]) and requests redraw of the
+    /// map. The position of the map view stays at the center of the visible area.
+    pub fn set_padding(&mut self, padding: ViewPadding) {
+        self.view = self.view.with_padding(padding);
+        if let Some(animation) = &mut self.animation {
+            animation.end_view = animation.end_view.with_padding(padding);
+        }
+
+        self.redraw();
+    }
+
+    /// Sets the number of physical pixels per logical pixel of the screen (see [`MapView::scale_factor`]) and requests
+    /// redraw of the map. Should be called when the window is moved to a monitor with different DPI, or the zoom of
+    /// the browser page changes.
+    ///
+    /// The resolution of the view is changed so that the map keeps its visible scale.
+    pub fn set_scale_factor(&mut self, scale_factor: f64) {
+        let rescale = |view: &MapView| {
+            let updated = view.with_scale_factor(scale_factor);
+            updated
+                .with_resolution(view.resolution() * view.scale_factor() / updated.scale_factor())
+        };
+
+        self.view = rescale(&self.view);
+        if let Some(animation) = &mut self.animation {
+            animation.start_view = rescale(&animation.start_view);
+            animation.end_view = rescale(&animation.end_view);
+        }
+
+        self.redraw();
+    }
+
+    /// Records the time it took to render the last frame of the map. This value is used to adjust the render quality
+    /// of the map to keep it interactive on slow devices.
+    pub fn record_frame_time(&mut self, frame_time: Duration) {
+        self.governor.record_frame(frame_time);
+    }
+
+    /// Quality the map should be rendered with, based on the recently recorded frame times.
+    pub fn quality(&self) -> RenderQuality {
+        self.governor.quality()
+    }
+
+    /// Sets the frame budget used to decide when the render quality of the map should be reduced.
+    pub fn set_frame_budget(&mut self, budget: FrameBudget) {
+        self.governor = FrameGovernor::new(budget);
+    }
+
+    /// Localizer used to format numbers and measurements shown on the map.
+    pub fn localizer(&self) -> &dyn Localizer {
+        &*self.localizer
+    }
+
+    /// Sets the localizer used to format numbers and measurements shown on the map.
+    pub fn set_localizer(&mut self, localizer: impl Localizer + 'static) {
+        self.localizer = Box::new(localizer);
+        self.redraw();
+    }
+
+    /// Direction of the user interface around the map. Overlays and application controls should be placed mirrored
+    /// for the right-to-left layout (see [`LayoutDirection::place_x`]).
+    pub fn layout_direction(&self) -> LayoutDirection {
+        self.layout_direction
+    }
+
+    /// Sets the direction of the user interface around the map.
+    pub fn set_layout_direction(&mut self, direction: LayoutDirection) {
+        self.layout_direction = direction;
+        self.redraw();
+    }
+
+    /// Calculates the scale bar for the current view, which is not longer than `max_width` pixels. The label of the bar
+    /// is formatted with the localizer of the map.
+    pub fn scale_bar(&self, max_width: f64) -> Option<ScaleBar> {
+        ScaleBar::for_view(&self.view, max_width, self.localizer())
+    }
+
     /// Sets the new event messenger for the map.
     pub fn set_messenger(&mut self, messenger: Option<impl Messenger + 'static>) {
         let messenger: Option<Box<dyn Messenger>> = if let Some(m) = messenger {
@@ -142,3 +458,57 @@ impl Map {
         self.messenger = messenger;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layer::feature_layer::symbol::CirclePointSymbol;
+    use crate::layer::FeatureLayer;
+    use crate::Color;
+    use galileo_types::cartesian::Point2d;
+    use galileo_types::geo::Crs;
+    use galileo_types::geometry_type::CartesianSpace2d;
+    use galileo_types::latlon;
+
+    type PointLayer = FeatureLayer<Point2d, Point2d, CirclePointSymbol, CartesianSpace2d>;
+
+    #[test]
+    fn detached_map_hit_test() {
+        let view = MapView::new(&latlon!(10.0, 20.0), 10.0).with_size(Size::new(100.0, 100.0));
+        let center = view
+            .screen_to_map(Point2d::new(50.0, 50.0))
+            .expect("view is valid");
+        let layer = PointLayer::new(
+            vec![center],
+            CirclePointSymbol::new(Color::RED, 5.0),
+            Crs::EPSG3857,
+        );
+
+        let mut map = Map::new_detached(view, vec![]);
+        let id = map.layers_mut().push(layer);
+        map.redraw();
+
+        let cursor = map
+            .view()
+            .screen_to_map(Point2d::new(51.0, 50.0))
+            .expect("view is valid");
+        let layer = map.layer::<PointLayer>(id).expect("layer type");
+        assert_eq!(layer.get_features_at(&cursor, 20.0).count(), 1);
+        assert_eq!(layer.get_features_at(&cursor, 5.0).count(), 0);
+
+        assert!(map.tiles_in_view(&TileSchema::web(18)).count() > 0);
+        let state = MapSessionState::from_map(&map);
+        assert_eq!(state.layer_visibility, vec![true]);
+    }
+
+    #[test]
+    fn set_scale_factor_keeps_logical_resolution() {
+        let view = MapView::new(&latlon!(10.0, 20.0), 10.0).with_size(Size::new(100.0, 100.0));
+        let mut map = Map::new_detached(view, vec![]);
+
+        map.set_scale_factor(2.0);
+        assert_eq!(map.view().scale_factor(), 2.0);
+        assert_eq!(map.view().resolution(), 5.0);
+        assert_eq!(map.view().logical_resolution(), 10.0);
+    }
+}
@@ -0,0 +1,133 @@
+use crate::view::MapView;
+use maybe_sync::MaybeSend;
+use std::sync::Mutex;
+use web_time::SystemTime;
+
+/// Information about a frame passed to the render hooks of a [`Map`](super::Map).
+#[derive(Debug, Clone)]
+pub struct FrameInfo {
+    /// Sequential number of the frame, starting from 0 for the first frame rendered for the map.
+    pub frame_number: u64,
+    /// Time the rendering of the frame started.
+    pub timestamp: SystemTime,
+    /// View of the map the frame is rendered with.
+    pub view: MapView,
+}
+
+/// Identifier of a render hook returned by [`Map::add_before_render_hook`](super::Map::add_before_render_hook) and
+/// [`Map::add_after_render_hook`](super::Map::add_after_render_hook). Can be used to remove the hook with
+/// [`Map::remove_render_hook`](super::Map::remove_render_hook).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct RenderHookId(u64);
+
+type RenderHook = Box<dyn FnMut(&FrameInfo) + MaybeSend>;
+
+#[derive(Default)]
+struct HookList {
+    before: Vec<(RenderHookId, RenderHook)>,
+    after: Vec<(RenderHookId, RenderHook)>,
+    next_id: u64,
+    frame_number: u64,
+}
+
+/// Callbacks called by renderers before and after every frame of the map.
+#[derive(Default)]
+pub(super) struct RenderHooks {
+    inner: Mutex<HookList>,
+}
+
+impl RenderHooks {
+    pub(super) fn add_before(&self, hook: RenderHook) -> RenderHookId {
+        let mut inner = self.inner.lock().expect("mutex is poisoned");
+        let id = inner.next_id();
+        inner.before.push((id, hook));
+        id
+    }
+
+    pub(super) fn add_after(&self, hook: RenderHook) -> RenderHookId {
+        let mut inner = self.inner.lock().expect("mutex is poisoned");
+        let id = inner.next_id();
+        inner.after.push((id, hook));
+        id
+    }
+
+    pub(super) fn remove(&self, id: RenderHookId) -> bool {
+        let mut inner = self.inner.lock().expect("mutex is poisoned");
+        let count = inner.before.len() + inner.after.len();
+        inner.before.retain(|(hook_id, _)| *hook_id != id);
+        inner.after.retain(|(hook_id, _)| *hook_id != id);
+        inner.before.len() + inner.after.len() != count
+    }
+
+    pub(super) fn begin_frame(&self, view: &MapView) -> FrameInfo {
+        let mut inner = self.inner.lock().expect("mutex is poisoned");
+        let frame = FrameInfo {
+            frame_number: inner.frame_number,
+            timestamp: SystemTime::now(),
+            view: view.clone(),
+        };
+        inner.frame_number += 1;
+
+        for (_, hook) in &mut inner.before {
+            hook(&frame);
+        }
+
+        frame
+    }
+
+    pub(super) fn end_frame(&self, frame: &FrameInfo) {
+        let mut inner = self.inner.lock().expect("mutex is poisoned");
+        for (_, hook) in &mut inner.after {
+            hook(frame);
+        }
+    }
+}
+
+impl HookList {
+    fn next_id(&mut self) -> RenderHookId {
+        self.next_id += 1;
+        RenderHookId(self.next_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use galileo_types::geo::impls::GeoPoint2d;
+    use std::sync::Arc;
+
+    #[test]
+    fn hooks_are_called_in_order() {
+        let hooks = RenderHooks::default();
+        let calls = Arc::new(Mutex::new(vec![]));
+
+        let before_calls = calls.clone();
+        hooks.add_before(Box::new(move |frame| {
+            before_calls
+                .lock()
+                .expect("mutex is poisoned")
+                .push(("before", frame.frame_number))
+        }));
+        let after_calls = calls.clone();
+        let after = hooks.add_after(Box::new(move |frame| {
+            after_calls
+                .lock()
+                .expect("mutex is poisoned")
+                .push(("after", frame.frame_number))
+        }));
+
+        let view = MapView::new(&GeoPoint2d::default(), 1.0);
+        let frame = hooks.begin_frame(&view);
+        hooks.end_frame(&frame);
+
+        assert!(hooks.remove(after));
+        assert!(!hooks.remove(after));
+        let frame = hooks.begin_frame(&view);
+        hooks.end_frame(&frame);
+
+        assert_eq!(
+            *calls.lock().expect("mutex is poisoned"),
+            vec![("before", 0), ("after", 0), ("before", 1)]
+        );
+    }
+}
@@ -0,0 +1,112 @@
+use crate::localization::Localizer;
+use crate::view::MapView;
+use galileo_types::cartesian::Point2d;
+use galileo_types::geo::GeoPoint;
+
+/// Mean radius of the Earth in meters.
+const EARTH_RADIUS: f64 = 6_371_008.8;
+
+/// Length and label of a scale bar for a map view.
+///
+/// The length of the bar is chosen so that it represents a round distance (1, 2 or 5 times a power of 10 in the
+/// units of the [`Localizer`]), and the label is formatted by the localizer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScaleBar {
+    /// Length of the bar in pixels.
+    pub width: f64,
+    /// Distance represented by the bar in meters.
+    pub distance: f64,
+    /// Label of the bar, e.g. `500 m`.
+    pub label: String,
+}
+
+impl ScaleBar {
+    /// Calculates the scale bar for the center of the view, which is not longer than `max_width` pixels.
+    ///
+    /// Returns `None` if the view has no geographic position, or the center of the view is outside the map. Maps in
+    /// the [planar](galileo_types::geo::Crs::PLANAR) CRS have no scale bar, since their units are not related to
+    /// distances on the ground.
+    pub fn for_view(view: &MapView, max_width: f64, localizer: &dyn Localizer) -> Option<Self> {
+        if view.crs().is_planar() {
+            return None;
+        }
+
+        let size = view.size();
+        let y = size.half_height();
+        let x = size.half_width();
+        let from = view.screen_to_map_geo(Point2d::new(x - max_width / 2.0, y))?;
+        let to = view.screen_to_map_geo(Point2d::new(x + max_width / 2.0, y))?;
+
+        let max_distance = haversine_distance(&from, &to);
+        if !max_distance.is_normal() {
+            return None;
+        }
+
+        let (unit_scale, _) = localizer.unit_system().distance_unit(max_distance);
+        let value = round_down(max_distance / unit_scale);
+        let distance = value * unit_scale;
+
+        Some(Self {
+            width: max_width * distance / max_distance,
+            distance,
+            label: localizer.format_distance(distance),
+        })
+    }
+}
+
+/// Returns the largest number of form 1, 2 or 5 times a power of 10 that is not greater than the value.
+fn round_down(value: f64) -> f64 {
+    let magnitude = 10f64.powf(value.log10().floor());
+    let leading = value / magnitude;
+    let rounded = if leading >= 5.0 {
+        5.0
+    } else if leading >= 2.0 {
+        2.0
+    } else {
+        1.0
+    };
+
+    rounded * magnitude
+}
+
+fn haversine_distance(a: &impl GeoPoint<Num = f64>, b: &impl GeoPoint<Num = f64>) -> f64 {
+    let (lat1, lat2) = (a.lat().to_radians(), b.lat().to_radians());
+    let d_lat = lat2 - lat1;
+    let d_lon = (b.lon() - a.lon()).to_radians();
+
+    let h = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS * h.sqrt().asin()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::localization::{Locale, UnitSystem};
+    use galileo_types::cartesian::Size;
+    use galileo_types::latlon;
+
+    #[test]
+    fn scale_bar_uses_round_distances() {
+        assert_eq!(round_down(7.3), 5.0);
+        assert!((round_down(0.031) - 0.02).abs() < 1e-12);
+        assert_eq!(round_down(1999.0), 1000.0);
+
+        // About 10 meters per pixel at the equator.
+        let view = MapView::new(&latlon!(0.0, 0.0), 10.0).with_size(Size::new(400.0, 300.0));
+
+        let metric = ScaleBar::for_view(&view, 100.0, &Locale::default()).expect("scale bar");
+        assert_eq!(metric.label, "500 m");
+        assert!((metric.width - 50.0).abs() < 0.5);
+
+        let imperial = ScaleBar::for_view(&view, 100.0, &Locale::new(UnitSystem::Imperial))
+            .expect("scale bar");
+        assert_eq!(imperial.label, "0.5 mi");
+    }
+
+    #[test]
+    fn planar_map_has_no_scale_bar() {
+        let view =
+            MapView::planar(&Point2d::new(500.0, 500.0), 1.0).with_size(Size::new(400.0, 300.0));
+        assert!(ScaleBar::for_view(&view, 100.0, &Locale::default()).is_none());
+    }
+}
@@ -0,0 +1,232 @@
+use crate::error::GalileoError;
+use crate::map::Map;
+use crate::view::MapView;
+use galileo_types::cartesian::Point2d;
+use galileo_types::geo::Crs;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use web_time::{SystemTime, UNIX_EPOCH};
+
+/// Complete state of a [`Map`] that can be stored between sessions of an application.
+///
+/// Unlike [`MapHashState`](super::MapHashState), which is short and human-readable, the session state keeps the exact
+/// projected position and resolution of the view in its CRS, the [time cursor](Map::time_cursor) and the visibility
+/// of every layer, so that the restored map looks exactly as it was left. With `serde` feature the state can be
+/// written in any serde format:
+///
+/// ```no_run
+/// # fn save(map: &mut galileo::Map) -> Result<(), Box<dyn std::error::Error>> {
+/// use galileo::MapSessionState;
+///
+/// let saved = serde_json::to_string(&MapSessionState::from_map(map))?;
+/// // ... next session
+/// let state: MapSessionState = serde_json::from_str(&saved)?;
+/// state.apply(map)?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// The state can also be kept in a persistent [`Storage`](crate::storage::Storage) with [`MapSessionState::save`] and
+/// [`MapSessionState::load`].
+///
+/// The state is versioned. States written by newer versions of the format are rejected by [`MapSessionState::apply`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MapSessionState {
+    /// Version of the state format.
+    pub version: u32,
+    /// CRS of the map view.
+    pub crs: Crs,
+    /// Position of the map center in the projected coordinates of the CRS. If not set, the position and CRS of the
+    /// map view are left unchanged on restore.
+    pub center: Option<[f64; 2]>,
+    /// Resolution of the map view.
+    pub resolution: f64,
+    /// Tilt of the map in radians.
+    pub rotation_x: f64,
+    /// Rotation of the map around the vertical axis in radians.
+    pub rotation_z: f64,
+    /// Time cursor of the map in milliseconds since the Unix epoch.
+    pub time_cursor: Option<i64>,
+    /// Visibility of the layers of the map, in the order of the layer collection.
+    pub layer_visibility: Vec<bool>,
+}
+
+impl MapSessionState {
+    /// Version of the state format written by this version of the library.
+    pub const CURRENT_VERSION: u32 = 1;
+
+    /// Namespace of a [`Storage`](crate::storage::Storage) the states are saved into by [`MapSessionState::save`].
+    pub const SESSION_NAMESPACE: &'static str = "session";
+
+    /// Reads the state of the map.
+    pub fn from_map(map: &Map) -> Self {
+        let view = map.view();
+        let layers = map.layers();
+
+        Self {
+            version: Self::CURRENT_VERSION,
+            crs: view.crs().clone(),
+            center: view.projected_position().map(|p| [p.x, p.y]),
+            resolution: view.resolution(),
+            rotation_x: view.rotation_x(),
+            rotation_z: view.rotation_z(),
+            time_cursor: map.time_cursor().map(to_unix_millis),
+            layer_visibility: layers
+                .ids()
+                .map(|id| layers.visibility(id) == Some(true))
+                .collect(),
+        }
+    }
+
+    /// Sets the view, time cursor and layer visibility of the map to this state.
+    ///
+    /// The size of the map view is not changed. If the map has different number of layers than the saved state,
+    /// visibility is restored only for the layers present in both.
+    ///
+    /// Returns an error if the state was written with a newer version of the format.
+    pub fn apply(&self, map: &mut Map) -> Result<(), GalileoError> {
+        if self.version > Self::CURRENT_VERSION {
+            return Err(GalileoError::Generic(format!(
+                "unsupported map session state version {}",
+                self.version
+            )));
+        }
+
+        let size = map.view().size();
+        let view = match self.center {
+            Some([x, y]) => MapView::new_projected_with_crs(
+                &Point2d::new(x, y),
+                self.resolution,
+                self.crs.clone(),
+            ),
+            None => map.view().with_resolution(self.resolution),
+        };
+        map.set_view(
+            view.with_size(size)
+                .with_rotation(self.rotation_x, self.rotation_z),
+        );
+        map.set_time_cursor(self.time_cursor.map(from_unix_millis));
+
+        let layers = map.layers_mut();
+        let ids: Vec<_> = layers.ids().collect();
+        for (id, visible) in ids.into_iter().zip(&self.layer_visibility) {
+            layers.set_visible(id, *visible);
+        }
+
+        Ok(())
+    }
+
+    /// Writes the state into the storage with the given key, in the [`SESSION_NAMESPACE`](Self::SESSION_NAMESPACE)
+    /// namespace. The state is stored as JSON.
+    #[cfg(feature = "serde")]
+    pub fn save(
+        &self,
+        storage: &dyn crate::storage::Storage,
+        key: &str,
+    ) -> Result<(), GalileoError> {
+        let data = serde_json::to_vec(self).map_err(|err| {
+            GalileoError::Generic(format!("failed to serialize map session state: {err}"))
+        })?;
+        storage.put(Self::SESSION_NAMESPACE, key, &data)
+    }
+
+    /// Reads the state saved with [`MapSessionState::save`] from the storage. Returns `None` if there is no state
+    /// saved with this key.
+    #[cfg(feature = "serde")]
+    pub fn load(
+        storage: &dyn crate::storage::Storage,
+        key: &str,
+    ) -> Result<Option<Self>, GalileoError> {
+        let Some(data) = storage.get(Self::SESSION_NAMESPACE, key)? else {
+            return Ok(None);
+        };
+
+        serde_json::from_slice(&data)
+            .map(Some)
+            .map_err(|err| GalileoError::Generic(format!("invalid map session state: {err}")))
+    }
+}
+
+fn to_unix_millis(time: SystemTime) -> i64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(duration) => duration.as_millis() as i64,
+        Err(err) => -(err.duration().as_millis() as i64),
+    }
+}
+
+fn from_unix_millis(millis: i64) -> SystemTime {
+    let duration = Duration::from_millis(millis.unsigned_abs());
+    if millis >= 0 {
+        UNIX_EPOCH + duration
+    } else {
+        UNIX_EPOCH - duration
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DummyMessenger;
+    use galileo_types::cartesian::Size;
+    use galileo_types::geo::impls::GeoPoint2d;
+    use galileo_types::geo::NewGeoPoint;
+
+    #[test]
+    fn session_state_roundtrip() {
+        let mut map = Map::new(
+            MapView::new(&GeoPoint2d::latlon(10.0, 20.0), 12.5)
+                .with_rotation(0.3, 1.2)
+                .with_size(Size::new(800.0, 600.0)),
+            vec![],
+            None::<DummyMessenger>,
+        );
+        let time = UNIX_EPOCH + Duration::from_millis(1_700_000_000_123);
+        map.set_time_cursor(Some(time));
+        let state = MapSessionState::from_map(&map);
+
+        let mut restored = Map::new(
+            MapView::new(&GeoPoint2d::default(), 1000.0).with_size(Size::new(300.0, 200.0)),
+            vec![],
+            None::<DummyMessenger>,
+        );
+        state.apply(&mut restored).expect("valid state");
+
+        assert_eq!(MapSessionState::from_map(&restored), state);
+        assert_eq!(restored.time_cursor(), Some(time));
+        assert_eq!(restored.view().size(), Size::new(300.0, 200.0));
+    }
+
+    #[test]
+    fn newer_version_is_rejected() {
+        let mut map = Map::new(
+            MapView::new(&GeoPoint2d::default(), 1000.0),
+            vec![],
+            None::<DummyMessenger>,
+        );
+        let mut state = MapSessionState::from_map(&map);
+        state.version = MapSessionState::CURRENT_VERSION + 1;
+
+        assert!(state.apply(&mut map).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn save_to_storage() {
+        let storage = crate::storage::MemoryStorage::default();
+        let map = Map::new(
+            MapView::new(&GeoPoint2d::latlon(10.0, 20.0), 12.5),
+            vec![],
+            None::<DummyMessenger>,
+        );
+        let state = MapSessionState::from_map(&map);
+
+        assert_eq!(MapSessionState::load(&storage, "main").unwrap(), None);
+        state.save(&storage, "main").unwrap();
+        assert_eq!(
+            MapSessionState::load(&storage, "main").unwrap(),
+            Some(state)
+        );
+    }
+}
@@ -0,0 +1,154 @@
+use crate::error::GalileoError;
+use crate::map::Map;
+use crate::tile_scheme::TileIndex;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Tiles recently drawn by the layers of a [`Map`], stored between sessions of an application.
+///
+/// When an application starts, tile layers have nothing to draw until the tiles are loaded, so the map flashes blank
+/// for a moment. Kiosk and dashboard applications usually show the same area every time, so they can save the
+/// manifest on shutdown and load the same tiles on the next start, before the first frame is drawn. If the layers
+/// use a persistent cache (e.g. [`FileCacheController`](crate::layer::data_provider::FileCacheController)), the tiles
+/// are read from the cache instead of the network.
+///
+/// ```no_run
+/// # fn save(map: &galileo::Map) -> Result<(), Box<dyn std::error::Error>> {
+/// use galileo::TileManifest;
+///
+/// std::fs::write("tiles.json", serde_json::to_string(&map.tile_manifest())?)?;
+/// // ... next session
+/// let manifest: TileManifest = serde_json::from_slice(&std::fs::read("tiles.json")?)?;
+/// map.warm_up_tiles(&manifest)?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`Map::warm_up_tiles`] starts loading the tiles in the background. To wait for the tiles of a
+/// [`RasterTileLayer`](crate::layer::RasterTileLayer) to load, call
+/// [`RasterTileLayer::load_tile_indices`](crate::layer::RasterTileLayer::load_tile_indices) instead.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TileManifest {
+    /// Version of the manifest format.
+    pub version: u32,
+    /// Recently drawn tiles of every layer of the map, in the order of the layer collection. Tiles of each layer are
+    /// ordered from the least to the most recently used.
+    pub layers: Vec<Vec<TileIndex>>,
+}
+
+impl TileManifest {
+    /// Version of the manifest format written by this version of the library.
+    pub const CURRENT_VERSION: u32 = 1;
+
+    /// Collects the recently drawn tiles of all the layers of the map (see [`Layer::recent_tiles`]).
+    ///
+    /// [`Layer::recent_tiles`]: crate::layer::Layer::recent_tiles
+    pub fn from_map(map: &Map) -> Self {
+        Self {
+            version: Self::CURRENT_VERSION,
+            layers: map
+                .layers()
+                .iter()
+                .map(|layer| layer.recent_tiles())
+                .collect(),
+        }
+    }
+
+    /// Starts loading the tiles of the manifest by the layers of the map (see [`Layer::warm_up`]).
+    ///
+    /// The layers are matched by their position in the layer collection. If the map has different number of layers
+    /// than the manifest, only the layers present in both are warmed up.
+    ///
+    /// Returns an error if the manifest was written with a newer version of the format.
+    ///
+    /// [`Layer::warm_up`]: crate::layer::Layer::warm_up
+    pub fn apply(&self, map: &Map) -> Result<(), GalileoError> {
+        if self.version > Self::CURRENT_VERSION {
+            return Err(GalileoError::Generic(format!(
+                "unsupported tile manifest version {}",
+                self.version
+            )));
+        }
+
+        for (layer, tiles) in map.layers().iter().zip(&self.layers) {
+            if !tiles.is_empty() {
+                layer.warm_up(tiles);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layer::Layer;
+    use crate::messenger::Messenger;
+    use crate::render::Canvas;
+    use crate::view::MapView;
+    use crate::DummyMessenger;
+    use galileo_types::geo::impls::GeoPoint2d;
+    use std::any::Any;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct TileLayer {
+        warmed_up: Mutex<Vec<TileIndex>>,
+    }
+
+    impl Layer for TileLayer {
+        fn render(&self, _view: &MapView, _canvas: &mut dyn Canvas) {}
+
+        fn prepare(&self, _view: &MapView) {}
+
+        fn set_messenger(&mut self, _messenger: Box<dyn Messenger>) {}
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+
+        fn as_any_mut(&mut self) -> &mut dyn Any {
+            self
+        }
+
+        fn recent_tiles(&self) -> Vec<TileIndex> {
+            vec![TileIndex::new(1, 2, 3)]
+        }
+
+        fn warm_up(&self, tiles: &[TileIndex]) {
+            self.warmed_up
+                .lock()
+                .expect("mutex is poisoned")
+                .extend_from_slice(tiles);
+        }
+    }
+
+    #[test]
+    fn manifest_warms_up_layers() {
+        let map = Map::new(
+            MapView::new(&GeoPoint2d::default(), 1.0),
+            vec![Box::new(TileLayer::default()) as Box<dyn Layer>],
+            None::<DummyMessenger>,
+        );
+        let manifest = TileManifest::from_map(&map);
+        assert_eq!(manifest.layers, vec![vec![TileIndex::new(1, 2, 3)]]);
+
+        manifest.apply(&map).expect("valid manifest");
+        let layer = map.layers()[0]
+            .as_any()
+            .downcast_ref::<TileLayer>()
+            .expect("layer type");
+        assert_eq!(
+            *layer.warmed_up.lock().expect("mutex is poisoned"),
+            vec![TileIndex::new(1, 2, 3)]
+        );
+
+        let newer = TileManifest {
+            version: TileManifest::CURRENT_VERSION + 1,
+            ..manifest
+        };
+        assert!(newer.apply(&map).is_err());
+    }
+}
@@ -0,0 +1,320 @@
+use crate::map::Map;
+use crate::view::MapView;
+use galileo_types::geo::impls::GeoPoint2d;
+use galileo_types::geo::{GeoPoint, NewGeoPoint};
+use nalgebra::Point3;
+use std::sync::{Arc, RwLock};
+
+/// Describes how the view of a map linked with [`ViewSync`] relates to the common view of the group.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ViewLink {
+    /// Resolution of the map is the resolution of the common view multiplied by this factor. E.g. an overview map
+    /// that shows 8 times larger area than the detail map would have the scale of `8.0`.
+    pub resolution_scale: f64,
+    /// Offset of the map center from the common center in degrees of latitude.
+    pub lat_offset: f64,
+    /// Offset of the map center from the common center in degrees of longitude.
+    pub lon_offset: f64,
+    /// If false, the rotation of the map is not synchronized.
+    pub sync_rotation: bool,
+    /// If false, the map only follows the other maps of the group, and changes of its own view are overwritten by
+    /// them.
+    pub leads: bool,
+}
+
+impl Default for ViewLink {
+    fn default() -> Self {
+        Self {
+            resolution_scale: 1.0,
+            lat_offset: 0.0,
+            lon_offset: 0.0,
+            sync_rotation: true,
+            leads: true,
+        }
+    }
+}
+
+impl ViewLink {
+    /// Returns a copy of the link with the given resolution scale.
+    pub fn with_resolution_scale(&self, resolution_scale: f64) -> Self {
+        Self {
+            resolution_scale,
+            ..*self
+        }
+    }
+
+    /// Returns a copy of the link with the given center offset in degrees.
+    pub fn with_offset(&self, lat_offset: f64, lon_offset: f64) -> Self {
+        Self {
+            lat_offset,
+            lon_offset,
+            ..*self
+        }
+    }
+
+    /// Returns a copy of the link that does or does not synchronize the rotation.
+    pub fn with_sync_rotation(&self, sync_rotation: bool) -> Self {
+        Self {
+            sync_rotation,
+            ..*self
+        }
+    }
+
+    /// Returns a copy of the link that does or does not let the map lead the group.
+    pub fn with_leads(&self, leads: bool) -> Self {
+        Self { leads, ..*self }
+    }
+
+    fn common_view(&self, view: &MapView) -> Option<CommonView> {
+        let position = view.position()?;
+        Some(CommonView {
+            center: GeoPoint2d::latlon(
+                position.lat() - self.lat_offset,
+                position.lon() - self.lon_offset,
+            ),
+            resolution: view.resolution() / self.resolution_scale,
+            rotation_x: view.rotation_x(),
+            rotation_z: view.rotation_z(),
+        })
+    }
+
+    fn apply_common(&self, common: &CommonView, current: &MapView) -> MapView {
+        let center = GeoPoint2d::latlon(
+            common.center.lat() + self.lat_offset,
+            common.center.lon() + self.lon_offset,
+        );
+        let view = current
+            .with_position(&center)
+            .with_resolution(common.resolution * self.resolution_scale);
+
+        if self.sync_rotation {
+            view.with_rotation(common.rotation_x, common.rotation_z)
+        } else {
+            view
+        }
+    }
+}
+
+/// View shared by the maps of a [`ViewSync`] group, before the [`ViewLink`] of a map is applied to it.
+struct CommonView {
+    center: GeoPoint2d,
+    resolution: f64,
+    rotation_x: f64,
+    rotation_z: f64,
+}
+
+/// Part of the view that is compared to find out if the view was changed.
+#[derive(Debug, PartialEq)]
+struct ViewState {
+    position: Option<Point3<f64>>,
+    resolution: f64,
+    rotation_x: f64,
+    rotation_z: f64,
+}
+
+impl From<&MapView> for ViewState {
+    fn from(view: &MapView) -> Self {
+        Self {
+            position: view.projected_position(),
+            resolution: view.resolution(),
+            rotation_x: view.rotation_x(),
+            rotation_z: view.rotation_z(),
+        }
+    }
+}
+
+struct LinkedMap {
+    map: Arc<RwLock<Map>>,
+    link: ViewLink,
+    synced_state: Option<ViewState>,
+}
+
+/// Links views of several maps, e.g. for side-by-side comparison or an overview map next to the detail map.
+///
+/// [`ViewSync::update`] should be called by the application before every frame is rendered. It finds a map whose view
+/// was changed since the last call (e.g. by the user), and moves all other maps to the same place, taking into account
+/// the [`ViewLink`] of each map. Views set by the `ViewSync` itself are remembered, so they are not propagated back
+/// to the map that led the change.
+///
+/// Maps are linked through geographic coordinates of their centers, so they can have different CRSs. The resolution
+/// is scaled as is, so maps with CRSs of different units should compensate for it with
+/// [`ViewLink::resolution_scale`].
+#[derive(Default)]
+pub struct ViewSync {
+    maps: Vec<LinkedMap>,
+}
+
+impl ViewSync {
+    /// Creates a new empty group.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a map to the group. The map is moved to the view of the group on the next update.
+    pub fn add_map(&mut self, map: Arc<RwLock<Map>>, link: ViewLink) {
+        self.maps.push(LinkedMap {
+            map,
+            link,
+            synced_state: None,
+        });
+    }
+
+    /// Returns a copy of the group with the map added to it.
+    pub fn with_map(mut self, map: Arc<RwLock<Map>>, link: ViewLink) -> Self {
+        self.add_map(map, link);
+        self
+    }
+
+    /// Removes the map from the group. Returns false if the map was not in the group.
+    pub fn remove_map(&mut self, map: &Arc<RwLock<Map>>) -> bool {
+        let count = self.maps.len();
+        self.maps.retain(|linked| !Arc::ptr_eq(&linked.map, map));
+        self.maps.len() != count
+    }
+
+    /// Number of maps in the group.
+    pub fn len(&self) -> usize {
+        self.maps.len()
+    }
+
+    /// Returns true if there are no maps in the group.
+    pub fn is_empty(&self) -> bool {
+        self.maps.is_empty()
+    }
+
+    /// Propagates the view of the changed map to other maps of the group. Returns the index of the map that led the
+    /// change, if any.
+    ///
+    /// If several maps were changed since the last update, the first of them in the order they were added leads. Maps
+    /// that were just added to the group follow the first leading map.
+    pub fn update(&mut self) -> Option<usize> {
+        let leader = self
+            .maps
+            .iter()
+            .position(|linked| linked.link.leads && linked.is_changed())
+            .or_else(|| {
+                // Newly added maps have no synced state, they are aligned with the rest of the group.
+                self.maps
+                    .iter()
+                    .any(|linked| linked.synced_state.is_none())
+                    .then(|| self.maps.iter().position(|linked| linked.link.leads))
+                    .flatten()
+            })?;
+
+        let common = {
+            let linked = &mut self.maps[leader];
+            let map = linked.map.read().expect("lock is poisoned");
+            let view = map.view();
+            linked.synced_state = Some(view.into());
+            linked.link.common_view(view)
+        }?;
+
+        for (index, linked) in self.maps.iter_mut().enumerate() {
+            if index == leader {
+                continue;
+            }
+
+            let mut map = linked.map.write().expect("lock is poisoned");
+            let view = linked.link.apply_common(&common, map.view());
+            linked.synced_state = Some((&view).into());
+            map.set_view(view);
+        }
+
+        Some(leader)
+    }
+}
+
+impl LinkedMap {
+    fn is_changed(&self) -> bool {
+        let map = self.map.read().expect("lock is poisoned");
+        self.synced_state.as_ref() != Some(&map.view().into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DummyMessenger;
+    use galileo_types::latlon;
+
+    fn map(lat: f64, lon: f64, resolution: f64) -> Arc<RwLock<Map>> {
+        Arc::new(RwLock::new(Map::new(
+            MapView::new(&latlon!(lat, lon), resolution),
+            vec![],
+            None::<DummyMessenger>,
+        )))
+    }
+
+    fn center(map: &Arc<RwLock<Map>>) -> GeoPoint2d {
+        map.read()
+            .expect("lock is poisoned")
+            .view()
+            .position()
+            .expect("view has position")
+    }
+
+    #[test]
+    fn views_are_synced_both_ways_without_loops() {
+        let detail = map(10.0, 20.0, 10.0);
+        let overview = map(0.0, 0.0, 1.0);
+        let mut sync = ViewSync::new()
+            .with_map(detail.clone(), ViewLink::default())
+            .with_map(
+                overview.clone(),
+                ViewLink::default().with_resolution_scale(8.0),
+            );
+
+        assert_eq!(sync.update(), Some(0));
+        assert!((center(&overview).lat() - 10.0).abs() < 1e-6);
+        assert!(
+            (overview
+                .read()
+                .expect("lock is poisoned")
+                .view()
+                .resolution()
+                - 80.0)
+                .abs()
+                < 1e-9
+        );
+
+        // Nothing changed, so nothing is propagated.
+        assert_eq!(sync.update(), None);
+
+        {
+            let mut map = overview.write().expect("lock is poisoned");
+            let view = map.view().with_position(&latlon!(-5.0, 30.0));
+            map.set_view(view);
+        }
+        assert_eq!(sync.update(), Some(1));
+        assert!((center(&detail).lat() + 5.0).abs() < 1e-6);
+        assert!((center(&detail).lon() - 30.0).abs() < 1e-6);
+        assert!((detail.read().expect("lock is poisoned").view().resolution() - 10.0).abs() < 1e-9);
+        assert_eq!(sync.update(), None);
+    }
+
+    #[test]
+    fn follower_does_not_lead() {
+        let main = map(0.0, 0.0, 10.0);
+        let follower = map(0.0, 0.0, 10.0);
+        let mut sync = ViewSync::new()
+            .with_map(main.clone(), ViewLink::default().with_offset(0.0, 1.0))
+            .with_map(follower.clone(), ViewLink::default().with_leads(false));
+        sync.update();
+
+        {
+            let mut map = follower.write().expect("lock is poisoned");
+            let view = map.view().with_position(&latlon!(45.0, 45.0));
+            map.set_view(view);
+        }
+        assert_eq!(sync.update(), None);
+        assert!((center(&follower).lon() - 45.0).abs() < 1e-6);
+
+        {
+            let mut map = main.write().expect("lock is poisoned");
+            let view = map.view().with_position(&latlon!(1.0, 2.0));
+            map.set_view(view);
+        }
+        assert_eq!(sync.update(), Some(0));
+        assert!((center(&follower).lon() - 1.0).abs() < 1e-6);
+    }
+}
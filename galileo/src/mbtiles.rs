@@ -0,0 +1,320 @@
+//! Export of the map into an [MBTiles](https://github.com/mapbox/mbtiles-spec) archive for offline use.
+//!
+//! Unlike downloading the tiles of a base map, [`MbTilesExport`] renders all the visible layers of the map (including
+//! feature layers and other overlays) into composited raster tiles, so the archive looks exactly like the map on the
+//! screen.
+//!
+//! ```no_run
+//! # use galileo::mbtiles::MbTilesExport;
+//! # async fn export(map: &mut galileo::Map) -> Result<(), galileo::error::GalileoError> {
+//! let export = MbTilesExport::from_view(map.view(), 16)
+//!     .expect("view has a bounding box")
+//!     .with_name("My trip");
+//! let tiles_written = export.export(map, "my_trip.mbtiles").await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::GalileoError;
+use crate::render::WgpuRenderer;
+use crate::tile_scheme::{TileIndex, TileSchema};
+use crate::view::MapView;
+use crate::Map;
+use galileo_types::cartesian::{Point2d, Rect, Size};
+use galileo_types::geo::impls::GeoPoint2d;
+use galileo_types::geo::{Crs, GeoPoint};
+use image::codecs::png::PngEncoder;
+use image::{ColorType, ImageEncoder};
+use rusqlite::{params, Connection};
+use std::path::Path;
+use std::time::Duration;
+
+/// Number of zoom levels of the web tile schema used for the export.
+const MAX_LODS: u32 = 23;
+
+/// Default time given to the layers to load their data for each exported tile.
+const DEFAULT_LOAD_WAIT: Duration = Duration::from_millis(500);
+
+/// Parameters of export of a [`Map`] into an MBTiles archive.
+///
+/// Tiles are rendered with the standard web tile schema ([`TileSchema::web`]), so the map must use `EPSG:3857` CRS.
+///
+/// The layers of the map load their data asynchronously, so for every tile the exporter calls [`Map::load_layers`]
+/// and then waits for [`MbTilesExport::with_load_wait`] before rendering the tile. Raster tile layers should have
+/// their fade in duration set to zero, otherwise the tiles are exported half-transparent.
+#[derive(Debug, Clone)]
+pub struct MbTilesExport {
+    bbox: Rect,
+    min_zoom: u32,
+    max_zoom: u32,
+    name: String,
+    load_wait: Duration,
+}
+
+impl MbTilesExport {
+    /// Creates a new export of the area `bbox` (in `EPSG:3857` coordinates) for the zoom levels from `min_zoom` to
+    /// `max_zoom` inclusive.
+    pub fn new(bbox: Rect, min_zoom: u32, max_zoom: u32) -> Self {
+        let max_zoom = max_zoom.min(MAX_LODS - 1);
+        Self {
+            bbox,
+            min_zoom: min_zoom.min(max_zoom),
+            max_zoom,
+            name: "galileo".into(),
+            load_wait: DEFAULT_LOAD_WAIT,
+        }
+    }
+
+    /// Creates an export of the area currently visible in the `view`, starting from the zoom level closest to the
+    /// view resolution up to `max_zoom`.
+    ///
+    /// Returns `None` if the view does not have a bounding box (e.g. its size is not set).
+    pub fn from_view(view: &MapView, max_zoom: u32) -> Option<Self> {
+        let bbox = view.get_bbox()?;
+        let min_zoom = TileSchema::web(MAX_LODS)
+            .select_lod(view.logical_resolution())?
+            .z_index();
+
+        Some(Self::new(bbox, min_zoom, max_zoom))
+    }
+
+    /// Sets the name of the tileset stored in the archive metadata.
+    pub fn with_name(&self, name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ..self.clone()
+        }
+    }
+
+    /// Sets the time given to the layers to load their data before each tile is rendered.
+    pub fn with_load_wait(&self, load_wait: Duration) -> Self {
+        Self {
+            load_wait,
+            ..self.clone()
+        }
+    }
+
+    /// Returns indices of all the tiles that will be exported.
+    pub fn tiles(&self) -> impl Iterator<Item = TileIndex> + '_ {
+        let schema = TileSchema::web(MAX_LODS);
+        (self.min_zoom..=self.max_zoom).flat_map(move |z| {
+            schema
+                .lod_resolution(z)
+                .and_then(|resolution| schema.iter_tiles_over_bbox(resolution, self.bbox))
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>()
+        })
+    }
+
+    /// Renders the visible layers of the `map` into tiles and writes them into a new MBTiles file at `path`. Returns
+    /// the number of written tiles.
+    ///
+    /// The view of the map is changed during the export and restored when it is done.
+    pub async fn export(
+        &self,
+        map: &mut Map,
+        path: impl AsRef<Path>,
+    ) -> Result<usize, GalileoError> {
+        let schema = TileSchema::web(MAX_LODS);
+        let tile_size = Size::new(schema.tile_width(), schema.tile_height());
+        let renderer = WgpuRenderer::new_with_texture_rt(tile_size)
+            .await
+            .ok_or_else(|| GalileoError::Generic("failed to create headless renderer".into()))?;
+
+        let writer = MbTilesWriter::create(path)?;
+        writer.write_metadata(self)?;
+
+        let original_view = map.view().clone();
+        let result = self.render_tiles(map, &renderer, &schema, &writer).await;
+        map.set_view(original_view);
+
+        result
+    }
+
+    async fn render_tiles(
+        &self,
+        map: &mut Map,
+        renderer: &WgpuRenderer,
+        schema: &TileSchema,
+        writer: &MbTilesWriter,
+    ) -> Result<usize, GalileoError> {
+        let mut count = 0;
+        for index in self.tiles() {
+            let (Some(bbox), Some(resolution)) =
+                (schema.tile_bbox(index), schema.lod_resolution(index.z))
+            else {
+                continue;
+            };
+
+            let view = MapView::new_projected(&bbox.center(), resolution).with_size(Size::new(
+                schema.tile_width() as f64,
+                schema.tile_height() as f64,
+            ));
+            map.set_view(view);
+            map.load_layers();
+            tokio::time::sleep(self.load_wait).await;
+
+            renderer
+                .render(map)
+                .map_err(|err| GalileoError::Generic(format!("failed to render tile: {err}")))?;
+            let image = renderer.get_image().await.map_err(|err| {
+                GalileoError::Generic(format!("failed to read tile image: {err}"))
+            })?;
+
+            writer.write_tile(
+                index,
+                &encode_png(&image, schema.tile_width(), schema.tile_height())?,
+            )?;
+            count += 1;
+        }
+
+        Ok(count)
+    }
+
+    /// Bounds of the exported area in degrees as `(west, south, east, north)`.
+    fn geo_bounds(&self) -> Option<(f64, f64, f64, f64)> {
+        let projection = Crs::EPSG3857.get_projection::<GeoPoint2d, Point2d>()?;
+        let min = projection.unproject(&Point2d::new(self.bbox.x_min(), self.bbox.y_min()))?;
+        let max = projection.unproject(&Point2d::new(self.bbox.x_max(), self.bbox.y_max()))?;
+
+        Some((min.lon(), min.lat(), max.lon(), max.lat()))
+    }
+}
+
+fn encode_png(rgba: &[u8], width: u32, height: u32) -> Result<Vec<u8>, GalileoError> {
+    let mut bytes = vec![];
+    PngEncoder::new(&mut bytes)
+        .write_image(rgba, width, height, ColorType::Rgba8)
+        .map_err(|err| GalileoError::Generic(format!("failed to encode tile image: {err}")))?;
+
+    Ok(bytes)
+}
+
+fn sqlite_error(err: rusqlite::Error) -> GalileoError {
+    GalileoError::Generic(format!("failed to write MBTiles archive: {err}"))
+}
+
+struct MbTilesWriter {
+    connection: Connection,
+}
+
+impl MbTilesWriter {
+    fn create(path: impl AsRef<Path>) -> Result<Self, GalileoError> {
+        let path = path.as_ref();
+        if path.exists() {
+            std::fs::remove_file(path)?;
+        }
+
+        Self::new(Connection::open(path).map_err(sqlite_error)?)
+    }
+
+    fn new(connection: Connection) -> Result<Self, GalileoError> {
+        connection
+            .execute_batch(
+                "CREATE TABLE metadata (name TEXT, value TEXT);
+                CREATE TABLE tiles (zoom_level INTEGER, tile_column INTEGER, tile_row INTEGER, tile_data BLOB);
+                CREATE UNIQUE INDEX tile_index ON tiles (zoom_level, tile_column, tile_row);",
+            )
+            .map_err(sqlite_error)?;
+
+        Ok(Self { connection })
+    }
+
+    fn write_metadata(&self, export: &MbTilesExport) -> Result<(), GalileoError> {
+        let mut metadata = vec![
+            ("name", export.name.clone()),
+            ("format", "png".to_string()),
+            ("type", "baselayer".to_string()),
+            ("minzoom", export.min_zoom.to_string()),
+            ("maxzoom", export.max_zoom.to_string()),
+        ];
+
+        if let Some((west, south, east, north)) = export.geo_bounds() {
+            metadata.push(("bounds", format!("{west},{south},{east},{north}")));
+            metadata.push((
+                "center",
+                format!(
+                    "{},{},{}",
+                    (west + east) / 2.0,
+                    (south + north) / 2.0,
+                    export.min_zoom
+                ),
+            ));
+        }
+
+        for (name, value) in metadata {
+            self.connection
+                .execute(
+                    "INSERT INTO metadata (name, value) VALUES (?1, ?2)",
+                    params![name, value],
+                )
+                .map_err(sqlite_error)?;
+        }
+
+        Ok(())
+    }
+
+    fn write_tile(&self, index: TileIndex, data: &[u8]) -> Result<(), GalileoError> {
+        self.connection
+            .execute(
+                "INSERT OR REPLACE INTO tiles (zoom_level, tile_column, tile_row, tile_data) VALUES (?1, ?2, ?3, ?4)",
+                params![index.z, index.x, tms_row(index), data],
+            )
+            .map_err(sqlite_error)?;
+
+        Ok(())
+    }
+}
+
+/// MBTiles uses TMS tile rows, which are counted from the bottom of the map.
+fn tms_row(index: TileIndex) -> i32 {
+    (1 << index.z) - 1 - index.y
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tiles_cover_bbox_on_all_zoom_levels() {
+        let export = MbTilesExport::new(Rect::new(-1.0, -1.0, 1.0, 1.0), 0, 2);
+        let tiles: Vec<_> = export.tiles().collect();
+
+        assert_eq!(tiles.iter().filter(|i| i.z == 0).count(), 1);
+        assert_eq!(tiles.iter().filter(|i| i.z == 1).count(), 4);
+        assert_eq!(tiles.iter().filter(|i| i.z == 2).count(), 4);
+    }
+
+    #[test]
+    fn writes_tiles_with_tms_rows() {
+        let writer = MbTilesWriter::new(Connection::open_in_memory().expect("in-memory db"))
+            .expect("schema created");
+        let export = MbTilesExport::new(Rect::new(-1.0, -1.0, 1.0, 1.0), 0, 1);
+        writer.write_metadata(&export).expect("metadata written");
+        writer
+            .write_tile(TileIndex::new(0, 0, 1), &[1, 2, 3])
+            .expect("tile written");
+
+        let (row, data): (i32, Vec<u8>) = writer
+            .connection
+            .query_row(
+                "SELECT tile_row, tile_data FROM tiles WHERE zoom_level = 1 AND tile_column = 0",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .expect("tile stored");
+        assert_eq!(row, 1);
+        assert_eq!(data, vec![1, 2, 3]);
+
+        let format: String = writer
+            .connection
+            .query_row(
+                "SELECT value FROM metadata WHERE name = 'format'",
+                [],
+                |row| row.get(0),
+            )
+            .expect("metadata stored");
+        assert_eq!(format, "png");
+    }
+}
@@ -1,7 +1,21 @@
+use crate::map::LayerId;
+use std::sync::Arc;
+
 /// Messenger used to notifiy application when the map requires update.
 pub trait Messenger: Send + Sync {
     /// Notifies the application that the map requires an update.
     fn request_redraw(&self);
+
+    /// Notifies the application that the map requires an update because the layer with the given id has changed
+    /// (e.g. a tile of the layer was loaded).
+    ///
+    /// Layers of a [`LayerCollection`](crate::LayerCollection) call this method through the [`LayerMessenger`] set by
+    /// [`LayerCollection::set_messenger`](crate::LayerCollection::set_messenger). The default implementation calls
+    /// [`Messenger::request_redraw`].
+    fn request_layer_redraw(&self, layer: LayerId) {
+        let _ = layer;
+        self.request_redraw();
+    }
 }
 
 /// Empty struct used for generic disambiguation.
@@ -11,3 +25,79 @@ impl Messenger for DummyMessenger {
         // do nothing
     }
 }
+
+impl<T: Messenger + ?Sized> Messenger for Arc<T> {
+    fn request_redraw(&self) {
+        (**self).request_redraw()
+    }
+
+    fn request_layer_redraw(&self, layer: LayerId) {
+        (**self).request_layer_redraw(layer)
+    }
+}
+
+/// Messenger given to a layer of a [`LayerCollection`](crate::LayerCollection). Redraw requests of the layer are
+/// forwarded to the map messenger with [`Messenger::request_layer_redraw`], so the application knows which layer
+/// has changed.
+#[derive(Clone)]
+pub struct LayerMessenger {
+    layer: LayerId,
+    inner: Arc<dyn Messenger>,
+}
+
+impl LayerMessenger {
+    /// Creates a messenger for the layer with the given id.
+    pub fn new(layer: LayerId, inner: Arc<dyn Messenger>) -> Self {
+        Self { layer, inner }
+    }
+
+    /// Id of the layer the messenger belongs to.
+    pub fn layer(&self) -> LayerId {
+        self.layer
+    }
+}
+
+impl Messenger for LayerMessenger {
+    fn request_redraw(&self) {
+        self.inner.request_layer_redraw(self.layer);
+    }
+
+    fn request_layer_redraw(&self, layer: LayerId) {
+        self.inner.request_layer_redraw(layer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct RecordingMessenger(Mutex<Vec<Option<LayerId>>>);
+
+    impl Messenger for RecordingMessenger {
+        fn request_redraw(&self) {
+            self.0.lock().expect("mutex is poisoned").push(None);
+        }
+
+        fn request_layer_redraw(&self, layer: LayerId) {
+            self.0.lock().expect("mutex is poisoned").push(Some(layer));
+        }
+    }
+
+    #[test]
+    fn layer_messenger_reports_layer_id() {
+        let recorder = Arc::new(RecordingMessenger::default());
+        let id = LayerId::from_raw(42);
+        let messenger = LayerMessenger::new(id, recorder.clone());
+
+        messenger.request_redraw();
+        recorder.request_redraw();
+        DummyMessenger {}.request_layer_redraw(id);
+
+        assert_eq!(
+            *recorder.0.lock().expect("mutex is poisoned"),
+            vec![Some(id), None]
+        );
+    }
+}
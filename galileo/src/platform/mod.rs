@@ -3,6 +3,7 @@
 use crate::decoded_image::DecodedImage;
 use crate::error::GalileoError;
 use async_trait::async_trait;
+use std::ops::Range;
 
 /// Service providing some platform specific functions in a generic way.
 #[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
@@ -15,6 +16,26 @@ pub trait PlatformService {
     async fn load_image_url(&self, url: &str) -> Result<DecodedImage, GalileoError>;
     /// Loads a byte array from the given url.
     async fn load_bytes_from_url(&self, url: &str) -> Result<bytes::Bytes, GalileoError>;
+    /// Loads the given byte range of the resource at the url with an HTTP range request. If the range extends past the
+    /// end of the resource, the returned data is shorter.
+    ///
+    /// Default implementation loads the whole resource and returns the requested part of it.
+    async fn load_bytes_range_from_url(
+        &self,
+        url: &str,
+        range: Range<u64>,
+    ) -> Result<bytes::Bytes, GalileoError> {
+        let bytes = self.load_bytes_from_url(url).await?;
+        Ok(slice_range(&bytes, range))
+    }
+}
+
+/// Returns the part of the `bytes` in the `range`, clamped to the length of the `bytes`.
+pub(crate) fn slice_range(bytes: &bytes::Bytes, range: Range<u64>) -> bytes::Bytes {
+    let len = bytes.len() as u64;
+    let start = range.start.min(len) as usize;
+    let end = range.end.clamp(range.start, len) as usize;
+    bytes.slice(start..end)
 }
 
 #[cfg(not(target_arch = "wasm32"))]
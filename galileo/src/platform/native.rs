@@ -2,10 +2,13 @@
 
 use crate::decoded_image::DecodedImage;
 use crate::error::GalileoError;
-use crate::platform::PlatformService;
+use crate::platform::{slice_range, PlatformService};
 use async_trait::async_trait;
 use bytes::Bytes;
 use log::info;
+use reqwest::header::RANGE;
+use reqwest::StatusCode;
+use std::ops::Range;
 
 pub mod map_builder;
 pub mod vt_processor;
@@ -35,6 +38,33 @@ impl PlatformService for NativePlatformService {
     async fn load_bytes_from_url(&self, url: &str) -> Result<Bytes, GalileoError> {
         self.load_from_web(url).await
     }
+
+    async fn load_bytes_range_from_url(
+        &self,
+        url: &str,
+        range: Range<u64>,
+    ) -> Result<Bytes, GalileoError> {
+        if range.is_empty() {
+            return Ok(Bytes::new());
+        }
+
+        let response = self
+            .http_client
+            .get(url)
+            .header(RANGE, format!("bytes={}-{}", range.start, range.end - 1))
+            .send()
+            .await?;
+        match response.status() {
+            StatusCode::PARTIAL_CONTENT => Ok(response.bytes().await?),
+            // The server does not support range requests and returned the whole resource.
+            StatusCode::OK => Ok(slice_range(&response.bytes().await?, range)),
+            StatusCode::RANGE_NOT_SATISFIABLE => Ok(Bytes::new()),
+            status => {
+                info!("Failed to load range {range:?} of {url}: {status}");
+                Err(GalileoError::IO)
+            }
+        }
+    }
 }
 
 impl NativePlatformService {
@@ -2,11 +2,12 @@
 
 use crate::decoded_image::DecodedImage;
 use crate::error::GalileoError;
-use crate::platform::PlatformService;
+use crate::platform::{slice_range, PlatformService};
 use async_trait::async_trait;
 use js_sys::Uint8Array;
 use std::cell::Cell;
 use std::future::Future;
+use std::ops::Range;
 use std::pin::Pin;
 use std::rc::Rc;
 use std::task::{Context, Poll};
@@ -19,6 +20,7 @@ use web_sys::{
 };
 
 pub mod map_builder;
+pub mod url_hash;
 pub mod vt_processor;
 pub mod web_workers;
 
@@ -72,38 +74,66 @@ impl PlatformService for WebPlatformService {
     }
 
     async fn load_bytes_from_url(&self, url: &str) -> Result<bytes::Bytes, GalileoError> {
-        let opts = RequestInit::new();
-        opts.set_method("GET");
-        opts.set_mode(RequestMode::Cors);
-
-        let request =
-            Request::new_with_str_and_init(url, &opts).expect("failed to create a request object");
-        request
-            .headers()
-            .set("Accept", "application/vnd.mapbox-vector-tile")?;
-
-        use wasm_bindgen::JsCast;
-        let resp_value = {
-            if let Some(window) = web_sys::window() {
-                JsFuture::from(window.fetch_with_request(&request)).await?
-            } else if let Ok(global) = js_sys::global().dyn_into::<WorkerGlobalScope>() {
-                JsFuture::from(global.fetch_with_request(&request)).await?
-            } else {
-                return Err(GalileoError::Wasm(Some(
-                    "Global object is not available".into(),
-                )));
-            }
-        };
+        let (_, bytes) = fetch_bytes(url, None).await?;
+        Ok(bytes)
+    }
 
-        assert!(resp_value.is_instance_of::<Response>());
-        let resp: Response = resp_value.dyn_into()?;
+    async fn load_bytes_range_from_url(
+        &self,
+        url: &str,
+        range: Range<u64>,
+    ) -> Result<bytes::Bytes, GalileoError> {
+        if range.is_empty() {
+            return Ok(bytes::Bytes::new());
+        }
 
-        let bytes_val = JsFuture::from(resp.array_buffer()?).await?;
-        let array = Uint8Array::new(&bytes_val);
-        Ok(array.to_vec().into())
+        let header = format!("bytes={}-{}", range.start, range.end - 1);
+        match fetch_bytes(url, Some(&header)).await? {
+            (206, bytes) => Ok(bytes),
+            // The server does not support range requests and returned the whole resource.
+            (200, bytes) => Ok(slice_range(&bytes, range)),
+            (416, _) => Ok(bytes::Bytes::new()),
+            _ => Err(GalileoError::IO),
+        }
     }
 }
 
+/// Fetches the resource at the url, optionally with the given `Range` header. Returns the status code of the response
+/// and its body.
+async fn fetch_bytes(url: &str, range: Option<&str>) -> Result<(u16, bytes::Bytes), GalileoError> {
+    let opts = RequestInit::new();
+    opts.set_method("GET");
+    opts.set_mode(RequestMode::Cors);
+
+    let request =
+        Request::new_with_str_and_init(url, &opts).expect("failed to create a request object");
+    request
+        .headers()
+        .set("Accept", "application/vnd.mapbox-vector-tile")?;
+    if let Some(range) = range {
+        request.headers().set("Range", range)?;
+    }
+
+    let resp_value = {
+        if let Some(window) = web_sys::window() {
+            JsFuture::from(window.fetch_with_request(&request)).await?
+        } else if let Ok(global) = js_sys::global().dyn_into::<WorkerGlobalScope>() {
+            JsFuture::from(global.fetch_with_request(&request)).await?
+        } else {
+            return Err(GalileoError::Wasm(Some(
+                "Global object is not available".into(),
+            )));
+        }
+    };
+
+    assert!(resp_value.is_instance_of::<Response>());
+    let resp: Response = resp_value.dyn_into()?;
+
+    let bytes_val = JsFuture::from(resp.array_buffer()?).await?;
+    let array = Uint8Array::new(&bytes_val);
+    Ok((resp.status(), array.to_vec().into()))
+}
+
 /// Future for getting image with browser API
 pub struct ImageFuture {
     image: Option<HtmlImageElement>,
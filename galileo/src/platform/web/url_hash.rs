@@ -0,0 +1,109 @@
+//! Synchronization of the map state with the URL of the page.
+
+use crate::error::GalileoError;
+use crate::map::{Map, MapHashState};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::{Arc, RwLock};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+const EVENTS: [&str; 2] = ["popstate", "hashchange"];
+
+/// Keeps the fragment part of the page URL in sync with the state of the map (see [`MapHashState`]), so that the
+/// page link can be shared and opens the map at the same place.
+///
+/// When created, the map state is restored from the current URL. After that [`UrlHashSync::update`] writes the map
+/// state into the URL (without adding history entries), and navigation in the browser history or manual editing of
+/// the URL moves the map.
+pub struct UrlHashSync {
+    map: Arc<RwLock<Map>>,
+    last_hash: Rc<RefCell<String>>,
+    listener: Closure<dyn FnMut()>,
+}
+
+impl UrlHashSync {
+    /// Restores the map state from the URL and starts listening for the URL changes.
+    pub fn new(map: Arc<RwLock<Map>>) -> Result<Self, GalileoError> {
+        let window = web_sys::window().ok_or(GalileoError::Wasm(Some(
+            "Window element is not available".into(),
+        )))?;
+
+        let last_hash = Rc::new(RefCell::new(String::new()));
+        let listener = {
+            let map = map.clone();
+            let last_hash = last_hash.clone();
+            Closure::wrap(Box::new(move || restore(&map, &last_hash)) as Box<dyn FnMut()>)
+        };
+
+        for event in EVENTS {
+            window.add_event_listener_with_callback(event, listener.as_ref().unchecked_ref())?;
+        }
+
+        restore(&map, &last_hash);
+
+        Ok(Self {
+            map,
+            last_hash,
+            listener,
+        })
+    }
+
+    /// Writes the current state of the map into the URL, if it has changed since the last call.
+    pub fn update(&self) {
+        let Some(state) = MapHashState::from_map(&self.map.read().expect("lock is poisoned"))
+        else {
+            return;
+        };
+        let Some(window) = web_sys::window() else {
+            return;
+        };
+
+        let current = window.location().hash().unwrap_or_default();
+        let hash = format!("#{}", state.merge_into_hash(&current));
+        if hash == *self.last_hash.borrow() {
+            return;
+        }
+
+        match window.history() {
+            Ok(history) => {
+                if let Err(err) = history.replace_state_with_url(&JsValue::NULL, "", Some(&hash)) {
+                    log::warn!("Failed to update page URL: {err:?}");
+                }
+            }
+            Err(err) => log::warn!("Browser history is not available: {err:?}"),
+        }
+
+        *self.last_hash.borrow_mut() = hash;
+    }
+}
+
+impl Drop for UrlHashSync {
+    fn drop(&mut self) {
+        if let Some(window) = web_sys::window() {
+            for event in EVENTS {
+                let _ = window.remove_event_listener_with_callback(
+                    event,
+                    self.listener.as_ref().unchecked_ref(),
+                );
+            }
+        }
+    }
+}
+
+fn restore(map: &RwLock<Map>, last_hash: &RefCell<String>) {
+    let Some(hash) = web_sys::window().and_then(|window| window.location().hash().ok()) else {
+        return;
+    };
+
+    // Changes made by `UrlHashSync::update` itself.
+    if hash == *last_hash.borrow() {
+        return;
+    }
+
+    if let Some(state) = MapHashState::from_hash(&hash) {
+        state.apply(&mut map.write().expect("lock is poisoned"));
+    }
+
+    *last_hash.borrow_mut() = hash;
+}
@@ -0,0 +1,77 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+static NEXT_SHADER_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Custom WGSL code that replaces the coloring of the primitives of a layer, e.g. to apply a thermal colormap to a
+/// raster layer or to make selected features glow.
+///
+/// The code must define the function
+///
+/// ```wgsl
+/// fn custom_fragment(color: vec4<f32>, info: FragmentInfo) -> vec4<f32>
+/// ```
+///
+/// that is called for every fragment of polygons, lines and images of the layer. `color` is the color the fragment
+/// would have with the default shader (in linear color space, with the opacity of the primitive and of the
+/// [render options](super::RenderOptions) applied). The returned color is expected in linear color space too, and is
+/// converted to the color space of the render target by the renderer.
+///
+/// The following declarations are available to the code:
+/// * `struct FragmentInfo { position: vec4<f32>, tex_coord: vec2<f32> }` - position of the fragment in framebuffer
+///   coordinates and texture coordinates (zero for polygons and lines).
+/// * `fn srgb_to_linear(color: vec4<f32>) -> vec4<f32>` and `fn linear_to_srgb(color: vec4<f32>) -> vec4<f32>` -
+///   color space conversions.
+/// * Bind group 0, binding 0: `var<uniform> transform: ViewUniform`, where
+///   `struct ViewUniform { view_proj: mat4x4<f32>, view_rotation: mat4x4<f32>, inv_screen_size: vec2<f32>,
+///   resolution: f32, opacity: f32 }`.
+/// * For images, bind group 1: `t_diffuse: texture_2d<f32>` (binding 0) and `s_diffuse: sampler` (binding 1) - the
+///   image texture and its sampler.
+///
+/// The code must not declare other bindings. Points, markers and labels are always drawn with the default shaders.
+///
+/// ```
+/// use galileo::render::CustomShader;
+///
+/// // Grayscale raster.
+/// let shader = CustomShader::new(r#"
+///     fn custom_fragment(color: vec4<f32>, info: FragmentInfo) -> vec4<f32> {
+///         let luminance = dot(color.rgb, vec3<f32>(0.2126, 0.7152, 0.0722));
+///         return vec4<f32>(vec3<f32>(luminance), color.a);
+///     }
+/// "#);
+/// ```
+///
+/// The shaders are compiled by the renderer the first time they are used. If the code fails to compile, an error is
+/// logged and the layer is drawn with the default shaders.
+#[derive(Debug, Clone)]
+pub struct CustomShader {
+    id: u64,
+    source: Arc<str>,
+}
+
+impl CustomShader {
+    /// Creates a new shader with the given WGSL code.
+    pub fn new(source: impl Into<String>) -> Self {
+        Self {
+            id: NEXT_SHADER_ID.fetch_add(1, Ordering::Relaxed),
+            source: source.into().into(),
+        }
+    }
+
+    /// WGSL code of the shader.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Unique id of the shader. Clones of the shader have the same id.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+}
+
+impl PartialEq for CustomShader {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
@@ -4,17 +4,23 @@
 //!
 //! At this point only [`WgpuRenderer`] is implemented.
 
-use crate::Color;
+use crate::alloc_audit::ScratchBuffer;
+use crate::{Color, RenderQuality};
 use galileo_types::cartesian::Size;
 use maybe_sync::{MaybeSend, MaybeSync};
 use render_bundle::RenderBundle;
 use serde::{Deserialize, Serialize};
 use std::any::Any;
 
+mod custom_shader;
+mod particles;
 #[cfg(feature = "wgpu")]
 mod wgpu;
 #[cfg(feature = "wgpu")]
-pub use wgpu::WgpuRenderer;
+pub use wgpu::{OutputColorSpace, WgpuRenderer};
+
+pub use custom_shader::CustomShader;
+pub use particles::{FieldVelocity, PackedParticles, ParticleField, ParticleFrame};
 
 pub mod point_paint;
 pub mod render_bundle;
@@ -43,14 +49,78 @@ pub trait Canvas {
     fn create_bundle(&self) -> RenderBundle;
     /// Packs a bundle to make it ready for be rendered with [`Canvas::draw_bundles`] method.
     fn pack_bundle(&self, bundle: &RenderBundle) -> Box<dyn PackedBundle>;
+    /// Packs the bundle in place of an already packed one. The GPU buffers of the `packed` bundle are reused when they
+    /// are large enough, so layers that change their primitives every frame (e.g. previews of editing tools) should
+    /// use this method instead of [`Canvas::pack_bundle`] to avoid allocating new GPU buffers for every frame.
+    fn repack_bundle(&self, packed: &mut Box<dyn PackedBundle>, bundle: &RenderBundle) {
+        *packed = self.pack_bundle(bundle);
+    }
     /// Render the bundles.
     fn draw_bundles(&mut self, bundles: &[&dyn PackedBundle], options: RenderOptions);
+    /// Renders the bundles with the given [`CustomShader`].
+    ///
+    /// Backends that do not support custom shaders render the bundles with the default shaders, which is what the
+    /// default implementation does.
+    fn draw_bundles_with_shader(
+        &mut self,
+        bundles: &[&dyn PackedBundle],
+        options: RenderOptions,
+        _shader: &CustomShader,
+    ) {
+        self.draw_bundles(bundles, options);
+    }
+    /// Creates a system of `count` particles moving with the velocity `field` (e.g. wind), each keeping its last
+    /// `trail_length` positions to draw a fading trail behind it.
+    ///
+    /// The whole state of the particles is kept by the backend, so animating them with [`Canvas::draw_particles`]
+    /// requires no work from the layer. Returns `None` if the backend does not support particle animation, which is
+    /// what the default implementation does.
+    fn create_particles(
+        &self,
+        _count: u32,
+        _trail_length: u32,
+        _field: &ParticleField,
+    ) -> Option<Box<dyn PackedParticles>> {
+        None
+    }
+    /// Replaces the velocity field of the particles. Particles keep their positions.
+    fn update_particle_field(&self, _particles: &mut dyn PackedParticles, _field: &ParticleField) {}
+    /// Moves the particles by one animation frame and draws their trails.
+    fn draw_particles(
+        &mut self,
+        _particles: &mut dyn PackedParticles,
+        _frame: &ParticleFrame,
+        _options: RenderOptions,
+    ) {
+    }
+    /// Quality the layers should be rendered with. When the quality is reduced, layers may skip expensive work, like
+    /// using finer levels of detail, animations or recalculation of label collisions (see
+    /// [`RenderQuality::postpones_label_collision`]), to keep the map interactive.
+    fn quality(&self) -> RenderQuality {
+        RenderQuality::Full
+    }
 }
 
 /// Packed render bundle ready to be drawn.
 pub trait PackedBundle: MaybeSend + MaybeSync {
     /// Used to convert from trait object into a specific type by the rendering backend.
     fn as_any(&self) -> &dyn Any;
+    /// Used to convert from trait object into a specific type by the rendering backend.
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    /// Approximate size of the GPU buffers and textures used by the bundle in bytes.
+    fn gpu_size(&self) -> usize {
+        0
+    }
+}
+
+/// Notifications from a rendering backend that the map should react to. Pass them to
+/// [`Map::handle_renderer_event`](crate::Map::handle_renderer_event).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RendererEvent {
+    /// The renderer lost its GPU device (e.g. because of a driver reset or switching between GPUs) and recreated its
+    /// state with a new one. All packed bundles created before the restart are invalid and must be packed again.
+    RendererRestarted,
 }
 
 /// Rendering options.
@@ -58,12 +128,63 @@ pub trait PackedBundle: MaybeSend + MaybeSync {
 pub struct RenderOptions {
     /// If set to true, the primitives will be drawn using antialiasing (multisampling).
     pub antialias: bool,
+    /// If set to true, map-referenced primitives (lines and polygons) write their depth to the depth buffer, so the
+    /// primitives drawn later that are positioned behind them are hidden.
+    ///
+    /// Depth buffer is shared between all layers of the map for the frame (separately for antialiased and
+    /// non-antialiased rendering), but it is only written by the primitives drawn with this option. All map-referenced
+    /// primitives are tested against the depth buffer regardless of this option.
+    pub write_depth: bool,
+    /// Opacity of the drawn primitives from `0.0` (invisible) to `1.0` (opaque). The opacity of every primitive is
+    /// multiplied by this value, which allows fading whole bundles in and out without repacking them.
+    pub opacity: f32,
 }
 
 impl Default for RenderOptions {
     fn default() -> Self {
-        Self { antialias: true }
+        Self {
+            antialias: true,
+            write_depth: false,
+            opacity: 1.0,
+        }
+    }
+}
+
+/// Draws the bundles, each with its own opacity, in the given order. Consecutive bundles with the same opacity are
+/// drawn in one call. The `opacity` of the `options` is ignored.
+///
+/// If the `shader` is given, the bundles are drawn with it (see [`Canvas::draw_bundles_with_shader`]).
+pub(crate) fn draw_bundles_with_opacity(
+    canvas: &mut dyn Canvas,
+    bundles: &[(&dyn PackedBundle, f32)],
+    options: RenderOptions,
+    shader: Option<&CustomShader>,
+) {
+    thread_local! {
+        static DRAW_LIST: ScratchBuffer<&'static dyn PackedBundle> = ScratchBuffer::default();
+    }
+
+    let mut to_draw: Vec<&dyn PackedBundle> = DRAW_LIST.with(ScratchBuffer::take);
+    let mut start = 0;
+    while start < bundles.len() {
+        let opacity = bundles[start].1;
+        let end = bundles[start..]
+            .iter()
+            .position(|(_, bundle_opacity)| *bundle_opacity != opacity)
+            .map_or(bundles.len(), |offset| start + offset);
+
+        to_draw.clear();
+        to_draw.extend(bundles[start..end].iter().map(|(bundle, _)| *bundle));
+        let options = RenderOptions { opacity, ..options };
+        match shader {
+            Some(shader) => canvas.draw_bundles_with_shader(&to_draw, options, shader),
+            None => canvas.draw_bundles(&to_draw, options),
+        }
+
+        start = end;
     }
+
+    DRAW_LIST.with(|list| list.recycle(to_draw));
 }
 
 /// Parameters to draw a polygon primitive with.
@@ -74,7 +195,7 @@ pub struct PolygonPaint {
 }
 
 /// Parameter to draw a line primitive with.
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct LinePaint {
     /// Color of the line.
     pub color: Color,
@@ -85,15 +206,49 @@ pub struct LinePaint {
     pub offset: f64,
     /// Type of the cap of the line.
     pub line_cap: LineCap,
+    /// Type of the joins between line segments.
+    #[serde(default)]
+    pub line_join: LineJoin,
+    /// Maximum ratio of the miter length to the line width for [`LineJoin::Miter`] joins. Joins that exceed this
+    /// limit are drawn as bevels. Values smaller than `1.0` are treated as `1.0`.
+    #[serde(default = "default_miter_limit")]
+    pub miter_limit: f32,
+    /// Units of the `width` and `offset` values.
+    #[serde(default)]
+    pub units: SizeUnits,
+}
+
+impl LinePaint {
+    /// Default value for [`LinePaint::miter_limit`].
+    pub const DEFAULT_MITER_LIMIT: f32 = 4.0;
+}
+
+fn default_miter_limit() -> f32 {
+    LinePaint::DEFAULT_MITER_LIMIT
+}
+
+/// Units a symbol dimension (size, width, offset) is specified in.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SizeUnits {
+    /// Screen pixels. Objects with sizes given in pixels keep the same size on the screen independent of the map
+    /// resolution.
+    #[default]
+    Pixels,
+    /// Units of the map CRS (usually meters). Objects with sizes given in map units are scaled together with the map
+    /// when it is zoomed in or out.
+    MapUnits,
 }
 
 /// Cap (end point) style of the line.
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum LineCap {
     /// Half-circle cap.
     Round,
     /// Strait rectangular cap.
+    #[default]
     Butt,
+    /// Rectangular cap that extends beyond the end point by half of the line width.
+    Square,
 }
 
 impl From<LineCap> for lyon::path::LineCap {
@@ -101,6 +256,29 @@ impl From<LineCap> for lyon::path::LineCap {
         match val {
             LineCap::Round => lyon::lyon_tessellation::LineCap::Round,
             LineCap::Butt => lyon::lyon_tessellation::LineCap::Butt,
+            LineCap::Square => lyon::lyon_tessellation::LineCap::Square,
+        }
+    }
+}
+
+/// Join style between segments of the line.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LineJoin {
+    /// Rounded join.
+    #[default]
+    Round,
+    /// The corner is cut off with a straight line.
+    Bevel,
+    /// Sharp corner. If the miter length exceeds [`LinePaint::miter_limit`], the join is drawn as bevel.
+    Miter,
+}
+
+impl From<LineJoin> for lyon::path::LineJoin {
+    fn from(val: LineJoin) -> Self {
+        match val {
+            LineJoin::Round => lyon::path::LineJoin::Round,
+            LineJoin::Bevel => lyon::path::LineJoin::Bevel,
+            LineJoin::Miter => lyon::path::LineJoin::Miter,
         }
     }
 }
@@ -0,0 +1,121 @@
+use crate::error::GalileoError;
+use crate::Color;
+use galileo_types::cartesian::Rect;
+use maybe_sync::{MaybeSend, MaybeSync};
+use std::any::Any;
+
+/// Velocity of one cell of a [`ParticleField`].
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct FieldVelocity {
+    /// Velocity along the `x` axis of the map, in map units per second.
+    pub x: f32,
+    /// Velocity along the `y` axis of the map, in map units per second.
+    pub y: f32,
+    /// Value the color of the particles is selected by, usually the speed on the ground in meters per second (which
+    /// differs from the speed in map units in most projections).
+    pub magnitude: f32,
+}
+
+/// Regular grid of velocities in map coordinates that moves the particles of a [`PackedParticles`] system.
+///
+/// Cells are stored row by row starting from the top left corner of the grid. Cells without data contain `None`,
+/// particles reaching them are respawned.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParticleField {
+    bbox: Rect,
+    width: u32,
+    height: u32,
+    cells: Vec<Option<FieldVelocity>>,
+}
+
+impl ParticleField {
+    /// Creates a new field covering `bbox` (in map coordinates) with a grid of `width` x `height` cells.
+    ///
+    /// Returns an error if the number of cells does not correspond to the grid size.
+    pub fn new(
+        bbox: Rect,
+        width: u32,
+        height: u32,
+        cells: Vec<Option<FieldVelocity>>,
+    ) -> Result<Self, GalileoError> {
+        if width == 0 || height == 0 || cells.len() != width as usize * height as usize {
+            return Err(GalileoError::Generic(
+                "particle field size does not match the number of cells".into(),
+            ));
+        }
+
+        Ok(Self {
+            bbox,
+            width,
+            height,
+            cells,
+        })
+    }
+
+    /// Area covered by the field, in map coordinates.
+    pub fn bbox(&self) -> Rect {
+        self.bbox
+    }
+
+    /// Number of cells in a row.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Number of rows.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Velocities of the cells.
+    pub fn cells(&self) -> &[Option<FieldVelocity>] {
+        &self.cells
+    }
+
+    /// Maximum magnitude of the velocities in the field.
+    pub fn max_magnitude(&self) -> f32 {
+        self.cells
+            .iter()
+            .flatten()
+            .map(|velocity| velocity.magnitude)
+            .fold(0.0, f32::max)
+    }
+}
+
+/// Parameters of one animation frame of a [`PackedParticles`] system, see [`Canvas::draw_particles`].
+///
+/// [`Canvas::draw_particles`]: super::Canvas::draw_particles
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParticleFrame {
+    /// Area the particles live in, in map coordinates. Particles that leave the area are respawned at a random
+    /// position inside it.
+    pub area: Rect,
+    /// Time in seconds the particles are moved by.
+    pub time_step: f32,
+    /// Maximum age of a particle in frames. Particles are respawned before they reach this age, so that they do not
+    /// gather in the convergence zones of the field.
+    pub max_age: u32,
+    /// Width of the particle trails in pixels.
+    pub line_width: f32,
+    /// Color of the particles with velocity magnitude of [`ParticleFrame::max_magnitude`] or more.
+    pub color: Color,
+    /// Color of the still particles. Colors of the particles with velocities between zero and
+    /// [`ParticleFrame::max_magnitude`] are interpolated.
+    pub slow_color: Color,
+    /// Velocity magnitude the particles are drawn with [`ParticleFrame::color`] at.
+    pub max_magnitude: f32,
+}
+
+/// Particle system stored by a rendering backend, created with [`Canvas::create_particles`].
+///
+/// [`Canvas::create_particles`]: super::Canvas::create_particles
+pub trait PackedParticles: MaybeSend + MaybeSync {
+    /// Used to convert from trait object into a specific type by the rendering backend.
+    fn as_any(&self) -> &dyn Any;
+    /// Used to convert from trait object into a specific type by the rendering backend.
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    /// Approximate size of the GPU buffers and textures used by the particles in bytes.
+    fn gpu_size(&self) -> usize {
+        0
+    }
+}
@@ -1,9 +1,10 @@
 //! [`PointPaint`] specifies the way a point should be drawn to the map.
 
 use crate::decoded_image::DecodedImage;
-use crate::render::text::TextStyle;
-use crate::render::{LineCap, LinePaint};
+use crate::render::text::{glyphs_bounds, FontService, TextShaping, TextStyle};
+use crate::render::{LineCap, LineJoin, LinePaint, SizeUnits};
 use crate::Color;
+use galileo_types::cartesian::Rect;
 use galileo_types::impls::ClosedContour;
 use nalgebra::{Point2, Vector2};
 use serde::{Deserialize, Serialize};
@@ -11,10 +12,12 @@ use std::borrow::Cow;
 use std::sync::Arc;
 
 /// Specifies the way a point should be drawn to the map.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PointPaint<'a> {
     pub(crate) shape: PointShape<'a>,
     pub(crate) offset: Vector2<f32>,
+    #[serde(default)]
+    pub(crate) units: SizeUnits,
 }
 
 impl<'a> PointPaint<'a> {
@@ -22,6 +25,7 @@ impl<'a> PointPaint<'a> {
     pub fn circle(color: Color, diameter: f32) -> Self {
         Self {
             offset: Vector2::default(),
+            units: SizeUnits::Pixels,
             shape: PointShape::Circle {
                 fill: color.into(),
                 radius: diameter / 2.0,
@@ -34,6 +38,7 @@ impl<'a> PointPaint<'a> {
     pub fn sector(color: Color, diameter: f32, start_angle: f32, end_angle: f32) -> Self {
         Self {
             offset: Vector2::default(),
+            units: SizeUnits::Pixels,
             shape: PointShape::Sector(SectorParameters {
                 fill: color.into(),
                 radius: diameter / 2.0,
@@ -48,6 +53,7 @@ impl<'a> PointPaint<'a> {
     pub fn square(color: Color, size: f32) -> Self {
         Self {
             offset: Vector2::default(),
+            units: SizeUnits::Pixels,
             shape: PointShape::Square {
                 fill: color,
                 size,
@@ -60,6 +66,7 @@ impl<'a> PointPaint<'a> {
     pub fn dot(color: Color) -> Self {
         Self {
             offset: Vector2::default(),
+            units: SizeUnits::Pixels,
             shape: PointShape::Dot { color },
         }
     }
@@ -68,6 +75,7 @@ impl<'a> PointPaint<'a> {
     pub fn shape(color: Color, contour: &'a ClosedContour<Point2<f32>>, scale: f32) -> Self {
         Self {
             offset: Vector2::default(),
+            units: SizeUnits::Pixels,
             shape: PointShape::FreeShape {
                 fill: color,
                 scale,
@@ -84,6 +92,7 @@ impl<'a> PointPaint<'a> {
         let height = image.height() as f32 * scale;
         Self {
             offset,
+            units: SizeUnits::Pixels,
             shape: PointShape::Image {
                 image,
                 opacity: 255,
@@ -97,6 +106,7 @@ impl<'a> PointPaint<'a> {
     pub fn label(text: &'a String, style: &'a TextStyle) -> Self {
         Self {
             offset: Vector2::new(0.0, 0.0),
+            units: SizeUnits::Pixels,
             shape: PointShape::Label {
                 text: Cow::Borrowed(text),
                 style: Cow::Borrowed(style),
@@ -108,6 +118,7 @@ impl<'a> PointPaint<'a> {
     pub fn label_owed(text: String, style: TextStyle) -> Self {
         Self {
             offset: Vector2::new(0.0, 0.0),
+            units: SizeUnits::Pixels,
             shape: PointShape::Label {
                 text: Cow::Owned(text),
                 style: Cow::Owned(style),
@@ -115,6 +126,22 @@ impl<'a> PointPaint<'a> {
         }
     }
 
+    /// Creates a paint that draws the text centered over a background image stretched to fit the text, e.g. a highway
+    /// shield or a transit line badge. The shield is centered at the point.
+    ///
+    /// The icon and the text are a single primitive: they are always drawn, moved and hidden together.
+    pub fn shield(text: String, style: TextStyle, background: NinePatch) -> Self {
+        Self {
+            offset: Vector2::new(0.0, 0.0),
+            units: SizeUnits::Pixels,
+            shape: PointShape::Shield {
+                text: Cow::Owned(text),
+                style: Cow::Owned(style),
+                background,
+            },
+        }
+    }
+
     /// Sets an outline for the symbol (if applicable).
     pub fn with_outline(mut self, color: Color, width: f32) -> Self {
         match &mut self.shape {
@@ -126,6 +153,9 @@ impl<'a> PointPaint<'a> {
                     width: width as f64,
                     offset: 0.0,
                     line_cap: LineCap::Round,
+                    line_join: LineJoin::Round,
+                    miter_limit: LinePaint::DEFAULT_MITER_LIMIT,
+                    units: self.units,
                 })
             }
             _ => {}
@@ -134,10 +164,39 @@ impl<'a> PointPaint<'a> {
         self
     }
 
+    /// Sets the units all the dimensions of the paint (shape size, outline width, label font size, offset) are
+    /// specified in. By default, all dimensions are in [pixels](SizeUnits::Pixels).
+    ///
+    /// Dot paint is always drawn as a single pixel, so this setting has no effect on it.
+    pub fn with_units(mut self, units: SizeUnits) -> Self {
+        self.units = units;
+        if let PointShape::Circle {
+            outline: Some(outline),
+            ..
+        }
+        | PointShape::Square {
+            outline: Some(outline),
+            ..
+        }
+        | PointShape::FreeShape {
+            outline: Some(outline),
+            ..
+        }
+        | PointShape::Sector(SectorParameters {
+            outline: Some(outline),
+            ..
+        }) = &mut self.shape
+        {
+            outline.units = units;
+        }
+
+        self
+    }
+
     /// Sets offset of the paint.
     ///
-    /// Offset is the distance in pixels from the base point the object will be drawn at. E.g.
-    /// offset does not depend on the map resolution.
+    /// Offset is the distance in pixels (or in map units, see [`PointPaint::with_units`]) from the base point the
+    /// object will be drawn at. E.g. pixel offset does not depend on the map resolution.
     ///
     /// Positive `x` values of offset move the object to the right, positive `y` values move the
     /// object towards the top of the screen.
@@ -145,9 +204,108 @@ impl<'a> PointPaint<'a> {
         self.offset = offset;
         self
     }
+
+    /// Replaces every color of the paint (fill, outline and label colors) with the result of the function.
+    pub(crate) fn map_colors(&mut self, f: impl Fn(Color) -> Color) {
+        let map_fill = |fill: &mut CircleFill| {
+            fill.center_color = f(fill.center_color);
+            fill.side_color = f(fill.side_color);
+        };
+        let map_outline = |outline: &mut Option<LinePaint>| {
+            if let Some(outline) = outline {
+                outline.color = f(outline.color);
+            }
+        };
+
+        match &mut self.shape {
+            PointShape::Dot { color } => *color = f(*color),
+            PointShape::Circle { fill, outline, .. } => {
+                map_fill(fill);
+                map_outline(outline);
+            }
+            PointShape::Sector(parameters) => {
+                map_fill(&mut parameters.fill);
+                map_outline(&mut parameters.outline);
+            }
+            PointShape::Square { fill, outline, .. }
+            | PointShape::FreeShape { fill, outline, .. } => {
+                *fill = f(*fill);
+                map_outline(outline);
+            }
+            PointShape::Image { .. } => {}
+            PointShape::Label { style, .. } | PointShape::Shield { style, .. } => {
+                let color = f(style.font_color);
+                if color != style.font_color {
+                    style.to_mut().font_color = color;
+                }
+            }
+        }
+    }
+
+    /// Name of the font of the label or shield paint. `None` for other paints.
+    pub(crate) fn font_name(&self) -> Option<&str> {
+        match &self.shape {
+            PointShape::Label { style, .. } | PointShape::Shield { style, .. } => {
+                Some(&style.font_name)
+            }
+            _ => None,
+        }
+    }
+
+    /// Sets the font of the label or shield paint. Does nothing for other paints.
+    pub(crate) fn set_font_name(&mut self, font_name: &str) {
+        if let PointShape::Label { style, .. } | PointShape::Shield { style, .. } = &mut self.shape
+        {
+            style.to_mut().font_name = font_name.to_string();
+        }
+    }
+
+    /// Text of the label or shield paint. `None` for other paints.
+    pub(crate) fn text_mut(&mut self) -> Option<&mut Cow<'a, String>> {
+        match &mut self.shape {
+            PointShape::Label { text, .. } | PointShape::Shield { text, .. } => Some(text),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if the paint draws a [shield](Self::shield).
+    pub(crate) fn is_shield(&self) -> bool {
+        matches!(self.shape, PointShape::Shield { .. })
+    }
+
+    /// Size of the shield in pixels, including the offset of the paint: the rectangle is given relative to the
+    /// anchor point of the paint with `y` axis pointing up. `None` for other paints or if the text cannot be shaped.
+    pub(crate) fn shield_bounds(&self) -> Option<Rect<f32>> {
+        let PointShape::Shield {
+            text,
+            style,
+            background,
+        } = &self.shape
+        else {
+            return None;
+        };
+
+        let text_bounds = FontService::with(|font_service| {
+            match font_service.shape(text, style, Vector2::new(0.0, 0.0)) {
+                Ok(TextShaping::Tessellation { glyphs }) => Some(glyphs_bounds(&glyphs)),
+                _ => None,
+            }
+        })?;
+        let text_size = text_bounds.map_or(Vector2::new(0.0, 0.0), |bounds| {
+            Vector2::new(bounds.width(), bounds.height())
+        });
+        let size = background.size_for(text_size);
+
+        Some(Rect::new(
+            self.offset.x - size.x / 2.0,
+            self.offset.y - size.y / 2.0,
+            self.offset.x + size.x / 2.0,
+            self.offset.y + size.y / 2.0,
+        ))
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub(crate) enum PointShape<'a> {
     Dot {
@@ -180,9 +338,123 @@ pub(crate) enum PointShape<'a> {
         text: Cow<'a, String>,
         style: Cow<'a, TextStyle>,
     },
+    Shield {
+        text: Cow<'a, String>,
+        style: Cow<'a, TextStyle>,
+        background: NinePatch,
+    },
+}
+
+/// Image that can be stretched to any size not smaller than the image itself without distorting its borders. Used as
+/// the background of [shields](PointPaint::shield).
+///
+/// The image is split into nine parts by the [insets](NinePatch::insets). The corners are drawn as they are, the
+/// sides are stretched along one axis and the center is stretched along both axes. Setting all insets to zero
+/// stretches the whole image.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NinePatch {
+    /// Background image.
+    pub image: Arc<DecodedImage>,
+    /// Widths of the left, top, right and bottom borders of the image in image pixels, that are not stretched.
+    #[serde(default)]
+    pub insets: [u32; 4],
+    /// Horizontal and vertical distance in pixels between the text and the sides of the background.
+    #[serde(default)]
+    pub padding: [f32; 2],
+    /// Scale of the image on the screen.
+    #[serde(default = "default_scale")]
+    pub scale: f32,
+}
+
+fn default_scale() -> f32 {
+    1.0
+}
+
+/// Part of a [`NinePatch`] as drawn on the screen.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct NinePatchSlice {
+    /// Top-left corner of the part in pixels, relative to the top-left corner of the background, `y` pointing down.
+    pub min: [f32; 2],
+    /// Bottom-right corner of the part.
+    pub max: [f32; 2],
+    /// Texture coordinates of the top-left corner of the part.
+    pub tex_min: [f32; 2],
+    /// Texture coordinates of the bottom-right corner of the part.
+    pub tex_max: [f32; 2],
+}
+
+impl NinePatch {
+    /// Creates a new nine-patch with the given insets (left, top, right, bottom) in image pixels.
+    pub fn new(image: Arc<DecodedImage>, insets: [u32; 4]) -> Self {
+        Self {
+            image,
+            insets,
+            padding: [0.0, 0.0],
+            scale: 1.0,
+        }
+    }
+
+    /// Sets the horizontal and vertical padding in pixels between the text and the sides of the background.
+    pub fn with_padding(mut self, horizontal: f32, vertical: f32) -> Self {
+        self.padding = [horizontal, vertical];
+        self
+    }
+
+    /// Sets the scale of the image on the screen.
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Size in pixels of the background fitting the content of the given size with the padding. The background is
+    /// never smaller than the scaled image.
+    pub fn size_for(&self, content_size: Vector2<f32>) -> Vector2<f32> {
+        Vector2::new(
+            (content_size.x + self.padding[0] * 2.0).max(self.image.width() as f32 * self.scale),
+            (content_size.y + self.padding[1] * 2.0).max(self.image.height() as f32 * self.scale),
+        )
+    }
+
+    /// Splits the background of the given size into the parts to draw. Parts of zero size are skipped.
+    pub(crate) fn slices(&self, size: Vector2<f32>) -> Vec<NinePatchSlice> {
+        let width = self.image.width() as f32;
+        let height = self.image.height() as f32;
+        if width == 0.0 || height == 0.0 {
+            return vec![];
+        }
+
+        let [left, top, right, bottom] = self.insets.map(|inset| inset as f32);
+        let left = left.min(width);
+        let right = right.min(width - left);
+        let top = top.min(height);
+        let bottom = bottom.min(height - top);
+
+        let xs = [0.0, left * self.scale, size.x - right * self.scale, size.x];
+        let us = [0.0, left / width, (width - right) / width, 1.0];
+        let ys = [0.0, top * self.scale, size.y - bottom * self.scale, size.y];
+        let vs = [0.0, top / height, (height - bottom) / height, 1.0];
+
+        let mut slices = vec![];
+        for row in 0..3 {
+            for column in 0..3 {
+                if xs[column + 1] <= xs[column] || ys[row + 1] <= ys[row] {
+                    continue;
+                }
+
+                slices.push(NinePatchSlice {
+                    min: [xs[column], ys[row]],
+                    max: [xs[column + 1], ys[row + 1]],
+                    tex_min: [us[column], vs[row]],
+                    tex_max: [us[column + 1], vs[row + 1]],
+                });
+            }
+        }
+
+        slices
+    }
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub(crate) struct SectorParameters {
     pub fill: CircleFill,
     pub radius: f32,
@@ -191,7 +463,7 @@ pub(crate) struct SectorParameters {
     pub outline: Option<LinePaint>,
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub(crate) struct CircleFill {
     pub center_color: Color,
     pub side_color: Color,
@@ -217,4 +489,33 @@ mod tests {
         assert_eq!(fill.center_color, color);
         assert_eq!(fill.side_color, color);
     }
+
+    #[test]
+    fn nine_patch_slices() {
+        let image = DecodedImage::from_raw(vec![0u8; 20 * 10 * 4], 20, 10).unwrap();
+        let patch = NinePatch::new(Arc::new(image), [4, 2, 4, 2]).with_padding(3.0, 1.0);
+
+        // Short text does not make the background smaller than the image.
+        assert_eq!(
+            patch.size_for(Vector2::new(6.0, 6.0)),
+            Vector2::new(20.0, 10.0)
+        );
+
+        let size = patch.size_for(Vector2::new(34.0, 6.0));
+        assert_eq!(size, Vector2::new(40.0, 10.0));
+
+        let slices = patch.slices(size);
+        assert_eq!(slices.len(), 9);
+        // Corners keep their size, the center is stretched.
+        assert_eq!(slices[0].max, [4.0, 2.0]);
+        assert_eq!(slices[0].tex_max, [0.2, 0.2]);
+        assert_eq!(slices[4].min, [4.0, 2.0]);
+        assert_eq!(slices[4].max, [36.0, 8.0]);
+        assert_eq!(slices[4].tex_max, [0.8, 0.8]);
+        assert_eq!(slices[8].max, [40.0, 10.0]);
+
+        let stretched = NinePatch::new(patch.image.clone(), [0; 4]).slices(size);
+        assert_eq!(stretched.len(), 1);
+        assert_eq!(stretched[0].tex_max, [1.0, 1.0]);
+    }
 }
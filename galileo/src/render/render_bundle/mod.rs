@@ -44,6 +44,29 @@ impl RenderBundle {
         }
     }
 
+    /// Sets the origin of the bundle coordinates.
+    ///
+    /// GPUs work with single precision floating point numbers, which have only about 7 significant digits. Map
+    /// coordinates of large CRSs (e.g. Web Mercator meters) lose so much precision when converted into them, that at
+    /// high zoom levels (20 and more) rendered geometries visibly wobble while the map is panned. To avoid this, a
+    /// bundle can store its coordinates relative to an origin close to its primitives (e.g. the center of a tile).
+    /// The offset from the origin to the view is then calculated in double precision by the renderer.
+    ///
+    /// All coordinates of the primitives added to the bundle (including the clip area) must be given relative to
+    /// the origin, so the origin should be set before any primitives are added. Default origin is `(0, 0)`.
+    pub fn set_origin(&mut self, origin: Point2d) {
+        match &mut self.0 {
+            RenderBundleType::Tessellating(inner) => inner.set_origin(origin),
+        }
+    }
+
+    /// Origin of the bundle coordinates. See [`RenderBundle::set_origin`].
+    pub fn origin(&self) -> Point2d {
+        match &self.0 {
+            RenderBundleType::Tessellating(inner) => inner.origin,
+        }
+    }
+
     /// Set the clip area for drawing. Only primitives inside the clipped area will be displayed after rendering.
     pub fn clip_area<N, P, Poly>(&mut self, polygon: &Poly)
     where
@@ -88,6 +111,64 @@ impl RenderBundle {
         }
     }
 
+    /// Adds a set of points that are all drawn with the same `paint` using GPU instancing.
+    ///
+    /// The shape of the paint is tessellated only once and then drawn at every instance position, which is much
+    /// cheaper than adding every point separately with [`RenderBundle::add`] when the number of points is large. Each
+    /// instance can be scaled and rotated with its [`PointInstanceTransform`].
+    ///
+    /// Only shapes and images that are drawn in screen coordinates (circles, sectors, squares, free shapes and images)
+    /// can be instanced. An error is returned for other paints.
+    pub fn add_instanced_points<N, P>(
+        &mut self,
+        paint: &PointPaint,
+        instances: &[(P, PointInstanceTransform)],
+    ) -> Result<PrimitiveId, GalileoError>
+    where
+        N: AsPrimitive<f32>,
+        P: CartesianPoint3d<Num = N>,
+    {
+        match &mut self.0 {
+            RenderBundleType::Tessellating(inner) => inner.add_instanced_points(paint, instances),
+        }
+    }
+
+    /// Adds a point that is drawn with GPU instancing together with all the other points of the bundle added with
+    /// the same `paint`.
+    ///
+    /// Unlike [`RenderBundle::add_instanced_points`], every point is a separate primitive, so it can be removed, updated
+    /// or faded on its own. This makes it suitable for layers that show a lot of points with only a few different
+    /// symbols. Points which paint cannot be instanced are added the same way as with [`RenderBundle::add`].
+    pub fn add_instanced_point<N, P>(&mut self, point: &P, paint: &PointPaint) -> PrimitiveId
+    where
+        N: AsPrimitive<f32>,
+        P: CartesianPoint3d<Num = N>,
+    {
+        match &mut self.0 {
+            RenderBundleType::Tessellating(inner) => inner.add_instanced_point(point, paint),
+        }
+    }
+
+    /// Replaces the instances of the primitive created with [`RenderBundle::add_instanced_points`].
+    ///
+    /// The shape is not tessellated again, so this method can be used to cheaply update positions of moving points
+    /// every frame.
+    pub fn update_instances<N, P>(
+        &mut self,
+        primitive_id: PrimitiveId,
+        instances: &[(P, PointInstanceTransform)],
+    ) -> Result<(), GalileoError>
+    where
+        N: AsPrimitive<f32>,
+        P: CartesianPoint3d<Num = N>,
+    {
+        match &mut self.0 {
+            RenderBundleType::Tessellating(inner) => {
+                inner.update_instances(primitive_id, instances)
+            }
+        }
+    }
+
     /// Removes the primitive from the bundle.
     pub fn remove(&mut self, primitive_id: PrimitiveId) -> Result<(), GalileoError> {
         match &mut self.0 {
@@ -135,6 +216,16 @@ impl RenderBundle {
         }
     }
 
+    /// Changes the opacity of the primitive without re-tessellating it. The `opacity` (from 0 to 1) is applied on top of
+    /// the colors the primitive was added with, so setting it to 1 restores the original look of the primitive.
+    ///
+    /// For point sets added with [`RenderBundle::add_instanced_points`] the opacity is applied to all the instances.
+    pub fn set_opacity(&mut self, id: PrimitiveId, opacity: f32) -> Result<(), GalileoError> {
+        match &mut self.0 {
+            RenderBundleType::Tessellating(inner) => inner.set_primitive_opacity(id, opacity),
+        }
+    }
+
     /// Sorts screen referenced primitives by depth relative to the camera position of the given `view`.
     pub fn sort_by_depth(&mut self, view: &MapView) {
         match &mut self.0 {
@@ -143,6 +234,24 @@ impl RenderBundle {
     }
 }
 
+/// Per-instance transformation of a point added with [`RenderBundle::add_instanced_points`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PointInstanceTransform {
+    /// Scale of the shape relative to the size set in the paint.
+    pub scale: f32,
+    /// Rotation of the shape in radians (counterclockwise).
+    pub rotation: f32,
+}
+
+impl Default for PointInstanceTransform {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            rotation: 0.0,
+        }
+    }
+}
+
 /// Rendering primitive.
 pub enum RenderPrimitive<'a, N, P, C, Poly>
 where
@@ -1,9 +1,9 @@
 use crate::decoded_image::DecodedImage;
 use crate::error::GalileoError;
-use crate::render::point_paint::{CircleFill, PointPaint, PointShape, SectorParameters};
-use crate::render::render_bundle::RenderPrimitive;
-use crate::render::text::{FontService, TextShaping, TextStyle};
-use crate::render::{ImagePaint, LinePaint, PolygonPaint, PrimitiveId};
+use crate::render::point_paint::{CircleFill, NinePatch, PointPaint, PointShape, SectorParameters};
+use crate::render::render_bundle::{PointInstanceTransform, RenderPrimitive};
+use crate::render::text::{glyphs_bounds, FontService, TextShaping, TextStyle};
+use crate::render::{ImagePaint, LinePaint, PolygonPaint, PrimitiveId, SizeUnits};
 use crate::view::MapView;
 use crate::Color;
 use galileo_types::cartesian::{CartesianPoint2d, CartesianPoint3d, Point2d, Point3d};
@@ -11,18 +11,19 @@ use galileo_types::contour::Contour;
 use galileo_types::impls::ClosedContour;
 use galileo_types::Polygon;
 use lyon::lyon_tessellation::{
-    BuffersBuilder, FillOptions, FillTessellator, FillVertex, FillVertexConstructor, LineJoin,
-    Side, StrokeOptions, StrokeTessellator, StrokeVertex, StrokeVertexConstructor, VertexBuffers,
+    BuffersBuilder, FillOptions, FillTessellator, FillVertex, FillVertexConstructor, Side,
+    StrokeOptions, StrokeTessellator, StrokeVertex, StrokeVertexConstructor, VertexBuffers,
 };
 use lyon::math::point;
 use lyon::path::builder::PathBuilder;
 use lyon::path::path::BuilderWithAttributes;
 use lyon::path::{EndpointId, Path};
 use lyon::tessellation::VertexSource;
-use nalgebra::{Point2, Vector2};
+use nalgebra::{Point2, Translation3, Vector2};
 use num_traits::AsPrimitive;
 use serde::{Deserialize, Serialize};
-use std::borrow::Borrow;
+use std::borrow::{Borrow, Cow};
+use std::collections::HashMap;
 use std::mem::size_of;
 use std::ops::Range;
 use std::sync::Arc;
@@ -32,14 +33,41 @@ pub(crate) struct TessellatingRenderBundle {
     pub poly_tessellation: VertexBuffers<PolyVertex, u32>,
     pub points: Vec<PointInstance>,
     pub screen_ref: ScreenRefTessellation,
+    pub instanced: Vec<InstancedShapeInfo>,
     pub images: Vec<ImageInfo>,
     pub clip_area: Option<VertexBuffers<PolyVertex, u32>>,
     pub image_store: Vec<ImageStoreInfo>,
     pub primitives: Vec<PrimitiveInfo>,
+    pub origin: Point2d,
     vacant_ids: Vec<usize>,
     vacant_image_ids: Vec<usize>,
     vacant_image_store_ids: Vec<usize>,
     buffer_size: usize,
+    faded_primitives: HashMap<usize, FadedPrimitive>,
+    shared_instances: Vec<SharedInstances>,
+}
+
+/// Maximum number of different paints of the points added with
+/// [`TessellatingRenderBundle::add_instanced_point`] that share instanced shapes. Points with other paints are
+/// tessellated one by one, so that looking up the shared shape stays cheap.
+const MAX_SHARED_PAINTS: usize = 64;
+
+/// Instanced shape shared by all the points added with [`TessellatingRenderBundle::add_instanced_point`] with the
+/// same paint. Every instance is a separate primitive.
+#[derive(Debug, Clone)]
+struct SharedInstances {
+    paint: PointPaint<'static>,
+    instanced_index: usize,
+    /// Primitive id of every instance.
+    primitive_ids: Vec<usize>,
+}
+
+/// Primitive drawn with reduced opacity. Stores the original alpha values of its vertices, so that the opacity can be
+/// changed again without re-tessellating the primitive.
+#[derive(Debug, Clone)]
+struct FadedPrimitive {
+    opacity: f32,
+    alphas: Vec<f32>,
 }
 
 #[derive(Debug, Clone)]
@@ -54,6 +82,29 @@ pub(crate) enum ImageInfo {
     Image((usize, [ImageVertex; 4])),
 }
 
+#[derive(Debug, Clone)]
+pub(crate) enum InstancedShapeInfo {
+    Vacant,
+    Shape {
+        template: ScreenRefTessellation,
+        instances: Vec<ShapeInstance>,
+    },
+    Image {
+        image_index: usize,
+        vertices: [ImageVertex; 4],
+        instances: Vec<ShapeInstance>,
+    },
+}
+
+impl InstancedShapeInfo {
+    fn instances_mut(&mut self) -> Option<&mut Vec<ShapeInstance>> {
+        match self {
+            Self::Vacant => None,
+            Self::Shape { instances, .. } | Self::Image { instances, .. } => Some(instances),
+        }
+    }
+}
+
 pub(crate) type ScreenRefTessellation = VertexBuffers<ScreenRefVertex, u32>;
 
 #[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
@@ -68,10 +119,28 @@ pub(crate) struct ScreenRefVertex {
 pub(crate) enum PrimitiveInfo {
     None,
     Vacant,
-    MapRef { vertex_range: Range<usize> },
-    ScreenRef { vertex_range: Range<usize> },
-    Dot { point_index: usize },
-    Image { image_index: usize },
+    MapRef {
+        vertex_range: Range<usize>,
+    },
+    ScreenRef {
+        vertex_range: Range<usize>,
+    },
+    Dot {
+        point_index: usize,
+    },
+    Image {
+        image_index: usize,
+    },
+    Instanced {
+        instanced_index: usize,
+    },
+    /// Single instance of a shape shared by several primitives.
+    SharedInstance {
+        instanced_index: usize,
+        instance_index: usize,
+    },
+    /// Several primitives that are always handled together, e.g. the background and the text of a shield.
+    Composite(Vec<PrimitiveInfo>),
 }
 
 impl Default for TessellatingRenderBundle {
@@ -86,17 +155,25 @@ impl TessellatingRenderBundle {
             poly_tessellation: VertexBuffers::new(),
             points: Vec::new(),
             screen_ref: VertexBuffers::new(),
+            instanced: Vec::new(),
             images: Vec::new(),
             primitives: Vec::new(),
+            origin: Point2d::new(0.0, 0.0),
             clip_area: None,
             image_store: Vec::new(),
             vacant_ids: vec![],
             vacant_image_ids: vec![],
             vacant_image_store_ids: vec![],
             buffer_size: 0,
+            faded_primitives: HashMap::new(),
+            shared_instances: Vec::new(),
         }
     }
 
+    pub fn set_origin(&mut self, origin: Point2d) {
+        self.origin = origin;
+    }
+
     pub fn approx_buffer_size(&self) -> usize {
         self.buffer_size
     }
@@ -191,36 +268,8 @@ impl TessellatingRenderBundle {
         self.buffer_size += image.bytes().len() + size_of::<ImageVertex>() * 4;
 
         let position = [position.x().as_(), position.y().as_()];
-        let offset_x = -offset[0] * width;
-        let offset_y = offset[1] * height;
-
         let index = self.add_image_to_store(image);
-        let vertices = [
-            ImageVertex {
-                position,
-                opacity,
-                tex_coords: [0.0, 1.0],
-                offset: [offset_x, offset_y - height],
-            },
-            ImageVertex {
-                position,
-                opacity,
-                tex_coords: [0.0, 0.0],
-                offset: [offset_x, offset_y],
-            },
-            ImageVertex {
-                position,
-                opacity,
-                tex_coords: [1.0, 1.0],
-                offset: [offset_x + width, offset_y - height],
-            },
-            ImageVertex {
-                position,
-                opacity,
-                tex_coords: [1.0, 0.0],
-                offset: [offset_x + width, offset_y],
-            },
-        ];
+        let vertices = image_point_vertices(position, opacity, width, height, offset);
 
         let image_index = self.add_image_info(index, vertices);
 
@@ -317,13 +366,175 @@ impl TessellatingRenderBundle {
 
         match info {
             PrimitiveInfo::MapRef { vertex_range } => {
-                self.update_map_ref(vertex_range.clone(), primitive)
+                self.update_map_ref(vertex_range.clone(), primitive)?;
+                self.refresh_faded(primitive_id.0)
+            }
+            PrimitiveInfo::SharedInstance { .. } => {
+                let RenderPrimitive::Point(point, paint) = primitive else {
+                    return Err(GalileoError::Generic(
+                        "type of the primitive cannot be changed".into(),
+                    ));
+                };
+
+                // The instance is moved to the shape of the new paint, keeping the id of the primitive.
+                let info =
+                    std::mem::replace(&mut self.primitives[primitive_id.0], PrimitiveInfo::None);
+                self.remove_info(info)?;
+                self.primitives[primitive_id.0] =
+                    self.instanced_point_info::<N, P>(point.borrow(), &paint, primitive_id.0);
+                self.refresh_faded(primitive_id.0)
             }
             PrimitiveInfo::Vacant => Ok(()),
             _ => todo!(),
         }
     }
 
+    /// Multiplies the alpha of all the vertices of the primitive by `opacity` (relative to the opacity the primitive
+    /// was added with).
+    pub fn set_primitive_opacity(
+        &mut self,
+        primitive_id: PrimitiveId,
+        opacity: f32,
+    ) -> Result<(), GalileoError> {
+        let opacity = opacity.clamp(0.0, 1.0);
+        let alphas = match self.faded_primitives.remove(&primitive_id.0) {
+            Some(faded) => faded.alphas,
+            None => {
+                if opacity >= 1.0 {
+                    return Ok(());
+                }
+
+                self.primitive_alphas(primitive_id.0)?
+            }
+        };
+
+        self.map_alphas(primitive_id.0, |index, alpha| {
+            alphas.get(index).copied().unwrap_or(alpha) * opacity
+        })?;
+        if opacity < 1.0 {
+            self.faded_primitives
+                .insert(primitive_id.0, FadedPrimitive { opacity, alphas });
+        }
+
+        Ok(())
+    }
+
+    /// Re-applies the opacity of a faded primitive after its colors were changed.
+    fn refresh_faded(&mut self, id: usize) -> Result<(), GalileoError> {
+        let Some(faded) = self.faded_primitives.remove(&id) else {
+            return Ok(());
+        };
+
+        self.set_primitive_opacity(PrimitiveId(id), faded.opacity)
+    }
+
+    fn primitive_alphas(&mut self, id: usize) -> Result<Vec<f32>, GalileoError> {
+        let mut alphas = vec![];
+        self.map_alphas(id, |_, alpha| {
+            alphas.push(alpha);
+            alpha
+        })?;
+
+        Ok(alphas)
+    }
+
+    /// Calls `f` with the index and alpha value (from 0 to 1) of every vertex of the primitive, replacing the alpha
+    /// with the returned value.
+    fn map_alphas(
+        &mut self,
+        id: usize,
+        mut f: impl FnMut(usize, f32) -> f32,
+    ) -> Result<(), GalileoError> {
+        let info = self
+            .primitives
+            .get(id)
+            .ok_or(GalileoError::Generic("primitive does not exist".into()))?
+            .clone();
+
+        self.map_info_alphas(&info, &mut 0, &mut f)
+    }
+
+    /// Same as [`Self::map_alphas`] for a single primitive info. `first_index` is the index of the first vertex of the
+    /// info among all the vertices of the primitive, and is advanced past the vertices of the info.
+    fn map_info_alphas(
+        &mut self,
+        info: &PrimitiveInfo,
+        first_index: &mut usize,
+        f: &mut impl FnMut(usize, f32) -> f32,
+    ) -> Result<(), GalileoError> {
+        let to_u8 = |alpha: f32| (alpha * 255.0).round() as u8;
+        let start = *first_index;
+
+        match info {
+            PrimitiveInfo::MapRef { vertex_range } => {
+                for (index, vertex) in self.poly_tessellation.vertices[vertex_range.clone()]
+                    .iter_mut()
+                    .enumerate()
+                {
+                    vertex.color[3] = f(start + index, vertex.color[3]);
+                }
+                *first_index += vertex_range.len();
+            }
+            PrimitiveInfo::ScreenRef { vertex_range } => {
+                for (index, vertex) in self.screen_ref.vertices[vertex_range.clone()]
+                    .iter_mut()
+                    .enumerate()
+                {
+                    vertex.color[3] = to_u8(f(start + index, vertex.color[3] as f32 / 255.0));
+                }
+                *first_index += vertex_range.len();
+            }
+            PrimitiveInfo::Dot { point_index } => {
+                let point = self
+                    .points
+                    .get_mut(*point_index)
+                    .ok_or(GalileoError::Generic("invalid point id".into()))?;
+                point.color[3] = to_u8(f(start, point.color[3] as f32 / 255.0));
+                *first_index += 1;
+            }
+            PrimitiveInfo::Image { image_index } => {
+                if let Some(ImageInfo::Image((_, vertices))) = self.images.get_mut(*image_index) {
+                    for (index, vertex) in vertices.iter_mut().enumerate() {
+                        vertex.opacity = f(start + index, vertex.opacity);
+                    }
+                }
+                *first_index += 4;
+            }
+            PrimitiveInfo::Instanced { instanced_index } => {
+                let instances = self
+                    .instanced
+                    .get_mut(*instanced_index)
+                    .and_then(InstancedShapeInfo::instances_mut)
+                    .ok_or(GalileoError::Generic("invalid instanced id".into()))?;
+                for (index, instance) in instances.iter_mut().enumerate() {
+                    instance.opacity = f(start + index, instance.opacity);
+                }
+                *first_index += instances.len();
+            }
+            PrimitiveInfo::SharedInstance {
+                instanced_index,
+                instance_index,
+            } => {
+                let instance = self
+                    .instanced
+                    .get_mut(*instanced_index)
+                    .and_then(InstancedShapeInfo::instances_mut)
+                    .and_then(|instances| instances.get_mut(*instance_index))
+                    .ok_or(GalileoError::Generic("invalid instance id".into()))?;
+                instance.opacity = f(start, instance.opacity);
+                *first_index += 1;
+            }
+            PrimitiveInfo::Composite(parts) => {
+                for part in parts {
+                    self.map_info_alphas(part, first_index, f)?;
+                }
+            }
+            PrimitiveInfo::Vacant | PrimitiveInfo::None => {}
+        }
+
+        Ok(())
+    }
+
     pub fn remove(&mut self, primitive_id: PrimitiveId) -> Result<(), GalileoError> {
         if primitive_id.0 >= self.primitives.len() {
             return Err(GalileoError::Generic(
@@ -332,12 +543,30 @@ impl TessellatingRenderBundle {
         }
 
         let info = std::mem::replace(&mut self.primitives[primitive_id.0], PrimitiveInfo::Vacant);
+        self.faded_primitives.remove(&primitive_id.0);
+
+        self.remove_info(info)
+    }
 
+    fn remove_info(&mut self, info: PrimitiveInfo) -> Result<(), GalileoError> {
         match info {
             PrimitiveInfo::MapRef { vertex_range } => self.remove_map_ref(vertex_range),
             PrimitiveInfo::ScreenRef { vertex_range } => self.remove_screen_ref(vertex_range),
             PrimitiveInfo::Dot { point_index } => self.remove_dot(point_index),
             PrimitiveInfo::Image { image_index } => self.remove_image(image_index),
+            PrimitiveInfo::Instanced { instanced_index } => self.remove_instanced(instanced_index),
+            PrimitiveInfo::SharedInstance {
+                instanced_index,
+                instance_index,
+            } => self.remove_shared_instance(instanced_index, instance_index),
+            PrimitiveInfo::Composite(parts) => {
+                // Parts added later are removed first, so that removing a part doesn't shift the ranges of the
+                // parts still to be removed.
+                for part in parts.into_iter().rev() {
+                    self.remove_info(part)?;
+                }
+                Ok(())
+            }
             PrimitiveInfo::Vacant => Ok(()),
             PrimitiveInfo::None => Ok(()),
         }
@@ -393,6 +622,82 @@ impl TessellatingRenderBundle {
         }
     }
 
+    fn remove_instanced(&mut self, index: usize) -> Result<(), GalileoError> {
+        let Some(info) = self.instanced.get_mut(index) else {
+            return Err(GalileoError::Generic("index out of bounds".into()));
+        };
+
+        let info = std::mem::replace(info, InstancedShapeInfo::Vacant);
+        self.buffer_size -= self.instanced_buffer_size(&info);
+
+        if let InstancedShapeInfo::Image { image_index, .. } = info {
+            self.release_instanced_image(image_index);
+        }
+
+        Ok(())
+    }
+
+    /// Removes the image from the store if it is not used by any other primitive.
+    fn release_instanced_image(&mut self, image_index: usize) {
+        let used_by_images = self
+            .images
+            .iter()
+            .any(|info| matches!(info, ImageInfo::Image((i, _)) if *i == image_index));
+        let used_by_instances = self.instanced.iter().any(
+            |info| matches!(info, InstancedShapeInfo::Image { image_index: i, .. } if *i == image_index),
+        );
+
+        if !used_by_images && !used_by_instances {
+            self.image_store[image_index] = ImageStoreInfo::Vacant;
+            self.vacant_image_store_ids.push(image_index);
+        }
+    }
+
+    fn remove_shared_instance(
+        &mut self,
+        instanced_index: usize,
+        instance_index: usize,
+    ) -> Result<(), GalileoError> {
+        let shared_index = self
+            .shared_instances
+            .iter()
+            .position(|shared| shared.instanced_index == instanced_index)
+            .ok_or(GalileoError::Generic(
+                "instanced shape is not shared".into(),
+            ))?;
+        let instances = self
+            .instanced
+            .get_mut(instanced_index)
+            .and_then(InstancedShapeInfo::instances_mut)
+            .ok_or(GalileoError::Generic("instanced shape is removed".into()))?;
+        if instance_index >= instances.len() {
+            return Err(GalileoError::Generic("index out of bounds".into()));
+        }
+
+        // The last instance takes the place of the removed one, so only its primitive has to be updated.
+        instances.swap_remove(instance_index);
+        self.buffer_size -= size_of::<ShapeInstance>();
+
+        let shared = &mut self.shared_instances[shared_index];
+        shared.primitive_ids.swap_remove(instance_index);
+        if let Some(&moved_id) = shared.primitive_ids.get(instance_index) {
+            if let Some(PrimitiveInfo::SharedInstance {
+                instance_index: index,
+                ..
+            }) = self.primitives.get_mut(moved_id)
+            {
+                *index = instance_index;
+            }
+        }
+
+        if shared.primitive_ids.is_empty() {
+            self.shared_instances.swap_remove(shared_index);
+            self.remove_instanced(instanced_index)?;
+        }
+
+        Ok(())
+    }
+
     fn remove_dot(&mut self, index: usize) -> Result<(), GalileoError> {
         if index >= self.points.len() {
             Err(GalileoError::Generic("index out of bounds".into()))
@@ -423,7 +728,7 @@ impl TessellatingRenderBundle {
         self.buffer_size -=
             size_of::<ScreenRefVertex>() * len + size_of::<u32>() * removed_index_count;
 
-        for info in &mut self.primitives {
+        fn shift(info: &mut PrimitiveInfo, range: &Range<usize>, len: usize) {
             match info {
                 PrimitiveInfo::ScreenRef {
                     ref mut vertex_range,
@@ -431,10 +736,19 @@ impl TessellatingRenderBundle {
                     vertex_range.start -= len;
                     vertex_range.end -= len;
                 }
+                PrimitiveInfo::Composite(parts) => {
+                    for part in parts {
+                        shift(part, range, len);
+                    }
+                }
                 _ => {}
             }
         }
 
+        for info in &mut self.primitives {
+            shift(info, &range, len);
+        }
+
         Ok(())
     }
 
@@ -496,8 +810,23 @@ impl TessellatingRenderBundle {
         N: AsPrimitive<f32>,
         P: CartesianPoint3d<Num = N>,
     {
+        let info = self.point_info(point, paint);
+        self.add_primitive_info(info)
+    }
+
+    fn point_info<N, P>(&mut self, point: &P, paint: &PointPaint) -> PrimitiveInfo
+    where
+        N: AsPrimitive<f32>,
+        P: CartesianPoint3d<Num = N>,
+    {
+        if paint.units == SizeUnits::MapUnits {
+            if let Some(info) = self.add_map_ref_point(point, paint) {
+                return info;
+            }
+        }
+
         let start_index = self.screen_ref.vertices.len();
-        let info = match &paint.shape {
+        match &paint.shape {
             PointShape::Dot { color } => {
                 self.add_dot(point, *color, paint.offset);
                 PrimitiveInfo::Dot {
@@ -542,22 +871,532 @@ impl TessellatingRenderBundle {
                 PrimitiveInfo::ScreenRef {
                     vertex_range: start_index..self.screen_ref.vertices.len(),
                 }
-            }
-            PointShape::FreeShape {
-                fill,
-                scale,
-                outline,
-                shape,
-            } => {
-                self.add_shape(point, *fill, *scale, *outline, shape, paint.offset);
-                PrimitiveInfo::ScreenRef {
-                    vertex_range: start_index..self.screen_ref.vertices.len(),
+            }
+            PointShape::FreeShape {
+                fill,
+                scale,
+                outline,
+                shape,
+            } => {
+                self.add_shape(point, *fill, *scale, *outline, shape, paint.offset);
+                PrimitiveInfo::ScreenRef {
+                    vertex_range: start_index..self.screen_ref.vertices.len(),
+                }
+            }
+            PointShape::Label { text, style } => self.add_label(point, text, style, paint.offset),
+            PointShape::Shield {
+                text,
+                style,
+                background,
+            } => self.add_shield(point, text, style, background, paint.offset),
+        }
+    }
+
+    pub fn add_instanced_points<N, P>(
+        &mut self,
+        paint: &PointPaint,
+        instances: &[(P, PointInstanceTransform)],
+    ) -> Result<PrimitiveId, GalileoError>
+    where
+        N: AsPrimitive<f32>,
+        P: CartesianPoint3d<Num = N>,
+    {
+        let mut info = self.instance_template(paint)?;
+        if let Some(stored) = info.instances_mut() {
+            stored.extend(
+                instances
+                    .iter()
+                    .map(|(point, transform)| ShapeInstance::new(point, transform)),
+            );
+        }
+
+        let instanced_index = self.add_instanced_info(info);
+        Ok(self.add_primitive_info(PrimitiveInfo::Instanced { instanced_index }))
+    }
+
+    pub fn update_instances<N, P>(
+        &mut self,
+        primitive_id: PrimitiveId,
+        instances: &[(P, PointInstanceTransform)],
+    ) -> Result<(), GalileoError>
+    where
+        N: AsPrimitive<f32>,
+        P: CartesianPoint3d<Num = N>,
+    {
+        let Some(PrimitiveInfo::Instanced { instanced_index }) =
+            self.primitives.get(primitive_id.0)
+        else {
+            return Err(GalileoError::Generic(
+                "primitive is not an instanced point set".into(),
+            ));
+        };
+
+        let Some(stored) = self
+            .instanced
+            .get_mut(*instanced_index)
+            .and_then(InstancedShapeInfo::instances_mut)
+        else {
+            return Err(GalileoError::Generic(
+                "instanced point set is removed".into(),
+            ));
+        };
+
+        self.buffer_size += instances.len() * size_of::<ShapeInstance>();
+        self.buffer_size -= stored.len() * size_of::<ShapeInstance>();
+
+        *stored = instances
+            .iter()
+            .map(|(point, transform)| ShapeInstance::new(point, transform))
+            .collect();
+
+        self.refresh_faded(primitive_id.0)
+    }
+
+    /// Adds a point that is drawn with GPU instancing together with all the other points added with the same paint.
+    /// Every point is still a separate primitive that can be removed or faded on its own.
+    ///
+    /// Points which paints cannot be instanced are added the same way as with [`Self::add_point`].
+    pub fn add_instanced_point<N, P>(&mut self, point: &P, paint: &PointPaint) -> PrimitiveId
+    where
+        N: AsPrimitive<f32>,
+        P: CartesianPoint3d<Num = N>,
+    {
+        let id = self.add_primitive_info(PrimitiveInfo::None);
+        self.primitives[id.0] = self.instanced_point_info(point, paint, id.0);
+
+        id
+    }
+
+    fn instanced_point_info<N, P>(
+        &mut self,
+        point: &P,
+        paint: &PointPaint,
+        primitive_id: usize,
+    ) -> PrimitiveInfo
+    where
+        N: AsPrimitive<f32>,
+        P: CartesianPoint3d<Num = N>,
+    {
+        let Some(shared_index) = self.shared_instances_index(paint) else {
+            return self.point_info(point, paint);
+        };
+
+        let shared = &mut self.shared_instances[shared_index];
+        let instanced_index = shared.instanced_index;
+        let Some(instances) = self.instanced[instanced_index].instances_mut() else {
+            return self.point_info(point, paint);
+        };
+
+        let instance_index = instances.len();
+        instances.push(ShapeInstance::new(
+            point,
+            &PointInstanceTransform::default(),
+        ));
+        shared.primitive_ids.push(primitive_id);
+        self.buffer_size += size_of::<ShapeInstance>();
+
+        PrimitiveInfo::SharedInstance {
+            instanced_index,
+            instance_index,
+        }
+    }
+
+    /// Returns the index of the shared shape for the paint, creating it if necessary. Returns `None` if the paint
+    /// cannot be instanced.
+    fn shared_instances_index(&mut self, paint: &PointPaint) -> Option<usize> {
+        if let Some(index) = self
+            .shared_instances
+            .iter()
+            .position(|shared| same_instanced_paint(&shared.paint, paint))
+        {
+            return Some(index);
+        }
+
+        if self.shared_instances.len() >= MAX_SHARED_PAINTS {
+            return None;
+        }
+
+        let paint = shared_paint(paint)?;
+        let info = self.instance_template(&paint).ok()?;
+        let instanced_index = self.add_instanced_info(info);
+        self.shared_instances.push(SharedInstances {
+            paint,
+            instanced_index,
+            primitive_ids: vec![],
+        });
+
+        Some(self.shared_instances.len() - 1)
+    }
+
+    fn add_instanced_info(&mut self, info: InstancedShapeInfo) -> usize {
+        self.buffer_size += self.instanced_buffer_size(&info);
+
+        match self
+            .instanced
+            .iter()
+            .position(|info| matches!(info, InstancedShapeInfo::Vacant))
+        {
+            Some(index) => {
+                self.instanced[index] = info;
+                index
+            }
+            None => {
+                self.instanced.push(info);
+                self.instanced.len() - 1
+            }
+        }
+    }
+
+    /// Creates an instanced shape without instances for the paint.
+    fn instance_template(
+        &mut self,
+        paint: &PointPaint,
+    ) -> Result<InstancedShapeInfo, GalileoError> {
+        if paint.units != SizeUnits::Pixels {
+            return Err(GalileoError::Generic(
+                "only points with sizes in pixels can be drawn with instancing".into(),
+            ));
+        }
+
+        if let PointShape::Image {
+            image,
+            opacity,
+            width,
+            height,
+        } = &paint.shape
+        {
+            let image_index = self.add_image_to_store(image.clone());
+            return Ok(InstancedShapeInfo::Image {
+                image_index,
+                vertices: image_point_vertices(
+                    [0.0, 0.0],
+                    *opacity as f32 / 255.0,
+                    *width,
+                    *height,
+                    paint.offset,
+                ),
+                instances: vec![],
+            });
+        }
+
+        let mut bundle = Self::new();
+        bundle.add_point(&Point3d::new(0.0, 0.0, 0.0), paint);
+
+        if !bundle.points.is_empty() || !bundle.images.is_empty() {
+            return Err(GalileoError::Generic(
+                "only screen referenced shapes and images can be drawn with instancing".into(),
+            ));
+        }
+
+        Ok(InstancedShapeInfo::Shape {
+            template: bundle.screen_ref,
+            instances: vec![],
+        })
+    }
+
+    fn instanced_buffer_size(&self, info: &InstancedShapeInfo) -> usize {
+        match info {
+            InstancedShapeInfo::Vacant => 0,
+            InstancedShapeInfo::Shape {
+                template,
+                instances,
+            } => {
+                template.vertices.len() * size_of::<ScreenRefVertex>()
+                    + template.indices.len() * size_of::<u32>()
+                    + size_of_val(instances.as_slice())
+            }
+            InstancedShapeInfo::Image {
+                image_index,
+                instances,
+                ..
+            } => {
+                let image_size = match self.image_store.get(*image_index) {
+                    Some(ImageStoreInfo::Image(image)) => image.bytes().len(),
+                    _ => 0,
+                };
+                image_size + size_of::<[ImageVertex; 4]>() + size_of_val(instances.as_slice())
+            }
+        }
+    }
+
+    /// Adds a point with all dimensions given in map units. Such points are tessellated in map coordinates, so they
+    /// are scaled together with the map. Returns `None` if the shape cannot be drawn in map units.
+    fn add_map_ref_point<N, P>(&mut self, point: &P, paint: &PointPaint) -> Option<PrimitiveInfo>
+    where
+        N: AsPrimitive<f32>,
+        P: CartesianPoint3d<Num = N>,
+    {
+        let position = [point.x().as_(), point.y().as_(), point.z().as_()];
+        let offset = paint.offset;
+
+        let start_vertex_count = self.poly_tessellation.vertices.len();
+        let start_index_count = self.poly_tessellation.indices.len();
+
+        match &paint.shape {
+            PointShape::Dot { .. } => return None,
+            PointShape::Image {
+                image,
+                opacity,
+                width,
+                height,
+            } => {
+                return Some(self.add_map_ref_image(
+                    position,
+                    image.clone(),
+                    *opacity,
+                    *width,
+                    *height,
+                    offset,
+                ))
+            }
+            PointShape::Circle {
+                fill,
+                radius,
+                outline,
+            } => self.add_map_ref_sector(
+                position,
+                SectorParameters {
+                    fill: *fill,
+                    radius: *radius,
+                    start_angle: 0.0,
+                    end_angle: std::f32::consts::PI * 2.0,
+                    outline: *outline,
+                },
+                offset,
+            ),
+            PointShape::Sector(parameters) => {
+                self.add_map_ref_sector(position, *parameters, offset)
+            }
+            PointShape::Square {
+                fill,
+                size,
+                outline,
+            } => self.add_map_ref_shape(position, *fill, *size, *outline, &square_shape(), offset),
+            PointShape::FreeShape {
+                fill,
+                scale,
+                outline,
+                shape,
+            } => self.add_map_ref_shape(position, *fill, *scale, *outline, shape, offset),
+            PointShape::Label { text, style } => {
+                self.add_map_ref_label(position, text, style, offset)
+            }
+            // Shields are always drawn in pixels.
+            PointShape::Shield { .. } => return None,
+        }
+
+        self.buffer_size += (self.poly_tessellation.vertices.len() - start_vertex_count)
+            * size_of::<PolyVertex>()
+            + (self.poly_tessellation.indices.len() - start_index_count) * size_of::<u32>();
+
+        Some(PrimitiveInfo::MapRef {
+            vertex_range: start_vertex_count..self.poly_tessellation.vertices.len(),
+        })
+    }
+
+    fn add_map_ref_shape(
+        &mut self,
+        position: [f32; 3],
+        fill: Color,
+        scale: f32,
+        outline: Option<LinePaint>,
+        shape: &ClosedContour<Point2<f32>>,
+        offset: Vector2<f32>,
+    ) {
+        let mut path_builder = BuilderWithAttributes::new(0);
+        build_contour_path(&mut path_builder, shape, scale);
+        let path = path_builder.build();
+
+        if let Some(outline) = outline {
+            let vertex_constructor = MapRefShapeVertexConstructor {
+                color: outline.color.to_f32_array(),
+                position,
+                offset,
+            };
+
+            if let Err(err) = StrokeTessellator::new().tessellate(
+                &path,
+                &StrokeOptions::DEFAULT.with_line_width(outline.width as f32 * 2.0),
+                &mut BuffersBuilder::new(&mut self.poly_tessellation, vertex_constructor),
+            ) {
+                log::warn!("Shape tessellation failed: {err:?}");
+                return;
+            }
+        }
+
+        if !fill.is_transparent() {
+            let vertex_constructor = MapRefShapeVertexConstructor {
+                color: fill.to_f32_array(),
+                position,
+                offset,
+            };
+
+            if let Err(err) = FillTessellator::new().tessellate(
+                &path,
+                &FillOptions::DEFAULT,
+                &mut BuffersBuilder::new(&mut self.poly_tessellation, vertex_constructor),
+            ) {
+                log::warn!("Shape tessellation failed: {err:?}");
+            }
+        }
+    }
+
+    fn add_map_ref_sector(
+        &mut self,
+        position: [f32; 3],
+        parameters: SectorParameters,
+        offset: Vector2<f32>,
+    ) {
+        let SectorParameters {
+            fill,
+            radius,
+            start_angle,
+            end_angle,
+            outline,
+        } = parameters;
+
+        // Circle is approximated with the same number of segments as a circle of 100 pixels radius would be.
+        const REFERENCE_RADIUS: f32 = 100.0;
+        let scale = radius / REFERENCE_RADIUS;
+        let mut contour: Vec<_> = get_circle_sector(REFERENCE_RADIUS, start_angle, end_angle)
+            .into_iter()
+            .map(|p| p * scale)
+            .collect();
+        let is_full_circle =
+            ((end_angle - start_angle).abs() - std::f32::consts::PI * 2.0).abs() < 0.1;
+
+        let first_index = self.poly_tessellation.vertices.len() as u32;
+        let center_color = fill.center_color.to_f32_array();
+        let side_color = fill.side_color.to_f32_array();
+        let vertex = |point: Point2<f32>, color: [f32; 4]| PolyVertex {
+            position: [
+                position[0] + point.x + offset.x,
+                position[1] + point.y + offset.y,
+                position[2],
+            ],
+            color,
+            normal: Default::default(),
+            norm_limit: f32::MAX,
+        };
+
+        self.poly_tessellation
+            .vertices
+            .push(vertex(Point2::new(0.0, 0.0), center_color));
+        for (i, point) in contour.iter().enumerate() {
+            self.poly_tessellation
+                .vertices
+                .push(vertex(*point, side_color));
+
+            let i = i as u32;
+            if i > 0 {
+                self.poly_tessellation.indices.extend([
+                    first_index,
+                    first_index + i,
+                    first_index + i + 1,
+                ]);
+            }
+        }
+
+        if is_full_circle && !contour.is_empty() {
+            let last = contour.len() as u32;
+            self.poly_tessellation.indices.extend([
+                first_index,
+                first_index + last,
+                first_index + 1,
+            ]);
+        }
+
+        if outline.is_some() {
+            if !is_full_circle {
+                contour.push(Point2::new(0.0, 0.0));
+            }
+            self.add_map_ref_shape(
+                position,
+                Color::TRANSPARENT,
+                1.0,
+                outline,
+                &ClosedContour::new(contour),
+                offset,
+            );
+        }
+    }
+
+    fn add_map_ref_label(
+        &mut self,
+        position: [f32; 3],
+        text: &str,
+        style: &TextStyle,
+        offset: Vector2<f32>,
+    ) {
+        FontService::with(
+            |font_service| match font_service.shape(text, style, offset) {
+                Ok(TextShaping::Tessellation { glyphs, .. }) => {
+                    let color = style.font_color.to_f32_array();
+                    for glyph in glyphs {
+                        let vertices_start = self.poly_tessellation.vertices.len() as u32;
+                        for vertex in glyph.vertices {
+                            self.poly_tessellation.vertices.push(PolyVertex {
+                                position: [
+                                    position[0] + vertex[0],
+                                    position[1] + vertex[1],
+                                    position[2],
+                                ],
+                                color,
+                                normal: Default::default(),
+                                norm_limit: f32::MAX,
+                            });
+                        }
+                        for index in glyph.indices {
+                            self.poly_tessellation.indices.push(index + vertices_start);
+                        }
+                    }
+                }
+                Ok(TextShaping::Raster) => {
+                    log::warn!("Raster text shaping is not supported for labels in map units");
                 }
-            }
-            PointShape::Label { text, style } => self.add_label(point, text, style, paint.offset),
+                Err(err) => {
+                    log::error!("Error shaping text label: {err:?}");
+                }
+            },
+        )
+    }
+
+    fn add_map_ref_image(
+        &mut self,
+        position: [f32; 3],
+        image: Arc<DecodedImage>,
+        opacity: u8,
+        width: f32,
+        height: f32,
+        offset: Vector2<f32>,
+    ) -> PrimitiveInfo {
+        let opacity = opacity as f32 / 255.0;
+
+        self.buffer_size += image.bytes().len() + size_of::<ImageVertex>() * 4;
+
+        let left = position[0] - offset[0] * width;
+        let top = position[1] + offset[1] * height;
+        let right = left + width;
+        let bottom = top - height;
+
+        let vertex = |x: f32, y: f32, tex_coords: [f32; 2]| ImageVertex {
+            position: [x, y],
+            opacity,
+            tex_coords,
+            offset: [0.0, 0.0],
         };
 
-        self.add_primitive_info(info)
+        let index = self.add_image_to_store(image);
+        let vertices = [
+            vertex(left, bottom, [0.0, 1.0]),
+            vertex(left, top, [0.0, 0.0]),
+            vertex(right, bottom, [1.0, 1.0]),
+            vertex(right, top, [1.0, 0.0]),
+        ];
+
+        let image_index = self.add_image_info(index, vertices);
+
+        PrimitiveInfo::Image { image_index }
     }
 
     pub fn add_line<N, P, C>(
@@ -618,12 +1457,23 @@ impl TessellatingRenderBundle {
         path_builder.end(line.is_closed());
         let path = path_builder.build();
 
+        // The path is built in the pixel coordinates for the `min_resolution`, so the widths given in map units are
+        // converted into the same space.
+        let (width, offset) = match paint.units {
+            SizeUnits::Pixels => (paint.width as f32, paint.offset as f32),
+            SizeUnits::MapUnits => (
+                (paint.width / min_resolution) as f32,
+                (paint.offset / min_resolution) as f32,
+            ),
+        };
+
         let vertex_constructor = LineVertexConstructor {
-            width: paint.width as f32,
-            offset: paint.offset as f32,
+            width,
+            offset,
             color: paint.color.to_f32_array(),
             resolution: min_resolution as f32,
             path: &path,
+            map_units: paint.units == SizeUnits::MapUnits,
         };
 
         let mut tesselator = StrokeTessellator::new();
@@ -634,10 +1484,10 @@ impl TessellatingRenderBundle {
             &path,
             &StrokeOptions::DEFAULT
                 .with_line_cap(paint.line_cap.into())
-                .with_line_width(paint.width as f32)
-                .with_miter_limit(1.0)
+                .with_line_width(width)
+                .with_miter_limit(paint.miter_limit.max(StrokeOptions::MINIMUM_MITER_LIMIT))
                 .with_tolerance(0.1)
-                .with_line_join(LineJoin::Round),
+                .with_line_join(paint.line_join.into()),
             &mut BuffersBuilder::new(tessellation, vertex_constructor),
         ) {
             log::error!("Tessellation failed: {err}");
@@ -693,7 +1543,7 @@ impl TessellatingRenderBundle {
             _ => return Err(GalileoError::Generic("invalid primitive type".into())),
         }
 
-        Ok(())
+        self.refresh_faded(id.0)
     }
 
     fn update_map_ref<N, P, C, Poly>(
@@ -982,12 +1832,55 @@ impl TessellatingRenderBundle {
 
     pub fn sort_by_depth(&mut self, view: &MapView) {
         self.sort_images_by_depth(view);
+        self.sort_shared_images_by_depth(view);
+    }
+
+    /// Sorts the instances of the shared image shapes, so that the images further from the camera are drawn first.
+    fn sort_shared_images_by_depth(&mut self, view: &MapView) {
+        let Some(transform) = view.map_to_scene_transform() else {
+            return;
+        };
+        let transform =
+            transform * Translation3::new(self.origin.x(), self.origin.y(), 0.0).to_homogeneous();
+
+        for shared in &mut self.shared_instances {
+            let Some(InstancedShapeInfo::Image { instances, .. }) =
+                self.instanced.get_mut(shared.instanced_index)
+            else {
+                continue;
+            };
+
+            let depth = |instance: &ShapeInstance| {
+                let [x, y, z] = instance.position;
+                (transform * Point3d::new(x as f64, y as f64, z as f64).to_homogeneous()).z
+            };
+            let mut order: Vec<usize> = (0..instances.len()).collect();
+            order.sort_by(|a, b| depth(&instances[*b]).total_cmp(&depth(&instances[*a])));
+
+            *instances = order.iter().map(|index| instances[*index]).collect();
+            shared.primitive_ids = order
+                .iter()
+                .map(|index| shared.primitive_ids[*index])
+                .collect();
+
+            for (instance_index, id) in shared.primitive_ids.iter().enumerate() {
+                if let Some(PrimitiveInfo::SharedInstance {
+                    instance_index: index,
+                    ..
+                }) = self.primitives.get_mut(*id)
+                {
+                    *index = instance_index;
+                }
+            }
+        }
     }
 
     pub fn sort_images_by_depth(&mut self, view: &MapView) {
         let Some(transform) = view.map_to_scene_transform() else {
             return;
         };
+        let transform =
+            transform * Translation3::new(self.origin.x(), self.origin.y(), 0.0).to_homogeneous();
         self.images.sort_by(|info_a, info_b| {
             let point_a = match info_a {
                 ImageInfo::Vacant => Point3d::new(0.0, 0.0, 0.0).to_homogeneous(),
@@ -1065,6 +1958,111 @@ impl TessellatingRenderBundle {
             },
         )
     }
+
+    /// Adds the parts of the background image and the text of a shield, as a single composite primitive. The shield
+    /// is centered at the `offset` from the position.
+    fn add_shield<N, P>(
+        &mut self,
+        position: &P,
+        text: &str,
+        style: &TextStyle,
+        background: &NinePatch,
+        offset: Vector2<f32>,
+    ) -> PrimitiveInfo
+    where
+        N: AsPrimitive<f32>,
+        P: CartesianPoint3d<Num = N>,
+    {
+        let glyphs = FontService::with(|font_service| {
+            match font_service.shape(text, style, Vector2::new(0.0, 0.0)) {
+                Ok(TextShaping::Tessellation { glyphs }) => Some(glyphs),
+                Err(err) => {
+                    log::error!("Error shaping shield text: {err:?}");
+                    None
+                }
+                _ => {
+                    log::error!("Not supported font type");
+                    None
+                }
+            }
+        });
+        let Some(glyphs) = glyphs else {
+            return PrimitiveInfo::None;
+        };
+
+        let text_bounds = glyphs_bounds(&glyphs);
+        let text_size = text_bounds.map_or(Vector2::new(0.0, 0.0), |bounds| {
+            Vector2::new(bounds.width(), bounds.height())
+        });
+        let size = background.size_for(text_size);
+        let left = offset.x - size.x / 2.0;
+        let top = offset.y + size.y / 2.0;
+
+        let mut parts = vec![];
+
+        let opacity = 1.0;
+        let image_position = [position.x().as_(), position.y().as_()];
+        let store_index = self.add_image_to_store(background.image.clone());
+        self.buffer_size += background.image.bytes().len();
+        for slice in background.slices(size) {
+            let vertex = |x: f32, y: f32, tex_coords: [f32; 2]| ImageVertex {
+                position: image_position,
+                opacity,
+                tex_coords,
+                // Slice coordinates have `y` axis pointing down.
+                offset: [left + x, top - y],
+            };
+            let vertices = [
+                vertex(
+                    slice.min[0],
+                    slice.max[1],
+                    [slice.tex_min[0], slice.tex_max[1]],
+                ),
+                vertex(slice.min[0], slice.min[1], slice.tex_min),
+                vertex(slice.max[0], slice.max[1], slice.tex_max),
+                vertex(
+                    slice.max[0],
+                    slice.min[1],
+                    [slice.tex_max[0], slice.tex_min[1]],
+                ),
+            ];
+
+            self.buffer_size += size_of::<ImageVertex>() * 4;
+            let image_index = self.add_image_info(store_index, vertices);
+            parts.push(PrimitiveInfo::Image { image_index });
+        }
+
+        if let Some(bounds) = text_bounds {
+            let shift = [
+                offset.x - (bounds.x_min() + bounds.x_max()) / 2.0,
+                offset.y - (bounds.y_min() + bounds.y_max()) / 2.0,
+            ];
+            let vertices_start = self.screen_ref.vertices.len();
+            let start_index_count = self.screen_ref.indices.len();
+            for glyph in glyphs {
+                let glyph_start = self.screen_ref.vertices.len() as u32;
+                for vertex in glyph.vertices {
+                    self.screen_ref.vertices.push(ScreenRefVertex {
+                        position: [position.x().as_(), position.y().as_(), position.z().as_()],
+                        normal: [vertex[0] + shift[0], vertex[1] + shift[1]],
+                        color: style.font_color.to_u8_array(),
+                    });
+                }
+                for index in glyph.indices {
+                    self.screen_ref.indices.push(index + glyph_start);
+                }
+            }
+
+            self.buffer_size += (self.screen_ref.vertices.len() - vertices_start)
+                * size_of::<ScreenRefVertex>()
+                + (self.screen_ref.indices.len() - start_index_count) * size_of::<u32>();
+            parts.push(PrimitiveInfo::ScreenRef {
+                vertex_range: vertices_start..self.screen_ref.vertices.len(),
+            });
+        }
+
+        PrimitiveInfo::Composite(parts)
+    }
 }
 
 fn get_circle_sector(radius: f32, start_angle: f32, end_angle: f32) -> Vec<Point2<f32>> {
@@ -1135,6 +2133,7 @@ struct LineVertexConstructor<'a> {
     color: [f32; 4],
     resolution: f32,
     path: &'a Path,
+    map_units: bool,
 }
 
 impl StrokeVertexConstructor<PolyVertex> for LineVertexConstructor<'_> {
@@ -1145,6 +2144,22 @@ impl StrokeVertexConstructor<PolyVertex> for LineVertexConstructor<'_> {
             Side::Positive => self.offset,
         };
 
+        if self.map_units {
+            // Line width is scaled together with the map, so the vertex is positioned in map coordinates directly
+            // instead of being shifted along the normal in the shader.
+            let position = position + vertex.normal() * (vertex.line_width() / 2.0 + offset);
+            return PolyVertex {
+                position: [
+                    position.x * self.resolution,
+                    position.y * self.resolution,
+                    vertex.interpolated_attributes()[0],
+                ],
+                color: self.color,
+                normal: Default::default(),
+                norm_limit: f32::MAX,
+            };
+        }
+
         let normal = [
             vertex.normal().x * (vertex.line_width() / 2.0 + offset),
             vertex.normal().y * (vertex.line_width() / 2.0 + offset),
@@ -1198,6 +2213,39 @@ impl FillVertexConstructor<PolyVertex> for PolygonVertexConstructor {
     }
 }
 
+struct MapRefShapeVertexConstructor {
+    color: [f32; 4],
+    position: [f32; 3],
+    offset: Vector2<f32>,
+}
+
+impl MapRefShapeVertexConstructor {
+    fn create_vertex(&self, position: lyon::math::Point) -> PolyVertex {
+        PolyVertex {
+            position: [
+                self.position[0] + position.x + self.offset.x,
+                self.position[1] + position.y + self.offset.y,
+                self.position[2],
+            ],
+            color: self.color,
+            normal: Default::default(),
+            norm_limit: f32::MAX,
+        }
+    }
+}
+
+impl StrokeVertexConstructor<PolyVertex> for MapRefShapeVertexConstructor {
+    fn new_vertex(&mut self, vertex: StrokeVertex) -> PolyVertex {
+        self.create_vertex(vertex.position())
+    }
+}
+
+impl FillVertexConstructor<PolyVertex> for MapRefShapeVertexConstructor {
+    fn new_vertex(&mut self, vertex: FillVertex) -> PolyVertex {
+        self.create_vertex(vertex.position())
+    }
+}
+
 struct ScreenRefVertexConstructor {
     color: [u8; 4],
     position: [f32; 3],
@@ -1242,6 +2290,155 @@ pub(crate) struct PointInstance {
     pub color: [u8; 4],
 }
 
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct ShapeInstance {
+    pub position: [f32; 3],
+    pub scale: f32,
+    pub rotation: f32,
+    pub opacity: f32,
+}
+
+impl ShapeInstance {
+    fn new<N, P>(point: &P, transform: &PointInstanceTransform) -> Self
+    where
+        N: AsPrimitive<f32>,
+        P: CartesianPoint3d<Num = N>,
+    {
+        Self {
+            position: [point.x().as_(), point.y().as_(), point.z().as_()],
+            scale: transform.scale,
+            rotation: transform.rotation,
+            opacity: 1.0,
+        }
+    }
+}
+
+/// Vertices of the image drawn at the `position` with its size in pixels. The `offset` is given as a portion of the
+/// image size.
+fn image_point_vertices(
+    position: [f32; 2],
+    opacity: f32,
+    width: f32,
+    height: f32,
+    offset: Vector2<f32>,
+) -> [ImageVertex; 4] {
+    let offset_x = -offset[0] * width;
+    let offset_y = offset[1] * height;
+
+    [
+        ImageVertex {
+            position,
+            opacity,
+            tex_coords: [0.0, 1.0],
+            offset: [offset_x, offset_y - height],
+        },
+        ImageVertex {
+            position,
+            opacity,
+            tex_coords: [0.0, 0.0],
+            offset: [offset_x, offset_y],
+        },
+        ImageVertex {
+            position,
+            opacity,
+            tex_coords: [1.0, 1.0],
+            offset: [offset_x + width, offset_y - height],
+        },
+        ImageVertex {
+            position,
+            opacity,
+            tex_coords: [1.0, 0.0],
+            offset: [offset_x + width, offset_y],
+        },
+    ]
+}
+
+/// Copy of the paint to be stored with a shared instanced shape. Returns `None` for paints that cannot be shared.
+fn shared_paint(paint: &PointPaint) -> Option<PointPaint<'static>> {
+    let shape = match &paint.shape {
+        PointShape::Circle {
+            fill,
+            radius,
+            outline,
+        } => PointShape::Circle {
+            fill: *fill,
+            radius: *radius,
+            outline: *outline,
+        },
+        PointShape::Sector(parameters) => PointShape::Sector(*parameters),
+        PointShape::Square {
+            fill,
+            size,
+            outline,
+        } => PointShape::Square {
+            fill: *fill,
+            size: *size,
+            outline: *outline,
+        },
+        PointShape::FreeShape {
+            fill,
+            scale,
+            outline,
+            shape,
+        } => PointShape::FreeShape {
+            fill: *fill,
+            scale: *scale,
+            outline: *outline,
+            shape: Cow::Owned(shape.as_ref().clone()),
+        },
+        PointShape::Image {
+            image,
+            opacity,
+            width,
+            height,
+        } => PointShape::Image {
+            image: image.clone(),
+            opacity: *opacity,
+            width: *width,
+            height: *height,
+        },
+        PointShape::Dot { .. } | PointShape::Label { .. } | PointShape::Shield { .. } => {
+            return None
+        }
+    };
+
+    Some(PointPaint {
+        shape,
+        offset: paint.offset,
+        units: paint.units,
+    })
+}
+
+/// Compares the paints of instanced points. Images are compared by reference, as comparing their pixels for every
+/// point would be too slow.
+fn same_instanced_paint(a: &PointPaint, b: &PointPaint) -> bool {
+    match (&a.shape, &b.shape) {
+        (
+            PointShape::Image {
+                image: image_a,
+                opacity: opacity_a,
+                width: width_a,
+                height: height_a,
+            },
+            PointShape::Image {
+                image: image_b,
+                opacity: opacity_b,
+                width: width_b,
+                height: height_b,
+            },
+        ) => {
+            Arc::ptr_eq(image_a, image_b)
+                && opacity_a == opacity_b
+                && width_a == width_b
+                && height_a == height_b
+                && a.offset == b.offset
+                && a.units == b.units
+        }
+        _ => a == b,
+    }
+}
+
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 pub(crate) struct ImageVertex {
@@ -1257,6 +2454,7 @@ pub(crate) mod serialization;
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::render::{LineCap, LineJoin};
 
     type C = galileo_types::impls::Contour<Point3d>;
 
@@ -1309,4 +2507,247 @@ mod tests {
 
         assert_eq!(vertex_range.end, vertex_count);
     }
+
+    #[test]
+    fn primitive_opacity_is_reversible() {
+        let mut bundle = TessellatingRenderBundle::new();
+        let polygon = galileo_types::impls::Polygon::from(vec![
+            Point3d::new(0.0, 0.0, 0.0),
+            Point3d::new(1.0, 0.0, 0.0),
+            Point3d::new(1.0, 1.0, 0.0),
+        ]);
+        let color = Color::RED.with_alpha(128);
+        let id = bundle.add(
+            RenderPrimitive::<_, _, C, _>::new_polygon_ref(&polygon, PolygonPaint { color }),
+            1.0,
+        );
+        let alphas = |bundle: &TessellatingRenderBundle| {
+            bundle
+                .poly_tessellation
+                .vertices
+                .iter()
+                .map(|v| v.color[3])
+                .collect::<Vec<_>>()
+        };
+        let original = alphas(&bundle);
+
+        bundle.set_primitive_opacity(id, 0.0).unwrap();
+        assert!(alphas(&bundle).iter().all(|alpha| *alpha == 0.0));
+
+        bundle.set_primitive_opacity(id, 0.5).unwrap();
+        assert_eq!(
+            alphas(&bundle),
+            original.iter().map(|alpha| alpha * 0.5).collect::<Vec<_>>()
+        );
+
+        bundle.set_primitive_opacity(id, 1.0).unwrap();
+        assert_eq!(alphas(&bundle), original);
+    }
+
+    #[test]
+    fn line_join_is_applied() {
+        let line = C::open(vec![
+            Point3d::new(0.0, 0.0, 0.0),
+            Point3d::new(100.0, 0.0, 0.0),
+            Point3d::new(0.0, 10.0, 0.0),
+        ]);
+        let vertex_count = |line_join| {
+            let mut bundle = TessellatingRenderBundle::new();
+            bundle.add_line(
+                &line,
+                LinePaint {
+                    color: Color::BLACK,
+                    width: 10.0,
+                    offset: 0.0,
+                    line_cap: LineCap::Butt,
+                    line_join,
+                    miter_limit: LinePaint::DEFAULT_MITER_LIMIT,
+                    units: SizeUnits::Pixels,
+                },
+                1.0,
+            );
+            bundle.poly_tessellation.vertices.len()
+        };
+
+        assert!(vertex_count(LineJoin::Round) > vertex_count(LineJoin::Bevel));
+    }
+
+    #[test]
+    fn line_width_in_map_units() {
+        let line = C::open(vec![
+            Point3d::new(0.0, 0.0, 0.0),
+            Point3d::new(100.0, 0.0, 0.0),
+        ]);
+        let mut bundle = TessellatingRenderBundle::new();
+        bundle.add_line(
+            &line,
+            LinePaint {
+                color: Color::BLACK,
+                width: 10.0,
+                offset: 0.0,
+                line_cap: LineCap::Butt,
+                line_join: LineJoin::Round,
+                miter_limit: LinePaint::DEFAULT_MITER_LIMIT,
+                units: SizeUnits::MapUnits,
+            },
+            2.0,
+        );
+
+        assert!(!bundle.poly_tessellation.vertices.is_empty());
+        for vertex in &bundle.poly_tessellation.vertices {
+            assert_eq!(vertex.normal, [0.0, 0.0]);
+            assert!((vertex.position[1].abs() - 5.0).abs() < 0.001);
+        }
+    }
+
+    #[test]
+    fn point_in_map_units() {
+        let mut bundle = TessellatingRenderBundle::new();
+        let paint = PointPaint::circle(Color::RED, 20.0).with_units(SizeUnits::MapUnits);
+        bundle.add_point(&Point3d::new(100.0, 100.0, 0.0), &paint);
+
+        assert!(bundle.screen_ref.vertices.is_empty());
+        assert!(!bundle.poly_tessellation.vertices.is_empty());
+        for vertex in &bundle.poly_tessellation.vertices {
+            let dx = vertex.position[0] - 100.0;
+            let dy = vertex.position[1] - 100.0;
+            assert!((dx * dx + dy * dy).sqrt() <= 10.001);
+        }
+    }
+
+    #[test]
+    fn instanced_points() {
+        let mut bundle = TessellatingRenderBundle::new();
+        let paint = PointPaint::circle(Color::RED, 10.0);
+        let instances = [
+            (
+                Point3d::new(0.0, 0.0, 0.0),
+                PointInstanceTransform::default(),
+            ),
+            (
+                Point3d::new(1.0, 1.0, 0.0),
+                PointInstanceTransform::default(),
+            ),
+        ];
+
+        let id = bundle.add_instanced_points(&paint, &instances).unwrap();
+        assert!(bundle.screen_ref.vertices.is_empty());
+
+        let InstancedShapeInfo::Shape {
+            template,
+            instances: stored,
+        } = &bundle.instanced[0]
+        else {
+            panic!("invalid instanced info");
+        };
+        assert!(!template.vertices.is_empty());
+        assert_eq!(stored.len(), 2);
+
+        bundle.set_primitive_opacity(id, 0.5).unwrap();
+        let size = bundle.approx_buffer_size();
+        bundle.update_instances(id, &instances[..1]).unwrap();
+        assert_eq!(
+            bundle.approx_buffer_size(),
+            size - size_of::<ShapeInstance>()
+        );
+
+        // The opacity is kept for the updated instances.
+        let InstancedShapeInfo::Shape { instances, .. } = &bundle.instanced[0] else {
+            panic!("invalid instanced info");
+        };
+        assert_eq!(instances[0].opacity, 0.5);
+
+        bundle.set_primitive_opacity(id, 1.0).unwrap();
+        let InstancedShapeInfo::Shape { instances, .. } = &bundle.instanced[0] else {
+            panic!("invalid instanced info");
+        };
+        assert_eq!(instances[0].opacity, 1.0);
+
+        bundle.remove(id).unwrap();
+        assert!(matches!(bundle.instanced[0], InstancedShapeInfo::Vacant));
+        assert_eq!(bundle.approx_buffer_size(), 0);
+    }
+
+    #[test]
+    fn points_share_instanced_shapes() {
+        let mut bundle = TessellatingRenderBundle::new();
+        let red = PointPaint::circle(Color::RED, 10.0);
+        let blue = PointPaint::circle(Color::BLUE, 10.0);
+
+        let ids: Vec<_> = (0..3)
+            .map(|i| bundle.add_instanced_point(&Point3d::new(i as f64, 0.0, 0.0), &red))
+            .collect();
+        let blue_id = bundle.add_instanced_point(&Point3d::new(0.0, 1.0, 0.0), &blue);
+
+        assert!(bundle.screen_ref.vertices.is_empty());
+        assert_eq!(bundle.instanced.len(), 2);
+
+        bundle.remove(ids[0]).unwrap();
+        let Some(PrimitiveInfo::SharedInstance { instance_index, .. }) =
+            bundle.primitives.get(ids[2].0)
+        else {
+            panic!("invalid primitive info");
+        };
+        assert_eq!(*instance_index, 0);
+
+        bundle.set_primitive_opacity(ids[2], 0.5).unwrap();
+        let InstancedShapeInfo::Shape { instances, .. } = &bundle.instanced[0] else {
+            panic!("invalid instanced info");
+        };
+        assert_eq!(instances[0].opacity, 0.5);
+        assert_eq!(instances[0].position, [2.0, 0.0, 0.0]);
+
+        bundle.remove(ids[1]).unwrap();
+        bundle.remove(ids[2]).unwrap();
+        bundle.remove(blue_id).unwrap();
+        assert!(bundle
+            .instanced
+            .iter()
+            .all(|info| matches!(info, InstancedShapeInfo::Vacant)));
+        assert_eq!(bundle.approx_buffer_size(), 0);
+    }
+
+    #[test]
+    fn images_share_instanced_shapes() {
+        let mut bundle = TessellatingRenderBundle::new();
+        let image = Arc::new(DecodedImage::from_raw(vec![0u8; 2 * 2 * 4], 2, 2).unwrap());
+        let paint = PointPaint::image(image, Vector2::new(0.5, 1.0), 1.0);
+
+        let first = bundle.add_instanced_point(&Point3d::new(0.0, 0.0, 0.0), &paint);
+        let second = bundle.add_instanced_point(&Point3d::new(1.0, 0.0, 0.0), &paint);
+
+        assert!(bundle.images.is_empty());
+        assert_eq!(bundle.image_store.len(), 1);
+        let InstancedShapeInfo::Image { instances, .. } = &bundle.instanced[0] else {
+            panic!("invalid instanced info");
+        };
+        assert_eq!(instances.len(), 2);
+
+        bundle.remove(first).unwrap();
+        bundle.remove(second).unwrap();
+        assert!(matches!(bundle.image_store[0], ImageStoreInfo::Vacant));
+        assert_eq!(bundle.approx_buffer_size(), 0);
+    }
+
+    #[test]
+    fn dots_are_not_instanced() {
+        let mut bundle = TessellatingRenderBundle::new();
+        let paint = PointPaint::dot(Color::RED);
+        let id = bundle.add_instanced_point(&Point3d::new(0.0, 0.0, 0.0), &paint);
+
+        assert!(bundle.instanced.is_empty());
+        assert!(matches!(bundle.primitives[id.0], PrimitiveInfo::Dot { .. }));
+    }
+
+    #[test]
+    fn instanced_points_reject_dots() {
+        let mut bundle = TessellatingRenderBundle::new();
+        let paint = PointPaint::dot(Color::RED);
+        let instances = [(
+            Point3d::new(0.0, 0.0, 0.0),
+            PointInstanceTransform::default(),
+        )];
+
+        assert!(bundle.add_instanced_points(&paint, &instances).is_err());
+    }
 }
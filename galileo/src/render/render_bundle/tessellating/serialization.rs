@@ -1,7 +1,9 @@
 use crate::decoded_image::DecodedImage;
 use crate::render::render_bundle::tessellating::{
-    ImageInfo, ImageStoreInfo, PolyVertex, PrimitiveInfo, ScreenRefVertex, TessellatingRenderBundle,
+    ImageInfo, ImageStoreInfo, InstancedShapeInfo, PolyVertex, PrimitiveInfo, ScreenRefVertex,
+    ShapeInstance, TessellatingRenderBundle,
 };
+use galileo_types::cartesian::{CartesianPoint2d, Point2d};
 use lyon::lyon_tessellation::VertexBuffers;
 use serde::{Deserialize, Serialize};
 use std::mem::size_of;
@@ -12,6 +14,7 @@ pub(crate) struct TessellatingRenderBundleBytes {
     pub poly_tessellation: PolyVertexBuffersBytes,
     pub points: Vec<u32>,
     pub screen_ref: ScreenRefVertexBuffersBytes,
+    pub instanced: Vec<Option<InstancedShapeBytes>>,
     pub images: Vec<Option<ImageBytes>>,
     pub primitives: Vec<PrimitiveInfo>,
     pub image_store: Vec<Option<(u32, u32, Vec<u8>)>>,
@@ -19,6 +22,7 @@ pub(crate) struct TessellatingRenderBundleBytes {
     pub vacant_image_store_ids: Vec<usize>,
     pub clip_area: Option<PolyVertexBuffersBytes>,
     pub bundle_size: usize,
+    pub origin: [f64; 2],
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -27,6 +31,22 @@ pub(crate) struct ImageBytes {
     vertices: Vec<u32>,
 }
 
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) enum InstancedShapeBytes {
+    Shape {
+        template: ScreenRefVertexBuffersBytes,
+        instances: Vec<ShapeInstanceShim>,
+    },
+    Image {
+        image_index: usize,
+        vertices: Vec<u32>,
+        instances: Vec<ShapeInstanceShim>,
+    },
+}
+
+const SHAPE_INSTANCE_BLOCKS: usize = size_of::<ShapeInstance>() / size_of::<u32>();
+type ShapeInstanceShim = [u32; SHAPE_INSTANCE_BLOCKS];
+
 const POLY_VERTEX_BLOCKS: usize = size_of::<PolyVertex>() / size_of::<u32>();
 
 type PolyVertexShim = [u32; POLY_VERTEX_BLOCKS];
@@ -88,6 +108,29 @@ impl TessellatingRenderBundle {
             poly_tessellation: self.poly_tessellation.into(),
             points: bytemuck::cast_vec(self.points),
             screen_ref: self.screen_ref.into(),
+            instanced: self
+                .instanced
+                .into_iter()
+                .map(|info| match info {
+                    InstancedShapeInfo::Vacant => None,
+                    InstancedShapeInfo::Shape {
+                        template,
+                        instances,
+                    } => Some(InstancedShapeBytes::Shape {
+                        template: template.into(),
+                        instances: bytemuck::cast_vec(instances),
+                    }),
+                    InstancedShapeInfo::Image {
+                        image_index,
+                        vertices,
+                        instances,
+                    } => Some(InstancedShapeBytes::Image {
+                        image_index,
+                        vertices: bytemuck::cast_vec(vertices.to_vec()),
+                        instances: bytemuck::cast_vec(instances),
+                    }),
+                })
+                .collect(),
             images: self
                 .images
                 .into_iter()
@@ -114,6 +157,7 @@ impl TessellatingRenderBundle {
             vacant_image_store_ids: self.vacant_image_store_ids,
             clip_area: self.clip_area.map(|v| v.into()),
             bundle_size: self.buffer_size,
+            origin: [self.origin.x(), self.origin.y()],
         }
     }
 
@@ -122,6 +166,31 @@ impl TessellatingRenderBundle {
             poly_tessellation: bundle.poly_tessellation.into_typed_unchecked(),
             points: bytemuck::cast_vec(bundle.points),
             screen_ref: bundle.screen_ref.into_typed_unchecked(),
+            instanced: bundle
+                .instanced
+                .into_iter()
+                .map(|item| match item {
+                    Some(InstancedShapeBytes::Shape {
+                        template,
+                        instances,
+                    }) => InstancedShapeInfo::Shape {
+                        template: template.into_typed_unchecked(),
+                        instances: bytemuck::cast_vec(instances),
+                    },
+                    Some(InstancedShapeBytes::Image {
+                        image_index,
+                        vertices,
+                        instances,
+                    }) => InstancedShapeInfo::Image {
+                        image_index,
+                        vertices: bytemuck::cast_vec(vertices)
+                            .try_into()
+                            .expect("invalid vector length"),
+                        instances: bytemuck::cast_vec(instances),
+                    },
+                    None => InstancedShapeInfo::Vacant,
+                })
+                .collect(),
             images: bundle
                 .images
                 .into_iter()
@@ -155,6 +224,9 @@ impl TessellatingRenderBundle {
             vacant_image_store_ids: bundle.vacant_image_store_ids,
             clip_area: bundle.clip_area.map(|v| v.into_typed_unchecked()),
             buffer_size: bundle.bundle_size,
+            origin: Point2d::new(bundle.origin[0], bundle.origin[1]),
+            faded_primitives: Default::default(),
+            shared_instances: vec![],
             vacant_ids: vec![],
         }
     }
@@ -1,5 +1,7 @@
 //! Service for text rendering.
 
+use crate::error::GalileoError;
+use crate::layer::data_provider::PersistentCacheController;
 use crate::render::text::rustybuzz::RustybuzzFontServiceProvider;
 use crate::render::text::{FontServiceProvider, TextShaping, TextStyle};
 use bytes::Bytes;
@@ -68,4 +70,17 @@ impl FontService {
     pub fn load_fonts(&mut self, fonts_data: Bytes) -> Result<(), FontServiceError> {
         self.provider.load_fonts(fonts_data)
     }
+
+    /// Loads the font file from the URL into the singleton instance of the service, using the persistent `cache` to
+    /// avoid loading it again on next starts of the application. To keep the fonts in a
+    /// [`Storage`](crate::storage::Storage), use a [`StorageCacheController`](crate::storage::StorageCacheController)
+    /// with the [`GLYPHS_NAMESPACE`](crate::storage::GLYPHS_NAMESPACE).
+    pub async fn load_fonts_from_url(
+        url: &str,
+        cache: &impl PersistentCacheController<str, Bytes>,
+    ) -> Result<(), GalileoError> {
+        let data = crate::storage::load_cached(url, cache).await?;
+        Self::with_mut(|service| service.load_fonts(data))
+            .map_err(|err| GalileoError::Generic(format!("failed to load fonts from {url}: {err}")))
+    }
 }
@@ -2,6 +2,7 @@
 
 use crate::Color;
 use bytes::Bytes;
+use galileo_types::cartesian::Rect;
 use nalgebra::Vector2;
 use serde::{Deserialize, Serialize};
 
@@ -17,7 +18,7 @@ pub(crate) use font_service::FontService;
 mod rustybuzz;
 
 /// Style of a text label on the map.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TextStyle {
     /// Name of the font to use.
     pub font_name: String,
@@ -32,6 +33,9 @@ pub struct TextStyle {
     /// Alignment of label along vertical axis.
     #[serde(default)]
     pub vertical_alignment: VerticalAlignment,
+    /// Base direction of the text.
+    #[serde(default)]
+    pub direction: TextDirection,
 }
 
 fn default_font_color() -> Color {
@@ -39,7 +43,7 @@ fn default_font_color() -> Color {
 }
 
 /// Horizontal alignment.
-#[derive(Default, Debug, Copy, Clone, Serialize, Deserialize)]
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum HorizontalAlignment {
     /// Align to left.
     Left,
@@ -51,7 +55,7 @@ pub enum HorizontalAlignment {
 }
 
 /// Vertical alignment.
-#[derive(Default, Debug, Copy, Clone, Serialize, Deserialize)]
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum VerticalAlignment {
     /// Align to top.
     Top,
@@ -62,6 +66,21 @@ pub enum VerticalAlignment {
     Bottom,
 }
 
+/// Base direction of a text.
+///
+/// Text that mixes left-to-right and right-to-left scripts (e.g. an Arabic street name with a number) is reordered
+/// with the Unicode Bidirectional Algorithm. The base direction determines the order of the directional runs.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TextDirection {
+    /// Direction is determined by the first strong directional character of the text.
+    #[default]
+    Auto,
+    /// Left-to-right text.
+    LeftToRight,
+    /// Right-to-left text.
+    RightToLeft,
+}
+
 /// Type of text render to use for label.
 pub enum TextShaping {
     /// Text will be renderred as a set of tessellated glyphs (e.g. a number of triangles) and
@@ -82,6 +101,19 @@ pub struct TessellatedGlyph {
     pub indices: Vec<u32>,
 }
 
+/// Bounding rectangle of the vertices of the glyphs. Returns `None` if there are no vertices (e.g. the text consists
+/// of whitespace only).
+pub(crate) fn glyphs_bounds(glyphs: &[TessellatedGlyph]) -> Option<Rect<f32>> {
+    let mut vertices = glyphs.iter().flat_map(|glyph| glyph.vertices.iter());
+    let first = vertices.next()?;
+    let mut bounds = Rect::new(first[0], first[1], first[0], first[1]);
+    for vertex in vertices {
+        bounds = bounds.merge(Rect::new(vertex[0], vertex[1], vertex[0], vertex[1]));
+    }
+
+    Some(bounds)
+}
+
 /// Data provider for font service.
 pub trait FontServiceProvider {
     /// Shape text label.
@@ -6,10 +6,14 @@ use lyon::path::path::Builder;
 use lyon::path::Path;
 use nalgebra::Vector2;
 use rustybuzz::ttf_parser::{GlyphId, OutlineBuilder};
-use rustybuzz::{Face, UnicodeBuffer};
+use rustybuzz::{Direction, Face, UnicodeBuffer};
+use std::ops::Range;
+use unicode_bidi::{BidiInfo, Level};
 
 use crate::render::text::font_service::FontServiceError;
-use crate::render::text::{FontServiceProvider, TessellatedGlyph, TextShaping, TextStyle};
+use crate::render::text::{
+    FontServiceProvider, TessellatedGlyph, TextDirection, TextShaping, TextStyle,
+};
 
 #[derive(Default)]
 pub struct RustybuzzFontServiceProvider {
@@ -31,35 +35,39 @@ impl FontServiceProvider for RustybuzzFontServiceProvider {
         style: &TextStyle,
         offset: Vector2<f32>,
     ) -> Result<TextShaping, FontServiceError> {
-        let mut buffer = UnicodeBuffer::new();
-        buffer.push_str(text);
-        buffer.guess_segment_properties();
-
-        let Some(face) = self.select_face(&buffer) else {
-            return Err(FontServiceError::FontNotFound);
-        };
-
-        let units = face.units_per_em() as f32;
-        let scale = style.font_size / units;
-
-        let glyph_buffer = rustybuzz::shape(&face, &[], buffer);
         let mut tessellations = vec![];
-
         let mut advance_x = 0;
         let mut advance_y = 0;
-        for index in 0..glyph_buffer.len() {
-            let position = glyph_buffer.glyph_positions()[index];
-            let glyph_info = glyph_buffer.glyph_infos()[index];
-
-            let mut path_builder = GlyphPathBuilder::new(scale);
-            face.outline_glyph(GlyphId(glyph_info.glyph_id as u16), &mut path_builder);
-            tessellations.push(path_builder.tessellate(Vector2::new(
-                offset.x + (position.x_offset + advance_x) as f32 * scale,
-                offset.y + (position.y_offset + advance_y) as f32 * scale,
-            )));
-
-            advance_x += position.x_advance;
-            advance_y += position.y_advance;
+
+        // Runs are shaped separately in the visual order (from left to right) and placed one after another.
+        for (range, direction) in visual_runs(text, style.direction) {
+            let mut buffer = UnicodeBuffer::new();
+            buffer.push_str(&text[range]);
+            buffer.set_direction(direction);
+            buffer.guess_segment_properties();
+
+            let Some(face) = self.select_face(&buffer) else {
+                return Err(FontServiceError::FontNotFound);
+            };
+
+            let units = face.units_per_em() as f32;
+            let scale = style.font_size / units;
+
+            let glyph_buffer = rustybuzz::shape(&face, &[], buffer);
+            for index in 0..glyph_buffer.len() {
+                let position = glyph_buffer.glyph_positions()[index];
+                let glyph_info = glyph_buffer.glyph_infos()[index];
+
+                let mut path_builder = GlyphPathBuilder::new(scale);
+                face.outline_glyph(GlyphId(glyph_info.glyph_id as u16), &mut path_builder);
+                tessellations.push(path_builder.tessellate(Vector2::new(
+                    offset.x + (position.x_offset + advance_x) as f32 * scale,
+                    offset.y + (position.y_offset + advance_y) as f32 * scale,
+                )));
+
+                advance_x += position.x_advance;
+                advance_y += position.y_advance;
+            }
         }
 
         Ok(TextShaping::Tessellation {
@@ -73,6 +81,32 @@ impl FontServiceProvider for RustybuzzFontServiceProvider {
     }
 }
 
+/// Splits the text into runs of the same direction with the Unicode Bidirectional Algorithm and returns them in the
+/// visual order. Lines of a multiline text are placed one after another.
+fn visual_runs(text: &str, direction: TextDirection) -> Vec<(Range<usize>, Direction)> {
+    let base_level = match direction {
+        TextDirection::Auto => None,
+        TextDirection::LeftToRight => Some(Level::ltr()),
+        TextDirection::RightToLeft => Some(Level::rtl()),
+    };
+
+    let bidi = BidiInfo::new(text, base_level);
+    let mut result = vec![];
+    for paragraph in &bidi.paragraphs {
+        let (levels, runs) = bidi.visual_runs(paragraph, paragraph.range.clone());
+        for run in runs {
+            let direction = if levels[run.start].is_rtl() {
+                Direction::RightToLeft
+            } else {
+                Direction::LeftToRight
+            };
+            result.push((run, direction));
+        }
+    }
+
+    result
+}
+
 struct GlyphPathBuilder {
     builder: Builder,
     scale: f32,
@@ -157,3 +191,37 @@ impl FillVertexConstructor<[f32; 2]> for GlyphVertexConstructor {
         ]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mixed_text_is_split_into_visual_runs() {
+        let text = "abc שלום";
+        assert_eq!(
+            visual_runs(text, TextDirection::Auto),
+            vec![
+                (0..4, Direction::LeftToRight),
+                (4..12, Direction::RightToLeft)
+            ]
+        );
+
+        // In a right-to-left paragraph the Latin run is displayed to the left of the Hebrew one.
+        let text = "שלום abc";
+        assert_eq!(
+            visual_runs(text, TextDirection::Auto),
+            vec![
+                (9..12, Direction::LeftToRight),
+                (0..9, Direction::RightToLeft)
+            ]
+        );
+        assert_eq!(
+            visual_runs(text, TextDirection::LeftToRight),
+            vec![
+                (0..8, Direction::RightToLeft),
+                (8..12, Direction::LeftToRight)
+            ]
+        );
+    }
+}
@@ -1,33 +1,40 @@
 use cfg_if::cfg_if;
-use galileo_types::cartesian::Size;
+use galileo_types::cartesian::{CartesianPoint2d, Point2d, Size};
 use lyon::tessellation::VertexBuffers;
-use nalgebra::{Rotation3, Vector3};
+use nalgebra::{Matrix4, Rotation3, Translation3, Vector3};
 use std::any::Any;
 use std::mem::size_of;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use wgpu::util::DeviceExt;
 use wgpu::{
-    Adapter, Buffer, BufferAddress, BufferDescriptor, BufferUsages, Device, Extent3d,
+    Adapter, BindGroup, Buffer, BufferAddress, BufferDescriptor, BufferUsages, Device, Extent3d,
     ImageCopyBuffer, ImageCopyTexture, ImageDataLayout, Origin3d, Queue,
     RenderPassDepthStencilAttachment, StoreOp, Surface, SurfaceConfiguration, SurfaceError,
     SurfaceTexture, Texture, TextureAspect, TextureDescriptor, TextureDimension, TextureFormat,
     TextureUsages, TextureView, TextureViewDescriptor, WasmNotSendSync,
 };
 
+use crate::alloc_audit;
 use crate::error::GalileoError;
 use crate::layer::Layer;
-use crate::map::Map;
+use crate::map::{Map, RenderQuality};
 use crate::render::render_bundle::tessellating::{
-    PointInstance, PolyVertex, TessellatingRenderBundle,
+    ImageVertex, InstancedShapeInfo, PointInstance, PolyVertex, ScreenRefTessellation,
+    ShapeInstance, TessellatingRenderBundle,
 };
 use crate::render::render_bundle::{RenderBundle, RenderBundleType};
 use crate::render::wgpu::pipelines::image::WgpuImage;
-use crate::render::wgpu::pipelines::Pipelines;
+use crate::render::wgpu::pipelines::particles::{ParticlePipeline, WgpuParticles};
+use crate::render::wgpu::pipelines::{CustomPipelines, Pipelines};
 use crate::view::MapView;
 use crate::Color;
 
 use super::render_bundle::tessellating::{ImageInfo, ImageStoreInfo};
-use super::{Canvas, PackedBundle, RenderOptions};
+use super::{
+    Canvas, CustomShader, PackedBundle, PackedParticles, ParticleField, ParticleFrame,
+    RenderOptions, RendererEvent,
+};
 
 mod pipelines;
 
@@ -36,11 +43,42 @@ const DEPTH_FORMAT: TextureFormat = TextureFormat::Depth24PlusStencil8;
 const TARGET_TEXTURE_FORMAT: TextureFormat = TextureFormat::Rgba8UnormSrgb;
 
 /// Render backend that uses `wgpu` crate to render the map.
+///
+/// # Device loss
+///
+/// The GPU device can be lost at any time, for example when the driver is reset or when a laptop switches between
+/// integrated and discrete GPUs. When the renderer created the device itself, it watches for this and stops
+/// submitting any work to the lost device, so [`WgpuRenderer::is_device_lost`] should be checked before rendering.
+/// A lost renderer can be brought back with [`WgpuRenderer::restart`], which creates a new device and returns
+/// [`RendererEvent::RendererRestarted`] to be passed to [`Map::handle_renderer_event`], so the layers upload their
+/// data again.
 pub struct WgpuRenderer {
     device: Arc<Device>,
     queue: Arc<Queue>,
     render_set: Option<RenderSet>,
     background: Color,
+    instance: Option<Arc<wgpu::Instance>>,
+    device_lost: Arc<AtomicBool>,
+    color_space: OutputColorSpace,
+}
+
+/// Color space of the window surface the [`WgpuRenderer`] draws to.
+///
+/// Colors of all the primitives are specified in sRGB. When the surface encodes colors itself (which is the case for
+/// both variants, if supported by the platform), the renderer converts the colors into linear space, so blending and
+/// image sampling are done in linear space, same as browsers do.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum OutputColorSpace {
+    /// 8-bit sRGB surface.
+    #[default]
+    Srgb,
+    /// Wide-gamut surface. The renderer selects a 16-bit float surface format, which the platforms supporting
+    /// wide-gamut displays (e.g. macOS with Display-P3 screens or Windows with HDR enabled) composite in extended
+    /// linear sRGB space, so the output is not clamped to the sRGB gamut and gradients have no banding.
+    ///
+    /// `wgpu` does not allow to set the color space of the surface explicitly, so the result depends on the
+    /// platform. If the surface does not support float formats, an sRGB surface is used.
+    WideGamut,
 }
 
 struct RenderSet {
@@ -120,12 +158,16 @@ impl WgpuRenderer {
             .await?;
 
         let (device, queue) = Self::create_device(&adapter).await;
+        let device_lost = Self::watch_device(&device);
 
         Some(Self {
             device: Arc::new(device),
             queue: Arc::new(queue),
             render_set: None,
             background: DEFAULT_BACKGROUND,
+            instance: Some(Arc::new(instance)),
+            device_lost,
+            color_space: OutputColorSpace::default(),
         })
     }
 
@@ -222,25 +264,63 @@ impl WgpuRenderer {
             + WasmNotSendSync
             + 'static,
     {
-        let (surface, adapter) = Self::get_window_surface(window).await?;
+        Self::new_with_window_and_color_space(window, size, OutputColorSpace::default()).await
+    }
+
+    /// Creates a new wgpu renderer that renders the map to the given window using the given output color space. The
+    /// given size must be equal to the window size.
+    ///
+    /// Returns `None` if a device adapter cannot be acquired.
+    pub async fn new_with_window_and_color_space<W>(
+        window: Arc<W>,
+        size: Size<u32>,
+        color_space: OutputColorSpace,
+    ) -> Option<Self>
+    where
+        W: raw_window_handle::HasWindowHandle
+            + raw_window_handle::HasDisplayHandle
+            + WasmNotSendSync
+            + 'static,
+    {
+        let (instance, surface, adapter) = Self::create_window_surface(window).await?;
         let (device, queue) = Self::create_device(&adapter).await;
+        let device_lost = Self::watch_device(&device);
 
-        let config = Self::get_surface_configuration(&surface, &adapter, size);
+        let config = Self::get_surface_configuration(&surface, &adapter, size, color_space);
         log::info!("Configuring surface with size {size:?}");
         surface.configure(&device, &config);
 
-        Some(Self::new_with_device_and_surface(
+        let mut renderer = Self::new_with_device_and_surface(
             Arc::new(device),
             Arc::new(surface),
             Arc::new(queue),
             config,
-        ))
+        );
+        renderer.instance = Some(Arc::new(instance));
+        renderer.device_lost = device_lost;
+        renderer.color_space = color_space;
+
+        Some(renderer)
     }
 
     /// Creates a wgpu surface for the given window.
     ///
     /// Returns `None` if a device adapter cannot be acquired.
     pub async fn get_window_surface<W>(window: Arc<W>) -> Option<(Surface<'static>, Adapter)>
+    where
+        W: raw_window_handle::HasWindowHandle
+            + raw_window_handle::HasDisplayHandle
+            + WasmNotSendSync
+            + 'static,
+    {
+        Self::create_window_surface(window)
+            .await
+            .map(|(_, surface, adapter)| (surface, adapter))
+    }
+
+    async fn create_window_surface<W>(
+        window: Arc<W>,
+    ) -> Option<(wgpu::Instance, Surface<'static>, Adapter)>
     where
         W: raw_window_handle::HasWindowHandle
             + raw_window_handle::HasDisplayHandle
@@ -265,21 +345,30 @@ impl WgpuRenderer {
                 force_fallback_adapter: false,
             })
             .await?;
-        Some((surface, adapter))
+        Some((instance, surface, adapter))
     }
 
     fn get_surface_configuration(
         surface: &Surface,
         adapter: &Adapter,
         size: Size<u32>,
+        color_space: OutputColorSpace,
     ) -> SurfaceConfiguration {
         let surface_caps = surface.get_capabilities(adapter);
-        let surface_format = surface_caps
-            .formats
-            .iter()
-            .copied()
-            .find(|f| f.is_srgb())
-            .unwrap_or(surface_caps.formats[0]);
+        let srgb_format = || surface_caps.formats.iter().copied().find(|f| f.is_srgb());
+        let surface_format = match color_space {
+            OutputColorSpace::Srgb => srgb_format(),
+            OutputColorSpace::WideGamut => surface_caps
+                .formats
+                .iter()
+                .copied()
+                .find(|f| *f == TextureFormat::Rgba16Float)
+                .or_else(|| {
+                    log::info!("Surface does not support wide-gamut output, using sRGB");
+                    srgb_format()
+                }),
+        }
+        .unwrap_or(surface_caps.formats[0]);
 
         SurfaceConfiguration {
             usage: TextureUsages::RENDER_ATTACHMENT,
@@ -306,6 +395,9 @@ impl WgpuRenderer {
             queue,
             render_set: None,
             background: DEFAULT_BACKGROUND,
+            instance: None,
+            device_lost: Arc::new(AtomicBool::new(false)),
+            color_space: OutputColorSpace::default(),
         };
         renderer.init_render_set(render_target);
 
@@ -324,6 +416,9 @@ impl WgpuRenderer {
             queue,
             render_set: None,
             background: DEFAULT_BACKGROUND,
+            instance: None,
+            device_lost: Arc::new(AtomicBool::new(false)),
+            color_space: OutputColorSpace::default(),
         };
 
         renderer.init_target_texture(size);
@@ -331,6 +426,11 @@ impl WgpuRenderer {
         renderer
     }
 
+    /// Color space of the window surface. See [`OutputColorSpace`].
+    pub fn output_color_space(&self) -> OutputColorSpace {
+        self.color_space
+    }
+
     /// Set the background color for the map.
     pub fn set_background(&mut self, color: Color) {
         self.background = color;
@@ -338,7 +438,84 @@ impl WgpuRenderer {
 
     /// Returns `true` if the renderer can be used to draw to.
     pub fn initialized(&self) -> bool {
-        self.render_set.is_some()
+        self.render_set.is_some() && !self.is_device_lost()
+    }
+
+    /// Returns `true` if the GPU device used by the renderer was lost. Nothing is rendered until the renderer is
+    /// restarted with [`WgpuRenderer::restart`].
+    ///
+    /// Loss of the device is only tracked if the device was created by the renderer itself.
+    pub fn is_device_lost(&self) -> bool {
+        self.device_lost.load(Ordering::Acquire)
+    }
+
+    /// Recreates the GPU device and all the GPU resources of the renderer, keeping the current render target.
+    ///
+    /// Returns the event that must be passed to [`Map::handle_renderer_event`] of all the maps rendered by this
+    /// renderer, so that the layers upload their data to the new device.
+    ///
+    /// Returns an error if the renderer was created from an external device, or if a new device adapter cannot be
+    /// acquired.
+    pub async fn restart(&mut self) -> Result<RendererEvent, GalileoError> {
+        let Some(instance) = self.instance.clone() else {
+            return Err(GalileoError::Generic(
+                "renderer created with an external device cannot be restarted".into(),
+            ));
+        };
+
+        let render_target = self.render_set.take().map(|set| set.render_target);
+        let compatible_surface = match &render_target {
+            Some(RenderTarget::Surface { surface, .. }) => Some(surface.clone()),
+            _ => None,
+        };
+
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::HighPerformance,
+                compatible_surface: compatible_surface.as_deref(),
+                force_fallback_adapter: false,
+            })
+            .await
+            .ok_or_else(|| GalileoError::Generic("failed to acquire a device adapter".into()))?;
+
+        let (device, queue) = Self::create_device(&adapter).await;
+        self.device_lost = Self::watch_device(&device);
+        self.device = Arc::new(device);
+        self.queue = Arc::new(queue);
+
+        match render_target {
+            Some(RenderTarget::Surface { surface, config }) => {
+                let size = Size::new(config.width, config.height);
+                let config =
+                    Self::get_surface_configuration(&surface, &adapter, size, self.color_space);
+                surface.configure(&self.device, &config);
+                self.init_render_set(RenderTarget::Surface { surface, config });
+            }
+            Some(RenderTarget::Texture(_, size)) => self.init_target_texture(size),
+            None => {}
+        }
+
+        log::info!("Renderer is restarted with a new GPU device");
+
+        Ok(RendererEvent::RendererRestarted)
+    }
+
+    fn watch_device(device: &Device) -> Arc<AtomicBool> {
+        let device_lost = Arc::new(AtomicBool::new(false));
+
+        let flag = device_lost.clone();
+        device.set_device_lost_callback(move |reason, message| {
+            log::error!("GPU device is lost ({reason:?}): {message}");
+            flag.store(true, Ordering::Release);
+        });
+
+        // By default wgpu panics on errors that are not captured in an error scope. After the device is lost every
+        // call results in such error, so they are logged instead.
+        device.on_uncaptured_error(Box::new(|error| {
+            log::error!("GPU error: {error}");
+        }));
+
+        device_lost
     }
 
     fn create_instance() -> wgpu::Instance {
@@ -456,7 +633,7 @@ impl WgpuRenderer {
         adapter: Adapter,
         size: Size<u32>,
     ) {
-        let config = Self::get_surface_configuration(&surface, &adapter, size);
+        let config = Self::get_surface_configuration(&surface, &adapter, size, self.color_space);
         surface.configure(&self.device, &config);
 
         let render_target = RenderTarget::Surface {
@@ -591,8 +768,20 @@ impl WgpuRenderer {
     }
 
     /// Renders the map to the given texture.
+    ///
+    /// Render hooks of the map (see [`Map::add_before_render_hook`]) are called before and after the frame.
     pub fn render_to_texture_view(&self, map: &Map, view: &TextureView) {
-        if let Some(render_set) = &self.render_set {
+        if self.is_device_lost() {
+            return;
+        }
+
+        let Some(render_set) = &self.render_set else {
+            return;
+        };
+
+        let frame = map.begin_frame();
+
+        alloc_audit::without_counting(|| {
             let mut encoder = self
                 .device
                 .create_command_encoder(&wgpu::CommandEncoderDescriptor {
@@ -600,7 +789,11 @@ impl WgpuRenderer {
                 });
 
             {
-                let background = self.background.to_f32_array();
+                let background = if pipelines::is_linear_output(render_set.render_target.format()) {
+                    self.background.to_linear_f32_array()
+                } else {
+                    self.background.to_f32_array()
+                };
                 let _ = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                     label: Some("Render Pass"),
                     color_attachments: &[Some(wgpu::RenderPassColorAttachment {
@@ -622,21 +815,65 @@ impl WgpuRenderer {
                 });
             }
 
+            // Depth buffer is shared by all layers during the frame, so it is cleared only once here.
+            for depth_view in [
+                &render_set.stencil_view_multisample,
+                &render_set.stencil_view,
+            ] {
+                let _ = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("Clear depth pass"),
+                    color_attachments: &[],
+                    depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+                        view: depth_view,
+                        depth_ops: Some(wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(1.0),
+                            store: StoreOp::Store,
+                        }),
+                        stencil_ops: Some(wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(0),
+                            store: StoreOp::Store,
+                        }),
+                    }),
+                    timestamp_writes: None,
+                    occlusion_query_set: None,
+                });
+            }
+
             self.queue.submit(std::iter::once(encoder.finish()));
-        } else {
-            return;
-        }
+        });
 
         self.render_map(map, view);
+
+        map.end_frame(&frame);
     }
 
     /// Renders the map.
+    ///
+    /// If the surface became outdated or was lost (which often happens while the window is being resized), it is
+    /// reconfigured and the frame is rendered again. Returns [`SurfaceError::Lost`] if the GPU device is lost (see
+    /// [`WgpuRenderer::is_device_lost`]).
     pub fn render(&self, map: &Map) -> Result<(), SurfaceError> {
+        if self.is_device_lost() {
+            return Err(SurfaceError::Lost);
+        }
+
         let Some(render_set) = &self.render_set else {
             return Ok(());
         };
 
-        let texture = render_set.render_target.texture()?;
+        let texture = match render_set.render_target.texture() {
+            Ok(texture) => texture,
+            Err(SurfaceError::Outdated | SurfaceError::Lost) => {
+                self.reconfigure_surface(render_set);
+                render_set.render_target.texture()?
+            }
+            Err(SurfaceError::Timeout) => {
+                log::debug!("Timed out while acquiring surface texture, skipping the frame");
+                map.redraw();
+                return Ok(());
+            }
+            Err(err) => return Err(err),
+        };
         let view = texture.view();
 
         self.render_to_texture_view(map, &view);
@@ -646,22 +883,68 @@ impl WgpuRenderer {
         Ok(())
     }
 
+    fn reconfigure_surface(&self, render_set: &RenderSet) {
+        if let RenderTarget::Surface { config, surface } = &render_set.render_target {
+            log::info!(
+                "Reconfiguring surface with size {}x{}",
+                config.width,
+                config.height
+            );
+            surface.configure(&self.device, config);
+        }
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "render_map",
+            skip_all,
+            fields(
+                layers = map.layers().len(),
+                resolution = map.view().resolution()
+            )
+        )
+    )]
     fn render_map(&self, map: &Map, texture_view: &TextureView) {
         let view = map.view();
-        for layer in map.layers().iter_visible() {
-            self.render_layer(layer, view, texture_view);
+        let quality = map.quality();
+
+        // Opaque layers are rendered first, so that translucent layers can be tested against their depth.
+        let is_opaque = |(layer, opacity): &(&dyn Layer, f32)| layer.is_opaque() && *opacity >= 1.0;
+        let opaque_layers = map.layers().iter_visible_with_opacity().filter(is_opaque);
+        let translucent_layers = map
+            .layers()
+            .iter_visible_with_opacity()
+            .filter(|layer| !is_opaque(layer));
+
+        for (layer, opacity) in opaque_layers.chain(translucent_layers) {
+            self.render_layer(layer, opacity, view, quality, texture_view);
         }
     }
 
-    fn render_layer(&self, layer: &dyn Layer, view: &MapView, texture_view: &TextureView) {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "render_layer", skip_all, fields(opaque = layer.is_opaque()))
+    )]
+    fn render_layer(
+        &self,
+        layer: &dyn Layer,
+        opacity: f32,
+        view: &MapView,
+        quality: RenderQuality,
+        texture_view: &TextureView,
+    ) {
         let Some(render_set) = &self.render_set else {
             return;
         };
-        let Some(mut canvas) = WgpuCanvas::new(self, render_set, texture_view, view.clone()) else {
+        let Some(mut canvas) =
+            WgpuCanvas::new(self, render_set, texture_view, view.clone(), quality)
+        else {
             log::warn!("Layer cannot be rendered to the map view.");
             return;
         };
 
+        canvas.layer_opacity = opacity;
         layer.render(view, &mut canvas);
     }
 
@@ -687,6 +970,11 @@ struct WgpuCanvas<'a> {
     renderer: &'a WgpuRenderer,
     render_set: &'a RenderSet,
     view: &'a TextureView,
+    quality: RenderQuality,
+    map_to_scene: Matrix4<f64>,
+    view_uniform: ViewUniform,
+    /// Opacity of the layer set in the [`LayerCollection`](crate::LayerCollection), applied to all the bundles.
+    layer_opacity: f32,
 }
 
 impl<'a> WgpuCanvas<'a> {
@@ -695,6 +983,7 @@ impl<'a> WgpuCanvas<'a> {
         render_set: &'a RenderSet,
         view: &'a TextureView,
         map_view: MapView,
+        quality: RenderQuality,
     ) -> Option<Self> {
         let rotation_mtx = Rotation3::new(Vector3::new(
             map_view.rotation_x(),
@@ -702,27 +991,186 @@ impl<'a> WgpuCanvas<'a> {
             -map_view.rotation_z(),
         ))
         .to_homogeneous();
-        renderer.queue.write_buffer(
-            render_set.pipelines.map_view_buffer(),
-            0,
-            bytemuck::cast_slice(&[ViewUniform {
-                view_proj: map_view.map_to_scene_mtx()?,
-                view_rotation: rotation_mtx.cast::<f32>().data.0,
-                inv_screen_size: [
-                    1.0 / renderer.size().width() as f32,
-                    1.0 / renderer.size().height() as f32,
-                ],
-                resolution: map_view.resolution() as f32,
-                _padding: [0.0; 1],
-            }]),
-        );
+        let map_to_scene = map_view.map_to_scene_transform()?;
+
+        // Pixel sizes of symbols are set in logical pixels, so they are scaled to physical pixels of the screen
+        // together with the screen size.
+        let scale_factor = map_view.scale_factor() as f32;
+        let view_uniform = ViewUniform {
+            view_proj: map_to_scene.cast::<f32>().data.0,
+            view_rotation: rotation_mtx.cast::<f32>().data.0,
+            inv_screen_size: [
+                scale_factor / renderer.size().width() as f32,
+                scale_factor / renderer.size().height() as f32,
+            ],
+            resolution: map_view.logical_resolution() as f32,
+            opacity: 1.0,
+        };
+        alloc_audit::without_counting(|| {
+            renderer.queue.write_buffer(
+                render_set.pipelines.map_view_buffer(),
+                0,
+                bytemuck::cast_slice(&[view_uniform]),
+            )
+        });
 
         Some(Self {
             renderer,
             render_set,
             view,
+            quality,
+            map_to_scene,
+            view_uniform,
+            layer_opacity: 1.0,
         })
     }
+
+    /// Updates the view uniform for the bundles with the given opacity and origin. Every draw pass is submitted
+    /// separately, so the values written here apply only to the bundles drawn by the next pass.
+    ///
+    /// The offset of the origin is added to the view transformation in double precision, so that bundle vertices,
+    /// that are stored relative to the origin, don't lose precision at high zoom levels.
+    fn update_view_uniform(&mut self, opacity: f32, origin: Point2d) {
+        let translation = Translation3::new(origin.x(), origin.y(), 0.0).to_homogeneous();
+        let view_uniform = ViewUniform {
+            view_proj: (self.map_to_scene * translation).cast::<f32>().data.0,
+            opacity,
+            ..self.view_uniform
+        };
+
+        if view_uniform == self.view_uniform {
+            return;
+        }
+
+        self.view_uniform = view_uniform;
+        alloc_audit::without_counting(|| {
+            self.renderer.queue.write_buffer(
+                self.render_set.pipelines.map_view_buffer(),
+                0,
+                bytemuck::cast_slice(&[self.view_uniform]),
+            )
+        });
+    }
+
+    fn draw_pass(
+        &self,
+        bundles: &[&dyn PackedBundle],
+        options: RenderOptions,
+        custom: Option<&CustomPipelines>,
+    ) {
+        let mut encoder =
+            self.renderer
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Render Encoder"),
+                });
+
+        {
+            let mut render_pass = self.begin_render_pass(&mut encoder, options);
+            for bundle in bundles
+                .iter()
+                .filter_map(|bundle| WgpuPackedBundle::downcast(*bundle))
+            {
+                self.render_set
+                    .pipelines
+                    .render(&mut render_pass, bundle, options, custom);
+            }
+        }
+
+        self.renderer
+            .queue
+            .submit(std::iter::once(encoder.finish()));
+    }
+
+    /// Moves the particles by one frame and draws their trails over the map.
+    fn draw_particles_pass(
+        &self,
+        pipeline: &ParticlePipeline,
+        particles: &mut WgpuParticles,
+        options: RenderOptions,
+    ) {
+        let mut encoder =
+            self.renderer
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Particles Encoder"),
+                });
+
+        pipeline.advect(&mut encoder, particles);
+        {
+            let mut render_pass = self.begin_render_pass(&mut encoder, options);
+            self.render_set
+                .pipelines
+                .render_particles(&mut render_pass, particles, options);
+        }
+
+        self.renderer
+            .queue
+            .submit(std::iter::once(encoder.finish()));
+    }
+
+    /// Begins a pass that draws over the render target, using the multisampled target if antialiasing is enabled.
+    fn begin_render_pass<'e>(
+        &'e self,
+        encoder: &'e mut wgpu::CommandEncoder,
+        options: RenderOptions,
+    ) -> wgpu::RenderPass<'e> {
+        let (view, resolve_target, depth_view) = if options.antialias {
+            (
+                &self.render_set.multisampling_view,
+                Some(self.view),
+                &self.render_set.stencil_view_multisample,
+            )
+        } else {
+            (self.view, None, &self.render_set.stencil_view)
+        };
+
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Render Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+                view: depth_view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: StoreOp::Store,
+                }),
+                stencil_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(0),
+                    store: StoreOp::Discard,
+                }),
+            }),
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        })
+    }
+
+    fn draw(
+        &mut self,
+        bundles: &[&dyn PackedBundle],
+        options: RenderOptions,
+        custom: Option<&CustomPipelines>,
+    ) {
+        let opacity = (options.opacity * self.layer_opacity).clamp(0.0, 1.0);
+        let origin =
+            |bundle: &&dyn PackedBundle| WgpuPackedBundle::downcast(*bundle).map(|b| b.origin);
+
+        // Bundles with different origins need different view transformations, so they are drawn in separate passes.
+        for group in bundles.chunk_by(|a, b| origin(a) == origin(b)) {
+            let Some(group_origin) = origin(&group[0]) else {
+                continue;
+            };
+
+            self.update_view_uniform(opacity, group_origin);
+            alloc_audit::without_counting(|| self.draw_pass(group, options, custom));
+        }
+    }
 }
 
 impl Canvas for WgpuCanvas<'_> {
@@ -734,6 +1182,10 @@ impl Canvas for WgpuCanvas<'_> {
         self.renderer.create_bundle()
     }
 
+    fn quality(&self) -> RenderQuality {
+        self.quality
+    }
+
     fn pack_bundle(&self, bundle: &RenderBundle) -> Box<dyn PackedBundle> {
         match bundle {
             RenderBundle(RenderBundleType::Tessellating(inner)) => {
@@ -742,62 +1194,83 @@ impl Canvas for WgpuCanvas<'_> {
         }
     }
 
+    fn repack_bundle(&self, packed: &mut Box<dyn PackedBundle>, bundle: &RenderBundle) {
+        match bundle {
+            RenderBundle(RenderBundleType::Tessellating(inner)) => {
+                match packed.as_any_mut().downcast_mut::<WgpuPackedBundle>() {
+                    Some(wgpu_bundle) => wgpu_bundle.update(inner, self.renderer, self.render_set),
+                    None => *packed = self.pack_bundle(bundle),
+                }
+            }
+        }
+    }
+
     fn draw_bundles(&mut self, bundles: &[&dyn PackedBundle], options: RenderOptions) {
-        let mut encoder =
-            self.renderer
-                .device
-                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                    label: Some("Render Encoder"),
-                });
+        self.draw(bundles, options, None);
+    }
 
-        {
-            let (view, resolve_target, depth_view) = if options.antialias {
-                (
-                    &self.render_set.multisampling_view,
-                    Some(self.view),
-                    &self.render_set.stencil_view_multisample,
-                )
-            } else {
-                (self.view, None, &self.render_set.stencil_view)
-            };
+    fn draw_bundles_with_shader(
+        &mut self,
+        bundles: &[&dyn PackedBundle],
+        options: RenderOptions,
+        shader: &CustomShader,
+    ) {
+        let custom = self
+            .render_set
+            .pipelines
+            .custom_pipelines(&self.renderer.device, shader);
+        self.draw(bundles, options, custom.as_deref());
+    }
 
-            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Render Pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view,
-                    resolve_target,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Load,
-                        store: StoreOp::Store,
-                    },
-                })],
-                depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
-                    view: depth_view,
-                    depth_ops: Some(wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(1.0),
-                        store: StoreOp::Discard,
-                    }),
-                    stencil_ops: Some(wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(0),
-                        store: StoreOp::Discard,
-                    }),
-                }),
-                timestamp_writes: None,
-                occlusion_query_set: None,
-            });
+    fn create_particles(
+        &self,
+        count: u32,
+        trail_length: u32,
+        field: &ParticleField,
+    ) -> Option<Box<dyn PackedParticles>> {
+        let device = &self.renderer.device;
+        let pipeline = self.render_set.pipelines.particle_pipeline(device)?;
+        Some(Box::new(pipeline.create_particles(
+            device,
+            &self.renderer.queue,
+            count,
+            trail_length,
+            field,
+        )))
+    }
 
-            for bundle in bundles {
-                if let Some(cast) = bundle.as_any().downcast_ref::<WgpuPackedBundle>() {
-                    self.render_set
-                        .pipelines
-                        .render(&mut render_pass, cast, options);
-                }
-            }
-        }
+    fn update_particle_field(&self, particles: &mut dyn PackedParticles, field: &ParticleField) {
+        let device = &self.renderer.device;
+        let (Some(pipeline), Some(particles)) = (
+            self.render_set.pipelines.particle_pipeline(device),
+            particles.as_any_mut().downcast_mut::<WgpuParticles>(),
+        ) else {
+            return;
+        };
 
-        self.renderer
-            .queue
-            .submit(std::iter::once(encoder.finish()));
+        pipeline.update_field(device, &self.renderer.queue, particles, field);
+    }
+
+    fn draw_particles(
+        &mut self,
+        particles: &mut dyn PackedParticles,
+        frame: &ParticleFrame,
+        options: RenderOptions,
+    ) {
+        let render_set = self.render_set;
+        let (Some(pipeline), Some(particles)) = (
+            render_set
+                .pipelines
+                .particle_pipeline(&self.renderer.device),
+            particles.as_any_mut().downcast_mut::<WgpuParticles>(),
+        ) else {
+            return;
+        };
+
+        particles.set_frame(&self.renderer.queue, frame);
+        let opacity = (options.opacity * self.layer_opacity).clamp(0.0, 1.0);
+        self.update_view_uniform(opacity, particles.origin);
+        alloc_audit::without_counting(|| self.draw_particles_pass(pipeline, particles, options));
     }
 }
 
@@ -806,7 +1279,11 @@ struct WgpuPackedBundle {
     map_ref_buffers: WgpuPolygonBuffers,
     screen_ref_buffers: Option<ScreenRefBuffers>,
     dot_buffers: Option<WgpuDotBuffers>,
+    instanced_buffers: Vec<InstancedShapeBuffers>,
+    instanced_image_buffers: Vec<InstancedImageBuffers>,
     image_buffers: Vec<WgpuImage>,
+    texture_size: usize,
+    origin: Point2d,
 }
 
 struct WgpuPolygonBuffers {
@@ -826,7 +1303,26 @@ struct WgpuDotBuffers {
     point_count: u32,
 }
 
+struct InstancedShapeBuffers {
+    vertex: Buffer,
+    index: Buffer,
+    index_count: u32,
+    instances: Buffer,
+    instance_count: u32,
+}
+
+struct InstancedImageBuffers {
+    texture_bind_group: Arc<BindGroup>,
+    vertex: Buffer,
+    instances: Buffer,
+    instance_count: u32,
+}
+
 impl WgpuPackedBundle {
+    fn downcast(bundle: &dyn PackedBundle) -> Option<&Self> {
+        bundle.as_any().downcast_ref()
+    }
+
     fn new(
         bundle: &TessellatingRenderBundle,
         renderer: &WgpuRenderer,
@@ -836,6 +1332,7 @@ impl WgpuPackedBundle {
             poly_tessellation,
             points,
             screen_ref,
+            instanced,
             images,
             clip_area,
             image_store,
@@ -892,6 +1389,29 @@ impl WgpuPackedBundle {
             })
         };
 
+        let instanced_buffers = instanced
+            .iter()
+            .filter_map(|info| match info {
+                InstancedShapeInfo::Shape {
+                    template,
+                    instances,
+                } if !instances.is_empty() && !template.indices.is_empty() => {
+                    Some(Self::write_instanced_buffers(template, instances, renderer))
+                }
+                _ => None,
+            })
+            .collect();
+
+        let texture_size = image_store
+            .iter()
+            .map(|stored| match stored {
+                ImageStoreInfo::Vacant => 0,
+                ImageStoreInfo::Image(image) => {
+                    image.width() as usize * image.height() as usize * 4
+                }
+            })
+            .sum();
+
         let textures: Vec<_> = image_store
             .iter()
             .map(|stored| match stored {
@@ -906,6 +1426,30 @@ impl WgpuPackedBundle {
             })
             .collect();
 
+        let instanced_image_buffers = instanced
+            .iter()
+            .filter_map(|info| match info {
+                InstancedShapeInfo::Image {
+                    image_index,
+                    vertices,
+                    instances,
+                } if !instances.is_empty() => {
+                    let texture_bind_group = textures
+                        .get(*image_index)
+                        .expect("texture at index must exist")
+                        .clone()
+                        .expect("image texture must not be None");
+                    Some(Self::write_instanced_image_buffers(
+                        texture_bind_group,
+                        vertices,
+                        instances,
+                        renderer,
+                    ))
+                }
+                _ => None,
+            })
+            .collect();
+
         let mut image_buffers = vec![];
         for image_info in images {
             if let ImageInfo::Image((image_index, vertices)) = image_info {
@@ -931,6 +1475,178 @@ impl WgpuPackedBundle {
             image_buffers,
             screen_ref_buffers,
             dot_buffers,
+            instanced_buffers,
+            instanced_image_buffers,
+            texture_size,
+            origin: bundle.origin,
+        }
+    }
+
+    /// Writes the bundle into the buffers of this packed bundle. Buffers that are large enough are reused, others are
+    /// replaced with larger ones. Bundles with images, instanced shapes or clip areas are packed anew.
+    fn update(
+        &mut self,
+        bundle: &TessellatingRenderBundle,
+        renderer: &WgpuRenderer,
+        render_set: &RenderSet,
+    ) {
+        let TessellatingRenderBundle {
+            poly_tessellation,
+            points,
+            screen_ref,
+            instanced,
+            images,
+            clip_area,
+            ..
+        } = bundle;
+
+        if !images.is_empty()
+            || !instanced.is_empty()
+            || clip_area.is_some()
+            || !self.image_buffers.is_empty()
+            || !self.instanced_buffers.is_empty()
+            || !self.instanced_image_buffers.is_empty()
+            || self.clip_area_buffers.is_some()
+        {
+            *self = Self::new(bundle, renderer, render_set);
+            return;
+        }
+
+        self.origin = bundle.origin;
+
+        let map_ref = &mut self.map_ref_buffers;
+        write_reused_buffer(
+            &mut map_ref.vertex,
+            bytemuck::cast_slice(&poly_tessellation.vertices),
+            BufferUsages::VERTEX,
+            renderer,
+        );
+        write_reused_buffer(
+            &mut map_ref.index,
+            bytemuck::cast_slice(&poly_tessellation.indices),
+            BufferUsages::INDEX,
+            renderer,
+        );
+        map_ref.index_count = poly_tessellation.indices.len() as u32;
+
+        if screen_ref.vertices.is_empty() {
+            self.screen_ref_buffers = None;
+        } else {
+            let vertices = bytemuck::cast_slice(&screen_ref.vertices);
+            let indices = bytemuck::cast_slice(&screen_ref.indices);
+            let index_count = screen_ref.indices.len() as u32;
+            match &mut self.screen_ref_buffers {
+                Some(buffers) => {
+                    write_reused_buffer(
+                        &mut buffers.vertex,
+                        vertices,
+                        BufferUsages::VERTEX,
+                        renderer,
+                    );
+                    write_reused_buffer(&mut buffers.index, indices, BufferUsages::INDEX, renderer);
+                    buffers.index_count = index_count;
+                }
+                None => {
+                    self.screen_ref_buffers = Some(ScreenRefBuffers {
+                        vertex: create_reusable_buffer(vertices, BufferUsages::VERTEX, renderer),
+                        index: create_reusable_buffer(indices, BufferUsages::INDEX, renderer),
+                        index_count,
+                    })
+                }
+            }
+        }
+
+        if points.is_empty() {
+            self.dot_buffers = None;
+        } else {
+            let contents = bytemuck::cast_slice(points);
+            match &mut self.dot_buffers {
+                Some(buffers) => {
+                    write_reused_buffer(
+                        &mut buffers.buffer,
+                        contents,
+                        BufferUsages::VERTEX,
+                        renderer,
+                    );
+                    buffers.point_count = points.len() as u32;
+                }
+                None => {
+                    self.dot_buffers = Some(WgpuDotBuffers {
+                        buffer: create_reusable_buffer(contents, BufferUsages::VERTEX, renderer),
+                        point_count: points.len() as u32,
+                    })
+                }
+            }
+        }
+    }
+
+    fn write_instanced_buffers(
+        template: &ScreenRefTessellation,
+        instances: &[ShapeInstance],
+        renderer: &WgpuRenderer,
+    ) -> InstancedShapeBuffers {
+        let index = renderer
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: None,
+                contents: bytemuck::cast_slice(&template.indices),
+                usage: wgpu::BufferUsages::INDEX,
+            });
+
+        let vertex = renderer
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: None,
+                contents: bytemuck::cast_slice(&template.vertices),
+                usage: wgpu::BufferUsages::VERTEX,
+            });
+
+        let instance_buffer =
+            renderer
+                .device
+                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: None,
+                    contents: bytemuck::cast_slice(instances),
+                    usage: wgpu::BufferUsages::VERTEX,
+                });
+
+        InstancedShapeBuffers {
+            vertex,
+            index,
+            index_count: template.indices.len() as u32,
+            instances: instance_buffer,
+            instance_count: instances.len() as u32,
+        }
+    }
+
+    fn write_instanced_image_buffers(
+        texture_bind_group: Arc<BindGroup>,
+        vertices: &[ImageVertex; 4],
+        instances: &[ShapeInstance],
+        renderer: &WgpuRenderer,
+    ) -> InstancedImageBuffers {
+        let vertex = renderer
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: None,
+                contents: bytemuck::cast_slice(vertices),
+                usage: wgpu::BufferUsages::VERTEX,
+            });
+
+        let instance_buffer =
+            renderer
+                .device
+                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: None,
+                    contents: bytemuck::cast_slice(instances),
+                    usage: wgpu::BufferUsages::VERTEX,
+                });
+
+        InstancedImageBuffers {
+            texture_bind_group,
+            vertex,
+            instances: instance_buffer,
+            instance_count: instances.len() as u32,
         }
     }
 
@@ -965,20 +1681,95 @@ impl WgpuPackedBundle {
     }
 }
 
+/// Writes the contents into the buffer if it is large enough and can be written to, or replaces the buffer with a new
+/// one otherwise.
+fn write_reused_buffer(
+    buffer: &mut Buffer,
+    contents: &[u8],
+    usage: BufferUsages,
+    renderer: &WgpuRenderer,
+) {
+    if buffer.size() >= contents.len() as BufferAddress
+        && buffer.usage().contains(BufferUsages::COPY_DST)
+    {
+        if !contents.is_empty() {
+            renderer.queue.write_buffer(buffer, 0, contents);
+        }
+    } else {
+        *buffer = create_reusable_buffer(contents, usage, renderer);
+    }
+}
+
+/// Creates a buffer that can be rewritten later. The buffer is created with some spare capacity to be reused when the
+/// contents grow.
+fn create_reusable_buffer(contents: &[u8], usage: BufferUsages, renderer: &WgpuRenderer) -> Buffer {
+    let size = (contents.len() as BufferAddress)
+        .next_power_of_two()
+        .max(wgpu::COPY_BUFFER_ALIGNMENT);
+    let buffer = renderer.device.create_buffer(&BufferDescriptor {
+        label: None,
+        size,
+        usage: usage | BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    if !contents.is_empty() {
+        renderer.queue.write_buffer(&buffer, 0, contents);
+    }
+
+    buffer
+}
+
 impl PackedBundle for WgpuPackedBundle {
     fn as_any(&self) -> &dyn Any {
         self
     }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn gpu_size(&self) -> usize {
+        let polygon_buffers =
+            |buffers: &WgpuPolygonBuffers| buffers.vertex.size() + buffers.index.size();
+
+        let mut size = polygon_buffers(&self.map_ref_buffers);
+        size += self.clip_area_buffers.as_ref().map_or(0, polygon_buffers);
+        size += self
+            .screen_ref_buffers
+            .as_ref()
+            .map_or(0, |buffers| buffers.vertex.size() + buffers.index.size());
+        size += self
+            .dot_buffers
+            .as_ref()
+            .map_or(0, |buffers| buffers.buffer.size());
+        size += self
+            .instanced_buffers
+            .iter()
+            .map(|buffers| buffers.vertex.size() + buffers.index.size() + buffers.instances.size())
+            .sum::<u64>();
+        size += self
+            .instanced_image_buffers
+            .iter()
+            .map(|buffers| buffers.vertex.size() + buffers.instances.size())
+            .sum::<u64>();
+        size += self
+            .image_buffers
+            .iter()
+            .map(|image| image.vertex_buffer.size())
+            .sum::<u64>();
+
+        size as usize + self.texture_size
+    }
 }
 
 #[repr(C)]
-#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+#[derive(Debug, Copy, Clone, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
 struct ViewUniform {
     view_proj: [[f32; 4]; 4],
     view_rotation: [[f32; 4]; 4],
     inv_screen_size: [f32; 2],
     resolution: f32,
-    _padding: [f32; 1],
+    opacity: f32,
 }
 
 impl PointInstance {
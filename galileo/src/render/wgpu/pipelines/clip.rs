@@ -1,5 +1,7 @@
 use crate::render::render_bundle::tessellating::PolyVertex;
-use crate::render::wgpu::pipelines::{default_pipeline_descriptor, default_targets};
+use crate::render::wgpu::pipelines::{
+    default_pipeline_descriptor, default_targets, output_constants,
+};
 use crate::render::wgpu::{WgpuPolygonBuffers, DEPTH_FORMAT};
 use crate::render::RenderOptions;
 use wgpu::{
@@ -31,6 +33,7 @@ impl ClipPipeline {
             pass_op: StencilOperation::Keep,
         };
         let targets = default_targets(format);
+        let constants = output_constants(format);
         let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: None,
             bind_group_layouts: &[map_view_layout],
@@ -52,11 +55,11 @@ impl ClipPipeline {
 
         let wgpu_pipeline_antialias = device.create_render_pipeline(&RenderPipelineDescriptor {
             depth_stencil: depth_stencil.clone(),
-            ..default_pipeline_descriptor(&layout, &shader, &targets, &buffers, true)
+            ..default_pipeline_descriptor(&layout, &shader, &targets, &buffers, &constants, true)
         });
         let wgpu_pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
             depth_stencil,
-            ..default_pipeline_descriptor(&layout, &shader, &targets, &buffers, false)
+            ..default_pipeline_descriptor(&layout, &shader, &targets, &buffers, &constants, false)
         });
 
         Self {
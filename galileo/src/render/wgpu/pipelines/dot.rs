@@ -1,5 +1,7 @@
 use crate::render::render_bundle::tessellating::PointInstance;
-use crate::render::wgpu::pipelines::{default_pipeline_descriptor, default_targets};
+use crate::render::wgpu::pipelines::{
+    default_pipeline_descriptor, default_targets, output_constants,
+};
 use crate::render::wgpu::{WgpuDotBuffers, DEPTH_FORMAT};
 use crate::render::RenderOptions;
 use wgpu::{
@@ -26,6 +28,8 @@ impl DotPipeline {
         let shader = device.create_shader_module(wgpu::include_wgsl!("./shaders/dot.wgsl"));
 
         let targets = default_targets(format);
+
+        let constants = output_constants(format);
         let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: None,
             bind_group_layouts: &[map_view_layout],
@@ -62,12 +66,12 @@ impl DotPipeline {
         let wgpu_pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
             primitive,
             depth_stencil: depth_stencil.clone(),
-            ..default_pipeline_descriptor(&layout, &shader, &targets, &buffers, false)
+            ..default_pipeline_descriptor(&layout, &shader, &targets, &buffers, &constants, false)
         });
         let wgpu_pipeline_antialias = device.create_render_pipeline(&RenderPipelineDescriptor {
             primitive,
             depth_stencil,
-            ..default_pipeline_descriptor(&layout, &shader, &targets, &buffers, true)
+            ..default_pipeline_descriptor(&layout, &shader, &targets, &buffers, &constants, true)
         });
         Self {
             wgpu_pipeline,
@@ -1,13 +1,14 @@
 use crate::decoded_image::DecodedImage;
-use crate::render::render_bundle::tessellating::ImageVertex;
+use crate::render::render_bundle::tessellating::{ImageVertex, ShapeInstance};
 use crate::render::wgpu::pipelines;
 use crate::render::wgpu::pipelines::default_targets;
+use crate::render::wgpu::InstancedImageBuffers;
 use crate::render::RenderOptions;
 use std::sync::Arc;
 use wgpu::util::{DeviceExt, TextureDataOrder};
 use wgpu::{
     BindGroup, BindGroupLayout, Device, Queue, RenderPass, RenderPipeline,
-    RenderPipelineDescriptor, TextureFormat,
+    RenderPipelineDescriptor, ShaderModule, TextureFormat, VertexBufferLayout,
 };
 
 const INDICES: &[u16] = &[1, 0, 2, 1, 2, 3];
@@ -18,9 +19,15 @@ pub struct WgpuImage {
 }
 
 pub struct ImagePipeline {
-    wgpu_pipeline: RenderPipeline,
+    pipelines: ImageShaderPipelines,
+    instanced_pipelines: ImageShaderPipelines,
     index_buffer: wgpu::Buffer,
     texture_bind_group_layout: BindGroupLayout,
+}
+
+/// Render pipelines of images compiled with a specific shader.
+pub struct ImageShaderPipelines {
+    wgpu_pipeline: RenderPipeline,
     pub wgpu_pipeline_antialias: RenderPipeline,
 }
 
@@ -54,35 +61,110 @@ impl ImagePipeline {
                 ],
                 label: Some("texture_bind_group_label"),
             });
-        let buffers = [ImageVertex::wgpu_desc()];
 
+        let pipelines = Self::create_pipelines(
+            device,
+            format,
+            map_view_layout,
+            &texture_bind_group_layout,
+            &shader,
+            &[ImageVertex::wgpu_desc()],
+        );
+
+        let instanced_shader =
+            device.create_shader_module(wgpu::include_wgsl!("./shaders/image_instanced.wgsl"));
+        let instanced_pipelines = Self::create_pipelines(
+            device,
+            format,
+            map_view_layout,
+            &texture_bind_group_layout,
+            &instanced_shader,
+            &[ImageVertex::wgpu_desc(), ShapeInstance::wgpu_desc()],
+        );
+
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Image index buffer"),
+            contents: bytemuck::cast_slice(INDICES),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        Self {
+            pipelines,
+            instanced_pipelines,
+            texture_bind_group_layout,
+            index_buffer,
+        }
+    }
+
+    /// Creates pipelines that draw images with the given shader module. The module must have the same vertex stage
+    /// and bindings as the default image shader.
+    pub fn create_shader_pipelines(
+        &self,
+        device: &Device,
+        format: TextureFormat,
+        map_view_layout: &BindGroupLayout,
+        shader: &ShaderModule,
+    ) -> ImageShaderPipelines {
+        Self::create_pipelines(
+            device,
+            format,
+            map_view_layout,
+            &self.texture_bind_group_layout,
+            shader,
+            &[ImageVertex::wgpu_desc()],
+        )
+    }
+
+    /// Same as [`Self::create_shader_pipelines`] for the instanced images. The module must have the same vertex stage
+    /// as the default instanced image shader.
+    pub fn create_instanced_shader_pipelines(
+        &self,
+        device: &Device,
+        format: TextureFormat,
+        map_view_layout: &BindGroupLayout,
+        shader: &ShaderModule,
+    ) -> ImageShaderPipelines {
+        Self::create_pipelines(
+            device,
+            format,
+            map_view_layout,
+            &self.texture_bind_group_layout,
+            shader,
+            &[ImageVertex::wgpu_desc(), ShapeInstance::wgpu_desc()],
+        )
+    }
+
+    fn create_pipelines(
+        device: &Device,
+        format: TextureFormat,
+        map_view_layout: &BindGroupLayout,
+        texture_bind_group_layout: &BindGroupLayout,
+        shader: &ShaderModule,
+        buffers: &[VertexBufferLayout],
+    ) -> ImageShaderPipelines {
         let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: None,
-            bind_group_layouts: &[map_view_layout, &texture_bind_group_layout],
+            bind_group_layouts: &[map_view_layout, texture_bind_group_layout],
             push_constant_ranges: &[],
         });
 
         let targets = default_targets(format);
 
+        let constants = pipelines::output_constants(format);
+
         let mut desc = RenderPipelineDescriptor {
-            ..pipelines::default_pipeline_descriptor(&layout, &shader, &targets, &buffers, false)
+            ..pipelines::default_pipeline_descriptor(
+                &layout, shader, &targets, buffers, &constants, false,
+            )
         };
 
         let wgpu_pipeline = device.create_render_pipeline(&desc);
         desc.multisample.count = 4;
         let wgpu_pipeline_antialias = device.create_render_pipeline(&desc);
 
-        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Image index buffer"),
-            contents: bytemuck::cast_slice(INDICES),
-            usage: wgpu::BufferUsages::INDEX,
-        });
-
-        Self {
+        ImageShaderPipelines {
             wgpu_pipeline,
             wgpu_pipeline_antialias,
-            texture_bind_group_layout,
-            index_buffer,
         }
     }
 
@@ -162,16 +244,19 @@ impl ImagePipeline {
         }
     }
 
+    /// Draws the image with the default shader, or with the given `shader_pipelines` if set.
     pub fn render<'a>(
         &'a self,
         buffers: &'a WgpuImage,
         render_pass: &mut RenderPass<'a>,
         render_options: RenderOptions,
+        shader_pipelines: Option<&'a ImageShaderPipelines>,
     ) {
+        let pipelines = shader_pipelines.unwrap_or(&self.pipelines);
         if render_options.antialias {
-            render_pass.set_pipeline(&self.wgpu_pipeline_antialias);
+            render_pass.set_pipeline(&pipelines.wgpu_pipeline_antialias);
         } else {
-            render_pass.set_pipeline(&self.wgpu_pipeline);
+            render_pass.set_pipeline(&pipelines.wgpu_pipeline);
         }
 
         let bind_group: &BindGroup = &buffers.texture_bind_group;
@@ -180,6 +265,29 @@ impl ImagePipeline {
         render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
         render_pass.draw_indexed(0..INDICES.len() as u32, 0, 0..1);
     }
+
+    /// Draws all the instances of the image with the default shader, or with the given `shader_pipelines` if set.
+    pub fn render_instanced<'a>(
+        &'a self,
+        buffers: &'a InstancedImageBuffers,
+        render_pass: &mut RenderPass<'a>,
+        render_options: RenderOptions,
+        shader_pipelines: Option<&'a ImageShaderPipelines>,
+    ) {
+        let pipelines = shader_pipelines.unwrap_or(&self.instanced_pipelines);
+        if render_options.antialias {
+            render_pass.set_pipeline(&pipelines.wgpu_pipeline_antialias);
+        } else {
+            render_pass.set_pipeline(&pipelines.wgpu_pipeline);
+        }
+
+        let bind_group: &BindGroup = &buffers.texture_bind_group;
+        render_pass.set_bind_group(1, bind_group, &[]);
+        render_pass.set_vertex_buffer(0, buffers.vertex.slice(..));
+        render_pass.set_vertex_buffer(1, buffers.instances.slice(..));
+        render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+        render_pass.draw_indexed(0..INDICES.len() as u32, 0, 0..buffers.instance_count);
+    }
 }
 
 impl ImageVertex {
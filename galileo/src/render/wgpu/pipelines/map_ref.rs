@@ -2,11 +2,16 @@ use crate::render::render_bundle::tessellating::PolyVertex;
 use crate::render::wgpu::pipelines::default_targets;
 use crate::render::wgpu::{pipelines, WgpuPolygonBuffers};
 use crate::render::RenderOptions;
-use wgpu::{BindGroupLayout, Device, RenderPass, RenderPipeline, TextureFormat};
+use wgpu::{
+    BindGroupLayout, CompareFunction, Device, RenderPass, RenderPipeline, ShaderModule,
+    TextureFormat,
+};
 
 pub struct MapRefPipeline {
     wgpu_pipeline: RenderPipeline,
     pub wgpu_pipeline_antialias: RenderPipeline,
+    wgpu_pipeline_depth: RenderPipeline,
+    wgpu_pipeline_depth_antialias: RenderPipeline,
 }
 
 impl MapRefPipeline {
@@ -15,25 +20,54 @@ impl MapRefPipeline {
         format: TextureFormat,
         map_view_layout: &BindGroupLayout,
     ) -> Self {
-        let buffers = [PolyVertex::wgpu_desc()];
         let shader = device.create_shader_module(wgpu::include_wgsl!("./shaders/map_ref.wgsl"));
+        Self::create_with_shader(device, format, map_view_layout, &shader)
+    }
+
+    /// Creates the pipeline with the given shader module. The module must have the same vertex stage and bindings as
+    /// the default shader.
+    pub fn create_with_shader(
+        device: &Device,
+        format: TextureFormat,
+        map_view_layout: &BindGroupLayout,
+        shader: &ShaderModule,
+    ) -> Self {
+        let buffers = [PolyVertex::wgpu_desc()];
 
         let targets = default_targets(format);
+
+        let constants = pipelines::output_constants(format);
         let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: None,
             bind_group_layouts: &[map_view_layout],
             push_constant_ranges: &[],
         });
-        let mut desc =
-            pipelines::default_pipeline_descriptor(&layout, &shader, &targets, &buffers, false);
+        let mut desc = pipelines::default_pipeline_descriptor(
+            &layout, shader, &targets, &buffers, &constants, false,
+        );
+        if let Some(depth_stencil) = &mut desc.depth_stencil {
+            depth_stencil.depth_compare = CompareFunction::LessEqual;
+        }
+
         let wgpu_pipeline = device.create_render_pipeline(&desc);
 
         desc.multisample.count = 4;
         let wgpu_pipeline_antialias = device.create_render_pipeline(&desc);
 
+        if let Some(depth_stencil) = &mut desc.depth_stencil {
+            depth_stencil.depth_write_enabled = true;
+        }
+
+        let wgpu_pipeline_depth_antialias = device.create_render_pipeline(&desc);
+
+        desc.multisample.count = 1;
+        let wgpu_pipeline_depth = device.create_render_pipeline(&desc);
+
         Self {
             wgpu_pipeline,
             wgpu_pipeline_antialias,
+            wgpu_pipeline_depth,
+            wgpu_pipeline_depth_antialias,
         }
     }
 
@@ -43,11 +77,13 @@ impl MapRefPipeline {
         render_pass: &mut RenderPass<'a>,
         render_options: RenderOptions,
     ) {
-        if render_options.antialias {
-            render_pass.set_pipeline(&self.wgpu_pipeline_antialias);
-        } else {
-            render_pass.set_pipeline(&self.wgpu_pipeline);
-        }
+        let pipeline = match (render_options.antialias, render_options.write_depth) {
+            (true, true) => &self.wgpu_pipeline_depth_antialias,
+            (true, false) => &self.wgpu_pipeline_antialias,
+            (false, true) => &self.wgpu_pipeline_depth,
+            (false, false) => &self.wgpu_pipeline,
+        };
+        render_pass.set_pipeline(pipeline);
         render_pass.set_vertex_buffer(0, buffers.vertex.slice(..));
         render_pass.set_index_buffer(buffers.index.slice(..), wgpu::IndexFormat::Uint32);
         render_pass.draw_indexed(0..buffers.index_count, 0, 0..1);
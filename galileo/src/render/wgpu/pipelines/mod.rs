@@ -1,29 +1,46 @@
 use crate::render::wgpu::pipelines::clip::ClipPipeline;
 use crate::render::wgpu::pipelines::dot::DotPipeline;
-use crate::render::wgpu::pipelines::image::ImagePipeline;
+use crate::render::wgpu::pipelines::image::{ImagePipeline, ImageShaderPipelines};
+use crate::render::wgpu::pipelines::instanced::InstancedPipeline;
 use crate::render::wgpu::pipelines::map_ref::MapRefPipeline;
+use crate::render::wgpu::pipelines::particles::{ParticlePipeline, WgpuParticles};
 use crate::render::wgpu::pipelines::screen_ref::ScreenRefPipeline;
 use crate::render::wgpu::{ViewUniform, WgpuPackedBundle, DEPTH_FORMAT};
-use crate::render::RenderOptions;
+use crate::render::{CustomShader, RenderOptions};
+use std::collections::HashMap;
 use std::mem::size_of;
+use std::sync::{Arc, Mutex, OnceLock};
 use wgpu::{
-    BindGroup, Buffer, CompareFunction, DepthStencilState, Device, PipelineLayout, RenderPass,
-    RenderPipelineDescriptor, ShaderModule, StencilFaceState, StencilOperation, StencilState,
-    TextureFormat, VertexBufferLayout,
+    BindGroup, BindGroupLayout, Buffer, CompareFunction, DepthStencilState, Device, PipelineLayout,
+    RenderPass, RenderPipelineDescriptor, ShaderModule, StencilFaceState, StencilOperation,
+    StencilState, TextureFormat, VertexBufferLayout,
 };
 
 mod clip;
 mod dot;
 pub mod image;
+mod instanced;
 mod map_ref;
+pub mod particles;
 mod screen_ref;
 
+/// Marker that separates the vertex stage of the default shaders from the fragment stage, which is replaced in the
+/// shaders compiled with custom code.
+const FRAGMENT_SHADER_MARKER: &str = "// Fragment shader";
+
 pub struct Pipelines {
     map_view_binding: BindGroup,
     map_view_buffer: Buffer,
+    map_view_bind_group_layout: BindGroupLayout,
+    format: TextureFormat,
+    /// Pipelines compiled with custom shaders by the id of the shader. `None` if the shader failed to compile.
+    custom: Mutex<HashMap<u64, Option<Arc<CustomPipelines>>>>,
+    /// Pipelines of particle animations, created when first used. `None` if the device does not support them.
+    particles: OnceLock<Option<ParticlePipeline>>,
 
     image: ImagePipeline,
     screen_ref: ScreenRefPipeline,
+    instanced: InstancedPipeline,
     map_ref: MapRefPipeline,
     clip: ClipPipeline,
     dot: DotPipeline,
@@ -65,12 +82,135 @@ impl Pipelines {
         Self {
             map_view_binding,
             map_view_buffer,
+            format,
+            custom: Mutex::default(),
+            particles: OnceLock::new(),
             image: ImagePipeline::create(device, format, &map_view_bind_group_layout),
             map_ref: MapRefPipeline::create(device, format, &map_view_bind_group_layout),
             screen_ref: ScreenRefPipeline::create(device, format, &map_view_bind_group_layout),
+            instanced: InstancedPipeline::create(device, format, &map_view_bind_group_layout),
             clip: ClipPipeline::create(device, format, &map_view_bind_group_layout),
             dot: DotPipeline::create(device, format, &map_view_bind_group_layout),
+            map_view_bind_group_layout,
+        }
+    }
+
+    /// Returns the pipelines compiled with the given custom shader, compiling them on the first call. Returns `None`
+    /// if the shader code is invalid.
+    pub fn custom_pipelines(
+        &self,
+        device: &Device,
+        shader: &CustomShader,
+    ) -> Option<Arc<CustomPipelines>> {
+        self.custom
+            .lock()
+            .expect("mutex is poisoned")
+            .entry(shader.id())
+            .or_insert_with(|| self.compile_custom(device, shader))
+            .clone()
+    }
+
+    fn compile_custom(
+        &self,
+        device: &Device,
+        shader: &CustomShader,
+    ) -> Option<Arc<CustomPipelines>> {
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+
+        let create_module = |label, base: &str, fragment: &str| {
+            device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some(label),
+                source: wgpu::ShaderSource::Wgsl(
+                    custom_shader_source(base, fragment, shader).into(),
+                ),
+            })
+        };
+        let map_ref_shader = create_module(
+            "Custom map ref shader",
+            include_str!("./shaders/map_ref.wgsl"),
+            include_str!("./shaders/custom_map_ref.wgsl"),
+        );
+        let image_shader = create_module(
+            "Custom image shader",
+            include_str!("./shaders/image.wgsl"),
+            include_str!("./shaders/custom_image.wgsl"),
+        );
+        let instanced_image_shader = create_module(
+            "Custom instanced image shader",
+            include_str!("./shaders/image_instanced.wgsl"),
+            include_str!("./shaders/custom_image.wgsl"),
+        );
+
+        let pipelines = CustomPipelines {
+            map_ref: MapRefPipeline::create_with_shader(
+                device,
+                self.format,
+                &self.map_view_bind_group_layout,
+                &map_ref_shader,
+            ),
+            image: self.image.create_shader_pipelines(
+                device,
+                self.format,
+                &self.map_view_bind_group_layout,
+                &image_shader,
+            ),
+            instanced_image: self.image.create_instanced_shader_pipelines(
+                device,
+                self.format,
+                &self.map_view_bind_group_layout,
+                &instanced_image_shader,
+            ),
+        };
+
+        let error = device.pop_error_scope();
+        // Errors cannot be waited for synchronously in browsers, there they are reported by the device instead.
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(error) = futures::executor::block_on(error) {
+            log::error!("Failed to compile custom shader: {error}");
+            return None;
         }
+        #[cfg(target_arch = "wasm32")]
+        drop(error);
+
+        Some(Arc::new(pipelines))
+    }
+
+    /// Returns the particle pipelines, creating them on the first call. Returns `None` if the device cannot render to
+    /// float textures, which the state of the particles is stored in.
+    pub fn particle_pipeline(&self, device: &Device) -> Option<&ParticlePipeline> {
+        self.particles
+            .get_or_init(|| {
+                device.push_error_scope(wgpu::ErrorFilter::Validation);
+                let pipeline =
+                    ParticlePipeline::create(device, self.format, &self.map_view_bind_group_layout);
+
+                let error = device.pop_error_scope();
+                // Errors cannot be waited for synchronously in browsers, there they are reported by the device instead.
+                #[cfg(not(target_arch = "wasm32"))]
+                if let Some(error) = futures::executor::block_on(error) {
+                    log::error!("Failed to create particle pipelines: {error}");
+                    return None;
+                }
+                #[cfg(target_arch = "wasm32")]
+                drop(error);
+
+                Some(pipeline)
+            })
+            .as_ref()
+    }
+
+    pub fn render_particles<'a>(
+        &'a self,
+        render_pass: &mut RenderPass<'a>,
+        particles: &'a WgpuParticles,
+        render_options: RenderOptions,
+    ) {
+        let Some(pipeline) = self.particles.get().and_then(Option::as_ref) else {
+            return;
+        };
+
+        self.set_bindings(render_pass);
+        pipeline.render(particles, render_pass, render_options);
     }
 
     pub fn render<'a>(
@@ -78,6 +218,7 @@ impl Pipelines {
         render_pass: &mut RenderPass<'a>,
         bundle: &'a WgpuPackedBundle,
         render_options: RenderOptions,
+        custom: Option<&'a CustomPipelines>,
     ) {
         self.set_bindings(render_pass);
 
@@ -86,11 +227,26 @@ impl Pipelines {
         }
 
         for image in &bundle.image_buffers {
-            self.image.render(image, render_pass, render_options);
+            self.image.render(
+                image,
+                render_pass,
+                render_options,
+                custom.map(|custom| &custom.image),
+            );
+        }
+
+        for instanced_buffers in &bundle.instanced_image_buffers {
+            self.image.render_instanced(
+                instanced_buffers,
+                render_pass,
+                render_options,
+                custom.map(|custom| &custom.instanced_image),
+            );
         }
 
         if bundle.map_ref_buffers.index_count > 0 {
-            self.map_ref
+            custom
+                .map_or(&self.map_ref, |custom| &custom.map_ref)
                 .render(&bundle.map_ref_buffers, render_pass, render_options);
         }
 
@@ -103,6 +259,11 @@ impl Pipelines {
                 .render(screen_ref_buffers, render_pass, render_options);
         }
 
+        for instanced_buffers in &bundle.instanced_buffers {
+            self.instanced
+                .render(instanced_buffers, render_pass, render_options);
+        }
+
         if let Some(dot_buffers) = &bundle.dot_buffers {
             self.dot.render(dot_buffers, render_pass, render_options);
         }
@@ -121,6 +282,27 @@ impl Pipelines {
     }
 }
 
+/// Pipelines of the primitives drawn with a [`CustomShader`].
+pub struct CustomPipelines {
+    map_ref: MapRefPipeline,
+    image: ImageShaderPipelines,
+    instanced_image: ImageShaderPipelines,
+}
+
+/// Replaces the fragment stage of the `base` shader with the custom `fragment` stage, that calls the code of the
+/// custom shader.
+fn custom_shader_source(base: &str, fragment: &str, shader: &CustomShader) -> String {
+    let vertex = base
+        .find(FRAGMENT_SHADER_MARKER)
+        .map_or(base, |index| &base[..index]);
+
+    format!(
+        "{vertex}\n{}\n{}\n{fragment}",
+        include_str!("./shaders/custom_prelude.wgsl"),
+        shader.source()
+    )
+}
+
 fn default_targets(format: TextureFormat) -> [Option<wgpu::ColorTargetState>; 1] {
     [Some(wgpu::ColorTargetState {
         format,
@@ -129,11 +311,28 @@ fn default_targets(format: TextureFormat) -> [Option<wgpu::ColorTargetState>; 1]
     })]
 }
 
+/// Returns `true` if the GPU converts the colors written to the target of the given format from linear space, so the
+/// shaders must output linear colors.
+pub fn is_linear_output(format: TextureFormat) -> bool {
+    format.is_srgb()
+        || matches!(
+            format,
+            TextureFormat::Rgba16Float | TextureFormat::Rgba32Float
+        )
+}
+
+/// Values of the pipeline-overridable constants of the shaders for the given target format.
+fn output_constants(format: TextureFormat) -> HashMap<String, f64> {
+    let linear_output = if is_linear_output(format) { 1.0 } else { 0.0 };
+    HashMap::from([("linear_output".to_string(), linear_output)])
+}
+
 fn default_pipeline_descriptor<'a>(
     pipeline_layout: &'a PipelineLayout,
     shader: &'a ShaderModule,
     targets: &'a [Option<wgpu::ColorTargetState>],
     buffers: &'a [VertexBufferLayout<'a>],
+    constants: &'a HashMap<String, f64>,
     antialias: bool,
 ) -> RenderPipelineDescriptor<'a> {
     let stencil_state = StencilFaceState {
@@ -150,13 +349,19 @@ fn default_pipeline_descriptor<'a>(
             module: shader,
             entry_point: "vs_main",
             buffers,
-            compilation_options: Default::default(),
+            compilation_options: wgpu::PipelineCompilationOptions {
+                constants,
+                ..Default::default()
+            },
         },
         fragment: Some(wgpu::FragmentState {
             module: shader,
             entry_point: "fs_main",
             targets,
-            compilation_options: Default::default(),
+            compilation_options: wgpu::PipelineCompilationOptions {
+                constants,
+                ..Default::default()
+            },
         }),
         primitive: wgpu::PrimitiveState {
             topology: wgpu::PrimitiveTopology::TriangleList,
@@ -0,0 +1,422 @@
+use crate::render::wgpu::pipelines::{
+    default_pipeline_descriptor, default_targets, output_constants,
+};
+use crate::render::{PackedParticles, ParticleField, ParticleFrame, RenderOptions};
+use galileo_types::cartesian::{CartesianPoint2d, Point2d, Rect};
+use std::any::Any;
+use wgpu::util::{DeviceExt, TextureDataOrder};
+use wgpu::{
+    BindGroup, BindGroupLayout, Buffer, CommandEncoder, Device, Queue, RenderPass, RenderPipeline,
+    RenderPipelineDescriptor, Texture, TextureFormat, TextureView,
+};
+
+/// Maximum number of particles stored in one row of the state texture.
+const STATE_COLUMNS: u32 = 256;
+/// Format of the particle state textures. Every texel stores four `f32` values (see the advection shader) as bits,
+/// because float formats cannot be rendered to on all platforms (e.g. WebGL), but integer formats can.
+const STATE_FORMAT: TextureFormat = TextureFormat::Rgba32Uint;
+/// Format of the velocity texture.
+const VELOCITY_FORMAT: TextureFormat = TextureFormat::Rgba32Float;
+
+/// Pipelines that move particles on the GPU and draw their trails.
+///
+/// Positions of the particles are stored in a texture, that is updated by a render pass of the advection pipeline
+/// reading the previous state from the second texture. This works on all backends, including WebGL, which doesn't
+/// support compute shaders.
+pub struct ParticlePipeline {
+    advect: RenderPipeline,
+    trails: RenderPipeline,
+    trails_antialias: RenderPipeline,
+    bind_group_layout: BindGroupLayout,
+}
+
+/// Particle system stored on the GPU.
+pub struct WgpuParticles {
+    state_textures: [Texture; 2],
+    state_views: [TextureView; 2],
+    velocity_texture: Texture,
+    uniform_buffer: Buffer,
+    /// Bind group `i` reads the state from the state texture `i`.
+    bind_groups: [BindGroup; 2],
+    /// Index of the state texture with the latest positions of the particles.
+    current: usize,
+    count: u32,
+    rows: u32,
+    frame: u32,
+    /// Positions of the particles are stored relative to this point to keep the precision of `f32` values.
+    pub origin: Point2d,
+    field_bbox: Rect,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct ParticleUniform {
+    area_min: [f32; 2],
+    area_max: [f32; 2],
+    field_min: [f32; 2],
+    field_size: [f32; 2],
+    time_step: f32,
+    max_age: f32,
+    seed: f32,
+    rows: f32,
+    max_magnitude: f32,
+    line_width: f32,
+    _padding: [f32; 2],
+    color: [f32; 4],
+    slow_color: [f32; 4],
+}
+
+impl ParticlePipeline {
+    pub fn create(
+        device: &Device,
+        format: TextureFormat,
+        map_view_layout: &BindGroupLayout,
+    ) -> Self {
+        let texture_entry = |binding, sample_type| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                multisampled: false,
+                view_dimension: wgpu::TextureViewDimension::D2,
+                sample_type,
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                texture_entry(1, wgpu::TextureSampleType::Uint),
+                texture_entry(2, wgpu::TextureSampleType::Float { filterable: false }),
+            ],
+            label: Some("Particles bind group layout"),
+        });
+
+        let advect_shader =
+            device.create_shader_module(wgpu::include_wgsl!("./shaders/particle_advect.wgsl"));
+        let advect_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: None,
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let advect_targets = [Some(wgpu::ColorTargetState {
+            format: STATE_FORMAT,
+            blend: None,
+            write_mask: wgpu::ColorWrites::ALL,
+        })];
+        let constants = output_constants(format);
+        let advect = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Particle advection pipeline"),
+            depth_stencil: None,
+            ..default_pipeline_descriptor(
+                &advect_layout,
+                &advect_shader,
+                &advect_targets,
+                &[],
+                &Default::default(),
+                false,
+            )
+        });
+
+        let trails_shader =
+            device.create_shader_module(wgpu::include_wgsl!("./shaders/particle_trails.wgsl"));
+        let trails_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: None,
+            bind_group_layouts: &[map_view_layout, &bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let targets = default_targets(format);
+        let mut desc = default_pipeline_descriptor(
+            &trails_layout,
+            &trails_shader,
+            &targets,
+            &[],
+            &constants,
+            false,
+        );
+        let trails = device.create_render_pipeline(&desc);
+        desc.multisample.count = 4;
+        let trails_antialias = device.create_render_pipeline(&desc);
+
+        Self {
+            advect,
+            trails,
+            trails_antialias,
+            bind_group_layout,
+        }
+    }
+
+    /// Creates a particle system. All the particles are spawned at random positions on the first frame.
+    ///
+    /// The number of particles is limited by the maximum texture size of the device.
+    pub fn create_particles(
+        &self,
+        device: &Device,
+        queue: &Queue,
+        count: u32,
+        trail_length: u32,
+        field: &ParticleField,
+    ) -> WgpuParticles {
+        let max_size = device.limits().max_texture_dimension_2d;
+        let trail_length = trail_length.clamp(2, max_size);
+        let columns = count.clamp(1, STATE_COLUMNS);
+        let rows = count.div_ceil(columns).clamp(1, max_size / trail_length);
+        let count = count.min(columns * rows);
+
+        let size = wgpu::Extent3d {
+            width: columns,
+            height: rows * trail_length,
+            depth_or_array_layers: 1,
+        };
+        // Particles older than the maximum age are respawned by the first advection pass.
+        let initial_state = [0.0, 0.0, f32::MAX, 0.0].repeat((size.width * size.height) as usize);
+        let create_state_texture = || {
+            device.create_texture_with_data(
+                queue,
+                &wgpu::TextureDescriptor {
+                    size,
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format: STATE_FORMAT,
+                    usage: wgpu::TextureUsages::TEXTURE_BINDING
+                        | wgpu::TextureUsages::RENDER_ATTACHMENT
+                        | wgpu::TextureUsages::COPY_DST,
+                    label: Some("Particle state texture"),
+                    view_formats: &[],
+                },
+                TextureDataOrder::default(),
+                bytemuck::cast_slice(&initial_state),
+            )
+        };
+        let state_textures = [create_state_texture(), create_state_texture()];
+        let state_views = state_textures
+            .each_ref()
+            .map(|texture| texture.create_view(&wgpu::TextureViewDescriptor::default()));
+
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Particle uniform buffer"),
+            size: size_of::<ParticleUniform>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let velocity_texture = create_velocity_texture(device, queue, field);
+        let bind_groups =
+            self.create_bind_groups(device, &uniform_buffer, &state_views, &velocity_texture);
+
+        let bbox = field.bbox();
+        WgpuParticles {
+            state_textures,
+            state_views,
+            velocity_texture,
+            uniform_buffer,
+            bind_groups,
+            current: 0,
+            count,
+            rows,
+            frame: 0,
+            origin: Point2d::new(
+                (bbox.x_min() + bbox.x_max()) / 2.0,
+                (bbox.y_min() + bbox.y_max()) / 2.0,
+            ),
+            field_bbox: bbox,
+        }
+    }
+
+    /// Replaces the velocity texture of the particles.
+    pub fn update_field(
+        &self,
+        device: &Device,
+        queue: &Queue,
+        particles: &mut WgpuParticles,
+        field: &ParticleField,
+    ) {
+        particles.velocity_texture = create_velocity_texture(device, queue, field);
+        particles.bind_groups = self.create_bind_groups(
+            device,
+            &particles.uniform_buffer,
+            &particles.state_views,
+            &particles.velocity_texture,
+        );
+        particles.field_bbox = field.bbox();
+    }
+
+    fn create_bind_groups(
+        &self,
+        device: &Device,
+        uniform_buffer: &Buffer,
+        state_views: &[TextureView; 2],
+        velocity_texture: &Texture,
+    ) -> [BindGroup; 2] {
+        let velocity_view = velocity_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        state_views.each_ref().map(|state_view| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                layout: &self.bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: uniform_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(state_view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: wgpu::BindingResource::TextureView(&velocity_view),
+                    },
+                ],
+                label: Some("Particles bind group"),
+            })
+        })
+    }
+
+    /// Encodes the pass that moves the particles by one frame.
+    pub fn advect(&self, encoder: &mut CommandEncoder, particles: &mut WgpuParticles) {
+        let next = 1 - particles.current;
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Particle advection pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &particles.state_views[next],
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        // Every texel is written by the pass.
+                        load: wgpu::LoadOp::Load,
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            render_pass.set_pipeline(&self.advect);
+            render_pass.set_bind_group(0, &particles.bind_groups[particles.current], &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+
+        particles.current = next;
+    }
+
+    /// Draws the trails of the particles. The map view must be bound to the group `0` of the render pass.
+    pub fn render<'a>(
+        &'a self,
+        particles: &'a WgpuParticles,
+        render_pass: &mut RenderPass<'a>,
+        render_options: RenderOptions,
+    ) {
+        if render_options.antialias {
+            render_pass.set_pipeline(&self.trails_antialias);
+        } else {
+            render_pass.set_pipeline(&self.trails);
+        }
+
+        let segments = particles.trail_length() - 1;
+        render_pass.set_bind_group(1, &particles.bind_groups[particles.current], &[]);
+        render_pass.draw(0..segments * 6, 0..particles.count);
+    }
+}
+
+impl WgpuParticles {
+    fn trail_length(&self) -> u32 {
+        self.state_textures[0].height() / self.rows
+    }
+
+    /// Writes the parameters of the next frame to the uniform buffer.
+    pub fn set_frame(&mut self, queue: &Queue, frame: &ParticleFrame) {
+        self.frame = self.frame.wrapping_add(1);
+        let relative =
+            |x: f64, y: f64| [(x - self.origin.x()) as f32, (y - self.origin.y()) as f32];
+        let uniform = ParticleUniform {
+            area_min: relative(frame.area.x_min(), frame.area.y_min()),
+            area_max: relative(frame.area.x_max(), frame.area.y_max()),
+            field_min: relative(self.field_bbox.x_min(), self.field_bbox.y_min()),
+            field_size: [
+                self.field_bbox.width() as f32,
+                self.field_bbox.height() as f32,
+            ],
+            time_step: frame.time_step,
+            max_age: frame.max_age.max(1) as f32,
+            // Keeps the argument of the random function small, where it has enough precision.
+            seed: (self.frame % 1024) as f32 * 0.37,
+            rows: self.rows as f32,
+            max_magnitude: frame.max_magnitude,
+            line_width: frame.line_width,
+            _padding: [0.0; 2],
+            color: frame.color.to_f32_array(),
+            slow_color: frame.slow_color.to_f32_array(),
+        };
+
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&[uniform]));
+    }
+}
+
+impl PackedParticles for WgpuParticles {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn gpu_size(&self) -> usize {
+        let texture_size = |texture: &Texture| {
+            texture.width() as usize * texture.height() as usize * size_of::<[f32; 4]>()
+        };
+
+        self.state_textures.iter().map(texture_size).sum::<usize>()
+            + texture_size(&self.velocity_texture)
+            + size_of::<ParticleUniform>()
+    }
+}
+
+/// Uploads the field into a texture with `(x, y, magnitude, has_data)` values. Fields larger than the maximum texture
+/// size of the device are downsampled.
+fn create_velocity_texture(device: &Device, queue: &Queue, field: &ParticleField) -> Texture {
+    let max_size = device.limits().max_texture_dimension_2d;
+    let step = field.width().max(field.height()).div_ceil(max_size).max(1);
+    let width = field.width().div_ceil(step);
+    let height = field.height().div_ceil(step);
+
+    let mut texels = Vec::with_capacity(width as usize * height as usize);
+    for y in 0..height {
+        for x in 0..width {
+            let index = (y * step) as usize * field.width() as usize + (x * step) as usize;
+            texels.push(match field.cells()[index] {
+                Some(velocity) => [velocity.x, velocity.y, velocity.magnitude, 1.0],
+                None => [0.0; 4],
+            });
+        }
+    }
+
+    device.create_texture_with_data(
+        queue,
+        &wgpu::TextureDescriptor {
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: VELOCITY_FORMAT,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            label: Some("Particle velocity texture"),
+            view_formats: &[],
+        },
+        TextureDataOrder::default(),
+        bytemuck::cast_slice(&texels),
+    )
+}
@@ -1,5 +1,7 @@
 use crate::render::render_bundle::tessellating::ScreenRefVertex;
-use crate::render::wgpu::pipelines::{default_pipeline_descriptor, default_targets};
+use crate::render::wgpu::pipelines::{
+    default_pipeline_descriptor, default_targets, output_constants,
+};
 use crate::render::wgpu::{ScreenRefBuffers, DEPTH_FORMAT};
 use crate::render::RenderOptions;
 use std::mem::size_of;
@@ -23,6 +25,8 @@ impl ScreenRefPipeline {
         let shader = device.create_shader_module(wgpu::include_wgsl!("./shaders/screen_ref.wgsl"));
 
         let targets = default_targets(format);
+
+        let constants = output_constants(format);
         let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: None,
             bind_group_layouts: &[map_view_layout],
@@ -47,7 +51,7 @@ impl ScreenRefPipeline {
                 },
                 bias: Default::default(),
             }),
-            ..default_pipeline_descriptor(&layout, &shader, &targets, &buffers, false)
+            ..default_pipeline_descriptor(&layout, &shader, &targets, &buffers, &constants, false)
         };
 
         let wgpu_pipeline = device.create_render_pipeline(&desc);
@@ -79,7 +83,7 @@ impl ScreenRefPipeline {
 }
 
 impl ScreenRefVertex {
-    fn wgpu_desc() -> wgpu::VertexBufferLayout<'static> {
+    pub(super) fn wgpu_desc() -> wgpu::VertexBufferLayout<'static> {
         wgpu::VertexBufferLayout {
             array_stride: size_of::<ScreenRefVertex>() as wgpu::BufferAddress,
             step_mode: wgpu::VertexStepMode::Vertex,
@@ -0,0 +1,25 @@
+
+// Fragment shader
+
+@group(1) @binding(0)
+var t_diffuse: texture_2d<f32>;
+@group(1) @binding(1)
+var s_diffuse: sampler;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    var info: FragmentInfo;
+    info.position = in.clip_position;
+    info.tex_coord = in.tex_coord;
+
+    var color = textureSample(t_diffuse, s_diffuse, in.tex_coord);
+    color[3] = color[3] * in.opacity;
+
+    color = custom_fragment(color, info);
+
+    if color[3] == 0.0 {
+        discard;
+    }
+
+    return to_output_color(color);
+}
@@ -0,0 +1,22 @@
+
+// Fragment shader
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    var info: FragmentInfo;
+    info.position = in.clip_position;
+    info.tex_coord = vec2<f32>(0.0, 0.0);
+
+    var color = in.color;
+    if !linear_output {
+        color = srgb_to_linear(color);
+    }
+
+    color = custom_fragment(color, info);
+
+    if !linear_output {
+        color = linear_to_srgb(color);
+    }
+
+    return color;
+}
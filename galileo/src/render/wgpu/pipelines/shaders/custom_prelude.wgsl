@@ -0,0 +1,21 @@
+// Declarations available to custom shader code, see `CustomShader`.
+
+struct FragmentInfo {
+    // Position of the fragment in framebuffer coordinates.
+    position: vec4<f32>,
+    // Texture coordinates of the fragment for images, zero for other primitives.
+    tex_coord: vec2<f32>,
+}
+
+fn srgb_to_linear(color: vec4<f32>) -> vec4<f32> {
+    let rgb = color.rgb;
+    let linear = select(pow((rgb + 0.055) / 1.055, vec3<f32>(2.4)), rgb / 12.92, rgb <= vec3<f32>(0.04045));
+    return vec4<f32>(linear, color.a);
+}
+
+fn linear_to_srgb(color: vec4<f32>) -> vec4<f32> {
+    let rgb = color.rgb;
+    let srgb = select(1.055 * pow(rgb, vec3<f32>(1.0 / 2.4)) - 0.055, rgb * 12.92, rgb <= vec3<f32>(0.0031308));
+    return vec4<f32>(srgb, color.a);
+}
+
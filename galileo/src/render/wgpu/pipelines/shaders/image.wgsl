@@ -5,11 +5,26 @@ struct ViewUniform {
     view_rotation: mat4x4<f32>,
     inv_screen_size: vec2<f32>,
     resolution: f32,
+    opacity: f32,
 }
 
 @group(0) @binding(0)
 var<uniform> transform: ViewUniform;
 
+// Set by the renderer to `true` if the render target expects linear colors (sRGB and float formats). Textures are
+// sampled as linear colors, so for other targets they are converted back to sRGB.
+override linear_output: bool = true;
+
+fn to_output_color(color: vec4<f32>) -> vec4<f32> {
+    if linear_output {
+        return color;
+    }
+
+    let rgb = color.rgb;
+    let srgb = select(1.055 * pow(rgb, vec3<f32>(1.0 / 2.4)) - 0.055, rgb * 12.92, rgb <= vec3<f32>(0.0031308));
+    return vec4<f32>(srgb, color.a);
+}
+
 struct VertexInput {
     @location(0) position: vec2<f32>,
     @location(1) opacity: f32,
@@ -34,7 +49,7 @@ fn vs_main(
     var vertex_delta = vec4<f32>(model.offset * transform.inv_screen_size * point_position[3] * 2.0, 0.0, 0.0);
 
     out.clip_position = point_position + vertex_delta;
-    out.opacity = model.opacity;
+    out.opacity = model.opacity * transform.opacity;
 
     return out;
 }
@@ -56,5 +71,5 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
         discard;
     }
 
-    return color;
+    return to_output_color(color);
 }
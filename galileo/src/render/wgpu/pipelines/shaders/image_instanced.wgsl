@@ -0,0 +1,90 @@
+// Vertex shader
+
+struct ViewUniform {
+    view_proj: mat4x4<f32>,
+    view_rotation: mat4x4<f32>,
+    inv_screen_size: vec2<f32>,
+    resolution: f32,
+    opacity: f32,
+}
+
+@group(0) @binding(0)
+var<uniform> transform: ViewUniform;
+
+// Set by the renderer to `true` if the render target expects linear colors (sRGB and float formats). Textures are
+// sampled as linear colors, so for other targets they are converted back to sRGB.
+override linear_output: bool = true;
+
+fn to_output_color(color: vec4<f32>) -> vec4<f32> {
+    if linear_output {
+        return color;
+    }
+
+    let rgb = color.rgb;
+    let srgb = select(1.055 * pow(rgb, vec3<f32>(1.0 / 2.4)) - 0.055, rgb * 12.92, rgb <= vec3<f32>(0.0031308));
+    return vec4<f32>(srgb, color.a);
+}
+
+struct VertexInput {
+    @location(0) position: vec2<f32>,
+    @location(1) opacity: f32,
+    @location(2) tex_coord: vec2<f32>,
+    @location(3) offset: vec2<f32>,
+}
+
+struct InstanceInput {
+    @location(4) position: vec3<f32>,
+    @location(5) scale: f32,
+    @location(6) rotation: f32,
+    @location(7) opacity: f32,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(1) tex_coord: vec2<f32>,
+    @location(2) opacity: f32,
+};
+
+@vertex
+fn vs_main(
+    model: VertexInput,
+    instance: InstanceInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.tex_coord = model.tex_coord;
+
+    let cos_a = cos(instance.rotation);
+    let sin_a = sin(instance.rotation);
+    let offset = vec2<f32>(
+        model.offset.x * cos_a - model.offset.y * sin_a,
+        model.offset.x * sin_a + model.offset.y * cos_a,
+    ) * instance.scale;
+
+    var point_position = transform.view_proj * vec4<f32>(instance.position + vec3<f32>(model.position, 0.0), 1.0);
+    var vertex_delta = vec4<f32>(offset * transform.inv_screen_size * point_position[3] * 2.0, 0.0, 0.0);
+
+    out.clip_position = point_position + vertex_delta;
+    out.opacity = model.opacity * instance.opacity * transform.opacity;
+
+    return out;
+}
+
+
+// Fragment shader
+
+@group(1) @binding(0)
+var t_diffuse: texture_2d<f32>;
+@group(1) @binding(1)
+var s_diffuse: sampler;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    var color = textureSample(t_diffuse, s_diffuse, in.tex_coord);
+    color[3] = color[3] * in.opacity;
+
+    if color[3] == 0.0 {
+        discard;
+    }
+
+    return to_output_color(color);
+}
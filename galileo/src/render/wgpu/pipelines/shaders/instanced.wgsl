@@ -0,0 +1,76 @@
+// Vertex shader
+
+struct ViewUniform {
+    view_proj: mat4x4<f32>,
+    view_rotation: mat4x4<f32>,
+    inv_screen_size: vec2<f32>,
+    resolution: f32,
+    opacity: f32,
+}
+
+@group(0) @binding(0)
+var<uniform> transform: ViewUniform;
+
+// Set by the renderer to `true` if the render target expects linear colors (sRGB and float formats). Colors of the
+// primitives are given in sRGB, so they are converted to linear space to be blended correctly.
+override linear_output: bool = true;
+
+fn to_output_color(color: vec4<f32>) -> vec4<f32> {
+    if !linear_output {
+        return color;
+    }
+
+    let rgb = color.rgb;
+    let linear = select(pow((rgb + 0.055) / 1.055, vec3<f32>(2.4)), rgb / 12.92, rgb <= vec3<f32>(0.04045));
+    return vec4<f32>(linear, color.a);
+}
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec2<f32>,
+    @location(2) color: vec4<u32>,
+}
+
+struct InstanceInput {
+    @location(4) position: vec3<f32>,
+    @location(5) scale: f32,
+    @location(6) rotation: f32,
+    @location(7) opacity: f32,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(1) color: vec4<f32>,
+};
+
+@vertex
+fn vs_main(
+    model: VertexInput,
+    instance: InstanceInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.color = to_output_color(vec4<f32>(model.color) / 255.0);
+    out.color.a = out.color.a * instance.opacity * transform.opacity;
+
+    let cos_a = cos(instance.rotation);
+    let sin_a = sin(instance.rotation);
+    let normal = vec2<f32>(
+        model.normal.x * cos_a - model.normal.y * sin_a,
+        model.normal.x * sin_a + model.normal.y * cos_a,
+    ) * instance.scale;
+
+    var point_position = transform.view_proj * vec4<f32>(instance.position + model.position, 1.0);
+    var vertex_delta = vec4<f32>(normal * transform.inv_screen_size * point_position[3] * 2.0, 0.0, 0.0);
+
+    out.clip_position = point_position + vertex_delta;
+
+    return out;
+}
+
+
+// Fragment shader
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return in.color;
+}
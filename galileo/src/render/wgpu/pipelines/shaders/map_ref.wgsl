@@ -5,11 +5,26 @@ struct ViewUniform {
     view_rotation: mat4x4<f32>,
     inv_screen_size: vec2<f32>,
     resolution: f32,
+    opacity: f32,
 }
 
 @group(0) @binding(0)
 var<uniform> transform: ViewUniform;
 
+// Set by the renderer to `true` if the render target expects linear colors (sRGB and float formats). Colors of the
+// primitives are given in sRGB, so they are converted to linear space to be blended correctly.
+override linear_output: bool = true;
+
+fn to_output_color(color: vec4<f32>) -> vec4<f32> {
+    if !linear_output {
+        return color;
+    }
+
+    let rgb = color.rgb;
+    let linear = select(pow((rgb + 0.055) / 1.055, vec3<f32>(2.4)), rgb / 12.92, rgb <= vec3<f32>(0.04045));
+    return vec4<f32>(linear, color.a);
+}
+
 struct VertexInput {
     @location(0) position: vec3<f32>,
     @location(1) color: vec4<f32>,
@@ -27,7 +42,8 @@ fn vs_main(
     model: VertexInput,
 ) -> VertexOutput {
     var out: VertexOutput;
-    out.color = model.color;
+    out.color = to_output_color(model.color);
+    out.color.a = out.color.a * transform.opacity;
 
     var vertex_position = transform.view_proj * vec4<f32>(model.position, 1.0);
     var norm_length = sqrt(model.norm[0] * model.norm[0] + model.norm[1] * model.norm[1]) * transform.resolution;
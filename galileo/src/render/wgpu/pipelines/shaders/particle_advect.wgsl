@@ -0,0 +1,94 @@
+// Moves the particles by one frame. The state texture holds `trail_length` blocks of `rows` rows, one texel per
+// particle: the first block has the current positions of the particles, the next ones their previous positions.
+// Texel values are `(x, y, age, magnitude)`, with the position relative to the origin of the particle system. The
+// values are stored as bits of `f32` in an integer texture, because float textures are not renderable on all platforms.
+
+struct ParticleUniform {
+    area_min: vec2<f32>,
+    area_max: vec2<f32>,
+    field_min: vec2<f32>,
+    field_size: vec2<f32>,
+    time_step: f32,
+    max_age: f32,
+    seed: f32,
+    rows: f32,
+    max_magnitude: f32,
+    line_width: f32,
+    padding: vec2<f32>,
+    color: vec4<f32>,
+    slow_color: vec4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> params: ParticleUniform;
+@group(0) @binding(1)
+var state: texture_2d<u32>;
+// Texel values are `(x, y, magnitude, has_data)`, with the velocity in map units per second.
+@group(0) @binding(2)
+var velocity: texture_2d<f32>;
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+    // One triangle covering the whole target.
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    return vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+}
+
+fn load_state(texel: vec2<i32>) -> vec4<f32> {
+    return bitcast<vec4<f32>>(textureLoad(state, texel, 0));
+}
+
+fn random(seed: vec2<f32>) -> f32 {
+    return fract(sin(dot(seed, vec2<f32>(12.9898, 78.233))) * 43758.5453);
+}
+
+// Velocity at the position interpolated between the centers of the texels.
+fn sample_velocity(position: vec2<f32>) -> vec4<f32> {
+    let size = vec2<f32>(textureDimensions(velocity));
+    let uv = (position - params.field_min) / params.field_size;
+    let texel = vec2<f32>(uv.x, 1.0 - uv.y) * size - 0.5;
+    let base = floor(texel);
+    let t = texel - base;
+
+    let max_index = vec2<i32>(size) - 1;
+    let i0 = clamp(vec2<i32>(base), vec2<i32>(0), max_index);
+    let i1 = clamp(vec2<i32>(base) + 1, vec2<i32>(0), max_index);
+    let top = mix(textureLoad(velocity, i0, 0), textureLoad(velocity, vec2<i32>(i1.x, i0.y), 0), t.x);
+    let bottom = mix(textureLoad(velocity, vec2<i32>(i0.x, i1.y), 0), textureLoad(velocity, i1, 0), t.x);
+
+    var result = mix(top, bottom, t.y);
+    if any(uv < vec2<f32>(0.0)) || any(uv > vec2<f32>(1.0)) {
+        result.w = 0.0;
+    }
+
+    return result;
+}
+
+@fragment
+fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<u32> {
+    return bitcast<vec4<u32>>(advect(position));
+}
+
+fn advect(position: vec4<f32>) -> vec4<f32> {
+    let texel = vec2<i32>(position.xy);
+    let rows = i32(params.rows);
+    if texel.y >= rows {
+        // Trail positions are shifted by one block.
+        return load_state(texel - vec2<i32>(0, rows));
+    }
+
+    let particle = load_state(texel);
+    let velocity = sample_velocity(particle.xy);
+    let next = particle.xy + velocity.xy * params.time_step;
+
+    let seed = position.xy + vec2<f32>(params.seed);
+    let in_area = all(next >= params.area_min) && all(next <= params.area_max);
+    // Particles die randomly, so that they are not respawned all at once.
+    let is_dead = particle.z >= params.max_age || random(seed) < 1.0 / params.max_age;
+    if velocity.w < 0.999 || !in_area || is_dead {
+        let spawn = vec2<f32>(random(seed + 1.7), random(seed + 3.1));
+        return vec4<f32>(params.area_min + spawn * (params.area_max - params.area_min), 0.0, 0.0);
+    }
+
+    return vec4<f32>(next, particle.z + 1.0, velocity.z);
+}
@@ -0,0 +1,106 @@
+// Vertex shader
+
+struct ViewUniform {
+    view_proj: mat4x4<f32>,
+    view_rotation: mat4x4<f32>,
+    inv_screen_size: vec2<f32>,
+    resolution: f32,
+    opacity: f32,
+}
+
+@group(0) @binding(0)
+var<uniform> transform: ViewUniform;
+
+struct ParticleUniform {
+    area_min: vec2<f32>,
+    area_max: vec2<f32>,
+    field_min: vec2<f32>,
+    field_size: vec2<f32>,
+    time_step: f32,
+    max_age: f32,
+    seed: f32,
+    rows: f32,
+    max_magnitude: f32,
+    line_width: f32,
+    padding: vec2<f32>,
+    color: vec4<f32>,
+    slow_color: vec4<f32>,
+}
+
+@group(1) @binding(0)
+var<uniform> params: ParticleUniform;
+@group(1) @binding(1)
+var state: texture_2d<u32>;
+
+// Set by the renderer to `true` if the render target expects linear colors (sRGB and float formats). Colors of the
+// primitives are given in sRGB, so they are converted to linear space to be blended correctly.
+override linear_output: bool = true;
+
+fn to_output_color(color: vec4<f32>) -> vec4<f32> {
+    if !linear_output {
+        return color;
+    }
+
+    let rgb = color.rgb;
+    let linear = select(pow((rgb + 0.055) / 1.055, vec3<f32>(2.4)), rgb / 12.92, rgb <= vec3<f32>(0.04045));
+    return vec4<f32>(linear, color.a);
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(1) color: vec4<f32>,
+};
+
+// Every instance is one particle. Its trail is drawn as a quad (two triangles) per segment between two consecutive
+// positions of the particle.
+@vertex
+fn vs_main(
+    @builtin(vertex_index) vertex_index: u32,
+    @builtin(instance_index) particle: u32,
+) -> VertexOutput {
+    var out: VertexOutput;
+
+    let segment = i32(vertex_index / 6u);
+    let corner = vertex_index % 6u;
+    let columns = i32(textureDimensions(state).x);
+    let texel = vec2<i32>(i32(particle) % columns, i32(particle) / columns);
+    let rows = i32(params.rows);
+    let head = bitcast<vec4<f32>>(textureLoad(state, texel + vec2<i32>(0, segment * rows), 0));
+    let tail = bitcast<vec4<f32>>(textureLoad(state, texel + vec2<i32>(0, (segment + 1) * rows), 0));
+
+    if head.z < 1.0 {
+        // The particle was respawned at this position, so there is no segment between the positions.
+        out.clip_position = vec4<f32>(2.0, 2.0, 2.0, 1.0);
+        return out;
+    }
+
+    let start = transform.view_proj * vec4<f32>(tail.xy, 0.0, 1.0);
+    let end = transform.view_proj * vec4<f32>(head.xy, 0.0, 1.0);
+    let screen_delta = (end.xy / end.w - start.xy / start.w) / transform.inv_screen_size;
+    var direction = vec2<f32>(1.0, 0.0);
+    if length(screen_delta) > 0.0 {
+        direction = normalize(screen_delta);
+    }
+
+    let use_head = corner == 2u || corner == 4u || corner == 5u;
+    let side = select(-1.0, 1.0, corner == 1u || corner == 2u || corner == 4u);
+    let normal = vec2<f32>(-direction.y, direction.x) * side * params.line_width * 0.5;
+    let point_position = select(start, end, use_head);
+    let vertex_delta = vec4<f32>(normal * transform.inv_screen_size * point_position.w * 2.0, 0.0, 0.0);
+    out.clip_position = point_position + vertex_delta;
+
+    let k = clamp(head.w / max(params.max_magnitude, 1e-6), 0.0, 1.0);
+    var color = mix(params.slow_color, params.color, k);
+    let trail_length = f32(textureDimensions(state).y) / params.rows;
+    color.a = color.a * (1.0 - f32(segment) / (trail_length - 1.0)) * transform.opacity;
+    out.color = to_output_color(color);
+
+    return out;
+}
+
+// Fragment shader
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return in.color;
+}
@@ -0,0 +1,329 @@
+//! Propagation of satellite orbits from two-line element sets (TLE) with the SGP4 model.
+//!
+//! [`Satellite`] calculates the sub-satellite point at any moment of time and the ground track of the satellite
+//! for a time interval. The results are time-stamped features that can be displayed with a
+//! [`FeatureLayer`](crate::layer::FeatureLayer): [`SatellitePosition`] is a point feature and [`GroundTrack`] is a line
+//! feature split at the antimeridian.
+//!
+//! ```no_run
+//! # use galileo::satellite::Satellite;
+//! # use std::time::Duration;
+//! # use web_time::SystemTime;
+//! let iss = Satellite::from_tle(
+//!     "ISS (ZARYA)",
+//!     "1 25544U 98067A   20194.88612269 -.00002218  00000-0 -31515-4 0  9992",
+//!     "2 25544  51.6461 221.2784 0001413  89.1723 280.4612 15.49507896236008",
+//! )?;
+//!
+//! let now = SystemTime::now();
+//! let position = iss.position_at(now)?;
+//! let track = iss.ground_track(now, now + Duration::from_secs(90 * 60), Duration::from_secs(30))?;
+//! # Ok::<(), galileo::error::GalileoError>(())
+//! ```
+
+use crate::error::GalileoError;
+use crate::layer::feature_layer::Feature;
+use galileo_types::geo::impls::GeoPoint2d;
+use galileo_types::geo::{GeoPoint, NewGeoPoint};
+use galileo_types::impls::{Contour, MultiContour};
+use std::f64::consts::TAU;
+use std::time::Duration;
+use web_time::{SystemTime, UNIX_EPOCH};
+
+/// Equatorial radius of the WGS84 ellipsoid in kilometers.
+const WGS84_A: f64 = 6378.137;
+/// First eccentricity squared of the WGS84 ellipsoid.
+const WGS84_E2: f64 = 6.694_379_990_14e-3;
+
+/// Unix time of the J2000 epoch (1 January 2000 12:00 UTC) in seconds.
+const J2000_UNIX_SECONDS: f64 = 946_728_000.0;
+/// Number of seconds in a Julian year.
+const SECONDS_PER_JULIAN_YEAR: f64 = 365.25 * 86_400.0;
+
+/// A satellite with known orbital elements.
+pub struct Satellite {
+    name: String,
+    constants: sgp4::Constants,
+    epoch_unix_seconds: f64,
+}
+
+impl Satellite {
+    /// Parses a two-line element set. The `name` is the optional title line of the three-line format, and is only used
+    /// to identify the satellite in the created features.
+    pub fn from_tle(
+        name: impl Into<String>,
+        line1: &str,
+        line2: &str,
+    ) -> Result<Self, GalileoError> {
+        let name = name.into();
+        let elements =
+            sgp4::Elements::from_tle(Some(name.clone()), line1.as_bytes(), line2.as_bytes())
+                .map_err(|err| GalileoError::Generic(format!("invalid TLE: {err}")))?;
+        let constants = sgp4::Constants::from_elements(&elements)
+            .map_err(|err| GalileoError::Generic(format!("invalid orbital elements: {err}")))?;
+
+        Ok(Self {
+            name,
+            constants,
+            epoch_unix_seconds: J2000_UNIX_SECONDS + elements.epoch() * SECONDS_PER_JULIAN_YEAR,
+        })
+    }
+
+    /// Name of the satellite.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Epoch of the orbital elements. Precision of the propagation degrades with the distance from the epoch, usually
+    /// TLEs older than a couple of weeks are not useful anymore.
+    pub fn epoch(&self) -> SystemTime {
+        from_unix_seconds(self.epoch_unix_seconds)
+    }
+
+    /// Calculates the position of the satellite at the given time.
+    pub fn position_at(&self, time: SystemTime) -> Result<SatellitePosition, GalileoError> {
+        let unix_seconds = to_unix_seconds(time);
+        let minutes = (unix_seconds - self.epoch_unix_seconds) / 60.0;
+        let prediction = self
+            .constants
+            .propagate(sgp4::MinutesSinceEpoch(minutes))
+            .map_err(|err| GalileoError::Generic(format!("failed to propagate orbit: {err}")))?;
+
+        let (location, altitude) = teme_to_geodetic(prediction.position, unix_seconds);
+        let [vx, vy, vz] = prediction.velocity;
+
+        Ok(SatellitePosition {
+            location,
+            altitude,
+            speed: (vx * vx + vy * vy + vz * vz).sqrt(),
+            time,
+        })
+    }
+
+    /// Calculates positions of the satellite from `start` to `end` with the given time `step`, and connects them into
+    /// a ground track.
+    pub fn ground_track(
+        &self,
+        start: SystemTime,
+        end: SystemTime,
+        step: Duration,
+    ) -> Result<GroundTrack, GalileoError> {
+        if step.is_zero() {
+            return Err(GalileoError::Generic(
+                "ground track step must be positive".into(),
+            ));
+        }
+
+        let mut positions = vec![];
+        let mut time = start;
+        while time < end {
+            positions.push(self.position_at(time)?);
+            time += step;
+        }
+        positions.push(self.position_at(end)?);
+
+        Ok(GroundTrack::new(self.name.clone(), positions))
+    }
+}
+
+/// Position of a satellite at a moment of time.
+///
+/// The feature geometry is the sub-satellite point.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SatellitePosition {
+    /// Sub-satellite point.
+    pub location: GeoPoint2d,
+    /// Altitude above the WGS84 ellipsoid in kilometers.
+    pub altitude: f64,
+    /// Orbital speed in kilometers per second.
+    pub speed: f64,
+    /// Time of the position.
+    pub time: SystemTime,
+}
+
+impl Feature for SatellitePosition {
+    type Geom = GeoPoint2d;
+
+    fn geometry(&self) -> &Self::Geom {
+        &self.location
+    }
+}
+
+/// Path of the sub-satellite point over a time interval.
+///
+/// The feature geometry is a set of lines, broken where the track crosses the antimeridian, so that it is drawn
+/// correctly on the maps with cylindrical projections.
+#[derive(Debug, Clone)]
+pub struct GroundTrack {
+    /// Name of the satellite.
+    pub name: String,
+    /// Positions of the satellite the track consists of, ordered by time.
+    pub positions: Vec<SatellitePosition>,
+    geometry: MultiContour<GeoPoint2d>,
+}
+
+impl GroundTrack {
+    fn new(name: String, positions: Vec<SatellitePosition>) -> Self {
+        let mut segments = vec![];
+        let mut current: Vec<GeoPoint2d> = vec![];
+
+        for position in &positions {
+            if let Some(prev) = current.last().copied() {
+                if (position.location.lon() - prev.lon()).abs() > 180.0 {
+                    let (prev_edge, next_edge) = antimeridian_crossing(&prev, &position.location);
+                    current.push(prev_edge);
+                    segments.push(Contour::open(std::mem::take(&mut current)));
+                    current.push(next_edge);
+                }
+            }
+
+            current.push(position.location);
+        }
+
+        if current.len() > 1 {
+            segments.push(Contour::open(current));
+        }
+
+        Self {
+            name,
+            positions,
+            geometry: segments.into(),
+        }
+    }
+
+    /// Returns the part of the track between `from` and `to`, e.g. to show the passed and the future parts of the
+    /// track with different styles.
+    pub fn between(&self, from: SystemTime, to: SystemTime) -> Self {
+        Self::new(
+            self.name.clone(),
+            self.positions
+                .iter()
+                .filter(|p| p.time >= from && p.time <= to)
+                .cloned()
+                .collect(),
+        )
+    }
+}
+
+impl Feature for GroundTrack {
+    type Geom = MultiContour<GeoPoint2d>;
+
+    fn geometry(&self) -> &Self::Geom {
+        &self.geometry
+    }
+}
+
+/// Returns points on both sides of the antimeridian where the segment between `from` and `to` crosses it.
+fn antimeridian_crossing(from: &GeoPoint2d, to: &GeoPoint2d) -> (GeoPoint2d, GeoPoint2d) {
+    let edge = 180.0f64.copysign(from.lon());
+    let to_lon = to.lon() + 360.0f64.copysign(from.lon());
+    let k = (edge - from.lon()) / (to_lon - from.lon());
+    let lat = from.lat() + (to.lat() - from.lat()) * k;
+
+    (
+        GeoPoint2d::latlon(lat, edge),
+        GeoPoint2d::latlon(lat, -edge),
+    )
+}
+
+fn to_unix_seconds(time: SystemTime) -> f64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(duration) => duration.as_secs_f64(),
+        Err(err) => -err.duration().as_secs_f64(),
+    }
+}
+
+fn from_unix_seconds(seconds: f64) -> SystemTime {
+    if seconds >= 0.0 {
+        UNIX_EPOCH + Duration::from_secs_f64(seconds)
+    } else {
+        UNIX_EPOCH - Duration::from_secs_f64(-seconds)
+    }
+}
+
+/// Greenwich mean sidereal time in radians (IAU 1982 model).
+fn gmst(unix_seconds: f64) -> f64 {
+    let t = (unix_seconds - J2000_UNIX_SECONDS) / (36_525.0 * 86_400.0);
+    let seconds = 67_310.548_41 + (876_600.0 * 3600.0 + 8_640_184.812_866) * t + 0.093_104 * t * t
+        - 6.2e-6 * t * t * t;
+
+    (seconds.rem_euclid(86_400.0) / 86_400.0) * TAU
+}
+
+/// Converts a position in the TEME frame (in kilometers) into geodetic coordinates and altitude in kilometers.
+fn teme_to_geodetic([x, y, z]: [f64; 3], unix_seconds: f64) -> (GeoPoint2d, f64) {
+    let (sin_g, cos_g) = gmst(unix_seconds).sin_cos();
+    let x_ecef = cos_g * x + sin_g * y;
+    let y_ecef = -sin_g * x + cos_g * y;
+
+    let lon = y_ecef.atan2(x_ecef);
+    let p = x_ecef.hypot(y_ecef);
+
+    let mut lat = z.atan2(p * (1.0 - WGS84_E2));
+    let mut altitude = 0.0;
+    for _ in 0..5 {
+        let sin_lat = lat.sin();
+        let n = WGS84_A / (1.0 - WGS84_E2 * sin_lat * sin_lat).sqrt();
+        altitude = p / lat.cos() - n;
+        lat = z.atan2(p * (1.0 - WGS84_E2 * n / (n + altitude)));
+    }
+
+    (
+        GeoPoint2d::latlon(lat.to_degrees(), lon.to_degrees()),
+        altitude,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use galileo_types::contour::Contour as _;
+    use galileo_types::multi_contour::MultiContour as _;
+
+    fn iss() -> Satellite {
+        Satellite::from_tle(
+            "ISS (ZARYA)",
+            "1 25544U 98067A   20194.88612269 -.00002218  00000-0 -31515-4 0  9992",
+            "2 25544  51.6461 221.2784 0001413  89.1723 280.4612 15.49507896236008",
+        )
+        .expect("valid TLE")
+    }
+
+    #[test]
+    fn propagates_low_earth_orbit() {
+        let iss = iss();
+        let position = iss.position_at(iss.epoch()).expect("propagated");
+
+        assert!(position.altitude > 350.0 && position.altitude < 450.0);
+        assert!(position.location.lat().abs() <= 51.7);
+        assert!(position.speed > 7.0 && position.speed < 8.0);
+    }
+
+    #[test]
+    fn ground_track_is_split_at_antimeridian() {
+        let iss = iss();
+        let start = iss.epoch();
+        let track = iss
+            .ground_track(
+                start,
+                start + Duration::from_secs(3 * 60 * 60),
+                Duration::from_secs(60),
+            )
+            .expect("propagated");
+
+        assert_eq!(track.positions.len(), 181);
+        assert!(track.geometry().contours().count() > 1);
+        for contour in track.geometry().contours() {
+            let points: Vec<_> = contour.iter_points().collect();
+            for pair in points.windows(2) {
+                assert!((pair[0].lon() - pair[1].lon()).abs() < 180.0);
+            }
+        }
+    }
+
+    #[test]
+    fn unix_seconds_roundtrip() {
+        let time = from_unix_seconds(J2000_UNIX_SECONDS);
+        assert_eq!(to_unix_seconds(time), J2000_UNIX_SECONDS);
+    }
+}
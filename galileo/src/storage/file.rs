@@ -0,0 +1,264 @@
+use crate::error::GalileoError;
+use crate::storage::{validate_key, validate_namespace, Storage};
+use bytes::Bytes;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+/// Maximum length of an escaped key used as the file name. Most file systems limit file names to 255 bytes, and some
+/// space is left for the suffix of the temporary files.
+const MAX_FILE_NAME_LENGTH: usize = 200;
+
+/// Prefix of the names of the files with hashed keys. It is never produced by key escaping.
+const HASHED_PREFIX: char = '@';
+
+/// Extension of the files that keep the keys of the entries with hashed keys.
+const KEY_FILE_SUFFIX: &str = ".key";
+
+/// Storage that keeps every namespace in a subfolder of the root folder and every entry in a separate file.
+///
+/// File names are made from the keys by escaping all characters except ASCII letters, digits, `-` and `_` (and `.`
+/// unless it is the first character), so any key can be stored. Entries whose escaped keys are longer than the file
+/// name length limit are stored in files named by the hash of the key, and the key itself is kept in a separate file
+/// with the `.key` extension next to the entry.
+#[derive(Debug, Clone)]
+pub struct FileStorage {
+    root: PathBuf,
+}
+
+impl FileStorage {
+    /// Creates a new storage in the given folder. The folder is created if it doesn't exist.
+    pub fn new(root: impl AsRef<Path>) -> Result<Self, GalileoError> {
+        std::fs::create_dir_all(root.as_ref())?;
+        Ok(Self {
+            root: root.as_ref().into(),
+        })
+    }
+
+    /// Root folder of the storage.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn entry_path(&self, namespace: &str, key: &str) -> Result<PathBuf, GalileoError> {
+        validate_namespace(namespace)?;
+        validate_key(key)?;
+
+        Ok(self.root.join(namespace).join(file_name(key)))
+    }
+}
+
+impl Storage for FileStorage {
+    fn get(&self, namespace: &str, key: &str) -> Result<Option<Bytes>, GalileoError> {
+        let path = self.entry_path(namespace, key)?;
+        if !is_entry_of(&path, key)? {
+            return Ok(None);
+        }
+
+        match std::fs::read(path) {
+            Ok(data) => Ok(Some(data.into())),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn put(&self, namespace: &str, key: &str, data: &[u8]) -> Result<(), GalileoError> {
+        let path = self.entry_path(namespace, key)?;
+        std::fs::create_dir_all(self.root.join(namespace))?;
+
+        if let Some(key_path) = key_file_path(&path) {
+            write_file(&key_path, key.as_bytes())?;
+        }
+        write_file(&path, data)
+    }
+
+    fn delete(&self, namespace: &str, key: &str) -> Result<(), GalileoError> {
+        let path = self.entry_path(namespace, key)?;
+        if !is_entry_of(&path, key)? {
+            return Ok(());
+        }
+
+        remove_file(&path)?;
+        if let Some(key_path) = key_file_path(&path) {
+            remove_file(&key_path)?;
+        }
+
+        Ok(())
+    }
+
+    fn list(&self, namespace: &str) -> Result<Vec<String>, GalileoError> {
+        validate_namespace(namespace)?;
+
+        let entries = match std::fs::read_dir(self.root.join(namespace)) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(vec![]),
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut keys = vec![];
+        for entry in entries {
+            let entry = entry?;
+            let Some(file_name) = entry.file_name().to_str().map(String::from) else {
+                continue;
+            };
+
+            let key = match key_file_path(&entry.path()) {
+                Some(key_path) if !file_name.ends_with(KEY_FILE_SUFFIX) => {
+                    read_key_file(&key_path)?
+                }
+                Some(_) => None,
+                None => decode_key(&file_name),
+            };
+            keys.extend(key);
+        }
+
+        Ok(keys)
+    }
+}
+
+/// Writes into a temporary file first, so that an interrupted write doesn't leave a broken file.
+fn write_file(path: &Path, data: &[u8]) -> Result<(), GalileoError> {
+    let mut tmp_path = path.to_path_buf().into_os_string();
+    tmp_path.push("~");
+    std::fs::write(&tmp_path, data)?;
+    std::fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+fn remove_file(path: &Path) -> Result<(), GalileoError> {
+    match std::fs::remove_file(path) {
+        Err(err) if err.kind() != ErrorKind::NotFound => Err(err.into()),
+        _ => Ok(()),
+    }
+}
+
+fn file_name(key: &str) -> String {
+    let encoded = encode_key(key);
+    if encoded.len() <= MAX_FILE_NAME_LENGTH {
+        encoded
+    } else {
+        format!("{HASHED_PREFIX}{:032x}", hash_key(key))
+    }
+}
+
+/// FNV-1a hash of the key. Unlike the hashers of the standard library it does not change between runs and versions.
+fn hash_key(key: &str) -> u128 {
+    const OFFSET: u128 = 0x6c62272e07bb014262b821756295c58d;
+    const PRIME: u128 = 0x0000000001000000000000000000013b;
+
+    key.bytes().fold(OFFSET, |hash, byte| {
+        (hash ^ byte as u128).wrapping_mul(PRIME)
+    })
+}
+
+/// Path of the file with the key of the entry, if the entry is stored with a hashed key.
+fn key_file_path(path: &Path) -> Option<PathBuf> {
+    let name = path.file_name()?.to_str()?;
+    name.starts_with(HASHED_PREFIX)
+        .then(|| path.with_file_name(format!("{name}{KEY_FILE_SUFFIX}")))
+}
+
+fn read_key_file(path: &Path) -> Result<Option<String>, GalileoError> {
+    match std::fs::read_to_string(path) {
+        Ok(key) => Ok(Some(key)),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Different keys can have the same hash, so an entry with a hashed key is only used if it was stored for this key.
+fn is_entry_of(path: &Path, key: &str) -> Result<bool, GalileoError> {
+    match key_file_path(path) {
+        Some(key_path) => Ok(read_key_file(&key_path)?.as_deref() == Some(key)),
+        None => Ok(true),
+    }
+}
+
+fn encode_key(key: &str) -> String {
+    let mut encoded = String::with_capacity(key.len());
+    for (index, byte) in key.bytes().enumerate() {
+        let is_plain = byte.is_ascii_alphanumeric()
+            || byte == b'-'
+            || byte == b'_'
+            || (byte == b'.' && index > 0);
+        if is_plain {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+
+    encoded
+}
+
+/// Returns `None` for file names that are not encoded keys, e.g. temporary files.
+fn decode_key(file_name: &str) -> Option<String> {
+    if file_name.ends_with('~') || file_name.starts_with(HASHED_PREFIX) {
+        return None;
+    }
+
+    let mut bytes = Vec::with_capacity(file_name.len());
+    let mut chars = file_name.bytes();
+    while let Some(byte) = chars.next() {
+        if byte == b'%' {
+            let hex = [chars.next()?, chars.next()?];
+            bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+        } else {
+            bytes.push(byte);
+        }
+    }
+
+    String::from_utf8(bytes).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_storage() {
+        let root = std::env::temp_dir().join(format!("galileo_storage_{}", std::process::id()));
+        let storage = FileStorage::new(&root).unwrap();
+        crate::storage::tests::check_storage(&storage);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn long_keys() {
+        let root =
+            std::env::temp_dir().join(format!("galileo_storage_long_{}", std::process::id()));
+        let storage = FileStorage::new(&root).unwrap();
+        let long = format!("https://example.com/tiles?layers={}", "a,".repeat(200));
+        let other = format!("{long}b");
+
+        storage.put("tiles", &long, b"long").unwrap();
+        storage.put("tiles", &other, b"other").unwrap();
+        storage.put("tiles", "short", b"short").unwrap();
+        assert_eq!(
+            storage.get("tiles", &long).unwrap(),
+            Some(Bytes::from_static(b"long"))
+        );
+
+        let mut keys = storage.list("tiles").unwrap();
+        keys.sort();
+        assert_eq!(keys, vec![long.clone(), other.clone(), "short".to_string()]);
+
+        storage.delete("tiles", &long).unwrap();
+        assert_eq!(storage.get("tiles", &long).unwrap(), None);
+        let mut keys = storage.list("tiles").unwrap();
+        keys.sort();
+        assert_eq!(keys, vec![other, "short".to_string()]);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn key_encoding() {
+        for key in ["a", ".", "..", "https://a.com/1/2.png?x=%20", "тайл"] {
+            let encoded = encode_key(key);
+            assert!(!encoded.starts_with('.'));
+            assert!(!encoded.contains('/'));
+            assert_eq!(decode_key(&encoded).as_deref(), Some(key));
+        }
+    }
+}
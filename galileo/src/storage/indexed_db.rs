@@ -0,0 +1,181 @@
+use crate::error::GalileoError;
+use crate::storage::{validate_key, validate_namespace, Storage};
+use bytes::Bytes;
+use js_sys::{Array, Promise, Uint8Array};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{IdbDatabase, IdbObjectStore, IdbRequest, IdbTransactionMode};
+
+const DB_VERSION: u32 = 1;
+const STORE_NAME: &str = "galileo_storage";
+
+/// Storage in a browser IndexedDB database.
+///
+/// IndexedDB can only be accessed asynchronously, so the whole content of the database is loaded into memory when the
+/// storage is [opened](IndexedDbStorage::open). Reads are served from memory, and writes update the memory
+/// immediately and are saved into the database in background. Errors of the background writes are logged.
+pub struct IndexedDbStorage {
+    database: IdbDatabase,
+    entries: RefCell<HashMap<String, BTreeMap<String, Bytes>>>,
+}
+
+impl IndexedDbStorage {
+    /// Opens the database with the given name, creating it if it doesn't exist, and loads its content.
+    pub async fn open(name: &str) -> Result<Self, GalileoError> {
+        let factory = web_sys::window()
+            .ok_or_else(|| GalileoError::Wasm(Some("no window object".into())))?
+            .indexed_db()?
+            .ok_or_else(|| GalileoError::Wasm(Some("IndexedDB is not supported".into())))?;
+
+        let open_request = factory.open_with_u32(name, DB_VERSION)?;
+        let upgrade_request = open_request.clone();
+        let on_upgrade = Closure::once_into_js(move || {
+            let Ok(database) = upgrade_request
+                .result()
+                .and_then(|value| value.dyn_into::<IdbDatabase>().map_err(JsValue::from))
+            else {
+                return;
+            };
+
+            if !database.object_store_names().contains(STORE_NAME) {
+                if let Err(err) = database.create_object_store(STORE_NAME) {
+                    log::warn!("Failed to create IndexedDB object store: {err:?}");
+                }
+            }
+        });
+        open_request.set_onupgradeneeded(Some(on_upgrade.unchecked_ref()));
+
+        let database: IdbDatabase = wait_for(&open_request).await?.dyn_into()?;
+
+        // Both requests are issued before awaiting, so that the transaction stays active for both of them.
+        let store = database
+            .transaction_with_str(STORE_NAME)?
+            .object_store(STORE_NAME)?;
+        let keys_request = store.get_all_keys()?;
+        let values_request = store.get_all()?;
+        let keys: Array = wait_for(&keys_request).await?.dyn_into()?;
+        let values: Array = wait_for(&values_request).await?.dyn_into()?;
+
+        let mut entries: HashMap<String, BTreeMap<String, Bytes>> = HashMap::new();
+        for (key, value) in keys.iter().zip(values.iter()) {
+            let (Some(key), Ok(value)) = (key.as_string(), value.dyn_into::<Uint8Array>()) else {
+                continue;
+            };
+            if let Some((namespace, key)) = key.split_once('/') {
+                entries
+                    .entry(namespace.to_string())
+                    .or_default()
+                    .insert(key.to_string(), value.to_vec().into());
+            }
+        }
+
+        Ok(Self {
+            database,
+            entries: RefCell::new(entries),
+        })
+    }
+
+    fn write(
+        &self,
+        request: impl FnOnce(&IdbObjectStore) -> Result<IdbRequest, JsValue>,
+    ) -> Result<(), GalileoError> {
+        let store = self
+            .database
+            .transaction_with_str_and_mode(STORE_NAME, IdbTransactionMode::Readwrite)?
+            .object_store(STORE_NAME)?;
+        let request = request(&store)?;
+
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Err(err) = wait_for(&request).await {
+                log::warn!("Failed to write into IndexedDB: {err}");
+            }
+        });
+
+        Ok(())
+    }
+}
+
+impl Storage for IndexedDbStorage {
+    fn get(&self, namespace: &str, key: &str) -> Result<Option<Bytes>, GalileoError> {
+        validate_namespace(namespace)?;
+        validate_key(key)?;
+
+        Ok(self
+            .entries
+            .borrow()
+            .get(namespace)
+            .and_then(|entries| entries.get(key))
+            .cloned())
+    }
+
+    fn put(&self, namespace: &str, key: &str, data: &[u8]) -> Result<(), GalileoError> {
+        validate_namespace(namespace)?;
+        validate_key(key)?;
+
+        self.write(|store| {
+            store.put_with_key(
+                &Uint8Array::from(data),
+                &JsValue::from_str(&db_key(namespace, key)),
+            )
+        })?;
+        self.entries
+            .borrow_mut()
+            .entry(namespace.to_string())
+            .or_default()
+            .insert(key.to_string(), Bytes::copy_from_slice(data));
+
+        Ok(())
+    }
+
+    fn delete(&self, namespace: &str, key: &str) -> Result<(), GalileoError> {
+        validate_namespace(namespace)?;
+        validate_key(key)?;
+
+        self.write(|store| store.delete(&JsValue::from_str(&db_key(namespace, key))))?;
+        if let Some(entries) = self.entries.borrow_mut().get_mut(namespace) {
+            entries.remove(key);
+        }
+
+        Ok(())
+    }
+
+    fn list(&self, namespace: &str) -> Result<Vec<String>, GalileoError> {
+        validate_namespace(namespace)?;
+
+        Ok(self
+            .entries
+            .borrow()
+            .get(namespace)
+            .map(|entries| entries.keys().cloned().collect())
+            .unwrap_or_default())
+    }
+}
+
+/// Namespaces cannot contain `/`, so the first `/` always separates the namespace from the key.
+fn db_key(namespace: &str, key: &str) -> String {
+    format!("{namespace}/{key}")
+}
+
+async fn wait_for(request: &IdbRequest) -> Result<JsValue, GalileoError> {
+    let promise = Promise::new(&mut |resolve, reject| {
+        let success_request = request.clone();
+        let on_success = Closure::once_into_js(move || {
+            let result = success_request.result().unwrap_or(JsValue::UNDEFINED);
+            let _ = resolve.call1(&JsValue::UNDEFINED, &result);
+        });
+        let on_error = Closure::once_into_js(move || {
+            let _ = reject.call1(
+                &JsValue::UNDEFINED,
+                &JsValue::from_str("IndexedDB request failed"),
+            );
+        });
+
+        request.set_onsuccess(Some(on_success.unchecked_ref()));
+        request.set_onerror(Some(on_error.unchecked_ref()));
+    });
+
+    Ok(JsFuture::from(promise).await?)
+}
@@ -0,0 +1,317 @@
+//! Pluggable key-value storage for the data the map keeps between sessions of an application.
+//!
+//! All persistent artifacts of the library go through the [`Storage`] trait: the persistent caches of tiles, vector
+//! tile styles and fonts (with [`StorageCacheController`]) and the [session state](crate::MapSessionState::save) of the
+//! map. The data is split into namespaces, so one storage can be shared by all of them:
+//!
+//! ```
+//! use std::sync::Arc;
+//! use galileo::storage::{MemoryStorage, Storage, StorageCacheController, TILES_NAMESPACE};
+//! use galileo::layer::data_provider::PersistentCacheController;
+//!
+//! let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::default());
+//! let cache = StorageCacheController::new(storage.clone(), TILES_NAMESPACE);
+//!
+//! cache.insert("https://example.com/1/0/0.png", &vec![1, 2, 3].into()).unwrap();
+//! assert_eq!(storage.list(TILES_NAMESPACE).unwrap(), vec!["https://example.com/1/0/0.png"]);
+//! ```
+//!
+//! The caches are used by:
+//! * tile providers - [`UrlDataProvider`](crate::layer::data_provider::UrlDataProvider),
+//!   [`UrlImageProvider`](crate::layer::data_provider::UrlImageProvider) and the vector tile loaders
+//!   ([`TILES_NAMESPACE`]);
+//! * [`VectorTileStyle::load_cached`](crate::layer::vector_tile_layer::style::VectorTileStyle::load_cached)
+//!   ([`STYLES_NAMESPACE`]);
+//! * [`FontService::load_fonts_from_url`](crate::render::text::font_service::FontService::load_fonts_from_url)
+//!   ([`GLYPHS_NAMESPACE`]).
+//!
+//! The library provides these backends:
+//! * [`MemoryStorage`] - keeps the data in memory, so nothing is actually persisted. Useful for tests.
+//! * [`FileStorage`] - a folder per namespace and a file per key (not available in browsers).
+//! * [`SqliteStorage`] - a single SQLite database file (`sqlite` feature, not available in browsers).
+//! * [`IndexedDbStorage`] - browser IndexedDB database (only in browsers).
+//!
+//! Applications running on platforms without any of these (consoles, sandboxed apps) can implement [`Storage`] over
+//! whatever the platform provides.
+
+use crate::error::GalileoError;
+use crate::layer::data_provider::PersistentCacheController;
+use crate::platform::{PlatformService, PlatformServiceImpl};
+use bytes::Bytes;
+use maybe_sync::{MaybeSend, MaybeSync};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, RwLock};
+
+#[cfg(not(target_arch = "wasm32"))]
+mod file;
+#[cfg(target_arch = "wasm32")]
+mod indexed_db;
+#[cfg(all(feature = "sqlite", not(target_arch = "wasm32")))]
+mod sqlite;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use file::FileStorage;
+#[cfg(target_arch = "wasm32")]
+pub use indexed_db::IndexedDbStorage;
+#[cfg(all(feature = "sqlite", not(target_arch = "wasm32")))]
+pub use sqlite::SqliteStorage;
+
+/// Namespace of the persistent tile cache.
+pub const TILES_NAMESPACE: &str = "tiles";
+/// Namespace of the cache of vector tile styles.
+pub const STYLES_NAMESPACE: &str = "styles";
+/// Namespace of the cache of font files.
+pub const GLYPHS_NAMESPACE: &str = "glyphs";
+
+/// Key-value storage of binary data split into namespaces.
+///
+/// Namespaces must be non-empty and consist only of ASCII letters, digits, `-`, `_` and `.` characters, so that
+/// implementations can map them directly to folder, table or store names. Keys can be any non-empty strings.
+/// Implementations should return an error for invalid names, which can be checked with [`validate_namespace`] and
+/// [`validate_key`].
+pub trait Storage: MaybeSend + MaybeSync {
+    /// Returns the data stored with the key in the namespace, or `None` if there is no such entry.
+    fn get(&self, namespace: &str, key: &str) -> Result<Option<Bytes>, GalileoError>;
+    /// Stores the data with the key in the namespace, replacing the existing entry if any.
+    fn put(&self, namespace: &str, key: &str, data: &[u8]) -> Result<(), GalileoError>;
+    /// Deletes the entry with the key from the namespace. Deleting an entry that does not exist is not an error.
+    fn delete(&self, namespace: &str, key: &str) -> Result<(), GalileoError>;
+    /// Returns the keys of all entries of the namespace in no particular order.
+    fn list(&self, namespace: &str) -> Result<Vec<String>, GalileoError>;
+}
+
+impl<T: Storage + ?Sized> Storage for Arc<T> {
+    fn get(&self, namespace: &str, key: &str) -> Result<Option<Bytes>, GalileoError> {
+        (**self).get(namespace, key)
+    }
+
+    fn put(&self, namespace: &str, key: &str, data: &[u8]) -> Result<(), GalileoError> {
+        (**self).put(namespace, key, data)
+    }
+
+    fn delete(&self, namespace: &str, key: &str) -> Result<(), GalileoError> {
+        (**self).delete(namespace, key)
+    }
+
+    fn list(&self, namespace: &str) -> Result<Vec<String>, GalileoError> {
+        (**self).list(namespace)
+    }
+}
+
+/// Returns an error if the namespace name is not valid for a [`Storage`].
+pub fn validate_namespace(namespace: &str) -> Result<(), GalileoError> {
+    let is_valid = !namespace.is_empty()
+        && namespace
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if is_valid {
+        Ok(())
+    } else {
+        Err(GalileoError::Generic(format!(
+            "invalid storage namespace: {namespace:?}"
+        )))
+    }
+}
+
+/// Returns an error if the key is not valid for a [`Storage`].
+pub fn validate_key(key: &str) -> Result<(), GalileoError> {
+    if key.is_empty() {
+        Err(GalileoError::Generic("storage key cannot be empty".into()))
+    } else {
+        Ok(())
+    }
+}
+
+/// Storage that keeps the data in memory.
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+    namespaces: RwLock<HashMap<String, BTreeMap<String, Bytes>>>,
+}
+
+impl Storage for MemoryStorage {
+    fn get(&self, namespace: &str, key: &str) -> Result<Option<Bytes>, GalileoError> {
+        validate_namespace(namespace)?;
+        validate_key(key)?;
+
+        Ok(self
+            .namespaces
+            .read()
+            .expect("lock is poisoned")
+            .get(namespace)
+            .and_then(|entries| entries.get(key))
+            .cloned())
+    }
+
+    fn put(&self, namespace: &str, key: &str, data: &[u8]) -> Result<(), GalileoError> {
+        validate_namespace(namespace)?;
+        validate_key(key)?;
+
+        self.namespaces
+            .write()
+            .expect("lock is poisoned")
+            .entry(namespace.to_string())
+            .or_default()
+            .insert(key.to_string(), Bytes::copy_from_slice(data));
+        Ok(())
+    }
+
+    fn delete(&self, namespace: &str, key: &str) -> Result<(), GalileoError> {
+        validate_namespace(namespace)?;
+        validate_key(key)?;
+
+        if let Some(entries) = self
+            .namespaces
+            .write()
+            .expect("lock is poisoned")
+            .get_mut(namespace)
+        {
+            entries.remove(key);
+        }
+        Ok(())
+    }
+
+    fn list(&self, namespace: &str) -> Result<Vec<String>, GalileoError> {
+        validate_namespace(namespace)?;
+
+        Ok(self
+            .namespaces
+            .read()
+            .expect("lock is poisoned")
+            .get(namespace)
+            .map(|entries| entries.keys().cloned().collect())
+            .unwrap_or_default())
+    }
+}
+
+/// Persistent cache of the tile data (or any other data loaded by URL) in a namespace of a [`Storage`].
+///
+/// Errors of the storage are logged and treated as cache misses, so a broken storage only makes the map load the data
+/// from the network again.
+#[derive(Debug, Clone)]
+pub struct StorageCacheController<S: Storage = Arc<dyn Storage>> {
+    storage: S,
+    namespace: String,
+}
+
+impl<S: Storage> StorageCacheController<S> {
+    /// Creates a new cache that keeps the entries in the given namespace of the storage.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the namespace name is not valid (see [`Storage`]).
+    pub fn new(storage: S, namespace: impl Into<String>) -> Self {
+        let namespace = namespace.into();
+        validate_namespace(&namespace).expect("invalid cache namespace");
+
+        Self { storage, namespace }
+    }
+
+    /// Storage of the cache.
+    pub fn storage(&self) -> &S {
+        &self.storage
+    }
+
+    /// Namespace of the storage the cache entries are kept in.
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+}
+
+impl<S: Storage> PersistentCacheController<str, Bytes> for StorageCacheController<S> {
+    fn get(&self, key: &str) -> Option<Bytes> {
+        match self.storage.get(&self.namespace, key) {
+            Ok(data) => data,
+            Err(err) => {
+                log::warn!("Failed to read cache entry {key}: {err}");
+                None
+            }
+        }
+    }
+
+    fn insert(&self, key: &str, data: &Bytes) -> Result<(), GalileoError> {
+        self.storage.put(&self.namespace, key, data)
+    }
+}
+
+/// Returns the data of the URL from the cache, or loads it and saves it to the cache if it is not cached yet.
+pub(crate) async fn load_cached(
+    url: &str,
+    cache: &impl PersistentCacheController<str, Bytes>,
+) -> Result<Bytes, GalileoError> {
+    if let Some(data) = cache.get(url) {
+        return Ok(data);
+    }
+
+    let data = PlatformServiceImpl::new().load_bytes_from_url(url).await?;
+    if let Err(error) = cache.insert(url, &data) {
+        log::warn!("Failed to write persistent cache entry: {:?}", error);
+    }
+
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks the behaviour every storage implementation must have.
+    pub(super) fn check_storage(storage: &dyn Storage) {
+        let key = "https://example.com/tiles/1/2/3.png?key=a b";
+        assert_eq!(storage.get("tiles", key).unwrap(), None);
+        assert!(storage.list("tiles").unwrap().is_empty());
+
+        storage.put("tiles", key, b"first").unwrap();
+        storage.put("tiles", key, b"second").unwrap();
+        storage.put("tiles", "..", b"dots").unwrap();
+        storage.put("session", key, b"other").unwrap();
+
+        assert_eq!(
+            storage.get("tiles", key).unwrap(),
+            Some(Bytes::from_static(b"second"))
+        );
+        assert_eq!(
+            storage.get("session", key).unwrap(),
+            Some(Bytes::from_static(b"other"))
+        );
+
+        let mut keys = storage.list("tiles").unwrap();
+        keys.sort();
+        assert_eq!(keys, vec!["..".to_string(), key.to_string()]);
+
+        storage.delete("tiles", key).unwrap();
+        storage.delete("tiles", "missing").unwrap();
+        assert_eq!(storage.get("tiles", key).unwrap(), None);
+        assert_eq!(storage.list("tiles").unwrap(), vec!["..".to_string()]);
+        assert_eq!(storage.list("session").unwrap(), vec![key.to_string()]);
+
+        assert!(storage.put("../tiles", key, b"").is_err());
+        assert!(storage.put("", key, b"").is_err());
+        assert!(storage.put("tiles", "", b"").is_err());
+    }
+
+    #[test]
+    fn memory_storage() {
+        check_storage(&MemoryStorage::default());
+    }
+
+    #[test]
+    fn cache_controller_uses_namespace() {
+        let storage = Arc::new(MemoryStorage::default());
+        let cache = StorageCacheController::new(storage.clone(), "tiles");
+
+        assert_eq!(cache.get("a"), None);
+        cache.insert("a", &Bytes::from_static(b"data")).unwrap();
+        assert_eq!(cache.get("a"), Some(Bytes::from_static(b"data")));
+        assert_eq!(storage.list("tiles").unwrap(), vec!["a".to_string()]);
+    }
+
+    #[test]
+    fn load_cached_uses_cache_entry() {
+        let cache = StorageCacheController::new(MemoryStorage::default(), STYLES_NAMESPACE);
+        let url = "https://example.com/style.json";
+        cache.insert(url, &Bytes::from_static(b"{}")).unwrap();
+
+        let data = tokio_test::block_on(load_cached(url, &cache)).unwrap();
+        assert_eq!(data, Bytes::from_static(b"{}"));
+    }
+}
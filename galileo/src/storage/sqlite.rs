@@ -0,0 +1,120 @@
+use crate::error::GalileoError;
+use crate::storage::{validate_key, validate_namespace, Storage};
+use bytes::Bytes;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Storage that keeps all the namespaces in a single table of an SQLite database.
+pub struct SqliteStorage {
+    connection: Mutex<Connection>,
+}
+
+impl SqliteStorage {
+    /// Opens the storage in the database file at the given path, creating the file if it doesn't exist.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, GalileoError> {
+        Self::new(Connection::open(path).map_err(sqlite_error)?)
+    }
+
+    /// Creates a storage that keeps the data in memory. Useful for tests.
+    pub fn in_memory() -> Result<Self, GalileoError> {
+        Self::new(Connection::open_in_memory().map_err(sqlite_error)?)
+    }
+
+    /// Creates a storage in the given database connection. The storage table is created if it doesn't exist.
+    pub fn new(connection: Connection) -> Result<Self, GalileoError> {
+        connection
+            .execute_batch(
+                "CREATE TABLE IF NOT EXISTS galileo_storage (
+                    namespace TEXT NOT NULL,
+                    key TEXT NOT NULL,
+                    data BLOB NOT NULL,
+                    PRIMARY KEY (namespace, key)
+                );",
+            )
+            .map_err(sqlite_error)?;
+
+        Ok(Self {
+            connection: Mutex::new(connection),
+        })
+    }
+}
+
+impl Storage for SqliteStorage {
+    fn get(&self, namespace: &str, key: &str) -> Result<Option<Bytes>, GalileoError> {
+        validate_namespace(namespace)?;
+        validate_key(key)?;
+
+        self.connection
+            .lock()
+            .expect("mutex is poisoned")
+            .query_row(
+                "SELECT data FROM galileo_storage WHERE namespace = ?1 AND key = ?2",
+                params![namespace, key],
+                |row| row.get::<_, Vec<u8>>(0),
+            )
+            .optional()
+            .map(|data| data.map(Bytes::from))
+            .map_err(sqlite_error)
+    }
+
+    fn put(&self, namespace: &str, key: &str, data: &[u8]) -> Result<(), GalileoError> {
+        validate_namespace(namespace)?;
+        validate_key(key)?;
+
+        self.connection
+            .lock()
+            .expect("mutex is poisoned")
+            .execute(
+                "INSERT OR REPLACE INTO galileo_storage (namespace, key, data) VALUES (?1, ?2, ?3)",
+                params![namespace, key, data],
+            )
+            .map_err(sqlite_error)?;
+        Ok(())
+    }
+
+    fn delete(&self, namespace: &str, key: &str) -> Result<(), GalileoError> {
+        validate_namespace(namespace)?;
+        validate_key(key)?;
+
+        self.connection
+            .lock()
+            .expect("mutex is poisoned")
+            .execute(
+                "DELETE FROM galileo_storage WHERE namespace = ?1 AND key = ?2",
+                params![namespace, key],
+            )
+            .map_err(sqlite_error)?;
+        Ok(())
+    }
+
+    fn list(&self, namespace: &str) -> Result<Vec<String>, GalileoError> {
+        validate_namespace(namespace)?;
+
+        let connection = self.connection.lock().expect("mutex is poisoned");
+        let mut statement = connection
+            .prepare("SELECT key FROM galileo_storage WHERE namespace = ?1")
+            .map_err(sqlite_error)?;
+        let keys = statement
+            .query_map(params![namespace], |row| row.get(0))
+            .map_err(sqlite_error)?
+            .collect::<Result<_, _>>()
+            .map_err(sqlite_error)?;
+
+        Ok(keys)
+    }
+}
+
+fn sqlite_error(err: rusqlite::Error) -> GalileoError {
+    GalileoError::Generic(format!("storage database error: {err}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sqlite_storage() {
+        crate::storage::tests::check_storage(&SqliteStorage::in_memory().unwrap());
+    }
+}
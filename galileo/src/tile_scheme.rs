@@ -113,12 +113,12 @@ impl TileSchema {
             return None;
         }
 
-        let resolution = view.resolution();
+        let resolution = view.logical_resolution();
         let bounding_box = view.get_bbox()?;
         self.iter_tiles_over_bbox(resolution, bounding_box)
     }
 
-    fn iter_tiles_over_bbox(
+    pub(crate) fn iter_tiles_over_bbox(
         &self,
         resolution: f64,
         bounding_box: Rect,
@@ -227,7 +227,46 @@ impl TileSchema {
         }
     }
 
-    pub(crate) fn tile_bbox(&self, index: TileIndex) -> Option<Rect> {
+    /// Tile schema of a deep-zoom pyramid of a non-geographic image (a gigapixel scan, game map, floor plan etc.) in
+    /// the [planar](Crs::PLANAR) CRS.
+    ///
+    /// Map coordinates are pixels of the full resolution image, with the bottom left corner of the image at `(0, 0)`
+    /// and `y` axis pointing up, so pixel `(x, y)` of the image (counted from the top left corner) has map coordinates
+    /// `(x, height - y)`. Tile `(0, 0)` of each level is at the top left corner of the image.
+    ///
+    /// The last z-level has resolution 1 (one map unit per image pixel), and each previous level halves the image
+    /// until it fits into a single tile at z-level 0. Tiles at the right and bottom edges of a level may be smaller
+    /// than the tile size.
+    ///
+    /// Returns `None` if any of the sizes is zero.
+    pub fn image_pyramid(width: u32, height: u32, tile_size: u32) -> Option<Self> {
+        if width == 0 || height == 0 || tile_size == 0 {
+            return None;
+        }
+
+        let mut max_z = 0;
+        while (width.max(height) as f64) > (tile_size as f64) * 2f64.powi(max_z as i32) {
+            max_z += 1;
+        }
+
+        let lods = (0..=max_z)
+            .map(|z| Lod::new(2f64.powi((max_z - z) as i32), z).expect("resolution is valid"))
+            .collect();
+
+        Some(TileSchema {
+            origin: Point2d::new(0.0, height as f64),
+            bounds: Rect::new(0.0, 0.0, width as f64, height as f64),
+            lods,
+            tile_width: tile_size,
+            tile_height: tile_size,
+            y_direction: VerticalDirection::TopToBottom,
+            crs: Crs::PLANAR,
+        })
+    }
+
+    /// Bounding box of the tile in the projected coordinates of the schema CRS. Returns `None` if the schema has no
+    /// level with the z-index of the tile.
+    pub fn tile_bbox(&self, index: TileIndex) -> Option<Rect> {
         let resolution = self
             .lods
             .iter()
@@ -258,18 +297,13 @@ impl TileSchema {
 
     fn max_x_index(&self, resolution: f64) -> i32 {
         let pix_bound = (self.bounds.x_max() - self.origin.x()) / resolution;
-        let floored = pix_bound.floor();
-        if (pix_bound - floored).abs() < 0.1 {
-            (floored / self.tile_width as f64) as i32 - 1
-        } else {
-            (floored / self.tile_width as f64) as i32
-        }
+        Self::last_tile_index(pix_bound, self.tile_width)
     }
 
     fn min_y_index(&self, resolution: f64) -> i32 {
         match self.y_direction {
             VerticalDirection::TopToBottom => {
-                ((self.bounds.y_min() + self.origin.y()) / resolution / self.tile_height as f64)
+                ((self.origin.y() - self.bounds.y_max()) / resolution / self.tile_height as f64)
                     .floor() as i32
             }
             VerticalDirection::BottomToTop => {
@@ -281,14 +315,21 @@ impl TileSchema {
 
     fn max_y_index(&self, resolution: f64) -> i32 {
         let pix_bound = match self.y_direction {
-            VerticalDirection::TopToBottom => (self.bounds.y_max() + self.origin.y()) / resolution,
+            VerticalDirection::TopToBottom => (self.origin.y() - self.bounds.y_min()) / resolution,
             VerticalDirection::BottomToTop => (self.bounds.y_max() - self.origin.y()) / resolution,
         };
-        let floored = pix_bound.floor();
-        if (pix_bound - floored).abs() < 0.1 {
-            (floored / self.tile_height as f64) as i32 - 1
+        Self::last_tile_index(pix_bound, self.tile_height)
+    }
+
+    /// Index of the last tile that contains pixels before `pix_bound`. Bounds are not required to be aligned with
+    /// the tile borders (e.g. for image pyramids), so the last tile can be partial.
+    fn last_tile_index(pix_bound: f64, tile_size: u32) -> i32 {
+        let tiles = pix_bound / tile_size as f64;
+        let floored = tiles.floor();
+        if (tiles - floored) * (tile_size as f64) < 0.1 {
+            floored as i32 - 1
         } else {
-            (floored / self.tile_height as f64) as i32
+            floored as i32
         }
     }
 }
@@ -432,6 +473,43 @@ mod tests {
         assert_eq!(schema.iter_tiles(&view).unwrap().count(), 16);
     }
 
+    #[test]
+    fn iter_tiles_uses_logical_resolution() {
+        let schema = simple_schema();
+        let bbox = Rect::new(0.0, 0.0, 2048.0, 2048.0);
+        let view = get_view(2.0, bbox);
+        assert_eq!(schema.iter_tiles(&view).unwrap().count(), 16);
+
+        let view = view.with_scale_factor(2.0);
+        assert_eq!(schema.iter_tiles(&view).unwrap().count(), 4);
+    }
+
+    #[test]
+    fn image_pyramid() {
+        assert!(TileSchema::image_pyramid(0, 100, 256).is_none());
+
+        let schema = TileSchema::image_pyramid(1000, 600, 256).unwrap();
+        assert_eq!(schema.lods.len(), 3);
+        assert_eq!(schema.lod_resolution(0), Some(4.0));
+        assert_eq!(schema.lod_resolution(2), Some(1.0));
+        assert_eq!(
+            schema.tile_bbox(TileIndex::new(0, 0, 2)).unwrap(),
+            Rect::new(0.0, 344.0, 256.0, 600.0)
+        );
+
+        let bbox = Rect::new(0.0, 0.0, 1000.0, 600.0);
+        let view = MapView::planar(&bbox.center(), 4.0).with_size(Size::new(250.0, 150.0));
+        assert_eq!(schema.iter_tiles(&view).unwrap().count(), 1);
+
+        let view = MapView::planar(&bbox.center(), 1.0).with_size(Size::new(1000.0, 600.0));
+        let tiles: Vec<_> = schema.iter_tiles(&view).unwrap().collect();
+        assert_eq!(tiles.len(), 12);
+        assert!(tiles.iter().all(|tile| tile.x <= 3 && tile.y <= 2));
+
+        // Web views are not rendered with planar tiles.
+        assert!(schema.iter_tiles(&get_view(1.0, bbox)).is_none());
+    }
+
     #[test]
     fn lod_over() {
         let schema = simple_schema();
@@ -17,7 +17,8 @@ use nalgebra::{
 ///   displayed in. Note, that currently geographic CRSs are not supported, and a map with such a view will not be
 ///   drawn.
 ///
-/// The view can also specify rotation along *x* (tilt) and *z* (rotation) axis.
+/// The view can also specify rotation along *x* (tilt) and *z* (rotation) axis, and [padding](ViewPadding) of the
+/// rendering area covered by the application UI.
 #[derive(Debug, Clone)]
 pub struct MapView {
     projected_position: Option<Point3<f64>>,
@@ -25,9 +26,72 @@ pub struct MapView {
     rotation_x: f64,
     rotation_z: f64,
     size: Size,
+    padding: ViewPadding,
+    scale_factor: f64,
     crs: Crs,
 }
 
+/// Insets of the rendering area of a [`MapView`] in pixels, covered by the UI of the application (side panels,
+/// bottom sheets, toolbars etc).
+///
+/// The position of the view is displayed at the center of the visible part of the rendering area instead of the
+/// center of the whole area, and [`MapView::fit_bbox`] fits the given area into the visible part. The map is still
+/// drawn under the padding.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ViewPadding {
+    /// Width of the covered area at the left side.
+    pub left: f64,
+    /// Width of the covered area at the right side.
+    pub right: f64,
+    /// Height of the covered area at the top.
+    pub top: f64,
+    /// Height of the covered area at the bottom.
+    pub bottom: f64,
+}
+
+impl ViewPadding {
+    /// Creates new padding.
+    pub fn new(left: f64, right: f64, top: f64, bottom: f64) -> Self {
+        Self {
+            left,
+            right,
+            top,
+            bottom,
+        }
+    }
+
+    /// Creates padding with the same inset on all the sides.
+    pub fn uniform(inset: f64) -> Self {
+        Self::new(inset, inset, inset, inset)
+    }
+
+    /// Size of the visible part of the area of the given size. Never negative.
+    pub fn visible_size(&self, size: Size) -> Size {
+        Size::new(
+            (size.width() - self.left - self.right).max(0.0),
+            (size.height() - self.top - self.bottom).max(0.0),
+        )
+    }
+
+    /// Offset of the center of the visible part from the center of the whole area in pixels, with *Y* going down.
+    fn center_offset(&self) -> Vector2<f64> {
+        Vector2::new(
+            (self.left - self.right) / 2.0,
+            (self.top - self.bottom) / 2.0,
+        )
+    }
+
+    fn interpolate(&self, target: &ViewPadding, k: f64) -> Self {
+        let lerp = |a: f64, b: f64| a + (b - a) * k;
+        Self {
+            left: lerp(self.left, target.left),
+            right: lerp(self.right, target.right),
+            top: lerp(self.top, target.top),
+            bottom: lerp(self.bottom, target.bottom),
+        }
+    }
+}
+
 impl MapView {
     /// Creates a new view with the given position and resolution with default CRS (web-mercator EPSG:3857).
     pub fn new(position: &impl GeoPoint<Num = f64>, resolution: f64) -> Self {
@@ -46,6 +110,8 @@ impl MapView {
             rotation_z: 0.0,
             rotation_x: 0.0,
             size: Default::default(),
+            padding: Default::default(),
+            scale_factor: 1.0,
             crs,
         }
     }
@@ -67,16 +133,25 @@ impl MapView {
             rotation_z: 0.0,
             rotation_x: 0.0,
             size: Default::default(),
+            padding: Default::default(),
+            scale_factor: 1.0,
             crs,
         }
     }
 
+    /// Creates a new view of a non-geographic map in the [planar](Crs::PLANAR) CRS, e.g. of an image pyramid
+    /// created with [`TileSchema::image_pyramid`](crate::TileSchema::image_pyramid).
+    pub fn planar(position: &impl CartesianPoint2d<Num = f64>, resolution: f64) -> Self {
+        Self::new_projected_with_crs(position, resolution, Crs::PLANAR)
+    }
+
     /// CRS of the view.
     pub fn crs(&self) -> &Crs {
         &self.crs
     }
 
-    /// Position of the center point of the map (screen).
+    /// Position of the center point of the map (screen). If the view has [padding](ViewPadding), this is the center
+    /// of the visible part of the screen.
     ///
     /// If the projected position cannot be projected into geographic coordinates, `None` is returned.
     pub fn position(&self) -> Option<GeoPoint2d> {
@@ -87,6 +162,29 @@ impl MapView {
         })
     }
 
+    /// Creates a new view, same as the current one, but centered at the given geographic position.
+    ///
+    /// If the position cannot be projected into the CRS of the view, the projected position of the returned view is
+    /// not set.
+    pub fn with_position(&self, position: &impl GeoPoint<Num = f64>) -> Self {
+        let projected_position = self
+            .crs
+            .get_projection()
+            .and_then(|projection| projection.project(&GeoPoint2d::from(position)))
+            .map(|p: Point2d| Point3::new(p.x, p.y, 0.0));
+
+        Self {
+            projected_position,
+            crs: self.crs.clone(),
+            ..*self
+        }
+    }
+
+    /// Position of the center of the map in the projected coordinates of the view CRS.
+    pub(crate) fn projected_position(&self) -> Option<Point3<f64>> {
+        self.projected_position
+    }
+
     /// Resolution at the center of the map.
     pub fn resolution(&self) -> f64 {
         self.resolution
@@ -115,6 +213,83 @@ impl MapView {
         }
     }
 
+    /// Padding of the rendering area covered by the application UI.
+    pub fn padding(&self) -> ViewPadding {
+        self.padding
+    }
+
+    /// Creates a new view, same as the current one, but with the given padding. The position of the view stays the
+    /// same, so the map moves to keep it at the center of the visible area.
+    pub fn with_padding(&self, padding: ViewPadding) -> Self {
+        Self {
+            padding,
+            crs: self.crs.clone(),
+            ..*self
+        }
+    }
+
+    /// Number of physical pixels per logical pixel of the screen (DPI scale factor of the monitor or the zoom of the
+    /// browser page).
+    ///
+    /// The size and the resolution of the view are in physical pixels, while the sizes of symbols and labels are set
+    /// in logical pixels and are multiplied by the scale factor when rendered. Tiles are selected for the logical
+    /// resolution, so that their content is displayed at its intended size.
+    pub fn scale_factor(&self) -> f64 {
+        self.scale_factor
+    }
+
+    /// Creates a new view, same as the current one, but with the given scale factor. Non-positive and non-finite
+    /// values are replaced with `1.0`.
+    pub fn with_scale_factor(&self, scale_factor: f64) -> Self {
+        let scale_factor = if scale_factor.is_finite() && scale_factor > 0.0 {
+            scale_factor
+        } else {
+            1.0
+        };
+
+        Self {
+            scale_factor,
+            crs: self.crs.clone(),
+            ..*self
+        }
+    }
+
+    /// Resolution of the view in map units per logical pixel. Used to select the level of detail of tiled data.
+    pub fn logical_resolution(&self) -> f64 {
+        self.resolution * self.scale_factor
+    }
+
+    /// Creates a new view, same as the current one, but centered at the center of the given rectangle (in projected
+    /// coordinates) with the resolution at which the whole rectangle fits into the visible (not covered by the
+    /// [padding](ViewPadding)) part of the view.
+    ///
+    /// Rotation of the view around *Z* axis is taken into account, tilt is not. If the visible area or the rectangle
+    /// is empty, only the position of the view is changed.
+    pub fn fit_bbox(&self, bbox: Rect) -> Self {
+        let center = Point3::new(
+            (bbox.x_min() + bbox.x_max()) / 2.0,
+            (bbox.y_min() + bbox.y_max()) / 2.0,
+            0.0,
+        );
+
+        let (sin, cos) = self.rotation_z.sin_cos();
+        let width = bbox.width() * cos.abs() + bbox.height() * sin.abs();
+        let height = bbox.width() * sin.abs() + bbox.height() * cos.abs();
+        let visible = self.padding.visible_size(self.size);
+        let resolution = (width / visible.width()).max(height / visible.height());
+
+        Self {
+            projected_position: Some(center),
+            resolution: if resolution.is_normal() {
+                resolution
+            } else {
+                self.resolution
+            },
+            crs: self.crs.clone(),
+            ..*self
+        }
+    }
+
     /// Returns bounding rectangle of the view (in projected coordinates).
     pub fn get_bbox(&self) -> Option<Rect> {
         let points = [
@@ -164,7 +339,19 @@ impl MapView {
 
         let translate_z = Translation3::new(0.0, 0.0, -self.size.height() / 2.0).to_homogeneous();
         let perspective = self.perspective();
-        Some(perspective * translate_z * scale * rotation_x * rotation_z * translate)
+
+        // Padding moves the whole image (including the vanishing point of the perspective) in the screen space.
+        let offset = self.padding.center_offset();
+        let padding_shift = Translation3::new(
+            offset.x / self.size.half_width(),
+            -offset.y / self.size.half_height(),
+            0.0,
+        )
+        .to_homogeneous();
+
+        Some(
+            padding_shift * perspective * translate_z * scale * rotation_x * rotation_z * translate,
+        )
     }
 
     fn perspective(&self) -> Matrix4<f64> {
@@ -237,8 +424,9 @@ impl MapView {
     pub fn screen_to_map(&self, px_position: Point2d) -> Option<Point2d> {
         // todo: this must be calculated with matrices somehow but I'm not bright enough
         // to figure out how to do it...
-        let x = px_position.x;
-        let y = px_position.y;
+        let offset = self.padding.center_offset();
+        let x = px_position.x - offset.x;
+        let y = px_position.y - offset.y;
         let a = (self.size.half_height() - y) * std::f64::consts::FRAC_PI_4.tan()
             / self.size.half_height();
 
@@ -352,6 +540,7 @@ impl MapView {
         Self {
             projected_position: Some(projected_position),
             resolution: self.resolution + (target.resolution - self.resolution) * k,
+            padding: self.padding.interpolate(&target.padding, k),
             crs: self.crs.clone(),
             ..*self
         }
@@ -488,4 +677,46 @@ mod tests {
             epsilon = 0.01
         );
     }
+
+    #[test]
+    fn padding_moves_center() {
+        let view = test_view()
+            .with_size(Size::new(100.0, 100.0))
+            .with_padding(ViewPadding::new(20.0, 0.0, 0.0, 40.0));
+
+        // Visible area is from (20, 0) to (100, 60).
+        assert_abs_diff_eq!(
+            view.screen_to_map(Point2d::new(60.0, 30.0)).unwrap(),
+            Point2d::new(0.0, 0.0),
+            epsilon = 0.0001,
+        );
+
+        let point = Point3::new(0.0, 0.0, 0.0).to_homogeneous();
+        let projected = view.map_to_scene_transform().unwrap() * point;
+        let projected = projected.unscale(projected.w);
+        assert_abs_diff_eq!(projected.x, 0.2, epsilon = 0.0001);
+        assert_abs_diff_eq!(projected.y, 0.4, epsilon = 0.0001);
+
+        let tilted = view.with_rotation_x(std::f64::consts::PI / 6.0);
+        assert_abs_diff_eq!(
+            tilted.screen_to_map(Point2d::new(60.0, 30.0)).unwrap(),
+            Point2d::new(0.0, 0.0),
+            epsilon = 0.0001,
+        );
+    }
+
+    #[test]
+    fn fit_bbox_into_visible_area() {
+        let view = test_view()
+            .with_size(Size::new(200.0, 100.0))
+            .with_padding(ViewPadding::new(0.0, 100.0, 0.0, 0.0))
+            .fit_bbox(Rect::new(100.0, 100.0, 300.0, 150.0));
+
+        assert_abs_diff_eq!(view.resolution(), 2.0);
+        assert_abs_diff_eq!(
+            view.screen_to_map(Point2d::new(0.0, 0.0)).unwrap(),
+            Point2d::new(100.0, 225.0),
+            epsilon = 0.0001,
+        );
+    }
 }
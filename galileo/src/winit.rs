@@ -1,10 +1,11 @@
 //! Types that help using `Galileo` with `winit`.
 
-use crate::control::{MouseButton, RawUserEvent, TouchEvent};
+use crate::control::{Key, Modifiers, MouseButton, RawUserEvent, TouchEvent};
 use crate::messenger::Messenger;
 use galileo_types::cartesian::Point2d;
 use std::sync::Arc;
 use winit::event::{ElementState, MouseScrollDelta, Touch, TouchPhase, WindowEvent};
+use winit::keyboard::NamedKey;
 use winit::window::Window;
 
 /// Converts `winit` events into `Galileo` [`RawUserEvent`]s.
@@ -49,6 +50,20 @@ impl WinitInputHandler {
                     Some(RawUserEvent::TouchEnd(self.get_touch_event(touch, scale)))
                 }
             },
+            WindowEvent::KeyboardInput { event, .. } if event.state == ElementState::Pressed => {
+                Some(RawUserEvent::KeyPressed((&event.logical_key).into()))
+            }
+            WindowEvent::ModifiersChanged(modifiers) => {
+                let state = modifiers.state();
+                Some(RawUserEvent::ModifiersChanged(Modifiers {
+                    shift: state.shift_key(),
+                    ctrl: state.control_key(),
+                    alt: state.alt_key(),
+                }))
+            }
+            WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                Some(RawUserEvent::ScaleFactorChanged(*scale_factor))
+            }
             _ => None,
         }
     }
@@ -74,6 +89,22 @@ impl From<&winit::event::MouseButton> for MouseButton {
     }
 }
 
+impl From<&winit::keyboard::Key> for Key {
+    fn from(value: &winit::keyboard::Key) -> Self {
+        match value {
+            winit::keyboard::Key::Named(NamedKey::Tab) => Key::Tab,
+            winit::keyboard::Key::Named(NamedKey::Enter) => Key::Enter,
+            winit::keyboard::Key::Named(NamedKey::Space) => Key::Space,
+            winit::keyboard::Key::Named(NamedKey::Escape) => Key::Escape,
+            winit::keyboard::Key::Named(NamedKey::ArrowLeft) => Key::ArrowLeft,
+            winit::keyboard::Key::Named(NamedKey::ArrowRight) => Key::ArrowRight,
+            winit::keyboard::Key::Named(NamedKey::ArrowUp) => Key::ArrowUp,
+            winit::keyboard::Key::Named(NamedKey::ArrowDown) => Key::ArrowDown,
+            _ => Key::Other,
+        }
+    }
+}
+
 /// Messenger for a `winit` window.
 #[derive(Debug, Clone)]
 pub struct WinitMessenger {
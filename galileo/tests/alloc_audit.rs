@@ -0,0 +1,29 @@
+//! Tests of the allocation counting. They install [`CountingAllocator`] as the global allocator, so they live in a
+//! separate test binary to not affect the unit tests of the library.
+
+use galileo::alloc_audit::{is_counting, thread_allocations, without_counting, CountingAllocator};
+use std::alloc::System;
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator<System> = CountingAllocator::new(System);
+
+#[test]
+fn counts_thread_allocations() {
+    let start = thread_allocations();
+    let buffer: Vec<u64> = Vec::with_capacity(16);
+    assert!(is_counting());
+    assert_eq!(thread_allocations() - start, 1);
+
+    let start = thread_allocations();
+    let excluded = without_counting(|| vec![1u8; 32]);
+    assert_eq!(thread_allocations(), start);
+    drop((buffer, excluded));
+}
+
+#[test]
+fn reallocations_are_counted() {
+    let mut buffer: Vec<u64> = Vec::with_capacity(1);
+    let start = thread_allocations();
+    buffer.extend(0..64);
+    assert!(thread_allocations() > start);
+}